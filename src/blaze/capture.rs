@@ -0,0 +1,315 @@
+//! Debug capture of the Blaze packets sent and received by sessions, only
+//! included with the `packet-capture` feature. Each session is recorded to
+//! its own file within [CAPTURE_DIR] which can later be replayed through
+//! the router to diagnose unknown packet structures without the game client.
+//!
+//! Sessions are only captured when enabled in the [PacketCaptureConfig],
+//! which limits the size of each capture and of all the captures combined
+//!
+//! Capture files start with [CAPTURE_MAGIC] followed by the user ID of the
+//! session, followed by a record for each packet made up of the direction,
+//! the milliseconds since the capture started and the packet as it is
+//! encoded on the wire
+
+use super::{
+    packet::{FrameFlags, Packet, PacketDebug},
+    router::BlazeRouter,
+    session::Session,
+};
+use crate::{
+    config::{Config, PacketCaptureConfig},
+    database::entity::{users::UserId, User},
+    services::sessions::Sessions,
+};
+use anyhow::Context;
+use bytes::{Buf, BufMut, BytesMut};
+use log::{error, info, warn};
+use sea_orm::DatabaseConnection;
+use std::{io, path::Path, sync::Arc, time::Instant};
+use tokio::{
+    fs::File,
+    io::{AsyncWriteExt, BufWriter},
+    sync::mpsc,
+};
+use uuid::Uuid;
+
+/// Directory the packet captures are stored in
+const CAPTURE_DIR: &str = "data/captures";
+
+/// Magic bytes identifying a packet capture file
+const CAPTURE_MAGIC: &[u8; 8] = b"BLZCAP01";
+
+/// Direction a captured packet was travelling
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Direction {
+    /// Packet received from the client
+    Inbound = 0,
+    /// Packet sent to the client
+    Outbound = 1,
+}
+
+/// Packet read from a capture file
+pub struct CapturedPacket {
+    /// Direction the packet was travelling
+    pub direction: Direction,
+    /// Milliseconds since the capture started
+    pub elapsed: u64,
+    /// The captured packet
+    pub packet: Packet,
+}
+
+/// Capture recording the packets of a single session, the packets are
+/// written to the capture file by a background task
+pub struct PacketCapture {
+    /// When the capture was started
+    start: Instant,
+    /// Sender for the packets to write
+    tx: mpsc::UnboundedSender<(Direction, u64, Packet)>,
+}
+
+impl PacketCapture {
+    /// Starts capturing the packets for the session with the provided
+    /// `session_id` of the user with the `user_id`, responds with [None]
+    /// when the sessions of the user aren't captured
+    pub fn start(user_id: UserId, session_id: Uuid) -> Option<Self> {
+        let config = &Config::get().packet_capture;
+        if !config.captures(user_id) {
+            return None;
+        }
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let path = Path::new(CAPTURE_DIR).join(format!("{}-{}.bin", user_id, session_id));
+
+        tokio::spawn(async move {
+            if let Err(err) = Self::write(&path, user_id, config, rx).await {
+                error!("Failed to write packet capture {}: {}", path.display(), err);
+            }
+        });
+
+        Some(Self {
+            start: Instant::now(),
+            tx,
+        })
+    }
+
+    /// Records a `packet` travelling in the provided `direction`
+    pub fn record(&self, direction: Direction, packet: &Packet) {
+        let elapsed = self.start.elapsed().as_millis() as u64;
+        _ = self.tx.send((direction, elapsed, packet.clone()));
+    }
+
+    /// Writes the received packets to the capture file at `path` until
+    /// the session is stopped or the capture reaches its size limit
+    async fn write(
+        path: &Path,
+        user_id: UserId,
+        config: &PacketCaptureConfig,
+        mut rx: mpsc::UnboundedReceiver<(Direction, u64, Packet)>,
+    ) -> io::Result<()> {
+        tokio::fs::create_dir_all(CAPTURE_DIR).await?;
+
+        // Make room for the new capture within the total limit
+        prune_captures(
+            config
+                .max_total_bytes
+                .saturating_sub(config.max_capture_bytes),
+        )
+        .await?;
+
+        let file = File::create(path).await?;
+        let mut writer = BufWriter::new(file);
+
+        writer.write_all(CAPTURE_MAGIC).await?;
+        writer.write_u32(user_id).await?;
+
+        let mut written = (CAPTURE_MAGIC.len() + 4) as u64;
+        let mut buffer = BytesMut::new();
+
+        while let Some((direction, elapsed, packet)) = rx.recv().await {
+            buffer.put_u8(direction as u8);
+            buffer.put_u64(elapsed);
+            packet.write(&mut buffer);
+
+            written = written.saturating_add(buffer.len() as u64);
+            if written > config.max_capture_bytes {
+                warn!(
+                    "Packet capture {} reached its size limit, remaining packets won't be recorded",
+                    path.display()
+                );
+                break;
+            }
+
+            writer.write_all(&buffer).await?;
+            buffer.clear();
+
+            // Flush each packet so captures are complete if the server crashes
+            writer.flush().await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Removes the oldest captures from [CAPTURE_DIR] until the
+/// captures use at most `max_bytes`
+async fn prune_captures(max_bytes: u64) -> io::Result<()> {
+    let mut captures = Vec::new();
+    let mut entries = tokio::fs::read_dir(CAPTURE_DIR).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let metadata = entry.metadata().await?;
+        if metadata.is_file() {
+            captures.push((metadata.modified()?, metadata.len(), entry.path()));
+        }
+    }
+
+    let mut total: u64 = captures.iter().map(|(_, len, _)| len).sum();
+    captures.sort_by_key(|(modified, _, _)| *modified);
+
+    for (_, len, path) in captures {
+        if total <= max_bytes {
+            break;
+        }
+
+        info!("Removing packet capture {} to free space", path.display());
+        tokio::fs::remove_file(&path).await?;
+        total = total.saturating_sub(len);
+    }
+
+    Ok(())
+}
+
+/// Reads the user ID and the captured packets from the `data` of a capture
+/// file, captures that were cut off while writing (i.e the server crashed)
+/// respond with the packets before the incomplete record
+pub fn read_capture(data: &[u8]) -> io::Result<(UserId, Vec<CapturedPacket>)> {
+    if data.len() < CAPTURE_MAGIC.len() + 4 || !data.starts_with(CAPTURE_MAGIC) {
+        return Err(invalid_data("File is not a packet capture"));
+    }
+
+    let mut buffer = BytesMut::from(&data[CAPTURE_MAGIC.len()..]);
+    let user_id = buffer.get_u32();
+    let mut packets = Vec::new();
+
+    while buffer.has_remaining() {
+        let packet = if buffer.remaining() < 9 {
+            None
+        } else {
+            let direction = match buffer.get_u8() {
+                0 => Direction::Inbound,
+                1 => Direction::Outbound,
+                _ => return Err(invalid_data("Unknown packet direction")),
+            };
+            let elapsed = buffer.get_u64();
+            Packet::read(&mut buffer).map(|packet| (direction, elapsed, packet))
+        };
+
+        let Some((direction, elapsed, packet)) = packet else {
+            warn!(
+                "Packet capture is truncated, using the {} complete packet(s)",
+                packets.len()
+            );
+            break;
+        };
+
+        packets.push(CapturedPacket {
+            direction,
+            elapsed,
+            packet,
+        });
+    }
+
+    Ok((user_id, packets))
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Replays the packets received in the capture at `path` through the `router`
+/// as the captured user. The captured response and the replayed response are
+/// logged for each packet along with any notifications the handlers sent.
+///
+/// Handlers are run against the real database, captures should be replayed
+/// against a copy of the database
+pub async fn replay(
+    path: &Path,
+    db: &DatabaseConnection,
+    router: &BlazeRouter,
+    sessions: Arc<Sessions>,
+) -> anyhow::Result<()> {
+    let data = tokio::fs::read(path)
+        .await
+        .context("Failed to read packet capture")?;
+    let (user_id, packets) = read_capture(&data).context("Failed to parse packet capture")?;
+
+    let user = User::by_id(db, user_id)
+        .await?
+        .with_context(|| format!("Captured user {} no longer exists", user_id))?;
+
+    warn!(
+        "Replaying {} captured packet(s) as user {}, handlers will modify the database",
+        packets.len(),
+        user_id
+    );
+
+    let (session, mut rx) = Session::replay(user, sessions);
+
+    let inbound = packets
+        .iter()
+        .filter(|captured| captured.direction == Direction::Inbound);
+
+    for captured in inbound {
+        let request = &captured.packet;
+        info!(
+            "Replaying packet captured at {}ms:\n{:?}",
+            captured.elapsed,
+            PacketDebug {
+                packet: request,
+                minified: false
+            }
+        );
+
+        let captured_response = packets.iter().find(|other| {
+            other.direction == Direction::Outbound
+                && other.packet.frame.seq == request.frame.seq
+                && other.packet.frame.flags.contains(FrameFlags::FLAG_RESPONSE)
+        });
+
+        if let Some(captured_response) = captured_response {
+            info!(
+                "Captured response:\n{:?}",
+                PacketDebug {
+                    packet: &captured_response.packet,
+                    minified: false
+                }
+            );
+        }
+
+        let response = router.handle(session.clone(), request.clone()).await;
+        info!(
+            "Replayed response:\n{:?}",
+            PacketDebug {
+                packet: &response,
+                minified: false
+            }
+        );
+
+        // Allow the notifications from the handler to be queued
+        tokio::task::yield_now().await;
+
+        while let Ok(notification) = rx.try_recv() {
+            info!(
+                "Replayed notification:\n{:?}",
+                PacketDebug {
+                    packet: &notification,
+                    minified: false
+                }
+            );
+        }
+    }
+
+    session.clear_player();
+
+    Ok(())
+}
@@ -22,13 +22,17 @@ pub mod game_manager {
     pub const UPDATE_GAME_STATE: u16 = 3;
     pub const UPDATE_GAME_ATTR: u16 = 7;
     pub const UPDATE_PLAYER_ATTR: u16 = 8;
+    pub const REMOVE_PLAYER: u16 = 11;
     pub const START_MATCHMAKING_SCENARIO: u16 = 16;
     pub const REPLAY_GAME: u16 = 19;
     pub const LEAVE_GAME_BY_GROUP: u16 = 22;
 
     // Notifications
+    pub const MATCHMAKING_ASYNC_STATUS: u16 = 12;
     pub const GAME_SETUP: u16 = 20;
     pub const PLAYER_REMOVED: u16 = 40;
+    pub const HOST_MIGRATION_FINISHED: u16 = 60;
+    pub const HOST_MIGRATION_START: u16 = 70;
     pub const GAME_ATTR_UPDATE: u16 = 80;
     pub const PLAYER_ATTR_UPDATE: u16 = 90;
     pub const GAME_STATE_CHANGE: u16 = 100;
@@ -36,6 +40,13 @@ pub mod game_manager {
     pub const GAME_TYPE: ObjectType = ObjectType::new(COMPONENT, 1);
 }
 
+pub mod messaging {
+    pub const COMPONENT: u16 = 15;
+
+    // Notifications
+    pub const SEND_MESSAGE: u16 = 1;
+}
+
 pub mod util {
     pub const COMPONENT: u16 = 9;
 
@@ -1,3 +1,5 @@
+#[cfg(feature = "packet-capture")]
+pub mod capture;
 pub mod components;
 pub mod models;
 pub mod packet;
@@ -81,6 +81,16 @@ pub struct ReplayGameRequest {
     pub gid: u32,
 }
 
+#[derive(TdfDeserialize)]
+pub struct RemovePlayerRequest {
+    #[tdf(tag = "GID")]
+    pub gid: u32,
+    #[tdf(tag = "PID")]
+    pub pid: u32,
+    #[tdf(tag = "REAS")]
+    pub reas: RemoveReason,
+}
+
 #[derive(TdfDeserialize)]
 pub struct LeaveGameRequest {
     #[tdf(tag = "GID")]
@@ -89,8 +99,16 @@ pub struct LeaveGameRequest {
     pub reas: RemoveReason,
 }
 
+/// Async matchmaking status update sent to players waiting
+/// in the matchmaking queue
 pub struct NotifyMatchmakingStatus {
     pub pid: u32,
+    /// Number of players in games matching the current criteria
+    pub players_found: u32,
+    /// Current criteria relaxation stage
+    pub relaxation_stage: u32,
+    /// Estimated remaining wait in seconds
+    pub estimated_wait: u32,
 }
 
 impl TdfSerialize for NotifyMatchmakingStatus {
@@ -99,9 +117,9 @@ impl TdfSerialize for NotifyMatchmakingStatus {
             w.tag_list_start(b"ASIL", TdfType::Group, 1);
             w.group_body(|w| {
                 w.group(b"CGS", |w| {
-                    w.tag_u8(b"EVST", 0);
+                    w.tag_owned(b"EVST", self.relaxation_stage);
                     w.tag_u8(b"MMSN", 1);
-                    w.tag_u8(b"NOMP", 0);
+                    w.tag_owned(b"NOMP", self.players_found);
                 });
 
                 w.group(b"FGS", |w| w.tag_u8(b"GNUM", 0));
@@ -140,6 +158,7 @@ impl TdfSerialize for NotifyMatchmakingStatus {
                 w.group(b"VGRS", |w| w.tag_u8(b"VVAL", 0));
             });
         }
+        w.tag_owned(b"ESTW", self.estimated_wait);
         w.tag_owned(b"MSCD", self.pid); // pid
         w.tag_owned(b"MSID", self.pid); // pid
         w.tag_owned(b"USID", self.pid); // pid
@@ -268,7 +287,7 @@ pub struct GameSetupResponse<'a> {
 impl TdfSerialize for GameSetupResponse<'_> {
     fn serialize<S: tdf::TdfSerializer>(&self, w: &mut S) {
         let game = self.game;
-        let host = game.players.first().expect("Missing game host for setup");
+        let host = game.host_player().expect("Missing game host for setup");
 
         w.group(b"GAME", |w| {
             // Admin player list
@@ -416,8 +435,8 @@ impl TdfSerialize for GameSetupResponse<'_> {
 
         // Player list
         w.tag_list_start(b"PROS", TdfType::Group, game.players.len());
-        for (slot, player) in game.players.iter().enumerate() {
-            player.encode(game.id, slot, w);
+        for player in &game.players {
+            player.encode(game.id, w);
         }
 
         // QoS settings
@@ -447,7 +466,7 @@ pub struct PlayerRemoved {
     pub reason: RemoveReason,
 }
 
-#[derive(Debug, Clone, Copy, TdfDeserialize, TdfSerialize, TdfTyped)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TdfDeserialize, TdfSerialize, TdfTyped)]
 #[repr(u8)]
 pub enum RemoveReason {
     /// Hit timeout while joining
@@ -517,6 +536,27 @@ pub struct NotifyGameStateChange {
     pub state: u8,
 }
 
+#[derive(TdfSerialize)]
+pub struct HostMigrateStart {
+    #[tdf(tag = "GID")]
+    pub game_id: GameID,
+    /// ID of the player becoming the new host
+    #[tdf(tag = "HOST")]
+    pub host_id: u32,
+    /// Type of migration (Topology host migration)
+    #[tdf(tag = "PMIG")]
+    pub pmig: u32,
+    /// Slot of the new host
+    #[tdf(tag = "SLOT")]
+    pub slot: u8,
+}
+
+#[derive(TdfSerialize)]
+pub struct HostMigrateFinished {
+    #[tdf(tag = "GID")]
+    pub game_id: GameID,
+}
+
 #[derive(TdfSerialize)]
 pub struct NotifyGameReplay {
     #[tdf(tag = "GID")]
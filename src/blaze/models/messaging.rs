@@ -0,0 +1,37 @@
+use crate::{
+    blaze::components::user_sessions::PLAYER_SESSION_TYPE, database::entity::users::UserId,
+};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tdf::prelude::*;
+
+/// Message sent by the server to a user
+pub struct NotifyMessage<'a> {
+    /// ID of the user receiving the message
+    pub user_id: UserId,
+    /// The message text
+    pub message: &'a str,
+}
+
+impl TdfSerialize for NotifyMessage<'_> {
+    fn serialize<S: TdfSerializer>(&self, w: &mut S) {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Clock went backwards")
+            .as_secs();
+        let target = ObjectId::new(PLAYER_SESSION_TYPE, self.user_id as u64);
+
+        w.tag_u8(b"FLAG", 0x1);
+        w.tag_u8(b"MGID", 0x1);
+        w.tag_str(b"NAME", self.message);
+        w.group(b"PYLD", |w| {
+            w.tag_map_tuples(b"ATTR", &[("B0000", "160")]);
+            w.tag_u8(b"FLAG", 0x1);
+            w.tag_u8(b"STAT", 0x0);
+            w.tag_u8(b"TAG", 0x0);
+            w.tag_alt(b"TARG", target);
+            w.tag_u8(b"TYPE", 0x0);
+        });
+        w.tag_alt(b"SRCE", target);
+        w.tag_u64(b"TIME", time);
+    }
+}
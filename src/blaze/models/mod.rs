@@ -4,6 +4,7 @@ use tdf::{TdfDeserialize, TdfSerialize, TdfTyped};
 pub mod auth;
 pub mod errors;
 pub mod game_manager;
+pub mod messaging;
 pub mod user_sessions;
 pub mod util;
 
@@ -3,7 +3,10 @@ use crate::{
         components::{self, game_manager::GAME_TYPE, user_sessions::PLAYER_SESSION_TYPE},
         session::NetData,
     },
-    database::entity::{users::UserId, User},
+    database::entity::{
+        users::{Platform, UserId},
+        User,
+    },
 };
 use bitflags::bitflags;
 use serde::Serialize;
@@ -15,19 +18,73 @@ use tdf::prelude::*;
 
 use super::util::PING_SITE_ALIAS;
 
+/// Quality of service results from the client QoS checks
 #[derive(Debug, Clone, Copy, Default, Serialize, TdfSerialize, TdfDeserialize, TdfTyped)]
 #[tdf(group)]
 pub struct QosNetworkData {
+    /// Result of the bandwidth test hop
     #[tdf(tag = "BWHR")]
-    pub bwhr: u32,
+    pub bandwidth_hop_result: u32,
+    /// Downstream bandwidth in bits per second
     #[tdf(tag = "DBPS")]
-    pub dbps: u32,
+    pub downstream_bps: u32,
+    /// Result of the NAT test hop
     #[tdf(tag = "NAHR")]
-    pub nahr: u32,
+    pub nat_hop_result: u32,
+    /// The NAT classification of the client
     #[tdf(tag = "NATT")]
-    pub natt: u8,
+    pub nat_type: NatType,
+    /// Upstream bandwidth in bits per second
     #[tdf(tag = "UBPS")]
-    pub ubps: u32,
+    pub upstream_bps: u32,
+}
+
+/// NAT classification determined by the client QoS checks
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, TdfSerialize, TdfDeserialize, TdfTyped,
+)]
+#[repr(u8)]
+pub enum NatType {
+    /// No restrictions, anyone can connect
+    Open = 0x0,
+    /// Connections from known peers only
+    Moderate = 0x1,
+    /// Ports are allocated sequentially per connection
+    Sequential = 0x2,
+    /// Only outgoing connections are possible
+    Strict = 0x3,
+    /// The QoS checks didn't determine the NAT type
+    #[default]
+    #[tdf(default)]
+    Unknown = 0x4,
+}
+
+impl NatType {
+    /// Priority for choosing this NAT type as a game host, lower
+    /// values are able to accept connections from more players
+    pub fn host_priority(&self) -> u8 {
+        match self {
+            NatType::Open => 0,
+            NatType::Moderate => 1,
+            NatType::Unknown => 2,
+            NatType::Sequential => 3,
+            NatType::Strict => 4,
+        }
+    }
+
+    /// Whether players with this NAT type and the `other` NAT type
+    /// are likely able to connect directly, unknown types are assumed
+    /// to be compatible as they can't be checked
+    pub fn is_compatible(&self, other: NatType) -> bool {
+        matches!(
+            (self, other),
+            (NatType::Unknown, _)
+                | (_, NatType::Unknown)
+                | (NatType::Open, _)
+                | (_, NatType::Open)
+                | (NatType::Moderate, NatType::Moderate)
+        )
+    }
 }
 
 #[derive(Default, Debug, Clone, TdfSerialize, TdfDeserialize, TdfTyped, Serialize)]
@@ -85,6 +142,9 @@ pub struct UpdateNetworkInfo {
 pub struct NetworkInfo {
     #[tdf(tag = "ADDR")]
     pub addr: NetworkAddress,
+    /// Latency in milliseconds to each of the ping sites
+    #[tdf(tag = "NLMP")]
+    pub ping_site_latency: TdfMap<String, u32>,
     #[tdf(tag = "NQOS")]
     pub qos: QosNetworkData,
 }
@@ -137,6 +197,8 @@ pub struct UserSessionExtendedData {
     pub net: Arc<NetData>,
     /// ID of the game the player is in (if present)
     pub game: Option<u32>,
+    /// Platform the user is playing on
+    pub platform: Platform,
 
     pub user_id: UserId,
 }
@@ -169,6 +231,8 @@ impl TdfSerialize for UserSessionExtendedData {
             );
             // Hardware flags
             w.tag_owned(b"HWFG", self.net.hardware_flags.bits());
+            // Client platform (SKU platform name)
+            w.tag_str(b"PLAT", self.platform.sku_name());
             // Internet Service Provider
             w.tag_str(b"ISP", "Example ISP");
             // Ping server latency list
@@ -245,6 +245,7 @@ impl FromPacketRequest for Player {
             Arc::downgrade(&req.state),
             req.state.notify_handle(),
             data.net.clone(),
+            &data.cosmetics,
         ))))
     }
 }
@@ -1,22 +1,27 @@
 use crate::{
     blaze::{
         models::{
+            errors::{GlobalError, ServerResult},
             game_manager::{
                 GameSetupContext, LeaveGameRequest, MatchmakeScenario, MatchmakingResult,
-                ReplayGameRequest, StartMatchmakingScenarioRequest,
-                StartMatchmakingScenarioResponse, UpdateAttrRequest, UpdateGameAttrRequest,
-                UpdateStateRequest,
+                RemovePlayerRequest, RemoveReason, ReplayGameRequest,
+                StartMatchmakingScenarioRequest, StartMatchmakingScenarioResponse,
+                UpdateAttrRequest, UpdateGameAttrRequest, UpdateStateRequest,
             },
             PlayerState,
         },
         router::{Blaze, Extension, SessionAuth},
         session::{self, SessionLink},
     },
+    database::entity::{MatchmakingPreferences, UserBan},
     services::{
-        game::{self, Player, DEFAULT_FIT},
+        game::{self, AttrMap, Game, Player, DEFAULT_FIT},
         game_manager::GameManager,
+        restarts::RestartScheduler,
     },
 };
+use log::warn;
+use sea_orm::DatabaseConnection;
 use std::sync::Arc;
 
 pub async fn start_matchmaking_scenario(
@@ -24,39 +29,57 @@ pub async fn start_matchmaking_scenario(
     mut player: Player,
     Blaze(req): Blaze<StartMatchmakingScenarioRequest>,
     Extension(game_manager): Extension<Arc<GameManager>>,
-) -> Blaze<StartMatchmakingScenarioResponse> {
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(restarts): Extension<Arc<RestartScheduler>>,
+) -> ServerResult<Blaze<StartMatchmakingScenarioResponse>> {
+    // Players shouldn't start missions that the restart would interrupt
+    if restarts.blocks_matchmaking() {
+        return Err(GlobalError::Cancelled.into());
+    }
+
     let user_id = player.user.id;
 
+    // Banned players can't join games
+    if UserBan::active(&db, user_id).await?.is_some() {
+        return Err(GlobalError::AuthorizationRequired.into());
+    }
+
+    let attributes: AttrMap = req
+        .attributes
+        .into_iter()
+        .filter_map(|(key, value)| {
+            let inner = value.inner?;
+            let value = match inner.value {
+                tdf::TdfGenericValue::String(value) => value,
+                _ => return None,
+            };
+            Some((key, value))
+        })
+        .collect();
+
     match req.ty {
         MatchmakeScenario::QuickMatch => {
-            // TODO:
-            // - Add to matchmaking queue
-            // - Send async matchmaking update (4, 12)
-            // - Couldn't find one? create new one
-            // - found one? send game details
+            let preferences = match MatchmakingPreferences::get(&db, &player.user).await {
+                Ok(value) => value,
+                Err(err) => {
+                    // Players can still be matched without their preferences
+                    warn!("Failed to load matchmaking preferences: {}", err);
+                    None
+                }
+            };
+
+            // Matched and sent status updates by the matchmaking task
+            game_manager.queue(player, attributes, preferences);
         }
         MatchmakeScenario::CreatePublicGame => {
-            let attributes = req
-                .attributes
-                .into_iter()
-                .filter_map(|(key, value)| {
-                    let inner = value.inner?;
-                    let value = match inner.value {
-                        tdf::TdfGenericValue::String(value) => value,
-                        _ => return None,
-                    };
-                    Some((key, value))
-                })
-                .collect();
-
             // Player is the host player (They are connected by default)
             player.state = PlayerState::ActiveConnected;
 
             // Create the new game
             let (game_ref, game_id) = game_manager.create(attributes).await;
 
-            // Add the player to the game
-            game_manager
+            // Add the player to the game, new games always have a free slot
+            _ = game_manager
                 .add_to_game(
                     game_ref,
                     player,
@@ -77,7 +100,7 @@ pub async fn start_matchmaking_scenario(
         }
     }
 
-    Blaze(StartMatchmakingScenarioResponse { user_id })
+    Ok(Blaze(StartMatchmakingScenarioResponse { user_id }))
 }
 
 pub async fn update_game_attr(
@@ -100,14 +123,34 @@ pub async fn update_player_attr(
     game.set_player_attributes(req.pid, req.attr);
 }
 
+/// Updates the state of the game, only the host can start the mission
+/// and only once the other players are ready
 pub async fn update_game_state(
+    SessionAuth(user): SessionAuth,
     Blaze(req): Blaze<UpdateStateRequest>,
     Extension(game_manager): Extension<Arc<GameManager>>,
-) {
-    let game = game_manager.get_game(req.gid).await.expect("Unknown game");
+) -> ServerResult<()> {
+    let game = game_manager
+        .get_game(req.gid)
+        .await
+        .ok_or(GlobalError::System)?;
 
     let game = &mut *game.write().await;
+
+    if req.state == Game::IN_GAME_STATE && game.state != Game::IN_GAME_STATE {
+        if !game.is_host(user.id) {
+            return Err(GlobalError::AuthorizationRequired.into());
+        }
+
+        if !game.can_start_mission() {
+            return Err(GlobalError::Cancelled.into());
+        }
+
+        game.reset_ready_check();
+    }
+
     game.set_state(req.state);
+    Ok(())
 }
 
 pub async fn replay_game(
@@ -121,6 +164,33 @@ pub async fn replay_game(
     game.notify_game_replay();
 }
 
+/// Removes a player from the game, players can remove themselves but
+/// only the host can kick other players. Players kicked with a ban
+/// can't be matched back into the game
+pub async fn remove_player(
+    SessionAuth(user): SessionAuth,
+    Blaze(req): Blaze<RemovePlayerRequest>,
+    Extension(game_manager): Extension<Arc<GameManager>>,
+) -> ServerResult<()> {
+    let game = game_manager
+        .get_game(req.gid)
+        .await
+        .ok_or(GlobalError::System)?;
+
+    let game = &mut *game.write().await;
+
+    if req.pid != user.id && !game.is_host(user.id) {
+        return Err(GlobalError::AuthorizationRequired.into());
+    }
+
+    if req.reas == RemoveReason::PlayerKickedWithBan {
+        game.ban_player(req.pid);
+    }
+
+    game.remove_player(req.pid, req.reas);
+    Ok(())
+}
+
 pub async fn leave_game(
     session: SessionLink,
     SessionAuth(user): SessionAuth,
@@ -76,6 +76,11 @@ pub fn router() -> BlazeRouterBuilder {
         components::game_manager::UPDATE_GAME_ATTR,
         game_manager::update_game_attr,
     );
+    router.route(
+        components::game_manager::COMPONENT,
+        components::game_manager::REMOVE_PLAYER,
+        game_manager::remove_player,
+    );
 
     router.route(
         components::game_manager::COMPONENT,
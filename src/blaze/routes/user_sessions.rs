@@ -6,7 +6,12 @@ use crate::blaze::{
 
 pub async fn update_network_info(session: SessionLink, Blaze(req): Blaze<UpdateNetworkInfo>) {
     let info = req.info;
-    session.set_network_info(info.addr, info.qos);
+    let ping_site_latency = info
+        .ping_site_latency
+        .iter()
+        .map(|(site, latency)| (site.clone(), *latency))
+        .collect();
+    session.set_network_info(info.addr, info.qos, ping_site_latency);
 }
 
 pub async fn update_hardware_flags(session: SessionLink, Blaze(req): Blaze<UpdateHardwareFlags>) {
@@ -1,8 +1,12 @@
+use log::error;
+use sea_orm::DatabaseConnection;
 use tdf::TdfMap;
 
-use crate::blaze::router::Blaze;
+use crate::blaze::router::{Blaze, Extension};
 use crate::blaze::session::SessionLink;
 use crate::blaze::{models::util::*, router::SessionAuth};
+use crate::services::{friends, notifications, sessions::Sessions};
+use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 pub async fn pre_auth(session: SessionLink) -> Blaze<PreAuthResponse> {
@@ -12,9 +16,24 @@ pub async fn pre_auth(session: SessionLink) -> Blaze<PreAuthResponse> {
 pub async fn post_auth(
     session: SessionLink,
     SessionAuth(user): SessionAuth,
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(sessions): Extension<Arc<Sessions>>,
 ) -> Blaze<PostAuthResponse> {
     session.add_subscriber(user.id, session.notify_handle());
 
+    // Share presence with the online friends of the user
+    if let Err(err) = friends::connect(&db, &sessions, &session, user.id).await {
+        error!("Failed to connect friends of user {}: {}", user.id, err);
+    }
+
+    // Deliver the notifications received while the user was offline
+    if let Err(err) = notifications::deliver_pending(&db, &session, user.id).await {
+        error!(
+            "Failed to deliver notifications to user {}: {}",
+            user.id, err
+        );
+    }
+
     Blaze(PostAuthResponse { user_id: user.id })
 }
 
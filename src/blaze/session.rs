@@ -1,3 +1,5 @@
+#[cfg(feature = "packet-capture")]
+use super::capture::{Direction, PacketCapture};
 use super::{
     components::{self, user_sessions},
     models::{
@@ -13,9 +15,12 @@ use super::{
 };
 use crate::{
     blaze::packet::PacketDebug,
+    config::Config,
     database::entity::{users::UserId, User},
     services::{
-        game::{GameID, Player, WeakGameRef},
+        crash_reports::{self, CrashContext},
+        game::{GameID, Player, PlayerCosmetics, WeakGameRef},
+        server_events::{ServerEvent, ServerEvents},
         sessions::Sessions,
     },
     utils::lock::{QueueLock, QueueLockGuard, TicketAquireFuture},
@@ -31,16 +36,19 @@ use log::{debug, error, warn};
 use parking_lot::Mutex;
 use serde::Serialize;
 use std::{
+    collections::HashMap,
     future::Future,
     pin::Pin,
     sync::Weak,
     task::{Context, Poll},
+    time::Duration,
 };
-use std::{io, sync::Arc, task::ready};
+use std::{io, net::IpAddr, sync::Arc, task::ready};
 use tdf::{serialize_vec, TdfSerialize};
 use tokio::{
     sync::{mpsc, RwLock},
     task::JoinSet,
+    time::{sleep_until, Instant, Sleep},
 };
 use tokio_util::codec::Framed;
 use uuid::Uuid;
@@ -50,6 +58,8 @@ pub type WeakSessionLink = Weak<Session>;
 
 pub struct Session {
     pub uuid: Uuid,
+    /// IP address the session is connected from
+    pub addr: IpAddr,
 
     busy_lock: QueueLock,
     tx: mpsc::UnboundedSender<Packet>,
@@ -81,6 +91,8 @@ impl SessionNotifyHandle {
 pub struct SessionExtData {
     pub user: Arc<User>,
     pub net: Arc<NetData>,
+    /// Cosmetics the user has equipped
+    pub cosmetics: PlayerCosmetics,
     game: Option<SessionGameData>,
     subscribers: Vec<(UserId, SessionNotifyHandle)>,
 }
@@ -91,19 +103,26 @@ struct SessionGameData {
 }
 
 impl SessionExtData {
-    pub fn new(user: User) -> Self {
+    pub fn new(user: User, cosmetics: PlayerCosmetics) -> Self {
         Self {
             user: Arc::new(user),
             net: Default::default(),
+            cosmetics,
             game: Default::default(),
             subscribers: Default::default(),
         }
     }
 
+    /// Gets the ID of the game the session is currently in
+    pub fn game_id(&self) -> Option<GameID> {
+        self.game.as_ref().map(|game| game.game_id)
+    }
+
     fn ext(&self) -> UserSessionExtendedData {
         UserSessionExtendedData {
             net: self.net.clone(),
             game: self.game.as_ref().map(|game| game.game_id),
+            platform: self.user.platform,
             user_id: self.user.id,
         }
     }
@@ -141,6 +160,12 @@ impl SessionExtData {
             // Remove the subscriber
             .map(|index| self.subscribers.swap_remove(index));
 
+        // Subscribers may be subscribed more than once (e.g. friends in the same game)
+        // they keep the user until the last subscription is removed
+        if self.subscribers.iter().any(|(id, _)| user_id.eq(id)) {
+            return;
+        }
+
         if let Some((_, subscriber)) = subscriber {
             // Notify the subscriber they've removed the user subcription
             subscriber.notify(Packet::notify(
@@ -175,24 +200,41 @@ pub struct NetData {
     pub addr: NetworkAddress,
     pub qos: QosNetworkData,
     pub hardware_flags: HardwareFlags,
+    /// Latency in milliseconds to each of the ping sites
+    pub ping_site_latency: HashMap<String, u32>,
 }
 
 impl NetData {
     // Re-creates the current net data using the provided address and QOS data
-    pub fn with_basic(&self, addr: NetworkAddress, qos: QosNetworkData) -> Self {
+    pub fn with_basic(
+        &self,
+        addr: NetworkAddress,
+        qos: QosNetworkData,
+        ping_site_latency: HashMap<String, u32>,
+    ) -> Self {
         Self {
             addr,
             qos,
             hardware_flags: self.hardware_flags,
+            ping_site_latency,
         }
     }
 
+    /// Finds the ping site with the lowest latency
+    pub fn best_ping_site(&self) -> Option<&str> {
+        self.ping_site_latency
+            .iter()
+            .min_by_key(|(_, latency)| **latency)
+            .map(|(site, _)| site.as_str())
+    }
+
     /// Re-creates the current net data using the provided hardware flags
     pub fn with_hardware_flags(&self, flags: HardwareFlags) -> Self {
         Self {
             addr: self.addr.clone(),
             qos: self.qos,
             hardware_flags: flags,
+            ping_site_latency: self.ping_site_latency.clone(),
         }
     }
 }
@@ -200,7 +242,9 @@ impl NetData {
 impl Session {
     pub async fn start(
         io: Upgraded,
+        addr: IpAddr,
         user: User,
+        cosmetics: PlayerCosmetics,
         router: Arc<BlazeRouter>,
         sessions: Arc<Sessions>,
     ) {
@@ -208,11 +252,17 @@ impl Session {
 
         let user_id = user.id;
 
+        ServerEvents::get().publish(ServerEvent::PlayerLogin {
+            user_id,
+            username: user.username.clone(),
+        });
+
         let session = Arc::new(Self {
             uuid: Uuid::new_v4(),
+            addr,
             busy_lock: QueueLock::new(),
             tx,
-            data: Mutex::new(SessionExtData::new(user)),
+            data: Mutex::new(SessionExtData::new(user, cosmetics)),
             sessions,
         });
 
@@ -222,7 +272,10 @@ impl Session {
 
         debug!("Session started {}", &session.uuid);
 
-        SessionFuture {
+        let idle_timeout = Duration::from_secs(Config::get().sessions.idle_timeout_secs);
+        let last_received = Instant::now();
+
+        let future = SessionFuture {
             io: Framed::new(io, PacketCodec),
             router: &router,
             rx,
@@ -230,12 +283,47 @@ impl Session {
             read_state: ReadState::Recv,
             write_state: WriteState::Recv,
             stop: false,
-        }
-        .await;
+            idle_timer: (!idle_timeout.is_zero())
+                .then(|| Box::pin(sleep_until(last_received + idle_timeout))),
+            idle_timeout,
+            last_received,
+            #[cfg(feature = "packet-capture")]
+            capture: PacketCapture::start(user_id, session.uuid),
+        };
+
+        let context = CrashContext::new("blaze").with_user(user_id);
+        crash_reports::scope(context, future).await;
 
         session.stop();
     }
 
+    /// Creates a session without a connection for replaying captured
+    /// packets, the packets sent to the session are received by the
+    /// returned receiver
+    #[cfg(feature = "packet-capture")]
+    pub fn replay(
+        user: User,
+        sessions: Arc<Sessions>,
+    ) -> (SessionLink, mpsc::UnboundedReceiver<Packet>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let user_id = user.id;
+
+        let session = Arc::new(Self {
+            uuid: Uuid::new_v4(),
+            addr: IpAddr::V4(std::net::Ipv4Addr::LOCALHOST),
+            busy_lock: QueueLock::new(),
+            tx,
+            data: Mutex::new(SessionExtData::new(user, PlayerCosmetics::default())),
+            sessions,
+        });
+
+        session
+            .sessions
+            .add_session(user_id, Arc::downgrade(&session));
+
+        (session, rx)
+    }
+
     pub fn notify_handle(&self) -> SessionNotifyHandle {
         SessionNotifyHandle {
             busy_lock: self.busy_lock.clone(),
@@ -244,9 +332,12 @@ impl Session {
     }
 
     /// Internal session stopped function called by the reader when
-    /// the connection is terminated, cleans up any references and
+    /// the connection is terminated or goes idle, cleans up any references and
     /// asserts only 1 strong reference exists
     fn stop(self: Arc<Self>) {
+        // Remove the player as disconnected so their slot is held for them
+        self.remove_from_game(RemoveReason::ServerConnectionLost);
+
         // Clear authentication
         self.clear_player();
 
@@ -284,8 +375,9 @@ impl Session {
         Some((user_id, game.game_ref))
     }
 
-    /// Called to remove the player from its current game
-    pub fn remove_from_game(&self) {
+    /// Called to remove the player from its current game for the
+    /// provided `reason`
+    pub fn remove_from_game(&self, reason: RemoveReason) {
         let (player_id, game_ref) = match self.clear_game() {
             Some(value) => value,
             // Player isn't in a game
@@ -301,20 +393,40 @@ impl Session {
         // Spawn an async task to handle removing the player
         tokio::spawn(async move {
             let game = &mut *game_ref.write().await;
-            game.remove_player(player_id, RemoveReason::PlayerLeft);
+            game.remove_player(player_id, reason);
         });
     }
 
     pub fn clear_player(&self) {
-        self.remove_from_game();
+        self.remove_from_game(RemoveReason::PlayerLeft);
 
-        // Check that theres authentication
-        let data = &mut *self.data.lock();
+        let (user_id, subscribers) = {
+            let data = &mut *self.data.lock();
+
+            // Existing sessions must be unsubscribed
+            (data.user.id, std::mem::take(&mut data.subscribers))
+        };
+
+        for (subscriber_id, subscriber) in subscribers {
+            // Sessions are subscribed to themselves
+            if subscriber_id == user_id {
+                continue;
+            }
+
+            // Notify the subscriber the user has gone offline
+            subscriber.notify(Packet::notify(
+                user_sessions::COMPONENT,
+                user_sessions::USER_REMOVED,
+                NotifyUserRemoved { user_id },
+            ));
 
-        // Existing sessions must be unsubscribed
-        data.subscribers.clear();
+            // Remove the subscription of this user to the subscriber
+            if let Some(session) = self.sessions.lookup_session(subscriber_id) {
+                session.remove_subscriber(user_id);
+            }
+        }
 
-        self.sessions.remove_session(data.user.id);
+        self.sessions.remove_session(user_id);
     }
 
     #[inline]
@@ -327,6 +439,13 @@ impl Session {
         self.data.lock().remove_subscriber(user_id);
     }
 
+    /// Publishes the current session data to the subscribed sessions
+    /// (Friends and players in the same game)
+    #[inline]
+    pub fn publish_presence(&self) {
+        self.data.lock().publish_update();
+    }
+
     #[inline]
     pub fn set_hardware_flags(&self, value: HardwareFlags) {
         self.update_data(|data| {
@@ -335,12 +454,56 @@ impl Session {
     }
 
     #[inline]
-    pub fn set_network_info(&self, address: NetworkAddress, qos: QosNetworkData) {
+    pub fn set_network_info(
+        &self,
+        address: NetworkAddress,
+        qos: QosNetworkData,
+        ping_site_latency: HashMap<String, u32>,
+    ) {
+        self.update_data(|data| {
+            data.net = Arc::new(data.net.with_basic(address, qos, ping_site_latency));
+        });
+    }
+
+    /// Replaces the user of the session with an updated copy, the
+    /// change is published to the subscribers so they see the updated
+    /// platform of the user
+    #[inline]
+    pub fn set_user(&self, user: User) {
         self.update_data(|data| {
-            data.net = Arc::new(data.net.with_basic(address, qos));
+            data.user = Arc::new(user);
+        });
+    }
+
+    /// Replaces the equipped cosmetics of the session, the player in
+    /// the current game is updated so other players see the change
+    pub fn set_cosmetics(&self, cosmetics: PlayerCosmetics) {
+        let attributes = cosmetics.attributes();
+        let (user_id, game_ref) = {
+            let data = &mut *self.data.lock();
+            data.cosmetics = cosmetics;
+            (
+                data.user.id,
+                data.game.as_ref().map(|game| game.game_ref.clone()),
+            )
+        };
+
+        let Some(game_ref) = game_ref.and_then(|game_ref| game_ref.upgrade()) else {
+            return;
+        };
+
+        tokio::spawn(async move {
+            let game = &mut *game_ref.write().await;
+            game.set_player_attributes(user_id, attributes);
         });
     }
 
+    /// Gets the current network details of the session
+    #[inline]
+    pub fn net(&self) -> Arc<NetData> {
+        self.data.lock().net.clone()
+    }
+
     #[inline]
     fn update_data<F>(&self, update: F)
     where
@@ -353,7 +516,7 @@ impl Session {
 
     pub fn set_game(&self, game_id: GameID, game_ref: WeakGameRef) {
         // Remove the player from the game if they are already present in one
-        self.remove_from_game();
+        self.remove_from_game(RemoveReason::PlayerLeft);
 
         // Set the current game
         self.update_data(|data| {
@@ -399,6 +562,17 @@ struct SessionFuture<'a> {
     write_state: WriteState,
     /// Whether the future has been stopped
     stop: bool,
+    /// Timer for checking whether the session has gone idle, [None]
+    /// when the idle timeout is disabled
+    idle_timer: Option<Pin<Box<Sleep>>>,
+    /// Time the session can go without receiving packets before it's closed
+    idle_timeout: Duration,
+    /// When a packet was last received or handled
+    last_received: Instant,
+    /// Capture recording the packets of the session, only present
+    /// when the sessions of the user are captured
+    #[cfg(feature = "packet-capture")]
+    capture: Option<PacketCapture>,
 }
 
 /// Session future writing state
@@ -478,6 +652,11 @@ impl SessionFuture<'_> {
                         }
                     }
 
+                    #[cfg(feature = "packet-capture")]
+                    if let Some(capture) = &self.capture {
+                        capture.record(Direction::Outbound, &packet);
+                    }
+
                     // Write the packet to the buffer
                     Pin::new(&mut self.io)
                         .start_send(packet)
@@ -513,6 +692,8 @@ impl SessionFuture<'_> {
                 let result = ready!(Pin::new(&mut self.io).poll_next(cx));
 
                 if let Some(Ok(packet)) = result {
+                    self.last_received = Instant::now();
+
                     let ticket = self.session.busy_lock.aquire();
                     self.read_state = ReadState::Aquire {
                         ticket,
@@ -531,6 +712,14 @@ impl SessionFuture<'_> {
 
                 self.session.debug_log_packet("Receive", &packet);
 
+                #[cfg(feature = "packet-capture")]
+                if let Some(capture) = &self.capture {
+                    capture.record(Direction::Inbound, &packet);
+                }
+
+                // Include the current game in any crash reports for the packet
+                crash_reports::set_game(self.session.data.lock().game_id());
+
                 let future = self.router.handle(self.session.clone(), packet);
 
                 // Move onto a handling state
@@ -546,12 +735,42 @@ impl SessionFuture<'_> {
                 // Send the response to the writer
                 _ = self.session.tx.send(response);
 
+                // Packets can't be received while handling so the idle time
+                // starts once the handler is complete
+                self.last_received = Instant::now();
+
                 // Reset back to the reading state
                 self.read_state = ReadState::Recv;
             }
         }
         Poll::Ready(())
     }
+
+    /// Polls the idle timer, stopping the future when no packets have
+    /// been received within the idle timeout. Clients that silently drop
+    /// never close the connection so the session would otherwise keep
+    /// its game slot forever
+    fn poll_idle(&mut self, cx: &mut Context<'_>) {
+        let Some(idle_timer) = &mut self.idle_timer else {
+            return;
+        };
+
+        while idle_timer.as_mut().poll(cx).is_ready() {
+            let deadline = self.last_received + self.idle_timeout;
+            if deadline <= Instant::now() {
+                warn!(
+                    "Session timed out after {}s without receiving packets (SID: {})",
+                    self.idle_timeout.as_secs(),
+                    self.session.uuid
+                );
+                self.stop = true;
+                return;
+            }
+
+            // Packets were received since the timer was set
+            idle_timer.as_mut().reset(deadline);
+        }
+    }
 }
 
 impl Future for SessionFuture<'_> {
@@ -562,6 +781,7 @@ impl Future for SessionFuture<'_> {
 
         while this.poll_write_state(cx).is_ready() {}
         while this.poll_read_state(cx).is_ready() {}
+        this.poll_idle(cx);
 
         if this.stop {
             Poll::Ready(())
@@ -0,0 +1,86 @@
+//! Badge levels that have already been rewarded to users, badge progress
+//! is reported by the client on every mission so this is used to only
+//! reward each badge level once
+
+use super::{users::UserId, User};
+use crate::{database::DbResult, definitions::badges::BadgeName, utils::clock};
+use sea_orm::{entity::prelude::*, sea_query::OnConflict, ActiveValue::Set};
+use std::collections::HashMap;
+
+/// Rewarded badge database structure
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "badge_rewards")]
+pub struct Model {
+    /// Unique ID of the rewarded badge
+    #[sea_orm(primary_key)]
+    pub id: u32,
+    /// ID of the user the badge was rewarded to
+    pub user_id: UserId,
+    /// Name of the rewarded badge
+    pub badge_name: BadgeName,
+    /// Number of badge levels that have been rewarded
+    pub rewarded_levels: u32,
+    /// When the last badge level was rewarded
+    pub updated: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::users::Entity",
+        from = "Column::UserId",
+        to = "super::users::Column::Id"
+    )]
+    User,
+}
+
+impl Model {
+    /// Gets the number of rewarded levels for each badge the
+    /// `user` has been rewarded
+    pub async fn all<C>(db: &C, user: &User) -> DbResult<HashMap<BadgeName, u32>>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let values = user.find_related(Entity).all(db).await?;
+        Ok(values
+            .into_iter()
+            .map(|value| (value.badge_name, value.rewarded_levels))
+            .collect())
+    }
+
+    /// Sets the number of rewarded levels for the `badge_name` badge
+    pub async fn set_rewarded<C>(
+        db: &C,
+        user: &User,
+        badge_name: BadgeName,
+        rewarded_levels: u32,
+    ) -> DbResult<()>
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::insert(ActiveModel {
+            user_id: Set(user.id),
+            badge_name: Set(badge_name),
+            rewarded_levels: Set(rewarded_levels),
+            updated: Set(clock::now()),
+            ..Default::default()
+        })
+        .on_conflict(
+            OnConflict::columns([Column::UserId, Column::BadgeName])
+                .update_columns([Column::RewardedLevels, Column::Updated])
+                .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+
+        Ok(())
+    }
+}
+
+impl Related<super::users::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -3,9 +3,8 @@ use crate::{
     database::DbResult,
     definitions::challenges::{ChallengeCounter, ChallengeDefinition, ChallengeName},
     services::game::ChallengeProgressChange,
-    utils::ImStr,
+    utils::{clock, ImStr},
 };
-use chrono::Utc;
 use sea_orm::{entity::prelude::*, ActiveValue::Set, IntoActiveModel};
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
@@ -133,6 +132,19 @@ impl Model {
         user.find_related(Entity).all(db)
     }
 
+    /// Deletes all the challenge progress belonging to the provided
+    /// `user`, responds with the number of deleted challenges
+    pub async fn delete_all<C>(db: &C, user: &User) -> DbResult<u64>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let result = Entity::delete_many()
+            .filter(Column::UserId.eq(user.id))
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
     /// Finds a specific [ChallengeProgress] by ID
     pub fn get<'db, C>(
         db: &'db C,
@@ -160,7 +172,7 @@ impl Model {
             return Ok(existing);
         }
 
-        let now = Utc::now();
+        let now = clock::now();
         // Create new model
         Entity::insert(ActiveModel {
             user_id: Set(user.id),
@@ -183,17 +195,19 @@ impl Model {
             .ok_or(DbErr::RecordNotInserted)
     }
 
+    /// Applies the progress `change` to the challenge progress of `user`, also
+    /// provides the number of new times the challenge counter was completed
     pub async fn update<C>(
         db: &C,
         user: &User,
         change: &ChallengeProgressChange,
-    ) -> DbResult<(Self, ChallengeProgressCounter, CounterUpdateType)>
+    ) -> DbResult<(Self, ChallengeProgressCounter, CounterUpdateType, u32)>
     where
         C: ConnectionTrait + Send,
     {
         // TODO: How are challenges reset?
 
-        let now = Utc::now();
+        let now = clock::now();
 
         // Load the challenge
         let mut challenge = Self::get_or_create(db, user, change.definition.name).await?;
@@ -219,7 +233,7 @@ impl Model {
                 times_completed: 0,
                 total_count: 0,
                 current_count: 0,
-                target_count: 0,
+                target_count: change.counter.target_count,
                 reset_count: 0,
                 last_changed: now,
             });
@@ -241,8 +255,12 @@ impl Model {
 
         // First completion
         let first_completion = prev_completion_times == 0 && counter.times_completed > 0;
+        // Number of new completions
+        let completions = counter
+            .times_completed
+            .saturating_sub(prev_completion_times);
         // Challenge counter was completed
-        let completed = prev_completion_times != counter.times_completed;
+        let completed = completions > 0;
 
         // Update the stored challenge progress
         let mut model = challenge.into_active_model();
@@ -257,10 +275,12 @@ impl Model {
         if completed {
             model.last_completed = Set(Some(now));
             model.state = Set(ChallengeState::Completed);
+            // Rewards are given by the caller for each completion
+            model.rewarded = Set(true);
         }
 
         let model = model.update(db).await?;
-        Ok((model, counter, update_type))
+        Ok((model, counter, update_type, completions))
     }
 }
 
@@ -0,0 +1,119 @@
+//! Previous customization states of characters, a limited number of the
+//! most recent states are kept so that customization changes can be reverted
+
+use super::characters::CharacterId;
+use crate::{database::DbResult, definitions::classes::CustomizationMap, utils::clock};
+use sea_orm::{entity::prelude::*, ActiveValue::Set, QueryOrder, QuerySelect};
+use serde::Serialize;
+
+/// Type alias for a [u32] representing a customization history ID
+pub type CustomizationHistoryId = u32;
+
+/// Customization history database structure
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "character_customization_history")]
+#[serde(rename_all = "camelCase")]
+pub struct Model {
+    /// Unique ID of the stored customization
+    #[sea_orm(primary_key)]
+    pub id: CustomizationHistoryId,
+    /// ID of the character the customization was for
+    #[serde(skip)]
+    pub character_id: CharacterId,
+    /// The previous customization data
+    pub customization: CustomizationMap,
+    /// When the customization was replaced
+    pub created: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::characters::Entity",
+        from = "Column::CharacterId",
+        to = "super::characters::Column::Id"
+    )]
+    Character,
+}
+
+impl Model {
+    /// Maximum number of previous customization states kept for a character
+    pub const MAX_STORED: u64 = 10;
+
+    /// Stores the previous `customization` of the character with the
+    /// provided `character_id`, the oldest states beyond [Self::MAX_STORED]
+    /// are removed
+    pub async fn create<C>(
+        db: &C,
+        character_id: CharacterId,
+        customization: CustomizationMap,
+    ) -> DbResult<Self>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let model = ActiveModel {
+            character_id: Set(character_id),
+            customization: Set(customization),
+            created: Set(clock::now()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
+        let expired: Vec<CustomizationHistoryId> = Entity::find()
+            .filter(Column::CharacterId.eq(character_id))
+            .order_by_desc(Column::Id)
+            .offset(Self::MAX_STORED)
+            .select_only()
+            .column(Column::Id)
+            .into_tuple()
+            .all(db)
+            .await?;
+
+        if !expired.is_empty() {
+            Entity::delete_many()
+                .filter(Column::Id.is_in(expired))
+                .exec(db)
+                .await?;
+        }
+
+        Ok(model)
+    }
+
+    /// Gets the stored customization states for the character with
+    /// the provided `character_id`, most recent first
+    pub async fn all<C>(db: &C, character_id: CharacterId) -> DbResult<Vec<Self>>
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::find()
+            .filter(Column::CharacterId.eq(character_id))
+            .order_by_desc(Column::Id)
+            .all(db)
+            .await
+    }
+
+    /// Gets a stored customization state by `id` for the character
+    /// with the provided `character_id`
+    pub async fn get<C>(
+        db: &C,
+        character_id: CharacterId,
+        id: CustomizationHistoryId,
+    ) -> DbResult<Option<Self>>
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::find_by_id(id)
+            .filter(Column::CharacterId.eq(character_id))
+            .one(db)
+            .await
+    }
+}
+
+impl Related<super::characters::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Character.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
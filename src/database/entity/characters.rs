@@ -3,8 +3,8 @@ use crate::{
     database::DbResult,
     definitions::{
         classes::{
-            CharacterAttributes, CharacterBonus, CharacterEquipment, ClassName, CustomizationMap,
-            PointMap,
+            CharacterAttributes, CharacterBonus, CharacterEquipment, ClassName, Classes,
+            CustomizationMap, EquipmentSlot, PointMap,
         },
         level_tables::ProgressionXp,
         skills::SkillTree,
@@ -26,6 +26,9 @@ use uuid::Uuid;
 /// UUIDs as primary keys in the SQLite database (Basically defeats the purpose of SeaORM)
 pub type CharacterId = u32;
 
+/// Number of skill points spent on the default skill trees of a new character
+pub const DEFAULT_SKILL_POINTS_SPENT: u32 = 3;
+
 /// Character data database structure
 #[derive(Clone, Debug, DeriveEntityModel)]
 #[sea_orm(table_name = "characters")]
@@ -96,6 +99,14 @@ impl Related<super::users::Entity> for Entity {
 impl ActiveModelBehavior for ActiveModel {}
 
 impl Model {
+    /// Equipment slots the character has unlocked at its current level
+    pub fn unlocked_slots(&self) -> Vec<EquipmentSlot> {
+        Classes::get()
+            .by_name(&self.class_name)
+            .map(|class| class.unlocked_slots(self.level))
+            .unwrap_or_default()
+    }
+
     pub fn update_xp<C>(
         self,
         db: &C,
@@ -124,6 +135,64 @@ impl Model {
         model.update(db)
     }
 
+    /// Skill points the character can spend on its skill trees, points
+    /// already spent are included as they can be reallocated
+    pub fn available_skill_points(&self) -> u32 {
+        self.points
+            .skill_points
+            .unwrap_or_default()
+            .saturating_add(self.points_spent.skill_points.unwrap_or_default())
+    }
+
+    /// Replaces the character skill trees with the `skill_trees` costing
+    /// `cost` skill points, the cost must be within the available points
+    pub fn set_skill_trees<C>(
+        self,
+        db: &C,
+        skill_trees: Vec<SkillTree>,
+        cost: u32,
+    ) -> impl Future<Output = DbResult<Self>> + '_
+    where
+        C: ConnectionTrait + Send,
+    {
+        let available = self.available_skill_points();
+
+        let mut model = self.into_active_model();
+        model.points = Set(PointMap {
+            skill_points: Some(available.saturating_sub(cost)),
+        });
+        model.points_spent = Set(PointMap {
+            skill_points: Some(cost),
+        });
+        model.skill_trees = Set(SeaJson(skill_trees));
+        model.update(db)
+    }
+
+    /// Resets the character skill trees to the provided default `skill_trees`
+    /// refunding the skill points spent beyond the defaults
+    pub fn respec<C>(
+        self,
+        db: &C,
+        skill_trees: Vec<SkillTree>,
+    ) -> impl Future<Output = DbResult<Self>> + '_
+    where
+        C: ConnectionTrait + Send,
+    {
+        let spent = self.points_spent.skill_points.unwrap_or_default();
+        let refund = spent.saturating_sub(DEFAULT_SKILL_POINTS_SPENT);
+        let available = self.points.skill_points.unwrap_or_default();
+
+        let mut model = self.into_active_model();
+        model.points = Set(PointMap {
+            skill_points: Some(available.saturating_add(refund)),
+        });
+        model.points_spent = Set(PointMap {
+            skill_points: Some(spent.min(DEFAULT_SKILL_POINTS_SPENT)),
+        });
+        model.skill_trees = Set(SeaJson(skill_trees));
+        model.update(db)
+    }
+
     /// Creates a new character from the provided base details
     #[allow(clippy::too_many_arguments)]
     pub fn create<'db, C>(
@@ -152,7 +221,7 @@ impl Model {
             points: Set(points),
             // 3 of the 5 points are spent by default
             points_spent: Set(PointMap {
-                skill_points: Some(3),
+                skill_points: Some(DEFAULT_SKILL_POINTS_SPENT),
             }),
             points_granted: Set(PointMap::default()),
             skill_trees: Set(SeaJson(skill_trees)),
@@ -220,9 +289,9 @@ impl Serialize for Model {
         S: Serializer,
     {
         let mut state: <S as Serializer>::SerializeStruct =
-            Serializer::serialize_struct(serializer, "Character", 19)?;
+            Serializer::serialize_struct(serializer, "Character", 20)?;
         state.serialize_field("characterId", &self.id.to_string())?;
-        state.serialize_field("sku", &Sku)?;
+        state.serialize_field("sku", &Sku::default())?;
         state.serialize_field("characterClassName", &self.class_name)?;
         state.serialize_field("name", &self.class_name)?;
         state.serialize_field("level", &self.level)?;
@@ -235,6 +304,7 @@ impl Serialize for Model {
         state.serialize_field("attributes", &self.attributes)?;
         state.serialize_field("bonus", &self.bonus)?;
         state.serialize_field("equipments", &self.equipments)?;
+        state.serialize_field("unlockedSlots", &self.unlocked_slots())?;
         state.serialize_field("customization", &self.customization)?;
         state.serialize_field("playStats", &self.play_stats)?;
         // Inventory namespace always appears to be "default"
@@ -1,7 +1,12 @@
-use super::{users::UserId, Currency, User};
-use crate::database::DbResult;
+use super::{
+    currency_ledger::CurrencyLedgerReason, users::UserId, Currency, CurrencyLedgerEntry, User,
+};
+use crate::{
+    config::Config,
+    database::{sql, DbResult},
+};
 use sea_orm::{
-    entity::prelude::*, sea_query::OnConflict, ActiveValue::Set, InsertResult, IntoActiveModel,
+    entity::prelude::*, sea_query::OnConflict, ActiveValue::Set, DbBackend, InsertResult,
 };
 use serde::{ser::SerializeStruct, Deserialize, Serialize};
 use serde_with::{DeserializeAs, DisplayFromStr};
@@ -29,6 +34,27 @@ pub enum CurrencyType {
     Mtx = 0,
     Grind = 1,
     Mission = 2,
+    /// Server only currency earned by completing challenges
+    ChallengePoints = 3,
+    /// Server only currency earned from apex point items
+    ApexPoints = 4,
+}
+
+impl CurrencyType {
+    /// Currencies known by the game client, other currencies
+    /// are only used by the server
+    pub const CLIENT: [CurrencyType; 3] = [Self::Mtx, Self::Grind, Self::Mission];
+
+    /// The maximum balance allowed for this currency, uses the configured
+    /// cap limited to [Model::MAX_SAFE_CURRENCY]
+    pub fn max_balance(self) -> u32 {
+        Config::get()
+            .currency_caps
+            .get(&self)
+            .copied()
+            .unwrap_or(Model::MAX_SAFE_CURRENCY)
+            .min(Model::MAX_SAFE_CURRENCY)
+    }
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -124,21 +150,57 @@ impl Model {
         .exec(db)
     }
 
-    /// Updates the currency balance setting it to the provided `amount`
-    pub fn update<C>(self, db: &C, amount: u32) -> impl Future<Output = DbResult<Self>> + '_
+    /// Takes the `amount` from the balance of the `ty` currency for the
+    /// `user` in a single update that only applies when the balance can
+    /// cover the amount. Responds with the updated currency or [None] if
+    /// the user can't afford the amount
+    pub async fn try_spend<C>(
+        db: &C,
+        user: &User,
+        ty: CurrencyType,
+        amount: u32,
+    ) -> DbResult<Option<Currency>>
     where
         C: ConnectionTrait + Send,
     {
-        let mut model = self.into_active_model();
-        model.balance = Set(amount);
-        model.update(db)
+        let result = Entity::update_many()
+            .col_expr(Column::Balance, Expr::col(Column::Balance).sub(amount))
+            .filter(
+                Column::UserId
+                    .eq(user.id)
+                    .and(Column::Ty.eq(ty))
+                    .and(Column::Balance.gte(amount)),
+            )
+            .exec(db)
+            .await?;
+
+        if result.rows_affected == 0 {
+            return Ok(None);
+        }
+
+        Self::get(db, user, ty).await
     }
 
-    /// Finds all the currency entities for the provided `user`
+    /// Finds all the currency entities for the provided `user` that
+    /// are known by the game client
     pub fn all<'db, C>(
         db: &'db C,
         user: &User,
     ) -> impl Future<Output = DbResult<Vec<Currency>>> + 'db
+    where
+        C: ConnectionTrait + Send,
+    {
+        user.find_related(Entity)
+            .filter(Column::Ty.is_in(CurrencyType::CLIENT))
+            .all(db)
+    }
+
+    /// Finds all the currency entities for the provided `user` including
+    /// the server only currencies
+    pub fn all_types<'db, C>(
+        db: &'db C,
+        user: &User,
+    ) -> impl Future<Output = DbResult<Vec<Currency>>> + 'db
     where
         C: ConnectionTrait + Send,
     {
@@ -159,16 +221,37 @@ impl Model {
 
     /// Conflict strategy for adding the balancing onto
     /// an existing balance
-    fn add_balance_conflict() -> OnConflict {
+    fn add_balance_conflict(backend: DbBackend) -> OnConflict {
+        // Maximum balance for each of the currency types
+        let mut limit = format!("CASE {}", sql::excluded(backend, "ty"));
+        let mut values: Vec<u32> = Vec::new();
+        for ty in CurrencyType::iter() {
+            limit.push_str(" WHEN ? THEN ?");
+            values.extend([ty as u32, ty.max_balance()]);
+        }
+        limit.push_str(" ELSE ? END");
+        values.push(Self::MAX_SAFE_CURRENCY);
+
         // Update the value column if a key already exists
         OnConflict::columns([Column::UserId, Column::Ty])
             .value(
                 Column::Balance,
                 // Adds the balance to the existing balance without surpassing
-                // the safe currency limit
+                // the currency limit
                 Expr::cust_with_values(
-                    "(SELECT MIN(`balance` + `excluded`.`balance`, ?))",
-                    [Self::MAX_SAFE_CURRENCY],
+                    sql::quoted(
+                        backend,
+                        &sql::least(
+                            backend,
+                            &format!(
+                                "{} + {}, {}",
+                                sql::target("currency", "balance"),
+                                sql::excluded(backend, "balance"),
+                                limit
+                            ),
+                        ),
+                    ),
+                    values,
                 ),
             )
             .to_owned()
@@ -187,12 +270,53 @@ impl Model {
         Entity::insert(ActiveModel {
             user_id: Set(user.id),
             ty: Set(ty),
-            balance: Set(amount),
+            balance: Set(amount.min(ty.max_balance())),
         })
-        .on_conflict(Self::add_balance_conflict())
+        .on_conflict(Self::add_balance_conflict(db.get_database_backend()))
         .exec(db)
     }
 
+    /// Earns `amount` of the `ty` currency for the `user` without going over
+    /// the currency cap, the change is recorded in the currency ledger.
+    /// Responds with the amount that was actually added
+    pub async fn earn<C>(
+        db: &C,
+        user: &User,
+        ty: CurrencyType,
+        amount: u32,
+        reason: CurrencyLedgerReason,
+    ) -> DbResult<u32>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let balance = Self::get(db, user, ty)
+            .await?
+            .map(|currency| currency.balance)
+            .unwrap_or_default();
+        let added = amount.min(ty.max_balance().saturating_sub(balance));
+
+        if added > 0 {
+            Self::add(db, user, ty, added).await?;
+            CurrencyLedgerEntry::create(db, user, ty, added as i64, reason).await?;
+        }
+
+        Ok(added)
+    }
+
+    /// Checks whether the `ty` currency balance of the `user` can hold
+    /// another `amount` without going over the currency cap
+    pub async fn can_hold<C>(db: &C, user: &User, ty: CurrencyType, amount: u32) -> DbResult<bool>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let balance = Self::get(db, user, ty)
+            .await?
+            .map(|currency| currency.balance)
+            .unwrap_or_default();
+
+        Ok(amount <= ty.max_balance().saturating_sub(balance))
+    }
+
     /// Adds an amount to multiple balances
     pub fn add_many<'db, C, I>(
         db: &'db C,
@@ -206,9 +330,9 @@ impl Model {
         Entity::insert_many(values.into_iter().map(|(ty, value)| ActiveModel {
             user_id: Set(user.id),
             ty: Set(ty),
-            balance: Set(value),
+            balance: Set(value.min(ty.max_balance())),
         }))
-        .on_conflict(Self::add_balance_conflict())
+        .on_conflict(Self::add_balance_conflict(db.get_database_backend()))
         .exec(db)
     }
 }
@@ -231,6 +355,8 @@ impl Display for CurrencyType {
             CurrencyType::Mtx => "MTXCurrency",
             CurrencyType::Grind => "GrindCurrency",
             CurrencyType::Mission => "MissionCurrency",
+            CurrencyType::ChallengePoints => "ChallengePointCurrency",
+            CurrencyType::ApexPoints => "ApexPointCurrency",
         })
     }
 }
@@ -247,6 +373,8 @@ impl FromStr for CurrencyType {
             "MTXCurrency" => Self::Mtx,
             "GrindCurrency" => Self::Grind,
             "MissionCurrency" => Self::Mission,
+            "ChallengePointCurrency" => Self::ChallengePoints,
+            "ApexPointCurrency" => Self::ApexPoints,
             _ => return Err(UnknownCurrency),
         })
     }
@@ -277,3 +405,29 @@ impl<'de> Deserialize<'de> for CurrencyType {
         DisplayFromStr::deserialize_as(deserializer)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{ActiveModel, CurrencyType, Entity, Model};
+    use sea_orm::{ActiveValue::Set, DbBackend, EntityTrait, QueryTrait};
+
+    /// Tests that the existing balance is qualified with the table name
+    /// when adding balances on Postgres, where an unqualified column is
+    /// ambiguous with the `excluded` row
+    #[test]
+    fn test_add_balance_conflict_postgres() {
+        let backend = DbBackend::Postgres;
+        let statement = Entity::insert(ActiveModel {
+            user_id: Set(1),
+            ty: Set(CurrencyType::Mission),
+            balance: Set(10),
+        })
+        .on_conflict(Model::add_balance_conflict(backend))
+        .build(backend);
+
+        assert!(statement.sql.contains(
+            r#"LEAST("currency"."balance" + "excluded"."balance", CASE "excluded"."ty""#
+        ));
+        assert!(!statement.sql.contains('`'));
+    }
+}
@@ -0,0 +1,187 @@
+//! Ledger recording changes to user currency balances, each entry
+//! records the amount a balance changed by and the reason for the change
+
+use super::{currency::CurrencyType, users::UserId, User};
+use crate::{database::DbResult, utils::clock};
+use sea_orm::{entity::prelude::*, sea_query::Expr, ActiveValue::Set, QueryOrder, QuerySelect};
+use serde::Serialize;
+use std::future::Future;
+
+/// Type alias for a [u32] representing a ledger entry ID
+pub type CurrencyLedgerId = u32;
+
+/// Currency ledger database structure
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "currency_ledger")]
+#[serde(rename_all = "camelCase")]
+pub struct Model {
+    /// Unique ID of the ledger entry
+    #[sea_orm(primary_key)]
+    pub id: CurrencyLedgerId,
+    /// ID of the user the entry is for
+    #[serde(skip)]
+    pub user_id: UserId,
+    /// The currency that was changed
+    pub ty: CurrencyType,
+    /// The amount the balance changed by, negative
+    /// for currency that was taken
+    pub amount: i64,
+    /// Reason for the change
+    pub reason: CurrencyLedgerReason,
+    /// When the change occurred
+    pub created: DateTimeUtc,
+}
+
+/// Reasons for currency balance changes
+#[derive(Debug, EnumIter, DeriveActiveEnum, Clone, Copy, PartialEq, Eq, Serialize)]
+#[sea_orm(rs_type = "u8", db_type = "Integer")]
+#[serde(rename_all = "camelCase")]
+#[repr(u8)]
+pub enum CurrencyLedgerReason {
+    /// Currency converted to another currency type
+    Conversion = 0,
+    /// Currency earned from completing a mission
+    MissionReward = 1,
+    /// Currency spent purchasing a store article
+    StorePurchase = 2,
+    /// Currency returned from refunding a store purchase
+    StoreRefund = 3,
+    /// Currency granted by an administrator
+    AdminGrant = 4,
+    /// Currency earned from completing a challenge
+    ChallengeReward = 5,
+    /// Currency granted by the effect of an inventory item
+    ItemEffect = 6,
+    /// Currency earned from a strike team mission
+    StrikeTeamReward = 7,
+    /// Currency granted by the welcome kit for new accounts
+    WelcomeKit = 8,
+    /// Currency granted for retiring a strike team
+    StrikeTeamRetirement = 9,
+    /// Currency adjusted by an administrator inventory import
+    AdminImport = 10,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::users::Entity",
+        from = "Column::UserId",
+        to = "super::users::Column::Id"
+    )]
+    User,
+}
+
+impl Model {
+    /// Records a change of `amount` to the `ty` currency balance
+    /// of the provided `user`
+    pub fn create<'db, C>(
+        db: &'db C,
+        user: &User,
+        ty: CurrencyType,
+        amount: i64,
+        reason: CurrencyLedgerReason,
+    ) -> impl Future<Output = DbResult<Self>> + Send + 'db
+    where
+        C: ConnectionTrait + Send,
+    {
+        ActiveModel {
+            user_id: Set(user.id),
+            ty: Set(ty),
+            amount: Set(amount),
+            reason: Set(reason),
+            created: Set(clock::now()),
+            ..Default::default()
+        }
+        .insert(db)
+    }
+
+    /// Finds a page of the ledger entries for the `user`,
+    /// most recent entries first
+    pub fn page<'db, C>(
+        db: &'db C,
+        user: &User,
+        offset: u64,
+        count: u64,
+    ) -> impl Future<Output = DbResult<Vec<Self>>> + Send + 'db
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::find()
+            .filter(Column::UserId.eq(user.id))
+            .order_by_desc(Column::Created)
+            .order_by_desc(Column::Id)
+            .offset(offset)
+            .limit(count)
+            .all(db)
+    }
+
+    /// Sums the amounts of the `ty` currency taken from `user` with the
+    /// provided `reason` that occurred after `since`. Only outgoing changes
+    /// are counted so currency gained for the same reason can't offset
+    /// what was spent
+    pub async fn spent_since<C>(
+        db: &C,
+        user: &User,
+        ty: CurrencyType,
+        reason: CurrencyLedgerReason,
+        since: DateTimeUtc,
+    ) -> DbResult<u64>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let total: Option<Option<i64>> = Entity::find()
+            .select_only()
+            .column_as(Expr::col(Column::Amount).sum(), "total")
+            .filter(
+                Column::UserId
+                    .eq(user.id)
+                    .and(Column::Ty.eq(ty))
+                    .and(Column::Reason.eq(reason))
+                    .and(Column::Created.gte(since))
+                    .and(Column::Amount.lt(0)),
+            )
+            .into_tuple()
+            .one(db)
+            .await?;
+
+        Ok(total.flatten().unwrap_or_default().unsigned_abs())
+    }
+
+    /// Sums the amounts of the `ty` currency changes for all the users in
+    /// `user_ids` with the provided `reason` that occurred after `since`
+    pub async fn sum_since_users<C>(
+        db: &C,
+        user_ids: Vec<UserId>,
+        ty: CurrencyType,
+        reason: CurrencyLedgerReason,
+        since: DateTimeUtc,
+    ) -> DbResult<i64>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let total: Option<Option<i64>> = Entity::find()
+            .select_only()
+            .column_as(Expr::col(Column::Amount).sum(), "total")
+            .filter(
+                Column::UserId
+                    .is_in(user_ids)
+                    .and(Column::Ty.eq(ty))
+                    .and(Column::Reason.eq(reason))
+                    .and(Column::Created.gte(since)),
+            )
+            .into_tuple()
+            .one(db)
+            .await?;
+
+        Ok(total.flatten().unwrap_or_default())
+    }
+}
+
+impl Related<super::users::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
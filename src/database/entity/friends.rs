@@ -0,0 +1,142 @@
+//! Friend relationships between users, a user adding another user as a
+//! friend creates a one way relationship that becomes mutual once the
+//! other user adds them back. Presence is only shared between mutual friends
+
+use super::users::UserId;
+use crate::{database::DbResult, utils::clock};
+use sea_orm::{entity::prelude::*, sea_query::OnConflict, ActiveValue::Set, Condition, QueryOrder};
+use serde::Serialize;
+
+/// Friend relationship database structure
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "friends")]
+#[serde(rename_all = "camelCase")]
+pub struct Model {
+    /// ID of the user that added the friend
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub user_id: UserId,
+    /// ID of the user that was added as a friend
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub friend_id: UserId,
+    /// When the friend was added
+    pub created: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::users::Entity",
+        from = "Column::UserId",
+        to = "super::users::Column::Id"
+    )]
+    User,
+}
+
+impl Model {
+    /// Adds the user with the provided `friend_id` as a friend of the user
+    /// with the provided `user_id`, adding an existing friend does nothing
+    pub async fn add<C>(db: &C, user_id: UserId, friend_id: UserId) -> DbResult<()>
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::insert(ActiveModel {
+            user_id: Set(user_id),
+            friend_id: Set(friend_id),
+            created: Set(clock::now()),
+        })
+        .on_conflict(
+            OnConflict::columns([Column::UserId, Column::FriendId])
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Removes the user with the provided `friend_id` from the friends of
+    /// the user with the provided `user_id`, returns whether the user was
+    /// a friend
+    pub async fn remove<C>(db: &C, user_id: UserId, friend_id: UserId) -> DbResult<bool>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let result = Entity::delete_many()
+            .filter(Column::UserId.eq(user_id))
+            .filter(Column::FriendId.eq(friend_id))
+            .exec(db)
+            .await?;
+
+        Ok(result.rows_affected > 0)
+    }
+
+    /// Gets all the friends added by the user with the provided `user_id`
+    pub async fn all<C>(db: &C, user_id: UserId) -> DbResult<Vec<Self>>
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::find()
+            .filter(Column::UserId.eq(user_id))
+            .order_by_asc(Column::Created)
+            .all(db)
+            .await
+    }
+
+    /// Gets the IDs of the users that have added the user with the
+    /// provided `user_id` as a friend
+    pub async fn added_by<C>(db: &C, user_id: UserId) -> DbResult<Vec<UserId>>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let friends = Entity::find()
+            .filter(Column::FriendId.eq(user_id))
+            .all(db)
+            .await?;
+
+        Ok(friends.into_iter().map(|friend| friend.user_id).collect())
+    }
+
+    /// Gets the IDs of the mutual friends of the user with the provided
+    /// `user_id`, users that have both added each other
+    pub async fn mutual<C>(db: &C, user_id: UserId) -> DbResult<Vec<UserId>>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let added_by = Self::added_by(db, user_id).await?;
+        let friends = Self::all(db, user_id).await?;
+
+        Ok(friends
+            .into_iter()
+            .map(|friend| friend.friend_id)
+            .filter(|friend_id| added_by.contains(friend_id))
+            .collect())
+    }
+
+    /// Checks whether the users with the provided IDs are mutual friends
+    pub async fn is_mutual<C>(db: &C, user_id: UserId, friend_id: UserId) -> DbResult<bool>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let count = Entity::find()
+            .filter(
+                Condition::any()
+                    .add(
+                        Column::UserId
+                            .eq(user_id)
+                            .and(Column::FriendId.eq(friend_id)),
+                    )
+                    .add(
+                        Column::UserId
+                            .eq(friend_id)
+                            .and(Column::FriendId.eq(user_id)),
+                    ),
+            )
+            .count(db)
+            .await?;
+
+        Ok(count == 2)
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,82 @@
+//! Records of the lifecycle of games that have stopped, used for
+//! tuning matchmaking and mission difficulty
+
+use crate::{database::DbResult, utils::clock};
+use sea_orm::{entity::prelude::*, ActiveValue::Set, DeleteResult};
+use std::future::Future;
+
+/// Type alias for a [u32] representing a game record ID
+pub type GameRecordId = u32;
+
+/// Game record database structure
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "game_records")]
+pub struct Model {
+    /// Unique ID of the game record
+    #[sea_orm(primary_key)]
+    pub id: GameRecordId,
+    /// Difficulty of the last mission played, not present when
+    /// no mission was finished in the game
+    pub difficulty: Option<String>,
+    /// Seconds the game existed for
+    pub duration_secs: u32,
+    /// Whether the last mission played was fully completed
+    pub completed: bool,
+    /// Most players that were in the game at once
+    pub peak_players: u32,
+    /// When the game stopped
+    pub created: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl Model {
+    /// Records a stopped game
+    pub fn create<C>(
+        db: &C,
+        difficulty: Option<String>,
+        duration_secs: u32,
+        completed: bool,
+        peak_players: u32,
+    ) -> impl Future<Output = DbResult<Self>> + Send + '_
+    where
+        C: ConnectionTrait + Send,
+    {
+        ActiveModel {
+            difficulty: Set(difficulty),
+            duration_secs: Set(duration_secs),
+            completed: Set(completed),
+            peak_players: Set(peak_players),
+            created: Set(clock::now()),
+            ..Default::default()
+        }
+        .insert(db)
+    }
+
+    /// Finds all the records created after `since`
+    pub fn since<C>(
+        db: &C,
+        since: DateTimeUtc,
+    ) -> impl Future<Output = DbResult<Vec<Self>>> + Send + '_
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::find().filter(Column::Created.gte(since)).all(db)
+    }
+
+    /// Deletes all the records created before `before`
+    pub fn delete_before<C>(
+        db: &C,
+        before: DateTimeUtc,
+    ) -> impl Future<Output = DbResult<DeleteResult>> + Send + '_
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::delete_many()
+            .filter(Column::Created.lt(before))
+            .exec(db)
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,88 @@
+//! Missions that were still in flight when the server shut down, stored so
+//! that they can be restored when the server starts again
+
+use super::{strike_team_mission::StrikeTeamMissionId, users::UserId, SeaJson};
+use crate::{
+    database::DbResult,
+    http::models::mission::CompleteMissionData,
+    services::{
+        game::{GameID, MissionReservation},
+        shutdown::AppliedProgress,
+    },
+    utils::clock,
+};
+use sea_orm::{entity::prelude::*, ActiveValue::Set, QueryOrder};
+
+/// Interrupted mission database structure
+#[derive(Clone, Debug, DeriveEntityModel)]
+#[sea_orm(table_name = "interrupted_missions")]
+pub struct Model {
+    /// Unique ID of the interrupted mission
+    #[sea_orm(primary_key)]
+    pub id: u32,
+    /// ID of the game the mission was played in
+    pub game_id: GameID,
+    /// ID of the user that started the mission, [None] when the
+    /// mission had already finished
+    pub started_by: Option<UserId>,
+    /// The APEX mission that was being played if the mission is one
+    pub mission: Option<StrikeTeamMissionId>,
+    /// When the mission was started
+    pub started: Option<DateTimeUtc>,
+    /// Submitted mission data that hadn't been processed
+    pub mission_data: SeaJson<Option<CompleteMissionData>>,
+    /// Progress already written from activities reported during the mission
+    pub applied: SeaJson<Vec<AppliedProgress>>,
+    /// When the mission was interrupted
+    pub created: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl Model {
+    /// Stores a mission from the game with the provided `game_id` that was
+    /// interrupted by the server shutting down
+    pub async fn create<C>(
+        db: &C,
+        game_id: GameID,
+        reservation: Option<MissionReservation>,
+        mission_data: Option<CompleteMissionData>,
+        applied: Vec<AppliedProgress>,
+    ) -> DbResult<Self>
+    where
+        C: ConnectionTrait + Send,
+    {
+        ActiveModel {
+            game_id: Set(game_id),
+            started_by: Set(reservation.as_ref().map(|value| value.started_by)),
+            mission: Set(reservation.as_ref().and_then(|value| value.mission)),
+            started: Set(reservation.map(|value| value.started)),
+            mission_data: Set(SeaJson(mission_data)),
+            applied: Set(SeaJson(applied)),
+            created: Set(clock::now()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+    }
+
+    /// Gets all the interrupted missions in the order they were stored
+    pub async fn all<C>(db: &C) -> DbResult<Vec<Self>>
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::find().order_by_asc(Column::Id).all(db).await
+    }
+
+    /// Removes the interrupted mission once it has been restored
+    pub async fn delete<C>(self, db: &C) -> DbResult<()>
+    where
+        C: ConnectionTrait + Send,
+    {
+        <Self as ModelTrait>::delete(self, db).await?;
+        Ok(())
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
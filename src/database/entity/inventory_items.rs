@@ -7,22 +7,26 @@
 //! SELECT `definition_name` FROM `inventory_items` WHERE `definition_name` = x'af3a2cf0dff74ca8919973ce546c3e7b'`
 //! ```
 //! (Don't include hyphens in the definition name)
+//!
+//! Items are soft deleted by setting `deleted_at` so that refunds and audits can
+//! still see consumed items, deleted items are excluded from every user query and
+//! purged by the database maintenance once they are old enough
 
 use super::{users::UserId, SeaGenericMap};
 use crate::{
     database::{
         entity::{InventoryItem, User},
-        DbResult,
+        sql, DbResult,
     },
     definitions::items::ItemName,
+    utils::clock,
 };
-use chrono::Utc;
 use futures::Future;
 use sea_orm::{
     entity::prelude::*,
     sea_query::{Expr, OnConflict},
     ActiveValue::{NotSet, Set},
-    IntoActiveModel, UpdateResult,
+    DbBackend, DeleteResult, IntoActiveModel, QueryOrder, QuerySelect, Select, UpdateResult,
 };
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
@@ -53,6 +57,9 @@ pub struct Model {
     #[serde(rename = "earndBy")]
     pub earned_by: String,
     pub restricted: bool,
+    /// When the item was deleted, [None] unless the item was deleted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTimeUtc>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -66,8 +73,50 @@ pub enum Relation {
 }
 
 impl Model {
+    /// Conflict strategy for adding the `stack_size` onto the stack
+    /// of an existing item without surpassing the `capacity`
+    fn add_stack_conflict(
+        backend: DbBackend,
+        stack_size: u32,
+        capacity: Option<u32>,
+    ) -> OnConflict {
+        let capacity = capacity.unwrap_or(u32::MAX);
+        // Existing row columns are qualified as they would be ambiguous on Postgres
+        let existing_stack = sql::target("inventory_items", "stack_size");
+        let existing_deleted = sql::target("inventory_items", "deleted_at");
+
+        // Update the value column if a key already exists
+        OnConflict::columns([Column::UserId, Column::DefinitionName])
+            .value(
+                Column::StackSize,
+                // Add the stack size but don't add above the capacity.
+                //
+                // The query below adds the stack size without surpassing
+                // the maximum capacity value, deleted items are replaced
+                // rather than added to
+                Expr::cust_with_values(
+                    sql::quoted(
+                        backend,
+                        &format!(
+                            "(CASE WHEN {} IS NULL THEN {} ELSE {} END)",
+                            existing_deleted,
+                            sql::least(backend, &format!("{} + ?, ?", existing_stack)),
+                            sql::least(backend, "?, ?")
+                        ),
+                    ),
+                    [stack_size, capacity, stack_size, capacity],
+                ),
+            )
+            // Restore the item if it was deleted
+            .value(Column::DeletedAt, Expr::cust("NULL"))
+            // Update the last granted column
+            .update_column(Column::LastGrant)
+            .to_owned()
+    }
+
     /// Adds an item for the provided player. If an item with a matching `definition_name`
-    /// already exists in the database the `stack_size` and `last_grant` columns will be updated
+    /// already exists in the database the `stack_size` and `last_grant` columns will be updated,
+    /// a deleted item is restored with the new `stack_size`
     ///
     /// ## Argumnets
     /// * `db`              - The database connection
@@ -85,7 +134,8 @@ impl Model {
     where
         C: ConnectionTrait + Send,
     {
-        let now = Utc::now();
+        let now = clock::now();
+        let backend = db.get_database_backend();
 
         // Upsert the inventory item
         Entity::insert(ActiveModel {
@@ -99,24 +149,7 @@ impl Model {
             earned_by: Set("granted".to_string()),
             ..Default::default()
         })
-        .on_conflict(
-            // Update the value column if a key already exists
-            OnConflict::columns([Column::UserId, Column::DefinitionName])
-                .value(
-                    Column::StackSize,
-                    // Add the stack size but don't add above the capacity.
-                    //
-                    // The query below adds the stack size without surpassing
-                    // the maximum capacity value
-                    Expr::cust_with_values(
-                        "(SELECT MIN(`stack_size` + ?, ?))",
-                        [stack_size, capacity.unwrap_or(u32::MAX)],
-                    ),
-                )
-                // Update the last granted column
-                .update_column(Column::LastGrant)
-                .to_owned(),
-        )
+        .on_conflict(Self::add_stack_conflict(backend, stack_size, capacity))
         .exec(db)
         .await?;
 
@@ -134,20 +167,20 @@ impl Model {
     }
 
     ///Sets the stack size of the item to `stack_size` if `stack_size` is zero
-    /// then the item will be deleted
+    /// then the item will be deleted, the stack size before deletion is kept
     pub async fn set_stack_size<C>(self, db: &C, stack_size: u32) -> DbResult<()>
     where
         C: ConnectionTrait,
     {
+        let mut model = self.into_active_model();
+
         // Remove empty stacks
         if stack_size == 0 {
-            self.delete(db).await?;
-            return Ok(());
+            model.deleted_at = Set(Some(clock::now()));
+        } else {
+            model.stack_size = Set(stack_size);
         }
 
-        // Update the model
-        let mut model = self.into_active_model();
-        model.stack_size = Set(stack_size);
         _ = model.update(db).await?;
 
         Ok(())
@@ -164,7 +197,46 @@ impl Model {
         // Updates all the matching items seen state
         Entity::update_many()
             .col_expr(Column::Seen, Expr::value(true))
-            .filter(Column::Id.is_in(list).and(Column::UserId.eq(user.id)))
+            .filter(
+                Column::Id
+                    .is_in(list)
+                    .and(Column::UserId.eq(user.id))
+                    .and(Column::DeletedAt.is_null()),
+            )
+            .exec(db)
+    }
+
+    /// Query for the items in the users collection that haven't been deleted
+    fn owned(user: &User) -> Select<Entity> {
+        user.find_related(Entity)
+            .filter(Column::DeletedAt.is_null())
+    }
+
+    /// Finds the deleted items from the users collection of items, most
+    /// recently deleted first
+    pub fn get_deleted_items<'db, C>(
+        db: &'db C,
+        user: &User,
+    ) -> impl Future<Output = DbResult<Vec<InventoryItem>>> + Send + 'db
+    where
+        C: ConnectionTrait + Send,
+    {
+        user.find_related(Entity)
+            .filter(Column::DeletedAt.is_not_null())
+            .order_by_desc(Column::DeletedAt)
+            .all(db)
+    }
+
+    /// Permanently removes the items that were deleted before `before`
+    pub fn purge_deleted_before<C>(
+        db: &C,
+        before: DateTimeUtc,
+    ) -> impl Future<Output = DbResult<DeleteResult>> + Send + '_
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::delete_many()
+            .filter(Column::DeletedAt.lt(before))
             .exec(db)
     }
 
@@ -175,7 +247,7 @@ impl Model {
     where
         C: ConnectionTrait + Send,
     {
-        user.find_related(Entity).all(db)
+        Self::owned(user).all(db)
     }
 
     pub fn get_items<'db, C>(
@@ -186,8 +258,22 @@ impl Model {
     where
         C: ConnectionTrait + Send,
     {
-        user.find_related(Entity)
-            .filter(Column::Id.is_in(ids))
+        Self::owned(user).filter(Column::Id.is_in(ids)).all(db)
+    }
+
+    /// Finds the `count` most recently granted items from the users
+    /// collection of items
+    pub fn get_recent_items<'db, C>(
+        db: &'db C,
+        user: &User,
+        count: u64,
+    ) -> impl Future<Output = DbResult<Vec<InventoryItem>>> + Send + 'db
+    where
+        C: ConnectionTrait + Send,
+    {
+        Self::owned(user)
+            .order_by_desc(Column::LastGrant)
+            .limit(count)
             .all(db)
     }
 
@@ -200,7 +286,7 @@ impl Model {
     where
         C: ConnectionTrait + Send,
     {
-        user.find_related(Entity).filter(Column::Id.eq(id)).one(db)
+        Self::owned(user).filter(Column::Id.eq(id)).one(db)
     }
 
     /// Finds a item with a matching definition `name` within the users
@@ -213,7 +299,7 @@ impl Model {
     where
         C: ConnectionTrait + Send,
     {
-        user.find_related(Entity)
+        Self::owned(user)
             .filter(Column::DefinitionName.eq(name))
             .one(db)
     }
@@ -227,7 +313,7 @@ impl Model {
     where
         C: ConnectionTrait + Send,
     {
-        user.find_related(Entity)
+        Self::owned(user)
             .filter(Column::DefinitionName.is_in(names))
             .all(db)
     }
@@ -240,3 +326,29 @@ impl Related<super::users::Entity> for Entity {
 }
 
 impl ActiveModelBehavior for ActiveModel {}
+
+#[cfg(test)]
+mod test {
+    use super::{ActiveModel, Entity, Model};
+    use sea_orm::{ActiveValue::Set, DbBackend, EntityTrait, QueryTrait};
+
+    /// Tests that the existing stack size and deletion time are qualified
+    /// with the table name when adding items on Postgres, where unqualified
+    /// columns are ambiguous with the `excluded` row
+    #[test]
+    fn test_add_stack_conflict_postgres() {
+        let backend = DbBackend::Postgres;
+        let statement = Entity::insert(ActiveModel {
+            user_id: Set(1),
+            stack_size: Set(1),
+            ..Default::default()
+        })
+        .on_conflict(Model::add_stack_conflict(backend, 1, Some(5)))
+        .build(backend);
+
+        assert!(statement.sql.contains(
+            r#"CASE WHEN "inventory_items"."deleted_at" IS NULL THEN LEAST("inventory_items"."stack_size" + "#
+        ));
+        assert!(!statement.sql.contains('`'));
+    }
+}
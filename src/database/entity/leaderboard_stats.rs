@@ -0,0 +1,148 @@
+//! Aggregated per-user stats that the leaderboards are ranked by, updated
+//! when missions are completed and periodically recalculated
+
+use super::{users::UserId, User};
+use crate::{database::DbResult, utils::clock};
+use sea_orm::{
+    entity::prelude::*, sea_query::OnConflict, ActiveValue::Set, InsertResult, QueryOrder,
+    QuerySelect,
+};
+use std::future::Future;
+
+/// Leaderboard stats database structure
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "leaderboard_stats")]
+pub struct Model {
+    /// ID of the user the stats are for
+    #[sea_orm(primary_key)]
+    pub user_id: UserId,
+    /// Total level of all the user characters
+    pub n7_rating: u32,
+    /// APEX (pathfinder) rating of the user
+    pub apex_rating: f32,
+    /// Points earned from completed challenges
+    pub challenge_points: u32,
+    /// Number of missions the user has completed
+    pub match_count: u32,
+    /// When the stats were last updated
+    pub updated: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::users::Entity",
+        from = "Column::UserId",
+        to = "super::users::Column::Id"
+    )]
+    User,
+}
+
+impl Model {
+    /// Gets the leaderboard stats for the user with the provided `user_id`
+    pub fn get<C>(
+        db: &C,
+        user_id: UserId,
+    ) -> impl Future<Output = DbResult<Option<Self>>> + Send + '_
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::find_by_id(user_id).one(db)
+    }
+
+    /// Sets the leaderboard stats for the `user` replacing any existing
+    /// stats, the match count is increased by `new_matches` within the
+    /// query so concurrent updates don't lose matches
+    pub fn set<'db, C>(
+        db: &'db C,
+        user: &User,
+        n7_rating: u32,
+        apex_rating: f32,
+        challenge_points: u32,
+        new_matches: u32,
+    ) -> impl Future<Output = DbResult<InsertResult<ActiveModel>>> + 'db
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::insert(ActiveModel {
+            user_id: Set(user.id),
+            n7_rating: Set(n7_rating),
+            apex_rating: Set(apex_rating),
+            challenge_points: Set(challenge_points),
+            match_count: Set(new_matches),
+            updated: Set(clock::now()),
+        })
+        .on_conflict(
+            OnConflict::column(Column::UserId)
+                .update_columns([
+                    Column::N7Rating,
+                    Column::ApexRating,
+                    Column::ChallengePoints,
+                    Column::Updated,
+                ])
+                .value(
+                    Column::MatchCount,
+                    Expr::col(Column::MatchCount).add(new_matches),
+                )
+                .to_owned(),
+        )
+        .exec(db)
+    }
+
+    /// Finds a page of stats sorted by the `column` in descending order
+    /// along with the users the stats belong to
+    pub fn page<C>(
+        db: &C,
+        column: Column,
+        offset: u64,
+        count: u64,
+    ) -> impl Future<Output = DbResult<Vec<(Self, Option<User>)>>> + Send + '_
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::find()
+            .find_also_related(super::users::Entity)
+            .order_by_desc(column)
+            // Ties are ranked by who got the stat first
+            .order_by_asc(Column::UserId)
+            .offset(offset)
+            .limit(count)
+            .all(db)
+    }
+
+    /// Finds the zero based position of these stats when sorted by
+    /// the `column` in descending order
+    pub async fn position<C>(&self, db: &C, column: Column) -> DbResult<u64>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let value: Value = ModelTrait::get(self, column);
+
+        Entity::find()
+            .filter(
+                Condition::any().add(column.gt(value.clone())).add(
+                    Condition::all()
+                        .add(column.eq(value))
+                        .add(Column::UserId.lt(self.user_id)),
+                ),
+            )
+            .count(db)
+            .await
+    }
+
+    /// Counts the total number of users with stats
+    pub fn total<C>(db: &C) -> impl Future<Output = DbResult<u64>> + Send + '_
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::find().count(db)
+    }
+}
+
+impl Related<super::users::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,173 @@
+//! Login events recorded whenever a user logs into their account,
+//! allows users to review where their account has been accessed from
+
+use super::{users::UserId, User};
+use crate::{database::DbResult, utils::clock};
+use sea_orm::{entity::prelude::*, ActiveValue::Set, DeleteResult, QueryOrder, QuerySelect};
+use serde::Serialize;
+use serde_with::skip_serializing_none;
+use std::future::Future;
+
+/// Type alias for a [u32] representing a login event ID
+pub type LoginEventId = u32;
+
+/// Login event database structure
+#[skip_serializing_none]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "login_events")]
+#[serde(rename_all = "camelCase")]
+pub struct Model {
+    /// Unique ID of the login event
+    #[sea_orm(primary_key)]
+    pub id: LoginEventId,
+    /// ID of the user that logged in
+    #[serde(skip)]
+    pub user_id: UserId,
+    /// IP address the login came from
+    pub ip: String,
+    /// Version of the client used to login
+    pub client_version: Option<String>,
+    /// Platform the client was running on
+    pub platform: Option<String>,
+    /// When the login occurred
+    pub created: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::users::Entity",
+        from = "Column::UserId",
+        to = "super::users::Column::Id"
+    )]
+    User,
+}
+
+impl Model {
+    /// Records a new login event for the provided `user`
+    pub fn create<C>(
+        db: &C,
+        user: &User,
+        ip: String,
+        client_version: Option<String>,
+        platform: Option<String>,
+    ) -> impl Future<Output = DbResult<Self>> + Send + '_
+    where
+        C: ConnectionTrait + Send,
+    {
+        ActiveModel {
+            user_id: Set(user.id),
+            ip: Set(ip),
+            client_version: Set(client_version),
+            platform: Set(platform),
+            created: Set(clock::now()),
+            ..Default::default()
+        }
+        .insert(db)
+    }
+
+    /// Finds the most recent login events for the user with the
+    /// provided `user_id`, newest first
+    pub fn recent<C>(
+        db: &C,
+        user_id: UserId,
+        count: u64,
+    ) -> impl Future<Output = DbResult<Vec<Self>>> + Send + '_
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::find()
+            .filter(Column::UserId.eq(user_id))
+            .order_by_desc(Column::Created)
+            .limit(count)
+            .all(db)
+    }
+
+    /// Checks whether the `user` has any previous logins
+    pub async fn has_any<C>(db: &C, user: &User) -> DbResult<bool>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let result: Option<LoginEventId> = Entity::find()
+            .select_only()
+            .column(Column::Id)
+            .filter(Column::UserId.eq(user.id))
+            .into_tuple()
+            .one(db)
+            .await?;
+
+        Ok(result.is_some())
+    }
+
+    /// Checks whether the `user` has previously logged in from the
+    /// provided `ip` address
+    pub async fn has_ip<C>(db: &C, user: &User, ip: &str) -> DbResult<bool>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let result: Option<LoginEventId> = Entity::find()
+            .select_only()
+            .column(Column::Id)
+            .filter(Column::UserId.eq(user.id).and(Column::Ip.eq(ip)))
+            .into_tuple()
+            .one(db)
+            .await?;
+
+        Ok(result.is_some())
+    }
+
+    /// Finds the IDs of all users that have logged in from the same IP
+    /// addresses as the `user` since `since`, includes the `user`
+    pub async fn linked_user_ids<C>(
+        db: &C,
+        user: &User,
+        since: DateTimeUtc,
+    ) -> DbResult<Vec<UserId>>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let ips: Vec<String> = Entity::find()
+            .select_only()
+            .column(Column::Ip)
+            .distinct()
+            .filter(Column::UserId.eq(user.id).and(Column::Created.gte(since)))
+            .into_tuple()
+            .all(db)
+            .await?;
+
+        // User hasn't logged in recently so isn't linked to anyone
+        if ips.is_empty() {
+            return Ok(vec![user.id]);
+        }
+
+        Entity::find()
+            .select_only()
+            .column(Column::UserId)
+            .distinct()
+            .filter(Column::Ip.is_in(ips).and(Column::Created.gte(since)))
+            .into_tuple()
+            .all(db)
+            .await
+    }
+
+    /// Deletes all login events that occurred before `before`
+    pub fn delete_before<C>(
+        db: &C,
+        before: DateTimeUtc,
+    ) -> impl Future<Output = DbResult<DeleteResult>> + Send + '_
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::delete_many()
+            .filter(Column::Created.lt(before))
+            .exec(db)
+    }
+}
+
+impl Related<super::users::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,84 @@
+//! Anonymous records of finished matches, used for producing
+//! aggregate statistics about the server
+
+use crate::{database::DbResult, utils::clock};
+use sea_orm::{entity::prelude::*, sea_query::Expr, ActiveValue::Set, QueryOrder, QuerySelect};
+use std::future::Future;
+
+/// Type alias for a [u32] representing a match record ID
+pub type MatchRecordId = u32;
+
+/// Match record database structure
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "match_records")]
+pub struct Model {
+    /// Unique ID of the match record
+    #[sea_orm(primary_key)]
+    pub id: MatchRecordId,
+    /// Map the match was played on
+    pub map: String,
+    /// Enemy type the match was played against
+    pub enemy_type: String,
+    /// Difficulty of the match
+    pub difficulty: String,
+    /// Number of players in the match
+    pub players: u32,
+    /// When the match finished
+    pub created: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl Model {
+    /// Records a finished match
+    pub fn create<C>(
+        db: &C,
+        map: String,
+        enemy_type: String,
+        difficulty: String,
+        players: u32,
+    ) -> impl Future<Output = DbResult<Self>> + Send + '_
+    where
+        C: ConnectionTrait + Send,
+    {
+        ActiveModel {
+            map: Set(map),
+            enemy_type: Set(enemy_type),
+            difficulty: Set(difficulty),
+            players: Set(players),
+            created: Set(clock::now()),
+            ..Default::default()
+        }
+        .insert(db)
+    }
+
+    /// Counts the number of matches finished after `since`
+    pub fn count_since<C>(
+        db: &C,
+        since: DateTimeUtc,
+    ) -> impl Future<Output = DbResult<u64>> + Send + '_
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::find().filter(Column::Created.gte(since)).count(db)
+    }
+
+    /// Finds the most common value of the `column` across all
+    /// the recorded matches
+    pub async fn most_played<C>(db: &C, column: Column) -> DbResult<Option<String>>
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::find()
+            .select_only()
+            .column(column)
+            .group_by(column)
+            .order_by_desc(Expr::col(Column::Id).count())
+            .into_tuple()
+            .one(db)
+            .await
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,168 @@
+//! Gameplay statistics decoded from the telemetry pin events sent by
+//! the client, statistics are stored for each user and match
+
+use super::users::UserId;
+use crate::{
+    database::DbResult,
+    services::{game::GameID, telemetry::MatchStats},
+    utils::clock,
+};
+use chrono::Duration;
+use sea_orm::{
+    entity::prelude::*, sea_query::Expr, ActiveValue::Set, DeleteResult, IntoActiveModel,
+    QueryOrder, QuerySelect,
+};
+use std::future::Future;
+
+/// Number of hours after the last update that a match is considered
+/// finished, game IDs restart when the server restarts so events for
+/// a reused game ID after this are recorded as a new match
+const MATCH_WINDOW_HOURS: i64 = 6;
+
+/// Match telemetry database structure
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "match_telemetry")]
+pub struct Model {
+    /// Unique ID of the telemetry record
+    #[sea_orm(primary_key)]
+    pub id: u32,
+    /// ID of the user the statistics are for
+    pub user_id: UserId,
+    /// ID of the game the match was played in
+    pub game_id: GameID,
+    /// Number of enemies killed
+    pub kills: u32,
+    /// Number of times the user extracted
+    pub extractions: u32,
+    /// Highest wave reached
+    pub highest_wave: u32,
+    /// When the first events for the match were received
+    pub created: DateTimeUtc,
+    /// When the last events for the match were received
+    pub updated: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::users::Entity",
+        from = "Column::UserId",
+        to = "super::users::Column::Id"
+    )]
+    User,
+}
+
+/// Totals across all the recorded matches, consists of the number of
+/// matches, kills, extractions, highest wave, and sum of the waves
+type Totals = (i64, Option<i64>, Option<i64>, Option<i64>, Option<i64>);
+
+impl Model {
+    /// Adds the `stats` to the match the `user_id` is playing in `game_id`,
+    /// creates a new record if there isn't a recent record for the match
+    pub async fn record<C>(
+        db: &C,
+        user_id: UserId,
+        game_id: GameID,
+        stats: MatchStats,
+    ) -> DbResult<()>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let now = clock::now();
+
+        let existing = Entity::find()
+            .filter(
+                Column::UserId
+                    .eq(user_id)
+                    .and(Column::GameId.eq(game_id))
+                    .and(Column::Updated.gte(now - Duration::hours(MATCH_WINDOW_HOURS))),
+            )
+            .order_by_desc(Column::Updated)
+            .one(db)
+            .await?;
+
+        match existing {
+            Some(existing) => {
+                let kills = existing.kills.saturating_add(stats.kills);
+                let extractions = existing.extractions.saturating_add(stats.extractions);
+                let highest_wave = existing.highest_wave.max(stats.highest_wave);
+
+                let mut model = existing.into_active_model();
+                model.kills = Set(kills);
+                model.extractions = Set(extractions);
+                model.highest_wave = Set(highest_wave);
+                model.updated = Set(now);
+                model.update(db).await?;
+            }
+            None => {
+                ActiveModel {
+                    user_id: Set(user_id),
+                    game_id: Set(game_id),
+                    kills: Set(stats.kills),
+                    extractions: Set(stats.extractions),
+                    highest_wave: Set(stats.highest_wave),
+                    created: Set(now),
+                    updated: Set(now),
+                    ..Default::default()
+                }
+                .insert(db)
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sums the statistics across all the recorded matches, provides the
+    /// number of matches, the totals of the statistics (Highest wave being
+    /// the highest of any match), and the sum of the waves reached
+    pub async fn totals<C>(db: &C) -> DbResult<(u64, MatchStats, u64)>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let totals: Option<Totals> = Entity::find()
+            .select_only()
+            .column_as(Expr::col(Column::Id).count(), "matches")
+            .column_as(Expr::col(Column::Kills).sum(), "kills")
+            .column_as(Expr::col(Column::Extractions).sum(), "extractions")
+            .column_as(Expr::col(Column::HighestWave).max(), "highest_wave")
+            .column_as(Expr::col(Column::HighestWave).sum(), "waves")
+            .into_tuple()
+            .one(db)
+            .await?;
+
+        let (matches, kills, extractions, highest_wave, waves) = totals.unwrap_or_default();
+        let value = |value: Option<i64>| value.unwrap_or_default() as u32;
+
+        Ok((
+            matches as u64,
+            MatchStats {
+                kills: value(kills),
+                extractions: value(extractions),
+                highest_wave: value(highest_wave),
+            },
+            waves.unwrap_or_default() as u64,
+        ))
+    }
+
+    /// Deletes all the match records that were last updated before `before`
+    pub fn delete_before<C>(
+        db: &C,
+        before: DateTimeUtc,
+    ) -> impl Future<Output = DbResult<DeleteResult>> + Send + '_
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::delete_many()
+            .filter(Column::Updated.lt(before))
+            .exec(db)
+    }
+}
+
+impl Related<super::users::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
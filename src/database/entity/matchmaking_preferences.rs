@@ -0,0 +1,75 @@
+//! Matchmaking preferences set by users, used by the matchmaking queue
+//! to only place users into games hosted close to them
+
+use super::{users::UserId, User};
+use crate::database::DbResult;
+use sea_orm::{entity::prelude::*, sea_query::OnConflict, ActiveValue::Set, InsertResult};
+use std::future::Future;
+
+/// Matchmaking preferences database structure
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "matchmaking_preferences")]
+pub struct Model {
+    /// ID of the user the preferences are for
+    #[sea_orm(primary_key)]
+    pub user_id: UserId,
+    /// Maximum latency in milliseconds to the host of a game
+    pub max_latency: Option<u32>,
+    /// Ping site the user would prefer the game host to be closest to
+    pub preferred_region: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::users::Entity",
+        from = "Column::UserId",
+        to = "super::users::Column::Id"
+    )]
+    User,
+}
+
+impl Model {
+    /// Gets the matchmaking preferences for the `user`
+    pub fn get<'db, C>(
+        db: &'db C,
+        user: &User,
+    ) -> impl Future<Output = DbResult<Option<Self>>> + Send + 'db
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::find_by_id(user.id).one(db)
+    }
+
+    /// Sets the matchmaking preferences for the `user` replacing
+    /// any existing preferences
+    pub fn set<'db, C>(
+        db: &'db C,
+        user: &User,
+        max_latency: Option<u32>,
+        preferred_region: Option<String>,
+    ) -> impl Future<Output = DbResult<InsertResult<ActiveModel>>> + 'db
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::insert(ActiveModel {
+            user_id: Set(user.id),
+            max_latency: Set(max_latency),
+            preferred_region: Set(preferred_region),
+        })
+        .on_conflict(
+            OnConflict::column(Column::UserId)
+                .update_columns([Column::MaxLatency, Column::PreferredRegion])
+                .to_owned(),
+        )
+        .exec(db)
+    }
+}
+
+impl Related<super::users::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,77 @@
+//! Records of players being matched into games by the matchmaking
+//! queue, used for tuning the matchmaking parameters
+
+use crate::{database::DbResult, utils::clock};
+use sea_orm::{entity::prelude::*, ActiveValue::Set, DeleteResult};
+use std::future::Future;
+
+/// Type alias for a [u32] representing a matchmaking record ID
+pub type MatchmakingRecordId = u32;
+
+/// Matchmaking record database structure
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "matchmaking_records")]
+pub struct Model {
+    /// Unique ID of the matchmaking record
+    #[sea_orm(primary_key)]
+    pub id: MatchmakingRecordId,
+    /// Milliseconds the player spent in the queue
+    pub queue_time_ms: u32,
+    /// Criteria relaxation stage the player was matched at
+    pub relaxation_stage: u32,
+    /// Number of players in the game after the player joined
+    pub game_size: u32,
+    /// When the player was matched
+    pub created: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl Model {
+    /// Records a player being matched into a game
+    pub fn create<C>(
+        db: &C,
+        queue_time_ms: u32,
+        relaxation_stage: u32,
+        game_size: u32,
+    ) -> impl Future<Output = DbResult<Self>> + Send + '_
+    where
+        C: ConnectionTrait + Send,
+    {
+        ActiveModel {
+            queue_time_ms: Set(queue_time_ms),
+            relaxation_stage: Set(relaxation_stage),
+            game_size: Set(game_size),
+            created: Set(clock::now()),
+            ..Default::default()
+        }
+        .insert(db)
+    }
+
+    /// Finds all the records created after `since`
+    pub fn since<C>(
+        db: &C,
+        since: DateTimeUtc,
+    ) -> impl Future<Output = DbResult<Vec<Self>>> + Send + '_
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::find().filter(Column::Created.gte(since)).all(db)
+    }
+
+    /// Deletes all the records created before `before`
+    pub fn delete_before<C>(
+        db: &C,
+        before: DateTimeUtc,
+    ) -> impl Future<Output = DbResult<DeleteResult>> + Send + '_
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::delete_many()
+            .filter(Column::Created.lt(before))
+            .exec(db)
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -6,26 +6,80 @@ use sea_orm::{
 };
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
+pub mod badge_rewards;
 pub mod challenge_progress;
+pub mod character_customization_history;
 pub mod characters;
 pub mod currency;
+pub mod currency_ledger;
+pub mod friends;
+pub mod game_records;
+pub mod interrupted_missions;
 pub mod inventory_items;
+pub mod leaderboard_stats;
+pub mod login_events;
+pub mod match_records;
+pub mod match_telemetry;
+pub mod matchmaking_preferences;
+pub mod matchmaking_records;
+pub mod notifications;
+pub mod pack_openings;
+pub mod permission_audit_log;
+pub mod player_titles;
+pub mod reports;
+pub mod retired_strike_teams;
 pub mod seen_articles;
 pub mod shared_data;
+pub mod store_purchases;
+pub mod strike_team_deployment_queue;
+pub mod strike_team_flair;
 pub mod strike_team_mission;
+pub mod strike_team_mission_history;
 pub mod strike_team_mission_progress;
 pub mod strike_teams;
+pub mod user_bans;
+pub mod user_identities;
+pub mod user_permissions;
 pub mod users;
+pub mod welcome_kit_grants;
+pub mod xp_boosts;
 
+pub type BadgeReward = badge_rewards::Model;
 pub type Character = characters::Model;
+pub type CharacterCustomizationHistory = character_customization_history::Model;
 pub type ChallengeProgress = challenge_progress::Model;
 pub type Currency = currency::Model;
+pub type CurrencyLedgerEntry = currency_ledger::Model;
+pub type Friend = friends::Model;
+pub type GameRecord = game_records::Model;
 pub type SharedData = shared_data::Model;
+pub type StorePurchase = store_purchases::Model;
 pub type InventoryItem = inventory_items::Model;
+pub type InterruptedMission = interrupted_missions::Model;
+pub type LeaderboardStats = leaderboard_stats::Model;
+pub type LoginEvent = login_events::Model;
+pub type MatchRecord = match_records::Model;
+pub type MatchTelemetry = match_telemetry::Model;
+pub type MatchmakingPreferences = matchmaking_preferences::Model;
+pub type MatchmakingRecord = matchmaking_records::Model;
+pub type Notification = notifications::Model;
+pub type PackOpening = pack_openings::Model;
+pub type PermissionAuditEntry = permission_audit_log::Model;
+pub type PlayerTitle = player_titles::Model;
+pub type Report = reports::Model;
+pub type RetiredStrikeTeam = retired_strike_teams::Model;
 pub type User = users::Model;
+pub type UserBan = user_bans::Model;
+pub type UserIdentity = user_identities::Model;
+pub type UserPermission = user_permissions::Model;
 pub type StrikeTeam = strike_teams::Model;
+pub type StrikeTeamDeployment = strike_team_deployment_queue::Model;
+pub type UnlockedStrikeTeamFlair = strike_team_flair::Model;
 pub type StrikeTeamMission = strike_team_mission::Model;
+pub type StrikeTeamMissionHistory = strike_team_mission_history::Model;
 pub type StrikeTeamMissionProgress = strike_team_mission_progress::Model;
+pub type WelcomeKitGrant = welcome_kit_grants::Model;
+pub type XpBoost = xp_boosts::Model;
 
 /// Wrapper around a generic [serde_json::Map]
 pub type SeaGenericMap = SeaJson<serde_json::Map<String, serde_json::Value>>;
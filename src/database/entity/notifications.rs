@@ -0,0 +1,170 @@
+//! Notifications stored for users so that events that happen while
+//! they are offline (strike team results, gifts, admin messages and
+//! moderation notices) are delivered the next time they log in
+
+use super::users::UserId;
+use crate::{database::DbResult, utils::clock};
+use sea_orm::{entity::prelude::*, sea_query::Expr, ActiveValue::Set, QueryOrder, QuerySelect};
+use serde::Serialize;
+
+/// Type alias for a [u32] representing a notification ID
+pub type NotificationId = u32;
+
+/// Notification database structure
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "notifications")]
+#[serde(rename_all = "camelCase")]
+pub struct Model {
+    /// Unique ID of the notification
+    #[sea_orm(primary_key)]
+    pub id: NotificationId,
+    /// ID of the user the notification is for
+    #[serde(skip)]
+    pub user_id: UserId,
+    /// The kind of notification
+    pub kind: NotificationKind,
+    /// The notification message text
+    pub message: String,
+    /// Whether the user has read the notification
+    pub read: bool,
+    /// Whether the notification has been sent to the client
+    #[serde(skip)]
+    pub delivered: bool,
+    /// When the notification was created
+    pub created: DateTimeUtc,
+}
+
+/// Kinds of notifications
+#[derive(Debug, EnumIter, DeriveActiveEnum, Clone, Copy, PartialEq, Eq, Serialize)]
+#[sea_orm(rs_type = "u8", db_type = "Integer")]
+#[serde(rename_all = "camelCase")]
+#[repr(u8)]
+pub enum NotificationKind {
+    /// A strike team has returned from a mission
+    StrikeTeamResult = 0,
+    /// Items or currency were gifted to the user
+    GiftReceived = 1,
+    /// Message sent by an administrator
+    AdminMessage = 2,
+    /// Notice about moderation actions on the account
+    ModerationNotice = 3,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::users::Entity",
+        from = "Column::UserId",
+        to = "super::users::Column::Id"
+    )]
+    User,
+}
+
+impl Model {
+    /// Maximum number of notifications listed
+    pub const MAX_LISTED: u64 = 100;
+
+    /// Creates a new unread notification for the user with the provided `user_id`
+    pub async fn create<C>(
+        db: &C,
+        user_id: UserId,
+        kind: NotificationKind,
+        message: String,
+    ) -> DbResult<Self>
+    where
+        C: ConnectionTrait + Send,
+    {
+        ActiveModel {
+            user_id: Set(user_id),
+            kind: Set(kind),
+            message: Set(message),
+            read: Set(false),
+            delivered: Set(false),
+            created: Set(clock::now()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+    }
+
+    /// Gets the most recent notifications of the user with the provided
+    /// `user_id`, only unread notifications when `unread_only` is set
+    pub async fn recent<C>(db: &C, user_id: UserId, unread_only: bool) -> DbResult<Vec<Self>>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let mut query = Entity::find().filter(Column::UserId.eq(user_id));
+        if unread_only {
+            query = query.filter(Column::Read.eq(false));
+        }
+
+        query
+            .order_by_desc(Column::Id)
+            .limit(Self::MAX_LISTED)
+            .all(db)
+            .await
+    }
+
+    /// Gets the notifications of the user with the provided `user_id`
+    /// that haven't been sent to the client in the order they were created
+    pub async fn undelivered<C>(db: &C, user_id: UserId) -> DbResult<Vec<Self>>
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::find()
+            .filter(Column::UserId.eq(user_id))
+            .filter(Column::Delivered.eq(false))
+            .order_by_asc(Column::Id)
+            .all(db)
+            .await
+    }
+
+    /// Marks the notifications with the provided `ids` as sent to the client
+    pub async fn set_delivered<C>(db: &C, ids: Vec<NotificationId>) -> DbResult<()>
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::update_many()
+            .col_expr(Column::Delivered, Expr::value(true))
+            .filter(Column::Id.is_in(ids))
+            .exec(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Marks the notification with the provided `id` belonging to the user
+    /// with the provided `user_id` as read, returns whether the notification
+    /// was found
+    pub async fn set_read<C>(db: &C, user_id: UserId, id: NotificationId) -> DbResult<bool>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let result = Entity::update_many()
+            .col_expr(Column::Read, Expr::value(true))
+            .filter(Column::UserId.eq(user_id))
+            .filter(Column::Id.eq(id))
+            .exec(db)
+            .await?;
+
+        Ok(result.rows_affected > 0)
+    }
+
+    /// Marks all the notifications of the user with the provided
+    /// `user_id` as read
+    pub async fn set_all_read<C>(db: &C, user_id: UserId) -> DbResult<()>
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::update_many()
+            .col_expr(Column::Read, Expr::value(true))
+            .filter(Column::UserId.eq(user_id))
+            .filter(Column::Read.eq(false))
+            .exec(db)
+            .await?;
+
+        Ok(())
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,169 @@
+//! History of the packs opened by users, openings of packs covered by a
+//! drop-rate experiment record which group of the experiment the user was
+//! in so that the outcomes of each group can be compared
+
+use super::{users::UserId, User};
+use crate::{
+    database::DbResult, definitions::items::ItemName, services::pack_experiments::RarityCounts,
+    utils::clock,
+};
+use sea_orm::{entity::prelude::*, ActiveValue::Set, QueryOrder, QuerySelect};
+use std::future::Future;
+
+/// Pack opening database structure
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "pack_openings")]
+pub struct Model {
+    /// Unique ID of the opening
+    #[sea_orm(primary_key)]
+    pub id: u32,
+    /// ID of the user that opened the pack
+    pub user_id: UserId,
+    /// Name of the opened pack
+    pub pack_name: ItemName,
+    /// Name of the experiment the pack was covered by
+    pub experiment: Option<String>,
+    /// Whether the user received the experiment weights rather
+    /// than being part of the control group
+    pub treatment: bool,
+    /// Number of items generated by the pack
+    pub items: u32,
+    /// Number of common items generated
+    pub common: u32,
+    /// Number of uncommon items generated
+    pub uncommon: u32,
+    /// Number of rare items generated
+    pub rare: u32,
+    /// Number of ultra rare items generated
+    pub ultra_rare: u32,
+    /// When the pack was opened
+    pub created: DateTimeUtc,
+    /// Seed of the random number generator the items were generated
+    /// with, allows the opening to be replayed
+    pub seed: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::users::Entity",
+        from = "Column::UserId",
+        to = "super::users::Column::Id"
+    )]
+    User,
+}
+
+/// Totals of the openings for one group of an experiment
+type GroupTotals = (
+    bool,
+    i64,
+    Option<i64>,
+    Option<i64>,
+    Option<i64>,
+    Option<i64>,
+    Option<i64>,
+);
+
+impl Model {
+    /// Records the `user` opening the `pack_name` pack with the items
+    /// generated from the `seed`
+    pub fn create<'db, C>(
+        db: &'db C,
+        user: &User,
+        pack_name: ItemName,
+        experiment: Option<(String, bool)>,
+        counts: RarityCounts,
+        seed: u64,
+    ) -> impl Future<Output = DbResult<Self>> + Send + 'db
+    where
+        C: ConnectionTrait + Send,
+    {
+        let (experiment, treatment) = match experiment {
+            Some((experiment, treatment)) => (Some(experiment), treatment),
+            None => (None, false),
+        };
+
+        ActiveModel {
+            user_id: Set(user.id),
+            pack_name: Set(pack_name),
+            experiment: Set(experiment),
+            treatment: Set(treatment),
+            items: Set(counts.items),
+            common: Set(counts.common),
+            uncommon: Set(counts.uncommon),
+            rare: Set(counts.rare),
+            ultra_rare: Set(counts.ultra_rare),
+            created: Set(clock::now()),
+            seed: Set(seed as i64),
+            ..Default::default()
+        }
+        .insert(db)
+    }
+
+    /// Gets the most recent `count` pack openings of the `user`, newest first
+    pub async fn recent<C>(db: &C, user: &User, count: u64) -> DbResult<Vec<Self>>
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::find()
+            .filter(Column::UserId.eq(user.id))
+            .order_by_desc(Column::Id)
+            .limit(count)
+            .all(db)
+            .await
+    }
+
+    /// Sums the openings of the `experiment` for each of its groups, provides
+    /// the group (treatment), number of openings, and the item totals
+    pub async fn experiment_totals<C>(
+        db: &C,
+        experiment: &str,
+    ) -> DbResult<Vec<(bool, u32, RarityCounts)>>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let totals: Vec<GroupTotals> = Entity::find()
+            .select_only()
+            .column(Column::Treatment)
+            .column_as(Expr::col(Column::Id).count(), "openings")
+            .column_as(Expr::col(Column::Items).sum(), "items")
+            .column_as(Expr::col(Column::Common).sum(), "common")
+            .column_as(Expr::col(Column::Uncommon).sum(), "uncommon")
+            .column_as(Expr::col(Column::Rare).sum(), "rare")
+            .column_as(Expr::col(Column::UltraRare).sum(), "ultra_rare")
+            .filter(Column::Experiment.eq(experiment))
+            .group_by(Column::Treatment)
+            .into_tuple()
+            .all(db)
+            .await?;
+
+        let sum = |value: Option<i64>| value.unwrap_or_default() as u32;
+
+        Ok(totals
+            .into_iter()
+            .map(
+                |(treatment, openings, items, common, uncommon, rare, ultra_rare)| {
+                    (
+                        treatment,
+                        openings as u32,
+                        RarityCounts {
+                            items: sum(items),
+                            common: sum(common),
+                            uncommon: sum(uncommon),
+                            rare: sum(rare),
+                            ultra_rare: sum(ultra_rare),
+                        },
+                    )
+                },
+            )
+            .collect())
+    }
+}
+
+impl Related<super::users::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
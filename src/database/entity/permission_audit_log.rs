@@ -0,0 +1,74 @@
+//! Audit log of the permissions granted to and revoked from
+//! administrators, records which administrator made each change
+
+use super::{user_permissions::AdminPermission, users::UserId};
+use crate::{database::DbResult, utils::clock};
+use sea_orm::{entity::prelude::*, ActiveValue::Set, QueryOrder, QuerySelect};
+use serde::Serialize;
+use std::future::Future;
+
+/// Permission audit log entry database structure
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "permission_audit_log")]
+#[serde(rename_all = "camelCase")]
+pub struct Model {
+    /// Unique ID of the entry
+    #[sea_orm(primary_key)]
+    pub id: u32,
+    /// ID of the administrator that made the change
+    pub actor_id: UserId,
+    /// ID of the user whose permissions were changed
+    pub target_id: UserId,
+    /// The changed permission
+    pub permission: AdminPermission,
+    /// Whether the permission was granted or revoked
+    pub granted: bool,
+    /// When the change was made
+    pub created: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl Model {
+    /// Records a change to the `permission` of the `target_id` user
+    /// made by the `actor_id` administrator
+    pub fn create<C>(
+        db: &C,
+        actor_id: UserId,
+        target_id: UserId,
+        permission: AdminPermission,
+        granted: bool,
+    ) -> impl Future<Output = DbResult<Self>> + Send + '_
+    where
+        C: ConnectionTrait + Send,
+    {
+        ActiveModel {
+            actor_id: Set(actor_id),
+            target_id: Set(target_id),
+            permission: Set(permission),
+            granted: Set(granted),
+            created: Set(clock::now()),
+            ..Default::default()
+        }
+        .insert(db)
+    }
+
+    /// Gets a page of the audit log ordered by newest first
+    pub fn page<C>(
+        db: &C,
+        offset: u64,
+        count: u64,
+    ) -> impl Future<Output = DbResult<Vec<Self>>> + Send + '_
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::find()
+            .order_by_desc(Column::Id)
+            .offset(offset)
+            .limit(count)
+            .all(db)
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,91 @@
+//! Player titles earned by users through challenges and career milestones
+
+use super::{users::UserId, User};
+use crate::{database::DbResult, definitions::titles::PlayerTitleName, utils::clock};
+use sea_orm::{entity::prelude::*, sea_query::OnConflict, ActiveValue::Set, QueryOrder};
+use serde::Serialize;
+
+/// Earned player title database structure
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "player_titles")]
+#[serde(rename_all = "camelCase")]
+pub struct Model {
+    /// Unique ID of the earned title
+    #[sea_orm(primary_key)]
+    #[serde(skip)]
+    pub id: u32,
+    /// ID of the user that earned the title
+    #[serde(skip)]
+    pub user_id: UserId,
+    /// Name of the earned title
+    pub name: PlayerTitleName,
+    /// When the title was earned
+    pub unlocked: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::users::Entity",
+        from = "Column::UserId",
+        to = "super::users::Column::Id"
+    )]
+    User,
+}
+
+impl Model {
+    /// Unlocks the title with the provided `name` for the `user`, unlocking
+    /// a title that is already unlocked does nothing
+    pub async fn unlock<C>(db: &C, user: &User, name: PlayerTitleName) -> DbResult<()>
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::insert(ActiveModel {
+            user_id: Set(user.id),
+            name: Set(name),
+            unlocked: Set(clock::now()),
+            ..Default::default()
+        })
+        .on_conflict(
+            OnConflict::columns([Column::UserId, Column::Name])
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Gets all the titles unlocked by the `user`
+    pub async fn all<C>(db: &C, user: &User) -> DbResult<Vec<Self>>
+    where
+        C: ConnectionTrait + Send,
+    {
+        user.find_related(Entity)
+            .order_by_asc(Column::Id)
+            .all(db)
+            .await
+    }
+
+    /// Checks whether the `user` has unlocked the title with the provided `name`
+    pub async fn is_unlocked<C>(db: &C, user: &User, name: &str) -> DbResult<bool>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let title = user
+            .find_related(Entity)
+            .filter(Column::Name.eq(name))
+            .one(db)
+            .await?;
+        Ok(title.is_some())
+    }
+}
+
+impl Related<super::users::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
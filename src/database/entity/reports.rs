@@ -0,0 +1,162 @@
+//! Reports submitted by players about bugs or abuse, stored along
+//! with context about the player at the time of reporting
+
+use super::{users::UserId, SeaJson, User};
+use crate::{
+    database::DbResult, definitions::items::ItemName, services::game::GameID, utils::clock,
+};
+use sea_orm::{entity::prelude::*, ActiveValue::Set, QueryOrder, QuerySelect};
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+use std::{collections::BTreeMap, future::Future};
+
+/// Type alias for a [u32] representing a report ID
+pub type ReportId = u32;
+
+/// Report database structure
+#[skip_serializing_none]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "reports")]
+#[serde(rename_all = "camelCase")]
+pub struct Model {
+    /// Unique ID of the report
+    #[sea_orm(primary_key)]
+    pub id: ReportId,
+    /// ID of the user that submitted the report
+    pub user_id: UserId,
+    /// The category of the report
+    pub category: ReportCategory,
+    /// Description of the problem provided by the user
+    pub description: String,
+    /// ID of the game the report relates to
+    pub game_id: Option<GameID>,
+    /// Context captured when the report was submitted
+    pub context: SeaJson<ReportContext>,
+    /// When the report was submitted
+    pub created: DateTimeUtc,
+}
+
+/// Categories of reports
+#[derive(Debug, EnumIter, DeriveActiveEnum, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[sea_orm(rs_type = "u8", db_type = "Integer")]
+#[serde(rename_all = "camelCase")]
+#[repr(u8)]
+pub enum ReportCategory {
+    /// Bug or problem with the server
+    Bug = 0,
+    /// Abusive behavior from another player
+    Abuse = 1,
+    /// Another player is cheating
+    Cheating = 2,
+    /// Anything else
+    Other = 3,
+}
+
+/// Context about the user captured at the time of reporting
+#[skip_serializing_none]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportContext {
+    /// Details about the users game session if they were connected
+    pub session: Option<ReportSessionContext>,
+    /// Details about the game the report relates to
+    pub game: Option<ReportGameContext>,
+    /// Items most recently granted to the user, gives some
+    /// insight into what the user was recently doing
+    pub recent_items: Vec<ReportRecentItem>,
+}
+
+/// Details about the users game session
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportSessionContext {
+    /// Unique ID of the session
+    pub session_id: Uuid,
+    /// ID of the game the session was in
+    pub game_id: Option<GameID>,
+}
+
+/// Details about the game at the time of reporting
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportGameContext {
+    /// The game state
+    pub state: u8,
+    /// The game attributes
+    pub attributes: BTreeMap<String, String>,
+    /// Names of the players within the game
+    pub players: Vec<String>,
+}
+
+/// Item recently granted to the user
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportRecentItem {
+    /// The item definition name
+    pub definition_name: ItemName,
+    /// The stack size of the item
+    pub stack_size: u32,
+    /// When the item was last granted
+    pub last_grant: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::users::Entity",
+        from = "Column::UserId",
+        to = "super::users::Column::Id"
+    )]
+    User,
+}
+
+impl Model {
+    /// Creates a new report for the provided `user`
+    pub fn create<'db, C>(
+        db: &'db C,
+        user: &User,
+        category: ReportCategory,
+        description: String,
+        game_id: Option<GameID>,
+        context: ReportContext,
+    ) -> impl Future<Output = DbResult<Self>> + Send + 'db
+    where
+        C: ConnectionTrait + Send,
+    {
+        ActiveModel {
+            user_id: Set(user.id),
+            category: Set(category),
+            description: Set(description),
+            game_id: Set(game_id),
+            context: Set(SeaJson(context)),
+            created: Set(clock::now()),
+            ..Default::default()
+        }
+        .insert(db)
+    }
+
+    /// Finds a page of reports, newest reports first
+    pub fn page<C>(
+        db: &C,
+        offset: u64,
+        count: u64,
+    ) -> impl Future<Output = DbResult<Vec<Self>>> + Send + '_
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::find()
+            .order_by_desc(Column::Created)
+            .offset(offset)
+            .limit(count)
+            .all(db)
+    }
+}
+
+impl Related<super::users::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,88 @@
+//! History of the strike teams retired by users along with the
+//! rewards that were granted for retiring them
+
+use super::{users::UserId, SeaJson, StrikeTeam, User};
+use crate::{
+    database::DbResult,
+    definitions::{
+        challenges::CurrencyReward,
+        strike_teams::{StrikeTeamIcon, StrikeTeamName},
+    },
+    utils::clock,
+};
+use sea_orm::{entity::prelude::*, ActiveValue::Set, QueryOrder};
+use serde::Serialize;
+
+/// Retired strike team database structure
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "retired_strike_teams")]
+#[serde(rename_all = "camelCase")]
+pub struct Model {
+    /// Unique ID of the retired team
+    #[sea_orm(primary_key)]
+    pub id: u32,
+    /// ID of the user that owned the team
+    #[serde(skip)]
+    pub user_id: UserId,
+    /// Name of the team
+    pub name: StrikeTeamName,
+    /// Icon of the team
+    pub icon: StrikeTeamIcon,
+    /// Level the team had reached
+    pub level: u32,
+    /// Name of the specialization the team had chosen
+    pub specialization: Option<String>,
+    /// Currencies granted for retiring the team
+    pub rewards: SeaJson<Vec<CurrencyReward>>,
+    /// When the team was retired
+    pub retired: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::users::Entity",
+        from = "Column::UserId",
+        to = "super::users::Column::Id"
+    )]
+    User,
+}
+
+impl Model {
+    /// Records the retirement of the `team` that granted the `rewards`
+    pub async fn create<C>(
+        db: &C,
+        team: &StrikeTeam,
+        rewards: Vec<CurrencyReward>,
+    ) -> DbResult<Self>
+    where
+        C: ConnectionTrait + Send,
+    {
+        ActiveModel {
+            user_id: Set(team.user_id),
+            name: Set(team.name.clone()),
+            icon: Set(team.icon.clone()),
+            level: Set(team.level),
+            specialization: Set(team.specialization.clone()),
+            rewards: Set(SeaJson(rewards)),
+            retired: Set(clock::now()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+    }
+
+    /// Gets all the teams retired by the `user`, most recent first
+    pub async fn all<C>(db: &C, user: &User) -> DbResult<Vec<Self>>
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::find()
+            .filter(Column::UserId.eq(user.id))
+            .order_by_desc(Column::Id)
+            .all(db)
+            .await
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,4 +1,6 @@
-use sea_orm::entity::prelude::*;
+use crate::database::DbResult;
+use sea_orm::{entity::prelude::*, DeleteResult};
+use std::future::Future;
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
 #[sea_orm(table_name = "seen_articles")]
@@ -19,6 +21,23 @@ pub enum Relation {
     User,
 }
 
+impl Model {
+    /// Deletes all seen articles that aren't in the provided
+    /// list of `articles`
+    pub fn delete_not_in<C, I>(
+        db: &C,
+        articles: I,
+    ) -> impl Future<Output = DbResult<DeleteResult>> + Send + '_
+    where
+        C: ConnectionTrait + Send,
+        I: IntoIterator<Item = Uuid>,
+    {
+        Entity::delete_many()
+            .filter(Column::ArticleId.is_not_in(articles))
+            .exec(db)
+    }
+}
+
 impl Related<super::users::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::User.def()
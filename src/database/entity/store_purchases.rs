@@ -0,0 +1,161 @@
+//! Records of store article purchases, purchases can be refunded
+//! within the configured refund window
+
+use super::{currency::CurrencyType, inventory_items::ItemId, users::UserId, User};
+use crate::{database::DbResult, definitions::store_catalogs::StoreArticleName, utils::clock};
+use sea_orm::{entity::prelude::*, ActiveValue::Set, IntoActiveModel, QueryOrder};
+use serde::Serialize;
+use serde_with::{serde_as, skip_serializing_none};
+use std::future::Future;
+
+/// Type alias for a [u32] representing a store purchase ID
+pub type StorePurchaseId = u32;
+
+/// Store purchase database structure
+#[serde_as]
+#[skip_serializing_none]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "store_purchases")]
+#[serde(rename_all = "camelCase")]
+pub struct Model {
+    /// Unique ID of the purchase
+    #[sea_orm(primary_key)]
+    pub id: StorePurchaseId,
+    /// ID of the user that made the purchase
+    #[serde(skip)]
+    pub user_id: UserId,
+    /// Name of the purchased article
+    pub article_name: StoreArticleName,
+    /// Currency the purchase was paid with
+    pub currency: CurrencyType,
+    /// Amount of currency paid
+    pub price: u32,
+    /// ID of the item stack the purchased item was granted to
+    #[serde_as(as = "Option<serde_with::DisplayFromStr>")]
+    pub item_id: Option<ItemId>,
+    /// Amount of the item that was granted
+    pub stack_size: u32,
+    /// When the purchase was made
+    pub created: DateTimeUtc,
+    /// When the purchase was refunded
+    pub refunded: Option<DateTimeUtc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::users::Entity",
+        from = "Column::UserId",
+        to = "super::users::Column::Id"
+    )]
+    User,
+    #[sea_orm(
+        belongs_to = "super::inventory_items::Entity",
+        from = "Column::ItemId",
+        to = "super::inventory_items::Column::Id"
+    )]
+    Item,
+}
+
+impl Model {
+    /// Records a purchase of the `article_name` by the `user`
+    pub fn create<'db, C>(
+        db: &'db C,
+        user: &User,
+        article_name: StoreArticleName,
+        currency: CurrencyType,
+        price: u32,
+        item_id: ItemId,
+        stack_size: u32,
+    ) -> impl Future<Output = DbResult<Self>> + Send + 'db
+    where
+        C: ConnectionTrait + Send,
+    {
+        ActiveModel {
+            user_id: Set(user.id),
+            article_name: Set(article_name),
+            currency: Set(currency),
+            price: Set(price),
+            item_id: Set(Some(item_id)),
+            stack_size: Set(stack_size),
+            created: Set(clock::now()),
+            refunded: Set(None),
+            ..Default::default()
+        }
+        .insert(db)
+    }
+
+    /// Finds a purchase made by the `user` with a matching `id`
+    pub fn get<'db, C>(
+        db: &'db C,
+        user: &User,
+        id: StorePurchaseId,
+    ) -> impl Future<Output = DbResult<Option<Self>>> + Send + 'db
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::find()
+            .filter(Column::UserId.eq(user.id).and(Column::Id.eq(id)))
+            .one(db)
+    }
+
+    /// Finds all the purchases made by the `user` after `since`,
+    /// most recent purchases first
+    pub fn all_since<'db, C>(
+        db: &'db C,
+        user: &User,
+        since: DateTimeUtc,
+    ) -> impl Future<Output = DbResult<Vec<Self>>> + Send + 'db
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::find()
+            .filter(Column::UserId.eq(user.id).and(Column::Created.gte(since)))
+            .order_by_desc(Column::Created)
+            .all(db)
+    }
+
+    /// Finds all the purchases made by the `user` of any of the
+    /// `article_names` that haven't been refunded
+    pub fn all_by_articles<'db, C>(
+        db: &'db C,
+        user: &User,
+        article_names: Vec<StoreArticleName>,
+    ) -> impl Future<Output = DbResult<Vec<Self>>> + Send + 'db
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::find()
+            .filter(
+                Column::UserId
+                    .eq(user.id)
+                    .and(Column::ArticleName.is_in(article_names))
+                    .and(Column::Refunded.is_null()),
+            )
+            .all(db)
+    }
+
+    /// Marks the purchase as refunded
+    pub fn set_refunded<C>(self, db: &C) -> impl Future<Output = DbResult<Self>> + Send + '_
+    where
+        C: ConnectionTrait + Send,
+    {
+        let mut model = self.into_active_model();
+        model.refunded = Set(Some(clock::now()));
+        model.update(db)
+    }
+}
+
+impl Related<super::users::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl Related<super::inventory_items::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Item.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,123 @@
+//! Queue of strike team missions waiting for a strike team to become
+//! available, missions are deployed in the order they were queued
+
+use super::{strike_team_mission::StrikeTeamMissionId, users::UserId, User};
+use crate::{database::DbResult, utils::clock};
+use sea_orm::{entity::prelude::*, ActiveValue::Set, QueryOrder};
+use serde::Serialize;
+use serde_with::serde_as;
+use std::future::Future;
+
+/// Type alias for a [u32] representing a queue entry ID
+pub type DeploymentQueueId = u32;
+
+/// Deployment queue database structure
+#[serde_as]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "strike_team_deployment_queue")]
+#[serde(rename_all = "camelCase")]
+pub struct Model {
+    /// Unique ID of the queue entry
+    #[sea_orm(primary_key)]
+    pub id: DeploymentQueueId,
+    /// ID of the user that queued the mission
+    #[serde(skip)]
+    pub user_id: UserId,
+    /// ID of the queued mission
+    #[serde_as(as = "serde_with::DisplayFromStr")]
+    pub mission_id: StrikeTeamMissionId,
+    /// When the mission was queued
+    pub created: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::users::Entity",
+        from = "Column::UserId",
+        to = "super::users::Column::Id"
+    )]
+    User,
+    #[sea_orm(
+        belongs_to = "super::strike_team_mission::Entity",
+        from = "Column::MissionId",
+        to = "super::strike_team_mission::Column::Id"
+    )]
+    Mission,
+}
+
+impl Model {
+    /// Queues the mission with the `mission_id` for the `user`
+    pub fn create<C>(
+        db: &C,
+        user: &User,
+        mission_id: StrikeTeamMissionId,
+    ) -> impl Future<Output = DbResult<Self>> + Send + '_
+    where
+        C: ConnectionTrait + Send,
+    {
+        ActiveModel {
+            user_id: Set(user.id),
+            mission_id: Set(mission_id),
+            created: Set(clock::now()),
+            ..Default::default()
+        }
+        .insert(db)
+    }
+
+    /// Finds all the missions queued by the `user` in the
+    /// order they were queued
+    pub fn all<'db, C>(db: &'db C, user: &User) -> impl Future<Output = DbResult<Vec<Self>>> + 'db
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::find()
+            .filter(Column::UserId.eq(user.id))
+            .order_by_asc(Column::Id)
+            .all(db)
+    }
+
+    /// Checks whether the `user` has queued the mission with the `mission_id`
+    pub async fn is_queued<C>(
+        db: &C,
+        user: &User,
+        mission_id: StrikeTeamMissionId,
+    ) -> DbResult<bool>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let entry = Entity::find()
+            .filter(
+                Column::UserId
+                    .eq(user.id)
+                    .and(Column::MissionId.eq(mission_id)),
+            )
+            .one(db)
+            .await?;
+
+        Ok(entry.is_some())
+    }
+
+    /// Removes the entry from the queue
+    pub async fn delete<C>(self, db: &C) -> DbResult<()>
+    where
+        C: ConnectionTrait + Send,
+    {
+        <Self as ModelTrait>::delete(self, db).await?;
+        Ok(())
+    }
+}
+
+impl Related<super::users::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl Related<super::strike_team_mission::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Mission.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
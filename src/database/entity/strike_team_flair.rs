@@ -0,0 +1,93 @@
+//! Strike team flair unlocked by users through challenge rewards
+
+use super::{users::UserId, User};
+use crate::{
+    database::DbResult, definitions::strike_team_flair::StrikeTeamFlairName, utils::clock,
+};
+use sea_orm::{entity::prelude::*, sea_query::OnConflict, ActiveValue::Set, QueryOrder};
+use serde::Serialize;
+
+/// Unlocked strike team flair database structure
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "strike_team_flair")]
+#[serde(rename_all = "camelCase")]
+pub struct Model {
+    /// Unique ID of the unlocked flair
+    #[sea_orm(primary_key)]
+    #[serde(skip)]
+    pub id: u32,
+    /// ID of the user that unlocked the flair
+    #[serde(skip)]
+    pub user_id: UserId,
+    /// Name of the unlocked flair
+    pub name: StrikeTeamFlairName,
+    /// When the flair was unlocked
+    pub unlocked: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::users::Entity",
+        from = "Column::UserId",
+        to = "super::users::Column::Id"
+    )]
+    User,
+}
+
+impl Model {
+    /// Unlocks the flair with the provided `name` for the `user`, unlocking
+    /// flair that is already unlocked does nothing
+    pub async fn unlock<C>(db: &C, user: &User, name: StrikeTeamFlairName) -> DbResult<()>
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::insert(ActiveModel {
+            user_id: Set(user.id),
+            name: Set(name),
+            unlocked: Set(clock::now()),
+            ..Default::default()
+        })
+        .on_conflict(
+            OnConflict::columns([Column::UserId, Column::Name])
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Gets all the flair unlocked by the `user`
+    pub async fn all<C>(db: &C, user: &User) -> DbResult<Vec<Self>>
+    where
+        C: ConnectionTrait + Send,
+    {
+        user.find_related(Entity)
+            .order_by_asc(Column::Id)
+            .all(db)
+            .await
+    }
+
+    /// Checks whether the `user` has unlocked the flair with the provided `name`
+    pub async fn is_unlocked<C>(db: &C, user: &User, name: &str) -> DbResult<bool>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let flair = user
+            .find_related(Entity)
+            .filter(Column::Name.eq(name))
+            .one(db)
+            .await?;
+        Ok(flair.is_some())
+    }
+}
+
+impl Related<super::users::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
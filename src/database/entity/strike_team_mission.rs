@@ -3,7 +3,7 @@ use std::future::Future;
 use crate::database::DbResult;
 use crate::definitions::shared::CustomAttributes;
 use crate::definitions::strike_teams::{
-    MissionDescriptor, MissionModifier, MissionRewards, MissionType, MissionWave,
+    MissionDescriptor, MissionDifficulty, MissionModifier, MissionRewards, MissionType, MissionWave,
 };
 use crate::definitions::strike_teams::{MissionTag, StrikeTeamMissionData};
 use log::debug;
@@ -81,6 +81,15 @@ pub enum MissionAccessibility {
 }
 
 impl Model {
+    /// Gets the difficulty of the mission from its static modifiers
+    pub fn difficulty(&self) -> Option<MissionDifficulty> {
+        self.static_modifiers
+            .0
+            .iter()
+            .find(|modifier| &*modifier.name == "difficulty")
+            .and_then(|modifier| MissionDifficulty::from_modifier(&modifier.value))
+    }
+
     pub fn by_id<C>(
         db: &C,
         id: StrikeTeamMissionId,
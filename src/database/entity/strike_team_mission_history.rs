@@ -0,0 +1,162 @@
+//! History of the missions completed by each strike team, kept so that
+//! players can review the track record of a team before retiring it
+
+use super::{
+    strike_team_mission::StrikeTeamMissionId, strike_teams::StrikeTeamId, SeaJson,
+    StrikeTeamMission,
+};
+use crate::{
+    database::DbResult,
+    definitions::{
+        challenges::CurrencyReward,
+        items::ItemName,
+        strike_teams::{MissionDescriptor, MissionDifficulty, StrikeTeamTrait},
+    },
+    utils::clock,
+};
+use sea_orm::{entity::prelude::*, ActiveValue::Set, QueryOrder, QuerySelect};
+use serde::{Deserialize, Serialize};
+
+/// Mission history database structure
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "strike_team_mission_history")]
+#[serde(rename_all = "camelCase")]
+pub struct Model {
+    /// Unique ID of the history entry
+    #[sea_orm(primary_key)]
+    pub id: u32,
+    /// ID of the team that completed the mission
+    #[serde(skip)]
+    pub strike_team_id: StrikeTeamId,
+    /// ID of the completed mission
+    pub mission_id: StrikeTeamMissionId,
+    /// The mission descriptor details
+    pub descriptor: MissionDescriptor,
+    /// Difficulty of the mission
+    pub difficulty: SeaJson<Option<MissionDifficulty>>,
+    /// Whether the mission was successful
+    pub successful: bool,
+    /// Currencies and items granted for the mission
+    pub rewards: SeaJson<MissionHistoryRewards>,
+    /// XP the team gained from the mission
+    pub xp_gained: u32,
+    /// Traits the team gained and lost from the mission
+    pub traits: SeaJson<MissionHistoryTraits>,
+    /// When the mission was resolved
+    pub completed: DateTimeUtc,
+}
+
+/// Rewards granted for a completed mission
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MissionHistoryRewards {
+    /// Currencies granted
+    pub currencies: Vec<CurrencyReward>,
+    /// Items granted
+    pub items: Vec<MissionHistoryItem>,
+}
+
+/// Item granted for a completed mission
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MissionHistoryItem {
+    /// Name of the granted item
+    pub name: ItemName,
+    /// The number of the item granted
+    pub count: u32,
+}
+
+/// Changes to the traits of a team from a completed mission
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MissionHistoryTraits {
+    /// Traits the team gained
+    pub gained: Vec<StrikeTeamTrait>,
+    /// Traits the team lost
+    pub lost: Vec<StrikeTeamTrait>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::strike_teams::Entity",
+        from = "Column::StrikeTeamId",
+        to = "super::strike_teams::Column::Id"
+    )]
+    StrikeTeam,
+}
+
+impl Model {
+    /// Maximum number of history entries listed at once
+    pub const MAX_LISTED: u64 = 100;
+
+    /// Records the `mission` completed by the team with the provided
+    /// `strike_team_id` along with the results of the mission
+    pub async fn create<C>(
+        db: &C,
+        strike_team_id: StrikeTeamId,
+        mission: &StrikeTeamMission,
+        successful: bool,
+        xp_gained: u32,
+        rewards: MissionHistoryRewards,
+        traits: MissionHistoryTraits,
+    ) -> DbResult<Self>
+    where
+        C: ConnectionTrait + Send,
+    {
+        ActiveModel {
+            strike_team_id: Set(strike_team_id),
+            mission_id: Set(mission.id),
+            descriptor: Set(mission.descriptor.clone()),
+            difficulty: Set(SeaJson(mission.difficulty())),
+            successful: Set(successful),
+            rewards: Set(SeaJson(rewards)),
+            xp_gained: Set(xp_gained),
+            traits: Set(SeaJson(traits)),
+            completed: Set(clock::now()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+    }
+
+    /// Gets a page of the missions completed by the team with the
+    /// provided `strike_team_id`, most recent first
+    pub async fn page<C>(
+        db: &C,
+        strike_team_id: StrikeTeamId,
+        offset: u64,
+        count: u64,
+    ) -> DbResult<Vec<Self>>
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::find()
+            .filter(Column::StrikeTeamId.eq(strike_team_id))
+            .order_by_desc(Column::Id)
+            .offset(offset)
+            .limit(count)
+            .all(db)
+            .await
+    }
+
+    /// Counts the missions recorded in the history of the team with
+    /// the provided `strike_team_id`
+    pub async fn count_by_team<C>(db: &C, strike_team_id: StrikeTeamId) -> DbResult<u64>
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::find()
+            .filter(Column::StrikeTeamId.eq(strike_team_id))
+            .count(db)
+            .await
+    }
+}
+
+impl Related<super::strike_teams::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::StrikeTeam.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -3,12 +3,17 @@
 
 use std::future::Future;
 
-use crate::database::DbResult;
+use crate::{database::DbResult, utils::clock};
 
 use super::users::UserId;
-use super::StrikeTeam;
 use super::{strike_team_mission::StrikeTeamMissionId, strike_teams::StrikeTeamId};
-use sea_orm::prelude::*;
+use super::{StrikeTeam, StrikeTeamMission, User};
+use chrono::Duration;
+use sea_orm::{
+    prelude::*,
+    sea_query::{Expr, OnConflict},
+    ActiveValue::Set,
+};
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 
@@ -18,18 +23,28 @@ use serde_with::skip_serializing_none;
 #[serde(rename_all = "camelCase")]
 pub struct Model {
     /// Unique ID of the strike team mission
-    #[sea_orm(primary_key)]
+    #[sea_orm(primary_key, auto_increment = false)]
     pub mission_id: StrikeTeamMissionId,
     /// The ID of the user this progress is for
+    #[sea_orm(primary_key, auto_increment = false)]
     pub user_id: UserId,
-    /// The ID of the strike team on the mission
-    pub strike_team_id: StrikeTeamId,
+    /// The ID of the strike team on the mission, cleared if the
+    /// strike team is retired
+    pub strike_team_id: Option<StrikeTeamId>,
     /// The users current mission state
     pub user_mission_state: UserMissionState,
     /// Whether the user has seen the mission
     pub seen: bool,
     /// Whether the mission is completed
     pub completed: bool,
+    /// When the strike team was deployed on the mission
+    pub deployed_at: Option<DateTimeUtc>,
+    /// When the strike team will return from the mission
+    pub finish_time: Option<DateTimeUtc>,
+    /// Whether the mission was successful, decided when the team returns
+    pub successful: Option<bool>,
+    /// Whether the team gains a negative trait when the mission is resolved
+    pub earn_negative_trait: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -70,7 +85,16 @@ pub enum UserMissionState {
     Completed = 3,
 }
 
+impl UserMissionState {
+    /// States where a strike team is still assigned to the mission
+    pub const ACTIVE: [UserMissionState; 2] = [
+        UserMissionState::InProgress,
+        UserMissionState::PendingResolve,
+    ];
+}
+
 impl Model {
+    /// Finds the progress for the mission the `team` is currently on
     pub fn get_by_team<'db, C>(
         db: &'db C,
         team: &StrikeTeam,
@@ -78,7 +102,206 @@ impl Model {
     where
         C: ConnectionTrait + Send,
     {
-        team.find_related(Entity).one(db)
+        team.find_related(Entity)
+            .filter(Column::UserMissionState.is_in(UserMissionState::ACTIVE))
+            .one(db)
+    }
+
+    /// Finds the progress for all the missions the `user` currently
+    /// has strike teams on
+    pub fn get_active<'db, C>(
+        db: &'db C,
+        user: &User,
+    ) -> impl Future<Output = DbResult<Vec<Self>>> + Send + 'db
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::find()
+            .filter(
+                Column::UserId
+                    .eq(user.id)
+                    .and(Column::UserMissionState.is_in(UserMissionState::ACTIVE)),
+            )
+            .all(db)
+    }
+
+    /// Finds the progress the `user` has for the `mission_id`
+    pub fn get_by_mission<'db, C>(
+        db: &'db C,
+        user: &User,
+        mission_id: StrikeTeamMissionId,
+    ) -> impl Future<Output = DbResult<Option<Self>>> + Send + 'db
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::find()
+            .filter(
+                Column::UserId
+                    .eq(user.id)
+                    .and(Column::MissionId.eq(mission_id)),
+            )
+            .one(db)
+    }
+
+    /// Deploys the `team` on the `mission` for the `user`, the team
+    /// returns after the mission length has passed.
+    ///
+    /// Existing progress is only replaced while the mission is available,
+    /// responds with [None] if the mission already has a team deployed
+    pub async fn deploy<C>(
+        db: &C,
+        user: &User,
+        team: &StrikeTeam,
+        mission: &StrikeTeamMission,
+    ) -> DbResult<Option<Self>>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let now = clock::now();
+        let finish_time = now + Duration::seconds(mission.sp_length_seconds as i64);
+
+        let inserted = Entity::insert(ActiveModel {
+            mission_id: Set(mission.id),
+            user_id: Set(user.id),
+            strike_team_id: Set(Some(team.id)),
+            user_mission_state: Set(UserMissionState::InProgress),
+            seen: Set(true),
+            completed: Set(false),
+            deployed_at: Set(Some(now)),
+            finish_time: Set(Some(finish_time)),
+            successful: Set(None),
+            earn_negative_trait: Set(false),
+        })
+        .on_conflict(
+            OnConflict::columns([Column::UserId, Column::MissionId])
+                .do_nothing()
+                .to_owned(),
+        )
+        // Returning doesn't work with composite key
+        .exec_without_returning(db)
+        .await?;
+
+        if inserted == 0 {
+            // Replace existing progress such as the mission being marked seen
+            let result = Entity::update_many()
+                .col_expr(Column::StrikeTeamId, Expr::value(team.id))
+                .col_expr(
+                    Column::UserMissionState,
+                    Expr::value(UserMissionState::InProgress),
+                )
+                .col_expr(Column::Seen, Expr::value(true))
+                .col_expr(Column::Completed, Expr::value(false))
+                .col_expr(Column::DeployedAt, Expr::value(now))
+                .col_expr(Column::FinishTime, Expr::value(finish_time))
+                .col_expr(Column::Successful, Expr::value(Option::<bool>::None))
+                .col_expr(Column::EarnNegativeTrait, Expr::value(false))
+                .filter(
+                    Column::UserId
+                        .eq(user.id)
+                        .and(Column::MissionId.eq(mission.id))
+                        .and(Column::UserMissionState.eq(UserMissionState::Available)),
+                )
+                .exec(db)
+                .await?;
+
+            if result.rows_affected == 0 {
+                return Ok(None);
+            }
+        }
+
+        Self::get_by_mission(db, user, mission.id)
+            .await?
+            .ok_or(DbErr::RecordNotInserted)
+            .map(Some)
+    }
+
+    /// Finds the progress for missions across all users where the strike
+    /// team has returned but the mission hasn't been marked as returned
+    pub fn get_returned<C>(
+        db: &C,
+        now: DateTimeUtc,
+    ) -> impl Future<Output = DbResult<Vec<Self>>> + Send + '_
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::find()
+            .filter(
+                Column::UserMissionState
+                    .eq(UserMissionState::InProgress)
+                    .and(Column::FinishTime.lte(now)),
+            )
+            .all(db)
+    }
+
+    /// Marks the strike team as returned from the mission storing
+    /// the outcome to apply when the mission is resolved. Only missions
+    /// that are still in progress are updated so that stale outcomes
+    /// can't move a resolved mission back to pending resolve.
+    ///
+    /// Responds with the updated progress or [None] if the mission
+    /// was no longer in progress
+    pub async fn set_returned<C>(
+        self,
+        db: &C,
+        successful: bool,
+        earn_negative_trait: bool,
+    ) -> DbResult<Option<Self>>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let result = Entity::update_many()
+            .col_expr(
+                Column::UserMissionState,
+                Expr::value(UserMissionState::PendingResolve),
+            )
+            .col_expr(Column::Successful, Expr::value(successful))
+            .col_expr(Column::EarnNegativeTrait, Expr::value(earn_negative_trait))
+            .filter(
+                Column::UserId
+                    .eq(self.user_id)
+                    .and(Column::MissionId.eq(self.mission_id))
+                    .and(Column::UserMissionState.eq(UserMissionState::InProgress)),
+            )
+            .exec(db)
+            .await?;
+
+        if result.rows_affected == 0 {
+            return Ok(None);
+        }
+
+        Ok(Some(Self {
+            user_mission_state: UserMissionState::PendingResolve,
+            successful: Some(successful),
+            earn_negative_trait,
+            ..self
+        }))
+    }
+
+    /// Moves the mission from pending resolve to completed in a single
+    /// update that only applies while the mission is pending resolve, so
+    /// concurrent resolves of the same mission only complete it once.
+    ///
+    /// Responds with whether the mission was completed
+    pub async fn set_completed<C>(&self, db: &C) -> DbResult<bool>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let result = Entity::update_many()
+            .col_expr(
+                Column::UserMissionState,
+                Expr::value(UserMissionState::Completed),
+            )
+            .col_expr(Column::Completed, Expr::value(true))
+            .filter(
+                Column::UserId
+                    .eq(self.user_id)
+                    .and(Column::MissionId.eq(self.mission_id))
+                    .and(Column::UserMissionState.eq(UserMissionState::PendingResolve)),
+            )
+            .exec(db)
+            .await?;
+
+        Ok(result.rows_affected > 0)
     }
 }
 
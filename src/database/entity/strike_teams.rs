@@ -2,6 +2,7 @@ use super::users::UserId;
 use super::{SeaJson, StrikeTeamMissionProgress, User};
 use crate::database::DbResult;
 use crate::definitions::level_tables::ProgressionXp;
+use crate::definitions::strike_team_flair::{StrikeTeamFlairKind, StrikeTeamFlairName};
 use crate::definitions::strike_teams::{
     StrikeTeamData, StrikeTeamEquipment, StrikeTeamIcon, StrikeTeamName, StrikeTeamTrait,
 };
@@ -44,6 +45,14 @@ pub struct Model {
     pub negative_traits: SeaJson<Vec<StrikeTeamTrait>>,
     /// Unknown usage
     pub out_of_date: bool,
+    /// Name of the equipped icon flair
+    pub icon_flair: Option<StrikeTeamFlairName>,
+    /// Name of the equipped name color flair
+    pub name_color_flair: Option<StrikeTeamFlairName>,
+    /// Number of consecutive successful missions
+    pub success_streak: u32,
+    /// Name of the specialization chosen for the team
+    pub specialization: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -55,7 +64,7 @@ pub enum Relation {
     )]
     User,
 
-    #[sea_orm(has_one = "super::strike_team_mission_progress::Entity")]
+    #[sea_orm(has_many = "super::strike_team_mission_progress::Entity")]
     MissionProgress,
 }
 
@@ -91,12 +100,76 @@ impl Model {
         model.update(db).await
     }
 
-    pub async fn delete<C>(self, db: &C) -> DbResult<()>
+    /// Sets the specialization of the team
+    pub async fn set_specialization<C>(self, db: &C, specialization: String) -> DbResult<Self>
     where
         C: ConnectionTrait + Send,
     {
-        <Self as ModelTrait>::delete(self, db).await?;
-        Ok(())
+        let mut model = self.into_active_model();
+        model.specialization = Set(Some(specialization));
+        model.update(db).await
+    }
+
+    /// Replaces the positive and negative traits of the team
+    pub async fn set_traits<C>(
+        self,
+        db: &C,
+        positive_traits: Vec<StrikeTeamTrait>,
+        negative_traits: Vec<StrikeTeamTrait>,
+    ) -> DbResult<Self>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let mut model = self.into_active_model();
+        model.positive_traits = Set(SeaJson(positive_traits));
+        model.negative_traits = Set(SeaJson(negative_traits));
+        model.update(db).await
+    }
+
+    /// Updates the team progression after resolving a mission
+    pub async fn set_progression<C>(
+        self,
+        db: &C,
+        xp: ProgressionXp,
+        level: u32,
+        success_streak: u32,
+    ) -> DbResult<Self>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let mut model = self.into_active_model();
+        model.xp = Set(xp);
+        model.level = Set(level);
+        model.success_streak = Set(success_streak);
+        model.update(db).await
+    }
+
+    /// Sets the equipped flair of the provided `kind`, [None] removes
+    /// the currently equipped flair
+    pub async fn set_flair<C>(
+        self,
+        db: &C,
+        kind: StrikeTeamFlairKind,
+        flair: Option<StrikeTeamFlairName>,
+    ) -> DbResult<Self>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let mut model = self.into_active_model();
+        match kind {
+            StrikeTeamFlairKind::Icon => model.icon_flair = Set(flair),
+            StrikeTeamFlairKind::NameColor => model.name_color_flair = Set(flair),
+        }
+        model.update(db).await
+    }
+
+    /// Deletes the strike team, returns whether the team was deleted
+    pub async fn delete<C>(self, db: &C) -> DbResult<bool>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let result = <Self as ModelTrait>::delete(self, db).await?;
+        Ok(result.rows_affected == 1)
     }
 
     // Checks if the strike team is on a mission
@@ -0,0 +1,120 @@
+//! Server wide bans preventing users from logging in and joining games,
+//! managed by administrators
+
+use super::{users::UserId, User};
+use crate::{database::DbResult, utils::clock};
+use sea_orm::{entity::prelude::*, sea_query::OnConflict, ActiveValue::Set, Condition, QueryOrder};
+use serde::Serialize;
+
+/// User ban database structure
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "user_bans")]
+#[serde(rename_all = "camelCase")]
+pub struct Model {
+    /// ID of the banned user
+    #[sea_orm(primary_key)]
+    pub user_id: UserId,
+    /// ID of the administrator that banned the user
+    pub banned_by: UserId,
+    /// Reason given for the ban
+    pub reason: Option<String>,
+    /// When the user was banned
+    pub created: DateTimeUtc,
+    /// When the ban expires, [None] for permanent bans
+    pub expires: Option<DateTimeUtc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::users::Entity",
+        from = "Column::UserId",
+        to = "super::users::Column::Id"
+    )]
+    User,
+}
+
+impl Model {
+    /// Bans the `user`, replacing any existing ban for the user
+    pub async fn create<C>(
+        db: &C,
+        user: &User,
+        banned_by: &User,
+        reason: Option<String>,
+        expires: Option<DateTimeUtc>,
+    ) -> DbResult<()>
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::insert(ActiveModel {
+            user_id: Set(user.id),
+            banned_by: Set(banned_by.id),
+            reason: Set(reason),
+            created: Set(clock::now()),
+            expires: Set(expires),
+        })
+        .on_conflict(
+            OnConflict::column(Column::UserId)
+                .update_columns([
+                    Column::BannedBy,
+                    Column::Reason,
+                    Column::Created,
+                    Column::Expires,
+                ])
+                .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Finds the ban for the user with the provided `user_id` if the
+    /// user is currently banned
+    pub async fn active<C>(db: &C, user_id: UserId) -> DbResult<Option<Self>>
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::find_by_id(user_id)
+            .filter(
+                Condition::any()
+                    .add(Column::Expires.is_null())
+                    .add(Column::Expires.gt(clock::now())),
+            )
+            .one(db)
+            .await
+    }
+
+    /// Gets all the bans that haven't expired, newest first
+    pub async fn all_active<C>(db: &C) -> DbResult<Vec<Self>>
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::find()
+            .filter(
+                Condition::any()
+                    .add(Column::Expires.is_null())
+                    .add(Column::Expires.gt(clock::now())),
+            )
+            .order_by_desc(Column::Created)
+            .all(db)
+            .await
+    }
+
+    /// Removes the ban for the `user`, returns whether the user was banned
+    pub async fn remove<C>(db: &C, user: &User) -> DbResult<bool>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let result = Entity::delete_by_id(user.id).exec(db).await?;
+        Ok(result.rows_affected > 0)
+    }
+}
+
+impl Related<super::users::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,101 @@
+//! External identities from authentication providers that
+//! have been linked to local user accounts
+
+use super::{users::UserId, User};
+use crate::database::DbResult;
+use sea_orm::{entity::prelude::*, ActiveValue::Set};
+use std::future::Future;
+
+/// Type alias for a [u32] representing a user identity ID
+pub type UserIdentityId = u32;
+
+/// User identity database structure
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "user_identities")]
+pub struct Model {
+    /// Unique ID of the identity
+    #[sea_orm(primary_key)]
+    pub id: UserIdentityId,
+    /// ID of the local user the identity is linked to
+    pub user_id: UserId,
+    /// Name of the provider the identity is from
+    pub provider: String,
+    /// Unique identifier for the user within the provider
+    pub subject: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::users::Entity",
+        from = "Column::UserId",
+        to = "super::users::Column::Id"
+    )]
+    User,
+}
+
+impl Model {
+    /// Links the identity from `provider` with the `subject` to
+    /// the provided `user`
+    pub fn create<C>(
+        db: &C,
+        user: &User,
+        provider: String,
+        subject: String,
+    ) -> impl Future<Output = DbResult<Self>> + Send + '_
+    where
+        C: ConnectionTrait + Send,
+    {
+        ActiveModel {
+            user_id: Set(user.id),
+            provider: Set(provider),
+            subject: Set(subject),
+            ..Default::default()
+        }
+        .insert(db)
+    }
+
+    /// Finds the local user linked to the identity from `provider`
+    /// with the `subject`
+    pub async fn find_user<C>(db: &C, provider: &str, subject: &str) -> DbResult<Option<User>>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let result = Entity::find()
+            .filter(
+                Column::Provider
+                    .eq(provider)
+                    .and(Column::Subject.eq(subject)),
+            )
+            .find_also_related(super::users::Entity)
+            .one(db)
+            .await?;
+
+        Ok(result.and_then(|(_, user)| user))
+    }
+
+    /// Checks whether the `user` has an identity linked from `provider`
+    pub async fn has_provider<C>(db: &C, user: &User, provider: &str) -> DbResult<bool>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let count = Entity::find()
+            .filter(
+                Column::UserId
+                    .eq(user.id)
+                    .and(Column::Provider.eq(provider)),
+            )
+            .count(db)
+            .await?;
+
+        Ok(count > 0)
+    }
+}
+
+impl Related<super::users::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
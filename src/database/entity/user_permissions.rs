@@ -0,0 +1,134 @@
+//! Permissions granted to administrator accounts, administrators can only
+//! use the admin endpoints that require the permissions they have been
+//! granted. Super administrators implicitly have every permission
+
+use super::{
+    users::{UserId, UserRole},
+    User,
+};
+use crate::database::DbResult;
+use sea_orm::{
+    entity::prelude::*, sea_query::OnConflict, ActiveValue::Set, QueryOrder, QuerySelect,
+};
+use serde::{Deserialize, Serialize};
+
+/// Permissions that can be granted to administrators
+#[derive(
+    Debug, EnumIter, DeriveActiveEnum, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "u8", db_type = "Integer")]
+#[serde(rename_all = "camelCase")]
+#[repr(u8)]
+pub enum AdminPermission {
+    /// Moderating players, handling reports and removing accounts
+    Moderator = 0,
+    /// Changing the player economy, granting items and currencies
+    Economy = 1,
+    /// Operating the server, changing configuration and running maintenance
+    Operator = 2,
+}
+
+/// User permission database structure
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "user_permissions")]
+pub struct Model {
+    /// Unique ID of the granted permission
+    #[sea_orm(primary_key)]
+    pub id: u32,
+    /// ID of the user the permission was granted to
+    pub user_id: UserId,
+    /// The granted permission
+    pub permission: AdminPermission,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::users::Entity",
+        from = "Column::UserId",
+        to = "super::users::Column::Id"
+    )]
+    User,
+}
+
+impl Model {
+    /// Gets all the permissions granted to the `user`
+    pub async fn all<C>(db: &C, user: &User) -> DbResult<Vec<AdminPermission>>
+    where
+        C: ConnectionTrait + Send,
+    {
+        user.find_related(Entity)
+            .select_only()
+            .column(Column::Permission)
+            .order_by_asc(Column::Permission)
+            .into_tuple()
+            .all(db)
+            .await
+    }
+
+    /// Checks whether the `user` has the `permission`, super administrators
+    /// have every permission without it being granted
+    pub async fn has<C>(db: &C, user: &User, permission: AdminPermission) -> DbResult<bool>
+    where
+        C: ConnectionTrait + Send,
+    {
+        if user.role == UserRole::SuperAdmin {
+            return Ok(true);
+        }
+
+        let granted = user
+            .find_related(Entity)
+            .filter(Column::Permission.eq(permission))
+            .one(db)
+            .await?;
+        Ok(granted.is_some())
+    }
+
+    /// Grants the `permission` to the `user`, granting a permission the
+    /// user already has does nothing
+    pub async fn grant<C>(db: &C, user: &User, permission: AdminPermission) -> DbResult<()>
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::insert(ActiveModel {
+            user_id: Set(user.id),
+            permission: Set(permission),
+            ..Default::default()
+        })
+        .on_conflict(
+            OnConflict::columns([Column::UserId, Column::Permission])
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Revokes the `permission` from the `user`, responds with
+    /// whether the user had the permission
+    pub async fn revoke<C>(db: &C, user: &User, permission: AdminPermission) -> DbResult<bool>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let result = Entity::delete_many()
+            .filter(
+                Column::UserId
+                    .eq(user.id)
+                    .and(Column::Permission.eq(permission)),
+            )
+            .exec(db)
+            .await?;
+
+        Ok(result.rows_affected > 0)
+    }
+}
+
+impl Related<super::users::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
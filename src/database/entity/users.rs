@@ -1,8 +1,9 @@
 use std::future::Future;
 
-use crate::database::DbResult;
+use crate::{database::DbResult, definitions::titles::PlayerTitleName, utils::clock};
 use sea_orm::entity::prelude::*;
-use sea_orm::{IntoActiveModel, QuerySelect};
+use sea_orm::{sea_query::Expr, ActiveValue::Set, IntoActiveModel, QueryOrder, QuerySelect};
+use serde::{Deserialize, Serialize};
 
 /// Type alias for a [u32] representing a user ID
 pub type UserId = u32;
@@ -20,6 +21,110 @@ pub struct Model {
     pub username: String,
     /// Password for the account
     pub password: String,
+    /// Role of the account
+    pub role: UserRole,
+    /// Platform the account last authenticated from
+    pub platform: Platform,
+    /// Name of the title the user has equipped
+    pub title: Option<PlayerTitleName>,
+    /// Unix timestamp in milliseconds that the tokens issued to the
+    /// user before are revoked, zero when tokens were never revoked
+    pub tokens_revoked_at: i64,
+}
+
+/// Roles an account can have, roles are ordered by
+/// their level of permission
+#[derive(
+    Debug,
+    EnumIter,
+    DeriveActiveEnum,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+)]
+#[sea_orm(rs_type = "u8", db_type = "Integer")]
+#[repr(u8)]
+pub enum UserRole {
+    /// Regular player account
+    Default = 0,
+    /// Administrator with access to the admin endpoints
+    Admin = 1,
+    /// Super administrator, assigned from the server config
+    SuperAdmin = 2,
+}
+
+impl UserRole {
+    /// Checks whether the role has administrator permissions
+    #[inline]
+    pub fn is_admin(&self) -> bool {
+        *self >= UserRole::Admin
+    }
+}
+
+/// Platforms the game client can be running on, detected from
+/// the platform of the SKU sent by the client
+#[derive(
+    Debug,
+    Default,
+    EnumIter,
+    DeriveActiveEnum,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+)]
+#[sea_orm(rs_type = "u8", db_type = "Integer")]
+#[serde(rename_all = "camelCase")]
+#[repr(u8)]
+pub enum Platform {
+    /// PC through the EA app / Origin
+    #[default]
+    Origin = 0,
+    /// PC through Steam
+    Steam = 1,
+    /// Xbox consoles
+    Xbox = 2,
+    /// PlayStation consoles
+    PlayStation = 3,
+}
+
+impl Platform {
+    /// Detects the platform from the `platform` of a client SKU,
+    /// responds with [None] for unknown platforms
+    pub fn from_sku(platform: &str) -> Option<Self> {
+        let platform = platform.to_ascii_lowercase();
+        Some(match platform.as_str() {
+            "origin" | "pc" | "ea" => Platform::Origin,
+            "steam" => Platform::Steam,
+            "xbox" | "xbl" | "xone" | "xbsx" | "durango" => Platform::Xbox,
+            "playstation" | "psn" | "ps4" | "ps5" | "orbis" => Platform::PlayStation,
+            _ => return None,
+        })
+    }
+
+    /// Platform name used within SKUs sent to the client
+    pub fn sku_name(&self) -> &'static str {
+        match self {
+            Platform::Origin => "origin",
+            Platform::Steam => "steam",
+            Platform::Xbox => "xbl",
+            Platform::PlayStation => "psn",
+        }
+    }
+
+    /// Checks whether the platform is a PC platform, PC platforms
+    /// are always allowed to play together
+    pub fn is_pc(&self) -> bool {
+        matches!(self, Platform::Origin | Platform::Steam)
+    }
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -38,6 +143,24 @@ pub enum Relation {
     SharedData,
     #[sea_orm(has_many = "super::strike_teams::Entity")]
     StrikeTeams,
+    #[sea_orm(has_many = "super::reports::Entity")]
+    Reports,
+    #[sea_orm(has_many = "super::login_events::Entity")]
+    LoginEvents,
+    #[sea_orm(has_many = "super::user_identities::Entity")]
+    Identities,
+    #[sea_orm(has_many = "super::currency_ledger::Entity")]
+    CurrencyLedger,
+    #[sea_orm(has_one = "super::welcome_kit_grants::Entity")]
+    WelcomeKitGrant,
+    #[sea_orm(has_many = "super::strike_team_flair::Entity")]
+    StrikeTeamFlair,
+    #[sea_orm(has_one = "super::matchmaking_preferences::Entity")]
+    MatchmakingPreferences,
+    #[sea_orm(has_many = "super::user_permissions::Entity")]
+    Permissions,
+    #[sea_orm(has_many = "super::player_titles::Entity")]
+    PlayerTitles,
 }
 
 /// Partial structure for creating a new user
@@ -105,6 +228,14 @@ impl Model {
         Ok(result.is_some())
     }
 
+    /// Counts the total number of registered users
+    pub fn count<C>(db: &C) -> impl Future<Output = DbResult<u64>> + Send + '_
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::find().count(db)
+    }
+
     /// Finds a user by its [UserId]
     pub fn by_id<C>(db: &C, id: UserId) -> impl Future<Output = DbResult<Option<Self>>> + Send + '_
     where
@@ -129,6 +260,101 @@ impl Model {
             .filter(Column::Email.eq(email_lower))
             .one(db)
     }
+
+    /// Obtains a page of users ordered by ID
+    pub fn page<C>(
+        db: &C,
+        offset: u64,
+        count: u64,
+    ) -> impl Future<Output = DbResult<Vec<Self>>> + Send + '_
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::find()
+            .order_by_asc(Column::Id)
+            .offset(offset)
+            .limit(count)
+            .all(db)
+    }
+
+    /// Deletes the user, the data belonging to the user
+    /// is removed by the foreign key cascades
+    pub async fn delete<C>(self, db: &C) -> DbResult<()>
+    where
+        C: ConnectionTrait + Send,
+    {
+        <Self as ModelTrait>::delete(self, db).await?;
+        Ok(())
+    }
+
+    /// Updates the role of the user
+    pub fn set_role<C>(self, db: &C, role: UserRole) -> impl Future<Output = DbResult<Self>> + '_
+    where
+        C: ConnectionTrait + Send,
+    {
+        let mut model = self.into_active_model();
+        model.role = Set(role);
+        model.update(db)
+    }
+
+    /// Updates the `password` hash of the user
+    pub fn set_password<C>(
+        self,
+        db: &C,
+        password: String,
+    ) -> impl Future<Output = DbResult<Self>> + '_
+    where
+        C: ConnectionTrait + Send,
+    {
+        let mut model = self.into_active_model();
+        model.password = Set(password);
+        model.update(db)
+    }
+
+    /// Updates the platform the user last authenticated from
+    pub fn set_platform<C>(
+        self,
+        db: &C,
+        platform: Platform,
+    ) -> impl Future<Output = DbResult<Self>> + '_
+    where
+        C: ConnectionTrait + Send,
+    {
+        let mut model = self.into_active_model();
+        model.platform = Set(platform);
+        model.update(db)
+    }
+
+    /// Revokes the tokens issued to the user with the provided `user_id`
+    /// before now, tokens issued after remain valid
+    pub async fn revoke_tokens<C>(db: &C, user_id: UserId) -> DbResult<()>
+    where
+        C: ConnectionTrait + Send,
+    {
+        Entity::update_many()
+            .col_expr(
+                Column::TokensRevokedAt,
+                Expr::value(clock::now().timestamp_millis()),
+            )
+            .filter(Column::Id.eq(user_id))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+
+    /// Updates the title equipped by the user
+    pub fn set_title<C>(
+        self,
+        db: &C,
+        title: Option<PlayerTitleName>,
+    ) -> impl Future<Output = DbResult<Self>> + '_
+    where
+        C: ConnectionTrait + Send,
+    {
+        let mut model = self.into_active_model();
+        model.title = Set(title);
+        model.update(db)
+    }
 }
 
 impl Related<super::currency::Entity> for Entity {
@@ -173,4 +399,58 @@ impl Related<super::strike_teams::Entity> for Entity {
     }
 }
 
+impl Related<super::reports::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Reports.def()
+    }
+}
+
+impl Related<super::login_events::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::LoginEvents.def()
+    }
+}
+
+impl Related<super::user_identities::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Identities.def()
+    }
+}
+
+impl Related<super::currency_ledger::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CurrencyLedger.def()
+    }
+}
+
+impl Related<super::welcome_kit_grants::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::WelcomeKitGrant.def()
+    }
+}
+
+impl Related<super::strike_team_flair::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::StrikeTeamFlair.def()
+    }
+}
+
+impl Related<super::player_titles::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PlayerTitles.def()
+    }
+}
+
+impl Related<super::matchmaking_preferences::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MatchmakingPreferences.def()
+    }
+}
+
+impl Related<super::user_permissions::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Permissions.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,62 @@
+//! Records which users have been granted the welcome kit, ensures
+//! the kit is only ever applied once per account
+
+use super::{users::UserId, User};
+use crate::{database::DbResult, utils::clock};
+use sea_orm::{entity::prelude::*, ActiveValue::Set};
+use std::future::Future;
+
+/// Welcome kit grant database structure
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "welcome_kit_grants")]
+pub struct Model {
+    /// ID of the user the kit was granted to
+    #[sea_orm(primary_key)]
+    pub user_id: UserId,
+    /// When the kit was granted
+    pub created: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::users::Entity",
+        from = "Column::UserId",
+        to = "super::users::Column::Id"
+    )]
+    User,
+}
+
+impl Model {
+    /// Records that the welcome kit was granted to the `user`
+    pub fn create<'db, C>(
+        db: &'db C,
+        user: &User,
+    ) -> impl Future<Output = DbResult<Self>> + Send + 'db
+    where
+        C: ConnectionTrait + Send,
+    {
+        ActiveModel {
+            user_id: Set(user.id),
+            created: Set(clock::now()),
+        }
+        .insert(db)
+    }
+
+    /// Checks whether the welcome kit has been granted to the `user`
+    pub async fn exists<C>(db: &C, user: &User) -> DbResult<bool>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let grant = Entity::find_by_id(user.id).one(db).await?;
+        Ok(grant.is_some())
+    }
+}
+
+impl Related<super::users::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,90 @@
+//! XP boosts activated by consuming boosters such as the "EXPERIENCE ENHANCER"
+//! items, the boost is applied to the next mission the user completes
+
+use super::{users::UserId, User};
+use crate::{database::DbResult, utils::clock};
+use sea_orm::{entity::prelude::*, sea_query::OnConflict, ActiveValue::Set};
+
+/// XP boost database structure
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "xp_boosts")]
+pub struct Model {
+    /// ID of the user the boost is for
+    #[sea_orm(primary_key)]
+    pub user_id: UserId,
+    /// Percentage of bonus XP given by the boost
+    pub percent: u32,
+    /// When the boost was activated
+    pub activated: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::users::Entity",
+        from = "Column::UserId",
+        to = "super::users::Column::Id"
+    )]
+    User,
+}
+
+impl Model {
+    /// Activates an XP boost of `percent` for the `user`, boosts that
+    /// haven't been used yet aren't replaced. Responds with whether the
+    /// boost was activated
+    pub async fn activate<C>(db: &C, user: &User, percent: u32) -> DbResult<bool>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let inserted = Entity::insert(ActiveModel {
+            user_id: Set(user.id),
+            percent: Set(percent),
+            activated: Set(clock::now()),
+        })
+        .on_conflict(OnConflict::column(Column::UserId).do_nothing().to_owned())
+        .exec_without_returning(db)
+        .await?;
+
+        Ok(inserted > 0)
+    }
+
+    /// Checks whether the `user` has an XP boost that hasn't been used yet
+    pub async fn is_active<C>(db: &C, user: &User) -> DbResult<bool>
+    where
+        C: ConnectionTrait + Send,
+    {
+        Ok(Entity::find_by_id(user.id).one(db).await?.is_some())
+    }
+
+    /// Takes the active XP boost for the `user` removing it so that it
+    /// only applies once, the boost is only responded with when this
+    /// call was the one to remove it
+    pub async fn take<C>(db: &C, user: &User) -> DbResult<Option<Self>>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let boost = match Entity::find_by_id(user.id).one(db).await? {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+
+        let result = Entity::delete_many()
+            .filter(
+                Column::UserId
+                    .eq(boost.user_id)
+                    .and(Column::Activated.eq(boost.activated)),
+            )
+            .exec(db)
+            .await?;
+
+        Ok((result.rows_affected > 0).then_some(boost))
+    }
+}
+
+impl Related<super::users::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,153 @@
+//! Instrumentation for database queries, counts the executed queries and
+//! logs the ones which take longer than the configured threshold along
+//! with where they were executed from
+
+use crate::config::{Config, DatabaseConfig};
+use log::warn;
+use sea_orm::{metric::Info, DatabaseConnection, Statement};
+use serde::Serialize;
+use std::{
+    backtrace::Backtrace,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+/// Total number of queries executed
+static TOTAL_QUERIES: AtomicU64 = AtomicU64::new(0);
+/// Number of queries that exceeded the slow query threshold
+static SLOW_QUERIES: AtomicU64 = AtomicU64::new(0);
+/// Number of queries that failed
+static FAILED_QUERIES: AtomicU64 = AtomicU64::new(0);
+
+/// Module path prefix for frames from within the server
+const CRATE_FRAME_PREFIX: &str = "pocket_ark::";
+/// Module path prefix for frames from this module, these are skipped
+/// when finding the call site of a query
+const METRICS_FRAME_PREFIX: &str = "pocket_ark::database::metrics";
+
+/// Snapshot of the database query counters
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryMetrics {
+    /// Total number of queries executed
+    pub total_queries: u64,
+    /// Number of queries that exceeded the slow query threshold
+    pub slow_queries: u64,
+    /// Number of queries that failed
+    pub failed_queries: u64,
+    /// Milliseconds a query can take before its considered slow
+    pub slow_query_threshold_ms: u64,
+}
+
+impl QueryMetrics {
+    /// Obtains the current query counters
+    pub fn get() -> Self {
+        Self {
+            total_queries: TOTAL_QUERIES.load(Ordering::Relaxed),
+            slow_queries: SLOW_QUERIES.load(Ordering::Relaxed),
+            failed_queries: FAILED_QUERIES.load(Ordering::Relaxed),
+            slow_query_threshold_ms: Config::get().database.slow_query_threshold_ms,
+        }
+    }
+}
+
+/// Installs the query instrumentation onto the provided `connection`
+pub fn install(connection: &mut DatabaseConnection) {
+    let config: &'static DatabaseConfig = &Config::get().database;
+    let threshold = Duration::from_millis(config.slow_query_threshold_ms);
+
+    connection.set_metric_callback(move |info: &Info<'_>| {
+        TOTAL_QUERIES.fetch_add(1, Ordering::Relaxed);
+
+        if info.failed {
+            FAILED_QUERIES.fetch_add(1, Ordering::Relaxed);
+        }
+
+        if info.elapsed < threshold {
+            return;
+        }
+
+        SLOW_QUERIES.fetch_add(1, Ordering::Relaxed);
+
+        if config.log_slow_queries {
+            warn!(
+                "Slow query took {}ms at {}: {} {}",
+                info.elapsed.as_millis(),
+                call_site().as_deref().unwrap_or("unknown"),
+                info.statement.sql,
+                redacted_parameters(info.statement)
+            );
+        }
+    });
+}
+
+/// Finds the first server function in the current backtrace outside of
+/// this module, this is the function that executed the query
+fn call_site() -> Option<String> {
+    let backtrace = Backtrace::force_capture().to_string();
+    let mut lines = backtrace.lines().map(str::trim);
+
+    while let Some(line) = lines.next() {
+        // Frame lines are formatted as "{index}: {symbol}"
+        let Some((_, symbol)) = line.split_once(": ") else {
+            continue;
+        };
+
+        if !symbol.starts_with(CRATE_FRAME_PREFIX) || symbol.starts_with(METRICS_FRAME_PREFIX) {
+            continue;
+        }
+
+        // Async functions are polled through closures which aren't useful to include
+        let symbol = symbol.trim_end_matches("::{{closure}}");
+
+        // Frame symbols are followed by their source location when available
+        return Some(
+            match lines.next().and_then(|line| line.strip_prefix("at ")) {
+                Some(location) => format!("{symbol} ({location})"),
+                None => symbol.to_string(),
+            },
+        );
+    }
+
+    None
+}
+
+/// Creates a list of the parameter types for the `statement`, the values
+/// themselves are redacted as they may contain user details
+fn redacted_parameters(statement: &Statement) -> String {
+    let Some(values) = &statement.values else {
+        return "[]".to_string();
+    };
+
+    let types: Vec<String> = values
+        .0
+        .iter()
+        .map(|value| {
+            let value = format!("{value:?}");
+            match value.split_once('(') {
+                Some((ty, _)) => ty.to_string(),
+                None => value,
+            }
+        })
+        .collect();
+
+    format!("[{}]", types.join(", "))
+}
+
+#[cfg(test)]
+mod test {
+    use super::redacted_parameters;
+    use sea_orm::{DbBackend, Statement};
+
+    /// Tests that parameter values are removed leaving only their types
+    #[test]
+    fn test_redacted_parameters() {
+        let statement = Statement::from_sql_and_values(
+            DbBackend::Sqlite,
+            "SELECT * FROM users WHERE email = ? AND id = ?",
+            ["user@example.com".into(), 1u32.into()],
+        );
+
+        assert_eq!(redacted_parameters(&statement), "[String, Unsigned]");
+    }
+}
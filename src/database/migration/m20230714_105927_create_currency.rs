@@ -47,10 +47,6 @@ impl MigrationTrait for Migration {
     }
 
     async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
-        manager
-            .drop_table(Table::drop().table(Currency::Table).to_owned())
-            .await?;
-
         // Drop the index
         manager
             .drop_index(
@@ -61,6 +57,10 @@ impl MigrationTrait for Migration {
             )
             .await?;
 
+        manager
+            .drop_table(Table::drop().table(Currency::Table).to_owned())
+            .await?;
+
         Ok(())
     }
 }
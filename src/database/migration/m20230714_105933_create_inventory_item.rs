@@ -99,11 +99,6 @@ impl MigrationTrait for Migration {
     }
 
     async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
-        // Drop the table
-        manager
-            .drop_table(Table::drop().table(InventoryItems::Table).to_owned())
-            .await?;
-
         // Drop the index
         manager
             .drop_index(
@@ -114,13 +109,18 @@ impl MigrationTrait for Migration {
             )
             .await?;
 
+        // Drop the table
+        manager
+            .drop_table(Table::drop().table(InventoryItems::Table).to_owned())
+            .await?;
+
         Ok(())
     }
 }
 
 /// Learn more at https://docs.rs/sea-query#iden
 #[derive(Iden)]
-enum InventoryItems {
+pub enum InventoryItems {
     Table,
     Id,
     UserId,
@@ -82,11 +82,6 @@ impl MigrationTrait for Migration {
     }
 
     async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
-        // Drop the table
-        manager
-            .drop_table(Table::drop().table(Characters::Table).to_owned())
-            .await?;
-
         // Drop the index
         manager
             .drop_index(
@@ -97,12 +92,17 @@ impl MigrationTrait for Migration {
             )
             .await?;
 
+        // Drop the table
+        manager
+            .drop_table(Table::drop().table(Characters::Table).to_owned())
+            .await?;
+
         Ok(())
     }
 }
 
 #[derive(Iden)]
-enum Characters {
+pub enum Characters {
     Table,
     Id,
     UserId,
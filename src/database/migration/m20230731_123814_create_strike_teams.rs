@@ -78,10 +78,6 @@ impl MigrationTrait for Migration {
     }
 
     async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
-        manager
-            .drop_table(Table::drop().table(StrikeTeams::Table).to_owned())
-            .await?;
-
         // Drop the index
         manager
             .drop_index(
@@ -92,6 +88,10 @@ impl MigrationTrait for Migration {
             )
             .await?;
 
+        manager
+            .drop_table(Table::drop().table(StrikeTeams::Table).to_owned())
+            .await?;
+
         Ok(())
     }
 }
@@ -0,0 +1,66 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230714_105755_create_users::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Reports::Table)
+                    .if_not_exists()
+                    // Unique ID for the report
+                    .col(
+                        ColumnDef::new(Reports::Id)
+                            .unsigned()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    // ID of the user that submitted the report
+                    .col(ColumnDef::new(Reports::UserId).unsigned().not_null())
+                    // Category of the report
+                    .col(ColumnDef::new(Reports::Category).unsigned().not_null())
+                    // Description provided by the user
+                    .col(ColumnDef::new(Reports::Description).string().not_null())
+                    // ID of the game the report is about
+                    .col(ColumnDef::new(Reports::GameId).unsigned().null())
+                    // Context captured when the report was created
+                    .col(ColumnDef::new(Reports::Context).json().not_null())
+                    // When the report was created
+                    .col(ColumnDef::new(Reports::Created).date_time().not_null())
+                    // Foreign key linking for the User ID
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Reports::Table, Reports::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Reports::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Reports {
+    Table,
+    Id,
+    UserId,
+    Category,
+    Description,
+    GameId,
+    Context,
+    Created,
+}
@@ -0,0 +1,86 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230714_105755_create_users::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(LoginEvents::Table)
+                    .if_not_exists()
+                    // Unique ID for the login event
+                    .col(
+                        ColumnDef::new(LoginEvents::Id)
+                            .unsigned()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    // ID of the user that logged in
+                    .col(ColumnDef::new(LoginEvents::UserId).unsigned().not_null())
+                    // IP address the login came from
+                    .col(ColumnDef::new(LoginEvents::Ip).string().not_null())
+                    // Version of the client used
+                    .col(ColumnDef::new(LoginEvents::ClientVersion).string().null())
+                    // Platform of the client
+                    .col(ColumnDef::new(LoginEvents::Platform).string().null())
+                    // When the login occurred
+                    .col(ColumnDef::new(LoginEvents::Created).date_time().not_null())
+                    // Foreign key linking for the User ID
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(LoginEvents::Table, LoginEvents::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create an index accross the user ID
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-login-event-uid")
+                    .table(LoginEvents::Table)
+                    .col(LoginEvents::UserId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Drop the index
+        manager
+            .drop_index(
+                Index::drop()
+                    .table(LoginEvents::Table)
+                    .name("idx-login-event-uid")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(LoginEvents::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum LoginEvents {
+    Table,
+    Id,
+    UserId,
+    Ip,
+    ClientVersion,
+    Platform,
+    Created,
+}
@@ -0,0 +1,82 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230714_105755_create_users::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserIdentities::Table)
+                    .if_not_exists()
+                    // Unique ID for the identity
+                    .col(
+                        ColumnDef::new(UserIdentities::Id)
+                            .unsigned()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    // ID of the user the identity is linked to
+                    .col(ColumnDef::new(UserIdentities::UserId).unsigned().not_null())
+                    // Name of the provider the identity is from
+                    .col(ColumnDef::new(UserIdentities::Provider).string().not_null())
+                    // Unique identifier within the provider
+                    .col(ColumnDef::new(UserIdentities::Subject).string().not_null())
+                    // Foreign key linking for the User ID
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(UserIdentities::Table, UserIdentities::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create a unique index accross the provider and subject
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-user-identity-provider-subject")
+                    .table(UserIdentities::Table)
+                    .col(UserIdentities::Provider)
+                    .col(UserIdentities::Subject)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Drop the index
+        manager
+            .drop_index(
+                Index::drop()
+                    .table(UserIdentities::Table)
+                    .name("idx-user-identity-provider-subject")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(UserIdentities::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum UserIdentities {
+    Table,
+    Id,
+    UserId,
+    Provider,
+    Subject,
+}
@@ -0,0 +1,94 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230714_105755_create_users::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(CurrencyLedger::Table)
+                    .if_not_exists()
+                    // Unique ID for the ledger entry
+                    .col(
+                        ColumnDef::new(CurrencyLedger::Id)
+                            .unsigned()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    // ID of the user the entry is for
+                    .col(ColumnDef::new(CurrencyLedger::UserId).unsigned().not_null())
+                    // Type of currency that was changed
+                    .col(ColumnDef::new(CurrencyLedger::Ty).unsigned().not_null())
+                    // Amount the balance changed by
+                    .col(
+                        ColumnDef::new(CurrencyLedger::Amount)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    // Reason for the change
+                    .col(ColumnDef::new(CurrencyLedger::Reason).unsigned().not_null())
+                    // When the change occurred
+                    .col(
+                        ColumnDef::new(CurrencyLedger::Created)
+                            .date_time()
+                            .not_null(),
+                    )
+                    // Foreign key linking for the User ID
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(CurrencyLedger::Table, CurrencyLedger::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create an index accross the user ID
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-currency-ledger-uid")
+                    .table(CurrencyLedger::Table)
+                    .col(CurrencyLedger::UserId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Drop the index
+        manager
+            .drop_index(
+                Index::drop()
+                    .table(CurrencyLedger::Table)
+                    .name("idx-currency-ledger-uid")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(CurrencyLedger::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum CurrencyLedger {
+    Table,
+    Id,
+    UserId,
+    Ty,
+    Amount,
+    Reason,
+    Created,
+}
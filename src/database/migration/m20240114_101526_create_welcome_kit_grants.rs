@@ -0,0 +1,54 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230714_105755_create_users::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(WelcomeKitGrants::Table)
+                    .if_not_exists()
+                    // ID of the user the kit was granted to
+                    .col(
+                        ColumnDef::new(WelcomeKitGrants::UserId)
+                            .unsigned()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    // When the kit was granted
+                    .col(
+                        ColumnDef::new(WelcomeKitGrants::Created)
+                            .date_time()
+                            .not_null(),
+                    )
+                    // Foreign key linking for the User ID
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(WelcomeKitGrants::Table, WelcomeKitGrants::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(WelcomeKitGrants::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum WelcomeKitGrants {
+    Table,
+    UserId,
+    Created,
+}
@@ -0,0 +1,290 @@
+//! Rebuilds the strike team mission progress table so that strike teams can
+//! be deployed on more than one mission over their lifetime (the strike team
+//! column was previously unique) and adds the deployment times.
+//!
+//! SQLite is unable to drop constraints so the table is recreated and the
+//! existing progress is copied across. Only SQLite databases can have progress
+//! from before this migration so other databases recreate the empty table
+
+use sea_orm_migration::{prelude::*, sea_orm::DbBackend};
+
+use super::{
+    m20230714_105755_create_users::Users, m20230731_123814_create_strike_teams::StrikeTeams,
+    m20231223_184934_create_strike_team_missions::StrikeTeamMissions,
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Name the existing table is moved to while the new table is created
+const OLD_TABLE: &str = "strike_team_mission_progress_old";
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let copy_existing = manager.get_database_backend() == DbBackend::Sqlite;
+        move_existing(manager, copy_existing).await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(StrikeTeamMissionProgress::Table)
+                    .if_not_exists()
+                    // This table uses a composite key over the UserId and MissionId
+                    .primary_key(
+                        Index::create()
+                            .col(StrikeTeamMissionProgress::UserId)
+                            .col(StrikeTeamMissionProgress::MissionId),
+                    )
+                    .col(
+                        ColumnDef::new(StrikeTeamMissionProgress::UserId)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    // Strike team on the mission, cleared if the team is retired
+                    .col(
+                        ColumnDef::new(StrikeTeamMissionProgress::StrikeTeamId)
+                            .unsigned()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(StrikeTeamMissionProgress::MissionId)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(StrikeTeamMissionProgress::UserMissionState)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(StrikeTeamMissionProgress::Seen)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(
+                        ColumnDef::new(StrikeTeamMissionProgress::Completed)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    // When the strike team was deployed
+                    .col(
+                        ColumnDef::new(StrikeTeamMissionProgress::DeployedAt)
+                            .date_time()
+                            .null(),
+                    )
+                    // When the strike team will return from the mission
+                    .col(
+                        ColumnDef::new(StrikeTeamMissionProgress::FinishTime)
+                            .date_time()
+                            .null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(
+                                StrikeTeamMissionProgress::Table,
+                                StrikeTeamMissionProgress::UserId,
+                            )
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(
+                                StrikeTeamMissionProgress::Table,
+                                StrikeTeamMissionProgress::MissionId,
+                            )
+                            .to(StrikeTeamMissions::Table, StrikeTeamMissions::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(
+                                StrikeTeamMissionProgress::Table,
+                                StrikeTeamMissionProgress::StrikeTeamId,
+                            )
+                            .to(StrikeTeams::Table, StrikeTeams::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        if copy_existing {
+            manager
+                .get_connection()
+                .execute_unprepared(&format!(
+                    "INSERT INTO `strike_team_mission_progress` \
+                    (`user_id`, `mission_id`, `strike_team_id`, `user_mission_state`, `seen`, `completed`) \
+                    SELECT `user_id`, `mission_id`, `strike_team_id`, `user_mission_state`, `seen`, `completed` \
+                    FROM `{OLD_TABLE}`"
+                ))
+                .await?;
+
+            manager
+                .drop_table(Table::drop().table(Alias::new(OLD_TABLE)).to_owned())
+                .await?;
+        }
+
+        // Create an index accross the strike team ID
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-strike-team-mission-progress-tid")
+                    .table(StrikeTeamMissionProgress::Table)
+                    .col(StrikeTeamMissionProgress::StrikeTeamId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Drop the index
+        manager
+            .drop_index(
+                Index::drop()
+                    .table(StrikeTeamMissionProgress::Table)
+                    .name("idx-strike-team-mission-progress-tid")
+                    .to_owned(),
+            )
+            .await?;
+
+        let copy_existing = manager.get_database_backend() == DbBackend::Sqlite;
+        move_existing(manager, copy_existing).await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(StrikeTeamMissionProgress::Table)
+                    .if_not_exists()
+                    .primary_key(
+                        Index::create()
+                            .col(StrikeTeamMissionProgress::UserId)
+                            .col(StrikeTeamMissionProgress::MissionId),
+                    )
+                    .col(
+                        ColumnDef::new(StrikeTeamMissionProgress::UserId)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(StrikeTeamMissionProgress::StrikeTeamId)
+                            .unsigned()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(StrikeTeamMissionProgress::MissionId)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(StrikeTeamMissionProgress::UserMissionState)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(StrikeTeamMissionProgress::Seen)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(
+                        ColumnDef::new(StrikeTeamMissionProgress::Completed)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(
+                                StrikeTeamMissionProgress::Table,
+                                StrikeTeamMissionProgress::UserId,
+                            )
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(
+                                StrikeTeamMissionProgress::Table,
+                                StrikeTeamMissionProgress::MissionId,
+                            )
+                            .to(StrikeTeamMissions::Table, StrikeTeamMissions::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(
+                                StrikeTeamMissionProgress::Table,
+                                StrikeTeamMissionProgress::StrikeTeamId,
+                            )
+                            .to(StrikeTeams::Table, StrikeTeams::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Other databases drop the progress as the old table can't be
+        // created alongside the existing constraints
+        if !copy_existing {
+            return Ok(());
+        }
+
+        // Progress without a strike team or for teams on multiple
+        // missions can't be represented by the old table
+        manager
+            .get_connection()
+            .execute_unprepared(&format!(
+                "INSERT OR IGNORE INTO `strike_team_mission_progress` \
+                (`user_id`, `mission_id`, `strike_team_id`, `user_mission_state`, `seen`, `completed`) \
+                SELECT `user_id`, `mission_id`, `strike_team_id`, `user_mission_state`, `seen`, `completed` \
+                FROM `{OLD_TABLE}` WHERE `strike_team_id` IS NOT NULL"
+            ))
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Alias::new(OLD_TABLE)).to_owned())
+            .await
+    }
+}
+
+/// Moves the existing table out of the way of the new table, the table is
+/// renamed when its progress is being copied otherwise its dropped
+async fn move_existing(manager: &SchemaManager<'_>, copy_existing: bool) -> Result<(), DbErr> {
+    if copy_existing {
+        manager
+            .rename_table(
+                Table::rename()
+                    .table(StrikeTeamMissionProgress::Table, Alias::new(OLD_TABLE))
+                    .to_owned(),
+            )
+            .await
+    } else {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(StrikeTeamMissionProgress::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum StrikeTeamMissionProgress {
+    Table,
+    MissionId,
+    UserId,
+    StrikeTeamId,
+    UserMissionState,
+    Seen,
+    Completed,
+    DeployedAt,
+    FinishTime,
+}
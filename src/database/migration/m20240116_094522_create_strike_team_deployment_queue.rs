@@ -0,0 +1,114 @@
+use sea_orm_migration::prelude::*;
+
+use super::{
+    m20230714_105755_create_users::Users,
+    m20231223_184934_create_strike_team_missions::StrikeTeamMissions,
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(StrikeTeamDeploymentQueue::Table)
+                    .if_not_exists()
+                    // Unique ID for the queue entry
+                    .col(
+                        ColumnDef::new(StrikeTeamDeploymentQueue::Id)
+                            .unsigned()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    // ID of the user that queued the mission
+                    .col(
+                        ColumnDef::new(StrikeTeamDeploymentQueue::UserId)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    // ID of the queued mission
+                    .col(
+                        ColumnDef::new(StrikeTeamDeploymentQueue::MissionId)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    // When the mission was queued
+                    .col(
+                        ColumnDef::new(StrikeTeamDeploymentQueue::Created)
+                            .date_time()
+                            .not_null(),
+                    )
+                    // Foreign key linking for the User ID
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(
+                                StrikeTeamDeploymentQueue::Table,
+                                StrikeTeamDeploymentQueue::UserId,
+                            )
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    // Foreign key linking for the Mission ID
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(
+                                StrikeTeamDeploymentQueue::Table,
+                                StrikeTeamDeploymentQueue::MissionId,
+                            )
+                            .to(StrikeTeamMissions::Table, StrikeTeamMissions::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Missions can only be queued once per user
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-strike-team-deployment-queue-uid-mid")
+                    .table(StrikeTeamDeploymentQueue::Table)
+                    .col(StrikeTeamDeploymentQueue::UserId)
+                    .col(StrikeTeamDeploymentQueue::MissionId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Drop the index
+        manager
+            .drop_index(
+                Index::drop()
+                    .table(StrikeTeamDeploymentQueue::Table)
+                    .name("idx-strike-team-deployment-queue-uid-mid")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(StrikeTeamDeploymentQueue::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum StrikeTeamDeploymentQueue {
+    Table,
+    Id,
+    UserId,
+    MissionId,
+    Created,
+}
@@ -0,0 +1,77 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(MatchRecords::Table)
+                    .if_not_exists()
+                    // Unique ID for the match record
+                    .col(
+                        ColumnDef::new(MatchRecords::Id)
+                            .unsigned()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    // Map the match was played on
+                    .col(ColumnDef::new(MatchRecords::Map).string().not_null())
+                    // Enemy type the match was played against
+                    .col(ColumnDef::new(MatchRecords::EnemyType).string().not_null())
+                    // Difficulty of the match
+                    .col(ColumnDef::new(MatchRecords::Difficulty).string().not_null())
+                    // Number of players in the match
+                    .col(ColumnDef::new(MatchRecords::Players).unsigned().not_null())
+                    // When the match finished
+                    .col(ColumnDef::new(MatchRecords::Created).date_time().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create an index accross the created time
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-match-records-created")
+                    .table(MatchRecords::Table)
+                    .col(MatchRecords::Created)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Drop the index
+        manager
+            .drop_index(
+                Index::drop()
+                    .table(MatchRecords::Table)
+                    .name("idx-match-records-created")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(MatchRecords::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum MatchRecords {
+    Table,
+    Id,
+    Map,
+    EnemyType,
+    Difficulty,
+    Players,
+    Created,
+}
@@ -0,0 +1,121 @@
+use sea_orm_migration::prelude::*;
+
+use super::{
+    m20230714_105755_create_users::Users, m20230714_105933_create_inventory_item::InventoryItems,
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(StorePurchases::Table)
+                    .if_not_exists()
+                    // Unique ID for the purchase
+                    .col(
+                        ColumnDef::new(StorePurchases::Id)
+                            .unsigned()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    // ID of the user that made the purchase
+                    .col(ColumnDef::new(StorePurchases::UserId).unsigned().not_null())
+                    // Name of the purchased article
+                    .col(
+                        ColumnDef::new(StorePurchases::ArticleName)
+                            .uuid()
+                            .not_null(),
+                    )
+                    // Currency the purchase was paid with
+                    .col(
+                        ColumnDef::new(StorePurchases::Currency)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    // Amount of currency paid
+                    .col(ColumnDef::new(StorePurchases::Price).unsigned().not_null())
+                    // ID of the item stack the purchased item was granted to,
+                    // cleared if the stack is removed
+                    .col(ColumnDef::new(StorePurchases::ItemId).unsigned().null())
+                    // Amount of the item that was granted
+                    .col(
+                        ColumnDef::new(StorePurchases::StackSize)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    // When the purchase was made
+                    .col(
+                        ColumnDef::new(StorePurchases::Created)
+                            .date_time()
+                            .not_null(),
+                    )
+                    // When the purchase was refunded
+                    .col(ColumnDef::new(StorePurchases::Refunded).date_time().null())
+                    // Foreign key linking for the User ID
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(StorePurchases::Table, StorePurchases::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    // Foreign key linking for the Item ID
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(StorePurchases::Table, StorePurchases::ItemId)
+                            .to(InventoryItems::Table, InventoryItems::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create an index accross the user ID
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-store-purchases-uid")
+                    .table(StorePurchases::Table)
+                    .col(StorePurchases::UserId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Drop the index
+        manager
+            .drop_index(
+                Index::drop()
+                    .table(StorePurchases::Table)
+                    .name("idx-store-purchases-uid")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(StorePurchases::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum StorePurchases {
+    Table,
+    Id,
+    UserId,
+    ArticleName,
+    Currency,
+    Price,
+    ItemId,
+    StackSize,
+    Created,
+    Refunded,
+}
@@ -0,0 +1,142 @@
+use sea_orm_migration::prelude::*;
+
+use super::{
+    m20230714_105755_create_users::Users, m20230731_123814_create_strike_teams::StrikeTeams,
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(StrikeTeamFlair::Table)
+                    .if_not_exists()
+                    // Unique ID for the unlocked flair
+                    .col(
+                        ColumnDef::new(StrikeTeamFlair::Id)
+                            .unsigned()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    // ID of the user that unlocked the flair
+                    .col(
+                        ColumnDef::new(StrikeTeamFlair::UserId)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    // Name of the unlocked flair
+                    .col(ColumnDef::new(StrikeTeamFlair::Name).string().not_null())
+                    // When the flair was unlocked
+                    .col(
+                        ColumnDef::new(StrikeTeamFlair::Unlocked)
+                            .date_time()
+                            .not_null(),
+                    )
+                    // Foreign key linking for the User ID
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(StrikeTeamFlair::Table, StrikeTeamFlair::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Each flair can only be unlocked once per user
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-strike-team-flair-uid-name")
+                    .table(StrikeTeamFlair::Table)
+                    .col(StrikeTeamFlair::UserId)
+                    .col(StrikeTeamFlair::Name)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Flair equipped on the strike team
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(StrikeTeams::Table)
+                    .add_column(
+                        ColumnDef::new(StrikeTeamFlairColumns::IconFlair)
+                            .string()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(StrikeTeams::Table)
+                    .add_column(
+                        ColumnDef::new(StrikeTeamFlairColumns::NameColorFlair)
+                            .string()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(StrikeTeams::Table)
+                    .drop_column(StrikeTeamFlairColumns::NameColorFlair)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(StrikeTeams::Table)
+                    .drop_column(StrikeTeamFlairColumns::IconFlair)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Drop the index
+        manager
+            .drop_index(
+                Index::drop()
+                    .table(StrikeTeamFlair::Table)
+                    .name("idx-strike-team-flair-uid-name")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(StrikeTeamFlair::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum StrikeTeamFlair {
+    Table,
+    Id,
+    UserId,
+    Name,
+    Unlocked,
+}
+
+/// Flair columns added to the strike teams table
+#[derive(Iden)]
+enum StrikeTeamFlairColumns {
+    IconFlair,
+    NameColorFlair,
+}
@@ -0,0 +1,42 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230731_123814_create_strike_teams::StrikeTeams;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(StrikeTeams::Table)
+                    // Number of consecutive successful missions
+                    .add_column(
+                        ColumnDef::new(SuccessStreak::SuccessStreak)
+                            .unsigned()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(StrikeTeams::Table)
+                    .drop_column(SuccessStreak::SuccessStreak)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum SuccessStreak {
+    SuccessStreak,
+}
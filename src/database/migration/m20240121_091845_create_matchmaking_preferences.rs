@@ -0,0 +1,67 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230714_105755_create_users::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(MatchmakingPreferences::Table)
+                    .if_not_exists()
+                    // ID of the user the preferences are for
+                    .col(
+                        ColumnDef::new(MatchmakingPreferences::UserId)
+                            .unsigned()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    // Maximum latency to the game host
+                    .col(
+                        ColumnDef::new(MatchmakingPreferences::MaxLatency)
+                            .unsigned()
+                            .null(),
+                    )
+                    // Preferred ping site for the game host
+                    .col(
+                        ColumnDef::new(MatchmakingPreferences::PreferredRegion)
+                            .string()
+                            .null(),
+                    )
+                    // Foreign key linking for the User ID
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(
+                                MatchmakingPreferences::Table,
+                                MatchmakingPreferences::UserId,
+                            )
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(MatchmakingPreferences::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum MatchmakingPreferences {
+    Table,
+    UserId,
+    MaxLatency,
+    PreferredRegion,
+}
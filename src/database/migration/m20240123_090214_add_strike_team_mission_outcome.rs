@@ -0,0 +1,62 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite only supports a single column change per alter statement
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(StrikeTeamMissionProgress::Table)
+                    // Whether the mission was successful, set when the team returns
+                    .add_column(ColumnDef::new(StrikeTeamMissionProgress::Successful).boolean())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(StrikeTeamMissionProgress::Table)
+                    // Whether the team gains a negative trait from the mission
+                    .add_column(
+                        ColumnDef::new(StrikeTeamMissionProgress::EarnNegativeTrait)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(StrikeTeamMissionProgress::Table)
+                    .drop_column(StrikeTeamMissionProgress::EarnNegativeTrait)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(StrikeTeamMissionProgress::Table)
+                    .drop_column(StrikeTeamMissionProgress::Successful)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum StrikeTeamMissionProgress {
+    Table,
+    Successful,
+    EarnNegativeTrait,
+}
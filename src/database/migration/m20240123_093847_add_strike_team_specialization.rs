@@ -0,0 +1,37 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230731_123814_create_strike_teams::StrikeTeams;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(StrikeTeams::Table)
+                    // Name of the specialization chosen for the team
+                    .add_column(ColumnDef::new(Specialization::Specialization).string())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(StrikeTeams::Table)
+                    .drop_column(Specialization::Specialization)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Specialization {
+    Specialization,
+}
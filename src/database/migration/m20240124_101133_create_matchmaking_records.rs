@@ -0,0 +1,89 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(MatchmakingRecords::Table)
+                    .if_not_exists()
+                    // Unique ID for the matchmaking record
+                    .col(
+                        ColumnDef::new(MatchmakingRecords::Id)
+                            .unsigned()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    // Milliseconds the player spent in the queue
+                    .col(
+                        ColumnDef::new(MatchmakingRecords::QueueTimeMs)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    // Criteria relaxation stage the player was matched at
+                    .col(
+                        ColumnDef::new(MatchmakingRecords::RelaxationStage)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    // Number of players in the game after the player joined
+                    .col(
+                        ColumnDef::new(MatchmakingRecords::GameSize)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    // When the player was matched
+                    .col(
+                        ColumnDef::new(MatchmakingRecords::Created)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create an index accross the created time
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-matchmaking-records-created")
+                    .table(MatchmakingRecords::Table)
+                    .col(MatchmakingRecords::Created)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Drop the index
+        manager
+            .drop_index(
+                Index::drop()
+                    .table(MatchmakingRecords::Table)
+                    .name("idx-matchmaking-records-created")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(MatchmakingRecords::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum MatchmakingRecords {
+    Table,
+    Id,
+    QueueTimeMs,
+    RelaxationStage,
+    GameSize,
+    Created,
+}
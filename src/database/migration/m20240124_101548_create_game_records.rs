@@ -0,0 +1,84 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(GameRecords::Table)
+                    .if_not_exists()
+                    // Unique ID for the game record
+                    .col(
+                        ColumnDef::new(GameRecords::Id)
+                            .unsigned()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    // Difficulty of the last mission played
+                    .col(ColumnDef::new(GameRecords::Difficulty).string())
+                    // Seconds the game existed for
+                    .col(
+                        ColumnDef::new(GameRecords::DurationSecs)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    // Whether the last mission played was fully completed
+                    .col(ColumnDef::new(GameRecords::Completed).boolean().not_null())
+                    // Most players that were in the game at once
+                    .col(
+                        ColumnDef::new(GameRecords::PeakPlayers)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    // When the game stopped
+                    .col(ColumnDef::new(GameRecords::Created).date_time().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create an index accross the created time
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-game-records-created")
+                    .table(GameRecords::Table)
+                    .col(GameRecords::Created)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Drop the index
+        manager
+            .drop_index(
+                Index::drop()
+                    .table(GameRecords::Table)
+                    .name("idx-game-records-created")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(GameRecords::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum GameRecords {
+    Table,
+    Id,
+    Difficulty,
+    DurationSecs,
+    Completed,
+    PeakPlayers,
+    Created,
+}
@@ -0,0 +1,159 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230714_105755_create_users::Users;
+use crate::database::sql;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserPermissions::Table)
+                    .if_not_exists()
+                    // Unique ID for the granted permission
+                    .col(
+                        ColumnDef::new(UserPermissions::Id)
+                            .unsigned()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    // ID of the user the permission was granted to
+                    .col(
+                        ColumnDef::new(UserPermissions::UserId)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    // The granted permission
+                    .col(
+                        ColumnDef::new(UserPermissions::Permission)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    // Foreign key linking for the User ID
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(UserPermissions::Table, UserPermissions::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Each permission can only be granted once per user
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-user-permissions-uid-permission")
+                    .table(UserPermissions::Table)
+                    .col(UserPermissions::UserId)
+                    .col(UserPermissions::Permission)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Existing administrators keep access to every admin endpoint
+        manager
+            .get_connection()
+            .execute_unprepared(&sql::quoted(
+                manager.get_database_backend(),
+                "INSERT INTO `user_permissions` (`user_id`, `permission`) \
+                SELECT `users`.`id`, `permissions`.`value` FROM `users` \
+                CROSS JOIN (SELECT 0 AS `value` UNION SELECT 1 UNION SELECT 2) AS `permissions` \
+                WHERE `users`.`role` = 1",
+            ))
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(PermissionAuditLog::Table)
+                    .if_not_exists()
+                    // Unique ID for the audit log entry
+                    .col(
+                        ColumnDef::new(PermissionAuditLog::Id)
+                            .unsigned()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    // ID of the administrator that made the change
+                    .col(
+                        ColumnDef::new(PermissionAuditLog::ActorId)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    // ID of the user whose permissions were changed
+                    .col(
+                        ColumnDef::new(PermissionAuditLog::TargetId)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    // The changed permission
+                    .col(
+                        ColumnDef::new(PermissionAuditLog::Permission)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    // Whether the permission was granted or revoked
+                    .col(
+                        ColumnDef::new(PermissionAuditLog::Granted)
+                            .boolean()
+                            .not_null(),
+                    )
+                    // When the change was made
+                    .col(
+                        ColumnDef::new(PermissionAuditLog::Created)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PermissionAuditLog::Table).to_owned())
+            .await?;
+
+        // Drop the index
+        manager
+            .drop_index(
+                Index::drop()
+                    .table(UserPermissions::Table)
+                    .name("idx-user-permissions-uid-permission")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(UserPermissions::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum UserPermissions {
+    Table,
+    Id,
+    UserId,
+    Permission,
+}
+
+#[derive(Iden)]
+enum PermissionAuditLog {
+    Table,
+    Id,
+    ActorId,
+    TargetId,
+    Permission,
+    Granted,
+    Created,
+}
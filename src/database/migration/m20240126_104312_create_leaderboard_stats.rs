@@ -0,0 +1,114 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230714_105755_create_users::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(LeaderboardStats::Table)
+                    .if_not_exists()
+                    // ID of the user the stats are for
+                    .col(
+                        ColumnDef::new(LeaderboardStats::UserId)
+                            .unsigned()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    // Total level of the user characters
+                    .col(
+                        ColumnDef::new(LeaderboardStats::N7Rating)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    // APEX (pathfinder) rating
+                    .col(
+                        ColumnDef::new(LeaderboardStats::ApexRating)
+                            .float()
+                            .not_null(),
+                    )
+                    // Points from completed challenges
+                    .col(
+                        ColumnDef::new(LeaderboardStats::ChallengePoints)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    // Number of missions completed
+                    .col(
+                        ColumnDef::new(LeaderboardStats::MatchCount)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    // When the stats were last updated
+                    .col(
+                        ColumnDef::new(LeaderboardStats::Updated)
+                            .date_time()
+                            .not_null(),
+                    )
+                    // Foreign key linking for the User ID
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(LeaderboardStats::Table, LeaderboardStats::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create indexes across each of the ranked stats
+        for (name, column) in [
+            (
+                "idx-leaderboard-stats-n7-rating",
+                LeaderboardStats::N7Rating,
+            ),
+            (
+                "idx-leaderboard-stats-apex-rating",
+                LeaderboardStats::ApexRating,
+            ),
+            (
+                "idx-leaderboard-stats-challenge-points",
+                LeaderboardStats::ChallengePoints,
+            ),
+            (
+                "idx-leaderboard-stats-match-count",
+                LeaderboardStats::MatchCount,
+            ),
+        ] {
+            manager
+                .create_index(
+                    Index::create()
+                        .name(name)
+                        .table(LeaderboardStats::Table)
+                        .col(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(LeaderboardStats::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum LeaderboardStats {
+    Table,
+    UserId,
+    #[iden = "n7_rating"]
+    N7Rating,
+    ApexRating,
+    ChallengePoints,
+    MatchCount,
+    Updated,
+}
@@ -0,0 +1,52 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230714_105755_create_users::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(XpBoosts::Table)
+                    .if_not_exists()
+                    // ID of the user the boost is for
+                    .col(
+                        ColumnDef::new(XpBoosts::UserId)
+                            .unsigned()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    // Percentage of bonus XP
+                    .col(ColumnDef::new(XpBoosts::Percent).unsigned().not_null())
+                    // When the boost was activated
+                    .col(ColumnDef::new(XpBoosts::Activated).date_time().not_null())
+                    // Foreign key linking for the User ID
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(XpBoosts::Table, XpBoosts::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(XpBoosts::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum XpBoosts {
+    Table,
+    UserId,
+    Percent,
+    Activated,
+}
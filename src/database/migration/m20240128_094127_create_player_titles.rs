@@ -0,0 +1,109 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230714_105755_create_users::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PlayerTitles::Table)
+                    .if_not_exists()
+                    // Unique ID for the earned title
+                    .col(
+                        ColumnDef::new(PlayerTitles::Id)
+                            .unsigned()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    // ID of the user that earned the title
+                    .col(ColumnDef::new(PlayerTitles::UserId).unsigned().not_null())
+                    // Name of the earned title
+                    .col(ColumnDef::new(PlayerTitles::Name).string().not_null())
+                    // When the title was earned
+                    .col(
+                        ColumnDef::new(PlayerTitles::Unlocked)
+                            .date_time()
+                            .not_null(),
+                    )
+                    // Foreign key linking for the User ID
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(PlayerTitles::Table, PlayerTitles::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Each title can only be earned once per user
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-player-titles-uid-name")
+                    .table(PlayerTitles::Table)
+                    .col(PlayerTitles::UserId)
+                    .col(PlayerTitles::Name)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Title equipped by the user
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .add_column(ColumnDef::new(PlayerTitleColumns::Title).string().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .drop_column(PlayerTitleColumns::Title)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Drop the index
+        manager
+            .drop_index(
+                Index::drop()
+                    .table(PlayerTitles::Table)
+                    .name("idx-player-titles-uid-name")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(PlayerTitles::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum PlayerTitles {
+    Table,
+    Id,
+    UserId,
+    Name,
+    Unlocked,
+}
+
+/// Title column added to the users table
+#[derive(Iden)]
+enum PlayerTitleColumns {
+    Title,
+}
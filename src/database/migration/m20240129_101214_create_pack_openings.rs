@@ -0,0 +1,100 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230714_105755_create_users::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PackOpenings::Table)
+                    .if_not_exists()
+                    // Unique ID for the opening
+                    .col(
+                        ColumnDef::new(PackOpenings::Id)
+                            .unsigned()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    // ID of the user that opened the pack
+                    .col(ColumnDef::new(PackOpenings::UserId).unsigned().not_null())
+                    // Name of the opened pack
+                    .col(ColumnDef::new(PackOpenings::PackName).uuid().not_null())
+                    // Name of the experiment the pack was covered by
+                    .col(ColumnDef::new(PackOpenings::Experiment).string().null())
+                    // Whether the user received the experiment weights
+                    .col(ColumnDef::new(PackOpenings::Treatment).boolean().not_null())
+                    // Number of items generated
+                    .col(ColumnDef::new(PackOpenings::Items).unsigned().not_null())
+                    // Number of items generated of each rarity
+                    .col(ColumnDef::new(PackOpenings::Common).unsigned().not_null())
+                    .col(ColumnDef::new(PackOpenings::Uncommon).unsigned().not_null())
+                    .col(ColumnDef::new(PackOpenings::Rare).unsigned().not_null())
+                    .col(
+                        ColumnDef::new(PackOpenings::UltraRare)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    // When the pack was opened
+                    .col(ColumnDef::new(PackOpenings::Created).date_time().not_null())
+                    // Foreign key linking for the User ID
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(PackOpenings::Table, PackOpenings::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create an index accross the experiment for reporting
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-pack-openings-experiment")
+                    .table(PackOpenings::Table)
+                    .col(PackOpenings::Experiment)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Drop the index
+        manager
+            .drop_index(
+                Index::drop()
+                    .table(PackOpenings::Table)
+                    .name("idx-pack-openings-experiment")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(PackOpenings::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum PackOpenings {
+    Table,
+    Id,
+    UserId,
+    PackName,
+    Experiment,
+    Treatment,
+    Items,
+    Common,
+    Uncommon,
+    Rare,
+    UltraRare,
+    Created,
+}
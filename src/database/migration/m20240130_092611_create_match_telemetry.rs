@@ -0,0 +1,107 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230714_105755_create_users::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(MatchTelemetry::Table)
+                    .if_not_exists()
+                    // Unique ID for the telemetry record
+                    .col(
+                        ColumnDef::new(MatchTelemetry::Id)
+                            .unsigned()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    // ID of the user the statistics are for
+                    .col(ColumnDef::new(MatchTelemetry::UserId).unsigned().not_null())
+                    // ID of the game the match was played in
+                    .col(ColumnDef::new(MatchTelemetry::GameId).unsigned().not_null())
+                    // Number of enemies killed
+                    .col(ColumnDef::new(MatchTelemetry::Kills).unsigned().not_null())
+                    // Number of times the user extracted
+                    .col(
+                        ColumnDef::new(MatchTelemetry::Extractions)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    // Highest wave reached
+                    .col(
+                        ColumnDef::new(MatchTelemetry::HighestWave)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    // When the first events for the match were received
+                    .col(
+                        ColumnDef::new(MatchTelemetry::Created)
+                            .date_time()
+                            .not_null(),
+                    )
+                    // When the last events for the match were received
+                    .col(
+                        ColumnDef::new(MatchTelemetry::Updated)
+                            .date_time()
+                            .not_null(),
+                    )
+                    // Foreign key linking for the User ID
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(MatchTelemetry::Table, MatchTelemetry::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create an index accross the user and game for finding the match
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-match-telemetry-uid-gid")
+                    .table(MatchTelemetry::Table)
+                    .col(MatchTelemetry::UserId)
+                    .col(MatchTelemetry::GameId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Drop the index
+        manager
+            .drop_index(
+                Index::drop()
+                    .table(MatchTelemetry::Table)
+                    .name("idx-match-telemetry-uid-gid")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(MatchTelemetry::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum MatchTelemetry {
+    Table,
+    Id,
+    UserId,
+    GameId,
+    Kills,
+    Extractions,
+    HighestWave,
+    Created,
+    Updated,
+}
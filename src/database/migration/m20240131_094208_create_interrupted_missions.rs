@@ -0,0 +1,88 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(InterruptedMissions::Table)
+                    .if_not_exists()
+                    // Unique ID for the interrupted mission
+                    .col(
+                        ColumnDef::new(InterruptedMissions::Id)
+                            .unsigned()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    // ID of the game the mission was played in
+                    .col(
+                        ColumnDef::new(InterruptedMissions::GameId)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    // ID of the user that started the mission
+                    .col(
+                        ColumnDef::new(InterruptedMissions::StartedBy)
+                            .unsigned()
+                            .null(),
+                    )
+                    // The APEX mission that was being played
+                    .col(
+                        ColumnDef::new(InterruptedMissions::Mission)
+                            .unsigned()
+                            .null(),
+                    )
+                    // When the mission was started
+                    .col(
+                        ColumnDef::new(InterruptedMissions::Started)
+                            .date_time()
+                            .null(),
+                    )
+                    // Submitted mission data that hadn't been processed
+                    .col(
+                        ColumnDef::new(InterruptedMissions::MissionData)
+                            .json()
+                            .not_null(),
+                    )
+                    // Progress already written during the mission
+                    .col(
+                        ColumnDef::new(InterruptedMissions::Applied)
+                            .json()
+                            .not_null(),
+                    )
+                    // When the mission was interrupted
+                    .col(
+                        ColumnDef::new(InterruptedMissions::Created)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(InterruptedMissions::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum InterruptedMissions {
+    Table,
+    Id,
+    GameId,
+    StartedBy,
+    Mission,
+    Started,
+    MissionData,
+    Applied,
+    Created,
+}
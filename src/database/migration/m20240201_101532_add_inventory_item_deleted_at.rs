@@ -0,0 +1,56 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(InventoryItems::Table)
+                    // When the item was deleted
+                    .add_column(ColumnDef::new(InventoryItems::DeletedAt).date_time().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Index for purging the old deleted items
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-inventory-items-deleted-at")
+                    .table(InventoryItems::Table)
+                    .col(InventoryItems::DeletedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .table(InventoryItems::Table)
+                    .name("idx-inventory-items-deleted-at")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(InventoryItems::Table)
+                    .drop_column(InventoryItems::DeletedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum InventoryItems {
+    Table,
+    DeletedAt,
+}
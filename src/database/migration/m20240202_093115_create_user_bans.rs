@@ -0,0 +1,59 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230714_105755_create_users::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserBans::Table)
+                    .if_not_exists()
+                    // ID of the banned user
+                    .col(
+                        ColumnDef::new(UserBans::UserId)
+                            .unsigned()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    // ID of the administrator that banned the user
+                    .col(ColumnDef::new(UserBans::BannedBy).unsigned().not_null())
+                    // Reason given for the ban
+                    .col(ColumnDef::new(UserBans::Reason).string().null())
+                    // When the user was banned
+                    .col(ColumnDef::new(UserBans::Created).date_time().not_null())
+                    // When the ban expires
+                    .col(ColumnDef::new(UserBans::Expires).date_time().null())
+                    // Foreign key linking for the User ID
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(UserBans::Table, UserBans::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(UserBans::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum UserBans {
+    Table,
+    UserId,
+    BannedBy,
+    Reason,
+    Created,
+    Expires,
+}
@@ -0,0 +1,68 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230714_105755_create_users::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Friends::Table)
+                    .if_not_exists()
+                    // This table uses a composite key over the UserId and FriendId
+                    .primary_key(Index::create().col(Friends::UserId).col(Friends::FriendId))
+                    // ID of the user that added the friend
+                    .col(ColumnDef::new(Friends::UserId).unsigned().not_null())
+                    // ID of the user that was added as a friend
+                    .col(ColumnDef::new(Friends::FriendId).unsigned().not_null())
+                    // When the friend was added
+                    .col(ColumnDef::new(Friends::Created).date_time().not_null())
+                    // Foreign key linking for the User ID
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Friends::Table, Friends::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    // Foreign key linking for the Friend ID
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Friends::Table, Friends::FriendId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Index for finding the users that added a friend
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-friends-friend-id")
+                    .table(Friends::Table)
+                    .col(Friends::FriendId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Friends::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Friends {
+    Table,
+    UserId,
+    FriendId,
+    Created,
+}
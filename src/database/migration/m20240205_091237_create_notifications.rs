@@ -0,0 +1,93 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230714_105755_create_users::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Notifications::Table)
+                    .if_not_exists()
+                    // Unique ID for the notification
+                    .col(
+                        ColumnDef::new(Notifications::Id)
+                            .unsigned()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    // ID of the user the notification is for
+                    .col(ColumnDef::new(Notifications::UserId).unsigned().not_null())
+                    // The kind of notification
+                    .col(ColumnDef::new(Notifications::Kind).unsigned().not_null())
+                    // The notification message text
+                    .col(ColumnDef::new(Notifications::Message).string().not_null())
+                    // Whether the user has read the notification
+                    .col(
+                        ColumnDef::new(Notifications::Read)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    // Whether the notification has been sent to the client
+                    .col(
+                        ColumnDef::new(Notifications::Delivered)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    // When the notification was created
+                    .col(
+                        ColumnDef::new(Notifications::Created)
+                            .date_time()
+                            .not_null(),
+                    )
+                    // Foreign key linking for the User ID
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Notifications::Table, Notifications::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create an index accross the user ID
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-notification-uid")
+                    .table(Notifications::Table)
+                    .col(Notifications::UserId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Notifications::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Notifications {
+    Table,
+    Id,
+    UserId,
+    Kind,
+    Message,
+    Read,
+    Delivered,
+    Created,
+}
@@ -0,0 +1,98 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230714_105755_create_users::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(RetiredStrikeTeams::Table)
+                    .if_not_exists()
+                    // Unique ID for the retired team
+                    .col(
+                        ColumnDef::new(RetiredStrikeTeams::Id)
+                            .unsigned()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    // ID of the user that owned the team
+                    .col(
+                        ColumnDef::new(RetiredStrikeTeams::UserId)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    // Name of the team
+                    .col(ColumnDef::new(RetiredStrikeTeams::Name).string().not_null())
+                    // Icon of the team
+                    .col(ColumnDef::new(RetiredStrikeTeams::Icon).json().not_null())
+                    // Level the team had reached
+                    .col(
+                        ColumnDef::new(RetiredStrikeTeams::Level)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    // Specialization the team had chosen
+                    .col(ColumnDef::new(RetiredStrikeTeams::Specialization).string())
+                    // Currencies granted for retiring the team
+                    .col(
+                        ColumnDef::new(RetiredStrikeTeams::Rewards)
+                            .json()
+                            .not_null(),
+                    )
+                    // When the team was retired
+                    .col(
+                        ColumnDef::new(RetiredStrikeTeams::Retired)
+                            .date_time()
+                            .not_null(),
+                    )
+                    // Foreign key linking for the User ID
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(RetiredStrikeTeams::Table, RetiredStrikeTeams::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create an index accross the user ID
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-retired-strike-team-uid")
+                    .table(RetiredStrikeTeams::Table)
+                    .col(RetiredStrikeTeams::UserId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RetiredStrikeTeams::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum RetiredStrikeTeams {
+    Table,
+    Id,
+    UserId,
+    Name,
+    Icon,
+    Level,
+    Specialization,
+    Rewards,
+    Retired,
+}
@@ -0,0 +1,131 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230731_123814_create_strike_teams::StrikeTeams;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(StrikeTeamMissionHistory::Table)
+                    .if_not_exists()
+                    // Unique ID for the history entry
+                    .col(
+                        ColumnDef::new(StrikeTeamMissionHistory::Id)
+                            .unsigned()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    // ID of the team that completed the mission
+                    .col(
+                        ColumnDef::new(StrikeTeamMissionHistory::StrikeTeamId)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    // ID of the completed mission
+                    .col(
+                        ColumnDef::new(StrikeTeamMissionHistory::MissionId)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    // The mission descriptor details
+                    .col(
+                        ColumnDef::new(StrikeTeamMissionHistory::Descriptor)
+                            .json()
+                            .not_null(),
+                    )
+                    // Difficulty of the mission
+                    .col(
+                        ColumnDef::new(StrikeTeamMissionHistory::Difficulty)
+                            .json()
+                            .not_null(),
+                    )
+                    // Whether the mission was successful
+                    .col(
+                        ColumnDef::new(StrikeTeamMissionHistory::Successful)
+                            .boolean()
+                            .not_null(),
+                    )
+                    // Currencies and items granted for the mission
+                    .col(
+                        ColumnDef::new(StrikeTeamMissionHistory::Rewards)
+                            .json()
+                            .not_null(),
+                    )
+                    // XP the team gained from the mission
+                    .col(
+                        ColumnDef::new(StrikeTeamMissionHistory::XpGained)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    // Traits the team gained and lost from the mission
+                    .col(
+                        ColumnDef::new(StrikeTeamMissionHistory::Traits)
+                            .json()
+                            .not_null(),
+                    )
+                    // When the mission was resolved
+                    .col(
+                        ColumnDef::new(StrikeTeamMissionHistory::Completed)
+                            .date_time()
+                            .not_null(),
+                    )
+                    // Foreign key linking for the strike team ID
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(
+                                StrikeTeamMissionHistory::Table,
+                                StrikeTeamMissionHistory::StrikeTeamId,
+                            )
+                            .to(StrikeTeams::Table, StrikeTeams::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create an index accross the strike team ID
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-strike-team-mission-history-tid")
+                    .table(StrikeTeamMissionHistory::Table)
+                    .col(StrikeTeamMissionHistory::StrikeTeamId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(StrikeTeamMissionHistory::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum StrikeTeamMissionHistory {
+    Table,
+    Id,
+    StrikeTeamId,
+    MissionId,
+    Descriptor,
+    Difficulty,
+    Successful,
+    Rewards,
+    XpGained,
+    Traits,
+    Completed,
+}
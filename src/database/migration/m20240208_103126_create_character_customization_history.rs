@@ -0,0 +1,89 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230714_105946_create_characters::Characters;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(CharacterCustomizationHistory::Table)
+                    .if_not_exists()
+                    // Unique ID for the stored customization
+                    .col(
+                        ColumnDef::new(CharacterCustomizationHistory::Id)
+                            .unsigned()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    // ID of the character the customization was for
+                    .col(
+                        ColumnDef::new(CharacterCustomizationHistory::CharacterId)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    // The previous customization data (Map)
+                    .col(
+                        ColumnDef::new(CharacterCustomizationHistory::Customization)
+                            .json()
+                            .not_null(),
+                    )
+                    // When the customization was replaced
+                    .col(
+                        ColumnDef::new(CharacterCustomizationHistory::Created)
+                            .date_time()
+                            .not_null(),
+                    )
+                    // Foreign key linking for the character ID
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(
+                                CharacterCustomizationHistory::Table,
+                                CharacterCustomizationHistory::CharacterId,
+                            )
+                            .to(Characters::Table, Characters::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create an index accross the character ID
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-character-customization-history-cid")
+                    .table(CharacterCustomizationHistory::Table)
+                    .col(CharacterCustomizationHistory::CharacterId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(CharacterCustomizationHistory::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum CharacterCustomizationHistory {
+    Table,
+    Id,
+    CharacterId,
+    Customization,
+    Created,
+}
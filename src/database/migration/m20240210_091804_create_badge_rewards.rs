@@ -0,0 +1,87 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20230714_105755_create_users::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(BadgeRewards::Table)
+                    .if_not_exists()
+                    // Unique ID for the rewarded badge
+                    .col(
+                        ColumnDef::new(BadgeRewards::Id)
+                            .unsigned()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    // ID of the user the badge was rewarded to
+                    .col(ColumnDef::new(BadgeRewards::UserId).unsigned().not_null())
+                    // Name of the rewarded badge
+                    .col(ColumnDef::new(BadgeRewards::BadgeName).uuid().not_null())
+                    // Number of badge levels that have been rewarded
+                    .col(
+                        ColumnDef::new(BadgeRewards::RewardedLevels)
+                            .unsigned()
+                            .not_null(),
+                    )
+                    // When the last badge level was rewarded
+                    .col(ColumnDef::new(BadgeRewards::Updated).date_time().not_null())
+                    // Foreign key linking for the User ID
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(BadgeRewards::Table, BadgeRewards::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Each badge only has one reward entry per user
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-badge-rewards-uid-name")
+                    .table(BadgeRewards::Table)
+                    .col(BadgeRewards::UserId)
+                    .col(BadgeRewards::BadgeName)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Drop the index
+        manager
+            .drop_index(
+                Index::drop()
+                    .table(BadgeRewards::Table)
+                    .name("idx-badge-rewards-uid-name")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(BadgeRewards::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum BadgeRewards {
+    Table,
+    Id,
+    UserId,
+    BadgeName,
+    RewardedLevels,
+    Updated,
+}
@@ -10,6 +10,41 @@ mod m20230720_145347_create_challenge_progress;
 mod m20230731_123814_create_strike_teams;
 mod m20231223_184934_create_strike_team_missions;
 mod m20231223_185554_create_strike_team_mission_progress;
+mod m20240108_120512_add_user_role;
+mod m20240108_121843_create_reports;
+mod m20240110_093412_create_login_events;
+mod m20240111_141207_create_user_identities;
+mod m20240112_160531_create_currency_ledger;
+mod m20240114_101526_create_welcome_kit_grants;
+mod m20240116_093015_rebuild_strike_team_mission_progress;
+mod m20240116_094522_create_strike_team_deployment_queue;
+mod m20240117_131042_create_match_records;
+mod m20240118_091233_create_store_purchases;
+mod m20240119_102204_create_strike_team_flair;
+mod m20240120_143512_add_strike_team_success_streak;
+mod m20240121_091845_create_matchmaking_preferences;
+mod m20240122_104517_add_user_platform;
+mod m20240123_090214_add_strike_team_mission_outcome;
+mod m20240123_093847_add_strike_team_specialization;
+mod m20240124_101133_create_matchmaking_records;
+mod m20240124_101548_create_game_records;
+mod m20240125_093214_create_user_permissions;
+mod m20240126_104312_create_leaderboard_stats;
+mod m20240127_091512_create_xp_boosts;
+mod m20240128_094127_create_player_titles;
+mod m20240129_101214_create_pack_openings;
+mod m20240130_092611_create_match_telemetry;
+mod m20240131_094208_create_interrupted_missions;
+mod m20240201_101532_add_inventory_item_deleted_at;
+mod m20240202_093115_create_user_bans;
+mod m20240203_104512_add_pack_opening_seed;
+mod m20240204_112406_create_friends;
+mod m20240205_091237_create_notifications;
+mod m20240206_101522_create_retired_strike_teams;
+mod m20240207_094418_create_strike_team_mission_history;
+mod m20240208_103126_create_character_customization_history;
+mod m20240209_092314_add_user_tokens_revoked_at;
+mod m20240210_091804_create_badge_rewards;
 
 pub struct Migrator;
 
@@ -27,6 +62,41 @@ impl MigratorTrait for Migrator {
             Box::new(m20230731_123814_create_strike_teams::Migration),
             Box::new(m20231223_184934_create_strike_team_missions::Migration),
             Box::new(m20231223_185554_create_strike_team_mission_progress::Migration),
+            Box::new(m20240108_120512_add_user_role::Migration),
+            Box::new(m20240108_121843_create_reports::Migration),
+            Box::new(m20240110_093412_create_login_events::Migration),
+            Box::new(m20240111_141207_create_user_identities::Migration),
+            Box::new(m20240112_160531_create_currency_ledger::Migration),
+            Box::new(m20240114_101526_create_welcome_kit_grants::Migration),
+            Box::new(m20240116_093015_rebuild_strike_team_mission_progress::Migration),
+            Box::new(m20240116_094522_create_strike_team_deployment_queue::Migration),
+            Box::new(m20240117_131042_create_match_records::Migration),
+            Box::new(m20240118_091233_create_store_purchases::Migration),
+            Box::new(m20240119_102204_create_strike_team_flair::Migration),
+            Box::new(m20240120_143512_add_strike_team_success_streak::Migration),
+            Box::new(m20240121_091845_create_matchmaking_preferences::Migration),
+            Box::new(m20240122_104517_add_user_platform::Migration),
+            Box::new(m20240123_090214_add_strike_team_mission_outcome::Migration),
+            Box::new(m20240123_093847_add_strike_team_specialization::Migration),
+            Box::new(m20240124_101133_create_matchmaking_records::Migration),
+            Box::new(m20240124_101548_create_game_records::Migration),
+            Box::new(m20240125_093214_create_user_permissions::Migration),
+            Box::new(m20240126_104312_create_leaderboard_stats::Migration),
+            Box::new(m20240127_091512_create_xp_boosts::Migration),
+            Box::new(m20240128_094127_create_player_titles::Migration),
+            Box::new(m20240129_101214_create_pack_openings::Migration),
+            Box::new(m20240130_092611_create_match_telemetry::Migration),
+            Box::new(m20240131_094208_create_interrupted_missions::Migration),
+            Box::new(m20240201_101532_add_inventory_item_deleted_at::Migration),
+            Box::new(m20240202_093115_create_user_bans::Migration),
+            Box::new(m20240203_104512_add_pack_opening_seed::Migration),
+            Box::new(m20240204_112406_create_friends::Migration),
+            Box::new(m20240205_091237_create_notifications::Migration),
+            Box::new(m20240206_101522_create_retired_strike_teams::Migration),
+            Box::new(m20240207_094418_create_strike_team_mission_history::Migration),
+            Box::new(m20240208_103126_create_character_customization_history::Migration),
+            Box::new(m20240209_092314_add_user_tokens_revoked_at::Migration),
+            Box::new(m20240210_091804_create_badge_rewards::Migration),
         ]
     }
 }
@@ -1,16 +1,23 @@
-use log::info;
-use migration::{Migrator, MigratorTrait};
-use sea_orm::Database as SeaDatabase;
+use crate::config::Config;
+use entity::{users::UserRole, User};
+use log::{debug, error, info, warn};
+use migration::{MigrationName, Migrator, MigratorTrait};
+use sea_orm::{ConnectOptions, ConnectionTrait, Database as SeaDatabase};
 use std::{
+    collections::HashSet,
     fs::{create_dir_all, File},
     path::Path,
+    time::Duration,
 };
+use thiserror::Error;
 
 pub mod entity;
+pub mod metrics;
 mod migration;
 /// Testing seeding logic
 #[cfg(test)]
 mod seed;
+pub mod sql;
 
 // Re-exports of database types
 pub use sea_orm::DatabaseConnection;
@@ -22,13 +29,99 @@ pub type DbResult<T> = Result<T, DbErr>;
 const DATABASE_PATH: &str = "data/app.db";
 const DATABASE_PATH_URL: &str = "sqlite:data/app.db";
 
-pub async fn init() -> DatabaseConnection {
+/// Errors that can occur while connecting to the database
+#[derive(Debug, Error)]
+pub enum DatabaseInitError {
+    /// Database contains migrations from a newer server version
+    #[error(
+        "Database schema is newer than this server version ({count} unknown migrations, \
+        latest {latest}), update the server or restore a database backup"
+    )]
+    SchemaTooNew { count: usize, latest: String },
+    #[error(transparent)]
+    Database(#[from] DbErr),
+}
+
+pub async fn init() -> Result<DatabaseConnection, DatabaseInitError> {
+    let db = connect_database().await?;
     info!("Connected to database..");
-    connect_database().await
+
+    // Assign the super admin role to the configured account
+    if let Some(super_email) = &Config::get().super_email {
+        setup_super_admin(&db, super_email).await;
+    }
+
+    Ok(db)
+}
+
+/// Gives the account with the provided `email` the [UserRole::SuperAdmin] role
+async fn setup_super_admin(db: &DatabaseConnection, email: &str) {
+    let user = match User::by_email(db, email).await {
+        Ok(Some(value)) => value,
+        Ok(None) => {
+            warn!("Super admin account '{}' does not exist", email);
+            return;
+        }
+        Err(err) => {
+            error!("Failed to find super admin account: {}", err);
+            return;
+        }
+    };
+
+    // Role is already set
+    if user.role == UserRole::SuperAdmin {
+        return;
+    }
+
+    if let Err(err) = user.set_role(db, UserRole::SuperAdmin).await {
+        error!("Failed to assign super admin role: {}", err);
+    }
+}
+
+/// Connects to the database and applies any pending migrations
+pub async fn connect_database() -> Result<DatabaseConnection, DatabaseInitError> {
+    let config = &Config::get().database;
+
+    // The SQLite database file is used unless another database is configured
+    let url = match &config.url {
+        Some(url) => url.clone(),
+        None => {
+            create_sqlite_database();
+            DATABASE_PATH_URL.to_string()
+        }
+    };
+
+    let mut options = ConnectOptions::new(url);
+    options
+        .max_connections(config.max_connections)
+        .min_connections(config.min_connections)
+        .connect_timeout(Duration::from_secs(config.connect_timeout_secs))
+        .idle_timeout(Duration::from_secs(config.idle_timeout_secs));
+
+    // Connect to database
+    let mut connection = SeaDatabase::connect(options).await?;
+    debug!(
+        "Connected to {:?} database",
+        connection.get_database_backend()
+    );
+
+    metrics::install(&mut connection);
+
+    check_schema_version(&connection).await?;
+
+    // Run migrations
+    let pending = Migrator::get_pending_migrations(&connection).await?;
+    if !pending.is_empty() {
+        info!("Applying {} database migrations", pending.len());
+        Migrator::up(&connection, None).await?;
+    }
+
+    Ok(connection)
 }
 
-/// Connects to the database
-async fn connect_database() -> DatabaseConnection {
+/// Creates the SQLite database file and the folder its stored
+/// in if they don't already exist
+fn create_sqlite_database() {
     let path = Path::new(&DATABASE_PATH);
 
     // Create path to database file if missing
@@ -42,16 +135,28 @@ async fn connect_database() -> DatabaseConnection {
     if !path.exists() {
         File::create(path).expect("Unable to create sqlite database file");
     }
+}
 
-    // Connect to database
-    let connection = SeaDatabase::connect(DATABASE_PATH_URL)
-        .await
-        .expect("Unable to create database connection");
+/// Ensures the database hasn't been migrated by a newer server version,
+/// running an older server against it could corrupt the newer schema
+async fn check_schema_version(connection: &DatabaseConnection) -> Result<(), DatabaseInitError> {
+    let known: HashSet<String> = Migrator::migrations()
+        .iter()
+        .map(|migration| migration.name().to_string())
+        .collect();
 
-    // Run migrations
-    Migrator::up(&connection, None)
-        .await
-        .expect("Unable to run database migrations");
+    let unknown: Vec<String> = Migrator::get_migration_models(connection)
+        .await?
+        .into_iter()
+        .map(|model| model.version)
+        .filter(|version| !known.contains(version))
+        .collect();
 
-    connection
+    match unknown.iter().max() {
+        Some(latest) => Err(DatabaseInitError::SchemaTooNew {
+            count: unknown.len(),
+            latest: latest.clone(),
+        }),
+        None => Ok(()),
+    }
 }
@@ -17,7 +17,7 @@ use crate::{
 pub async fn seed() {
     setup_test_logging();
 
-    let db = connect_database().await;
+    let db = connect_database().await.unwrap();
 
     let create_user = CreateUser {
         email: "test@test.com".to_string(),
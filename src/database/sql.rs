@@ -0,0 +1,75 @@
+//! Helpers for the raw SQL fragments used within queries. SeaORM builds the
+//! queries for each database backend but the raw fragments are written in
+//! the SQLite dialect (backtick quoting) and adjusted here for the others
+
+use sea_orm::DbBackend;
+
+/// Adjusts the `sql` written with backtick quoted identifiers for the
+/// `backend`, Postgres quotes identifiers with double quotes
+pub fn quoted(backend: DbBackend, sql: &str) -> String {
+    match backend {
+        DbBackend::Postgres => sql.replace('`', "\""),
+        DbBackend::MySql | DbBackend::Sqlite => sql.to_string(),
+    }
+}
+
+/// Expression for the smallest of the comma separated `args`
+pub fn least(backend: DbBackend, args: &str) -> String {
+    match backend {
+        DbBackend::Sqlite => format!("(SELECT MIN({}))", args),
+        DbBackend::MySql | DbBackend::Postgres => format!("LEAST({})", args),
+    }
+}
+
+/// Reference to the value an insert that conflicted with an existing
+/// row attempted to insert into the `column`
+pub fn excluded(backend: DbBackend, column: &str) -> String {
+    match backend {
+        DbBackend::MySql => format!("VALUES(`{}`)", column),
+        DbBackend::Postgres | DbBackend::Sqlite => format!("`excluded`.`{}`", column),
+    }
+}
+
+/// Reference to the `column` of the existing `table` row that an insert
+/// conflicted with, the column is qualified with the table name as Postgres
+/// also has the `excluded` row in scope making unqualified columns ambiguous
+pub fn target(table: &str, column: &str) -> String {
+    format!("`{}`.`{}`", table, column)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{excluded, least, quoted, target};
+    use sea_orm::DbBackend;
+
+    /// Tests that the fragments are adjusted for each backend
+    #[test]
+    fn test_backend_fragments() {
+        let sql = least(DbBackend::Sqlite, "`balance` + 1, 10");
+        assert_eq!(
+            quoted(DbBackend::Sqlite, &sql),
+            "(SELECT MIN(`balance` + 1, 10))"
+        );
+
+        let sql = least(DbBackend::Postgres, "`balance` + 1, 10");
+        assert_eq!(
+            quoted(DbBackend::Postgres, &sql),
+            "LEAST(\"balance\" + 1, 10)"
+        );
+
+        assert_eq!(excluded(DbBackend::MySql, "balance"), "VALUES(`balance`)");
+        assert_eq!(
+            quoted(
+                DbBackend::Postgres,
+                &excluded(DbBackend::Postgres, "balance")
+            ),
+            "\"excluded\".\"balance\""
+        );
+
+        assert_eq!(target("currency", "balance"), "`currency`.`balance`");
+        assert_eq!(
+            quoted(DbBackend::Postgres, &target("currency", "balance")),
+            "\"currency\".\"balance\""
+        );
+    }
+}
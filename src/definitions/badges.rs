@@ -1,10 +1,12 @@
 use crate::{
     database::entity::currency::CurrencyType,
     definitions::{
+        definition_init,
         i18n::{I18n, I18nDescription, I18nTitle, Localized},
+        parse_definition,
         shared::CustomAttributes,
     },
-    services::activity::{ActivityDescriptor, ActivityEvent},
+    services::activity::{ActivityDescriptor, ActivityEvent, ActivityName},
     utils::ImStr,
 };
 use anyhow::Context;
@@ -31,6 +33,8 @@ pub struct Badges {
 /// (Allows the definitions to be passed with static lifetimes)
 static STORE: OnceLock<Badges> = OnceLock::new();
 
+definition_init!(Badges, STORE, load);
+
 impl Badges {
     /// Gets a static reference to the global [Badges] collection
     pub fn get() -> &'static Badges {
@@ -38,7 +42,7 @@ impl Badges {
     }
 
     fn load() -> anyhow::Result<Self> {
-        let values: Vec<Badge> = serde_json::from_str(BADGE_DEFINITIONS)
+        let values: Vec<Badge> = parse_definition("matchBadges.json", BADGE_DEFINITIONS)
             .context("Failed to load match badge definitions")?;
 
         debug!("Loaded {} badge definition(s)", values.len(),);
@@ -50,9 +54,26 @@ impl Badges {
         &self.values
     }
 
+    /// Checks whether `name` is the image path of any badge level
+    pub fn has_icon(&self, name: &str) -> bool {
+        self.values
+            .iter()
+            .flat_map(|badge| badge.levels.iter())
+            .any(|level| level.img_path.as_deref() == Some(name))
+    }
+
+    /// Finds a badge by its `name`
+    pub fn by_name(&self, name: &BadgeName) -> Option<&Badge> {
+        self.values.iter().find(|badge| badge.name.eq(name))
+    }
+
     pub fn by_activity(&self, activity: &ActivityEvent) -> Option<(&Badge, u32, Vec<&BadgeLevel>)> {
         // Find a badge with an activity that can be applied
         let (badge, badge_activity) = self.values.iter().find_map(|badge| {
+            if !badge.enabled {
+                return None;
+            }
+
             badge
                 .by_activity(activity)
                 .map(|activity| (badge, activity))
@@ -61,14 +82,24 @@ impl Badges {
         // Get the activity progression
         let progress = activity.attribute_u32(&badge_activity.progress_key).ok()?;
 
-        // Find all the badge levels that have been reached
-        let levels: Vec<&BadgeLevel> = badge
-            .levels
-            .iter()
-            .take_while(|level| level.target_count <= progress)
-            .collect();
+        Some((badge, progress, badge.levels_reached(progress)))
+    }
 
-        Some((badge, progress, levels))
+    /// Finds the badge named by a [ActivityName::BadgeEarned] `activity` along
+    /// with the progress count and the levels that have been reached
+    pub fn by_earned_activity(
+        &self,
+        activity: &ActivityEvent,
+    ) -> Option<(&Badge, u32, Vec<&BadgeLevel>)> {
+        if activity.name != ActivityName::BadgeEarned {
+            return None;
+        }
+
+        let name = activity.attribute_uuid("badgeName").ok()?;
+        let badge = self.by_name(&name).filter(|badge| badge.enabled)?;
+        let progress = activity.attribute_u32("count").ok()?;
+
+        Some((badge, progress, badge.levels_reached(progress)))
     }
 }
 
@@ -110,6 +141,14 @@ impl Badge {
             .iter()
             .find(|descriptor| descriptor.matches(activity))
     }
+
+    /// Finds all the badge levels that have been reached with `progress`
+    pub fn levels_reached(&self, progress: u32) -> Vec<&BadgeLevel> {
+        self.levels
+            .iter()
+            .take_while(|level| level.target_count <= progress)
+            .collect()
+    }
 }
 
 impl Localized for Badge {
@@ -147,10 +186,29 @@ pub struct BadgeLevel {
 #[cfg(test)]
 mod test {
     use super::Badges;
+    use crate::services::activity::{ActivityEvent, ActivityName};
 
-    /// Tests ensuring loading succeeds
+    /// Tests that badge earned activities find the named badge and
+    /// the levels reached by the reported count
     #[test]
-    fn ensure_load_succeed() {
-        _ = Badges::load().unwrap();
+    fn test_by_earned_activity() {
+        let badges = Badges::get();
+        let badge = badges.all().first().unwrap();
+        let target_count = badge.levels.last().unwrap().target_count;
+
+        let activity = ActivityEvent {
+            name: ActivityName::BadgeEarned,
+            attributes: [
+                ("badgeName".to_string(), badge.name.into()),
+                ("count".to_string(), target_count.into()),
+            ]
+            .into_iter()
+            .collect(),
+        };
+
+        let (found, progress, levels) = badges.by_earned_activity(&activity).unwrap();
+        assert_eq!(found.name, badge.name);
+        assert_eq!(progress, target_count);
+        assert_eq!(levels.len(), badge.levels.len());
     }
 }
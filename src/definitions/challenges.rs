@@ -1,23 +1,30 @@
 use crate::{
     database::entity::currency::CurrencyType,
     definitions::{
+        definition_init,
         i18n::{I18nDescription, I18nKey, I18nTitle},
-        items::ItemName,
+        items::{ItemName, Items},
+        load_definition, parse_definition,
         shared::CustomAttributes,
+        strike_team_flair::{StrikeTeamFlairName, StrikeTeamFlairs},
     },
     services::activity::{ActivityDescriptor, ActivityEvent},
     utils::{models::DateDuration, ImStr},
 };
-use anyhow::Context;
-use log::debug;
+use anyhow::{anyhow, Context};
+use log::{debug, error};
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
-use std::sync::OnceLock;
+use std::{path::Path, sync::OnceLock};
+use thiserror::Error;
 use uuid::Uuid;
 
 /// Challenge definitions (192)
 const CHALLENGE_DEFINITIONS: &str = include_str!("../resources/data/challengeDefinitions.json");
 
+/// Path to the file containing custom challenges defined by the server operator
+const CUSTOM_CHALLENGES_PATH: &str = "data/challenges.json";
+
 pub struct Challenges {
     pub values: Vec<ChallengeDefinition>,
 }
@@ -26,6 +33,8 @@ pub struct Challenges {
 /// (Allows the definitions to be passed with static lifetimes)
 static STORE: OnceLock<Challenges> = OnceLock::new();
 
+definition_init!(Challenges, STORE, load);
+
 impl Challenges {
     /// Gets a static reference to the global [ChallengeDefinitions] collection
     pub fn get() -> &'static Challenges {
@@ -34,12 +43,98 @@ impl Challenges {
 
     fn load() -> anyhow::Result<Self> {
         debug!("Loading challenges");
-        let values: Vec<ChallengeDefinition> = serde_json::from_str(CHALLENGE_DEFINITIONS)
-            .context("Failed to load challenge definitions")?;
+        let mut values: Vec<ChallengeDefinition> =
+            load_definition("challengeDefinitions.json", CHALLENGE_DEFINITIONS)
+                .context("Failed to load challenge definitions")?;
         debug!("Loaded {} challenge definition(s)", values.len());
+
+        let custom = Self::load_custom();
+        Self::append_custom(&mut values, custom);
+
         Ok(Self { values })
     }
 
+    /// Loads the custom challenge definitions from [CUSTOM_CHALLENGES_PATH]
+    /// if the file exists, errors are logged and no custom challenges loaded
+    fn load_custom() -> Vec<ChallengeDefinition> {
+        match Self::read_custom() {
+            Ok(value) => value,
+            Err(err) => {
+                error!("Unable to load custom challenges: {:#}", err);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Reads and parses the custom challenge definitions from
+    /// [CUSTOM_CHALLENGES_PATH] if the file exists
+    fn read_custom() -> anyhow::Result<Vec<ChallengeDefinition>> {
+        let path = Path::new(CUSTOM_CHALLENGES_PATH);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let data = std::fs::read_to_string(path).context("Failed to read custom challenges")?;
+        let custom = parse_definition(CUSTOM_CHALLENGES_PATH, &data)
+            .context("Failed to parse custom challenges")?;
+
+        Ok(custom)
+    }
+
+    /// Checks the custom challenge definitions reporting every invalid
+    /// definition rather than skipping them like when loading
+    pub fn check_custom() -> anyhow::Result<()> {
+        let custom = Self::read_custom()?;
+        let mut values: Vec<ChallengeDefinition> =
+            load_definition("challengeDefinitions.json", CHALLENGE_DEFINITIONS)?;
+
+        let mut errors = Vec::new();
+        for definition in custom {
+            match definition.validate(&values) {
+                Ok(()) => values.push(definition),
+                Err(err) => errors.push(format!("{}: {}", definition.name, err)),
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(anyhow!(
+                "{} invalid custom challenge(s): {}",
+                errors.len(),
+                errors.join(", ")
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Validates and appends the `custom` definitions onto `values`, invalid
+    /// definitions are logged and skipped
+    fn append_custom(values: &mut Vec<ChallengeDefinition>, custom: Vec<ChallengeDefinition>) {
+        let mut loaded = 0;
+
+        for definition in custom {
+            if let Err(err) = definition.validate(values) {
+                error!(
+                    "Skipping invalid custom challenge {}: {}",
+                    definition.name, err
+                );
+                continue;
+            }
+
+            values.push(definition);
+            loaded += 1;
+        }
+
+        if loaded > 0 {
+            debug!("Loaded {} custom challenge definition(s)", loaded);
+        }
+    }
+
+    /// Finds a challenge definition by its `name`
+    pub fn by_name(&self, name: &ChallengeName) -> Option<&ChallengeDefinition> {
+        self.values.iter().find(|value| value.name.eq(name))
+    }
+
     pub fn get_by_activity(
         &self,
         activity: &ActivityEvent,
@@ -101,14 +196,112 @@ pub struct ChallengeDefinition {
     /// Collection of challenges that parent this challenge
     pub parents: Vec<ChallengeName>,
 
-    /// TODO: Giving challenge rewards is not yet implemented
+    /// Rewards given each time the challenge is completed
     pub reward: ChallengeReward,
 
-    /// Unknown usage. Possibly for shared player-base wide challenges..?
+    /// Co-op challenge where the progress made by any member of a game
+    /// counts towards the challenge for all the members present at the end
     pub community: bool,
 }
 
+/// Errors that can occur when validating a challenge definition
+#[derive(Debug, Error)]
+pub enum ChallengeValidationError {
+    #[error("Challenge name is already in use")]
+    DuplicateName,
+    #[error("Challenge has no counters")]
+    MissingCounters,
+    #[error("Counter '{0}' target count must be greater than zero")]
+    InvalidTargetCount(ImStr),
+    #[error("Counter '{0}' has no activities")]
+    MissingActivities(ImStr),
+    #[error("Counter '{0}' has an activity without a progress key")]
+    MissingProgressKey(ImStr),
+    #[error("Unknown parent challenge '{0}'")]
+    UnknownParent(ChallengeName),
+    #[error("Unknown reward item '{0}'")]
+    UnknownRewardItem(ItemName),
+    #[error("Unknown reward strike team flair '{0}'")]
+    UnknownRewardFlair(StrikeTeamFlairName),
+}
+
 impl ChallengeDefinition {
+    /// Name of the icon texture used by the challenge, this is the last
+    /// segment of the "textureId" game path
+    pub fn icon_name(&self) -> Option<&str> {
+        let texture = self.custom_attributes.get_str("textureId")?;
+        texture.rsplit('/').next()
+    }
+
+    /// Validates that the definition can be tracked and rewarded, `existing`
+    /// contains the other loaded definitions
+    pub fn validate(
+        &self,
+        existing: &[ChallengeDefinition],
+    ) -> Result<(), ChallengeValidationError> {
+        if existing.iter().any(|other| other.name == self.name) {
+            return Err(ChallengeValidationError::DuplicateName);
+        }
+
+        if self.counters.is_empty() {
+            return Err(ChallengeValidationError::MissingCounters);
+        }
+
+        for counter in &self.counters {
+            if counter.target_count == 0 {
+                return Err(ChallengeValidationError::InvalidTargetCount(
+                    counter.name.clone(),
+                ));
+            }
+
+            if counter.activities.is_empty() {
+                return Err(ChallengeValidationError::MissingActivities(
+                    counter.name.clone(),
+                ));
+            }
+
+            if counter
+                .activities
+                .iter()
+                .any(|activity| activity.progress_key.is_empty())
+            {
+                return Err(ChallengeValidationError::MissingProgressKey(
+                    counter.name.clone(),
+                ));
+            }
+        }
+
+        if let Some(parent) = self
+            .parents
+            .iter()
+            .find(|parent| !existing.iter().any(|other| other.name == **parent))
+        {
+            return Err(ChallengeValidationError::UnknownParent(*parent));
+        }
+
+        let items = Items::get();
+        if let Some(item) = self
+            .reward
+            .items
+            .iter()
+            .find(|item| items.by_name(&item.name).is_none())
+        {
+            return Err(ChallengeValidationError::UnknownRewardItem(item.name));
+        }
+
+        let flairs = StrikeTeamFlairs::get();
+        if let Some(flair) = self
+            .reward
+            .flair
+            .iter()
+            .find(|flair| flairs.by_name(flair).is_none())
+        {
+            return Err(ChallengeValidationError::UnknownRewardFlair(flair.clone()));
+        }
+
+        Ok(())
+    }
+
     /// Attempts to find a counter associated to this definition that
     /// matches the provided `activity`
     pub fn get_by_activity(
@@ -176,6 +369,10 @@ pub struct ChallengeReward {
     pub items: Vec<ItemReward>,
     /// Entitlement rewards
     pub entitlements: Vec<serde_json::Value>,
+    /// Strike team flair rewards, unlocked for the player the
+    /// first time the challenge is completed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub flair: Vec<StrikeTeamFlairName>,
 }
 
 /// Representing a type of currency to be given as a reward
@@ -202,11 +399,25 @@ pub struct ItemReward {
 
 #[cfg(test)]
 mod test {
-    use super::Challenges;
+    use super::{ChallengeDefinition, ChallengeValidationError, Challenges};
 
-    /// Tests ensuring loading succeeds
+    /// Tests that custom challenges using the same name as an
+    /// existing challenge are rejected
     #[test]
-    fn ensure_load_succeed() {
-        _ = Challenges::load().unwrap();
+    fn test_custom_duplicate_rejected() {
+        let mut values: Vec<ChallengeDefinition> =
+            serde_json::from_str(super::CHALLENGE_DEFINITIONS).unwrap();
+        let custom: Vec<ChallengeDefinition> =
+            serde_json::from_str(super::CHALLENGE_DEFINITIONS).unwrap();
+
+        let duplicate = custom.first().unwrap();
+        assert!(matches!(
+            duplicate.validate(&values),
+            Err(ChallengeValidationError::DuplicateName)
+        ));
+
+        let count = values.len();
+        Challenges::append_custom(&mut values, custom);
+        assert_eq!(values.len(), count);
     }
 }
@@ -1,23 +1,78 @@
 use crate::{
-    database::entity::{Character, User},
+    config::CharactersConfig,
+    database::entity::{currency::CurrencyType, Character, User},
     definitions::{
-        classes::{Classes, PointMap},
+        classes::{ClassName, Classes, PointMap},
         items::ItemName,
-        level_tables::{LevelTables, ProgressionXp},
+        level_tables::{LevelTable, LevelTables, ProgressionXp},
     },
 };
 use anyhow::{anyhow, Context};
 use sea_orm::ConnectionTrait;
+use serde::Serialize;
+
+/// Outcome of acquiring the character for a character item
+pub enum AcquiredCharacter {
+    /// The character was created
+    Created,
+    /// The user already had the character unlocked
+    Duplicate(Character),
+}
+
+/// Duplicate character item that was converted into a reward
+/// for the already unlocked character
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateCharacter {
+    /// Class of the duplicate character
+    pub class_name: ClassName,
+    /// What the duplicate was converted into
+    pub conversion: DuplicateConversion,
+}
+
+/// Reward a duplicate character item is converted into
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum DuplicateConversion {
+    /// XP granted to the unlocked character
+    Xp { amount: u32 },
+    /// Currency granted when the character can't level up any further
+    Currency { name: CurrencyType, amount: u32 },
+}
+
+/// Determines what a duplicate character item is converted into for a
+/// character at `level` on the class `level_table`. Characters that can
+/// still level up earn XP, otherwise currency is granted instead
+pub fn duplicate_conversion(
+    config: &CharactersConfig,
+    level_table: Option<&LevelTable>,
+    level: u32,
+) -> DuplicateConversion {
+    let can_level =
+        level_table.is_some_and(|level_table| level_table.get_xp_requirement(level + 1).is_some());
+
+    if can_level {
+        DuplicateConversion::Xp {
+            amount: config.duplicate_xp,
+        }
+    } else {
+        DuplicateConversion::Currency {
+            name: config.duplicate_currency,
+            amount: config.duplicate_currency_value,
+        }
+    }
+}
 
 /// Handles the initialization of a character after an item for
-/// that character has been acquired
+/// that character has been acquired, characters the user already
+/// has unlocked are not created again
 pub async fn acquire_item_character<C>(
     db: &C,
     user: &User,
     item: &ItemName,
     classes: &Classes,
     level_tables: &LevelTables,
-) -> anyhow::Result<()>
+) -> anyhow::Result<AcquiredCharacter>
 where
     C: ConnectionTrait + Send,
 {
@@ -26,10 +81,8 @@ where
         .ok_or(anyhow!("Missing class for character item"))?;
 
     // User already has the character unlocked
-    if let Some(_existing) = Character::find_by_user_by_def(db, user, class.name).await? {
-        // TODO: Getting the same character as a reward again adds 4 skill points for card rank II and IV and 5 points for VI, VII, and X
-
-        return Ok(());
+    if let Some(existing) = Character::find_by_user_by_def(db, user, class.name).await? {
+        return Ok(AcquiredCharacter::Duplicate(existing));
     }
 
     // Character is aquired at level 1
@@ -67,5 +120,50 @@ where
     )
     .await?;
 
-    Ok(())
+    Ok(AcquiredCharacter::Created)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{duplicate_conversion, DuplicateConversion};
+    use crate::{
+        config::CharactersConfig,
+        database::entity::currency::CurrencyType,
+        definitions::{classes::Classes, level_tables::LevelTables},
+    };
+
+    /// Tests that duplicates grant XP until the character reaches
+    /// the max level of its class level table
+    #[test]
+    fn test_duplicate_conversion() {
+        let config = CharactersConfig {
+            duplicate_xp: 1000,
+            duplicate_currency: CurrencyType::Mission,
+            duplicate_currency_value: 250,
+        };
+
+        let class = Classes::get().values.first().unwrap();
+        let level_table = LevelTables::get().by_name(&class.level_name).unwrap();
+        let max_level = level_table
+            .table
+            .iter()
+            .map(|entry| entry.level)
+            .max()
+            .unwrap();
+
+        assert_eq!(
+            duplicate_conversion(&config, Some(level_table), 1),
+            DuplicateConversion::Xp { amount: 1000 }
+        );
+
+        let currency = DuplicateConversion::Currency {
+            name: CurrencyType::Mission,
+            amount: 250,
+        };
+        assert_eq!(
+            duplicate_conversion(&config, Some(level_table), max_level),
+            currency
+        );
+        assert_eq!(duplicate_conversion(&config, None, 1), currency);
+    }
 }
@@ -11,9 +11,11 @@
 //! https://masseffectandromeda.fandom.com/wiki/Character_kit
 
 use crate::definitions::{
+    definition_init,
     i18n::{I18nDescription, I18nName},
-    items::{InventoryNamespace, ItemLink, ItemName},
+    items::{BaseCategory, InventoryNamespace, ItemLink, ItemName},
     level_tables::LevelTableName,
+    load_definition,
     shared::CustomAttributes,
     skills::SkillTree,
 };
@@ -24,6 +26,7 @@ use sea_orm::FromJsonQueryResult;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
 use std::sync::OnceLock;
+use thiserror::Error;
 use uuid::Uuid;
 
 /// Class definitions (36)
@@ -44,6 +47,8 @@ pub struct Classes {
 /// (Allows the definitions to be passed with static lifetimes)
 static STORE: OnceLock<Classes> = OnceLock::new();
 
+definition_init!(Classes, STORE, load);
+
 impl Classes {
     /// Gets a static reference to the global [Classes] collection
     pub fn get() -> &'static Classes {
@@ -51,8 +56,8 @@ impl Classes {
     }
 
     fn load() -> anyhow::Result<Self> {
-        let values: Vec<Class> =
-            serde_json::from_str(CLASS_DEFINITIONS).context("Failed to load class definitions")?;
+        let values: Vec<Class> = load_definition("characterClasses.json", CLASS_DEFINITIONS)
+            .context("Failed to load class definitions")?;
 
         debug!("Loaded {} class definition(s)", values.len());
 
@@ -128,6 +133,10 @@ pub struct Class {
 
     /// Default equipment for the character
     pub default_equipments: Vec<CharacterEquipment>,
+    /// Levels the character must reach to unlock equipment slots, slots
+    /// not listed are unlocked from the start
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub equipment_slot_levels: Vec<EquipmentSlotLevel>,
 
     /// Default customization data
     pub default_customization: CustomizationMap,
@@ -151,6 +160,30 @@ pub struct Class {
     pub i18n_description: I18nDescription,
 }
 
+impl Class {
+    /// Level a character of this class must reach to use the equipment `slot`
+    pub fn slot_unlock_level(&self, slot: EquipmentSlot) -> u32 {
+        self.equipment_slot_levels
+            .iter()
+            .find(|value| value.slot == slot)
+            .map(|value| value.level)
+            .unwrap_or(1)
+    }
+
+    /// Whether a character of this class at `level` can use the equipment `slot`
+    pub fn is_slot_unlocked(&self, slot: EquipmentSlot, level: u32) -> bool {
+        level >= self.slot_unlock_level(slot)
+    }
+
+    /// Equipment slots unlocked for a character of this class at `level`
+    pub fn unlocked_slots(&self, level: u32) -> Vec<EquipmentSlot> {
+        EquipmentSlot::ALL
+            .into_iter()
+            .filter(|slot| self.is_slot_unlocked(*slot, level))
+            .collect()
+    }
+}
+
 pub type CharacterBonus = serde_json::Map<String, serde_json::Value>;
 
 /// Game mapping for different kinds of character points,
@@ -193,6 +226,58 @@ impl CustomizationMap {
             self.0.push((key, entry))
         }
     }
+
+    pub fn get(&self, key: &str) -> Option<&CustomizationEntry> {
+        self.0
+            .iter()
+            .find(|(k, _)| key.eq(k))
+            .map(|(_, value)| value)
+    }
+
+    /// Validates an `entry` update for the `key` against this map of the
+    /// class default customization, entries must exist in the defaults
+    /// and keep the same type and param ID as the default entry
+    pub fn validate(
+        &self,
+        key: &str,
+        entry: &CustomizationEntry,
+    ) -> Result<(), CustomizationError> {
+        let default = self
+            .get(key)
+            .ok_or_else(|| CustomizationError::UnknownEntry(key.to_string()))?;
+
+        if entry.ty != default.ty {
+            return Err(CustomizationError::TypeMismatch(key.to_string()));
+        }
+
+        if entry.param_id != default.param_id {
+            return Err(CustomizationError::ParamMismatch(key.to_string()));
+        }
+
+        let values = [entry.value_x, entry.value_y, entry.value_z, entry.value_w];
+        if !values.iter().all(|value| value.is_finite()) {
+            return Err(CustomizationError::InvalidValue(key.to_string()));
+        }
+
+        Ok(())
+    }
+}
+
+/// Errors from validating a customization entry update
+#[derive(Debug, Error)]
+pub enum CustomizationError {
+    /// The class has no default customization for the entry
+    #[error("Unknown customization entry {0}")]
+    UnknownEntry(String),
+    /// The entry type doesn't match the class default
+    #[error("Customization entry {0} has the wrong type")]
+    TypeMismatch(String),
+    /// The entry param ID doesn't match the class default
+    #[error("Customization entry {0} has the wrong param ID")]
+    ParamMismatch(String),
+    /// The entry values aren't finite numbers
+    #[error("Customization entry {0} has invalid values")]
+    InvalidValue(String),
 }
 
 /// Customization entry structure for characters, contains the
@@ -229,6 +314,41 @@ pub enum EquipmentSlot {
     BannerSlot,
 }
 
+impl EquipmentSlot {
+    /// All the equipment slots
+    pub const ALL: [EquipmentSlot; 7] = [
+        EquipmentSlot::WeaponSlot1,
+        EquipmentSlot::WeaponSlot2,
+        EquipmentSlot::EquipmentSlot,
+        EquipmentSlot::EquipmentHistorySlot,
+        EquipmentSlot::Booster1,
+        EquipmentSlot::Booster2,
+        EquipmentSlot::BannerSlot,
+    ];
+
+    /// Categories of the items that can be equipped in the slot, slots
+    /// without categories accept any item
+    pub fn categories(self) -> &'static [BaseCategory] {
+        match self {
+            EquipmentSlot::WeaponSlot1 | EquipmentSlot::WeaponSlot2 => {
+                &[BaseCategory::Weapons, BaseCategory::WeaponsSpecialized]
+            }
+            EquipmentSlot::EquipmentSlot => &[BaseCategory::Equipment],
+            EquipmentSlot::Booster1 | EquipmentSlot::Booster2 => &[BaseCategory::Boosters],
+            EquipmentSlot::EquipmentHistorySlot | EquipmentSlot::BannerSlot => &[],
+        }
+    }
+}
+
+/// Level required to unlock an equipment slot
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EquipmentSlotLevel {
+    /// The equipment slot
+    pub slot: EquipmentSlot,
+    /// Character level the slot unlocks at
+    pub level: u32,
+}
+
 /// Character equipment
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CharacterEquipment {
@@ -253,11 +373,69 @@ pub enum NameOrEmpty {
 
 #[cfg(test)]
 mod test {
-    use super::Classes;
+    use super::{Classes, CustomizationError, EquipmentSlot, EquipmentSlotLevel};
 
-    /// Tests ensuring loading succeeds
+    /// Tests that slots unlock at the class defined levels and that
+    /// slots without a level are unlocked from the start
     #[test]
-    fn ensure_load_succeed() {
-        _ = Classes::load().unwrap();
+    fn test_slot_unlocks() {
+        let classes = Classes::load().unwrap();
+        let mut class = classes.values.into_iter().next().unwrap();
+
+        assert!(class.is_slot_unlocked(EquipmentSlot::Booster1, 1));
+        assert!(class.is_slot_unlocked(EquipmentSlot::Booster2, 1));
+
+        class.equipment_slot_levels = vec![EquipmentSlotLevel {
+            slot: EquipmentSlot::Booster1,
+            level: 5,
+        }];
+
+        let unlocked = class.unlocked_slots(4);
+        assert!(!unlocked.contains(&EquipmentSlot::Booster1));
+        assert!(!unlocked.contains(&EquipmentSlot::Booster2));
+        assert!(unlocked.contains(&EquipmentSlot::WeaponSlot1));
+        assert!(class.unlocked_slots(5).contains(&EquipmentSlot::Booster1));
+    }
+
+    /// Tests that customization updates must match the class defaults
+    #[test]
+    fn test_validate_customization() {
+        let classes = Classes::load().unwrap();
+        let class = classes
+            .values
+            .iter()
+            .find(|class| !class.default_customization.0.is_empty())
+            .unwrap();
+        let defaults = &class.default_customization;
+        let (key, default) = defaults.0.first().unwrap();
+
+        let mut entry = default.clone();
+        entry.value_x = 0.5;
+        assert!(defaults.validate(key, &entry).is_ok());
+
+        assert!(matches!(
+            defaults.validate("unknown", &entry),
+            Err(CustomizationError::UnknownEntry(_))
+        ));
+
+        let mut wrong_type = entry.clone();
+        wrong_type.ty = wrong_type.ty.wrapping_add(1);
+        assert!(matches!(
+            defaults.validate(key, &wrong_type),
+            Err(CustomizationError::TypeMismatch(_))
+        ));
+
+        let mut wrong_param = entry.clone();
+        wrong_param.param_id = wrong_param.param_id.wrapping_add(1);
+        assert!(matches!(
+            defaults.validate(key, &wrong_param),
+            Err(CustomizationError::ParamMismatch(_))
+        ));
+
+        entry.value_w = f32::NAN;
+        assert!(matches!(
+            defaults.validate(key, &entry),
+            Err(CustomizationError::InvalidValue(_))
+        ));
     }
 }
@@ -0,0 +1,149 @@
+//! Equipment sets are collections of items which grant a bonus to a
+//! character when every item in the set is equipped at the same time
+
+use super::{
+    classes::{CharacterEquipment, NameOrEmpty},
+    definition_init,
+    items::{ItemName, Items},
+    parse_definition,
+};
+use anyhow::{anyhow, Context};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// Type alias for a [String] representing a [EquipmentSet::name]
+pub type EquipmentSetName = String;
+
+/// Equipment set definitions
+const EQUIPMENT_SET_DEFINITIONS: &str = include_str!("../resources/data/equipmentSets.json");
+
+/// Collection of equipment set definitions
+pub struct EquipmentSets {
+    pub values: Vec<EquipmentSet>,
+}
+
+/// Static storage for the definitions once its loaded
+/// (Allows the definitions to be passed with static lifetimes)
+static STORE: OnceLock<EquipmentSets> = OnceLock::new();
+
+definition_init!(EquipmentSets, STORE, load);
+
+impl EquipmentSets {
+    /// Gets a static reference to the global [EquipmentSets] collection
+    pub fn get() -> &'static EquipmentSets {
+        STORE.get_or_init(|| Self::load().unwrap())
+    }
+
+    fn load() -> anyhow::Result<Self> {
+        let values: Vec<EquipmentSet> =
+            parse_definition("equipmentSets.json", EQUIPMENT_SET_DEFINITIONS)
+                .context("Failed to load equipment set definitions")?;
+
+        let items = Items::get();
+
+        for (index, set) in values.iter().enumerate() {
+            // Ensure set names are unique
+            if values[..index].iter().any(|other| other.name == set.name) {
+                return Err(anyhow!("Duplicate equipment set '{}'", set.name));
+            }
+
+            // Sets without items would always be active
+            if set.items.is_empty() {
+                return Err(anyhow!("Equipment set '{}' has no items", set.name));
+            }
+
+            // Ensure all the items in the set exist
+            if let Some(item) = set.items.iter().find(|item| items.by_name(item).is_none()) {
+                return Err(anyhow!(
+                    "Unknown item '{}' in equipment set '{}'",
+                    item,
+                    set.name
+                ));
+            }
+        }
+
+        debug!("Loaded {} equipment set definition(s)", values.len());
+
+        Ok(Self { values })
+    }
+
+    /// Finds the sets that are completed by the provided `equipment`
+    pub fn active_sets(&self, equipment: &[CharacterEquipment]) -> Vec<&EquipmentSet> {
+        self.values
+            .iter()
+            .filter(|set| set.is_complete(equipment))
+            .collect()
+    }
+}
+
+/// Collection of items that grants bonuses when all equipped together
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EquipmentSet {
+    /// Unique name of the set
+    pub name: EquipmentSetName,
+    /// The items which must all be equipped for the set to be active,
+    /// these can be equipped items or their attachments
+    pub items: Vec<ItemName>,
+    /// The bonuses granted while the set is active
+    pub bonuses: Vec<EquipmentSetBonus>,
+}
+
+impl EquipmentSet {
+    /// Checks whether every item in the set is present within
+    /// the provided `equipment`
+    pub fn is_complete(&self, equipment: &[CharacterEquipment]) -> bool {
+        self.items.iter().all(|item| {
+            equipment.iter().any(|equipment| {
+                matches!(&equipment.name, NameOrEmpty::Name(name) if name == item)
+                    || equipment.attachments.contains(item)
+            })
+        })
+    }
+}
+
+/// Bonus granted by an active [EquipmentSet]
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EquipmentSetBonus {
+    /// Name of the attribute the bonus applies to
+    pub attribute: String,
+    /// The amount the attribute is increased by
+    pub value: f32,
+}
+
+#[cfg(test)]
+mod test {
+    use super::EquipmentSets;
+    use crate::definitions::classes::{CharacterEquipment, EquipmentSlot, NameOrEmpty};
+
+    /// Tests that sets are only active when every item is equipped
+    #[test]
+    fn test_active_sets() {
+        let sets = EquipmentSets::load().unwrap();
+        let set = sets.values.first().unwrap();
+
+        let mut equipment: Vec<CharacterEquipment> = set
+            .items
+            .iter()
+            .map(|item| CharacterEquipment {
+                slot: EquipmentSlot::WeaponSlot1,
+                name: NameOrEmpty::Name(*item),
+                attachments: Vec::new(),
+            })
+            .collect();
+
+        assert!(sets
+            .active_sets(&equipment)
+            .iter()
+            .any(|active| active.name == set.name));
+
+        equipment.pop();
+
+        assert!(!sets
+            .active_sets(&equipment)
+            .iter()
+            .any(|active| active.name == set.name));
+    }
+}
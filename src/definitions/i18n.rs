@@ -5,6 +5,7 @@
 //!
 //! Translation mappings are stored in the csv file at `src/resources/data/i18n.csv`
 
+use super::definition_init;
 use crate::utils::{
     hashing::{int_hash_map, IntHashMap},
     ImStr,
@@ -22,30 +23,56 @@ const I18N_TRANSLATIONS: &[u8] = include_bytes!("../resources/data/i18n.csv");
 /// Type alias for a lookup key in the i18n map
 pub type LookupKey = u32;
 
+/// Language used when the requested language is unknown or
+/// has no bundled translations
+pub const DEFAULT_LANGUAGE: &str = "en-us";
+
+/// Translation files bundled for each language, the first entry
+/// must be the [DEFAULT_LANGUAGE]
+const BUNDLED_LANGUAGES: [(&str, &[u8]); 1] = [(DEFAULT_LANGUAGE, I18N_TRANSLATIONS)];
+
 /// Translation definitions
 pub struct I18n {
     /// Mapping between translation keys and the actual translation value
     map: IntHashMap<LookupKey, ImStr>,
 }
 
-/// Static storage for the definitions once its loaded
-/// (Allows the definitions to be passed with static lifetimes)
-static STORE: OnceLock<I18n> = OnceLock::new();
+/// Static storage for the translations of each bundled language once
+/// they are loaded (Allows the definitions to be passed with static lifetimes)
+static STORE: OnceLock<Vec<(&'static str, I18n)>> = OnceLock::new();
+
+definition_init!(I18n, STORE, load_languages);
 
 impl I18n {
     /// Gets a static reference to the global [I18n] collection
+    /// for the [DEFAULT_LANGUAGE]
     pub fn get() -> &'static I18n {
-        STORE.get_or_init(|| Self::load().unwrap())
+        &Self::languages()[0].1
+    }
+
+    /// Gets the translations for every bundled language
+    fn languages() -> &'static [(&'static str, I18n)] {
+        STORE.get_or_init(|| Self::load_languages().unwrap())
+    }
+
+    /// Loads the translations for each of the [BUNDLED_LANGUAGES]
+    fn load_languages() -> anyhow::Result<Vec<(&'static str, I18n)>> {
+        BUNDLED_LANGUAGES
+            .iter()
+            .map(|(language, data)| {
+                let i18n = Self::load(data)
+                    .with_context(|| format!("Failed to load {language} translations"))?;
+                Ok((*language, i18n))
+            })
+            .collect()
     }
 
     /// Creates a new [I18n] collection, loading the translations
-    /// from the embedded file [I18N_TRANSLATIONS]
-    fn load() -> anyhow::Result<Self> {
+    /// from the provided csv `data`
+    fn load(data: &[u8]) -> anyhow::Result<Self> {
         let mut map = int_hash_map();
 
-        let records = ReaderBuilder::new()
-            .from_reader(I18N_TRANSLATIONS)
-            .into_records();
+        let records = ReaderBuilder::new().from_reader(data).into_records();
 
         for record in records {
             let record = record.context("Failed to parse translation record")?;
@@ -63,6 +90,26 @@ impl I18n {
         Ok(Self { map })
     }
 
+    /// Gets the translations for the provided `language` (i.e "en-us"),
+    /// when the exact language isn't bundled another region of the same
+    /// language is used before falling back to the [DEFAULT_LANGUAGE]
+    pub fn for_language(language: &str) -> &'static I18n {
+        let languages = Self::languages();
+        let language = language.to_ascii_lowercase();
+        let primary = language.split('-').next().unwrap_or_default();
+
+        languages
+            .iter()
+            .find(|(name, _)| *name == language)
+            .or_else(|| {
+                languages
+                    .iter()
+                    .find(|(name, _)| name.split('-').next() == Some(primary))
+            })
+            .map(|(_, i18n)| i18n)
+            .unwrap_or_else(Self::get)
+    }
+
     /// Attempts to find a specific translation from its translation key
     pub fn by_key(&self, key: &I18nKey) -> Option<&ImStr> {
         match key {
@@ -70,6 +117,19 @@ impl I18n {
             I18nKey::Raw(_) => None,
         }
     }
+
+    /// Attempts to find a specific translation from its translation key
+    /// replacing the `{CUSTOM0}`, `{CUSTOM1}`, ... placeholders with the
+    /// provided `params`
+    pub fn format(&self, key: &I18nKey, params: &[String]) -> Option<String> {
+        let mut value = self.by_key(key)?.to_string();
+
+        for (index, param) in params.iter().enumerate() {
+            value = value.replace(&format!("{{CUSTOM{index}}}"), param);
+        }
+
+        Some(value)
+    }
 }
 
 /// Trait implemented by structures that can
@@ -321,11 +381,27 @@ impl Localized for I18nDesc {
 
 #[cfg(test)]
 mod test {
-    use super::I18n;
+    use super::{I18n, I18nKey, I18N_TRANSLATIONS};
+    use std::ptr;
+
+    /// Tests that languages resolve to their bundled translations and
+    /// unknown languages fall back to the default translations
+    #[test]
+    fn test_for_language() {
+        let default = I18n::get();
+        assert!(ptr::eq(I18n::for_language("en-US"), default));
+        assert!(ptr::eq(I18n::for_language("en-gb"), default));
+        assert!(ptr::eq(I18n::for_language("fr-fr"), default));
+        assert!(ptr::eq(I18n::for_language(""), default));
+    }
 
-    /// Tests ensuring loading succeeds
+    /// Tests that placeholders are replaced when formatting
     #[test]
-    fn ensure_load_succeed() {
-        _ = I18n::load().unwrap();
+    fn test_format_params() {
+        let i18n = I18n::load(I18N_TRANSLATIONS).unwrap();
+        let value = i18n
+            .format(&I18nKey::Lookup(23119), &["Test".to_string()])
+            .unwrap();
+        assert_eq!(value, "[FAILED] Test");
     }
 }
@@ -0,0 +1,203 @@
+//! Effects executed when items are consumed, added, or removed from an
+//! inventory. Effects are read from the [ItemEvents] lists of the item
+//! definitions, definitions without an event list fall back to the built
+//! in effects for known items (Character respec, XP boosters, Apex points)
+//!
+//! Capacity increase items have no effect, they aren't consumable and the
+//! client applies the increase from the number of the item owned
+//!
+//! Effects are tagged by type, i.e:
+//!
+//! ```json
+//! { "type": "grantPack", "pack": "c5b3d9e6-7932-4579-ba8a-fd469ed43fda" }
+//! { "type": "grantItem", "item": "<item name>", "count": 2 }
+//! { "type": "grantCurrency", "currency": "MTXCurrency", "amount": 500 }
+//! { "type": "grantXp", "amount": 5000 }
+//! { "type": "xpBoost", "percent": 25 }
+//! { "type": "respec" }
+//! ```
+
+use super::items::{ItemDefinition, ItemEvents, ItemName};
+use crate::database::entity::currency::CurrencyType;
+use log::warn;
+use serde::Deserialize;
+use serde_json::Value;
+use uuid::{uuid, Uuid};
+
+/// Name of the "CHARACTER RESPEC" item definition
+pub const CHARACTER_RESPEC: Uuid = uuid!("52a2e172-2ae6-49f4-9914-bf3094f3a363");
+
+/// Prefix of the booster modifier used by the "EXPERIENCE ENHANCER" boosters,
+/// the modifier is suffixed by the tier of the booster (XP_Bonus_01)
+const XP_BOOSTER_MODIFIER: &str = "XP_Bonus_";
+
+/// Percentage of bonus XP given by each tier of XP booster (From the
+/// descriptions of the "EXPERIENCE ENHANCER" items)
+const XP_BOOSTER_PERCENTS: [u32; 4] = [25, 50, 75, 100];
+
+/// Effect that can be executed for an item event
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ItemEffect {
+    /// Opens the item pack with the provided name
+    GrantPack { pack: ItemName },
+    /// Grants `count` of the item with the provided name
+    GrantItem {
+        item: ItemName,
+        #[serde(default = "default_count")]
+        count: u32,
+    },
+    /// Grants an amount of currency
+    GrantCurrency { currency: CurrencyType, amount: u32 },
+    /// Grants XP to the targeted character
+    GrantXp { amount: u32 },
+    /// Boosts the XP earned from the next completed mission
+    XpBoost { percent: u32 },
+    /// Resets the skill trees of the targeted character
+    Respec,
+}
+
+/// Default count for granted items
+fn default_count() -> u32 {
+    1
+}
+
+/// Events on an item that can execute effects
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemEventKind {
+    /// The item was consumed
+    Consume,
+    /// The item was added to the inventory
+    Add,
+    /// The last of the item was removed from the inventory
+    Remove,
+}
+
+impl ItemEventKind {
+    /// Gets the list of event values for this kind of event from `events`
+    fn values(self, events: &ItemEvents) -> Option<&Vec<Value>> {
+        match self {
+            Self::Consume => events.on_consume.as_ref(),
+            Self::Add => events.on_add.as_ref(),
+            Self::Remove => events.on_remove.as_ref(),
+        }
+    }
+}
+
+/// Gets the effects that should be executed for the `kind` of event on
+/// the item `definition`, values that aren't known effects are skipped
+pub fn item_effects(definition: &ItemDefinition, kind: ItemEventKind) -> Vec<ItemEffect> {
+    let Some(values) = kind.values(&definition.events) else {
+        return match kind {
+            ItemEventKind::Consume => default_consume_effects(definition),
+            ItemEventKind::Add => default_add_effects(definition),
+            ItemEventKind::Remove => Vec::new(),
+        };
+    };
+
+    values
+        .iter()
+        .filter_map(|value| match ItemEffect::deserialize(value) {
+            Ok(effect) => Some(effect),
+            Err(err) => {
+                warn!(
+                    "Skipping unknown {:?} effect on item {}: {}",
+                    kind, definition.name, err
+                );
+                None
+            }
+        })
+        .collect()
+}
+
+/// Built in consume effects for known consumables that
+/// don't define their own effects
+fn default_consume_effects(definition: &ItemDefinition) -> Vec<ItemEffect> {
+    if definition.name == CHARACTER_RESPEC {
+        return vec![ItemEffect::Respec];
+    }
+
+    let xp_boost = definition
+        .attributes
+        .booster_modifier
+        .as_deref()
+        .and_then(|modifier| modifier.strip_prefix(XP_BOOSTER_MODIFIER))
+        .and_then(|tier| tier.parse::<usize>().ok())
+        .and_then(|tier| XP_BOOSTER_PERCENTS.get(tier.checked_sub(1)?));
+
+    match xp_boost {
+        Some(percent) => vec![ItemEffect::XpBoost { percent: *percent }],
+        None => Vec::new(),
+    }
+}
+
+/// Built in add effects for known items that don't
+/// define their own effects
+fn default_add_effects(definition: &ItemDefinition) -> Vec<ItemEffect> {
+    // Apex point items grant their amount of apex points
+    match definition.apex_points() {
+        Some(amount) => vec![ItemEffect::GrantCurrency {
+            currency: CurrencyType::ApexPoints,
+            amount,
+        }],
+        None => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{item_effects, ItemEffect, ItemEventKind, CHARACTER_RESPEC};
+    use crate::{
+        database::entity::currency::CurrencyType,
+        definitions::items::{ItemEvents, Items},
+    };
+    use serde_json::json;
+    use uuid::uuid;
+
+    /// Tests that effects are parsed from the item events and
+    /// the known consumables use their built in effects
+    #[test]
+    fn test_item_effects() {
+        let items = Items::get();
+
+        let respec = items.by_name(&CHARACTER_RESPEC).unwrap();
+        assert_eq!(
+            item_effects(respec, ItemEventKind::Consume),
+            vec![ItemEffect::Respec]
+        );
+        assert!(item_effects(respec, ItemEventKind::Add).is_empty());
+
+        // EXPERIENCE ENHANCER III
+        let booster = items
+            .by_name(&uuid!("83d69f5b-3f97-4d41-ad76-99ea37a35ba8"))
+            .unwrap();
+        assert_eq!(
+            item_effects(booster, ItemEventKind::Consume),
+            vec![ItemEffect::XpBoost { percent: 75 }]
+        );
+
+        let mut definition = respec.clone();
+        definition.events = ItemEvents {
+            on_consume: Some(vec![
+                json!({ "type": "grantItem", "item": CHARACTER_RESPEC }),
+                json!({ "type": "grantCurrency", "currency": "MTXCurrency", "amount": 50 }),
+                json!({ "type": "unknown" }),
+            ]),
+            on_add: None,
+            on_remove: None,
+        };
+        assert_eq!(
+            item_effects(&definition, ItemEventKind::Consume),
+            vec![
+                ItemEffect::GrantItem {
+                    item: CHARACTER_RESPEC,
+                    count: 1
+                },
+                ItemEffect::GrantCurrency {
+                    currency: CurrencyType::Mtx,
+                    amount: 50
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,190 @@
+//! Search index over the item definitions, used for searching the
+//! inventory by localized item name and custom attribute values
+
+use super::items::{Category, ItemDefinition, ItemName, ItemRarity, Items};
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops::Bound,
+    sync::OnceLock,
+};
+
+/// Search index for the item definitions
+pub struct ItemSearchIndex {
+    /// Indexed definitions
+    entries: Vec<SearchEntry>,
+    /// Lowercase words from the localized item names mapped to
+    /// the indexes of the entries containing them
+    words: BTreeMap<String, Vec<usize>>,
+}
+
+/// Indexed item definition
+struct SearchEntry {
+    /// Name of the indexed definition
+    name: ItemName,
+    /// Category of the definition
+    category: Category,
+    /// Rarity of the definition
+    rarity: Option<ItemRarity>,
+    /// Lowercase custom attribute keys mapped to their lowercase values
+    attributes: HashMap<String, String>,
+}
+
+/// Query to search the index with
+#[derive(Debug, Default)]
+pub struct ItemSearchQuery<'a> {
+    /// Text the localized item name must contain words starting with
+    pub text: Option<&'a str>,
+    /// Category the item must be within
+    pub category: Option<&'a Category>,
+    /// Rarity the item must have
+    pub rarity: Option<ItemRarity>,
+    /// Custom attribute keys and the values they must have
+    pub attributes: Vec<(&'a str, &'a str)>,
+}
+
+/// Static storage for the index once its built
+static STORE: OnceLock<ItemSearchIndex> = OnceLock::new();
+
+impl ItemSearchIndex {
+    /// Gets a static reference to the global [ItemSearchIndex], the
+    /// index is built from the [Items] on first use
+    pub fn get() -> &'static ItemSearchIndex {
+        STORE.get_or_init(|| Self::new(Items::get().all()))
+    }
+
+    fn new(definitions: &[ItemDefinition]) -> Self {
+        let mut entries = Vec::with_capacity(definitions.len());
+        let mut words: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+
+        for (index, definition) in definitions.iter().enumerate() {
+            if let Some(loc_name) = &definition.i18n_name.loc_name {
+                for word in split_words(loc_name) {
+                    let indexes = words.entry(word).or_default();
+                    if indexes.last() != Some(&index) {
+                        indexes.push(index);
+                    }
+                }
+            }
+
+            let attributes = definition
+                .custom_attributes
+                .iter()
+                .map(|(key, value)| {
+                    let value = match value.as_str() {
+                        Some(value) => value.to_lowercase(),
+                        None => value.to_string().to_lowercase(),
+                    };
+                    (key.to_lowercase(), value)
+                })
+                .collect();
+
+            entries.push(SearchEntry {
+                name: definition.name,
+                category: definition.category.clone(),
+                rarity: definition.rarity,
+                attributes,
+            });
+        }
+
+        Self { entries, words }
+    }
+
+    /// Searches the index responding with the names of the definitions
+    /// that match all the parts of the `query`
+    pub fn search(&self, query: &ItemSearchQuery<'_>) -> Vec<ItemName> {
+        let mut matches: Vec<bool> = vec![true; self.entries.len()];
+
+        // Every word in the text must be the start of a word in the name
+        if let Some(text) = query.text {
+            for word in split_words(text) {
+                let mut word_matches = vec![false; self.entries.len()];
+                let range = self
+                    .words
+                    .range::<str, _>((Bound::Included(word.as_str()), Bound::Unbounded))
+                    .take_while(|(indexed, _)| indexed.starts_with(&word));
+
+                for (_, indexes) in range {
+                    for index in indexes {
+                        word_matches[*index] = true;
+                    }
+                }
+
+                matches
+                    .iter_mut()
+                    .zip(word_matches)
+                    .for_each(|(matched, word_matched)| *matched &= word_matched);
+            }
+        }
+
+        let attributes: Vec<(String, String)> = query
+            .attributes
+            .iter()
+            .map(|(key, value)| (key.to_lowercase(), value.to_lowercase()))
+            .collect();
+
+        self.entries
+            .iter()
+            .zip(matches)
+            .filter(|(_, matched)| *matched)
+            .map(|(entry, _)| entry)
+            .filter(|entry| match query.category {
+                Some(category) => entry.category.is_within(category),
+                None => true,
+            })
+            .filter(|entry| query.rarity.is_none() || entry.rarity == query.rarity)
+            .filter(|entry| {
+                attributes
+                    .iter()
+                    .all(|(key, value)| entry.attributes.get(key) == Some(value))
+            })
+            .map(|entry| entry.name)
+            .collect()
+    }
+}
+
+/// Splits the `text` into lowercase words for indexing and searching
+fn split_words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|value: char| !value.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ItemSearchIndex, ItemSearchQuery};
+    use crate::definitions::items::{Category, Items};
+    use uuid::uuid;
+
+    /// Tests searching by name words and attribute values
+    #[test]
+    fn test_search() {
+        let items = Items::get();
+        let index = ItemSearchIndex::new(items.all());
+
+        // COBRA RPG
+        let cobra = uuid!("eaefec2a-d892-498b-a175-e5d2048ae39a");
+        let results = index.search(&ItemSearchQuery {
+            text: Some("cob rp"),
+            ..Default::default()
+        });
+        assert!(results.contains(&cobra));
+
+        let results = index.search(&ItemSearchQuery {
+            attributes: vec![("consumableType", "ammo")],
+            ..Default::default()
+        });
+        assert!(!results.is_empty());
+        assert!(!results.contains(&cobra));
+
+        // Every result must be within the requested category
+        let category: Category = "4".parse().unwrap();
+        let results = index.search(&ItemSearchQuery {
+            category: Some(&category),
+            ..Default::default()
+        });
+        assert!(results.contains(&cobra));
+        assert!(results.iter().all(|name| items
+            .by_name(name)
+            .is_some_and(|definition| definition.category.is_within(&category))));
+    }
+}
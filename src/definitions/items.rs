@@ -1,16 +1,16 @@
 use crate::{
-    database::entity::{inventory_items::ItemId, InventoryItem, User},
+    database::entity::inventory_items::ItemId,
     definitions::{
-        characters::acquire_item_character,
-        classes::Classes,
-        i18n::{I18nDescription, I18nName, Localized},
-        level_tables::LevelTables,
+        classes::EquipmentSlot,
+        definition_init,
+        i18n::{I18nDescription, I18nKey, I18nName, Localized},
+        strike_teams::StrikeTeamTraitAction,
     },
+    utils::ImStr,
 };
-use anyhow::{anyhow, Context};
+use anyhow::Context;
 use log::debug;
 use num_enum::{TryFromPrimitive, TryFromPrimitiveError};
-use sea_orm::ConnectionTrait;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use serde_with::{serde_as, skip_serializing_none, DeserializeAs, DisplayFromStr};
@@ -22,64 +22,13 @@ use std::{
     sync::OnceLock,
 };
 use thiserror::Error;
-use uuid::{uuid, Uuid};
+use uuid::Uuid;
 
-use super::shared::CustomAttributes;
+use super::{load_definition, shared::CustomAttributes};
 
-/// Item definitions (628)
+/// Item definitions (630)
 const INVENTORY_DEFINITIONS: &str = include_str!("../resources/data/inventoryDefinitions.json");
 
-/// Adds the collection of default items and characters to the
-/// provided user
-pub async fn create_default_items<C>(db: &C, user: &User) -> anyhow::Result<()>
-where
-    C: ConnectionTrait + Send,
-{
-    let item_definitions = Items::get();
-    let classes = Classes::get();
-    let level_tables = LevelTables::get();
-
-    // Create models from initial item defs
-    let ids = [
-        uuid!("af3a2cf0-dff7-4ca8-9199-73ce546c3e7b"), // HUMAN MALE SOLDIER
-        uuid!("79f3511c-55da-67f0-5002-359c370015d8"), // HUMAN FEMALE SOLDIER
-        uuid!("a3960123-3625-4126-82e4-1f9a127d33aa"), // HUMAN MALE ENGINEER
-        uuid!("c756c741-1bc8-47a8-9f35-b7ca943ba034"), // HUMAN FEMALE ENGINEER
-        uuid!("baae0381-8690-4097-ae6d-0c16473519b4"), // HUMAN MALE SENTINEL
-        uuid!("319ffe5d-f8fb-4217-bd2f-2e8af4f53fc8"), // HUMAN FEMALE SENTINEL
-        uuid!("7fd30824-e20c-473e-b906-f4f30ebc4bb0"), // HUMAN MALE VANGUARD
-        uuid!("96fa16c5-9f2b-46f8-a491-a4b0a24a1089"), // HUMAN FEMALE VANGUARD
-        uuid!("34aeef66-a030-445e-98e2-1513c0c78df4"), // HUMAN MALE INFILTRATOR
-        uuid!("cae8a2f3-fdaf-471c-9391-c29f6d4308c3"), // HUMAN FEMALE INFILTRATOR
-        uuid!("e4357633-93bc-4596-99c3-4cc0a49b2277"), // HUMAN MALE ADEPT
-        uuid!("e2f76cf1-4b42-4dba-9751-f2add5c3f654"), // HUMAN FEMALE ADEPT
-        uuid!("4ccc7f54-791c-4b66-954b-a0bd6496f210"), // M-3 PREDATOR
-        uuid!("d5bf2213-d2d2-f892-7310-c39a15fb2ef3"), // M-8 AVENGER
-        uuid!("38e07595-764b-4d9c-b466-f26c7c416860"), // VIPER
-        uuid!("ca7d0f24-fc19-4a78-9d25-9c84eb01e3a5"), // M-23 KATANA
-    ];
-
-    for item in ids {
-        let definition = item_definitions
-            .by_name(&item)
-            .ok_or(anyhow!("Missing default item '{item}'"))?;
-
-        InventoryItem::add_item(db, user, definition.name, 1, definition.capacity)
-            .await
-            .unwrap();
-
-        // Handle character creation if the item is a character item
-        if definition
-            .category
-            .is_within(&Category::Base(BaseCategory::Characters))
-        {
-            acquire_item_character(db, user, &definition.name, classes, level_tables).await?;
-        }
-    }
-
-    Ok(())
-}
-
 /// Type of the name for items, names are [Uuid]s with some exceptions (Thanks EA)
 pub type ItemName = Uuid;
 
@@ -100,6 +49,8 @@ pub struct Items {
 /// (Allows the definitions to be passed with static lifetimes)
 static STORE: OnceLock<Items> = OnceLock::new();
 
+definition_init!(Items, STORE, load);
+
 impl Items {
     /// Gets a static reference to the global [Items] collection
     pub fn get() -> &'static Items {
@@ -107,8 +58,15 @@ impl Items {
     }
 
     fn load() -> anyhow::Result<Self> {
-        let values: Vec<ItemDefinition> = serde_json::from_str(INVENTORY_DEFINITIONS)
-            .context("Failed to load inventory definitions")?;
+        let mut values: Vec<ItemDefinition> =
+            load_definition("inventoryDefinitions.json", INVENTORY_DEFINITIONS)
+                .context("Failed to load inventory definitions")?;
+
+        // Parse the known custom attributes
+        for definition in &mut values {
+            definition.attributes = ItemAttributes::parse(&definition.custom_attributes)
+                .with_context(|| format!("Invalid attributes for item {}", definition.name))?;
+        }
 
         debug!("Loaded {} item definition(s)", values.len());
 
@@ -148,6 +106,10 @@ pub struct ItemDefinition {
     /// Custom attributes associated with the item
     pub custom_attributes: CustomAttributes,
 
+    /// Known attributes parsed from the [ItemDefinition::custom_attributes]
+    #[serde(skip)]
+    pub attributes: ItemAttributes,
+
     /// Category the item falls under
     #[serde_as(as = "serde_with::DisplayFromStr")]
     pub category: Category,
@@ -175,12 +137,10 @@ pub struct ItemDefinition {
     pub deletable: Option<bool>,
 
     /// Specified if this item requires another item having reached its
-    /// capacity before this item can be dropped.
+    /// capacity before this item can be dropped or granted.
     ///
-    /// TODO: This field needs to be handled in store rewards
     /// Name of definition that this item depends on
     /// (Requires the item to reach its capacity before it can be dropped)
-    /// TODO: Handle this when doing store rewards
     pub unlock_definition: Option<ItemName>,
 
     /// Activity events that should be created when various events are
@@ -190,9 +150,6 @@ pub struct ItemDefinition {
     #[serde(flatten)]
     pub events: ItemEvents,
 
-    /// TODO: I can't seem to find this field..? why have I added it..?
-    pub restrictions: Option<String>,
-
     /// The default namespace this item belongs to
     pub default_namespace: InventoryNamespace,
 
@@ -223,6 +180,19 @@ impl ItemDefinition {
         self.consumable.unwrap_or_default()
     }
 
+    /// Amount of apex points granted by an apex points item, the amount is
+    /// only present within the i18n name of the item (FREE_300_APEX_POINTS_ON_ADD)
+    pub fn apex_points(&self) -> Option<u32> {
+        let I18nKey::Raw(name) = &self.i18n_name.i18n_name else {
+            return None;
+        };
+
+        name.strip_prefix("FREE_")?
+            .strip_suffix("_APEX_POINTS_ON_ADD")?
+            .parse()
+            .ok()
+    }
+
     #[inline]
     pub fn is_droppable(&self) -> bool {
         self.droppable.unwrap_or_default()
@@ -232,6 +202,91 @@ impl ItemDefinition {
     pub fn is_deletable(&self) -> bool {
         self.deletable.unwrap_or_default()
     }
+
+    /// Checks whether the `other` item can be attached to this item
+    /// based on the [ItemDefinition::attachable_categories]
+    pub fn can_attach(&self, other: &ItemDefinition) -> bool {
+        self.attachable_categories
+            .iter()
+            .any(|category| other.category.is_within(category))
+    }
+
+    /// Checks whether the item can be equipped in the equipment `slot`
+    /// based on the [ItemDefinition::category]
+    pub fn can_equip(&self, slot: EquipmentSlot) -> bool {
+        let categories = slot.categories();
+        categories.is_empty()
+            || categories
+                .iter()
+                .any(|category| self.category.base_eq(category))
+    }
+}
+
+/// Known item custom attributes, parsed and validated from the item
+/// [CustomAttributes] when loaded. Only the attributes the server acts
+/// on are parsed, the remaining attributes (weapon types, textures, ...)
+/// are only used by the client and are left in the [CustomAttributes]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ItemAttributes {
+    /// Type of consumable (consumableType), used by pack filters
+    pub consumable_type: Option<ConsumableType>,
+    /// Modifier applied by boosters (boosterModifier), used to
+    /// find the XP boost of the booster
+    pub booster_modifier: Option<ImStr>,
+    /// Change made to a strike team when consumed (StrikeTeamTraitAction)
+    pub strike_team_trait_action: Option<StrikeTeamTraitAction>,
+}
+
+/// Errors that can occur when parsing [ItemAttributes]
+#[derive(Debug, Error)]
+#[error("Invalid attribute '{key}': {source}")]
+pub struct ItemAttributeError {
+    /// The attribute key that was invalid
+    key: &'static str,
+    /// The underlying parsing error
+    source: serde_json::Error,
+}
+
+impl ItemAttributes {
+    /// Parses the known attributes from the provided `attributes`
+    pub fn parse(attributes: &CustomAttributes) -> Result<Self, ItemAttributeError> {
+        /// Parses the attribute with the provided `key`
+        fn attr<T>(
+            attributes: &CustomAttributes,
+            key: &'static str,
+        ) -> Result<Option<T>, ItemAttributeError>
+        where
+            T: serde::de::DeserializeOwned,
+        {
+            attributes
+                .get_as(key)
+                .transpose()
+                .map_err(|source| ItemAttributeError { key, source })
+        }
+
+        Ok(Self {
+            consumable_type: attr(attributes, "consumableType")?,
+            booster_modifier: attr(attributes, "boosterModifier")?,
+            strike_team_trait_action: attr(attributes, "StrikeTeamTraitAction")?,
+        })
+    }
+}
+
+/// Types of consumable items
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ConsumableType {
+    /// Offensive consumables such as "COBRA RPG"
+    Offensive,
+    /// Weapon boosters such as "ASSAULT RIFLE RAIL AMP"
+    Weapon,
+    /// Power boosters
+    Power,
+    /// Ammo boosters such as "CRYO AMMO"
+    Ammo,
+    /// Defensive consumables such as "REVIVE PACK"
+    Defensive,
+    /// Progression boosters
+    Progression,
 }
 
 /// Activity events that should be created when
@@ -601,11 +656,54 @@ impl FromStr for BaseCategory {
 
 #[cfg(test)]
 mod test {
-    use super::Items;
+    use super::{BaseCategory, Items};
+    use crate::definitions::classes::EquipmentSlot;
 
-    /// Tests ensuring loading succeeds
+    /// Tests that every apex points item has an amount of apex points
     #[test]
-    fn ensure_load_succeed() {
-        _ = Items::load().unwrap();
+    fn test_apex_points() {
+        let items = Items::get();
+        let mut apex_items = items
+            .values
+            .iter()
+            .filter(|item| item.category.base_eq(&BaseCategory::ApexPoints))
+            .peekable();
+
+        assert!(apex_items.peek().is_some());
+        for item in apex_items {
+            assert!(item.apex_points().is_some_and(|amount| amount > 0));
+        }
+
+        let item = items
+            .values
+            .iter()
+            .find(|item| !item.category.base_eq(&BaseCategory::ApexPoints))
+            .unwrap();
+        assert_eq!(item.apex_points(), None);
+    }
+
+    /// Tests that real weapons only fit weapon slots and only accept the
+    /// mods for their weapon type
+    #[test]
+    fn test_equip_restrictions() {
+        let items = Items::get();
+        let weapon = items
+            .values
+            .iter()
+            .find(|item| item.category.base_eq(&BaseCategory::Weapons))
+            .unwrap();
+
+        assert!(weapon.can_equip(EquipmentSlot::WeaponSlot1));
+        assert!(!weapon.can_equip(EquipmentSlot::Booster1));
+        assert!(!weapon.can_equip(EquipmentSlot::EquipmentSlot));
+
+        let mods = items
+            .values
+            .iter()
+            .filter(|item| item.category.base_eq(&BaseCategory::WeaponMods));
+        let (compatible, incompatible): (Vec<_>, Vec<_>) =
+            mods.partition(|item| weapon.can_attach(item));
+        assert!(!compatible.is_empty());
+        assert!(!incompatible.is_empty());
     }
 }
@@ -1,6 +1,6 @@
 //! Leveling table structures and logic
 
-use super::shared::CustomAttributes;
+use super::{definition_init, load_definition, shared::CustomAttributes};
 use crate::utils::ImStr;
 use anyhow::Context;
 use log::debug;
@@ -22,6 +22,8 @@ pub struct LevelTables {
 /// (Allows the definitions to be passed with static lifetimes)
 static STORE: OnceLock<LevelTables> = OnceLock::new();
 
+definition_init!(LevelTables, STORE, load);
+
 impl LevelTables {
     /// Gets a static reference to the global [LevelTables] collection
     pub fn get() -> &'static LevelTables {
@@ -30,7 +32,7 @@ impl LevelTables {
 
     /// Creates and loads the level tables from [LEVEL_TABLE_DEFINITIONS]
     fn load() -> anyhow::Result<Self> {
-        let values: Vec<LevelTable> = serde_json::from_str(LEVEL_TABLE_DEFINITIONS)
+        let values: Vec<LevelTable> = load_definition("levelTables.json", LEVEL_TABLE_DEFINITIONS)
             .context("Failed to parse level table definitions")?;
 
         debug!("Loaded {} level table definition(s)", values.len());
@@ -172,13 +174,3 @@ impl From<(u32, u32, u32)> for ProgressionXp {
         }
     }
 }
-#[cfg(test)]
-mod test {
-    use super::LevelTables;
-
-    /// Tests ensuring loading succeeds
-    #[test]
-    fn ensure_load_succeed() {
-        _ = LevelTables::load().unwrap();
-    }
-}
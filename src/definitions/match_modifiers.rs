@@ -1,4 +1,4 @@
-use super::shared::CustomAttributes;
+use super::{definition_init, parse_definition, shared::CustomAttributes};
 use crate::database::entity::currency::CurrencyType;
 use anyhow::Context;
 use log::debug;
@@ -17,6 +17,8 @@ pub struct MatchModifiers {
 /// (Allows the definitions to be passed with static lifetimes)
 static STORE: OnceLock<MatchModifiers> = OnceLock::new();
 
+definition_init!(MatchModifiers, STORE, load);
+
 impl MatchModifiers {
     /// Gets a static reference to the global [MatchModifiers] collection
     pub fn get() -> &'static MatchModifiers {
@@ -24,8 +26,9 @@ impl MatchModifiers {
     }
 
     fn load() -> anyhow::Result<Self> {
-        let values: Vec<MatchModifier> = serde_json::from_str(MATCH_MODIFIER_DEFINITIONS)
-            .context("Failed to load match modifier definitions")?;
+        let values: Vec<MatchModifier> =
+            parse_definition("matchModifiers.json", MATCH_MODIFIER_DEFINITIONS)
+                .context("Failed to load match modifier definitions")?;
 
         debug!("Loaded {} match modifier definition(s)", values.len(),);
 
@@ -100,14 +103,3 @@ impl ModifierAmount {
         self.flat_amount + adative_value
     }
 }
-
-#[cfg(test)]
-mod test {
-    use super::MatchModifiers;
-
-    /// Tests ensuring loading succeeds
-    #[test]
-    fn ensure_load_succeed() {
-        _ = MatchModifiers::load().unwrap();
-    }
-}
@@ -6,7 +6,10 @@ pub mod badges;
 pub mod challenges;
 pub mod characters;
 pub mod classes;
+pub mod equipment_sets;
 pub mod i18n;
+pub mod item_effects;
+pub mod item_search;
 pub mod items;
 pub mod level_tables;
 pub mod match_modifiers;
@@ -14,4 +17,237 @@ pub mod packs;
 pub mod shared;
 pub mod skills;
 pub mod store_catalogs;
+pub mod strike_team_flair;
 pub mod strike_teams;
+pub mod titles;
+pub mod welcome_kit;
+
+use anyhow::Context;
+use log::info;
+use serde::de::DeserializeOwned;
+use std::{
+    fmt::{self, Display},
+    path::Path,
+    sync::atomic::{AtomicU64, Ordering},
+};
+use thiserror::Error;
+
+/// Directory operators can place definition files within to override
+/// the definitions embedded in the server
+const DEFINITIONS_OVERRIDE_DIR: &str = "data/definitions";
+
+/// Total size of the definition files that have been loaded
+static LOADED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// Total size in bytes of the definition files that have been loaded
+pub fn loaded_bytes() -> u64 {
+    LOADED_BYTES.load(Ordering::Relaxed)
+}
+
+/// Error produced when a definition file fails to parse, includes the
+/// path to the offending key within the file
+#[derive(Debug, Error)]
+#[error("{file}: error at `{path}`: {source}")]
+pub struct DefinitionParseError {
+    /// Name of the file that failed to parse
+    pub file: &'static str,
+    /// Path to the key that failed to parse
+    pub path: String,
+    /// The underlying parsing error, includes the line and column
+    #[source]
+    pub source: serde_json::Error,
+}
+
+/// Parses the definition JSON `data` from the provided `file` tracking
+/// the path to any keys that fail to parse
+pub fn parse_definition<T>(file: &'static str, data: &str) -> Result<T, DefinitionParseError>
+where
+    T: DeserializeOwned,
+{
+    let deserializer = &mut serde_json::Deserializer::from_str(data);
+    serde_path_to_error::deserialize(deserializer).map_err(|err| DefinitionParseError {
+        file,
+        path: err.path().to_string(),
+        source: err.into_inner(),
+    })
+}
+
+/// Loads the definition `file` from the [DEFINITIONS_OVERRIDE_DIR] when an
+/// override is present, otherwise parses the `embedded` copy of the file
+pub fn load_definition<T>(file: &'static str, embedded: &str) -> anyhow::Result<T>
+where
+    T: DeserializeOwned,
+{
+    let path = Path::new(DEFINITIONS_OVERRIDE_DIR).join(file);
+    if !path.exists() {
+        LOADED_BYTES.fetch_add(embedded.len() as u64, Ordering::Relaxed);
+        return Ok(parse_definition(file, embedded)?);
+    }
+
+    info!("Loading {} from {}", file, path.display());
+
+    let data = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let value = parse_definition(file, &data)
+        .with_context(|| format!("Invalid override {}", path.display()))?;
+    LOADED_BYTES.fetch_add(data.len() as u64, Ordering::Relaxed);
+
+    Ok(value)
+}
+
+/// Implements `init` for the definitions `$ty` which loads the definitions
+/// using `$load` into the global `$store`, also adds a test ensuring that
+/// loading the definitions succeeds
+macro_rules! definition_init {
+    ($ty:ident, $store:ident, $load:ident) => {
+        impl $ty {
+            /// Loads the definitions into the global store, used at startup so
+            /// that errors can be reported rather than panicking on first use
+            pub fn init() -> anyhow::Result<()> {
+                let value = Self::$load()?;
+                _ = $store.set(value);
+                Ok(())
+            }
+        }
+
+        #[cfg(test)]
+        mod load_test {
+            /// Tests ensuring loading succeeds
+            #[test]
+            fn ensure_load_succeed() {
+                _ = super::$ty::$load().unwrap();
+            }
+        }
+    };
+}
+
+pub(crate) use definition_init;
+
+/// Report of all the definitions that failed to load
+#[derive(Debug, Default)]
+pub struct DefinitionsReport {
+    /// Name of the definitions paired with the error that occurred
+    pub errors: Vec<(&'static str, anyhow::Error)>,
+}
+
+impl Display for DefinitionsReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} definition(s) failed to load:", self.errors.len())?;
+        for (name, err) in &self.errors {
+            writeln!(f, "  - {}: {:#}", name, err)?;
+        }
+        Ok(())
+    }
+}
+
+impl DefinitionsReport {
+    /// Records the `result` of loading the `name` definitions,
+    /// returns whether the definitions loaded successfully
+    fn record(&mut self, name: &'static str, result: anyhow::Result<()>) -> bool {
+        match result {
+            Ok(()) => true,
+            Err(err) => {
+                self.errors.push((name, err));
+                false
+            }
+        }
+    }
+
+    /// Converts the report into a result, only an error when
+    /// some of the definitions failed to load
+    fn into_result(self) -> Result<(), DefinitionsReport> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(self)
+        }
+    }
+}
+
+/// Loads all the shared definitions, collecting any errors that
+/// occur into a single report rather than stopping at the first
+pub fn init() -> Result<(), DefinitionsReport> {
+    let mut report = DefinitionsReport::default();
+
+    report.record("translations", i18n::I18n::init());
+    report.record("classes", classes::Classes::init());
+    report.record("level tables", level_tables::LevelTables::init());
+    report.record("badges", badges::Badges::init());
+    report.record("match modifiers", match_modifiers::MatchModifiers::init());
+    report.record("strike teams", strike_teams::StrikeTeams::init());
+    let flair_loaded = report.record(
+        "strike team flair",
+        strike_team_flair::StrikeTeamFlairs::init(),
+    );
+    report.record("skills", skills::Skills::init());
+    report.record("store catalog", store_catalogs::StoreCatalogs::init());
+
+    // Challenges, the welcome kit, and equipment sets reference items so they
+    // can only be validated once the items have loaded
+    if report.record("items", items::Items::init()) {
+        // Challenges also reference the strike team flair they reward
+        if flair_loaded && report.record("challenges", challenges::Challenges::init()) {
+            // Titles reference the challenges that grant them
+            report.record("player titles", titles::PlayerTitles::init());
+        }
+        report.record("welcome kit", welcome_kit::WelcomeKit::init());
+        report.record("equipment sets", equipment_sets::EquipmentSets::init());
+    }
+
+    report.into_result()
+}
+
+/// Loads all the shared definitions like [init] while also strictly
+/// checking the operator defined definitions which are normally skipped
+/// when invalid
+pub fn check() -> Result<(), DefinitionsReport> {
+    let mut report = match init() {
+        Ok(()) => DefinitionsReport::default(),
+        Err(report) => report,
+    };
+
+    // Custom challenges reference items and flair so can only be checked when they loaded
+    if !report
+        .errors
+        .iter()
+        .any(|(name, _)| *name == "items" || *name == "strike team flair")
+    {
+        report.record("custom challenges", challenges::Challenges::check_custom());
+    }
+
+    report.into_result()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{load_definition, parse_definition};
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    #[allow(unused)]
+    struct Definition {
+        name: String,
+        count: u32,
+    }
+
+    /// Tests that parse errors include the path to the offending key
+    #[test]
+    fn test_parse_error_path() {
+        let data = r#"[{"name": "a", "count": 1}, {"name": "b", "count": "x"}]"#;
+        let err = parse_definition::<Vec<Definition>>("test.json", data).unwrap_err();
+
+        assert_eq!(err.file, "test.json");
+        assert_eq!(err.path, "[1].count");
+        assert_eq!(err.source.line(), 1);
+    }
+
+    /// Tests that the embedded definitions are used when there is no override
+    #[test]
+    fn test_load_without_override() {
+        let data = r#"[{"name": "a", "count": 1}]"#;
+        let values = load_definition::<Vec<Definition>>("missingOverride.json", data).unwrap();
+
+        assert_eq!(values.len(), 1);
+        assert_eq!(values[0].count, 1);
+    }
+}
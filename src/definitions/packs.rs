@@ -5,12 +5,18 @@
 //! be accurate to the actual game loot tables.
 
 use crate::{
+    config::RarityWeights,
     database::entity::{InventoryItem, User},
-    definitions::items::{BaseCategory, Category, ItemDefinition, ItemName, ItemRarity, Items},
+    definitions::items::{
+        BaseCategory, Category, ConsumableType, ItemDefinition, ItemName, ItemRarity, Items,
+    },
+    services::items::unlock_reached,
 };
 use rand::{distributions::WeightedError, rngs::StdRng, seq::SliceRandom};
 use sea_orm::{ConnectionTrait, DbErr};
+use serde::Serialize;
 use serde_json::Value;
+use serde_with::{serde_as, DisplayFromStr};
 use std::{collections::HashMap, sync::OnceLock};
 use thiserror::Error;
 use uuid::uuid;
@@ -46,6 +52,11 @@ impl Packs {
     pub fn by_name(&self, name: &ItemName) -> Option<&Pack> {
         self.packs.get(name)
     }
+
+    /// Iterates all the defined packs
+    pub fn all(&self) -> impl Iterator<Item = &Pack> {
+        self.packs.values()
+    }
 }
 
 /// Builder for creating [Pack]s
@@ -96,7 +107,8 @@ impl Pack {
     }
 
     /// Generates a [RewardCollection] from this [Pack] using the provided
-    /// random number generator `rng`
+    /// random number generator `rng`, the rarity `weights` replace the
+    /// default rarity weights when provided
     ///
     /// Requires database access for checking item ownership requirement
     /// in order to match
@@ -107,21 +119,31 @@ impl Pack {
         user: &User,
         rng: &mut StdRng,
         defs: &'def Items,
+        weights: Option<&RarityWeights>,
         rewards: &mut RewardCollection<'def>,
     ) -> Result<(), GenerateError>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let items = Self::available_items(db, user, defs).await?;
+        self.generate_from_items(rng, &items, weights, rewards)
+    }
+
+    /// Collects the item definitions from `defs` that packs can drop for
+    /// the `user`, items with an unlock requirement are only included once
+    /// the user has reached the capacity of the unlocking item
+    pub async fn available_items<'def, C>(
+        db: &C,
+        user: &User,
+        defs: &'def Items,
+    ) -> Result<Vec<&'def ItemDefinition>, DbErr>
     where
         C: ConnectionTrait + Send,
     {
         // Creates a list of items that are applicable for dropping (If they match filters)
         // this step is done so unlock definitions and droppability don't have to be
         // done for every single collection filter
-        let mut items: Vec<&ItemDefinition> = defs
-            // Iterate all the definitions
-            .all()
-            .iter()
-            // Only include droppable items
-            .filter(|item| item.is_droppable())
-            .collect();
+        let mut items: Vec<&ItemDefinition> = Self::droppable_items(defs);
 
         // Collection of requirements for items (For requirement filtering)
         let required_items: Vec<ItemName> = items
@@ -149,30 +171,88 @@ impl Pack {
                 None => return false,
             };
 
-            // Ensure the user owns atleast one of the item
-            let owned_item = match owned_items
+            // Find the owned unlocking item
+            let owned_item = owned_items
                 .iter()
-                .find(|item| item.definition_name == definition.name)
-            {
-                Some(value) => value,
+                .find(|item| item.definition_name == *unlock_def_name);
 
-                // Requirement of owning the item not met
-                None => return false,
-            };
-
-            // If there is a max capacity for the item ensure its been reached
-            unlock_def
-                .capacity
-                .is_some_and(|capacity| owned_item.stack_size == capacity)
+            // Unlocking item must have reached its capacity
+            unlock_reached(unlock_def, owned_item)
         });
 
+        Ok(items)
+    }
+
+    /// Collects the item definitions from `defs` that can be dropped by packs
+    pub fn droppable_items(defs: &Items) -> Vec<&ItemDefinition> {
+        defs
+            // Iterate all the definitions
+            .all()
+            .iter()
+            // Only include droppable items
+            .filter(|item| item.is_droppable())
+            .collect()
+    }
+
+    /// Generates a [RewardCollection] from this [Pack] choosing from the
+    /// already filtered collection of `items`
+    pub fn generate_from_items<'def>(
+        &self,
+        rng: &mut StdRng,
+        items: &[&'def ItemDefinition],
+        weights: Option<&RarityWeights>,
+        rewards: &mut RewardCollection<'def>,
+    ) -> Result<(), GenerateError> {
         // Generate rewards from each collection
         for collection in self.collections.iter() {
-            collection.generate_rewards(rng, &items, rewards)?;
+            collection.generate_rewards(rng, items, weights, rewards)?;
         }
 
         Ok(())
     }
+
+    /// Lists the items each collection of this [Pack] can drop along with
+    /// their odds when choosing from the already filtered `items`
+    pub fn preview<'def>(
+        &self,
+        items: &[&'def ItemDefinition],
+        weights: Option<&RarityWeights>,
+    ) -> Vec<PackCollectionPreview<'def>> {
+        self.collections
+            .iter()
+            .map(|collection| collection.preview(items, weights))
+            .collect()
+    }
+}
+
+/// Preview of the items a collection of a pack can drop
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackCollectionPreview<'def> {
+    /// Number of items chosen from the collection, [None] when
+    /// every item is given
+    pub amount: Option<u32>,
+    /// Stack size of each item given
+    pub stack_size: u32,
+    /// The items that can be chosen
+    pub items: Vec<PackItemOdds<'def>>,
+}
+
+/// Item that can be dropped by a pack collection along with its odds
+#[serde_as]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackItemOdds<'def> {
+    /// Definition name of the item
+    pub name: &'def ItemName,
+    /// Rarity of the item
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    pub rarity: Option<ItemRarity>,
+    /// Weight of the item within the collection
+    pub weight: u32,
+    /// Chance of this item being one of the items chosen from the
+    /// collection (0.0 to 1.0)
+    pub chance: f32,
 }
 
 /// Chance for gaining an item from a specific filter
@@ -223,24 +303,65 @@ impl PackCollection {
         self
     }
 
-    fn generate_rewards<'def>(
+    /// Applies the collection filter and weights to the `items`
+    fn weighted_items<'def>(
         &self,
-        rng: &mut StdRng,
         items: &[&'def ItemDefinition],
-        rewards: &mut RewardCollection<'def>,
-    ) -> Result<(), GenerateError> {
-        // Collection of items with the filter and weights applied
-        let weighted_items: Vec<(&ItemDefinition, Weight)> = items
+        weights: Option<&RarityWeights>,
+    ) -> Vec<(&'def ItemDefinition, Weight)> {
+        items
             .iter()
             .filter_map(|item| {
-                let weight = self.filter.apply_filter(item)?;
+                let weight = self.filter.apply_filter(item, weights)?;
                 // Ensure non zero weights
                 let weight = weight.max(1);
 
                 Some((*item, weight))
             })
+            .collect()
+    }
+
+    fn preview<'def>(
+        &self,
+        items: &[&'def ItemDefinition],
+        weights: Option<&RarityWeights>,
+    ) -> PackCollectionPreview<'def> {
+        let weighted_items = self.weighted_items(items, weights);
+        let item_weights: Vec<Weight> = weighted_items.iter().map(|(_, weight)| *weight).collect();
+        let chances = match self.amount {
+            Some(amount) => inclusion_chances(&item_weights, amount as usize),
+            // Every item is given
+            None => vec![1.0; item_weights.len()],
+        };
+
+        let items = weighted_items
+            .into_iter()
+            .zip(chances)
+            .map(|((definition, weight), chance)| PackItemOdds {
+                name: &definition.name,
+                rarity: definition.rarity,
+                weight,
+                chance,
+            })
             .collect();
 
+        PackCollectionPreview {
+            amount: self.amount,
+            stack_size: self.stack_size,
+            items,
+        }
+    }
+
+    fn generate_rewards<'def>(
+        &self,
+        rng: &mut StdRng,
+        items: &[&'def ItemDefinition],
+        weights: Option<&RarityWeights>,
+        rewards: &mut RewardCollection<'def>,
+    ) -> Result<(), GenerateError> {
+        // Collection of items with the filter and weights applied
+        let weighted_items = self.weighted_items(items, weights);
+
         // Handle complete collection rewards
         let amount = match self.amount {
             Some(value) => value,
@@ -295,7 +416,7 @@ pub struct ItemReward<'a> {
 }
 
 impl<'a> RewardCollection<'a> {
-    fn add_reward(&mut self, definition: &'a ItemDefinition, stack_size: u32) {
+    pub fn add_reward(&mut self, definition: &'a ItemDefinition, stack_size: u32) {
         let existing = self
             .rewards
             .iter_mut()
@@ -316,6 +437,93 @@ impl<'a> RewardCollection<'a> {
 /// Type used for the weight of a filter result
 type Weight = u32;
 
+/// Number of steps used when integrating [inclusion_chances]
+const INCLUSION_STEPS: u32 = 256;
+
+/// Chance of each item with the provided `weights` being one of the
+/// `amount` items chosen when sampling without replacement.
+///
+/// Sampling gives every item the key `u^(1/weight)` for a uniform `u`
+/// and keeps the `amount` largest keys, so the chance of an item is its
+/// key integrated against the chance that fewer than `amount` of the
+/// other keys are larger. Items sharing a weight share a chance so the
+/// chance is only computed once for each distinct weight
+fn inclusion_chances(weights: &[Weight], amount: usize) -> Vec<f32> {
+    let total: u64 = weights.iter().map(|weight| *weight as u64).sum();
+    if total == 0 || amount == 0 {
+        return vec![0.0; weights.len()];
+    }
+
+    // Every item is chosen
+    if amount >= weights.len() {
+        return vec![1.0; weights.len()];
+    }
+
+    if amount == 1 {
+        return weights
+            .iter()
+            .map(|weight| *weight as f32 / total as f32)
+            .collect();
+    }
+
+    // Distinct weights along with the number of items with the weight
+    let mut groups: Vec<(Weight, usize)> = Vec::new();
+    for weight in weights {
+        match groups.iter_mut().find(|(value, _)| value == weight) {
+            Some((_, count)) => *count += 1,
+            None => groups.push((*weight, 1)),
+        }
+    }
+
+    let group_chances: Vec<(Weight, f32)> = groups
+        .iter()
+        .map(|(group_weight, _)| {
+            let weight = *group_weight as f64;
+            let sum: f64 = (0..INCLUSION_STEPS)
+                .map(|step| {
+                    let u = (step as f64 + 0.5) / INCLUSION_STEPS as f64;
+
+                    // Chance of exactly `index` other keys being larger
+                    let mut larger = vec![0.0; amount];
+                    larger[0] = 1.0;
+
+                    for (other_weight, count) in &groups {
+                        // The item itself isn't one of the other keys
+                        let count = if other_weight == group_weight {
+                            count - 1
+                        } else {
+                            *count
+                        };
+
+                        let chance = 1.0 - u.powf(*other_weight as f64 / weight);
+                        for _ in 0..count {
+                            for index in (0..amount).rev() {
+                                let below = if index > 0 { larger[index - 1] } else { 0.0 };
+                                larger[index] = larger[index] * (1.0 - chance) + below * chance;
+                            }
+                        }
+                    }
+
+                    larger.iter().sum::<f64>()
+                })
+                .sum();
+
+            (*group_weight, (sum / INCLUSION_STEPS as f64) as f32)
+        })
+        .collect();
+
+    weights
+        .iter()
+        .map(|weight| {
+            group_chances
+                .iter()
+                .find(|(value, _)| value == weight)
+                .map(|(_, chance)| *chance)
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
 /// Item filtering
 #[derive(Debug, Clone)]
 enum Filter {
@@ -330,6 +538,8 @@ enum Filter {
     Category(Category),
     /// Filter based on a specific item attribute
     Attribute(String, Value),
+    /// Filter based on the consumable type of the item
+    ConsumableType(ConsumableType),
 
     /// Filter matching many filters. Only one of the filters needs to
     /// pass, will compare all the filters and the weight will become
@@ -481,8 +691,13 @@ impl Filter {
 
     /// Applies the filter against the provided `item` definition
     /// returns [None] if the value did not match otherwise returns
-    /// [Some] with the calculated [FilterWeight]
-    fn apply_filter(&self, item: &ItemDefinition) -> Option<Weight> {
+    /// [Some] with the calculated [FilterWeight], the rarity `weights`
+    /// replace the default rarity weights when provided
+    fn apply_filter(
+        &self,
+        item: &ItemDefinition,
+        weights: Option<&RarityWeights>,
+    ) -> Option<Weight> {
         match self {
             Filter::Named(name) => {
                 if name != &item.name {
@@ -497,7 +712,10 @@ impl Filter {
                     return None;
                 }
 
-                Some(rarity.weight())
+                Some(match weights {
+                    Some(weights) => weights.weight(*rarity),
+                    None => rarity.weight(),
+                })
             }
             Filter::Category(category) => {
                 let item_category = &item.category;
@@ -520,12 +738,19 @@ impl Filter {
                     None
                 }
             }
+            Filter::ConsumableType(consumable_type) => {
+                if item.attributes.consumable_type != Some(*consumable_type) {
+                    return None;
+                }
+
+                Some(0)
+            }
             Filter::Many(filters) => {
                 let mut weight_sum = 0;
                 let mut matches = false;
 
                 for filter in filters {
-                    if let Some(weight) = filter.apply_filter(item) {
+                    if let Some(weight) = filter.apply_filter(item, weights) {
                         weight_sum += weight;
                         matches = true;
                     }
@@ -538,26 +763,26 @@ impl Filter {
                 }
             }
             Filter::And(left, right) => {
-                let left = left.apply_filter(item)?;
-                let right = right.apply_filter(item)?;
+                let left = left.apply_filter(item, weights)?;
+                let right = right.apply_filter(item, weights)?;
                 Some(left + right)
             }
             Filter::Or(left, right) => {
-                if let Some(left) = left.apply_filter(item) {
+                if let Some(left) = left.apply_filter(item, weights) {
                     Some(left)
                 } else {
-                    right.apply_filter(item)
+                    right.apply_filter(item, weights)
                 }
             }
             Filter::Not(filter) => {
-                if filter.apply_filter(item).is_some() {
+                if filter.apply_filter(item, weights).is_some() {
                     None
                 } else {
                     Some(0)
                 }
             }
             Filter::Weighted(filter, weight) => filter
-                .apply_filter(item)
+                .apply_filter(item, weights)
                 // Add the additional weight
                 .map(|filter_weight| filter_weight + *weight),
             Filter::Never => None,
@@ -648,7 +873,7 @@ fn generate_packs() -> HashMap<ItemName, Pack> {
             PackCollection::new(
                 // Uncommon ammo booster
                 Filter::Category(Category::Base(BaseCategory::Boosters))
-                    .and(Filter::attributes([("consumableType", "Ammo")]))
+                    .and(Filter::ConsumableType(ConsumableType::Ammo))
                     .and(Filter::Rarity(ItemRarity::Uncommon)),
             )
             // Give them all the uncommon ammo boosters
@@ -831,6 +1056,17 @@ fn generate_packs() -> HashMap<ItemName, Pack> {
         )
         .build();
 
+    // "Awarded for owning Mass Effect 3." the contents are unknown, guessed as 5 random
+    // items or characters including at least 1 that is Rare or better
+    let loyalty_pack_me3 = Pack::builder(uuid!("47088308-e623-494e-a436-cccfd7f4150f"))
+        .add(PackCollection::new(items_and_characters_filter.clone()).amount(4))
+        .add(PackCollection::new(
+            items_and_characters_filter
+                .clone()
+                .and(Filter::rarities([ItemRarity::Rare, ItemRarity::UltraRare])),
+        ))
+        .build();
+
     // "Contains 5 random items or characters, including at least 1 Uncommon, with a small chance for a Rare"
     let bonus_reward_pack = |name: ItemName| {
         Pack::builder(name)
@@ -978,8 +1214,7 @@ fn generate_packs() -> HashMap<ItemName, Pack> {
         todo(uuid!("80a9babf-3088-4ce9-a986-804f6ce9660c")),
         // APEX POINTS
         todo(uuid!("3b2c8ed8-df9a-4659-aeda-786e06cc7dd9")),
-        // LOYALTY PACK (ME3)
-        todo(uuid!("47088308-e623-494e-a436-cccfd7f4150f")),
+        loyalty_pack_me3,
         // LOYALTY PACK (DA:I)
         todo(uuid!("523226d2-8a17-4081-9c22-71c890d1b4ab")),
         // BONUS REWARD PACK
@@ -1294,3 +1529,29 @@ fn generate_packs() -> HashMap<ItemName, Pack> {
     .map(|pack| (pack.name, pack))
     .collect()
 }
+
+#[cfg(test)]
+mod test {
+    use super::inclusion_chances;
+
+    /// Tests that the chances of the items being chosen add up to the
+    /// amount chosen and favour the heavier items
+    #[test]
+    fn test_inclusion_chances() {
+        let chances = inclusion_chances(&[1, 3], 1);
+        assert!((chances[0] - 0.25).abs() < 0.001 && (chances[1] - 0.75).abs() < 0.001);
+        assert!(inclusion_chances(&[1, 3], 2)
+            .iter()
+            .all(|chance| *chance >= 1.0));
+        assert!(inclusion_chances(&[], 1).is_empty());
+
+        let chances = inclusion_chances(&[1, 1, 1, 1], 2);
+        assert!(chances.iter().all(|chance| (chance - 0.5).abs() < 0.01));
+
+        let chances = inclusion_chances(&[1, 2, 4, 8, 8], 3);
+        let total: f32 = chances.iter().sum();
+        assert!((total - 3.0).abs() < 0.01);
+        assert!(chances.windows(2).all(|pair| pair[0] < pair[1] + 0.001));
+        assert!(chances.iter().all(|chance| *chance <= 1.0));
+    }
+}
@@ -1,7 +1,7 @@
 //! Shared commonly used type definitions
 
 use sea_orm::FromJsonQueryResult;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_with::serde_as;
 
 /// Collection of custom attributes
@@ -22,6 +22,26 @@ impl CustomAttributes {
             .map(|(_, v)| v)
     }
 
+    /// Iterates the attribute keys and values
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &serde_json::Value)> {
+        self.0.iter().map(|(key, value)| (key.as_str(), value))
+    }
+
+    /// Gets the string value of an attribute, returns [None] if the
+    /// attribute is missing or isn't a string
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.get(key)?.as_str()
+    }
+
+    /// Gets the value of an attribute deserialized as `T`, returns [None]
+    /// if the attribute is missing otherwise the deserialization result
+    pub fn get_as<T>(&self, key: &str) -> Option<serde_json::Result<T>>
+    where
+        T: DeserializeOwned,
+    {
+        self.get(key).map(|value| T::deserialize(value))
+    }
+
     pub fn insert(&mut self, key: String, value: serde_json::Value) {
         if let Some(existing) = self.get_mut(&key) {
             *existing = value;
@@ -3,7 +3,9 @@
 //! https://masseffectandromeda.fandom.com/wiki/Character_Customization_(multiplayer)#Skills
 
 use super::{
+    definition_init,
     i18n::{I18nDescription, I18nName},
+    load_definition,
     shared::CustomAttributes,
 };
 use crate::utils::ImStr;
@@ -12,7 +14,8 @@ use chrono::{DateTime, Utc};
 use log::debug;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
-use std::{str::FromStr, sync::OnceLock};
+use std::{collections::BTreeMap, str::FromStr, sync::OnceLock};
+use thiserror::Error;
 use uuid::Uuid;
 
 /// Skill definitions (64)
@@ -34,6 +37,8 @@ pub struct Skills {
 /// (Allows the definitions to be passed with static lifetimes)
 static STORE: OnceLock<Skills> = OnceLock::new();
 
+definition_init!(Skills, STORE, load);
+
 impl Skills {
     /// Gets a static reference to the global [ChallengeDefinitions] collection
     pub fn get() -> &'static Skills {
@@ -43,7 +48,8 @@ impl Skills {
     /// Creates and loads the skill definitions from [LEVEL_TABLE_DEFINITIONS]
     fn load() -> anyhow::Result<Self> {
         let values: Vec<SkillDefinition> =
-            serde_json::from_str(SKILL_DEFINITIONS).context("Failed to parse skill definitions")?;
+            load_definition("skillDefinitions.json", SKILL_DEFINITIONS)
+                .context("Failed to parse skill definitions")?;
 
         debug!("Loaded {} skill definition(s)", values.len());
 
@@ -56,8 +62,121 @@ impl Skills {
             .iter()
             .find(|definition| definition.name.eq(name))
     }
+
+    /// Computes the skill point cost and combined bonuses of the skills
+    /// unlocked in the `trees`, ensures the skills exist, tiers are
+    /// unlocked in order, and the skill unlock conditions are met
+    pub fn allocate(&self, trees: &[SkillTree]) -> Result<SkillAllocation, SkillAllocationError> {
+        let mut cost: u32 = 0;
+        let mut bonuses: BTreeMap<&str, SkillBonus> = BTreeMap::new();
+
+        for tree in trees {
+            let definition = self
+                .by_name(&tree.name)
+                .ok_or(SkillAllocationError::UnknownSkillTree(tree.name))?;
+
+            for tier in &tree.tree {
+                let unlocked = tier
+                    .skills
+                    .iter()
+                    .filter(|(_, unlocked)| *unlocked)
+                    .map(|(name, _)| name);
+
+                for name in unlocked {
+                    let skill = definition
+                        .skill(tier.tier, name)
+                        .ok_or_else(|| SkillAllocationError::UnknownSkill(name.clone()))?;
+
+                    // Previous tier must have a skill unlocked
+                    if tier.tier > 1 && !tree.is_tier_unlocked(tier.tier - 1) {
+                        return Err(SkillAllocationError::TierLocked(name.clone()));
+                    }
+
+                    let conditions_met = skill
+                        .unlock_conditions
+                        .iter()
+                        .all(|condition| condition.is_met(trees));
+                    if !conditions_met {
+                        return Err(SkillAllocationError::ConditionNotMet(name.clone()));
+                    }
+
+                    cost = skill.levels.iter().fold(cost, |cost, level| {
+                        cost.saturating_add(level.cost.skill_points)
+                    });
+
+                    skill.add_bonuses(&mut bonuses);
+                }
+            }
+        }
+
+        Ok(SkillAllocation {
+            cost,
+            bonuses: bonuses.into_values().collect(),
+        })
+    }
+}
+
+/// Skill point cost and bonuses of a skill tree allocation
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkillAllocation {
+    /// Total skill points cost of the unlocked skills
+    pub cost: u32,
+    /// Bonuses from the unlocked skills combined by stat
+    pub bonuses: Vec<SkillBonus>,
+}
+
+/// Bonus to a stat given by unlocked skills
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkillBonus {
+    /// Stat the bonus applies to
+    pub stat: String,
+    /// Display name of the stat
+    pub name: Option<String>,
+    /// Combined value of the bonus
+    pub value: f64,
+    /// Whether the value is displayed as a percentage
+    pub percentage: bool,
+}
+
+/// Errors that can occur when computing a skill allocation
+#[derive(Debug, Error)]
+pub enum SkillAllocationError {
+    /// Skill tree definition doesn't exist
+    #[error("Unknown skill tree {0}")]
+    UnknownSkillTree(SkillDefinitionName),
+    /// Skill doesn't exist within the tier of the skill tree
+    #[error("Unknown skill {0}")]
+    UnknownSkill(SkillName),
+    /// Skill was unlocked without unlocking the previous tier
+    #[error("Previous tier must be unlocked before {0}")]
+    TierLocked(SkillName),
+    /// Skill unlock conditions weren't met (i.e. conflicting choice)
+    #[error("Unlock conditions for {0} not met")]
+    ConditionNotMet(SkillName),
 }
 
+/// Stat property attribute of a skill, skills give bonuses through
+/// attributes prefixed with [STAT_PROPERTY_PREFIX]
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StatProperty {
+    #[serde(default)]
+    display_as_percentage: bool,
+    #[serde(rename = "loc_statReferenceName")]
+    name: Option<String>,
+    #[serde(rename = "value_Percent")]
+    percent: Option<f64>,
+    #[serde(rename = "value_Asset_Percent")]
+    asset_percent: Option<f64>,
+    #[serde(rename = "value_ConstValue")]
+    const_value: Option<f64>,
+}
+
+/// Prefix for skill attributes describing a stat bonus
+const STAT_PROPERTY_PREFIX: &str = "statProperties_";
+
 /// Represents a skill/ability that a character can have
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase", deny_unknown_fields)]
@@ -79,6 +198,18 @@ pub struct SkillDefinition {
     pub i18n_description: I18nDescription,
 }
 
+impl SkillDefinition {
+    /// Finds the skill with the provided `name` within the `tier`
+    pub fn skill(&self, tier: u8, name: &str) -> Option<&Skill> {
+        self.tiers
+            .iter()
+            .find(|value| value.tier == tier)?
+            .skills
+            .iter()
+            .find(|skill| skill.name.as_ref() == name)
+    }
+}
+
 /// Tier of a [Skill]
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase", deny_unknown_fields)]
@@ -104,6 +235,43 @@ pub struct UnlockCondition {
     pub conditions: Vec<(Condition, ConditionInt)>,
 }
 
+impl UnlockCondition {
+    /// Checks whether the conditions are met by the skills unlocked in the
+    /// `trees`. Conditions are in the form `character:skills:{tree}:{tier}:{skill}`
+    /// and are negated when prefixed with `!`, other conditions are ignored
+    fn is_met(&self, trees: &[SkillTree]) -> bool {
+        self.conditions.iter().all(|(condition, expected)| {
+            let (negated, condition) = match condition.strip_prefix('!') {
+                Some(condition) => (true, condition),
+                None => (false, condition.as_str()),
+            };
+
+            let Ok(PathRef(parts)) = condition.parse::<PathRef>() else {
+                return true;
+            };
+            let [kind, category, tree, tier, skill] = parts.as_slice() else {
+                return true;
+            };
+
+            if kind.as_str() != "character" || category.as_str() != "skills" {
+                return true;
+            }
+
+            let (Ok(tree), Ok(tier)) = (tree.parse::<SkillDefinitionName>(), tier.parse::<u8>())
+            else {
+                return true;
+            };
+
+            let unlocked = trees
+                .iter()
+                .find(|value| value.name == tree)
+                .is_some_and(|tree| tree.is_skill_unlocked(tier, skill));
+
+            (unlocked != negated) == (*expected != 0)
+        })
+    }
+}
+
 /// Conditional string, needs to be parsed at some point
 type Condition = String;
 /// Condition value either 1 or 0
@@ -133,6 +301,37 @@ pub struct Skill {
     pub i18n_description: Option<I18nDescription>,
 }
 
+impl Skill {
+    /// Adds the stat bonuses given by the skill to the `bonuses`
+    fn add_bonuses<'a>(&'a self, bonuses: &mut BTreeMap<&'a str, SkillBonus>) {
+        for (key, value) in self.custom_attributes.iter() {
+            let Some(stat) = key.strip_prefix(STAT_PROPERTY_PREFIX) else {
+                continue;
+            };
+
+            let Ok(property) = StatProperty::deserialize(value) else {
+                continue;
+            };
+
+            let value = property
+                .percent
+                .or(property.asset_percent)
+                .or(property.const_value)
+                .unwrap_or_default();
+
+            bonuses
+                .entry(stat)
+                .or_insert_with(|| SkillBonus {
+                    stat: stat.to_string(),
+                    name: property.name,
+                    value: 0.0,
+                    percentage: property.display_as_percentage,
+                })
+                .value += value;
+        }
+    }
+}
+
 /// Defines a level of a skill
 #[serde_as]
 #[derive(Debug, Serialize, Deserialize)]
@@ -203,6 +402,65 @@ pub struct SkillTreeTier {
     pub skills: Vec<(SkillName, bool)>,
 }
 
+impl SkillTree {
+    /// Checks whether any skill in the `tier` is unlocked
+    fn is_tier_unlocked(&self, tier: u8) -> bool {
+        self.tree
+            .iter()
+            .filter(|value| value.tier == tier)
+            .any(|value| value.skills.iter().any(|(_, unlocked)| *unlocked))
+    }
+
+    /// Checks whether the skill with the provided `name` in the `tier` is unlocked
+    fn is_skill_unlocked(&self, tier: u8, name: &str) -> bool {
+        self.tree
+            .iter()
+            .filter(|value| value.tier == tier)
+            .flat_map(|value| value.skills.iter())
+            .any(|(skill, unlocked)| *unlocked && skill.as_ref() == name)
+    }
+}
+
+/// Counts the skills unlocked in the `trees` that weren't unlocked
+/// in the `previous` trees
+pub fn count_newly_unlocked(previous: &[SkillTree], trees: &[SkillTree]) -> u32 {
+    trees
+        .iter()
+        .flat_map(|tree| {
+            let previous = previous.iter().find(|value| value.name == tree.name);
+            tree.tree.iter().flat_map(move |tier| {
+                tier.skills
+                    .iter()
+                    .filter(|(_, unlocked)| *unlocked)
+                    .filter(move |(name, _)| {
+                        !previous
+                            .is_some_and(|previous| previous.is_skill_unlocked(tier.tier, name))
+                    })
+            })
+        })
+        .count() as u32
+}
+
+/// Applies the `changes` to the matching tiers of the skill `trees`, trees
+/// and tiers that don't exist in `trees` are ignored
+pub fn merge_skill_trees(trees: &mut [SkillTree], changes: Vec<SkillTree>) {
+    for change in changes {
+        let Some(tree) = trees.iter_mut().find(|value| value.name == change.name) else {
+            continue;
+        };
+
+        for entry in change.tree {
+            let Some(tier) = tree.tree.iter_mut().find(|value| value.tier == entry.tier) else {
+                continue;
+            };
+
+            for (key, value) in entry.skills {
+                tier.set_skill(key, value);
+            }
+        }
+    }
+}
+
 impl SkillTreeTier {
     /// Sets a skill tree tier value
     pub fn set_skill(&mut self, name: SkillName, value: bool) {
@@ -216,11 +474,107 @@ impl SkillTreeTier {
 
 #[cfg(test)]
 mod test {
-    use super::Skills;
+    use super::{
+        count_newly_unlocked, merge_skill_trees, SkillAllocationError, SkillTree, SkillTreeTier,
+        Skills,
+    };
+    use crate::definitions::classes::Classes;
+    use uuid::uuid;
+
+    /// Tests that the default skill trees of each class cost the
+    /// points spent on new characters
+    #[test]
+    fn test_allocate_defaults() {
+        let skills = Skills::load().unwrap();
+
+        for class in Classes::get().all() {
+            let allocation = skills.allocate(&class.skill_trees).unwrap();
+            assert_eq!(allocation.cost, 3);
+        }
+    }
+
+    /// Tests that tier order and conflicting choices are rejected
+    #[test]
+    fn test_allocate_invalid() {
+        const SKILL_PREFIX: &str = "game/progression/multiplayer/skills/offensive/apextraining";
 
-    /// Tests ensuring loading succeeds
+        let skills = Skills::load().unwrap();
+        let tier = |tier: u8, skills: &[(&str, bool)]| SkillTreeTier {
+            tier,
+            skills: skills
+                .iter()
+                .map(|(name, unlocked)| (format!("{SKILL_PREFIX}/{name}").into(), *unlocked))
+                .collect(),
+        };
+        let tree = |tree: Vec<SkillTreeTier>| SkillTree {
+            name: uuid!("2bff7c2b-670f-6a41-6c2e-cd00b2f1df60"),
+            tree,
+            timestamp: None,
+            obsolete: false,
+        };
+
+        let skipped = tree(vec![
+            tier(1, &[("skill_apextraining1_mp", false)]),
+            tier(2, &[("skill_apextraining2_mp", true)]),
+        ]);
+        assert!(matches!(
+            skills.allocate(&[skipped]),
+            Err(SkillAllocationError::TierLocked(_))
+        ));
+
+        let unlocked = [
+            tier(1, &[("skill_apextraining1_mp", true)]),
+            tier(2, &[("skill_apextraining2_mp", true)]),
+            tier(3, &[("skill_apextraining3_mp", true)]),
+        ];
+
+        let mut valid = unlocked.to_vec();
+        valid.push(tier(4, &[("skill_apextraining4_weapons_mp", true)]));
+        let allocation = skills.allocate(&[tree(valid)]).unwrap();
+        assert_eq!(allocation.cost, 10);
+        assert!(!allocation.bonuses.is_empty());
+
+        let mut conflicting = unlocked.to_vec();
+        conflicting.push(tier(
+            4,
+            &[
+                ("skill_apextraining4_weapons_mp", true),
+                ("skill_apextraining4_powers_mp", true),
+            ],
+        ));
+        assert!(matches!(
+            skills.allocate(&[tree(conflicting)]),
+            Err(SkillAllocationError::ConditionNotMet(_))
+        ));
+    }
+
+    /// Tests that only skills unlocked by the changes are counted
     #[test]
-    fn ensure_load_succeed() {
-        _ = Skills::load().unwrap();
+    fn test_count_newly_unlocked() {
+        let class = Classes::get().all().first().unwrap();
+        let previous = class.skill_trees.clone();
+        assert_eq!(count_newly_unlocked(&previous, &previous), 0);
+
+        let mut trees = previous.clone();
+        let tree = &trees[0];
+        let tier = tree
+            .tree
+            .iter()
+            .find(|tier| tier.skills.iter().any(|(_, unlocked)| !*unlocked))
+            .unwrap();
+        let (name, _) = tier.skills.iter().find(|(_, unlocked)| !*unlocked).unwrap();
+        let changes = vec![SkillTree {
+            name: tree.name,
+            tree: vec![SkillTreeTier {
+                tier: tier.tier,
+                skills: vec![(name.clone(), true)],
+            }],
+            timestamp: None,
+            obsolete: false,
+        }];
+
+        merge_skill_trees(&mut trees, changes);
+        assert_eq!(count_newly_unlocked(&previous, &trees), 1);
+        assert_eq!(count_newly_unlocked(&trees, &previous), 0);
     }
 }
@@ -1,8 +1,11 @@
 use crate::{
+    config::Config,
     database::entity::currency::CurrencyType,
     definitions::{
+        definition_init,
         i18n::{I18nDescription, I18nName},
-        items::ItemName,
+        items::{ItemName, Items},
+        load_definition,
         shared::CustomAttributes,
     },
     utils::{models::DateDuration, ImStr},
@@ -24,6 +27,8 @@ pub struct StoreCatalogs {
 /// (Allows the definitions to be passed with static lifetimes)
 static STORE: OnceLock<StoreCatalogs> = OnceLock::new();
 
+definition_init!(StoreCatalogs, STORE, load);
+
 impl StoreCatalogs {
     /// Gets a static reference to the global [StoreCatalogs] collection
     pub fn get() -> &'static StoreCatalogs {
@@ -31,13 +36,47 @@ impl StoreCatalogs {
     }
 
     fn load() -> anyhow::Result<Self> {
-        let catalog: StoreCatalog = serde_json::from_str(STORE_CATALOG_DEFINITION)
-            .context("Failed to load store catalog definitions")?;
+        let mut catalog: StoreCatalog =
+            load_definition("storeCatalog.json", STORE_CATALOG_DEFINITION)
+                .context("Failed to load store catalog definitions")?;
+
+        apply_trait_item_prices(&mut catalog);
 
         Ok(Self { catalog })
     }
 }
 
+/// Replaces the prices of the strike team trait item articles with
+/// the prices from the configuration
+fn apply_trait_item_prices(catalog: &mut StoreCatalog) {
+    let items = Items::get();
+    let config = &Config::get().strike_teams;
+
+    for article in catalog.articles.iter_mut() {
+        let Some(action) = items
+            .by_name(&article.item_name)
+            .and_then(|item| item.attributes.strike_team_trait_action)
+        else {
+            continue;
+        };
+
+        let mut prices: Vec<StorePrice> = config
+            .trait_item_cost(action)
+            .iter()
+            .map(|(currency, cost)| StorePrice {
+                currency: *currency,
+                original_price: *cost,
+                final_price: *cost,
+            })
+            .collect();
+
+        // Keep a consistent ordering for the prices
+        prices.sort_by_key(|price| price.currency as u8);
+
+        article.prices = prices;
+    }
+}
+
 /// Type alias for a string representing a store catalog name
 pub type StoreCatalogName = ImStr;
 
@@ -169,14 +208,3 @@ pub struct StorePrice {
     /// The final cost price of the item (The actual price)
     pub final_price: u32,
 }
-
-#[cfg(test)]
-mod test {
-    use super::StoreCatalogs;
-
-    /// Tests ensuring loading succeeds
-    #[test]
-    fn ensure_load_succeed() {
-        _ = StoreCatalogs::load().unwrap();
-    }
-}
@@ -0,0 +1,77 @@
+//! Cosmetic flair (special icons and name colors) that can be unlocked
+//! through challenge rewards and equipped onto strike teams
+
+use super::{definition_init, parse_definition};
+use anyhow::{anyhow, Context};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// Type alias for a [String] representing a [StrikeTeamFlair::name]
+pub type StrikeTeamFlairName = String;
+
+/// Strike team flair definitions
+const STRIKE_TEAM_FLAIR_DEFINITIONS: &str = include_str!("../resources/data/strikeTeamFlair.json");
+
+/// Collection of strike team flair definitions
+pub struct StrikeTeamFlairs {
+    pub values: Vec<StrikeTeamFlair>,
+}
+
+/// Static storage for the definitions once its loaded
+/// (Allows the definitions to be passed with static lifetimes)
+static STORE: OnceLock<StrikeTeamFlairs> = OnceLock::new();
+
+definition_init!(StrikeTeamFlairs, STORE, load);
+
+impl StrikeTeamFlairs {
+    /// Gets a static reference to the global [StrikeTeamFlairs] collection
+    pub fn get() -> &'static StrikeTeamFlairs {
+        STORE.get_or_init(|| Self::load().unwrap())
+    }
+
+    fn load() -> anyhow::Result<Self> {
+        let values: Vec<StrikeTeamFlair> =
+            parse_definition("strikeTeamFlair.json", STRIKE_TEAM_FLAIR_DEFINITIONS)
+                .context("Failed to load strike team flair definitions")?;
+
+        // Ensure flair names are unique
+        for (index, flair) in values.iter().enumerate() {
+            if values[..index].iter().any(|other| other.name == flair.name) {
+                return Err(anyhow!("Duplicate strike team flair '{}'", flair.name));
+            }
+        }
+
+        debug!("Loaded {} strike team flair definition(s)", values.len());
+
+        Ok(Self { values })
+    }
+
+    /// Finds a flair definition by its `name`
+    pub fn by_name(&self, name: &str) -> Option<&StrikeTeamFlair> {
+        self.values.iter().find(|flair| flair.name == name)
+    }
+}
+
+/// Cosmetic flair that can be applied to a strike team
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StrikeTeamFlair {
+    /// Unique name of the flair
+    pub name: StrikeTeamFlairName,
+    /// The kind of flair, determines where its applied
+    pub kind: StrikeTeamFlairKind,
+    /// Icon image path or name color depending on the kind
+    pub value: String,
+}
+
+/// Kinds of strike team flair, a strike team can have
+/// one flair of each kind equipped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StrikeTeamFlairKind {
+    /// Special icon shown for the strike team
+    Icon,
+    /// Color used for the strike team name
+    NameColor,
+}
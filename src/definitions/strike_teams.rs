@@ -6,30 +6,31 @@
 //! and are rotated
 
 use crate::{
+    config::{Config, StrikeTeamsConfig},
     database::entity::{
-        currency::CurrencyType, strike_team_mission::MissionAccessibility, StrikeTeam, User,
+        currency::CurrencyType, strike_team_mission::MissionAccessibility, StrikeTeam,
+        StrikeTeamMission, User,
     },
     definitions::{
         challenges::CurrencyReward,
+        definition_init,
         i18n::{I18nDesc, I18nDescription, I18nName},
         items::{ItemDefinition, ItemName},
         level_tables::{LevelTable, LevelTableName, LevelTables, ProgressionXp},
+        parse_definition,
         shared::CustomAttributes,
     },
-    utils::ImStr,
+    services::random::{RandomPurpose, RandomService},
+    utils::{clock, ImStr},
 };
 use anyhow::Context;
-use chrono::Utc;
-use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use rand::{seq::SliceRandom, Rng};
 use sea_orm::{ConnectionTrait, FromJsonQueryResult};
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, skip_serializing_none};
-use std::{
-    collections::HashMap,
-    sync::OnceLock,
-    time::{SystemTime, UNIX_EPOCH},
-};
+use std::{collections::HashMap, sync::OnceLock};
 use strum::Display;
+use thiserror::Error;
 use uuid::{uuid, Uuid};
 
 use super::{
@@ -59,7 +60,7 @@ static STRIKE_TEAM_NAMES: &[&str] = &[
 ];
 
 /// Name of the [LevelTable] used for leveling strike teams
-static STRIKE_TEAM_LEVEL_TABLE: LevelTableName = uuid!("5e6f7542-7309-9367-8437-fe83678e5c28");
+pub static STRIKE_TEAM_LEVEL_TABLE: LevelTableName = uuid!("5e6f7542-7309-9367-8437-fe83678e5c28");
 
 /// Collection of strike team icons and their associated internal
 /// team name
@@ -82,8 +83,36 @@ static MISSION_LEVELS: [&str; 9] = [
     "MPHangar",
 ];
 
-pub const MAX_STRIKE_TEAMS: usize = 6;
-pub static STRIKE_TEAM_COSTS: [u32; MAX_STRIKE_TEAMS] = [0, 40, 80, 120, 160, 200];
+/// The default number of strike teams, teams beyond this are expansion slots
+pub const DEFAULT_STRIKE_TEAMS: usize = 6;
+/// Costs for each of the default strike team slots
+pub static STRIKE_TEAM_COSTS: [u32; DEFAULT_STRIKE_TEAMS] = [0, 40, 80, 120, 160, 200];
+
+/// Gets the configured maximum number of strike teams
+pub fn max_strike_teams() -> usize {
+    Config::get().strike_teams.max_teams
+}
+
+/// Gets the cost of the strike team at the provided slot `index`. Slots
+/// beyond the default slots are expansion slots which increase in cost by the
+/// configured step for each slot.
+///
+/// Returns [None] if the slot is beyond the maximum number of strike teams
+pub fn strike_team_cost(config: &StrikeTeamsConfig, index: usize) -> Option<u32> {
+    if index >= config.max_teams {
+        return None;
+    }
+
+    if let Some(cost) = STRIKE_TEAM_COSTS.get(index) {
+        return Some(*cost);
+    }
+
+    // Expansion slots escalate from the last default slot cost
+    let last_cost = STRIKE_TEAM_COSTS[DEFAULT_STRIKE_TEAMS - 1];
+    let expansion = (index - DEFAULT_STRIKE_TEAMS + 1) as u32;
+
+    Some(last_cost.saturating_add(config.expansion_cost_step.saturating_mul(expansion)))
+}
 
 pub struct StrikeTeams {
     pub traits: StrikeTeamTraits,
@@ -97,6 +126,8 @@ pub struct StrikeTeams {
 /// (Allows the definitions to be passed with static lifetimes)
 static STORE: OnceLock<StrikeTeams> = OnceLock::new();
 
+definition_init!(StrikeTeams, STORE, load);
+
 impl StrikeTeams {
     /// Gets a static reference to the global [StrikeTeamDefinitions] collection
     pub fn get() -> &'static StrikeTeams {
@@ -104,18 +135,25 @@ impl StrikeTeams {
     }
 
     fn load() -> anyhow::Result<Self> {
-        let traits: StrikeTeamTraits = serde_json::from_str(STRIKE_TEAM_TRAIT_DEFINITIONS)
-            .context("Failed to load strike team traits")?;
-        let tags: MissionTags = serde_json::from_str(STRIKE_TEAM_TAG_DEFINITIONS)
-            .context("Failed to load strike team mission tags")?;
-        let missions: MissionDefinitions = serde_json::from_str(STRIKE_TEAM_MISSION_DEFINITIONS)
-            .context("Failed to load strike team mission definitions")?;
-        let equipment: Vec<StrikeTeamEquipment> =
-            serde_json::from_str(STRIKE_TEAM_EQUIPMENT_DEFINITIONS)
-                .context("Failed to load strike team equipment definitions")?;
-        let specializations: Vec<StrikeTeamSpecialization> =
-            serde_json::from_str(STRIKE_TEAM_SPECIALIZATION_DEFINITIONS)
-                .context("Failed to load strike team equipment definitions")?;
+        let traits: StrikeTeamTraits =
+            parse_definition("strikeTeamTraits.json", STRIKE_TEAM_TRAIT_DEFINITIONS)
+                .context("Failed to load strike team traits")?;
+        let tags: MissionTags =
+            parse_definition("strikeTeamTags.json", STRIKE_TEAM_TAG_DEFINITIONS)
+                .context("Failed to load strike team mission tags")?;
+        let missions: MissionDefinitions =
+            parse_definition("strikeTeamMissions.json", STRIKE_TEAM_MISSION_DEFINITIONS)
+                .context("Failed to load strike team mission definitions")?;
+        let equipment: Vec<StrikeTeamEquipment> = parse_definition(
+            "strikeTeamEquipment.json",
+            STRIKE_TEAM_EQUIPMENT_DEFINITIONS,
+        )
+        .context("Failed to load strike team equipment definitions")?;
+        let specializations: Vec<StrikeTeamSpecialization> = parse_definition(
+            "strikeTeamSpecialization.json",
+            STRIKE_TEAM_SPECIALIZATION_DEFINITIONS,
+        )
+        .context("Failed to load strike team equipment definitions")?;
 
         Ok(Self {
             traits,
@@ -236,7 +274,7 @@ where
     // Get the custom wave definitions or empty list
     let waves = mission.waves.clone().unwrap_or_default();
 
-    let now = Utc::now().timestamp();
+    let now = clock::timestamp();
 
     // Mission starts immediately and ends after 24 hours
     let start_seconds = now;
@@ -279,7 +317,7 @@ where
     C: ConnectionTrait + Send,
 {
     // Generate random strike team data
-    let mut rng = StdRng::from_entropy();
+    let mut rng = RandomService::get().rng(RandomPurpose::StrikeTeamCreation);
     let strike_team_data = random_strike_team(&mut rng).context("Failed to create strike team")?;
 
     // Create the strike team
@@ -424,6 +462,41 @@ impl StrikeTeamTraits {
             .cloned()
     }
 
+    /// Chooses a random negative trait for a team that failed a mission
+    /// with the provided mission `tags`, traits for one of the mission tags
+    /// are preferred. Traits in `existing` are never chosen
+    pub fn random_negative<R>(
+        &self,
+        rng: &mut R,
+        tags: &[MissionTag],
+        existing: &[StrikeTeamTrait],
+    ) -> Option<StrikeTeamTrait>
+    where
+        R: Rng,
+    {
+        let is_new =
+            |value: &&StrikeTeamTrait| !existing.iter().any(|other| other.name == value.name);
+
+        let tagged: Vec<&StrikeTeamTrait> = tags
+            .iter()
+            .filter_map(|tag| self.by_mission_tag(&tag.name, false))
+            .filter(is_new)
+            .collect();
+
+        if let Some(value) = tagged.choose(rng) {
+            return Some((*value).clone());
+        }
+
+        let general: Vec<&StrikeTeamTrait> = self
+            .negative
+            .iter()
+            .filter(|value| value.tag.is_none())
+            .filter(is_new)
+            .collect();
+
+        general.choose(rng).map(|value| (*value).clone())
+    }
+
     /// Finds a [StrikeTeamTrait] by a specific mission `tag` and uses
     /// `positive` to determine whether the trait must be positive or negative
     fn by_mission_tag(&self, tag: &MissionTagName, positive: bool) -> Option<&StrikeTeamTrait> {
@@ -441,6 +514,91 @@ impl StrikeTeamTraits {
     }
 }
 
+/// Changes that can be made to the traits of a strike team by
+/// consuming a trait item
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum StrikeTeamTraitAction {
+    /// Removes a random negative trait
+    RemoveNegative,
+    /// Replaces a random trait with a different trait of the same kind
+    Reroll,
+}
+
+/// Errors that can occur when applying a [StrikeTeamTraitAction]
+#[derive(Debug, Error)]
+pub enum StrikeTeamTraitError {
+    #[error("Strike team has no traits the action can change")]
+    NoApplicableTraits,
+    #[error("No replacement trait available")]
+    NoReplacement,
+}
+
+impl StrikeTeamTraitAction {
+    /// Checks whether the action can be applied to a team with the
+    /// provided `positive` and `negative` traits
+    pub fn check(
+        &self,
+        positive: &[StrikeTeamTrait],
+        negative: &[StrikeTeamTrait],
+    ) -> Result<(), StrikeTeamTraitError> {
+        let applicable = match self {
+            StrikeTeamTraitAction::RemoveNegative => !negative.is_empty(),
+            StrikeTeamTraitAction::Reroll => !positive.is_empty() || !negative.is_empty(),
+        };
+
+        if !applicable {
+            return Err(StrikeTeamTraitError::NoApplicableTraits);
+        }
+
+        Ok(())
+    }
+
+    /// Applies the action to the `positive` and `negative` traits of a team,
+    /// re-rolled traits are replaced with one from the `traits` definitions
+    pub fn apply<R>(
+        &self,
+        rng: &mut R,
+        traits: &StrikeTeamTraits,
+        positive: &mut Vec<StrikeTeamTrait>,
+        negative: &mut Vec<StrikeTeamTrait>,
+    ) -> Result<(), StrikeTeamTraitError>
+    where
+        R: Rng,
+    {
+        self.check(positive, negative)?;
+
+        match self {
+            StrikeTeamTraitAction::RemoveNegative => {
+                let index = rng.gen_range(0..negative.len());
+                negative.remove(index);
+            }
+            StrikeTeamTraitAction::Reroll => {
+                let index = rng.gen_range(0..positive.len() + negative.len());
+
+                // Traits are replaced with a trait of the same kind
+                let (team_traits, definitions, index) = if index < positive.len() {
+                    (positive, &traits.positive, index)
+                } else {
+                    (negative, &traits.negative, index - positive.len())
+                };
+
+                // Replacements can't be traits the team already has
+                let replacement = definitions
+                    .iter()
+                    .filter(|value| !team_traits.iter().any(|other| other.name == value.name))
+                    .collect::<Vec<_>>()
+                    .choose(rng)
+                    .copied()
+                    .ok_or(StrikeTeamTraitError::NoReplacement)?;
+
+                team_traits[index] = replacement.clone();
+            }
+        }
+
+        Ok(())
+    }
+}
+
 impl Localized for StrikeTeamTraits {
     fn localize(&mut self, i18n: &I18n) {
         self.positive
@@ -492,6 +650,120 @@ pub enum MissionDifficulty {
     Platinum,
 }
 
+impl MissionDifficulty {
+    /// Parses the difficulty from the value of a "difficulty" modifier
+    pub fn from_modifier(value: &str) -> Option<Self> {
+        match value {
+            "bronze" => Some(Self::Bronze),
+            "silver" => Some(Self::Silver),
+            "gold" => Some(Self::Gold),
+            "platinum" => Some(Self::Platinum),
+            _ => None,
+        }
+    }
+
+    /// Improves the difficulty by the provided number of `tiers`, strike
+    /// team only missions can't be improved beyond [MissionDifficulty::Gold]
+    pub fn improved(self, tiers: u8, accessibility: MissionAccessibility) -> Self {
+        let max = match accessibility {
+            MissionAccessibility::SinglePlayer => Self::Gold,
+            _ => Self::Platinum,
+        };
+
+        (0..tiers).fold(self, |difficulty, _| {
+            if difficulty == max {
+                return difficulty;
+            }
+
+            match difficulty {
+                Self::Bronze => Self::Silver,
+                Self::Silver => Self::Gold,
+                Self::Gold | Self::Platinum => Self::Platinum,
+            }
+        })
+    }
+}
+
+/// Chance of a strike team succeeding a mission before
+/// its traits are taken into account
+const BASE_SUCCESS_RATE: f32 = 0.85;
+/// Lowest chance of success a strike team can have
+const MIN_SUCCESS_RATE: f32 = 0.05;
+
+/// Level a strike team must reach before it can choose a specialization
+pub const MIN_SPECIALIZATION_LEVEL: u32 = 16;
+
+/// Computes the chance (0.0 - 1.0) of the `team` succeeding the `mission`,
+/// each point of effectiveness is worth one percent
+pub fn mission_success_rate(team: &StrikeTeam, mission: &StrikeTeamMission) -> f32 {
+    let effectiveness = team_effectiveness(team, mission, &StrikeTeams::get().specializations);
+
+    (BASE_SUCCESS_RATE + effectiveness as f32 / 100.0).clamp(MIN_SUCCESS_RATE, 1.0)
+}
+
+/// Computes the total effectiveness of the `team` on the `mission`
+///
+/// General traits and equipment always apply while tagged traits and
+/// equipment only apply to missions with a matching tag. Specializations
+/// apply to missions of the same type
+fn team_effectiveness(
+    team: &StrikeTeam,
+    mission: &StrikeTeamMission,
+    specializations: &[StrikeTeamSpecialization],
+) -> i32 {
+    let has_tag = |tag: &str| {
+        mission
+            .tags
+            .0
+            .iter()
+            .any(|mission_tag| mission_tag.name.as_ref() == tag)
+    };
+
+    let traits: i32 = team
+        .positive_traits
+        .0
+        .iter()
+        .chain(team.negative_traits.0.iter())
+        .filter(|value| match value.tag.as_deref() {
+            Some(tag) => has_tag(tag),
+            None => true,
+        })
+        .map(|value| value.effectiveness as i32)
+        .sum();
+
+    let equipment: i32 = team
+        .equipment
+        .as_ref()
+        .filter(|equipment| match &equipment.tags {
+            Some(tags) => tags.iter().any(|tag| has_tag(tag)),
+            None => true,
+        })
+        .map(|equipment| equipment.effectiveness as i32)
+        .unwrap_or_default();
+
+    let specialization: i32 = team
+        .specialization
+        .as_deref()
+        .and_then(|name| specializations.iter().find(|value| value.name == name))
+        .filter(|value| mission_specialization(mission) == Some(value.tag.as_str()))
+        .map(|value| value.effectiveness as i32)
+        .unwrap_or_default();
+
+    traits + equipment + specialization
+}
+
+/// Finds the tag of the specialization suited to the `mission`, missions
+/// don't store their type so its taken from the descriptor icon
+fn mission_specialization(mission: &StrikeTeamMission) -> Option<&'static str> {
+    match mission.descriptor.custom_attributes.get_str("Icon")? {
+        "SpecAssault" => Some("assault"),
+        // Official definitions use both spellings
+        "SpecDefence" | "SpecDefense" => Some("defense"),
+        "SpecExtraction" => Some("extraction"),
+        _ => None,
+    }
+}
+
 /// Collection of mission definitions
 #[derive(Deserialize)]
 pub struct MissionDefinitions {
@@ -871,11 +1143,83 @@ impl Localized for StrikeTeamSpecialization {
 
 #[cfg(test)]
 mod test {
-    use super::StrikeTeams;
+    use super::{strike_team_cost, StrikeTeamTraitAction, StrikeTeams, STRIKE_TEAM_COSTS};
+    use crate::config::StrikeTeamsConfig;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    /// Tests that the default slots use the fixed costs and that
+    /// slots beyond the maximum have no cost
+    #[test]
+    fn test_default_strike_team_costs() {
+        let config = StrikeTeamsConfig::default();
+
+        for (index, cost) in STRIKE_TEAM_COSTS.iter().enumerate() {
+            assert_eq!(strike_team_cost(&config, index), Some(*cost));
+        }
 
-    /// Tests ensuring loading succeeds
+        assert_eq!(strike_team_cost(&config, STRIKE_TEAM_COSTS.len()), None);
+    }
+
+    /// Tests that expansion slots escalate by the configured step
+    /// up to the configured maximum
+    #[test]
+    fn test_expansion_strike_team_costs() {
+        let config = StrikeTeamsConfig {
+            max_teams: 8,
+            expansion_cost_step: 50,
+            ..Default::default()
+        };
+
+        assert_eq!(strike_team_cost(&config, 6), Some(250));
+        assert_eq!(strike_team_cost(&config, 7), Some(300));
+        assert_eq!(strike_team_cost(&config, 8), None);
+    }
+
+    /// Tests that trait actions remove negative traits and re-roll
+    /// traits without duplicating the traits a team already has
+    #[test]
+    fn test_trait_actions() {
+        let strike_teams = StrikeTeams::load().unwrap();
+        let traits = &strike_teams.traits;
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let mut positive = vec![traits.positive[0].clone()];
+        let mut negative = Vec::new();
+
+        // Nothing to remove without negative traits
+        assert!(StrikeTeamTraitAction::RemoveNegative
+            .apply(&mut rng, traits, &mut positive, &mut negative)
+            .is_err());
+
+        StrikeTeamTraitAction::Reroll
+            .apply(&mut rng, traits, &mut positive, &mut negative)
+            .unwrap();
+        assert_eq!(positive.len(), 1);
+        assert_ne!(positive[0].name, traits.positive[0].name);
+
+        negative.push(traits.negative[0].clone());
+        StrikeTeamTraitAction::RemoveNegative
+            .apply(&mut rng, traits, &mut positive, &mut negative)
+            .unwrap();
+        assert!(negative.is_empty());
+    }
+
+    /// Tests that negative traits gained from failed missions are never
+    /// traits the team already has
     #[test]
-    fn ensure_load_succeed() {
-        _ = StrikeTeams::load().unwrap();
+    fn test_random_negative_excludes_existing() {
+        let strike_teams = StrikeTeams::load().unwrap();
+        let traits = &strike_teams.traits;
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let mut existing = Vec::new();
+        while let Some(value) = traits.random_negative(&mut rng, &[], &existing) {
+            assert!(value.tag.is_none());
+            assert!(!existing.iter().any(|other| other.name == value.name));
+            existing.push(value);
+        }
+
+        let general = traits.negative.iter().filter(|value| value.tag.is_none());
+        assert_eq!(existing.len(), general.count());
     }
 }
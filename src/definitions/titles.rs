@@ -0,0 +1,143 @@
+//! Player titles that are earned by completing challenges or reaching career
+//! milestones, players can equip one of their earned titles which is shown
+//! to the other players and on their public profile
+
+use super::{
+    challenges::{ChallengeName, Challenges},
+    definition_init, load_definition,
+};
+use crate::{database::entity::LeaderboardStats, services::leaderboard::LeaderboardStat};
+use anyhow::{anyhow, Context};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// Type alias for a [String] representing a [PlayerTitle::name]
+pub type PlayerTitleName = String;
+
+/// Player title definitions
+const PLAYER_TITLE_DEFINITIONS: &str = include_str!("../resources/data/playerTitles.json");
+
+/// Collection of player title definitions
+pub struct PlayerTitles {
+    pub values: Vec<PlayerTitle>,
+}
+
+/// Static storage for the definitions once its loaded
+/// (Allows the definitions to be passed with static lifetimes)
+static STORE: OnceLock<PlayerTitles> = OnceLock::new();
+
+definition_init!(PlayerTitles, STORE, load);
+
+impl PlayerTitles {
+    /// Gets a static reference to the global [PlayerTitles] collection
+    pub fn get() -> &'static PlayerTitles {
+        STORE.get_or_init(|| Self::load().unwrap())
+    }
+
+    fn load() -> anyhow::Result<Self> {
+        let values: Vec<PlayerTitle> =
+            load_definition("playerTitles.json", PLAYER_TITLE_DEFINITIONS)
+                .context("Failed to load player title definitions")?;
+
+        let challenges = Challenges::get();
+
+        for (index, title) in values.iter().enumerate() {
+            // Ensure title names are unique
+            if values[..index].iter().any(|other| other.name == title.name) {
+                return Err(anyhow!("Duplicate player title '{}'", title.name));
+            }
+
+            // Ensure the challenges granting titles exist
+            if let TitleUnlock::Challenge { challenge } = &title.unlock {
+                if challenges.by_name(challenge).is_none() {
+                    return Err(anyhow!(
+                        "Player title '{}' is granted by unknown challenge {}",
+                        title.name,
+                        challenge
+                    ));
+                }
+            }
+        }
+
+        debug!("Loaded {} player title definition(s)", values.len());
+
+        Ok(Self { values })
+    }
+
+    /// Finds a title definition by its `name`
+    pub fn by_name(&self, name: &str) -> Option<&PlayerTitle> {
+        self.values.iter().find(|title| title.name == name)
+    }
+
+    /// Finds the titles granted by completing the challenge with the provided `name`
+    pub fn by_challenge<'a>(
+        &'a self,
+        name: &'a ChallengeName,
+    ) -> impl Iterator<Item = &'a PlayerTitle> + 'a {
+        self.values.iter().filter(move |title| match &title.unlock {
+            TitleUnlock::Challenge { challenge } => challenge == name,
+            TitleUnlock::Milestone { .. } => false,
+        })
+    }
+
+    /// Finds the milestone titles that have been reached by the `stats`
+    pub fn milestones_reached<'a>(
+        &'a self,
+        stats: &'a LeaderboardStats,
+    ) -> impl Iterator<Item = &'a PlayerTitle> + 'a {
+        self.values.iter().filter(move |title| match &title.unlock {
+            TitleUnlock::Milestone { stat, value } => stat.value(stats) >= *value as f32,
+            TitleUnlock::Challenge { .. } => false,
+        })
+    }
+}
+
+/// Title that can be earned and equipped by a player
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerTitle {
+    /// Unique name of the title
+    pub name: PlayerTitleName,
+    /// Text displayed for the title
+    pub text: String,
+    /// How the title is earned
+    pub unlock: TitleUnlock,
+}
+
+/// Ways a title can be earned
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum TitleUnlock {
+    /// Earned the first time the challenge is completed
+    Challenge { challenge: ChallengeName },
+    /// Earned once the career `stat` reaches the `value`
+    Milestone { stat: LeaderboardStat, value: u32 },
+}
+
+#[cfg(test)]
+mod test {
+    use super::PlayerTitles;
+    use crate::database::entity::LeaderboardStats;
+    use chrono::Utc;
+
+    /// Tests that only the milestones reached by the stats are found
+    #[test]
+    fn test_milestones_reached() {
+        let titles = PlayerTitles::load().unwrap();
+        let stats = LeaderboardStats {
+            user_id: 1,
+            n7_rating: 20,
+            apex_rating: 0.0,
+            challenge_points: 0,
+            match_count: 100,
+            updated: Utc::now(),
+        };
+
+        let reached: Vec<&str> = titles
+            .milestones_reached(&stats)
+            .map(|title| title.name.as_str())
+            .collect();
+        assert_eq!(reached, ["Recruit", "Veteran"]);
+    }
+}
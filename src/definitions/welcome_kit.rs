@@ -0,0 +1,118 @@
+//! Welcome kit granted to newly created accounts, the kit is loaded
+//! from the welcome kit definitions file
+
+use super::{
+    challenges::CurrencyReward,
+    definition_init,
+    items::{ItemName, Items},
+    parse_definition,
+};
+use crate::{
+    database::entity::{currency_ledger::CurrencyLedgerReason, User, WelcomeKitGrant},
+    services::{
+        activity::ActivityResult,
+        rewards::{RewardBundle, RewardEngine},
+    },
+};
+use anyhow::{anyhow, Context};
+use log::debug;
+use sea_orm::{ConnectionTrait, TransactionTrait};
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// Welcome kit definition
+const WELCOME_KIT_DEFINITION: &str = include_str!("../resources/data/welcomeKit.json");
+
+/// Starter kit of items (characters, weapons, etc) and currencies
+/// given to new accounts
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WelcomeKit {
+    /// Items to grant
+    pub items: Vec<WelcomeKitItem>,
+    /// Currencies to grant
+    pub currencies: Vec<CurrencyReward>,
+}
+
+/// Item within the welcome kit
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WelcomeKitItem {
+    /// The [ItemName] of the item to give
+    pub name: ItemName,
+    /// How much of the item to give
+    pub count: u32,
+}
+
+/// Static storage for the definitions once its loaded
+/// (Allows the definitions to be passed with static lifetimes)
+static STORE: OnceLock<WelcomeKit> = OnceLock::new();
+
+definition_init!(WelcomeKit, STORE, load);
+
+impl WelcomeKit {
+    /// Gets a static reference to the global [WelcomeKit]
+    pub fn get() -> &'static WelcomeKit {
+        STORE.get_or_init(|| Self::load().unwrap())
+    }
+
+    fn load() -> anyhow::Result<Self> {
+        let kit: WelcomeKit = parse_definition("welcomeKit.json", WELCOME_KIT_DEFINITION)
+            .context("Failed to load welcome kit definition")?;
+
+        // Ensure all the items in the kit exist
+        let items = Items::get();
+        for item in &kit.items {
+            if items.by_name(&item.name).is_none() {
+                return Err(anyhow!("Unknown welcome kit item '{}'", item.name));
+            }
+        }
+
+        debug!(
+            "Loaded welcome kit with {} item(s) and {} currencies",
+            kit.items.len(),
+            kit.currencies.len()
+        );
+
+        Ok(kit)
+    }
+
+    /// Grants the welcome kit to the provided `user`, the kit is only
+    /// granted once per account. Returns whether the kit was granted
+    pub async fn apply<C>(&self, db: &C, user: &User) -> anyhow::Result<bool>
+    where
+        C: ConnectionTrait + TransactionTrait + Send,
+    {
+        if WelcomeKitGrant::exists(db, user).await? {
+            return Ok(false);
+        }
+
+        let item_definitions = Items::get();
+        let mut rewards = RewardBundle::default();
+
+        for item in &self.items {
+            let definition = item_definitions
+                .by_name(&item.name)
+                .ok_or(anyhow!("Missing welcome kit item '{}'", item.name))?;
+
+            rewards.add_item(definition, item.count);
+        }
+
+        for currency in &self.currencies {
+            rewards.add_currency(currency.name, currency.value);
+        }
+
+        // The activity result isn't used as the account is still being created
+        let mut result = ActivityResult::default();
+        RewardEngine::grant(
+            db,
+            user,
+            rewards,
+            CurrencyLedgerReason::WelcomeKit,
+            &mut result,
+        )
+        .await?;
+
+        WelcomeKitGrant::create(db, user).await?;
+
+        Ok(true)
+    }
+}
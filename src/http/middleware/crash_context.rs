@@ -0,0 +1,15 @@
+//! Middleware for including the request being handled in the context
+//! of any crash reports created while handling it
+
+use crate::services::crash_reports::{self, CrashContext};
+use axum::{middleware::Next, response::Response};
+use hyper::Request;
+
+/// Middleware function that scopes the handling of the request with
+/// a crash context for the requested route
+pub async fn crash_context<B>(req: Request<B>, next: Next<B>) -> Response {
+    let context =
+        CrashContext::new("http").with_route(format!("{} {}", req.method(), req.uri().path()));
+
+    crash_reports::scope(context, next.run(req)).await
+}
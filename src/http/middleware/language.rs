@@ -0,0 +1,37 @@
+//! Middleware for tracking the language of the current request, used
+//! when localizing error responses
+
+use crate::{definitions::i18n::DEFAULT_LANGUAGE, utils::ImStr};
+use axum::{middleware::Next, response::Response};
+use hyper::{header::ACCEPT_LANGUAGE, Request};
+
+tokio::task_local! {
+    /// Language of the request currently being handled
+    static REQUEST_LANGUAGE: ImStr;
+}
+
+/// Middleware function that scopes the handling of the request with the
+/// language from its `Accept-Language` header
+pub async fn request_language<B>(req: Request<B>, next: Next<B>) -> Response {
+    let language: ImStr = req
+        .headers()
+        .get(ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok())
+        // Take the most preferred language ignoring the quality value
+        .and_then(|value| value.split(',').next())
+        .and_then(|value| value.split(';').next())
+        .map(|value| value.trim().to_lowercase())
+        .filter(|value| !value.is_empty())
+        .map(Box::from)
+        .unwrap_or_else(|| Box::from(DEFAULT_LANGUAGE));
+
+    REQUEST_LANGUAGE.scope(language, next.run(req)).await
+}
+
+/// Gets the language of the request currently being handled, falls back to
+/// [DEFAULT_LANGUAGE] when called outside of a request
+pub fn current_language() -> ImStr {
+    REQUEST_LANGUAGE
+        .try_with(Clone::clone)
+        .unwrap_or_else(|_| Box::from(DEFAULT_LANGUAGE))
+}
@@ -7,7 +7,15 @@ pub type JsonDump<T> = axum::Json<T>;
 #[cfg(debug_assertions)]
 mod json_dump;
 
+pub mod crash_context;
+
 pub mod json_validated;
 
+pub mod language;
+
+pub mod read_only;
+
+pub mod signed_json;
+
 pub mod upgrade;
 pub mod user;
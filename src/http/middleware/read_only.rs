@@ -0,0 +1,57 @@
+//! Middleware for rejecting requests that modify player data while
+//! the server is in read-only mode for maintenance, applied to every
+//! route so new routes are covered without opting in
+
+use crate::{
+    config::Config,
+    http::models::{maintenance::MaintenanceError, DynHttpError},
+    services::maintenance::ReadOnlyMode,
+};
+use axum::{
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Extension,
+};
+use hyper::{header::RETRY_AFTER, http::HeaderValue, Method, Request};
+use std::sync::Arc;
+
+/// Paths that modify data but stay usable in read-only mode so players
+/// can still log in and administrators can turn the mode off
+const ALLOWED_PATHS: [&str; 7] = [
+    "/api/server/login",
+    "/api/server/pocketRelay/login",
+    "/api/server/maintenance",
+    "/api/server/maintenance/readOnly",
+    "/auth",
+    "/auth/refresh",
+    "/presence/session",
+];
+
+/// Middleware function applied to every route, rejects requests that
+/// aren't reads with a retry-later error while read-only mode is enabled
+pub async fn reject_read_only<B>(
+    Extension(read_only): Extension<Arc<ReadOnlyMode>>,
+    req: Request<B>,
+    next: Next<B>,
+) -> Response {
+    if !read_only.is_enabled() || is_allowed(&req) {
+        return next.run(req).await;
+    }
+
+    let mut response = DynHttpError::from(MaintenanceError::ReadOnly).into_response();
+    response.headers_mut().insert(
+        RETRY_AFTER,
+        HeaderValue::from(Config::get().maintenance.read_only_retry_secs),
+    );
+    response
+}
+
+/// Checks whether the `req` is allowed while in read-only mode
+fn is_allowed<B>(req: &Request<B>) -> bool {
+    let method = req.method();
+    if method == Method::GET || method == Method::HEAD || method == Method::OPTIONS {
+        return true;
+    }
+
+    ALLOWED_PATHS.contains(&req.uri().path().trim_end_matches('/'))
+}
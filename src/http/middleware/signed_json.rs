@@ -0,0 +1,248 @@
+//! JSON extractor for requests signed by the client plugin, signed requests
+//! include a timestamp and nonce which are checked against the replay cache
+//! so that captured requests can't be submitted again to double claim rewards
+//!
+//! The signature is a HMAC-SHA256 over the request method, path, timestamp,
+//! nonce, and body separated by newlines. The signature is keyed with the
+//! request signing key given to the client alongside the session token,
+//! the key is derived from the token by the server so holding a captured
+//! token isn't enough to sign requests
+
+use super::user::TOKEN_HEADER;
+use crate::{
+    config::Config,
+    http::models::{DynHttpError, HttpError},
+    services::{replay_cache::ReplayCache, sessions::Sessions},
+    utils::clock,
+};
+use axum::{
+    async_trait,
+    extract::{FromRequest, OriginalUri},
+    BoxError,
+};
+use base64ct::{Base64UrlUnpadded, Encoding};
+use bytes::Bytes;
+use hyper::{body::HttpBody, Request, StatusCode};
+use log::{error, warn};
+use ring::hmac::{self, Key, HMAC_SHA256};
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Header containing the unix timestamp in seconds the request was signed at
+const TIMESTAMP_HEADER: &str = "X-Request-Timestamp";
+/// Header containing the unique nonce for the request
+const NONCE_HEADER: &str = "X-Request-Nonce";
+/// Header containing the base64 url encoded request signature
+const SIGNATURE_HEADER: &str = "X-Request-Signature";
+
+/// Maximum allowed length of a request nonce
+const MAX_NONCE_LENGTH: usize = 64;
+
+/// [axum::Json] extractor alternative that verifies the request signature
+/// and rejects requests that have already been submitted
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SignedJson<T>(pub T);
+
+#[derive(Debug, Error)]
+pub enum SignedRequestError {
+    /// Request wasn't signed while signing is required
+    #[error("Missing request signature")]
+    MissingSignature,
+    /// Signature headers were malformed or the signature didn't match
+    #[error("Invalid request signature")]
+    InvalidSignature,
+    /// Request timestamp is too far from the server time
+    #[error("Request timestamp outside of the allowed window")]
+    Expired,
+    /// Request nonce has already been used
+    #[error("Request has already been submitted")]
+    Replayed,
+    /// Unable to load the content
+    #[error("Content error")]
+    BadContent,
+    /// Failed to deserialize
+    #[error(transparent)]
+    Deserialize(serde_path_to_error::Error<serde_json::Error>),
+}
+
+impl HttpError for SignedRequestError {
+    fn status(&self) -> StatusCode {
+        match self {
+            SignedRequestError::MissingSignature | SignedRequestError::InvalidSignature => {
+                StatusCode::UNAUTHORIZED
+            }
+            SignedRequestError::Expired
+            | SignedRequestError::BadContent
+            | SignedRequestError::Deserialize(_) => StatusCode::BAD_REQUEST,
+            SignedRequestError::Replayed => StatusCode::CONFLICT,
+        }
+    }
+}
+
+/// Signature details taken from the request headers
+struct RequestSignature {
+    timestamp: String,
+    nonce: String,
+    signature: String,
+    token: String,
+}
+
+#[async_trait]
+impl<T, S, B> FromRequest<S, B> for SignedJson<T>
+where
+    T: DeserializeOwned,
+    B: HttpBody + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<BoxError>,
+    S: Send + Sync,
+{
+    type Rejection = DynHttpError;
+
+    async fn from_request(req: Request<B>, state: &S) -> Result<Self, Self::Rejection> {
+        let config = &Config::get().request_signing;
+
+        let replay_cache: Arc<ReplayCache> = req
+            .extensions()
+            .get::<Arc<ReplayCache>>()
+            .expect("Replay cache extension missing")
+            .clone();
+
+        let sessions: Arc<Sessions> = req
+            .extensions()
+            .get::<Arc<Sessions>>()
+            .expect("Sessions extension missing")
+            .clone();
+
+        // Nested routers strip the path prefix so the original path is used
+        let method = req.method().to_string();
+        let path = req
+            .extensions()
+            .get::<OriginalUri>()
+            .map(|uri| uri.0.path())
+            .unwrap_or_else(|| req.uri().path())
+            .to_string();
+
+        let header = |name: &str| -> Option<String> {
+            req.headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+
+        let signature = match (
+            header(TIMESTAMP_HEADER),
+            header(NONCE_HEADER),
+            header(SIGNATURE_HEADER),
+            header(TOKEN_HEADER),
+        ) {
+            (Some(timestamp), Some(nonce), Some(signature), Some(token)) => {
+                Some(RequestSignature {
+                    timestamp,
+                    nonce,
+                    signature,
+                    token,
+                })
+            }
+            _ => None,
+        };
+
+        if signature.is_none() && config.required {
+            return Err(SignedRequestError::MissingSignature.into());
+        }
+
+        // Get request bytes
+        let bytes = Bytes::from_request(req, state).await.map_err(|err| {
+            error!("Failed to get request bytes: {}", err);
+            SignedRequestError::BadContent
+        })?;
+
+        if let Some(signature) = signature {
+            let timestamp: i64 = signature
+                .timestamp
+                .parse()
+                .map_err(|_| SignedRequestError::InvalidSignature)?;
+
+            let now = clock::timestamp();
+
+            if timestamp.abs_diff(now) > config.max_age_secs {
+                return Err(SignedRequestError::Expired.into());
+            }
+
+            if signature.nonce.is_empty() || signature.nonce.len() > MAX_NONCE_LENGTH {
+                return Err(SignedRequestError::InvalidSignature.into());
+            }
+
+            let user_id = sessions
+                .verify_token(&signature.token)
+                .map_err(|_| SignedRequestError::InvalidSignature)?
+                .user_id;
+            let key = sessions.request_signing_key(&signature.token);
+
+            let message = signing_message(
+                &method,
+                &path,
+                &signature.timestamp,
+                &signature.nonce,
+                &bytes,
+            );
+
+            if !verify_signature(&key, &message, &signature.signature) {
+                return Err(SignedRequestError::InvalidSignature.into());
+            }
+
+            // Nonces only need to be remembered until the timestamp would be rejected
+            let expires_at = timestamp.saturating_add(config.max_age_secs as i64);
+            if !replay_cache.insert(user_id, &signature.nonce, expires_at, now) {
+                warn!("Rejected replayed request: {} {}", method, path);
+                return Err(SignedRequestError::Replayed.into());
+            }
+        }
+
+        // Deserialize value
+        let deserializer = &mut serde_json::Deserializer::from_slice(&bytes);
+        let value: T = serde_path_to_error::deserialize(deserializer)
+            .map_err(SignedRequestError::Deserialize)?;
+
+        Ok(SignedJson(value))
+    }
+}
+
+/// Creates the message that is signed for a request
+fn signing_message(method: &str, path: &str, timestamp: &str, nonce: &str, body: &[u8]) -> Vec<u8> {
+    let mut message = format!("{method}\n{path}\n{timestamp}\n{nonce}\n").into_bytes();
+    message.extend_from_slice(body);
+    message
+}
+
+/// Verifies the base64 url encoded `signature` of the `message` was
+/// created using the request signing `key`
+fn verify_signature(key: &str, message: &[u8], signature: &str) -> bool {
+    let Ok(signature) = Base64UrlUnpadded::decode_vec(signature) else {
+        return false;
+    };
+
+    let key = Key::new(HMAC_SHA256, key.as_bytes());
+    hmac::verify(&key, message, &signature).is_ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{signing_message, verify_signature};
+    use base64ct::{Base64UrlUnpadded, Encoding};
+    use ring::hmac::{self, Key, HMAC_SHA256};
+
+    /// Tests that signatures are only valid for the signed message and key
+    #[test]
+    fn test_verify_signature() {
+        let message = signing_message("POST", "/mission/1/finish", "100", "nonce", b"{}");
+        let key = Key::new(HMAC_SHA256, b"key");
+        let signature = Base64UrlUnpadded::encode_string(hmac::sign(&key, &message).as_ref());
+
+        assert!(verify_signature("key", &message, &signature));
+        assert!(!verify_signature("other", &message, &signature));
+
+        let message = signing_message("POST", "/mission/2/finish", "100", "nonce", b"{}");
+        assert!(!verify_signature("key", &message, &signature));
+    }
+}
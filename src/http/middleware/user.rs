@@ -1,7 +1,10 @@
 use crate::{
-    database::entity::User,
-    http::models::{DynHttpError, HttpError},
-    services::sessions::{Sessions, VerifyError},
+    database::entity::{user_permissions::AdminPermission, User, UserBan, UserPermission},
+    http::models::{DynHttpError, HttpError, HttpErrorI18n},
+    services::{
+        crash_reports,
+        sessions::{Sessions, VerifyError},
+    },
 };
 use axum::extract::FromRequestParts;
 use futures::future::BoxFuture;
@@ -12,8 +15,24 @@ use thiserror::Error;
 
 pub struct Auth(pub User);
 
+/// Authentication extractor that requires the user
+/// has administrator permissions
+pub struct AdminAuth(pub User);
+
+/// Authentication extractor that requires the administrator has
+/// been granted the [AdminPermission::Moderator] permission
+pub struct ModeratorAuth(pub User);
+
+/// Authentication extractor that requires the administrator has
+/// been granted the [AdminPermission::Economy] permission
+pub struct EconomyAuth(pub User);
+
+/// Authentication extractor that requires the administrator has
+/// been granted the [AdminPermission::Operator] permission
+pub struct OperatorAuth(pub User);
+
 /// The HTTP header that contains the authentication token
-const TOKEN_HEADER: &str = "X-Token";
+pub const TOKEN_HEADER: &str = "X-Token";
 
 #[derive(Debug, Error)]
 pub enum AuthError {
@@ -23,11 +42,31 @@ pub enum AuthError {
     /// Invalid token provided (Or the associated user doesn't exist anymore)
     #[error("Authorization token invalid")]
     InvalidToken,
+    /// The user doesn't have permission to access the resource
+    #[error("Insufficient permission")]
+    InsufficientPermission,
+    /// The user is banned from the server
+    #[error("Account is banned")]
+    Banned,
 }
 
 impl HttpError for AuthError {
     fn status(&self) -> StatusCode {
-        StatusCode::BAD_REQUEST
+        match self {
+            AuthError::MissingToken | AuthError::InvalidToken => StatusCode::BAD_REQUEST,
+            AuthError::InsufficientPermission | AuthError::Banned => StatusCode::FORBIDDEN,
+        }
+    }
+
+    fn i18n(&self) -> Option<HttpErrorI18n> {
+        match self {
+            // "Error"
+            AuthError::MissingToken | AuthError::InvalidToken => Some(HttpErrorI18n::new(12873)),
+            // "UNAVAILABLE"
+            AuthError::InsufficientPermission | AuthError::Banned => {
+                Some(HttpErrorI18n::new(65339))
+            }
+        }
     }
 }
 
@@ -63,16 +102,99 @@ impl<S> FromRequestParts<S> for Auth {
                 .and_then(|value| value.to_str().ok())
                 .ok_or(AuthError::MissingToken)?;
 
-            let user_id: u32 = sessions
+            let claims = sessions
                 .verify_token(token)
                 .map_err(|_| AuthError::InvalidToken)?;
 
-            let user = User::by_id(&db, user_id)
+            let user = User::by_id(&db, claims.user_id)
                 .await?
                 .ok_or(VerifyError::Invalid)
                 .map_err(|_| AuthError::InvalidToken)?;
 
+            // Tokens issued before the user tokens were revoked (i.e. after
+            // changing password) are no longer valid
+            if claims.is_revoked(user.tokens_revoked_at) {
+                return Err(AuthError::InvalidToken.into());
+            }
+
+            // Tokens issued before a ban must stop working once the user is banned
+            if UserBan::active(&db, user.id).await?.is_some() {
+                return Err(AuthError::Banned.into());
+            }
+
+            // Include the user in any crash reports for the request
+            crash_reports::set_user(user.id);
+
+            Ok(Self(user))
+        })
+    }
+}
+
+impl<S> FromRequestParts<S> for AdminAuth {
+    type Rejection = DynHttpError;
+
+    fn from_request_parts<'a, 'b, 'c>(
+        parts: &'a mut axum::http::request::Parts,
+        state: &'b S,
+    ) -> BoxFuture<'c, Result<Self, Self::Rejection>>
+    where
+        'a: 'c,
+        'b: 'c,
+        Self: 'c,
+    {
+        let auth = Auth::from_request_parts(parts, state);
+
+        Box::pin(async move {
+            let Auth(user) = auth.await?;
+
+            // Ensure the user is an admin
+            if !user.role.is_admin() {
+                return Err(AuthError::InsufficientPermission.into());
+            }
+
             Ok(Self(user))
         })
     }
 }
+
+/// Implements [FromRequestParts] for an extractor that requires the
+/// administrator has been granted the provided permission
+macro_rules! impl_permission_auth {
+    ($ty:ident, $permission:expr) => {
+        impl<S> FromRequestParts<S> for $ty {
+            type Rejection = DynHttpError;
+
+            fn from_request_parts<'a, 'b, 'c>(
+                parts: &'a mut axum::http::request::Parts,
+                state: &'b S,
+            ) -> BoxFuture<'c, Result<Self, Self::Rejection>>
+            where
+                'a: 'c,
+                'b: 'c,
+                Self: 'c,
+            {
+                let db = parts
+                    .extensions
+                    .get::<DatabaseConnection>()
+                    .expect("Database connection extension missing")
+                    .clone();
+
+                let auth = AdminAuth::from_request_parts(parts, state);
+
+                Box::pin(async move {
+                    let AdminAuth(user) = auth.await?;
+
+                    if !UserPermission::has(&db, &user, $permission).await? {
+                        return Err(AuthError::InsufficientPermission.into());
+                    }
+
+                    Ok(Self(user))
+                })
+            }
+        }
+    };
+}
+
+impl_permission_auth!(ModeratorAuth, AdminPermission::Moderator);
+impl_permission_auth!(EconomyAuth, AdminPermission::Economy);
+impl_permission_auth!(OperatorAuth, AdminPermission::Operator);
@@ -0,0 +1,206 @@
+use super::HttpError;
+use crate::{
+    database::entity::{
+        currency::CurrencyType,
+        users::{Platform, UserId, UserRole},
+        User,
+    },
+    definitions::items::ItemName,
+    services::inventory_transfer::{ImportError, ImportJobId, ImportMode, TransferFormat},
+};
+use hyper::StatusCode;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use validator::Validate;
+
+#[derive(Debug, Error)]
+pub enum AdminError {
+    /// User with the requested ID doesn't exist
+    #[error("Unknown user")]
+    UnknownUser,
+    /// Item definition with the requested name doesn't exist
+    #[error("Unknown item")]
+    UnknownItem,
+    /// Administrators can't delete their own account
+    #[error("Cannot delete your own account")]
+    DeleteSelf,
+    /// Target user has the same or a higher role than the administrator
+    #[error("Insufficient role to modify user")]
+    InsufficientRole,
+    /// Permissions can only be granted to administrators
+    #[error("User is not an administrator")]
+    NotAdmin,
+    /// Administrators can't ban their own account
+    #[error("Cannot ban your own account")]
+    BanSelf,
+    /// The user doesn't have an active ban
+    #[error("User is not banned")]
+    NotBanned,
+    /// Inventory import job with the requested ID doesn't exist
+    #[error("Unknown import")]
+    UnknownImport,
+    /// The item is locked or granting it would exceed its capacity
+    #[error("Item capacity reached")]
+    ItemCapacityReached,
+}
+
+impl HttpError for AdminError {
+    fn status(&self) -> StatusCode {
+        match self {
+            AdminError::UnknownUser
+            | AdminError::UnknownItem
+            | AdminError::NotBanned
+            | AdminError::UnknownImport => StatusCode::NOT_FOUND,
+            AdminError::DeleteSelf | AdminError::NotAdmin | AdminError::BanSelf => {
+                StatusCode::BAD_REQUEST
+            }
+            AdminError::InsufficientRole => StatusCode::FORBIDDEN,
+            AdminError::ItemCapacityReached => StatusCode::CONFLICT,
+        }
+    }
+}
+
+impl HttpError for ImportError {
+    fn status(&self) -> StatusCode {
+        match self {
+            ImportError::InvalidFile(_) => StatusCode::BAD_REQUEST,
+            ImportError::TooManyRows => StatusCode::PAYLOAD_TOO_LARGE,
+        }
+    }
+}
+
+/// Query for the format of inventory exports
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct TransferFormatQuery {
+    /// The file format, defaults to JSON
+    pub format: TransferFormat,
+}
+
+/// Query for the format and mode of inventory imports
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ImportQuery {
+    /// The file format, defaults to JSON
+    pub format: TransferFormat,
+    /// How the rows are applied, defaults to adjusting the balances
+    pub mode: ImportMode,
+}
+
+/// Response after starting an inventory import
+#[derive(Debug, Serialize)]
+pub struct ImportStartedResponse {
+    /// ID of the import job for checking its progress
+    pub id: ImportJobId,
+}
+
+/// Query for paginating the list of users
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct UsersQuery {
+    /// The number of users to skip
+    pub offset: u64,
+    /// The number of users to return
+    pub count: u64,
+}
+
+impl Default for UsersQuery {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            count: 20,
+        }
+    }
+}
+
+/// Request to ban a user from the server
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct BanUserRequest {
+    /// Reason for the ban
+    #[validate(length(max = 256))]
+    pub reason: Option<String>,
+    /// How many hours the ban lasts for, [None] for a permanent ban
+    #[validate(range(min = 1))]
+    pub duration_hours: Option<u32>,
+}
+
+/// Request to send a notification message to a user
+#[derive(Debug, Deserialize, Validate)]
+pub struct SendNotificationRequest {
+    /// The message to send
+    #[validate(length(min = 1, max = 512))]
+    pub message: String,
+}
+
+/// Query for paginating the permission audit log
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct PermissionAuditQuery {
+    /// The number of entries to skip
+    pub offset: u64,
+    /// The number of entries to return
+    pub count: u64,
+}
+
+impl Default for PermissionAuditQuery {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            count: 20,
+        }
+    }
+}
+
+/// User details visible to administrators
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminUser {
+    pub id: UserId,
+    pub email: String,
+    pub username: String,
+    pub role: UserRole,
+    pub platform: Platform,
+}
+
+impl From<User> for AdminUser {
+    fn from(value: User) -> Self {
+        Self {
+            id: value.id,
+            email: value.email,
+            username: value.username,
+            role: value.role,
+            platform: value.platform,
+        }
+    }
+}
+
+/// Request to grant an item to a user
+#[derive(Debug, Validate, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GrantItemRequest {
+    /// Name of the item definition to grant
+    pub item_name: ItemName,
+    /// The number of the item to grant
+    #[validate(range(min = 1, max = 10000))]
+    pub count: u32,
+}
+
+/// Request to grant currency to a user
+#[derive(Debug, Validate, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GrantCurrencyRequest {
+    /// The currency to grant
+    pub currency: CurrencyType,
+    /// The amount of currency to grant
+    #[validate(range(min = 1, max = 100000000))]
+    pub amount: u32,
+}
+
+/// Response after resetting the challenge progress of a user
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResetChallengesResponse {
+    /// The number of challenges that had their progress removed
+    pub reset_challenges: u64,
+}
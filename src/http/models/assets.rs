@@ -0,0 +1,18 @@
+use super::HttpError;
+use hyper::StatusCode;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum AssetError {
+    /// Icon isn't referenced by any badge or challenge definition
+    #[error("Unknown icon")]
+    UnknownIcon,
+}
+
+impl HttpError for AssetError {
+    fn status(&self) -> StatusCode {
+        match self {
+            AssetError::UnknownIcon => StatusCode::NOT_FOUND,
+        }
+    }
+}
@@ -21,6 +21,8 @@ pub struct AuthRequest {
 #[serde(rename_all = "camelCase")]
 pub struct AuthResponse {
     pub session_id: String,
+    /// Key used to sign requests made with the session token
+    pub signing_key: String,
     pub user: AuthUser,
     pub pid: String,
     pub server_time: DateTime<Utc>,
@@ -38,3 +40,13 @@ pub struct AuthUser {
     pub anonymous: bool,
     pub name: String,
 }
+
+/// Response containing a new authentication token
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshTokenResponse {
+    /// The new token
+    pub token: String,
+    /// Key used to sign requests made with the new token
+    pub signing_key: String,
+}
@@ -2,9 +2,11 @@ use super::HttpError;
 use crate::{
     database::entity::{characters::CharacterId, Character, SharedData},
     definitions::{
-        classes::{CharacterEquipment, Class, CustomizationEntry},
+        classes::{CharacterEquipment, Class, CustomizationEntry, CustomizationError},
+        equipment_sets::EquipmentSet,
+        items::ItemName,
         level_tables::LevelTable,
-        skills::{SkillDefinition, SkillTree},
+        skills::{SkillAllocation, SkillAllocationError, SkillDefinition, SkillTree},
     },
 };
 use hyper::StatusCode;
@@ -17,16 +19,41 @@ use thiserror::Error;
 pub enum CharactersError {
     #[error("Character not found")]
     NotFound,
+    #[error("Character class not found")]
+    MissingClass,
+    #[error("Skill trees cost {0} skill points but only {1} are available")]
+    InsufficientSkillPoints(u32, u32),
+    #[error("Item {0} can't be equipped in this slot")]
+    InvalidEquipment(ItemName),
+    #[error("Customization version not found")]
+    UnknownCustomizationVersion,
 }
 
 impl HttpError for CharactersError {
     fn status(&self) -> StatusCode {
         match self {
-            CharactersError::NotFound => StatusCode::NOT_FOUND,
+            CharactersError::NotFound | CharactersError::UnknownCustomizationVersion => {
+                StatusCode::NOT_FOUND
+            }
+            CharactersError::MissingClass => StatusCode::INTERNAL_SERVER_ERROR,
+            CharactersError::InvalidEquipment(_) => StatusCode::BAD_REQUEST,
+            CharactersError::InsufficientSkillPoints(_, _) => StatusCode::CONFLICT,
         }
     }
 }
 
+impl HttpError for CustomizationError {
+    fn status(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
+}
+
+impl HttpError for SkillAllocationError {
+    fn status(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CharactersResponse {
@@ -50,6 +77,22 @@ pub struct UpdateSkillTreesRequest {
     pub skill_trees: Vec<SkillTree>,
 }
 
+/// Computed costs and bonuses for a hypothetical skill tree allocation
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkillTreePreviewResponse {
+    /// Skill points the character has to spend in total
+    pub available: u32,
+    /// Skill points remaining after the allocation
+    pub remaining: u32,
+    /// Whether the character has enough skill points for the allocation
+    pub affordable: bool,
+    #[serde(flatten)]
+    pub allocation: SkillAllocation,
+    /// The character skill trees with the allocation applied
+    pub skill_trees: Vec<SkillTree>,
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CharacterResponse {
@@ -57,6 +100,8 @@ pub struct CharacterResponse {
     pub character: Character,
     #[serde(flatten)]
     pub shared_data: SharedData,
+    /// Equipment sets completed by the character equipment
+    pub active_equipment_sets: Vec<&'static EquipmentSet>,
 }
 
 #[derive(Debug, Serialize)]
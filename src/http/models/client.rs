@@ -1,4 +1,11 @@
 use super::HttpError;
+use crate::{
+    config::ClientDownload,
+    definitions::welcome_kit::WelcomeKit,
+    services::{
+        auth::AuthProviderError, feature_flags::EvaluatedFlags, pocket_relay::PocketRelayError,
+    },
+};
 use hyper::StatusCode;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
@@ -6,12 +13,6 @@ use validator::Validate;
 
 #[derive(Debug, Error)]
 pub enum ClientError {
-    #[error("Account not found")]
-    AccountNotFound,
-
-    #[error("Incorrect password")]
-    IncorrectPassword,
-
     /// Username is already taken
     #[error("Email already in use")]
     EmailTaken,
@@ -19,14 +20,88 @@ pub enum ClientError {
     /// Username is already taken
     #[error("Username already in use")]
     UsernameAlreadyTaken,
+
+    /// Accounts are managed by an external authentication provider
+    #[error("Account creation is disabled")]
+    RegistrationDisabled,
+
+    /// Too many sessions are connected from the same household
+    #[error("Too many accounts are connected from your network")]
+    HouseholdSessionLimit,
+
+    /// Passwords are managed by an external authentication provider
+    #[error("Password changes are disabled")]
+    PasswordChangeDisabled,
+
+    /// The account has been banned from the server
+    #[error("Your account is banned from this server")]
+    Banned,
+
+    /// The server doesn't serve client plugin updates
+    #[error("No client updates are available")]
+    NoClientUpdate,
 }
 
 impl HttpError for ClientError {
     fn status(&self) -> StatusCode {
         match self {
-            ClientError::AccountNotFound => StatusCode::NOT_FOUND,
-            ClientError::IncorrectPassword => StatusCode::BAD_REQUEST,
             ClientError::UsernameAlreadyTaken | ClientError::EmailTaken => StatusCode::CONFLICT,
+            ClientError::RegistrationDisabled
+            | ClientError::PasswordChangeDisabled
+            | ClientError::Banned => StatusCode::FORBIDDEN,
+            ClientError::HouseholdSessionLimit => StatusCode::TOO_MANY_REQUESTS,
+            ClientError::NoClientUpdate => StatusCode::NOT_FOUND,
+        }
+    }
+}
+
+impl HttpError for AuthProviderError {
+    fn status(&self) -> StatusCode {
+        match self {
+            AuthProviderError::AccountNotFound => StatusCode::NOT_FOUND,
+            AuthProviderError::InvalidCredentials => StatusCode::BAD_REQUEST,
+            AuthProviderError::AccountExists
+            | AuthProviderError::LinkedToOther
+            | AuthProviderError::AlreadyLinked => StatusCode::CONFLICT,
+            AuthProviderError::LinkingUnsupported => StatusCode::FORBIDDEN,
+            AuthProviderError::Database(_) | AuthProviderError::Other(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    fn reason(&self) -> String {
+        match self {
+            // Provider and database errors shouldn't be visible to users
+            AuthProviderError::Database(_) | AuthProviderError::Other(_) => {
+                "Server error".to_string()
+            }
+            _ => self.to_string(),
+        }
+    }
+}
+
+impl HttpError for PocketRelayError {
+    fn status(&self) -> StatusCode {
+        match self {
+            PocketRelayError::Disabled => StatusCode::FORBIDDEN,
+            PocketRelayError::InvalidCredentials => StatusCode::BAD_REQUEST,
+            PocketRelayError::LinkedToOther
+            | PocketRelayError::AlreadyLinked
+            | PocketRelayError::AccountExists => StatusCode::CONFLICT,
+            PocketRelayError::Database(_) | PocketRelayError::Other(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    fn reason(&self) -> String {
+        match self {
+            // Pocket Relay and database errors shouldn't be visible to users
+            PocketRelayError::Database(_) | PocketRelayError::Other(_) => {
+                "Server error".to_string()
+            }
+            _ => self.to_string(),
         }
     }
 }
@@ -40,6 +115,26 @@ pub struct ServerDetailsResponse {
     pub version: &'static str,
 }
 
+/// Query for checking for client plugin updates
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ClientUpdateQuery {
+    /// Only include the downloads for this platform
+    pub platform: Option<String>,
+}
+
+/// Response containing the latest client plugin release
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientUpdateResponse {
+    /// The latest plugin version
+    pub version: &'static str,
+    /// Release notes for the version
+    pub release_notes: Option<&'static str>,
+    /// Downloads of the version for each platform
+    pub downloads: Vec<&'static ClientDownload>,
+}
+
 /// Request to create a new user
 #[derive(Debug, Validate, Deserialize)]
 pub struct CreateUserRequest {
@@ -56,6 +151,7 @@ pub struct CreateUserRequest {
 
 /// Request to login to a user
 #[derive(Debug, Validate, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct LoginUserRequest {
     /// The user email
     #[validate(email)]
@@ -63,11 +159,53 @@ pub struct LoginUserRequest {
     /// The user password
     #[validate(length(min = 1))]
     pub password: String,
+    /// Version of the client logging in
+    #[serde(default)]
+    #[validate(length(max = 64))]
+    pub client_version: Option<String>,
+    /// Platform the client is running on
+    #[serde(default)]
+    #[validate(length(max = 64))]
+    pub platform: Option<String>,
+}
+
+/// Request to link an external account (authentication provider or
+/// Pocket Relay) to the current user
+#[derive(Debug, Validate, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkAccountRequest {
+    /// The external account email
+    #[validate(email)]
+    pub email: String,
+    /// The external account password
+    #[validate(length(min = 1))]
+    pub password: String,
+}
+
+/// Request to change the password of the current user
+#[derive(Debug, Validate, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangePasswordRequest {
+    /// The current password
+    #[validate(length(min = 1))]
+    pub current_password: String,
+    /// The new password
+    #[validate(length(min = 1))]
+    pub new_password: String,
 }
 
 /// Response JSON containing a token
 #[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct TokenResponse {
     /// The token field
     pub token: String,
+    /// Key used to sign requests made with the token
+    pub signing_key: String,
+    /// The welcome kit granted to the account, only included
+    /// on the first login
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub welcome_kit: Option<&'static WelcomeKit>,
+    /// Feature flags evaluated for the account
+    pub feature_flags: EvaluatedFlags,
 }
@@ -0,0 +1,204 @@
+//! Compact models for the companion app API, these only contain the details
+//! a companion app needs to display and manage strike teams rather than the
+//! full structures the game client expects
+
+use super::strike_teams::{ResolveMissionResponse, StrikeTeamWithMission};
+use crate::{
+    config::StreakBonus,
+    database::entity::{
+        currency::CurrencyType, strike_team_mission::StrikeTeamMissionId,
+        strike_teams::StrikeTeamId, StrikeTeamMission,
+    },
+    definitions::strike_teams::{
+        MissionDifficulty, StrikeTeamEquipment, StrikeTeamEquipmentName, StrikeTeamName,
+    },
+    services::strike_team_deployment::StrikeTeamState,
+    utils::{clock, ImStr},
+};
+use sea_orm::prelude::DateTimeUtc;
+use serde::Serialize;
+use serde_with::{serde_as, skip_serializing_none};
+use std::collections::HashMap;
+
+/// Overview of the users strike teams and the missions they can deploy on
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompanionOverview {
+    /// The users strike teams
+    pub teams: Vec<CompanionTeam>,
+    /// Missions that strike teams can be deployed onto
+    pub missions: Vec<CompanionMission>,
+    /// Number of missions waiting for a strike team to return
+    pub queued_missions: usize,
+    /// Current server time so apps can run timers without clock drift
+    pub server_time: DateTimeUtc,
+}
+
+/// Compact strike team details
+#[serde_as]
+#[skip_serializing_none]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompanionTeam {
+    #[serde_as(as = "serde_with::DisplayFromStr")]
+    pub id: StrikeTeamId,
+    pub name: StrikeTeamName,
+    pub level: u32,
+    pub success_streak: u32,
+    /// Name of the equipment the team has
+    pub equipment: Option<StrikeTeamEquipmentName>,
+    pub state: StrikeTeamState,
+    /// The mission the team is assigned to
+    #[serde_as(as = "Option<serde_with::DisplayFromStr>")]
+    pub mission: Option<StrikeTeamMissionId>,
+    /// When the team returns from its mission
+    pub finish_time: Option<DateTimeUtc>,
+    /// Seconds until the team returns from its mission
+    pub seconds_remaining: Option<i64>,
+}
+
+impl From<StrikeTeamWithMission> for CompanionTeam {
+    fn from(value: StrikeTeamWithMission) -> Self {
+        let StrikeTeamWithMission {
+            team,
+            mission,
+            state,
+        } = value;
+
+        let finish_time = mission.as_ref().and_then(|mission| mission.finish_time);
+        let seconds_remaining =
+            finish_time.map(|finish_time| (finish_time - clock::now()).num_seconds().max(0));
+
+        Self {
+            id: team.id,
+            name: team.name,
+            level: team.level,
+            success_streak: team.success_streak,
+            equipment: team.equipment.map(|equipment| equipment.name),
+            state,
+            mission: mission.map(|mission| mission.name),
+            finish_time,
+            seconds_remaining,
+        }
+    }
+}
+
+/// Compact strike team mission details
+#[serde_as]
+#[skip_serializing_none]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompanionMission {
+    #[serde_as(as = "serde_with::DisplayFromStr")]
+    pub id: StrikeTeamMissionId,
+    /// Localized mission name
+    pub name: Option<ImStr>,
+    pub difficulty: Option<MissionDifficulty>,
+    /// Time in seconds a strike team takes to complete the mission
+    pub duration_seconds: u16,
+    /// Time in seconds when the mission is no longer available
+    pub end_seconds: i64,
+}
+
+impl From<StrikeTeamMission> for CompanionMission {
+    fn from(value: StrikeTeamMission) -> Self {
+        Self {
+            id: value.id,
+            difficulty: value.difficulty(),
+            name: value.descriptor.i18n_name.loc_name,
+            duration_seconds: value.sp_length_seconds,
+            end_seconds: value.end_seconds,
+        }
+    }
+}
+
+/// Response to deploying onto a mission from the companion app
+#[skip_serializing_none]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompanionDeployResponse {
+    /// Whether the mission was queued as all teams were busy
+    pub queued: bool,
+    /// The team that was deployed if the mission wasn't queued
+    pub team: Option<CompanionTeam>,
+    /// Position in the deployment queue if the mission was queued
+    pub queue_position: Option<usize>,
+}
+
+/// Outcome of resolving a strike team mission
+#[skip_serializing_none]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompanionResolution {
+    pub team: CompanionTeam,
+    pub mission_successful: bool,
+    /// Currencies earned from the mission
+    pub earned: HashMap<CurrencyType, u32>,
+    pub streak_bonus: Option<StreakBonus>,
+}
+
+impl From<ResolveMissionResponse> for CompanionResolution {
+    fn from(value: ResolveMissionResponse) -> Self {
+        let mut earned: HashMap<CurrencyType, u32> = HashMap::new();
+        for currency in value.activity_response.currency_earned {
+            *earned.entry(currency.ty).or_default() += currency.balance;
+        }
+
+        Self {
+            team: value.team.into(),
+            mission_successful: value.mission_successful,
+            earned,
+            streak_bonus: value.streak_bonus,
+        }
+    }
+}
+
+/// Response listing the outcomes of resolving every returned team
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompanionResolveAllResponse {
+    pub resolutions: Vec<CompanionResolution>,
+}
+
+/// Strike team equipment available for purchase
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompanionShop {
+    /// The users current currency balances
+    pub balances: HashMap<CurrencyType, u32>,
+    pub equipment: Vec<CompanionEquipment>,
+}
+
+/// Compact strike team equipment details
+#[skip_serializing_none]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompanionEquipment {
+    pub name: StrikeTeamEquipmentName,
+    /// Localized equipment name
+    pub display_name: Option<ImStr>,
+    pub level_required: u32,
+    pub effectiveness: u32,
+    pub cost_by_currency: HashMap<CurrencyType, u32>,
+}
+
+impl From<&StrikeTeamEquipment> for CompanionEquipment {
+    fn from(value: &StrikeTeamEquipment) -> Self {
+        Self {
+            name: value.name.clone(),
+            display_name: value.i18n_name.loc_name.clone(),
+            level_required: value.level_required,
+            effectiveness: value.effectiveness,
+            cost_by_currency: value.cost_by_currency.clone(),
+        }
+    }
+}
+
+/// Response to purchasing strike team equipment
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompanionPurchaseResponse {
+    pub team: CompanionTeam,
+    /// The balance of the currency spent on the equipment
+    pub balance: u32,
+}
@@ -0,0 +1,48 @@
+use super::HttpError;
+use crate::services::crash_reports::{CrashContext, CrashReport, CrashReportId};
+use chrono::{DateTime, Utc};
+use hyper::StatusCode;
+use serde::Serialize;
+use serde_with::skip_serializing_none;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum CrashReportError {
+    /// Crash report with the requested ID doesn't exist
+    #[error("Unknown crash report")]
+    UnknownReport,
+}
+
+impl HttpError for CrashReportError {
+    fn status(&self) -> StatusCode {
+        match self {
+            CrashReportError::UnknownReport => StatusCode::NOT_FOUND,
+        }
+    }
+}
+
+/// Crash report details without the backtrace, used when listing reports
+#[skip_serializing_none]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashReportSummary {
+    pub id: CrashReportId,
+    pub created_at: DateTime<Utc>,
+    pub version: String,
+    pub message: String,
+    pub location: Option<String>,
+    pub context: Option<CrashContext>,
+}
+
+impl From<CrashReport> for CrashReportSummary {
+    fn from(value: CrashReport) -> Self {
+        Self {
+            id: value.id,
+            created_at: value.created_at,
+            version: value.version,
+            message: value.message,
+            location: value.location,
+            context: value.context,
+        }
+    }
+}
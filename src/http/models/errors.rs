@@ -1,6 +1,11 @@
 //! Module for HTTP error dynamic backed types, also contains
 //! shared HTTP error types used by multiple route groups
 
+use crate::{
+    database::entity::currency::CurrencyType,
+    definitions::i18n::{I18n, I18nKey, LookupKey},
+    http::middleware::language::current_language,
+};
 use hyper::StatusCode;
 use log::error;
 use std::{
@@ -24,17 +29,52 @@ pub enum CurrencyError {
     InvalidCurrency,
     /// User doesn't have enough currency to purchase the item
     #[error("Currency balance cannot be less than 0.")]
-    InsufficientCurrency,
+    InsufficientCurrency {
+        /// The currency that was being spent
+        currency: CurrencyType,
+        /// The amount of the currency that was required
+        amount: u32,
+    },
+    /// No exchange rate is configured between the currencies
+    #[error("Currency conversion not available")]
+    ConversionUnavailable,
+    /// Conversion amount isn't a multiple of the exchange rate
+    #[error("Invalid conversion amount")]
+    InvalidConversionAmount,
+    /// Conversion would exceed the daily conversion cap
+    #[error("Daily conversion limit reached")]
+    ConversionCapReached,
+    /// Balance can't hold the currency without going over the currency cap
+    #[error("Currency balance cannot exceed the maximum")]
+    BalanceCapReached,
 }
 
 impl HttpError for CurrencyError {
     fn status(&self) -> StatusCode {
         match self {
-            CurrencyError::InvalidCurrency | CurrencyError::InsufficientCurrency => {
-                StatusCode::CONFLICT
+            CurrencyError::InvalidCurrency
+            | CurrencyError::InsufficientCurrency { .. }
+            | CurrencyError::ConversionCapReached
+            | CurrencyError::BalanceCapReached => StatusCode::CONFLICT,
+            CurrencyError::ConversionUnavailable | CurrencyError::InvalidConversionAmount => {
+                StatusCode::BAD_REQUEST
             }
         }
     }
+
+    fn i18n(&self) -> Option<HttpErrorI18n> {
+        match self {
+            CurrencyError::InsufficientCurrency { currency, amount } => Some(match currency {
+                // "Not enough credits - {CUSTOM0} credits needed."
+                CurrencyType::Grind => HttpErrorI18n::with_params(188574, vec![amount.to_string()]),
+                // "<font color=A52716>INSUFFICIENT MISSION FUNDS</font>"
+                CurrencyType::Mission => HttpErrorI18n::new(109952),
+                // "INSUFFICIENT FUNDS"
+                _ => HttpErrorI18n::new(112557),
+            }),
+            _ => None,
+        }
+    }
 }
 
 /// Type alias for dynamic error handling and JSON responses
@@ -68,9 +108,22 @@ impl IntoResponse for DynHttpError {
         // Log the underlying error
         self.inner.log();
 
+        // Resolve the localized reason using the request language
+        let (i18n_reason, loc_reason) = match self.inner.i18n() {
+            Some(HttpErrorI18n { key, params }) => {
+                let key = I18nKey::Lookup(key);
+                let i18n = I18n::for_language(&current_language());
+                let loc_reason = i18n.format(&key, &params);
+                (Some(key), loc_reason)
+            }
+            None => (None, None),
+        };
+
         // Create the response body
         let body = Json(RawHttpError {
             reason: self.inner.reason(),
+            i18n_reason,
+            loc_reason,
             cause: None,
             stack_trace: None,
             trace_id: None,
@@ -100,6 +153,13 @@ pub trait HttpError: Error + Send + Sync + 'static {
         self.to_string()
     }
 
+    /// Provides the translation to use for the localized reason message
+    /// in the error response, errors without a translation only provide
+    /// the [HttpError::reason]
+    fn i18n(&self) -> Option<HttpErrorI18n> {
+        None
+    }
+
     /// Provides the full type name for the actual error type thats been
     /// erased by dynamic typing (For better error source clarity)
     fn type_name(&self) -> &str {
@@ -112,6 +172,35 @@ impl HttpError for DbErr {
         // Database errors shouldn't be visible to users
         "Server error".to_string()
     }
+
+    fn i18n(&self) -> Option<HttpErrorI18n> {
+        // "Error"
+        Some(HttpErrorI18n::new(12873))
+    }
+}
+
+/// Translation details for a [HttpError] reason
+#[derive(Debug)]
+pub struct HttpErrorI18n {
+    /// The translation lookup key
+    pub key: LookupKey,
+    /// Parameters to replace the translation placeholders with
+    pub params: Vec<String>,
+}
+
+impl HttpErrorI18n {
+    /// Creates a new translation without any parameters
+    pub fn new(key: LookupKey) -> Self {
+        Self {
+            key,
+            params: Vec::new(),
+        }
+    }
+
+    /// Creates a new translation replacing the placeholders with `params`
+    pub fn with_params(key: LookupKey, params: Vec<String>) -> Self {
+        Self { key, params }
+    }
 }
 
 /// Wrapper around [anyhow::Error] allowing it to be used as a [HttpError]
@@ -134,6 +223,11 @@ impl HttpError for AnyhowHttpError {
         // Anyhow errors use a generic message
         "Server error".to_string()
     }
+
+    fn i18n(&self) -> Option<HttpErrorI18n> {
+        // "Error"
+        Some(HttpErrorI18n::new(12873))
+    }
 }
 
 /// Allow conversion from anyhow errors into [DynHttpError] by wrapping
@@ -178,6 +272,12 @@ where
 #[serde(rename_all = "camelCase")]
 pub struct RawHttpError {
     pub reason: String,
+    /// Translation key for the reason when available
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub i18n_reason: Option<I18nKey>,
+    /// Reason translated into the request language when available
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub loc_reason: Option<String>,
     pub cause: Option<String>,
     pub stack_trace: Option<String>,
     pub trace_id: Option<String>,
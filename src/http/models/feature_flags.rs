@@ -0,0 +1,22 @@
+use super::HttpError;
+use hyper::StatusCode;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum FeatureFlagError {
+    /// Feature flag with the requested name doesn't exist
+    #[error("Unknown feature flag")]
+    UnknownFlag,
+    /// Rollout percentage is outside of the 0-100 range
+    #[error("Rollout percentage must be between 0 and 100")]
+    InvalidRollout,
+}
+
+impl HttpError for FeatureFlagError {
+    fn status(&self) -> StatusCode {
+        match self {
+            FeatureFlagError::UnknownFlag => StatusCode::NOT_FOUND,
+            FeatureFlagError::InvalidRollout => StatusCode::BAD_REQUEST,
+        }
+    }
+}
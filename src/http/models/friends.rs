@@ -0,0 +1,55 @@
+use super::HttpError;
+use crate::{database::entity::users::UserId, services::game::GameID};
+use hyper::StatusCode;
+use sea_orm::prelude::DateTimeUtc;
+use serde::Serialize;
+use serde_with::skip_serializing_none;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum FriendsError {
+    /// User with the requested ID doesn't exist
+    #[error("Unknown user")]
+    UnknownUser,
+    /// Users cannot add themselves as a friend
+    #[error("Cannot add yourself as a friend")]
+    AddSelf,
+    /// The user isn't a friend of the user
+    #[error("User is not a friend")]
+    NotFriend,
+}
+
+impl HttpError for FriendsError {
+    fn status(&self) -> StatusCode {
+        match self {
+            FriendsError::UnknownUser | FriendsError::NotFriend => StatusCode::NOT_FOUND,
+            FriendsError::AddSelf => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+/// Response listing the friends of the user
+#[derive(Debug, Serialize)]
+pub struct FriendsResponse {
+    pub list: Vec<FriendResponse>,
+}
+
+/// Friend of the user along with their presence, presence is
+/// only provided for mutual friends
+#[skip_serializing_none]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FriendResponse {
+    /// ID of the friend
+    pub id: UserId,
+    /// Username of the friend
+    pub username: String,
+    /// Whether the friend has added the user back
+    pub mutual: bool,
+    /// Whether the friend is currently online
+    pub online: bool,
+    /// ID of the game the friend is currently in
+    pub game_id: Option<GameID>,
+    /// When the friend was added
+    pub added: DateTimeUtc,
+}
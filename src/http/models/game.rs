@@ -0,0 +1,64 @@
+use super::HttpError;
+use crate::services::game_timeline::TimelineVisibility;
+use hyper::StatusCode;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum GameTimelineError {
+    /// The game doesn't exist or has already stopped
+    #[error("Unknown game")]
+    UnknownGame,
+    /// The host hasn't made the timeline public
+    #[error("Game timeline is not public")]
+    NotVisible,
+    /// Only the host can change the timeline visibility
+    #[error("Only the game host can change the timeline visibility")]
+    NotHost,
+}
+
+impl HttpError for GameTimelineError {
+    fn status(&self) -> StatusCode {
+        match self {
+            GameTimelineError::UnknownGame => StatusCode::NOT_FOUND,
+            GameTimelineError::NotVisible | GameTimelineError::NotHost => StatusCode::FORBIDDEN,
+        }
+    }
+}
+
+/// Query for filtering and paginating the server browser games list
+#[derive(Debug, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct GamesQuery {
+    /// Only list games with this difficulty
+    pub difficulty: Option<String>,
+    /// Only list games on this map
+    pub map: Option<String>,
+    /// Only list games against this enemy type
+    pub enemy: Option<String>,
+    /// Only list games that can or can't be joined
+    pub joinable: Option<bool>,
+    /// The number of games to skip
+    pub offset: usize,
+    /// The number of games to return
+    pub count: usize,
+}
+
+impl Default for GamesQuery {
+    fn default() -> Self {
+        Self {
+            difficulty: None,
+            map: None,
+            enemy: None,
+            joinable: None,
+            offset: 0,
+            count: 20,
+        }
+    }
+}
+
+/// Request to change who can spectate the game timeline
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TimelineVisibilityRequest {
+    pub visibility: TimelineVisibility,
+}
@@ -1,11 +1,18 @@
-use super::HttpError;
+use super::{HttpError, HttpErrorI18n};
 use crate::{
-    database::entity::{inventory_items::ItemId, InventoryItem},
-    definitions::items::{InventoryNamespace, ItemDefinition},
+    database::entity::{inventory_items::ItemId, Currency, InventoryItem},
+    definitions::{
+        characters::DuplicateCharacter,
+        items::{Category, InventoryNamespace, ItemDefinition, ItemName, ItemRarity},
+        packs::{GenerateError, PackCollectionPreview},
+    },
+    services::activity::ActivityResult,
 };
 use hyper::StatusCode;
+use sea_orm::prelude::DateTimeUtc;
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, skip_serializing_none};
+use std::collections::HashMap;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -25,17 +32,60 @@ pub enum InventoryError {
     /// Internal server error because item definition was missing
     #[error("Item missing definition")]
     MissingDefinition,
+
+    /// Tried to bulk open an item that isn't a pack
+    #[error("Item is not a pack")]
+    NotPack,
+
+    /// Bulk open count was zero or above the allowed maximum
+    #[error("Invalid number of packs to open")]
+    InvalidOpenCount,
+
+    /// Item requires a target but the target was missing or invalid
+    #[error("Invalid consume target")]
+    InvalidTarget,
+
+    /// Pack definition with the requested name doesn't exist
+    #[error("Unknown pack")]
+    UnknownPack,
+
+    /// Tried to consume a booster while an XP boost is waiting to be used
+    #[error("XP boost already active")]
+    XpBoostActive,
 }
 
 impl HttpError for InventoryError {
     fn status(&self) -> StatusCode {
         match self {
-            InventoryError::NotOwned => StatusCode::NOT_FOUND,
-            InventoryError::NotConsumable => StatusCode::BAD_REQUEST,
-            InventoryError::NotEnough => StatusCode::CONFLICT,
+            InventoryError::NotOwned | InventoryError::UnknownPack => StatusCode::NOT_FOUND,
+            InventoryError::NotConsumable
+            | InventoryError::NotPack
+            | InventoryError::InvalidOpenCount
+            | InventoryError::InvalidTarget => StatusCode::BAD_REQUEST,
+            InventoryError::NotEnough | InventoryError::XpBoostActive => StatusCode::CONFLICT,
             InventoryError::MissingDefinition => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
+
+    fn i18n(&self) -> Option<HttpErrorI18n> {
+        match self {
+            // "NOT AVAILABLE"
+            InventoryError::NotOwned | InventoryError::NotEnough => {
+                Some(HttpErrorI18n::new(179622))
+            }
+            // "UNAVAILABLE"
+            InventoryError::XpBoostActive => Some(HttpErrorI18n::new(65339)),
+            // "Error"
+            InventoryError::MissingDefinition => Some(HttpErrorI18n::new(12873)),
+            _ => None,
+        }
+    }
+}
+
+impl HttpError for GenerateError {
+    fn status(&self) -> StatusCode {
+        StatusCode::INTERNAL_SERVER_ERROR
+    }
 }
 
 /// Paramas for requesting inventory
@@ -48,6 +98,26 @@ pub struct InventoryRequestQuery {
     pub namespace: Option<InventoryNamespace>,
 }
 
+/// Query for searching the inventory, any parameters other than
+/// the known ones are matched against the item custom attributes
+#[serde_as]
+#[derive(Debug, Deserialize)]
+pub struct InventorySearchQuery {
+    /// Text the localized item name must contain
+    pub q: Option<String>,
+    /// Category the items must be within
+    #[serde_as(as = "Option<serde_with::DisplayFromStr>")]
+    #[serde(default)]
+    pub category: Option<Category>,
+    /// Rarity the items must have
+    #[serde_as(as = "Option<serde_with::DisplayFromStr>")]
+    #[serde(default)]
+    pub rarity: Option<ItemRarity>,
+    /// Custom attribute values the items must have
+    #[serde(flatten)]
+    pub attributes: HashMap<String, String>,
+}
+
 /// Response containing all the inventory items and their definitions
 #[skip_serializing_none]
 #[derive(Debug, Serialize)]
@@ -92,5 +162,211 @@ pub struct ConsumeTarget {
     /// ID of the item to consume
     #[serde_as(as = "serde_with::DisplayFromStr")]
     pub item_id: ItemId,
-    // pub target_id: String, *unused*
+    /// ID of the thing the item is used on, only used by items that
+    /// change strike teams where this is the strike team ID
+    #[serde(default)]
+    pub target_id: String,
+}
+
+/// Request to open multiple copies of an owned pack
+#[serde_as]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkOpenRequest {
+    /// ID of the pack item to open
+    #[serde_as(as = "serde_with::DisplayFromStr")]
+    pub item_id: ItemId,
+    /// Number of packs to open
+    pub count: u32,
+}
+
+/// Response after opening multiple packs
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkOpenResponse {
+    /// Combined result of opening all the packs
+    pub result: ActivityResult,
+    /// Rewards from each individual pack
+    pub packs: Vec<PackOpenBreakdown>,
+}
+
+/// Rewards from opening a single pack
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackOpenBreakdown {
+    /// Items rewarded from the pack
+    pub items: Vec<PackOpenItem>,
+    /// Currency given for items over capacity
+    pub currency_earned: Vec<Currency>,
+    /// Duplicate characters from the pack and what they were converted into
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub duplicate_characters: Vec<DuplicateCharacter>,
+}
+
+impl PackOpenBreakdown {
+    /// Creates the breakdown of the rewards in the `result` of opening a pack
+    pub fn new(result: &ActivityResult) -> Self {
+        Self {
+            items: result
+                .items_earned
+                .iter()
+                .map(|item| PackOpenItem {
+                    definition_name: item.definition_name,
+                    stack_size: item.stack_size,
+                })
+                .collect(),
+            currency_earned: result.currency_earned.clone(),
+            duplicate_characters: result.duplicate_characters.clone(),
+        }
+    }
+}
+
+/// Item rewarded from opening a pack
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackOpenItem {
+    /// Definition name of the item
+    pub definition_name: ItemName,
+    /// Amount of the item rewarded
+    pub stack_size: u32,
+}
+
+/// Query for previewing a pack
+#[serde_as]
+#[derive(Debug, Deserialize)]
+pub struct PackPreviewQuery {
+    /// Seed to generate the pack items with, provides the items the
+    /// pack opening with the same seed would have granted
+    #[serde_as(as = "Option<serde_with::DisplayFromStr>")]
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+/// Response previewing the items a pack can drop and their odds
+#[skip_serializing_none]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackPreviewResponse {
+    /// Name of the pack item
+    pub name: ItemName,
+    /// The items each collection of the pack can drop
+    pub collections: Vec<PackCollectionPreview<'static>>,
+    /// Items generated from the requested seed
+    pub rolled: Option<Vec<PackOpenItem>>,
+}
+
+/// Pack opened by the user
+#[serde_as]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackOpeningHistory {
+    /// Name of the opened pack
+    pub pack_name: ItemName,
+    /// Seed the items were generated with
+    #[serde_as(as = "serde_with::DisplayFromStr")]
+    pub seed: u64,
+    /// Number of items generated
+    pub items: u32,
+    /// When the pack was opened
+    pub created: DateTimeUtc,
+}
+
+/// Response containing the weapon mod compatibility for
+/// the users owned weapons
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModCompatibilityResponse {
+    pub total_count: usize,
+    pub list: Vec<WeaponModCompatibility>,
+}
+
+/// Owned weapon along with the owned mods that can be attached to it
+#[serde_as]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WeaponModCompatibility {
+    /// ID of the weapon item
+    #[serde_as(as = "serde_with::DisplayFromStr")]
+    pub item_id: ItemId,
+    /// Definition name of the weapon
+    pub definition_name: ItemName,
+    /// Owned mods that can be attached to the weapon
+    pub mods: Vec<CompatibleMod>,
+}
+
+/// Owned mod that can be attached to a weapon
+#[serde_as]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompatibleMod {
+    /// ID of the mod item
+    #[serde_as(as = "serde_with::DisplayFromStr")]
+    pub item_id: ItemId,
+    /// Definition name of the mod
+    pub definition_name: ItemName,
+}
+
+#[cfg(test)]
+mod test {
+    use super::PackOpenBreakdown;
+    use crate::{
+        database::entity::{
+            currency::CurrencyType,
+            users::{Platform, UserRole},
+            User,
+        },
+        definitions::characters::{DuplicateCharacter, DuplicateConversion},
+        services::activity::ActivityResult,
+    };
+    use uuid::Uuid;
+
+    /// Tests that the duplicate character conversions from each pack in a
+    /// bulk open are kept in both the pack breakdowns and combined result
+    #[test]
+    fn test_bulk_open_duplicate_characters() {
+        let user = User {
+            id: 1,
+            email: "test@example.com".to_string(),
+            username: "test".to_string(),
+            password: String::new(),
+            role: UserRole::Default,
+            platform: Platform::Origin,
+            title: None,
+            tokens_revoked_at: 0,
+        };
+
+        let conversions = [
+            DuplicateConversion::Xp { amount: 5000 },
+            DuplicateConversion::Currency {
+                name: CurrencyType::Grind,
+                amount: 250,
+            },
+        ];
+
+        let mut result = ActivityResult::default();
+        let mut packs = Vec::new();
+
+        for conversion in conversions.clone() {
+            let mut pack_result = ActivityResult::default();
+            pack_result.duplicate_characters.push(DuplicateCharacter {
+                class_name: Uuid::nil(),
+                conversion,
+            });
+
+            packs.push(PackOpenBreakdown::new(&pack_result));
+            result.merge(&user, pack_result);
+        }
+
+        for (pack, conversion) in packs.iter().zip(&conversions) {
+            assert_eq!(pack.duplicate_characters.len(), 1);
+            assert_eq!(&pack.duplicate_characters[0].conversion, conversion);
+        }
+
+        let merged: Vec<&DuplicateConversion> = result
+            .duplicate_characters
+            .iter()
+            .map(|value| &value.conversion)
+            .collect();
+        assert_eq!(merged, conversions.iter().collect::<Vec<_>>());
+    }
 }
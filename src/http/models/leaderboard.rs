@@ -1,9 +1,31 @@
-use crate::definitions::i18n::{I18nDescription, I18nName, Localized};
+use super::HttpError;
+use crate::{
+    database::entity::users::UserId,
+    definitions::i18n::{I18nDescription, I18nName, Localized},
+    services::leaderboard::LeaderboardStat,
+};
+use hyper::StatusCode;
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 use serde_with::skip_serializing_none;
+use thiserror::Error;
 use uuid::Uuid;
 
+#[derive(Debug, Error)]
+pub enum LeaderboardError {
+    /// Leaderboard with the requested name doesn't exist
+    #[error("Unknown leaderboard")]
+    UnknownLeaderboard,
+}
+
+impl HttpError for LeaderboardError {
+    fn status(&self) -> StatusCode {
+        match self {
+            LeaderboardError::UnknownLeaderboard => StatusCode::NOT_FOUND,
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct LeaderboardsResponse {
@@ -75,3 +97,47 @@ pub struct LeaderboardResponse {
     pub identifier: LeaderboardIdent,
     pub rows: Vec<LeaderboardRow>,
 }
+
+/// Query for paginating the leaderboard stats of all users
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct LeaderboardStatsQuery {
+    /// The stat to rank the users by
+    pub sort: LeaderboardStat,
+    /// The number of users to skip
+    pub offset: u64,
+    /// The number of users to return
+    pub count: u64,
+}
+
+impl Default for LeaderboardStatsQuery {
+    fn default() -> Self {
+        Self {
+            sort: LeaderboardStat::default(),
+            offset: 0,
+            count: 20,
+        }
+    }
+}
+
+/// Leaderboard stats of a single user
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LeaderboardStatsRow {
+    /// Rank of the user for the sorted stat
+    pub rank: u64,
+    pub user_id: UserId,
+    pub name: String,
+    pub n7_rating: u32,
+    pub apex_rating: f32,
+    pub challenge_points: u32,
+    pub match_count: u32,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LeaderboardStatsResponse {
+    /// Total number of users on the leaderboard
+    pub total_count: u64,
+    pub rows: Vec<LeaderboardStatsRow>,
+}
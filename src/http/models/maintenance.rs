@@ -0,0 +1,43 @@
+use super::HttpError;
+use hyper::StatusCode;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum MaintenanceError {
+    /// Server is in read-only mode for maintenance
+    #[error("Server is undergoing maintenance, try again later")]
+    ReadOnly,
+}
+
+impl HttpError for MaintenanceError {
+    fn log(&self) {
+        // Rejections are expected while in read-only mode
+        debug!("{self}");
+    }
+
+    fn status(&self) -> StatusCode {
+        match self {
+            MaintenanceError::ReadOnly => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+}
+
+/// Request to change the read-only mode
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetReadOnlyRequest {
+    /// Whether the read-only mode should be enabled
+    pub enabled: bool,
+}
+
+/// Current state of the read-only mode
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadOnlyResponse {
+    /// Whether the read-only mode is enabled
+    pub enabled: bool,
+    /// Seconds clients are told to wait before retrying rejected requests
+    pub retry_after_secs: u64,
+}
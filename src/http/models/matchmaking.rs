@@ -0,0 +1,49 @@
+use super::HttpError;
+use hyper::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum MatchmakingError {
+    /// Max latency is outside of the allowed range
+    #[error("Max latency must be between 1 and 5000 milliseconds")]
+    InvalidLatency,
+    /// Preferred region is empty or too long
+    #[error("Invalid preferred region")]
+    InvalidRegion,
+}
+
+impl HttpError for MatchmakingError {
+    fn status(&self) -> StatusCode {
+        match self {
+            MatchmakingError::InvalidLatency | MatchmakingError::InvalidRegion => {
+                StatusCode::BAD_REQUEST
+            }
+        }
+    }
+}
+
+/// Request to update the matchmaking preferences
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdatePreferencesRequest {
+    /// Maximum latency in milliseconds to the game host, [None]
+    /// allows any latency
+    pub max_latency: Option<u32>,
+    /// Ping site the game host should be closest to, [None]
+    /// allows any region
+    pub preferred_region: Option<String>,
+}
+
+/// Matchmaking preferences along with the latency the user
+/// currently has to each ping site
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreferencesResponse {
+    pub max_latency: Option<u32>,
+    pub preferred_region: Option<String>,
+    /// Latency to each ping site reported by the client, empty
+    /// when the user isn't connected to the game
+    pub ping_site_latency: HashMap<String, u32>,
+}
@@ -0,0 +1,27 @@
+use crate::services::game_stats::{GameSummary, MatchmakingSummary};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Query for the time period to summarize game statistics over
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct GameStatsQuery {
+    /// The number of days of statistics to include
+    pub days: u32,
+}
+
+impl Default for GameStatsQuery {
+    fn default() -> Self {
+        Self { days: 7 }
+    }
+}
+
+/// Summary of the matchmaking and game statistics
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GameStatsResponse {
+    /// Start of the period the statistics were summarized over
+    pub since: DateTime<Utc>,
+    pub matchmaking: MatchmakingSummary,
+    pub games: GameSummary,
+}
@@ -1,5 +1,8 @@
 use crate::{
-    database::entity::{characters::CharacterId, currency::CurrencyType, InventoryItem},
+    database::entity::{
+        characters::CharacterId, currency::CurrencyType, strike_team_mission::StrikeTeamMissionId,
+        InventoryItem,
+    },
     definitions::{badges::BadgeLevelName, challenges::CurrencyReward},
     services::activity::{ActivityEvent, ChallengeUpdated, PrestigeProgression},
     utils::models::Sku,
@@ -22,13 +25,46 @@ pub enum MissionError {
 
     #[error("Missing mission data")]
     MissingMissionData,
+
+    /// User isn't a player in the game
+    #[error("Not a player in the game")]
+    NotInGame,
+
+    /// APEX mission doesn't exist or is outside its availability window
+    #[error("Mission is not available")]
+    MissionUnavailable,
+
+    /// APEX mission can't be played in multiplayer
+    #[error("Mission is not accessible")]
+    MissionNotAccessible,
+
+    /// Mission was finished without being started
+    #[error("Mission has not been started")]
+    NotStarted,
+
+    /// Mission reported a longer duration than has passed since it started
+    #[error("Mission duration is invalid")]
+    InvalidDuration,
+
+    /// Mission results weren't processed in time, the client should retry
+    #[error("Mission results are still processing")]
+    StillProcessing,
+
+    /// Host started the mission before the other players were ready
+    #[error("Not all players are ready")]
+    PlayersNotReady,
 }
 
 impl HttpError for MissionError {
     fn status(&self) -> StatusCode {
         match self {
-            MissionError::UnknownGame => StatusCode::BAD_REQUEST,
+            MissionError::UnknownGame
+            | MissionError::MissionUnavailable
+            | MissionError::InvalidDuration => StatusCode::BAD_REQUEST,
+            MissionError::NotInGame | MissionError::MissionNotAccessible => StatusCode::FORBIDDEN,
+            MissionError::NotStarted | MissionError::PlayersNotReady => StatusCode::CONFLICT,
             MissionError::MissingMissionData => StatusCode::INTERNAL_SERVER_ERROR,
+            MissionError::StillProcessing => StatusCode::SERVICE_UNAVAILABLE,
         }
     }
 }
@@ -83,9 +119,14 @@ impl MissionActivityReport {
     }
 }
 
+#[serde_as]
 #[derive(Debug, Serialize, Deserialize)]
 pub struct StartMissionRequest {
     pub modifiers: Vec<MissionModifier>,
+    /// The APEX mission being started if the mission is one
+    #[serde(default)]
+    #[serde_as(as = "Option<serde_with::DisplayFromStr>")]
+    pub mission: Option<StrikeTeamMissionId>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
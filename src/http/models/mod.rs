@@ -3,18 +3,31 @@ use hyper::{header::CONTENT_TYPE, http::HeaderValue};
 use serde::Serialize;
 use std::fmt::Debug;
 
+pub mod admin;
+pub mod assets;
 pub mod auth;
 pub mod challenge;
 pub mod character;
 pub mod client;
+pub mod companion;
+pub mod crashes;
 pub mod errors;
+pub mod feature_flags;
+pub mod friends;
+pub mod game;
 pub mod inventory;
 pub mod leaderboard;
+pub mod maintenance;
+pub mod matchmaking;
+pub mod metrics;
 pub mod mission;
+pub mod notifications;
 pub mod qos;
+pub mod reports;
 pub mod store;
 pub mod strike_teams;
 pub mod telemetry;
+pub mod titles;
 pub mod user_match;
 
 pub use errors::*;
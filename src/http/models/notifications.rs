@@ -0,0 +1,34 @@
+use super::HttpError;
+use crate::database::entity::Notification;
+use hyper::StatusCode;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum NotificationsError {
+    /// The user doesn't have a notification with the requested ID
+    #[error("Unknown notification")]
+    UnknownNotification,
+}
+
+impl HttpError for NotificationsError {
+    fn status(&self) -> StatusCode {
+        match self {
+            NotificationsError::UnknownNotification => StatusCode::NOT_FOUND,
+        }
+    }
+}
+
+/// Query for listing notifications
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct NotificationsQuery {
+    /// Only list the notifications that haven't been read
+    pub unread: bool,
+}
+
+/// Response listing the notifications of the user, newest first
+#[derive(Debug, Serialize)]
+pub struct NotificationsResponse {
+    pub list: Vec<Notification>,
+}
@@ -0,0 +1,35 @@
+use crate::{database::entity::reports::ReportCategory, services::game::GameID};
+use serde::Deserialize;
+use validator::Validate;
+
+/// Request to submit a new report
+#[derive(Debug, Validate, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateReportRequest {
+    /// The category of the report
+    pub category: ReportCategory,
+    /// Description of the problem
+    #[validate(length(min = 1, max = 2000))]
+    pub description: String,
+    /// Optional ID of the game the report is about
+    pub game_id: Option<GameID>,
+}
+
+/// Query for paginating the list of reports
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct ReportsQuery {
+    /// The number of reports to skip
+    pub offset: u64,
+    /// The number of reports to return
+    pub count: u64,
+}
+
+impl Default for ReportsQuery {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            count: 20,
+        }
+    }
+}
@@ -1,6 +1,9 @@
-use super::HttpError;
+use super::{HttpError, HttpErrorI18n};
 use crate::{
-    database::entity::{currency::CurrencyType, Currency, InventoryItem},
+    database::entity::{
+        currency::CurrencyType, store_purchases::StorePurchaseId, Currency, CurrencyLedgerEntry,
+        InventoryItem, StorePurchase,
+    },
     definitions::{
         items::ItemDefinition,
         store_catalogs::{StoreArticleName, StoreCatalog},
@@ -10,6 +13,7 @@ use crate::{
 use hyper::StatusCode;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use serde_with::skip_serializing_none;
 use thiserror::Error;
 use uuid::Uuid;
 
@@ -18,12 +22,73 @@ pub enum StoreError {
     /// Couldn't find the article requested
     #[error("Unknown article")]
     UnknownArticle,
+    /// Couldn't find the purchase requested
+    #[error("Unknown purchase")]
+    UnknownPurchase,
+    /// Purchase has already been refunded
+    #[error("Purchase already refunded")]
+    AlreadyRefunded,
+    /// Refund window for the purchase has passed
+    #[error("Purchase can no longer be refunded")]
+    RefundWindowExpired,
+    /// Purchased item has been consumed or removed
+    #[error("Purchased item has been consumed")]
+    ItemConsumed,
+    /// Purchased item can't be refunded
+    #[error("Purchase is not refundable")]
+    NotRefundable,
+    /// Article is outside of its availability window
+    #[error("Article is not available")]
+    ArticleUnavailable,
+    /// User has purchased the maximum amount of the article
+    #[error("Article purchase limit reached")]
+    PurchaseLimitReached,
+    /// User already has the maximum amount of the article item
+    #[error("Item capacity reached")]
+    ItemCapacityReached,
+    /// Item given by the article doesn't exist
+    #[error("Unknown article item")]
+    UnknownArticleItem,
+    /// User hasn't unlocked the article item
+    #[error("Article item is locked")]
+    ItemLocked,
+    /// Article item couldn't be given to the user
+    #[error("Article item could not be granted")]
+    ItemNotGranted,
 }
 
 impl HttpError for StoreError {
     fn status(&self) -> StatusCode {
         match self {
-            StoreError::UnknownArticle => StatusCode::NOT_FOUND,
+            StoreError::UnknownArticle | StoreError::UnknownPurchase => StatusCode::NOT_FOUND,
+            StoreError::AlreadyRefunded
+            | StoreError::RefundWindowExpired
+            | StoreError::ItemConsumed
+            | StoreError::ArticleUnavailable
+            | StoreError::PurchaseLimitReached
+            | StoreError::ItemCapacityReached
+            | StoreError::ItemNotGranted => StatusCode::CONFLICT,
+            StoreError::NotRefundable => StatusCode::BAD_REQUEST,
+            StoreError::ItemLocked => StatusCode::FORBIDDEN,
+            StoreError::UnknownArticleItem => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn i18n(&self) -> Option<HttpErrorI18n> {
+        match self {
+            // "NOT AVAILABLE"
+            StoreError::ArticleUnavailable => Some(HttpErrorI18n::new(179622)),
+            // "Expired"
+            StoreError::RefundWindowExpired => Some(HttpErrorI18n::new(11020)),
+            // "Purchase Failed"
+            StoreError::PurchaseLimitReached
+            | StoreError::ItemCapacityReached
+            | StoreError::ItemNotGranted => Some(HttpErrorI18n::new(36716)),
+            // "LOCKED"
+            StoreError::ItemLocked => Some(HttpErrorI18n::new(19868)),
+            // "Error"
+            StoreError::UnknownArticleItem => Some(HttpErrorI18n::new(12873)),
+            _ => None,
         }
     }
 }
@@ -31,7 +96,7 @@ impl HttpError for StoreError {
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct StoreCatalogResponse {
-    pub list: Vec<&'static StoreCatalog>,
+    pub list: Vec<StoreCatalog>,
 }
 
 #[derive(Serialize)]
@@ -49,6 +114,8 @@ pub struct ObtainStoreItemRequest {
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ObtainStoreItemResponse {
+    /// ID of the purchase, used to refund the purchase
+    pub purchase_id: StorePurchaseId,
     pub generated_activity_result: ActivityResult,
     pub items: Vec<InventoryItem>,
     pub definitions: Vec<&'static ItemDefinition>,
@@ -66,3 +133,75 @@ pub struct ClaimUncalimedResponse {
 pub struct UpdateSeenArticles {
     pub article_names: Vec<Uuid>,
 }
+
+/// Request to convert between two currencies
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConvertCurrencyRequest {
+    /// The currency to convert from
+    pub from: CurrencyType,
+    /// The currency to convert into
+    pub to: CurrencyType,
+    /// Amount of the `from` currency to convert
+    pub amount: u32,
+}
+
+/// Response after converting currency
+#[skip_serializing_none]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConvertCurrencyResponse {
+    /// Amount of the `to` currency received
+    pub received: u32,
+    /// Remaining amount that can be converted today when
+    /// the conversion has a daily cap
+    pub remaining_daily: Option<u32>,
+    /// The updated currency balances
+    pub currencies: Vec<Currency>,
+}
+
+/// Query for paginating the currency transaction history
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct CurrencyHistoryQuery {
+    /// The number of entries to skip
+    pub offset: u64,
+    /// The number of entries to return
+    pub count: u64,
+}
+
+impl Default for CurrencyHistoryQuery {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            count: 20,
+        }
+    }
+}
+
+/// Currency balances along with a page of the transaction history
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurrencyHistoryResponse {
+    /// Balances of every currency including the server only currencies
+    pub balances: Vec<Currency>,
+    /// Transactions in the page, most recent first
+    pub list: Vec<CurrencyLedgerEntry>,
+}
+
+/// Purchases that are still within the refund window
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RefundablePurchasesResponse {
+    pub list: Vec<StorePurchase>,
+}
+
+/// Response after refunding a purchase
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RefundPurchaseResponse {
+    /// The refunded purchase
+    pub purchase: StorePurchase,
+    /// The updated currency balances
+    pub currencies: Vec<Currency>,
+}
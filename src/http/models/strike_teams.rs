@@ -1,12 +1,19 @@
-use super::HttpError;
+use super::{HttpError, HttpErrorI18n};
 use crate::{
+    config::StreakBonus,
     database::entity::{
         currency::CurrencyType, strike_team_mission::StrikeTeamMissionId,
         strike_team_mission_progress::UserMissionState, strike_teams::StrikeTeamId, Currency,
-        StrikeTeam, StrikeTeamMission,
+        StrikeTeam, StrikeTeamDeployment, StrikeTeamMission,
+    },
+    definitions::{
+        strike_team_flair::{StrikeTeamFlair, StrikeTeamFlairKind, StrikeTeamFlairName},
+        strike_teams::{StrikeTeamEquipmentName, StrikeTeamName, StrikeTeamTrait},
+    },
+    services::{
+        activity::ActivityResult, strike_team_deployment::StrikeTeamState,
+        strike_team_resolve::MissionRewardPreview, strike_team_retirement::RetireError,
     },
-    definitions::strike_teams::{StrikeTeamName, StrikeTeamTrait},
-    services::activity::ActivityResult,
 };
 use hyper::StatusCode;
 use sea_orm::prelude::DateTimeUtc;
@@ -27,20 +34,127 @@ pub enum StrikeTeamError {
     UnknownEquipmentItem,
     /// Cannot recruit any more teams
     #[error("Maximum number of strike teams reached")]
-    MaxTeams,
+    MaxTeams(usize),
+    /// Mission has already been completed
+    #[error("Strike team mission is not available")]
+    MissionUnavailable,
+    /// Mission isn't part of the current rotation yet
+    #[error("Strike team mission has not started")]
+    MissionNotStarted,
+    /// Mission is no longer part of the current rotation
+    #[error("Strike team mission has expired")]
+    MissionExpired,
+    /// Mission can only be played as an APEX mission
+    #[error("Strike team mission can only be played by APEX")]
+    MissionApexOnly,
+    /// Strike team hasn't reached the level required by the equipment
+    #[error("Strike team must be level {0} to use the equipment")]
+    EquipmentLevelRequired(u32),
+    /// Mission already has a strike team deployed
+    #[error("Strike team mission already deployed")]
+    MissionAlreadyDeployed,
+    /// Mission is already waiting in the deployment queue
+    #[error("Strike team mission already queued")]
+    MissionAlreadyQueued,
+    /// Strike team hasn't returned from a mission to resolve
+    #[error("Strike team has no mission to resolve")]
+    MissionNotReturned,
+    /// Flair definition doesn't exist
+    #[error("Unknown strike team flair")]
+    UnknownFlair,
+    /// Flair hasn't been unlocked by the user
+    #[error("Strike team flair not unlocked")]
+    FlairNotUnlocked,
+    /// Flair can't be equipped in the requested slot
+    #[error("Strike team flair is the wrong kind")]
+    FlairKindMismatch,
+    /// Strike team has no traits that the trait item can change
+    #[error("Strike team has no traits to change")]
+    NoTraitsToChange,
+    /// Specialization definition doesn't exist
+    #[error("Unknown strike team specialization")]
+    UnknownSpecialization,
+    /// Strike team already has a specialization
+    #[error("Strike team is already specialized")]
+    AlreadySpecialized,
+    /// Strike team hasn't reached the level required to specialize
+    #[error("Strike team must be level {0} to specialize")]
+    SpecializationLevel(u32),
 }
 
 impl HttpError for StrikeTeamError {
     fn status(&self) -> StatusCode {
         match self {
-            StrikeTeamError::MaxTeams | StrikeTeamError::TeamOnMission => StatusCode::CONFLICT,
+            StrikeTeamError::MaxTeams(_)
+            | StrikeTeamError::TeamOnMission
+            | StrikeTeamError::MissionAlreadyDeployed
+            | StrikeTeamError::MissionAlreadyQueued
+            | StrikeTeamError::MissionNotReturned
+            | StrikeTeamError::NoTraitsToChange
+            | StrikeTeamError::AlreadySpecialized => StatusCode::CONFLICT,
+            StrikeTeamError::MissionUnavailable
+            | StrikeTeamError::MissionNotStarted
+            | StrikeTeamError::MissionExpired
+            | StrikeTeamError::MissionApexOnly
+            | StrikeTeamError::FlairKindMismatch
+            | StrikeTeamError::SpecializationLevel(_) => StatusCode::BAD_REQUEST,
+            StrikeTeamError::FlairNotUnlocked | StrikeTeamError::EquipmentLevelRequired(_) => {
+                StatusCode::FORBIDDEN
+            }
             StrikeTeamError::UnknownTeam
+            | StrikeTeamError::UnknownFlair
             | StrikeTeamError::UnknownEquipmentItem
+            | StrikeTeamError::UnknownSpecialization
             | StrikeTeamError::UnknownMission => StatusCode::NOT_FOUND,
         }
     }
+
+    fn i18n(&self) -> Option<HttpErrorI18n> {
+        match self {
+            // "<font color=A52716>MAX NUMBER OF STRIKE TEAMS: {CUSTOM0}</font>"
+            StrikeTeamError::MaxTeams(max_teams) => Some(HttpErrorI18n::with_params(
+                107334,
+                vec![max_teams.to_string()],
+            )),
+            // "UNAVAILABLE"
+            StrikeTeamError::TeamOnMission
+            | StrikeTeamError::MissionUnavailable
+            | StrikeTeamError::MissionNotStarted
+            | StrikeTeamError::MissionApexOnly
+            | StrikeTeamError::MissionAlreadyDeployed
+            | StrikeTeamError::MissionAlreadyQueued => Some(HttpErrorI18n::new(65339)),
+            // "Expired"
+            StrikeTeamError::MissionExpired => Some(HttpErrorI18n::new(11020)),
+            // "LEVEL {CUSTOM0} REQUIREMENT"
+            StrikeTeamError::EquipmentLevelRequired(level)
+            | StrikeTeamError::SpecializationLevel(level) => {
+                Some(HttpErrorI18n::with_params(63333, vec![level.to_string()]))
+            }
+            // "LOCKED"
+            StrikeTeamError::FlairNotUnlocked => Some(HttpErrorI18n::new(19868)),
+            _ => None,
+        }
+    }
+}
+
+impl HttpError for RetireError {
+    fn status(&self) -> StatusCode {
+        match self {
+            RetireError::UnknownTeam => StatusCode::NOT_FOUND,
+            RetireError::TeamOnMission => StatusCode::CONFLICT,
+            RetireError::Database(_) | RetireError::Other(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn reason(&self) -> String {
+        match self {
+            RetireError::Database(_) | RetireError::Other(_) => "Server error".to_string(),
+            _ => self.to_string(),
+        }
+    }
 }
 
+#[skip_serializing_none]
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ResolveMissionResponse {
@@ -48,6 +162,8 @@ pub struct ResolveMissionResponse {
     pub mission_successful: bool,
     pub traits_acquired: Vec<StrikeTeamTrait>,
     pub activity_response: ActivityResult,
+    /// Success streak bonus applied to the mission
+    pub streak_bonus: Option<StreakBonus>,
 }
 
 #[skip_serializing_none]
@@ -58,11 +174,76 @@ pub struct PurchaseResponse {
     pub team: StrikeTeam,
     pub next_purchase_cost: Option<u32>,
 }
+
+/// Request to choose the specialization of a strike team
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpecializeRequest {
+    /// Name of the specialization to choose
+    pub name: String,
+}
+
+/// Request to equip flair onto a strike team
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EquipFlairRequest {
+    /// The kind of flair to equip
+    pub kind: StrikeTeamFlairKind,
+    /// Name of the flair to equip, [None] removes the equipped flair
+    pub name: Option<StrikeTeamFlairName>,
+}
+
+/// Strike team flair along with when the user unlocked it
+#[skip_serializing_none]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StrikeTeamFlairWithUnlock {
+    #[serde(flatten)]
+    pub flair: &'static StrikeTeamFlair,
+    /// When the flair was unlocked, [None] if its still locked
+    pub unlocked: Option<DateTimeUtc>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct PurchaseQuery {
     pub currency: CurrencyType,
 }
 
+/// Query for deploying a strike team onto a mission, optionally
+/// purchasing equipment to boost the team for the mission
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeployTeamQuery {
+    /// Name of the equipment to purchase for the team before deploying
+    pub equipment: Option<StrikeTeamEquipmentName>,
+    /// Currency to purchase the equipment with
+    #[serde(default = "default_equipment_currency")]
+    pub currency: CurrencyType,
+}
+
+fn default_equipment_currency() -> CurrencyType {
+    CurrencyType::Mission
+}
+
+/// Query for paginating the mission history of a strike team
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct MissionHistoryQuery {
+    /// The number of missions to skip
+    pub offset: u64,
+    /// The number of missions to return
+    pub count: u64,
+}
+
+impl Default for MissionHistoryQuery {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            count: 20,
+        }
+    }
+}
+
 #[skip_serializing_none]
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -80,6 +261,22 @@ pub struct StrikeTeamsList {
     pub total_count: usize,
     pub list: Vec<StrikeTeamWithMission>,
     pub cap: usize,
+    /// Details about each of the strike team slots
+    pub slots: Vec<StrikeTeamSlot>,
+}
+
+/// Details about a strike team slot
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StrikeTeamSlot {
+    /// Index of the slot
+    pub index: usize,
+    /// Whether the user owns a team in this slot
+    pub owned: bool,
+    /// Whether this slot is an expansion beyond the default slots
+    pub expansion: bool,
+    /// Mission currency cost to purchase this slot
+    pub cost: u32,
 }
 
 #[derive(Debug, Serialize)]
@@ -88,6 +285,32 @@ pub struct StrikeTeamWithMission {
     #[serde(flatten)]
     pub team: StrikeTeam,
     pub mission: Option<StrikeTeamActiveMission>,
+    /// Current deployment state of the team
+    pub state: StrikeTeamState,
+}
+
+/// Response to deploying a strike team onto a mission
+#[skip_serializing_none]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeployResponse {
+    /// Whether the mission was queued as all teams were busy
+    pub queued: bool,
+    /// The team that was deployed if the mission wasn't queued
+    pub team: Option<StrikeTeamWithMission>,
+    /// Position in the deployment queue if the mission was queued
+    pub queue_position: Option<usize>,
+}
+
+/// Consolidated details about the strike teams that have returned from
+/// missions, allows the client to resolve all the missions at once
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingResolutionsResponse {
+    /// Teams that have returned from a mission awaiting resolution
+    pub resolutions: Vec<StrikeTeamWithMission>,
+    /// Missions still waiting for a strike team
+    pub queue: Vec<StrikeTeamDeployment>,
 }
 
 #[serde_as]
@@ -122,6 +345,9 @@ pub struct StrikeTeamMissionWithState {
     pub user_mission_state: UserMissionState,
     pub seen: bool,
     pub completed: bool,
+    /// Rewards a strike team can earn from the mission
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reward_preview: Option<MissionRewardPreview>,
 }
 
 #[serde_as]
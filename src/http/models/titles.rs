@@ -0,0 +1,75 @@
+use super::HttpError;
+use crate::{
+    database::entity::users::UserId,
+    definitions::titles::{PlayerTitle, PlayerTitleName},
+};
+use hyper::StatusCode;
+use sea_orm::prelude::DateTimeUtc;
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum TitleError {
+    /// Title definition with the requested name doesn't exist
+    #[error("Unknown title")]
+    UnknownTitle,
+    /// The user hasn't earned the title
+    #[error("Title has not been earned")]
+    TitleNotUnlocked,
+    /// User with the requested ID doesn't exist
+    #[error("Unknown user")]
+    UnknownUser,
+}
+
+impl HttpError for TitleError {
+    fn status(&self) -> StatusCode {
+        match self {
+            TitleError::UnknownTitle | TitleError::UnknownUser => StatusCode::NOT_FOUND,
+            TitleError::TitleNotUnlocked => StatusCode::FORBIDDEN,
+        }
+    }
+}
+
+/// Response listing the titles along with the equipped title
+#[skip_serializing_none]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TitlesResponse {
+    /// Name of the equipped title
+    pub active: Option<PlayerTitleName>,
+    /// All the titles along with which have been earned
+    pub list: Vec<PlayerTitleWithUnlock>,
+}
+
+/// Player title along with when the user earned it
+#[skip_serializing_none]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerTitleWithUnlock {
+    #[serde(flatten)]
+    pub title: &'static PlayerTitle,
+    /// When the title was earned, [None] if its still locked
+    pub unlocked: Option<DateTimeUtc>,
+}
+
+/// Request to equip a title
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EquipTitleRequest {
+    /// Name of the title to equip
+    pub name: PlayerTitleName,
+}
+
+/// Public profile of a user
+#[skip_serializing_none]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PublicProfileResponse {
+    /// ID of the user
+    pub id: UserId,
+    /// Username of the user
+    pub username: String,
+    /// The title the user has equipped
+    pub title: Option<&'static PlayerTitle>,
+}
@@ -1,30 +1,83 @@
 use crate::{
+    database::entity::User,
     http::{
-        middleware::{user::Auth, JsonDump},
-        models::RawJson,
+        middleware::{signed_json::SignedJson, user::Auth},
+        models::{mission::MissionActivityReport, RawJson},
+    },
+    services::{
+        activity::ActivityResult, activity_buffer::ActivityBuffer, game::GameID,
+        game_manager::GameManager, game_timeline::GameTimelineEvent, sessions::Sessions,
     },
-    services::activity::ActivityResult,
 };
-use axum::Json;
+use axum::{Extension, Json};
 use log::debug;
 use serde_json::Value;
+use std::sync::Arc;
 
 /// POST /activity
 ///
 /// This endpoint recieves requests whenever in game activities
 /// from the activity metadata definitions are completed. The request
 /// contains details about the activity
+///
+/// The challenge progress from the activities is buffered and written
+/// in batches so the response doesn't include the challenge updates
 pub async fn create_report(
     Auth(user): Auth,
-    JsonDump(req): JsonDump<Value>,
+    Extension(sessions): Extension<Arc<Sessions>>,
+    Extension(game_manager): Extension<Arc<GameManager>>,
+    Extension(activity_buffer): Extension<Arc<ActivityBuffer>>,
+    SignedJson(req): SignedJson<Value>,
 ) -> Json<ActivityResult> {
     debug!("Activity reported: {} {}", user.username, req);
 
-    // TODO: actually handle activities
+    // Reports that aren't in the expected structure aren't processed
+    let report: MissionActivityReport = match serde_json::from_value(req) {
+        Ok(value) => value,
+        Err(_) => return Json(ActivityResult::default()),
+    };
+
+    let game_id = sessions
+        .lookup_session(user.id)
+        .and_then(|session| session.data.lock().game_id());
+
+    activity_buffer
+        .push(user.id, game_id, &report.activities)
+        .await;
+
+    // Activities are only published when the player is in a game
+    if let Some(game_id) = game_id {
+        publish_timeline_activities(&user, game_id, &game_manager, report).await;
+    }
 
     Json(ActivityResult::default())
 }
 
+/// Publishes the reported activities to the timeline of the
+/// game the `user` is currently playing
+async fn publish_timeline_activities(
+    user: &User,
+    game_id: GameID,
+    game_manager: &GameManager,
+    report: MissionActivityReport,
+) {
+    let game = match game_manager.get_game(game_id).await {
+        Some(value) => value,
+        None => return,
+    };
+
+    let game = &*game.read().await;
+    let player = match game.players.iter().find(|player| player.user.id == user.id) {
+        Some(value) => value,
+        None => return,
+    };
+
+    game.timeline.push(GameTimelineEvent::ActivitySubmitted {
+        player: player.timeline_player(),
+        activities: report.activities,
+    });
+}
+
 /// Definition of different activities that can happen within a game.
 static ACTIVITY_METADATA_DEFINITION: &str =
     include_str!("../../resources/data/activityMetadata.json");
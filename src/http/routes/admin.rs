@@ -0,0 +1,614 @@
+//! Routes for administrators managing user accounts, allows fixing
+//! player accounts without editing the database by hand
+
+use crate::{
+    blaze::models::game_manager::RemoveReason,
+    database::entity::{
+        characters,
+        currency_ledger::CurrencyLedgerReason,
+        notifications::NotificationKind,
+        user_permissions::AdminPermission,
+        users::{UserId, UserRole},
+        ChallengeProgress, Character, Currency, CurrencyLedgerEntry, InventoryItem,
+        PermissionAuditEntry, User, UserBan, UserPermission,
+    },
+    definitions::items::Items,
+    http::{
+        middleware::{
+            json_validated::JsonValidated,
+            user::{AdminAuth, EconomyAuth, ModeratorAuth},
+        },
+        models::{
+            admin::{
+                AdminError, AdminUser, BanUserRequest, GrantCurrencyRequest, GrantItemRequest,
+                ImportQuery, ImportStartedResponse, PermissionAuditQuery, ResetChallengesResponse,
+                SendNotificationRequest, TransferFormatQuery, UsersQuery,
+            },
+            DynHttpError, HttpResult, VecWithCount,
+        },
+    },
+    services::{
+        inventory_transfer::{
+            self, ImportJob, ImportJobId, InventoryImports, InventoryRow, TransferFormat,
+        },
+        items, notifications,
+        sessions::Sessions,
+    },
+    utils::clock,
+};
+use axum::{
+    body::Bytes,
+    extract::{Path, Query},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use chrono::Duration;
+use hyper::{header, http::HeaderValue, StatusCode};
+use log::{error, info};
+use sea_orm::{DatabaseConnection, ModelTrait, TransactionTrait};
+use std::sync::Arc;
+
+/// The maximum number of users that can be requested at once
+const MAX_USERS_COUNT: u64 = 100;
+/// The maximum number of audit log entries that can be requested at once
+const MAX_AUDIT_COUNT: u64 = 100;
+
+/// Finds the user with the provided `user_id`
+async fn find_user(db: &DatabaseConnection, user_id: UserId) -> Result<User, DynHttpError> {
+    let user = User::by_id(db, user_id)
+        .await?
+        .ok_or(AdminError::UnknownUser)?;
+    Ok(user)
+}
+
+/// GET /api/server/admin/users
+///
+/// Lists the registered users ordered by ID
+pub async fn get_users(
+    AdminAuth(_): AdminAuth,
+    Query(query): Query<UsersQuery>,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<VecWithCount<AdminUser>> {
+    let count = query.count.min(MAX_USERS_COUNT);
+    let users = User::page(&db, query.offset, count)
+        .await?
+        .into_iter()
+        .map(AdminUser::from)
+        .collect();
+
+    Ok(Json(VecWithCount::new(users)))
+}
+
+/// GET /api/server/admin/users/:id
+pub async fn get_user(
+    AdminAuth(_): AdminAuth,
+    Path(user_id): Path<UserId>,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<AdminUser> {
+    let user = find_user(&db, user_id).await?;
+
+    Ok(Json(user.into()))
+}
+
+/// GET /api/server/admin/users/:id/inventory
+pub async fn get_inventory(
+    AdminAuth(_): AdminAuth,
+    Path(user_id): Path<UserId>,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<VecWithCount<InventoryItem>> {
+    let user = find_user(&db, user_id).await?;
+    let items = InventoryItem::get_all_items(&db, &user).await?;
+
+    Ok(Json(VecWithCount::new(items)))
+}
+
+/// GET /api/server/admin/users/:id/inventory/deleted
+///
+/// Lists the items deleted from the inventory of a user that
+/// haven't been purged yet
+pub async fn get_deleted_inventory(
+    AdminAuth(_): AdminAuth,
+    Path(user_id): Path<UserId>,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<VecWithCount<InventoryItem>> {
+    let user = find_user(&db, user_id).await?;
+    let items = InventoryItem::get_deleted_items(&db, &user).await?;
+
+    Ok(Json(VecWithCount::new(items)))
+}
+
+/// GET /api/server/admin/users/:id/characters
+pub async fn get_characters(
+    AdminAuth(_): AdminAuth,
+    Path(user_id): Path<UserId>,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<VecWithCount<Character>> {
+    let user = find_user(&db, user_id).await?;
+    let characters = user.find_related(characters::Entity).all(&db).await?;
+
+    Ok(Json(VecWithCount::new(characters)))
+}
+
+/// GET /api/server/admin/users/:id/currencies
+pub async fn get_currencies(
+    AdminAuth(_): AdminAuth,
+    Path(user_id): Path<UserId>,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<VecWithCount<Currency>> {
+    let user = find_user(&db, user_id).await?;
+    let currencies = Currency::all(&db, &user).await?;
+
+    Ok(Json(VecWithCount::new(currencies)))
+}
+
+/// POST /api/server/admin/users/:id/inventory
+///
+/// Grants an item to the user through the shared item grant pipeline,
+/// character items also create the character if the user doesn't
+/// already have it
+pub async fn grant_item(
+    EconomyAuth(admin): EconomyAuth,
+    Path(user_id): Path<UserId>,
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(sessions): Extension<Arc<Sessions>>,
+    JsonValidated(req): JsonValidated<GrantItemRequest>,
+) -> HttpResult<InventoryItem> {
+    let user = find_user(&db, user_id).await?;
+    let definition = Items::get()
+        .by_name(&req.item_name)
+        .ok_or(AdminError::UnknownItem)?;
+
+    let item = db
+        .transaction(|db| {
+            Box::pin(async move {
+                let granted = items::grant_item(db, &user, definition, req.count).await?;
+
+                // Grants that don't fit are rejected rather than partially applied
+                match granted.item {
+                    Some(item) if granted.overflow == 0 => Ok(item),
+                    _ => Err(DynHttpError::from(AdminError::ItemCapacityReached)),
+                }
+            })
+        })
+        .await?;
+
+    info!(
+        "Admin {} granted {} of item {} to user {}",
+        admin.id, req.count, definition.name, user_id
+    );
+
+    // The change is already stored so a failed notification is only logged
+    if let Err(err) = notifications::push(
+        &db,
+        &sessions,
+        user_id,
+        NotificationKind::GiftReceived,
+        format!("You received {} new item(s)", req.count),
+    )
+    .await
+    {
+        error!("Failed to notify granted items (UID: {}): {}", user_id, err);
+    }
+
+    Ok(Json(item))
+}
+
+/// POST /api/server/admin/users/:id/currencies
+///
+/// Grants currency to the user, the grant is recorded in the
+/// currency ledger
+pub async fn grant_currency(
+    EconomyAuth(admin): EconomyAuth,
+    Path(user_id): Path<UserId>,
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(sessions): Extension<Arc<Sessions>>,
+    JsonValidated(req): JsonValidated<GrantCurrencyRequest>,
+) -> HttpResult<VecWithCount<Currency>> {
+    let user = find_user(&db, user_id).await?;
+
+    let currencies = db
+        .transaction(|db| {
+            Box::pin(async move {
+                Currency::add(db, &user, req.currency, req.amount).await?;
+                CurrencyLedgerEntry::create(
+                    db,
+                    &user,
+                    req.currency,
+                    req.amount as i64,
+                    CurrencyLedgerReason::AdminGrant,
+                )
+                .await?;
+
+                Currency::all(db, &user).await
+            })
+        })
+        .await?;
+
+    info!(
+        "Admin {} granted {} {} to user {}",
+        admin.id, req.amount, req.currency, user_id
+    );
+
+    if let Err(err) = notifications::push(
+        &db,
+        &sessions,
+        user_id,
+        NotificationKind::GiftReceived,
+        format!("You received {} {}", req.amount, req.currency),
+    )
+    .await
+    {
+        error!(
+            "Failed to notify granted currency (UID: {}): {}",
+            user_id, err
+        );
+    }
+
+    Ok(Json(VecWithCount::new(currencies)))
+}
+
+/// GET /api/server/admin/users/:id/inventory/export
+///
+/// Exports the items and currencies of the user as JSON or CSV
+pub async fn export_user_inventory(
+    EconomyAuth(_): EconomyAuth,
+    Path(user_id): Path<UserId>,
+    Query(query): Query<TransferFormatQuery>,
+    Extension(db): Extension<DatabaseConnection>,
+) -> Result<Response, DynHttpError> {
+    let user = find_user(&db, user_id).await?;
+    let rows = inventory_transfer::export_user(&db, &user).await?;
+    export_response(&rows, query.format)
+}
+
+/// GET /api/server/admin/inventory/export
+///
+/// Exports the items and currencies of every user as JSON or CSV
+pub async fn export_inventories(
+    EconomyAuth(admin): EconomyAuth,
+    Query(query): Query<TransferFormatQuery>,
+    Extension(db): Extension<DatabaseConnection>,
+) -> Result<Response, DynHttpError> {
+    let rows = inventory_transfer::export_all(&db).await?;
+
+    info!(
+        "Admin {} exported the inventories of every user ({} rows)",
+        admin.id,
+        rows.len()
+    );
+
+    export_response(&rows, query.format)
+}
+
+/// Creates the response for an export of `rows` in the `format`
+fn export_response(
+    rows: &[InventoryRow],
+    format: TransferFormat,
+) -> Result<Response, DynHttpError> {
+    let bytes = inventory_transfer::encode(rows, format)?;
+    let content_type = match format {
+        TransferFormat::Json => "application/json",
+        TransferFormat::Csv => "text/csv",
+    };
+
+    let mut resp = bytes.into_response();
+    resp.headers_mut()
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    Ok(resp)
+}
+
+/// POST /api/server/admin/inventory/import
+///
+/// Starts importing the items and currencies of users from a JSON or
+/// CSV file, the rows either set the balances of the users or adjust
+/// them depending on the import mode. Rows are applied in the background
+pub async fn import_inventories(
+    EconomyAuth(admin): EconomyAuth,
+    Query(query): Query<ImportQuery>,
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(imports): Extension<Arc<InventoryImports>>,
+    body: Bytes,
+) -> Result<(StatusCode, Json<ImportStartedResponse>), DynHttpError> {
+    let rows = inventory_transfer::decode(&body, query.format)?;
+    let total = rows.len();
+    let id = imports.start(db, rows, query.mode);
+
+    info!(
+        "Admin {} started inventory import {} ({} rows)",
+        admin.id, id, total
+    );
+
+    Ok((StatusCode::ACCEPTED, Json(ImportStartedResponse { id })))
+}
+
+/// GET /api/server/admin/inventory/import/:id
+///
+/// Gets the progress of an inventory import along with
+/// the errors for the rows that couldn't be imported
+pub async fn get_import(
+    EconomyAuth(_): EconomyAuth,
+    Path(id): Path<ImportJobId>,
+    Extension(imports): Extension<Arc<InventoryImports>>,
+) -> HttpResult<ImportJob> {
+    let job = imports.get(id).ok_or(AdminError::UnknownImport)?;
+    Ok(Json(job))
+}
+
+/// DELETE /api/server/admin/users/:id/challenges
+///
+/// Removes all the challenge progress for the user
+pub async fn reset_challenges(
+    EconomyAuth(admin): EconomyAuth,
+    Path(user_id): Path<UserId>,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<ResetChallengesResponse> {
+    let user = find_user(&db, user_id).await?;
+    let reset_challenges = ChallengeProgress::delete_all(&db, &user).await?;
+
+    info!(
+        "Admin {} reset {} challenge(s) for user {}",
+        admin.id, reset_challenges, user_id
+    );
+
+    Ok(Json(ResetChallengesResponse { reset_challenges }))
+}
+
+/// DELETE /api/server/admin/users/:id
+///
+/// Deletes the account of the user along with all of its data,
+/// administrators can only delete users with a lower role
+pub async fn delete_user(
+    ModeratorAuth(admin): ModeratorAuth,
+    Path(user_id): Path<UserId>,
+    Extension(db): Extension<DatabaseConnection>,
+) -> Result<StatusCode, DynHttpError> {
+    if admin.id == user_id {
+        return Err(AdminError::DeleteSelf.into());
+    }
+
+    let user = find_user(&db, user_id).await?;
+
+    if user.role >= admin.role {
+        return Err(AdminError::InsufficientRole.into());
+    }
+
+    user.delete(&db).await?;
+
+    info!("Admin {} deleted user {}", admin.id, user_id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /api/server/admin/bans
+///
+/// Lists the bans that haven't expired, newest first
+pub async fn get_bans(
+    ModeratorAuth(_): ModeratorAuth,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<Vec<UserBan>> {
+    let bans = UserBan::all_active(&db).await?;
+    Ok(Json(bans))
+}
+
+/// PUT /api/server/admin/users/:id/ban
+///
+/// Bans the user from the server, the user is removed from their
+/// current game and their existing tokens are revoked. Administrators
+/// can only ban users with a lower role
+pub async fn ban_user(
+    ModeratorAuth(admin): ModeratorAuth,
+    Path(user_id): Path<UserId>,
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(sessions): Extension<Arc<Sessions>>,
+    JsonValidated(req): JsonValidated<BanUserRequest>,
+) -> Result<StatusCode, DynHttpError> {
+    if admin.id == user_id {
+        return Err(AdminError::BanSelf.into());
+    }
+
+    let user = find_user(&db, user_id).await?;
+
+    if user.role >= admin.role {
+        return Err(AdminError::InsufficientRole.into());
+    }
+
+    let expires = req
+        .duration_hours
+        .map(|hours| clock::now() + Duration::hours(hours as i64));
+
+    let message = match &req.reason {
+        Some(reason) => format!("Your account has been banned: {}", reason),
+        None => "Your account has been banned".to_string(),
+    };
+
+    UserBan::create(&db, &user, &admin, req.reason, expires).await?;
+    if let Err(err) = notifications::push(
+        &db,
+        &sessions,
+        user_id,
+        NotificationKind::ModerationNotice,
+        message,
+    )
+    .await
+    {
+        error!("Failed to notify banned user (UID: {}): {}", user_id, err);
+    }
+
+    User::revoke_tokens(&db, user_id).await?;
+    if let Some(session) = sessions.lookup_session(user_id) {
+        session.remove_from_game(RemoveReason::PlayerKicked);
+    }
+
+    info!("Admin {} banned user {}", admin.id, user_id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// DELETE /api/server/admin/users/:id/ban
+///
+/// Lifts the ban on the user
+pub async fn unban_user(
+    ModeratorAuth(admin): ModeratorAuth,
+    Path(user_id): Path<UserId>,
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(sessions): Extension<Arc<Sessions>>,
+) -> Result<StatusCode, DynHttpError> {
+    let user = find_user(&db, user_id).await?;
+
+    if !UserBan::remove(&db, &user).await? {
+        return Err(AdminError::NotBanned.into());
+    }
+
+    if let Err(err) = notifications::push(
+        &db,
+        &sessions,
+        user_id,
+        NotificationKind::ModerationNotice,
+        "Your account ban has been lifted".to_string(),
+    )
+    .await
+    {
+        error!("Failed to notify unbanned user (UID: {}): {}", user_id, err);
+    }
+
+    info!("Admin {} unbanned user {}", admin.id, user_id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /api/server/admin/users/:id/notifications
+///
+/// Sends a message to the user, the message is stored in their
+/// notification inbox until they read it
+pub async fn send_notification(
+    ModeratorAuth(admin): ModeratorAuth,
+    Path(user_id): Path<UserId>,
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(sessions): Extension<Arc<Sessions>>,
+    JsonValidated(req): JsonValidated<SendNotificationRequest>,
+) -> Result<StatusCode, DynHttpError> {
+    // Ensure the user exists
+    _ = find_user(&db, user_id).await?;
+
+    notifications::push(
+        &db,
+        &sessions,
+        user_id,
+        NotificationKind::AdminMessage,
+        req.message,
+    )
+    .await?;
+
+    info!("Admin {} sent a notification to user {}", admin.id, user_id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /api/server/admin/users/:id/permissions
+///
+/// Lists the permissions granted to the user
+pub async fn get_permissions(
+    AdminAuth(_): AdminAuth,
+    Path(user_id): Path<UserId>,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<Vec<AdminPermission>> {
+    let user = find_user(&db, user_id).await?;
+    let permissions = UserPermission::all(&db, &user).await?;
+
+    Ok(Json(permissions))
+}
+
+/// Finds the administrator the `admin` is changing the permissions of,
+/// only super administrators can change permissions
+async fn find_permission_target(
+    db: &DatabaseConnection,
+    admin: &User,
+    user_id: UserId,
+) -> Result<User, DynHttpError> {
+    if admin.role != UserRole::SuperAdmin {
+        return Err(AdminError::InsufficientRole.into());
+    }
+
+    let user = find_user(db, user_id).await?;
+
+    if !user.role.is_admin() {
+        return Err(AdminError::NotAdmin.into());
+    }
+
+    Ok(user)
+}
+
+/// PUT /api/server/admin/users/:id/permissions/:permission
+///
+/// Grants the permission to the administrator, the change is
+/// recorded in the permission audit log
+pub async fn grant_permission(
+    AdminAuth(admin): AdminAuth,
+    Path((user_id, permission)): Path<(UserId, AdminPermission)>,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<Vec<AdminPermission>> {
+    let user = find_permission_target(&db, &admin, user_id).await?;
+    let admin_id = admin.id;
+
+    let permissions = db
+        .transaction(|db| {
+            Box::pin(async move {
+                UserPermission::grant(db, &user, permission).await?;
+                PermissionAuditEntry::create(db, admin_id, user.id, permission, true).await?;
+                UserPermission::all(db, &user).await
+            })
+        })
+        .await?;
+
+    info!(
+        "Admin {} granted permission {:?} to user {}",
+        admin_id, permission, user_id
+    );
+
+    Ok(Json(permissions))
+}
+
+/// DELETE /api/server/admin/users/:id/permissions/:permission
+///
+/// Revokes the permission from the administrator, the change is
+/// recorded in the permission audit log
+pub async fn revoke_permission(
+    AdminAuth(admin): AdminAuth,
+    Path((user_id, permission)): Path<(UserId, AdminPermission)>,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<Vec<AdminPermission>> {
+    let user = find_permission_target(&db, &admin, user_id).await?;
+    let admin_id = admin.id;
+
+    let permissions = db
+        .transaction(|db| {
+            Box::pin(async move {
+                if UserPermission::revoke(db, &user, permission).await? {
+                    PermissionAuditEntry::create(db, admin_id, user.id, permission, false).await?;
+                }
+                UserPermission::all(db, &user).await
+            })
+        })
+        .await?;
+
+    info!(
+        "Admin {} revoked permission {:?} from user {}",
+        admin_id, permission, user_id
+    );
+
+    Ok(Json(permissions))
+}
+
+/// GET /api/server/admin/permissions/audit
+///
+/// Lists the changes made to administrator permissions, newest first
+pub async fn get_permission_audit(
+    AdminAuth(_): AdminAuth,
+    Query(query): Query<PermissionAuditQuery>,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<VecWithCount<PermissionAuditEntry>> {
+    let count = query.count.min(MAX_AUDIT_COUNT);
+    let entries = PermissionAuditEntry::page(&db, query.offset, count).await?;
+
+    Ok(Json(VecWithCount::new(entries)))
+}
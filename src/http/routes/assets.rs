@@ -0,0 +1,57 @@
+use crate::{
+    config::Config,
+    definitions::{badges::Badges, challenges::Challenges},
+    http::models::{assets::AssetError, DynHttpError},
+};
+use axum::{
+    extract::Path,
+    response::{IntoResponse, Response},
+};
+use hyper::{header, http::HeaderValue};
+use log::warn;
+use std::{io::ErrorKind, path::PathBuf};
+
+/// Icon served when the icon image is missing from the asset directory
+static PLACEHOLDER_ICON: &[u8] = include_bytes!("../../resources/assets/placeholderIcon.png");
+
+/// GET /api/public/icons/:name
+///
+/// Obtains the icon image for a badge level `imgPath` or challenge
+/// `textureId`, icons are loaded from the configured asset directory
+/// falling back to an embedded placeholder when not present
+pub async fn get_icon(Path(name): Path<String>) -> Result<Response, DynHttpError> {
+    // Only serve icons referenced by the definitions, this also prevents
+    // paths from escaping the asset directory
+    let known = Badges::get().has_icon(&name)
+        || Challenges::get()
+            .values
+            .iter()
+            .any(|challenge| challenge.icon_name() == Some(name.as_str()));
+    if !known || name.contains(['/', '\\', '.']) {
+        return Err(AssetError::UnknownIcon.into());
+    }
+
+    let config = &Config::get().assets;
+    let path = PathBuf::from(&config.directory).join(format!("{name}.png"));
+
+    let bytes = match tokio::fs::read(&path).await {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            if err.kind() != ErrorKind::NotFound {
+                warn!("Failed to read icon asset {}: {}", path.display(), err);
+            }
+            PLACEHOLDER_ICON.to_vec()
+        }
+    };
+
+    let mut resp = bytes.into_response();
+    let headers = resp.headers_mut();
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("image/png"));
+    if let Ok(value) =
+        HeaderValue::from_str(&format!("public, max-age={}", config.cache_max_age_seconds))
+    {
+        headers.insert(header::CACHE_CONTROL, value);
+    }
+
+    Ok(resp)
+}
@@ -1,31 +1,58 @@
 use std::sync::Arc;
 
 use crate::{
+    config::Config,
+    database::entity::users::Platform,
     http::{
         middleware::{user::Auth, JsonDump},
         models::{
-            auth::{AuthRequest, AuthResponse, AuthUser},
+            auth::{AuthRequest, AuthResponse, AuthUser, RefreshTokenResponse},
             HttpResult,
         },
     },
     services::sessions::Sessions,
+    utils::{clock, models::Sku},
 };
 use axum::{Extension, Json};
-use chrono::Utc;
-use log::debug;
+use log::{debug, warn};
+use sea_orm::DatabaseConnection;
 
 /// POST /auth
+///
+/// Authenticates the client, the platform from the client SKU is
+/// stored on the user for use when matchmaking
 pub async fn authenticate(
-    Auth(user): Auth,
+    Auth(mut user): Auth,
     Extension(sessions): Extension<Arc<Sessions>>,
+    Extension(db): Extension<DatabaseConnection>,
     JsonDump(req): JsonDump<AuthRequest>,
 ) -> HttpResult<AuthResponse> {
     debug!("Authenticate: {:?}", &req);
 
+    let platform = Platform::from_sku(&req.sku.platform).unwrap_or_else(|| {
+        let default_platform = Config::get().platforms.default_platform;
+        warn!(
+            "Unknown client platform \"{}\" for user {}, using {:?}",
+            req.sku.platform, user.id, default_platform
+        );
+        default_platform
+    });
+
+    if user.platform != platform {
+        user = user.set_platform(&db, platform).await?;
+
+        // Sessions that are already connected need the updated platform for matchmaking
+        if let Some(session) = sessions.lookup_session(user.id) {
+            session.set_user(user.clone());
+        }
+    }
+
     let token = sessions.create_token(user.id);
+    let signing_key = sessions.request_signing_key(&token);
 
     Ok(Json(AuthResponse {
         session_id: token,
+        signing_key,
         user: AuthUser {
             roles: &[
                 "GameSettings.Anonymous",
@@ -61,12 +88,28 @@ pub async fn authenticate(
             ],
             pid: user.id,
             persona_id: user.id,
-            sku: req.sku,
+            sku: Sku::new(platform),
             anonymous: false,
             name: user.username,
         },
         language: "en-us".to_string(),
-        server_time: Utc::now(),
+        server_time: clock::now(),
         pid: user.id.to_string(),
     }))
 }
+
+/// POST /auth/refresh
+///
+/// Provides a new token for the authenticated user, used by clients
+/// to extend their login before the current token expires. Banned
+/// users are rejected by the [Auth] extractor so they can't extend
+/// a token issued before their ban
+pub async fn refresh_token(
+    Auth(user): Auth,
+    Extension(sessions): Extension<Arc<Sessions>>,
+) -> Json<RefreshTokenResponse> {
+    let token = sessions.create_token(user.id);
+    let signing_key = sessions.request_signing_key(&token);
+
+    Json(RefreshTokenResponse { token, signing_key })
+}
@@ -1,29 +1,41 @@
-use std::mem::swap;
+use std::{mem::swap, sync::Arc};
 
 use crate::{
     database::entity::{
+        character_customization_history::CustomizationHistoryId,
         characters::{self, CharacterId},
-        Character, SeaJson, SharedData,
+        Character, CharacterCustomizationHistory, SeaJson, SharedData,
     },
     definitions::{
-        classes::{ClassName, Classes, CustomizationMap},
+        classes::{
+            CharacterEquipment, ClassName, Classes, CustomizationEntry, CustomizationMap,
+            NameOrEmpty,
+        },
+        equipment_sets::EquipmentSets,
+        items::{ItemName, Items},
         level_tables::LevelTables,
-        skills::{SkillDefinition, Skills},
+        skills::{count_newly_unlocked, merge_skill_trees, SkillDefinition, Skills},
     },
     http::{
         middleware::{user::Auth, JsonDump},
         models::{
             character::*,
             errors::{DynHttpError, HttpResult},
+            VecWithCount,
         },
     },
+    services::{
+        activity::{ActivityEvent, ActivityName, ActivityService},
+        game::PlayerCosmetics,
+        sessions::Sessions,
+    },
 };
 use axum::{extract::Path, Extension, Json};
 use hyper::StatusCode;
 use log::debug;
 use sea_orm::{
     ActiveModelTrait, ActiveValue, ColumnTrait, DatabaseConnection, IntoActiveModel, ModelTrait,
-    QueryFilter,
+    QueryFilter, TransactionTrait,
 };
 
 /// GET /characters
@@ -53,10 +65,12 @@ pub async fn get_character(
         .ok_or(CharactersError::NotFound)?;
 
     let shared_data = SharedData::get(&db, &user).await?;
+    let active_equipment_sets = EquipmentSets::get().active_sets(&character.equipments.0);
 
     Ok(Json(CharacterResponse {
         character,
         shared_data,
+        active_equipment_sets,
     }))
 }
 
@@ -123,30 +137,130 @@ pub async fn update_character_equip(
         .await?
         .ok_or(CharactersError::NotFound)?;
 
+    let class = Classes::get()
+        .by_name(&character.class_name)
+        .ok_or(CharactersError::MissingClass)?;
+
+    // Changes to locked slots are dropped rather than rejecting the whole
+    // list, equipment already in a locked slot is kept and cleared slots
+    // are allowed so that locked slots can be emptied
+    let list: Vec<CharacterEquipment> = req
+        .list
+        .into_iter()
+        .filter_map(|equipment| {
+            if equipment.name == NameOrEmpty::Empty
+                || class.is_slot_unlocked(equipment.slot, character.level)
+            {
+                return Some(equipment);
+            }
+
+            debug!(
+                "Ignoring equipment for locked slot {:?} (CID: {})",
+                equipment.slot, character_id
+            );
+
+            character
+                .equipments
+                .0
+                .iter()
+                .find(|existing| existing.slot == equipment.slot)
+                .cloned()
+        })
+        .collect();
+
+    // Items must fit their slot and the attachments must fit the item
+    if let Some(name) = invalid_item(&list) {
+        return Err(CharactersError::InvalidEquipment(name).into());
+    }
+
+    // Find the sets that are only completed by the new equipment
+    let equipment_sets = EquipmentSets::get();
+    let previous_sets = equipment_sets.active_sets(&character.equipments.0);
+    let events: Vec<ActivityEvent> = equipment_sets
+        .active_sets(&list)
+        .into_iter()
+        .filter(|set| !previous_sets.iter().any(|other| other.name == set.name))
+        .map(|set| {
+            ActivityEvent::new(ActivityName::EquipmentSetCompleted)
+                .with_attribute("setName", set.name.as_str())
+                .with_attribute("count", 1)
+        })
+        .collect();
+
     let mut character = character.into_active_model();
-    character.equipments = ActiveValue::Set(SeaJson(req.list));
+    character.equipments = ActiveValue::Set(SeaJson(list));
     let _ = character.update(&db).await?;
 
+    if !events.is_empty() {
+        ActivityService::process_events(&db, &user, events).await?;
+    }
+
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// Finds the first item in the `equipment` that can't be equipped in its
+/// slot or attachment that can't be attached to its item, items without
+/// a definition are skipped
+fn invalid_item(equipment: &[CharacterEquipment]) -> Option<ItemName> {
+    let items = Items::get();
+
+    equipment.iter().find_map(|equipment| {
+        // Attachments need an item to be attached to
+        let NameOrEmpty::Name(name) = &equipment.name else {
+            return equipment.attachments.first().copied();
+        };
+
+        let definition = items.by_name(name)?;
+        if !definition.can_equip(equipment.slot) {
+            return Some(*name);
+        }
+
+        equipment
+            .attachments
+            .iter()
+            .find(|attachment| {
+                items
+                    .by_name(attachment)
+                    .is_some_and(|other| !definition.can_attach(other))
+            })
+            .copied()
+    })
+}
+
 /// PUT /character/equipment/shared
 ///
 /// Updates share character equipment
 pub async fn update_shared_equip(
     Auth(user): Auth,
     Extension(db): Extension<DatabaseConnection>,
+    Extension(sessions): Extension<Arc<Sessions>>,
     JsonDump(req): JsonDump<CharacterEquipmentList>,
 ) -> Result<StatusCode, DynHttpError> {
     debug!("Update shared equipment: {:?}", req);
+
+    if let Some(name) = invalid_item(&req.list) {
+        return Err(CharactersError::InvalidEquipment(name).into());
+    }
+
     let shared_data = SharedData::get(&db, &user).await?;
-    shared_data.set_shared_equipment(&db, req.list).await?;
+    let shared_data = shared_data.set_shared_equipment(&db, req.list).await?;
+
+    // Update the cosmetics shown to other players
+    if let Some(session) = sessions.lookup_session(user.id) {
+        session.set_cosmetics(
+            PlayerCosmetics::from_equipment(&shared_data.shared_equipment.list)
+                .with_title(user.title.clone()),
+        );
+    }
+
     Ok(StatusCode::NO_CONTENT)
 }
 
 /// PUT /character/:id/customization
 ///
-/// Updates the customization settings for a character
+/// Updates the customization settings for a character, the entries are
+/// validated against the class default customization and the previous
+/// customization is stored in the customization history
 pub async fn update_character_customization(
     Path(character_id): Path<CharacterId>,
     Auth(user): Auth,
@@ -165,17 +279,94 @@ pub async fn update_character_customization(
         .await?
         .ok_or(CharactersError::NotFound)?;
 
+    let class = Classes::get()
+        .by_name(&character.class_name)
+        .ok_or(CharactersError::MissingClass)?;
+
+    let entries: Vec<(String, CustomizationEntry)> = req
+        .customization
+        .into_iter()
+        .map(|(key, value)| (key, value.into()))
+        .collect();
+
+    for (key, entry) in &entries {
+        class.default_customization.validate(key, entry)?;
+    }
+
     // Swap the customization map for an empty one so we can edit it
     let mut customization = CustomizationMap::default();
     swap(&mut customization, &mut character.customization);
 
+    let previous = customization.clone();
+
     // Update the customization with the request values
-    req.customization
+    entries
         .into_iter()
-        .for_each(|(key, value)| customization.set(key, value.into()));
+        .for_each(|(key, value)| customization.set(key, value));
+
+    let tx = db.begin().await?;
+    CharacterCustomizationHistory::create(&tx, character.id, previous).await?;
+    _ = character.update_customization(&tx, customization).await?;
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /character/:id/customization/history
+///
+/// Lists the previous customization states of a character that can
+/// be reverted to, most recent first
+pub async fn get_customization_history(
+    Path(character_id): Path<CharacterId>,
+    Auth(user): Auth,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<VecWithCount<CharacterCustomizationHistory>> {
+    let character = user
+        .find_related(characters::Entity)
+        .filter(characters::Column::Id.eq(character_id))
+        .one(&db)
+        .await?
+        .ok_or(CharactersError::NotFound)?;
+
+    let history = CharacterCustomizationHistory::all(&db, character.id).await?;
+
+    Ok(Json(VecWithCount::new(history)))
+}
+
+/// POST /character/:id/customization/history/:history_id/revert
+///
+/// Reverts the customization of a character to a previous state, the
+/// current customization is stored in the history so the revert can
+/// itself be undone
+pub async fn revert_customization(
+    Path((character_id, history_id)): Path<(CharacterId, CustomizationHistoryId)>,
+    Auth(user): Auth,
+    Extension(db): Extension<DatabaseConnection>,
+) -> Result<StatusCode, DynHttpError> {
+    debug!(
+        "Revert character customization: {} - {}",
+        character_id, history_id
+    );
 
-    // Update the stored customization
-    _ = character.update_customization(&db, customization).await?;
+    let character = user
+        .find_related(characters::Entity)
+        .filter(characters::Column::Id.eq(character_id))
+        .one(&db)
+        .await?
+        .ok_or(CharactersError::NotFound)?;
+
+    let version = CharacterCustomizationHistory::get(&db, character.id, history_id)
+        .await?
+        .ok_or(CharactersError::UnknownCustomizationVersion)?;
+
+    let previous = character.customization.clone();
+
+    let tx = db.begin().await?;
+    CharacterCustomizationHistory::create(&tx, character.id, previous).await?;
+    _ = character
+        .update_customization(&tx, version.customization)
+        .await?;
+    tx.commit().await?;
 
     Ok(StatusCode::NO_CONTENT)
 }
@@ -214,45 +405,72 @@ pub async fn update_skill_tree(
 ) -> HttpResult<Character> {
     debug!("Req update skill tree: {} {:?}", character_id, req);
 
-    let mut character = user
+    let character = user
         .find_related(characters::Entity)
         .filter(characters::Column::Id.eq(character_id))
         .one(&db)
         .await?
         .ok_or(CharactersError::NotFound)?;
 
-    // TODO: Calculate skill requirement and ensure user can afford it, update
-    // associated points fields
-
-    // TODO: Clean this up and properly diff the trees
-    req.skill_trees.into_iter().for_each(|tree| {
-        let par = character
-            .skill_trees
-            .0
-            .iter_mut()
-            .find(|value| value.name == tree.name);
-        if let Some(par) = par {
-            for entry in tree.tree {
-                let par = par.tree.iter_mut().find(|value| value.tier == entry.tier);
-                if let Some(par) = par {
-                    for (key, value) in entry.skills {
-                        par.set_skill(key, value);
-                    }
-                }
-            }
-        }
-    });
+    let mut skill_trees = character.skill_trees.0.clone();
+    merge_skill_trees(&mut skill_trees, req.skill_trees);
 
-    // TODO: Update available skillpoints
+    // Ensure the skills exist, tiers are unlocked in order, and
+    // the character has enough skill points
+    let allocation = Skills::get().allocate(&skill_trees)?;
+    let available = character.available_skill_points();
+    if allocation.cost > available {
+        return Err(CharactersError::InsufficientSkillPoints(allocation.cost, available).into());
+    }
 
-    let mut character = character.into_active_model();
-    character.skill_trees =
-        ActiveValue::Set(character.skill_trees.take().expect("Skill tree missing"));
-    let character = character.update(&db).await?;
+    let purchased = count_newly_unlocked(&character.skill_trees.0, &skill_trees);
+    let character = character
+        .set_skill_trees(&db, skill_trees, allocation.cost)
+        .await?;
+
+    if purchased > 0 {
+        let event =
+            ActivityEvent::new(ActivityName::SkillPurchased).with_attribute("count", purchased);
+        ActivityService::process_event(&db, &user, event).await?;
+    }
 
     Ok(Json(character))
 }
 
+/// POST /character/:id/skillTrees/preview
+///
+/// Computes the skill point cost, remaining points, and bonuses of the
+/// character skill trees with the requested changes applied without
+/// saving the changes, used by build planners
+pub async fn preview_skill_tree(
+    Path(character_id): Path<CharacterId>,
+    Auth(user): Auth,
+    Extension(db): Extension<DatabaseConnection>,
+    Json(req): Json<UpdateSkillTreesRequest>,
+) -> HttpResult<SkillTreePreviewResponse> {
+    let character = user
+        .find_related(characters::Entity)
+        .filter(characters::Column::Id.eq(character_id))
+        .one(&db)
+        .await?
+        .ok_or(CharactersError::NotFound)?;
+
+    let mut skill_trees = character.skill_trees.0;
+    merge_skill_trees(&mut skill_trees, req.skill_trees);
+
+    let allocation = Skills::get().allocate(&skill_trees)?;
+
+    let available = character.available_skill_points();
+
+    Ok(Json(SkillTreePreviewResponse {
+        available,
+        remaining: available.saturating_sub(allocation.cost),
+        affordable: allocation.cost <= available,
+        allocation,
+        skill_trees,
+    }))
+}
+
 /// GET /character/classes
 pub async fn get_classes(
     Auth(user): Auth,
@@ -3,28 +3,43 @@
 
 use crate::{
     blaze::{router::BlazeRouter, session::Session},
-    database::entity::{users::CreateUser, Currency, SharedData, User},
-    definitions::{items::create_default_items, strike_teams::create_user_strike_team},
+    config::Config,
+    database::entity::{users::CreateUser, LoginEvent, SharedData, User, UserBan, WelcomeKitGrant},
+    definitions::welcome_kit::WelcomeKit,
     http::{
         middleware::{json_validated::JsonValidated, upgrade::Upgrade, user::Auth},
         models::{
+            auth::RefreshTokenResponse,
             client::{
-                ClientError, CreateUserRequest, LoginUserRequest, ServerDetailsResponse,
+                ChangePasswordRequest, ClientError, ClientUpdateQuery, ClientUpdateResponse,
+                CreateUserRequest, LinkAccountRequest, LoginUserRequest, ServerDetailsResponse,
                 TokenResponse,
             },
             DynHttpError, HttpResult,
         },
     },
-    services::sessions::Sessions,
+    services::{
+        auth::{create_account, AuthProviderError, AuthService},
+        email::send_email,
+        feature_flags::FeatureFlags,
+        game::PlayerCosmetics,
+        household::exceeds_session_limit,
+        pocket_relay::PocketRelayService,
+        sessions::Sessions,
+    },
     utils::hashing::{hash_password, verify_password},
     VERSION,
 };
 use anyhow::Context;
-use axum::{response::IntoResponse, Extension, Json};
+use axum::{
+    extract::{ConnectInfo, Query},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
 use hyper::{header, http::HeaderValue, StatusCode};
-use log::error;
-use sea_orm::{DatabaseConnection, TransactionTrait};
-use std::sync::Arc;
+use log::{error, warn};
+use sea_orm::{DatabaseConnection, DbErr};
+use std::{net::SocketAddr, sync::Arc};
 
 /// GET /ark/client/details
 ///
@@ -37,41 +52,274 @@ pub async fn details() -> Json<ServerDetailsResponse> {
     })
 }
 
+/// Provides the latest client plugin release along with the downloads,
+/// their hashes and publisher signatures
+fn client_update(query: &ClientUpdateQuery) -> HttpResult<ClientUpdateResponse> {
+    let config = &Config::get().client_updates;
+    let version = config
+        .version
+        .as_deref()
+        .ok_or(ClientError::NoClientUpdate)?;
+
+    let downloads = config
+        .downloads
+        .iter()
+        .filter(|download| match &query.platform {
+            Some(platform) => download.platform.eq_ignore_ascii_case(platform),
+            None => true,
+        })
+        .filter(|download| {
+            let valid = download.is_valid();
+            if !valid {
+                warn!(
+                    "Skipping client download for {} without a HTTPS URL, SHA-256 hash \
+                    and publisher signature",
+                    download.platform
+                );
+            }
+            valid
+        })
+        .collect();
+
+    Ok(Json(ClientUpdateResponse {
+        version,
+        release_notes: config.release_notes.as_deref(),
+        downloads,
+    }))
+}
+
 /// POST /ark/client/login
 ///
-/// Used by the client tool to login to an account on the server
+/// Used by the client tool to login to an account on the server, the
+/// login is verified by the configured authentication provider
 pub async fn login(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
     Extension(db): Extension<DatabaseConnection>,
     Extension(sessions): Extension<Arc<Sessions>>,
-    JsonValidated(LoginUserRequest { email, password }): JsonValidated<LoginUserRequest>,
+    Extension(auth): Extension<Arc<AuthService>>,
+    Extension(feature_flags): Extension<Arc<FeatureFlags>>,
+    JsonValidated(LoginUserRequest {
+        email,
+        password,
+        client_version,
+        platform,
+    }): JsonValidated<LoginUserRequest>,
 ) -> HttpResult<TokenResponse> {
-    // Find the user requested
-    let user = User::by_email(&db, &email)
-        .await?
-        .ok_or(ClientError::AccountNotFound)?;
-
-    // Ensure the passwords match
-    if !verify_password(&password, &user.password) {
-        return Err(ClientError::IncorrectPassword.into());
+    let user = auth.authenticate(&db, &email, &password).await?;
+    complete_login(
+        addr,
+        &db,
+        &sessions,
+        &feature_flags,
+        user,
+        client_version,
+        platform,
+    )
+    .await
+}
+
+/// Completes the login of a `user` authenticated by one of the login
+/// routes, rejects banned users and provides a token for the user
+async fn complete_login(
+    addr: SocketAddr,
+    db: &DatabaseConnection,
+    sessions: &Sessions,
+    feature_flags: &FeatureFlags,
+    user: User,
+    client_version: Option<String>,
+    platform: Option<String>,
+) -> HttpResult<TokenResponse> {
+    ensure_not_banned(db, &user).await?;
+
+    if exceeds_session_limit(sessions, addr.ip(), user.id) {
+        return Err(ClientError::HouseholdSessionLimit.into());
     }
 
+    let first_login = record_login(db, &user, addr, client_version, platform).await?;
+    let welcome_kit = first_login_welcome_kit(db, &user, first_login).await?;
+
     let token = sessions.create_token(user.id);
+    let signing_key = sessions.request_signing_key(&token);
 
-    Ok(Json(TokenResponse { token }))
+    Ok(Json(TokenResponse {
+        token,
+        signing_key,
+        welcome_kit,
+        feature_flags: feature_flags.evaluate(user.id),
+    }))
+}
+
+/// POST /api/server/pocketRelay/login
+///
+/// Used by the client tool to login with an account from the configured
+/// Pocket Relay server, the account is imported on its first login
+pub async fn pocket_relay_login(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(sessions): Extension<Arc<Sessions>>,
+    Extension(pocket_relay): Extension<Arc<PocketRelayService>>,
+    Extension(feature_flags): Extension<Arc<FeatureFlags>>,
+    JsonValidated(LoginUserRequest {
+        email,
+        password,
+        client_version,
+        platform,
+    }): JsonValidated<LoginUserRequest>,
+) -> HttpResult<TokenResponse> {
+    let user = pocket_relay.login(&db, &email, &password).await?;
+    complete_login(
+        addr,
+        &db,
+        &sessions,
+        &feature_flags,
+        user,
+        client_version,
+        platform,
+    )
+    .await
+}
+
+/// POST /api/server/pocketRelay/link
+///
+/// Links an account from the configured Pocket Relay server to the
+/// current user so either login can be used
+pub async fn link_pocket_relay(
+    Auth(user): Auth,
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(pocket_relay): Extension<Arc<PocketRelayService>>,
+    JsonValidated(LinkAccountRequest { email, password }): JsonValidated<LinkAccountRequest>,
+) -> Result<StatusCode, DynHttpError> {
+    pocket_relay.link(&db, &user, &email, &password).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /api/server/link
+///
+/// Links the account from the configured external authentication provider
+/// to the current user, used by users whose existing account couldn't be
+/// linked automatically as the provider hadn't verified their email
+pub async fn link_account(
+    Auth(user): Auth,
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(auth): Extension<Arc<AuthService>>,
+    JsonValidated(LinkAccountRequest { email, password }): JsonValidated<LinkAccountRequest>,
+) -> Result<StatusCode, DynHttpError> {
+    auth.link(&db, &user, &email, &password).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// PUT /api/server/password
+///
+/// Changes the password of the current user, tokens issued before the
+/// change are revoked so other logins have to login again. Provides a
+/// new token for the current client
+pub async fn change_password(
+    Auth(user): Auth,
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(sessions): Extension<Arc<Sessions>>,
+    Extension(auth): Extension<Arc<AuthService>>,
+    JsonValidated(ChangePasswordRequest {
+        current_password,
+        new_password,
+    }): JsonValidated<ChangePasswordRequest>,
+) -> HttpResult<RefreshTokenResponse> {
+    // External providers manage their own passwords
+    if !auth.allows_registration() {
+        return Err(ClientError::PasswordChangeDisabled.into());
+    }
+
+    if !verify_password(&current_password, &user.password) {
+        return Err(AuthProviderError::InvalidCredentials.into());
+    }
+
+    let password = hash_password(&new_password).context("Failed to hash password")?;
+    let user = user.set_password(&db, password).await?;
+
+    User::revoke_tokens(&db, user.id).await?;
+    let token = sessions.create_token(user.id);
+    let signing_key = sessions.request_signing_key(&token);
+
+    Ok(Json(RefreshTokenResponse { token, signing_key }))
+}
+
+/// Rejects logins from users with an active server ban
+async fn ensure_not_banned(db: &DatabaseConnection, user: &User) -> Result<(), DynHttpError> {
+    if UserBan::active(db, user.id).await?.is_some() {
+        return Err(ClientError::Banned.into());
+    }
+    Ok(())
+}
+
+/// Records a login event for the `user`, notifies the user by email
+/// when the login is from an IP address they haven't used before.
+///
+/// Returns whether this was the first login for the `user`
+async fn record_login(
+    db: &DatabaseConnection,
+    user: &User,
+    addr: SocketAddr,
+    client_version: Option<String>,
+    platform: Option<String>,
+) -> Result<bool, DbErr> {
+    let ip = addr.ip().to_string();
+
+    let first_login = !LoginEvent::has_any(db, user).await?;
+
+    // First logins and known addresses don't need a notification
+    let is_new_ip = !first_login && !LoginEvent::has_ip(db, user, &ip).await?;
+
+    LoginEvent::create(db, user, ip.clone(), client_version, platform).await?;
+
+    if is_new_ip {
+        send_email(
+            user.email.clone(),
+            "New login to your account".to_string(),
+            format!(
+                "Hi {},\n\nYour account was just logged into from a new IP address ({}).\n\n\
+                If this wasn't you, change your password immediately.",
+                user.username, ip
+            ),
+        );
+    }
+
+    Ok(first_login)
+}
+
+/// Provides the welcome kit to include in the login response when
+/// this is the `first_login` of a `user` that was granted the kit
+async fn first_login_welcome_kit(
+    db: &DatabaseConnection,
+    user: &User,
+    first_login: bool,
+) -> Result<Option<&'static WelcomeKit>, DbErr> {
+    if !first_login || !WelcomeKitGrant::exists(db, user).await? {
+        return Ok(None);
+    }
+
+    Ok(Some(WelcomeKit::get()))
 }
 
 /// POST /ark/client/create
 ///
-/// Used by the client tool to create an account on the server
+/// Used by the client tool to create an account on the server, only
+/// available when the authentication provider allows registration
 pub async fn create(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
     Extension(db): Extension<DatabaseConnection>,
     Extension(sessions): Extension<Arc<Sessions>>,
+    Extension(auth): Extension<Arc<AuthService>>,
+    Extension(feature_flags): Extension<Arc<FeatureFlags>>,
     JsonValidated(CreateUserRequest {
         email,
         username,
         password,
     }): JsonValidated<CreateUserRequest>,
 ) -> HttpResult<TokenResponse> {
+    // External providers manage their own accounts
+    if !auth.allows_registration() {
+        return Err(ClientError::RegistrationDisabled.into());
+    }
+
     // Ensure the email doesn't exist already
     if User::email_exists(&db, &email).await? {
         return Err(ClientError::EmailTaken.into());
@@ -90,43 +338,53 @@ pub async fn create(
         password,
     };
 
-    let user = db
-        .transaction(|db| {
-            Box::pin(async move {
-                // Create the user account
-                let user = User::create(db, create).await?;
-
-                // Give the user all the default items
-                create_default_items(db, &user).await?;
-
-                // Give the user the default currencies
-                Currency::set_default(db, &user).await?;
-
-                // Setup the user shared data
-                SharedData::create_default(db, &user).await?;
+    let user = create_account(&db, create).await?;
 
-                // Setup the user strike teams
-                create_user_strike_team(db, &user).await?;
-
-                Ok::<_, DynHttpError>(user)
-            })
-        })
-        .await?;
+    let first_login = record_login(&db, &user, addr, None, None).await?;
+    let welcome_kit = first_login_welcome_kit(&db, &user, first_login).await?;
 
     let token = sessions.create_token(user.id);
+    let signing_key = sessions.request_signing_key(&token);
 
-    Ok(Json(TokenResponse { token }))
+    Ok(Json(TokenResponse {
+        token,
+        signing_key,
+        welcome_kit,
+        feature_flags: feature_flags.evaluate(user.id),
+    }))
 }
 
-/// GET /ark/client/upgrade
+/// GET /api/server/upgrade
 ///
-/// Handles upgrading a HTTP connection to a blaze stream for game traffic
+/// Handles upgrading a HTTP connection to a blaze stream for game traffic,
+/// requests that aren't upgrading the connection are given the latest
+/// client plugin release for the plugin to check for updates
 pub async fn upgrade(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
     Auth(user): Auth,
+    Extension(db): Extension<DatabaseConnection>,
     Extension(router): Extension<Arc<BlazeRouter>>,
     Extension(sessions): Extension<Arc<Sessions>>,
-    Upgrade(upgrade): Upgrade,
-) -> Result<impl IntoResponse, DynHttpError> {
+    Query(query): Query<ClientUpdateQuery>,
+    upgrade: Option<Upgrade>,
+) -> Result<Response, DynHttpError> {
+    let Some(Upgrade(upgrade)) = upgrade else {
+        return client_update(&query).map(IntoResponse::into_response);
+    };
+
+    // Tokens issued before a ban can't be used to connect
+    ensure_not_banned(&db, &user).await?;
+
+    // Enforce the household session limit
+    if exceeds_session_limit(&sessions, addr.ip(), user.id) {
+        return Err(ClientError::HouseholdSessionLimit.into());
+    }
+
+    // Load the equipped cosmetics to show other players in games
+    let shared_data = SharedData::get(&db, &user).await?;
+    let cosmetics = PlayerCosmetics::from_equipment(&shared_data.shared_equipment.list)
+        .with_title(user.title.clone());
+
     // Handle the client upgrading in a new task
     tokio::spawn(async move {
         let io = match upgrade.await {
@@ -137,7 +395,7 @@ pub async fn upgrade(
             }
         };
 
-        Session::start(io, user, router, sessions).await;
+        Session::start(io, addr.ip(), user, cosmetics, router, sessions).await;
     });
 
     // Tell the client to switch protocols
@@ -147,5 +405,6 @@ pub async fn upgrade(
             (header::CONNECTION, HeaderValue::from_static("upgrade")),
             (header::UPGRADE, HeaderValue::from_static("blaze")),
         ],
-    ))
+    )
+        .into_response())
 }
@@ -0,0 +1,177 @@
+//! Routes for companion apps, these wrap the strike team routes with compact
+//! responses so apps can manage strike teams away from the game similar to
+//! the Apex HQ app for the official servers
+
+use super::strike_teams::{self, team_with_mission};
+use crate::{
+    database::entity::{
+        strike_team_mission::{MissionAccessibility, StrikeTeamMissionId},
+        strike_team_mission_progress::UserMissionState,
+        strike_teams::StrikeTeamId,
+        Currency, StrikeTeam, StrikeTeamDeployment, StrikeTeamMission,
+    },
+    definitions::strike_teams::StrikeTeams,
+    http::{
+        middleware::user::Auth,
+        models::{
+            companion::{
+                CompanionDeployResponse, CompanionMission, CompanionOverview,
+                CompanionPurchaseResponse, CompanionResolution, CompanionResolveAllResponse,
+                CompanionShop, CompanionTeam,
+            },
+            strike_teams::PurchaseQuery,
+            HttpResult,
+        },
+    },
+    services::{maintenance::ReadOnlyMode, strike_team_deployment, user_locks::UserLocks},
+    utils::clock,
+};
+use axum::{
+    extract::{Path, Query},
+    Extension, Json,
+};
+use sea_orm::DatabaseConnection;
+use std::sync::Arc;
+
+/// GET /api/companion/teams
+///
+/// Obtains the users strike teams along with the missions they
+/// can be deployed onto
+pub async fn get_overview(
+    Auth(user): Auth,
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(read_only): Extension<Arc<ReadOnlyMode>>,
+) -> HttpResult<CompanionOverview> {
+    let now = clock::now();
+    let active = strike_team_deployment::view(&db, &user, &read_only).await?;
+
+    let strike_teams = StrikeTeam::get_by_user(&db, &user).await?;
+    let mut teams: Vec<CompanionTeam> = Vec::with_capacity(strike_teams.len());
+    for team in strike_teams {
+        let progress = active
+            .iter()
+            .find(|progress| progress.strike_team_id == Some(team.id));
+        teams.push(team_with_mission(&db, team, progress).await?.into());
+    }
+
+    let missions = StrikeTeamMission::available_missions(&db, &user, now.timestamp())
+        .await?
+        .into_iter()
+        .map(|(mission, _)| mission)
+        .filter(|mission| mission.accessibility != MissionAccessibility::MultiPlayer)
+        .map(CompanionMission::from)
+        .collect();
+
+    let queued_missions = StrikeTeamDeployment::all(&db, &user).await?.len();
+
+    Ok(Json(CompanionOverview {
+        teams,
+        missions,
+        queued_missions,
+        server_time: now,
+    }))
+}
+
+/// POST /api/companion/deploy/:mission_id
+///
+/// Deploys the next available strike team onto a mission, queueing
+/// the mission when all the teams are busy
+pub async fn deploy(
+    auth: Auth,
+    path: Path<StrikeTeamMissionId>,
+    db: Extension<DatabaseConnection>,
+    user_locks: Extension<Arc<UserLocks>>,
+) -> HttpResult<CompanionDeployResponse> {
+    let Json(response) = strike_teams::deploy(auth, path, db, user_locks).await?;
+
+    Ok(Json(CompanionDeployResponse {
+        queued: response.queued,
+        team: response.team.map(CompanionTeam::from),
+        queue_position: response.queue_position,
+    }))
+}
+
+/// POST /api/companion/teams/:id/resolve
+///
+/// Resolves the mission a strike team has returned from
+pub async fn resolve(
+    auth: Auth,
+    path: Path<StrikeTeamId>,
+    db: Extension<DatabaseConnection>,
+) -> HttpResult<CompanionResolution> {
+    let Json(response) = strike_teams::resolve_mission(auth, path, db).await?;
+    Ok(Json(response.into()))
+}
+
+/// POST /api/companion/resolveAll
+///
+/// Resolves the missions of every strike team that has returned
+pub async fn resolve_all(
+    Auth(user): Auth,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<CompanionResolveAllResponse> {
+    let active = strike_team_deployment::refresh(&db, &user).await?;
+
+    let team_ids: Vec<StrikeTeamId> = active
+        .iter()
+        .filter(|progress| progress.user_mission_state == UserMissionState::PendingResolve)
+        .filter_map(|progress| progress.strike_team_id)
+        .collect();
+
+    let mut resolutions = Vec::with_capacity(team_ids.len());
+    for team_id in team_ids {
+        let Json(response) =
+            strike_teams::resolve_mission(Auth(user.clone()), Path(team_id), Extension(db.clone()))
+                .await?;
+        resolutions.push(response.into());
+    }
+
+    Ok(Json(CompanionResolveAllResponse { resolutions }))
+}
+
+/// GET /api/companion/shop
+///
+/// Obtains the strike team equipment available for purchase along
+/// with the users currency balances
+pub async fn get_shop(
+    Auth(user): Auth,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<CompanionShop> {
+    let balances = Currency::all(&db, &user)
+        .await?
+        .into_iter()
+        .map(|currency| (currency.ty, currency.balance))
+        .collect();
+
+    let equipment = StrikeTeams::get()
+        .equipment
+        .iter()
+        .map(Into::into)
+        .collect();
+
+    Ok(Json(CompanionShop {
+        balances,
+        equipment,
+    }))
+}
+
+/// POST /api/companion/teams/:id/equipment/:name?currency=MissionCurrency
+///
+/// Purchases equipment for a strike team
+pub async fn purchase_equipment(
+    auth: Auth,
+    query: Query<PurchaseQuery>,
+    path: Path<(StrikeTeamId, String)>,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<CompanionPurchaseResponse> {
+    let Json(response) =
+        strike_teams::purchase_equipment(auth, query, path, Extension(db.clone())).await?;
+
+    // Teams can't purchase equipment while on a mission
+    let team = team_with_mission(&db, response.team, None).await?;
+
+    Ok(Json(CompanionPurchaseResponse {
+        team: team.into(),
+        balance: response.currency_balance.balance,
+    }))
+}
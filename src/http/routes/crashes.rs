@@ -0,0 +1,41 @@
+//! Routes for administrators reviewing the crash reports stored
+//! when the server panics
+
+use crate::{
+    http::{
+        middleware::user::AdminAuth,
+        models::{
+            crashes::{CrashReportError, CrashReportSummary},
+            HttpResult, VecWithCount,
+        },
+    },
+    services::crash_reports::{self, CrashReport},
+};
+use axum::{extract::Path, Json};
+
+/// GET /api/server/crashes
+///
+/// Used by administrators to list the stored crash reports, newest first
+pub async fn get_crashes(AdminAuth(_): AdminAuth) -> HttpResult<VecWithCount<CrashReportSummary>> {
+    let reports = crash_reports::all()
+        .await
+        .map_err(anyhow::Error::from)?
+        .into_iter()
+        .map(CrashReportSummary::from)
+        .collect();
+
+    Ok(Json(VecWithCount::new(reports)))
+}
+
+/// GET /api/server/crashes/:id
+///
+/// Used by administrators to obtain a crash report along with its
+/// backtrace for reporting upstream
+pub async fn get_crash(AdminAuth(_): AdminAuth, Path(id): Path<String>) -> HttpResult<CrashReport> {
+    let report = crash_reports::get(&id)
+        .await
+        .map_err(anyhow::Error::from)?
+        .ok_or(CrashReportError::UnknownReport)?;
+
+    Ok(Json(report))
+}
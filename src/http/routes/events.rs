@@ -0,0 +1,59 @@
+//! Routes for external dashboard tooling to follow the live server events
+
+use crate::{
+    http::middleware::user::AdminAuth,
+    services::server_events::{ServerEventEntry, ServerEvents},
+};
+use axum::{
+    extract::ws::{Message, WebSocket, WebSocketUpgrade},
+    response::Response,
+};
+use log::{debug, error};
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+
+/// GET /api/server/events
+///
+/// Upgrades to a WebSocket streaming the server events as JSON text
+/// messages until the client disconnects. Messages sent by the client
+/// are ignored
+pub async fn get_events(AdminAuth(user): AdminAuth, ws: WebSocketUpgrade) -> Response {
+    debug!("Dashboard subscribed to server events (UID: {})", user.id);
+
+    let rx = ServerEvents::get().subscribe();
+    ws.on_upgrade(move |socket| stream_events(socket, rx))
+}
+
+/// Sends the events received on `rx` to the `socket`
+async fn stream_events(mut socket: WebSocket, mut rx: Receiver<ServerEventEntry>) {
+    loop {
+        tokio::select! {
+            entry = rx.recv() => {
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    // Slow dashboards skip the events they missed
+                    Err(RecvError::Lagged(skipped)) => {
+                        debug!("Dashboard missed {} server event(s)", skipped);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+
+                let text = match serde_json::to_string(&entry) {
+                    Ok(value) => value,
+                    Err(err) => {
+                        error!("Failed to serialize server event: {}", err);
+                        continue;
+                    }
+                };
+
+                if socket.send(Message::Text(text)).await.is_err() {
+                    break;
+                }
+            }
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,67 @@
+//! Routes for evaluating and managing the feature flags delivered
+//! to the client plugin
+
+use crate::{
+    config::FeatureFlag,
+    http::{
+        middleware::user::{AdminAuth, Auth, OperatorAuth},
+        models::{feature_flags::FeatureFlagError, DynHttpError, HttpResult},
+    },
+    services::feature_flags::{EvaluatedFlags, FeatureFlags},
+};
+use axum::{extract::Path, Extension, Json};
+use hyper::StatusCode;
+use std::sync::Arc;
+
+/// GET /api/server/featureFlags/evaluated
+///
+/// Obtains the feature flags evaluated for the current user, used by
+/// the client plugin to refresh the flags it received at login
+pub async fn get_evaluated(
+    Auth(user): Auth,
+    Extension(flags): Extension<Arc<FeatureFlags>>,
+) -> Json<EvaluatedFlags> {
+    Json(flags.evaluate(user.id))
+}
+
+/// GET /api/server/featureFlags
+///
+/// Used by administrators to list all the feature flags
+pub async fn get_flags(
+    AdminAuth(_): AdminAuth,
+    Extension(flags): Extension<Arc<FeatureFlags>>,
+) -> Json<Vec<FeatureFlag>> {
+    Json(flags.all())
+}
+
+/// PUT /api/server/featureFlags
+///
+/// Used by administrators to create or replace a feature flag, changes
+/// apply to logins from this point and are not persisted
+pub async fn set_flag(
+    OperatorAuth(_): OperatorAuth,
+    Extension(flags): Extension<Arc<FeatureFlags>>,
+    Json(flag): Json<FeatureFlag>,
+) -> HttpResult<Vec<FeatureFlag>> {
+    if flag.rollout_percent > 100 {
+        return Err(FeatureFlagError::InvalidRollout.into());
+    }
+
+    flags.set(flag);
+    Ok(Json(flags.all()))
+}
+
+/// DELETE /api/server/featureFlags/:name
+///
+/// Used by administrators to remove a feature flag
+pub async fn delete_flag(
+    OperatorAuth(_): OperatorAuth,
+    Path(name): Path<String>,
+    Extension(flags): Extension<Arc<FeatureFlags>>,
+) -> Result<StatusCode, DynHttpError> {
+    if !flags.remove(&name) {
+        return Err(FeatureFlagError::UnknownFlag.into());
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,110 @@
+use crate::{
+    database::entity::{users::UserId, Friend, User},
+    http::{
+        middleware::user::Auth,
+        models::{
+            friends::{FriendResponse, FriendsError, FriendsResponse},
+            DynHttpError, HttpResult,
+        },
+    },
+    services::{friends, sessions::Sessions},
+};
+use axum::{extract::Path, Extension, Json};
+use hyper::StatusCode;
+use sea_orm::DatabaseConnection;
+use std::sync::Arc;
+
+/// GET /user/friends
+///
+/// Lists the friends of the user along with the presence
+/// of the mutual friends
+pub async fn get_friends(
+    Auth(user): Auth,
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(sessions): Extension<Arc<Sessions>>,
+) -> HttpResult<FriendsResponse> {
+    let friends = Friend::all(&db, user.id).await?;
+    let added_by = Friend::added_by(&db, user.id).await?;
+
+    let mut list = Vec::with_capacity(friends.len());
+
+    for friend in friends {
+        // Friend accounts are removed along with their friends
+        let Some(friend_user) = User::by_id(&db, friend.friend_id).await? else {
+            continue;
+        };
+
+        let mutual = added_by.contains(&friend.friend_id);
+
+        // Presence is only shared with mutual friends
+        let session = mutual
+            .then(|| sessions.lookup_session(friend.friend_id))
+            .flatten();
+
+        list.push(FriendResponse {
+            id: friend_user.id,
+            username: friend_user.username,
+            mutual,
+            online: session.is_some(),
+            game_id: session
+                .as_ref()
+                .and_then(|session| session.data.lock().game_id()),
+            added: friend.created,
+        });
+    }
+
+    Ok(Json(FriendsResponse { list }))
+}
+
+/// PUT /user/friends/:id
+///
+/// Adds a user as a friend, adding a user that has already
+/// added the user makes them mutual friends
+pub async fn add_friend(
+    Auth(user): Auth,
+    Path(friend_id): Path<UserId>,
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(sessions): Extension<Arc<Sessions>>,
+) -> Result<StatusCode, DynHttpError> {
+    if friend_id == user.id {
+        return Err(FriendsError::AddSelf.into());
+    }
+
+    if User::by_id(&db, friend_id).await?.is_none() {
+        return Err(FriendsError::UnknownUser.into());
+    }
+
+    let was_mutual = Friend::is_mutual(&db, user.id, friend_id).await?;
+
+    Friend::add(&db, user.id, friend_id).await?;
+
+    // Share presence now that the users are mutual friends
+    if !was_mutual && Friend::is_mutual(&db, user.id, friend_id).await? {
+        friends::link(&sessions, user.id, friend_id);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// DELETE /user/friends/:id
+///
+/// Removes a user from the friends of the user
+pub async fn remove_friend(
+    Auth(user): Auth,
+    Path(friend_id): Path<UserId>,
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(sessions): Extension<Arc<Sessions>>,
+) -> Result<StatusCode, DynHttpError> {
+    let was_mutual = Friend::is_mutual(&db, user.id, friend_id).await?;
+
+    if !Friend::remove(&db, user.id, friend_id).await? {
+        return Err(FriendsError::NotFriend.into());
+    }
+
+    // Stop sharing presence with the removed friend
+    if was_mutual {
+        friends::unlink(&sessions, user.id, friend_id);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
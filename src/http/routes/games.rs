@@ -0,0 +1,147 @@
+use crate::{
+    http::{
+        middleware::{user::Auth, JsonDump},
+        models::{
+            errors::{DynHttpError, HttpResult},
+            game::{GameTimelineError, GamesQuery, TimelineVisibilityRequest},
+            VecWithCount,
+        },
+    },
+    services::{
+        game::GameID,
+        game_manager::{BrowsedGame, GameManager},
+        game_timeline::{TimelineEntry, TimelineVisibility},
+    },
+};
+use axum::{
+    extract::{Path, Query},
+    response::sse::{Event, KeepAlive, Sse},
+    Extension, Json,
+};
+use futures::{stream, Stream, StreamExt};
+use log::debug;
+use std::sync::Arc;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+
+/// The maximum number of games that can be requested at once
+const MAX_GAMES_COUNT: usize = 100;
+
+/// Game attribute storing the mission difficulty
+const DIFFICULTY_ATTR: &str = "difficulty";
+/// Game attribute storing the mission map
+const MAP_ATTR: &str = "level";
+/// Game attribute storing the mission enemy type
+const ENEMY_ATTR: &str = "enemytype";
+
+/// GET /api/server/games
+///
+/// Lists the active games and their attributes for browsing games outside
+/// of the game client, games can be filtered by their difficulty, map,
+/// enemy type and whether they can be joined
+pub async fn get_games(
+    Query(query): Query<GamesQuery>,
+    Extension(game_manager): Extension<Arc<GameManager>>,
+) -> HttpResult<VecWithCount<BrowsedGame>> {
+    let filters: Vec<(&str, &str)> = [
+        (DIFFICULTY_ATTR, &query.difficulty),
+        (MAP_ATTR, &query.map),
+        (ENEMY_ATTR, &query.enemy),
+    ]
+    .into_iter()
+    .filter_map(|(key, value)| value.as_deref().map(|value| (key, value)))
+    .collect();
+
+    let count = query.count.min(MAX_GAMES_COUNT);
+    let (total_count, list) = game_manager.browse(&filters, query.joinable, query.offset, count);
+
+    Ok(Json(VecWithCount { total_count, list }))
+}
+
+/// GET /api/server/games/:id/timeline
+///
+/// Streams the timeline of an in-progress game as server sent events.
+/// The first event is a snapshot of the game followed by an event for
+/// each change to the game. Available to the players in the game, other
+/// authenticated users can only watch when the host has made the timeline
+/// public
+pub async fn get_timeline(
+    Auth(user): Auth,
+    Path(game_id): Path<GameID>,
+    Extension(game_manager): Extension<Arc<GameManager>>,
+) -> Result<Sse<impl Stream<Item = Result<Event, axum::Error>>>, DynHttpError> {
+    let game = game_manager
+        .get_game(game_id)
+        .await
+        .ok_or(GameTimelineError::UnknownGame)?;
+
+    let (snapshot, rx) = {
+        let game = &*game.read().await;
+
+        let is_player = game.players.iter().any(|player| player.user.id == user.id);
+
+        if game.timeline.visibility != TimelineVisibility::Public && !is_player {
+            return Err(GameTimelineError::NotVisible.into());
+        }
+
+        // Subscribe while holding the lock so no events are missed
+        // between the snapshot and the subscription
+        (game.timeline_snapshot(), game.timeline.subscribe())
+    };
+
+    // Drop the game reference so the spectator doesn't keep the game alive
+    drop(game);
+
+    debug!("Spectator watching game timeline (GID: {})", game_id);
+
+    let snapshot = Event::default().event("snapshot").json_data(snapshot);
+    let entries = stream::unfold(rx, next_entry)
+        .map(|entry| Event::default().event("timeline").json_data(entry));
+
+    Ok(
+        Sse::new(stream::once(async move { snapshot }).chain(entries))
+            .keep_alive(KeepAlive::default()),
+    )
+}
+
+/// Receives the next timeline entry, ends the stream once the
+/// game has stopped
+async fn next_entry(
+    mut rx: Receiver<TimelineEntry>,
+) -> Option<(TimelineEntry, Receiver<TimelineEntry>)> {
+    loop {
+        match rx.recv().await {
+            Ok(entry) => return Some((entry, rx)),
+            // Slow spectators skip the entries they missed
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => return None,
+        }
+    }
+}
+
+/// PUT /api/server/games/:id/timeline/visibility
+///
+/// Allows the host of a game to change who can spectate
+/// the game timeline
+pub async fn set_timeline_visibility(
+    Auth(user): Auth,
+    Path(game_id): Path<GameID>,
+    Extension(game_manager): Extension<Arc<GameManager>>,
+    JsonDump(req): JsonDump<TimelineVisibilityRequest>,
+) -> HttpResult<TimelineVisibilityRequest> {
+    let game = game_manager
+        .get_game(game_id)
+        .await
+        .ok_or(GameTimelineError::UnknownGame)?;
+
+    let game = &mut *game.write().await;
+
+    if !game.is_host(user.id) {
+        return Err(GameTimelineError::NotHost.into());
+    }
+
+    game.timeline.visibility = req.visibility;
+
+    Ok(Json(TimelineVisibilityRequest {
+        visibility: game.timeline.visibility,
+    }))
+}
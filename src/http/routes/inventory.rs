@@ -1,22 +1,45 @@
 use crate::{
-    database::entity::{inventory_items::ItemId, InventoryItem, User},
-    definitions::items::{InventoryNamespace, ItemDefinition, Items},
+    config::Config,
+    database::entity::{
+        characters::CharacterId, inventory_items::ItemId, strike_teams::StrikeTeamId, Character,
+        Currency, InventoryItem, PackOpening, SharedData, StrikeTeam, User, XpBoost,
+    },
+    definitions::{
+        item_effects::{item_effects, ItemEffect, ItemEventKind},
+        item_search::{ItemSearchIndex, ItemSearchQuery},
+        items::{BaseCategory, InventoryNamespace, ItemDefinition, ItemName, Items},
+        packs::{Pack, Packs, RewardCollection},
+        strike_teams::StrikeTeamTraitAction,
+    },
     http::{
         middleware::{user::Auth, JsonDump},
         models::{
             inventory::{
-                ConsumeRequest, InventoryError, InventoryRequestQuery, InventoryResponse,
-                InventorySeenRequest, ItemDefinitionsResponse,
+                BulkOpenRequest, BulkOpenResponse, CompatibleMod, ConsumeRequest, InventoryError,
+                InventoryRequestQuery, InventoryResponse, InventorySearchQuery,
+                InventorySeenRequest, ItemDefinitionsResponse, ModCompatibilityResponse,
+                PackOpenBreakdown, PackOpenItem, PackOpeningHistory, PackPreviewQuery,
+                PackPreviewResponse, WeaponModCompatibility,
             },
+            strike_teams::StrikeTeamError,
             DynHttpError, HttpResult,
         },
     },
-    services::activity::{ActivityEvent, ActivityName, ActivityResult, ActivityService},
+    services::{
+        activity::{ActivityEvent, ActivityName, ActivityResult, ActivityService},
+        pack_experiments,
+        user_locks::UserLocks,
+    },
+};
+use axum::{
+    extract::{Path, Query},
+    Extension, Json,
 };
-use axum::{extract::Query, Extension, Json};
 use hyper::StatusCode;
 use log::debug;
+use rand::{rngs::StdRng, SeedableRng};
 use sea_orm::{ConnectionTrait, DatabaseConnection, TransactionTrait};
+use std::sync::Arc;
 
 /// GET /inventory
 ///
@@ -59,6 +82,40 @@ pub async fn get_inventory(
     Ok(Json(InventoryResponse { items, definitions }))
 }
 
+/// GET /inventory/search
+///
+/// Searches the players inventory items by localized item name, category,
+/// rarity, and custom attribute values (e.g. `consumableType=Ammo`)
+pub async fn search_inventory(
+    Query(query): Query<InventorySearchQuery>,
+    Auth(user): Auth,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<InventoryResponse> {
+    let matching = ItemSearchIndex::get().search(&ItemSearchQuery {
+        text: query.q.as_deref(),
+        category: query.category.as_ref(),
+        rarity: query.rarity,
+        attributes: query
+            .attributes
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect(),
+    });
+
+    let items = InventoryItem::all_by_names(&db, &user, matching).await?;
+
+    let item_definitions = Items::get();
+    let definitions = items
+        .iter()
+        .filter_map(|item| item_definitions.by_name(&item.definition_name))
+        .collect();
+
+    Ok(Json(InventoryResponse {
+        items,
+        definitions: Some(definitions),
+    }))
+}
+
 /// GET /inventory/definitions
 ///
 /// Obtains the definitions for all the inventory items this includes things
@@ -72,6 +129,132 @@ pub async fn get_definitions() -> Json<ItemDefinitionsResponse> {
     })
 }
 
+/// The number of recent pack openings included in the history
+const PACK_HISTORY_COUNT: u64 = 50;
+
+/// GET /inventory/packs/:name/preview
+///
+/// Lists the items the pack can drop for the user along with their
+/// odds. When a seed is provided the items the pack generates with
+/// that seed are included, allowing recorded openings to be replayed
+/// (Replays only match while the items the user can unlock are unchanged)
+pub async fn get_pack_preview(
+    Auth(user): Auth,
+    Path(name): Path<ItemName>,
+    Query(query): Query<PackPreviewQuery>,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<PackPreviewResponse> {
+    let pack = Packs::get()
+        .by_name(&name)
+        .ok_or(InventoryError::UnknownPack)?;
+
+    // Preview the weights the user would receive
+    let assignment = pack_experiments::assign(&pack.name, user.id);
+    let weights = assignment.as_ref().and_then(|value| value.weights());
+
+    let items = Pack::available_items(&db, &user, Items::get()).await?;
+    let collections = pack.preview(&items, weights);
+
+    let rolled = match query.seed {
+        Some(seed) => {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut rewards = RewardCollection::default();
+            pack.generate_from_items(&mut rng, &items, weights, &mut rewards)?;
+
+            Some(
+                rewards
+                    .rewards
+                    .into_iter()
+                    .map(|reward| PackOpenItem {
+                        definition_name: reward.definition.name,
+                        stack_size: reward.stack_size,
+                    })
+                    .collect(),
+            )
+        }
+        None => None,
+    };
+
+    Ok(Json(PackPreviewResponse {
+        name: pack.name,
+        collections,
+        rolled,
+    }))
+}
+
+/// GET /inventory/packs/openings
+///
+/// Lists the packs the user recently opened along with the seeds
+/// the items were generated with
+pub async fn get_pack_openings(
+    Auth(user): Auth,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<Vec<PackOpeningHistory>> {
+    let openings = PackOpening::recent(&db, &user, PACK_HISTORY_COUNT).await?;
+
+    Ok(Json(
+        openings
+            .into_iter()
+            .map(|opening| PackOpeningHistory {
+                pack_name: opening.pack_name,
+                seed: opening.seed as u64,
+                items: opening.items,
+                created: opening.created,
+            })
+            .collect(),
+    ))
+}
+
+/// GET /inventory/mods/compatibility
+///
+/// Responds with the owned weapons that mods can be attached to along with
+/// the owned mods that are compatible with each of the weapons
+pub async fn get_mod_compatibility(
+    Auth(user): Auth,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<ModCompatibilityResponse> {
+    let items = InventoryItem::get_all_items(&db, &user).await?;
+    let item_definitions = Items::get();
+
+    // Pair the owned items with their definitions
+    let owned: Vec<(&InventoryItem, &'static ItemDefinition)> = items
+        .iter()
+        .filter(|item| item.stack_size > 0)
+        .filter_map(|item| {
+            item_definitions
+                .by_name(&item.definition_name)
+                .map(|definition| (item, definition))
+        })
+        .collect();
+
+    let list: Vec<WeaponModCompatibility> = owned
+        .iter()
+        // Only items that can have other items attached
+        .filter(|(_, definition)| !definition.attachable_categories.is_empty())
+        .map(|(weapon, weapon_definition)| {
+            let mods = owned
+                .iter()
+                .filter(|(_, mod_definition)| weapon_definition.can_attach(mod_definition))
+                .map(|(item, definition)| CompatibleMod {
+                    item_id: item.id,
+                    definition_name: definition.name,
+                })
+                .collect();
+
+            WeaponModCompatibility {
+                item_id: weapon.id,
+                definition_name: weapon_definition.name,
+                mods,
+            }
+        })
+        .collect();
+
+    Ok(Json(ModCompatibilityResponse {
+        total_count: list.len(),
+        list,
+    }))
+}
+
 /// PUT /inventory/seen
 ///
 /// Updates the seen status of a list of inventory item IDs
@@ -127,6 +310,61 @@ where
     Ok(definition)
 }
 
+/// Finds the strike team targeted by a trait item, ensures the team
+/// isn't on a mission and that the trait `action` can be applied to it
+async fn trait_item_target<C>(
+    db: &C,
+    user: &User,
+    target_id: &str,
+    action: StrikeTeamTraitAction,
+) -> Result<StrikeTeamId, DynHttpError>
+where
+    C: ConnectionTrait + Send,
+{
+    let team_id: StrikeTeamId = target_id
+        .parse()
+        .map_err(|_| InventoryError::InvalidTarget)?;
+
+    let team = StrikeTeam::get_by_id(db, user, team_id)
+        .await?
+        .ok_or(StrikeTeamError::UnknownTeam)?;
+
+    // Traits can't change while the success rate is being used for a mission
+    if team.is_on_mission(db).await? {
+        return Err(StrikeTeamError::TeamOnMission.into());
+    }
+
+    action
+        .check(&team.positive_traits.0, &team.negative_traits.0)
+        .map_err(|_| StrikeTeamError::NoTraitsToChange)?;
+
+    Ok(team.id)
+}
+
+/// Finds the character targeted by a respec item, the `target_id` is the
+/// ID of the character falling back to the active character when empty
+async fn respec_target<C>(db: &C, user: &User, target_id: &str) -> Result<CharacterId, DynHttpError>
+where
+    C: ConnectionTrait + Send,
+{
+    let character_id: CharacterId = if target_id.is_empty() {
+        SharedData::get(db, user)
+            .await?
+            .active_character_id
+            .ok_or(InventoryError::InvalidTarget)?
+    } else {
+        target_id
+            .parse()
+            .map_err(|_| InventoryError::InvalidTarget)?
+    };
+
+    let character = Character::find_by_id_user(db, user, character_id)
+        .await?
+        .ok_or(InventoryError::InvalidTarget)?;
+
+    Ok(character.id)
+}
+
 /// POST /inventory/consume
 ///
 /// Consumes an item from the inventory providing details about the changes to
@@ -135,17 +373,23 @@ where
 pub async fn consume_inventory(
     Auth(user): Auth,
     Extension(db): Extension<DatabaseConnection>,
+    Extension(user_locks): Extension<Arc<UserLocks>>,
     JsonDump(req): JsonDump<ConsumeRequest>,
 ) -> HttpResult<ActivityResult> {
     const CONSUME_COUNT: u32 = 1;
 
     debug!("Consume inventory items: {:?}", req);
 
+    // Hold the user lock so racing consumes can't use the same item twice
+    let _guard = user_locks.lock(user.id).await;
+
     let result: ActivityResult = db
         .transaction(|db| {
             Box::pin(async move {
                 let mut events: Vec<ActivityEvent> = Vec::with_capacity(req.items.len());
                 let item_definitions = Items::get();
+                // Only a single XP boost can be waiting to be used at a time
+                let mut boost_active = XpBoost::is_active(db, &user).await?;
 
                 // Create the consumption event for each item
                 for target in req.items {
@@ -156,11 +400,40 @@ pub async fn consume_inventory(
                         consume_item(db, &user, item_id, CONSUME_COUNT, item_definitions).await?;
 
                     // Create the activity event
-                    let event = ActivityEvent::new(ActivityName::ItemConsumed)
+                    let mut event = ActivityEvent::new(ActivityName::ItemConsumed)
                         .with_attribute("category", item_definition.category.to_string())
                         .with_attribute("definitionName", item_definition.name)
                         .with_attribute("count", CONSUME_COUNT);
 
+                    // Trait items must target a strike team they can change
+                    if let Some(action) = item_definition.attributes.strike_team_trait_action {
+                        let team_id =
+                            trait_item_target(db, &user, &target.target_id, action).await?;
+                        event = event.with_attribute("targetId", team_id);
+                    }
+
+                    // Boosters can't replace a boost that hasn't been used yet
+                    if item_effects(item_definition, ItemEventKind::Consume)
+                        .iter()
+                        .any(|effect| matches!(effect, ItemEffect::XpBoost { .. }))
+                    {
+                        if boost_active {
+                            return Err(InventoryError::XpBoostActive.into());
+                        }
+                        boost_active = true;
+                    }
+
+                    // Respec and XP items must target one of the user characters
+                    if item_effects(item_definition, ItemEventKind::Consume)
+                        .iter()
+                        .any(|effect| {
+                            matches!(effect, ItemEffect::Respec | ItemEffect::GrantXp { .. })
+                        })
+                    {
+                        let character_id = respec_target(db, &user, &target.target_id).await?;
+                        event = event.with_attribute("targetId", character_id);
+                    }
+
                     events.push(event);
                 }
 
@@ -174,3 +447,69 @@ pub async fn consume_inventory(
 
     Ok(Json(result))
 }
+
+/// POST /inventory/consume/bulk
+///
+/// Opens multiple copies of an owned pack in a single request, responds
+/// with the combined result along with the rewards from each pack
+pub async fn bulk_open_packs(
+    Auth(user): Auth,
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(user_locks): Extension<Arc<UserLocks>>,
+    JsonDump(req): JsonDump<BulkOpenRequest>,
+) -> HttpResult<BulkOpenResponse> {
+    debug!("Bulk open packs: {:?}", req);
+
+    if req.count == 0 || req.count > Config::get().inventory.max_bulk_open {
+        return Err(InventoryError::InvalidOpenCount.into());
+    }
+
+    // Hold the user lock so double clicked opens can't duplicate rewards
+    let _guard = user_locks.lock(user.id).await;
+
+    let response = db
+        .transaction(|db| {
+            Box::pin(async move {
+                let item_definitions = Items::get();
+
+                let item = InventoryItem::get(db, &user, req.item_id)
+                    .await?
+                    .ok_or(InventoryError::NotOwned)?;
+                let definition = item_definitions
+                    .by_name(&item.definition_name)
+                    .ok_or(InventoryError::MissingDefinition)?;
+
+                if !definition.category.base_eq(&BaseCategory::ItemPack) {
+                    return Err(InventoryError::NotPack.into());
+                }
+
+                // Consume all the packs upfront
+                consume_item(db, &user, req.item_id, req.count, item_definitions).await?;
+
+                let mut result = ActivityResult::default();
+                let mut packs = Vec::with_capacity(req.count as usize);
+
+                for _ in 0..req.count {
+                    let event = ActivityEvent::new(ActivityName::ItemConsumed)
+                        .with_attribute("category", definition.category.to_string())
+                        .with_attribute("definitionName", definition.name)
+                        .with_attribute("count", 1);
+
+                    let mut pack_result = ActivityResult::default();
+                    ActivityService::process_event_inner(db, &user, event, &mut pack_result)
+                        .await?;
+
+                    packs.push(PackOpenBreakdown::new(&pack_result));
+
+                    result.merge(&user, pack_result);
+                }
+
+                result.currencies = Currency::all(db, &user).await?;
+
+                Ok::<_, DynHttpError>(BulkOpenResponse { result, packs })
+            })
+        })
+        .await?;
+
+    Ok(Json(response))
+}
@@ -1,14 +1,32 @@
 use crate::{
+    database::entity::LeaderboardStats,
     definitions::i18n::{I18n, I18nName, Localized},
-    http::models::leaderboard::{
-        LeaderboardCategory, LeaderboardIdent, LeaderboardResponse, LeaderboardRow,
-        LeaderboardsResponse,
+    http::{
+        middleware::user::Auth,
+        models::{
+            leaderboard::{
+                LeaderboardCategory, LeaderboardError, LeaderboardIdent, LeaderboardParams,
+                LeaderboardResponse, LeaderboardRow, LeaderboardStatsQuery,
+                LeaderboardStatsResponse, LeaderboardStatsRow, LeaderboardsResponse,
+            },
+            HttpResult,
+        },
     },
+    services::leaderboard::{LeaderboardStat, APEX_CATEGORY, CHALLENGE_CATEGORY},
+};
+use axum::{
+    extract::{Path, Query},
+    Extension, Json,
 };
-use axum::{extract::Path, Json};
+use sea_orm::DatabaseConnection;
 use serde_json::Map;
 use uuid::{uuid, Uuid};
 
+/// Default number of rows in a leaderboard page
+const DEFAULT_ROWS_COUNT: u64 = 20;
+/// Maximum number of rows in a leaderboard page
+const MAX_ROWS_COUNT: u64 = 100;
+
 /// GET /leaderboards
 ///
 /// Retrieves a list of the leaderboard categories
@@ -17,7 +35,7 @@ pub async fn get_leaderboards() -> Json<LeaderboardsResponse> {
 
     let mut list = vec![
         LeaderboardCategory {
-            name: uuid!("2e9181f0-bd7b-e489-1a64-91598df0780c"),
+            name: APEX_CATEGORY,
 
             stat_collection_name: uuid!("3e02497e-9c2b-6ed6-0dfb-028c134326dc"),
             stat_owner_name: "personaId".to_string(),
@@ -30,7 +48,7 @@ pub async fn get_leaderboards() -> Json<LeaderboardsResponse> {
             i18n_description: None,
         },
         LeaderboardCategory {
-            name: uuid!("aff90bf0-a9fd-0a5e-679d-60fc8691ff45"),
+            name: CHALLENGE_CATEGORY,
             stat_collection_name: uuid!("b3b3061a-6056-fb0a-7edd-7a09b8c90650"),
             stat_owner_name: "personaId".to_string(),
             ranked_stat_name: "challengeRating".to_string(),
@@ -54,18 +72,83 @@ pub async fn get_leaderboards() -> Json<LeaderboardsResponse> {
 
 /// GET /leaderboards/:id
 ///
-/// Retrieves the contents of a specific leaderboard
-pub async fn get_leaderboard(Path(name): Path<Uuid>) -> Json<LeaderboardResponse> {
-    Json(LeaderboardResponse {
+/// Retrieves the contents of a specific leaderboard, centered leaderboards
+/// contain the rows surrounding the position of the requesting user
+pub async fn get_leaderboard(
+    Path(name): Path<Uuid>,
+    Auth(user): Auth,
+    Query(params): Query<LeaderboardParams>,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<LeaderboardResponse> {
+    let stat = LeaderboardStat::from_category(&name).ok_or(LeaderboardError::UnknownLeaderboard)?;
+    let column = stat.column();
+
+    let count = match params.count as u64 {
+        0 => DEFAULT_ROWS_COUNT,
+        count => count.min(MAX_ROWS_COUNT),
+    };
+
+    let mut offset = params.offset as u64;
+    if params.centered {
+        if let Some(stats) = LeaderboardStats::get(&db, user.id).await? {
+            let position = stats.position(&db, column).await?;
+            offset = position.saturating_sub(count / 2);
+        }
+    }
+
+    let rows = LeaderboardStats::page(&db, column, offset, count)
+        .await?
+        .into_iter()
+        .zip(offset + 1..)
+        .filter_map(|((stats, user), rank)| {
+            let user = user?;
+            Some(LeaderboardRow {
+                rank,
+                name: user.username,
+                owner_id: user.id,
+                stat_value: stat.value(&stats),
+            })
+        })
+        .collect();
+
+    Ok(Json(LeaderboardResponse {
         identifier: LeaderboardIdent {
             name,
             property_value_map: Map::new(),
         },
-        rows: vec![LeaderboardRow {
-            rank: 1,
-            name: "Jacobtread".to_string(),
-            owner_id: 1,
-            stat_value: 512.0,
-        }],
-    })
+        rows,
+    }))
+}
+
+/// GET /leaderboards/stats
+///
+/// Retrieves a page of the leaderboard stats for all users sorted
+/// by the requested stat
+pub async fn get_leaderboard_stats(
+    Auth(_): Auth,
+    Query(query): Query<LeaderboardStatsQuery>,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<LeaderboardStatsResponse> {
+    let count = query.count.min(MAX_ROWS_COUNT);
+    let total_count = LeaderboardStats::total(&db).await?;
+
+    let rows = LeaderboardStats::page(&db, query.sort.column(), query.offset, count)
+        .await?
+        .into_iter()
+        .zip(query.offset + 1..)
+        .filter_map(|((stats, user), rank)| {
+            let user = user?;
+            Some(LeaderboardStatsRow {
+                rank,
+                user_id: user.id,
+                name: user.username,
+                n7_rating: stats.n7_rating,
+                apex_rating: stats.apex_rating,
+                challenge_points: stats.challenge_points,
+                match_count: stats.match_count,
+            })
+        })
+        .collect();
+
+    Ok(Json(LeaderboardStatsResponse { total_count, rows }))
 }
@@ -0,0 +1,41 @@
+//! Routes for users and administrators reviewing the login history
+//! of accounts
+
+use crate::{
+    database::entity::{users::UserId, LoginEvent},
+    http::{
+        middleware::user::{AdminAuth, Auth},
+        models::{HttpResult, VecWithCount},
+    },
+};
+use axum::{extract::Path, Extension, Json};
+use sea_orm::DatabaseConnection;
+
+/// The number of recent logins to include in the login history
+const RECENT_LOGINS_COUNT: u64 = 20;
+
+/// GET /api/server/logins
+///
+/// Used by users to review the most recent logins to their account
+pub async fn get_logins(
+    Auth(user): Auth,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<VecWithCount<LoginEvent>> {
+    let logins = LoginEvent::recent(&db, user.id, RECENT_LOGINS_COUNT).await?;
+
+    Ok(Json(VecWithCount::new(logins)))
+}
+
+/// GET /api/server/users/:id/logins
+///
+/// Used by administrators to review the most recent logins to
+/// the account of another user
+pub async fn get_user_logins(
+    AdminAuth(_): AdminAuth,
+    Path(user_id): Path<UserId>,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<VecWithCount<LoginEvent>> {
+    let logins = LoginEvent::recent(&db, user_id, RECENT_LOGINS_COUNT).await?;
+
+    Ok(Json(VecWithCount::new(logins)))
+}
@@ -0,0 +1,67 @@
+//! Routes for administrators managing server maintenance
+
+use crate::{
+    config::Config,
+    http::{
+        middleware::user::{AdminAuth, OperatorAuth},
+        models::{
+            maintenance::{ReadOnlyResponse, SetReadOnlyRequest},
+            HttpResult,
+        },
+    },
+    services::maintenance::{run_maintenance, MaintenanceReport, ReadOnlyMode},
+};
+use axum::{Extension, Json};
+use log::info;
+use sea_orm::DatabaseConnection;
+use std::sync::Arc;
+
+/// POST /api/server/maintenance
+///
+/// Used by administrators to manually run the database maintenance
+/// outside of the regular schedule
+pub async fn run(
+    OperatorAuth(_): OperatorAuth,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<MaintenanceReport> {
+    let report = run_maintenance(&db, &Config::get().maintenance).await?;
+
+    Ok(Json(report))
+}
+
+/// GET /api/server/maintenance/readOnly
+///
+/// Used by administrators to check whether the read-only mode is enabled
+pub async fn get_read_only(
+    AdminAuth(_): AdminAuth,
+    Extension(read_only): Extension<Arc<ReadOnlyMode>>,
+) -> Json<ReadOnlyResponse> {
+    Json(read_only_response(&read_only))
+}
+
+/// PUT /api/server/maintenance/readOnly
+///
+/// Used by administrators to enable or disable the read-only mode, while
+/// enabled purchases, consumes and mission finishes are rejected so that
+/// maintenance can be performed on the database. Changes are not persisted
+pub async fn set_read_only(
+    OperatorAuth(user): OperatorAuth,
+    Extension(read_only): Extension<Arc<ReadOnlyMode>>,
+    Json(req): Json<SetReadOnlyRequest>,
+) -> Json<ReadOnlyResponse> {
+    read_only.set(req.enabled);
+    info!(
+        "Read-only mode {} by {}",
+        if req.enabled { "enabled" } else { "disabled" },
+        user.username
+    );
+
+    Json(read_only_response(&read_only))
+}
+
+fn read_only_response(read_only: &ReadOnlyMode) -> ReadOnlyResponse {
+    ReadOnlyResponse {
+        enabled: read_only.is_enabled(),
+        retry_after_secs: Config::get().maintenance.read_only_retry_secs,
+    }
+}
@@ -0,0 +1,89 @@
+//! Routes for managing the region and latency preferences used
+//! when placing users into games
+
+use crate::{
+    database::entity::{MatchmakingPreferences, User},
+    http::{
+        middleware::user::Auth,
+        models::{
+            matchmaking::{MatchmakingError, PreferencesResponse, UpdatePreferencesRequest},
+            HttpResult,
+        },
+    },
+    services::sessions::Sessions,
+};
+use axum::{Extension, Json};
+use sea_orm::DatabaseConnection;
+use std::sync::Arc;
+
+/// Range of allowed max latency values in milliseconds
+const LATENCY_RANGE: std::ops::RangeInclusive<u32> = 1..=5000;
+/// Max length of a preferred region name
+const MAX_REGION_LENGTH: usize = 64;
+
+/// GET /user/matchmaking/preferences
+///
+/// Obtains the matchmaking preferences of the current user
+pub async fn get_preferences(
+    Auth(user): Auth,
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(sessions): Extension<Arc<Sessions>>,
+) -> HttpResult<PreferencesResponse> {
+    let preferences = MatchmakingPreferences::get(&db, &user).await?;
+    Ok(Json(preferences_response(&user, &sessions, preferences)))
+}
+
+/// PUT /user/matchmaking/preferences
+///
+/// Replaces the matchmaking preferences of the current user, applied
+/// the next time the user starts matchmaking
+pub async fn update_preferences(
+    Auth(user): Auth,
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(sessions): Extension<Arc<Sessions>>,
+    Json(req): Json<UpdatePreferencesRequest>,
+) -> HttpResult<PreferencesResponse> {
+    if req
+        .max_latency
+        .is_some_and(|latency| !LATENCY_RANGE.contains(&latency))
+    {
+        return Err(MatchmakingError::InvalidLatency.into());
+    }
+
+    if req
+        .preferred_region
+        .as_ref()
+        .is_some_and(|region| region.is_empty() || region.len() > MAX_REGION_LENGTH)
+    {
+        return Err(MatchmakingError::InvalidRegion.into());
+    }
+
+    MatchmakingPreferences::set(&db, &user, req.max_latency, req.preferred_region).await?;
+    let preferences = MatchmakingPreferences::get(&db, &user).await?;
+
+    Ok(Json(preferences_response(&user, &sessions, preferences)))
+}
+
+/// Creates the preferences response including the latencies from
+/// the users current game session
+fn preferences_response(
+    user: &User,
+    sessions: &Sessions,
+    preferences: Option<MatchmakingPreferences>,
+) -> PreferencesResponse {
+    let ping_site_latency = sessions
+        .lookup_session(user.id)
+        .map(|session| session.net().ping_site_latency.clone())
+        .unwrap_or_default();
+
+    let (max_latency, preferred_region) = match preferences {
+        Some(preferences) => (preferences.max_latency, preferences.preferred_region),
+        None => (None, None),
+    };
+
+    PreferencesResponse {
+        max_latency,
+        preferred_region,
+        ping_site_latency,
+    }
+}
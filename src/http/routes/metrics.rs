@@ -0,0 +1,84 @@
+//! Routes for administrators diagnosing server performance
+
+use crate::{
+    database::{
+        entity::{GameRecord, MatchmakingRecord},
+        metrics::QueryMetrics,
+    },
+    http::{
+        middleware::user::AdminAuth,
+        models::{
+            metrics::{GameStatsQuery, GameStatsResponse},
+            HttpResult,
+        },
+    },
+    services::{
+        diagnostics::{self, MemoryReport},
+        game_manager::GameManager,
+        game_stats::{GameSummary, MatchmakingSummary},
+        random::{RandomService, RandomStreamMetrics},
+        replay_cache::ReplayCache,
+        sessions::Sessions,
+    },
+    utils::clock,
+};
+use axum::{extract::Query, Extension, Json};
+use chrono::Duration;
+use sea_orm::DatabaseConnection;
+use std::sync::Arc;
+
+/// The maximum number of days of game statistics that can be summarized
+const MAX_GAME_STATS_DAYS: u32 = 90;
+
+/// GET /api/server/metrics/database
+///
+/// Used by administrators to obtain the database query counters,
+/// includes the number of queries that were slow
+pub async fn get_database_metrics(AdminAuth(_): AdminAuth) -> HttpResult<QueryMetrics> {
+    Ok(Json(QueryMetrics::get()))
+}
+
+/// GET /api/server/metrics/games
+///
+/// Used by administrators to obtain a summary of the recorded matchmaking
+/// and game statistics for tuning the matchmaking parameters
+pub async fn get_game_metrics(
+    AdminAuth(_): AdminAuth,
+    Query(query): Query<GameStatsQuery>,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<GameStatsResponse> {
+    let days = query.days.min(MAX_GAME_STATS_DAYS);
+    let since = clock::now() - Duration::days(days as i64);
+
+    let matchmaking = MatchmakingRecord::since(&db, since).await?;
+    let games = GameRecord::since(&db, since).await?;
+
+    Ok(Json(GameStatsResponse {
+        since,
+        matchmaking: MatchmakingSummary::from_records(&matchmaking),
+        games: GameSummary::from_records(&games),
+    }))
+}
+
+/// GET /api/server/metrics/random
+///
+/// Used by administrators to obtain the number of random generators
+/// handed out for each generation purpose
+pub async fn get_random_metrics(AdminAuth(_): AdminAuth) -> HttpResult<Vec<RandomStreamMetrics>> {
+    Ok(Json(RandomService::get().metrics()))
+}
+
+/// GET /api/server/metrics/memory
+///
+/// Used by administrators to obtain the approximate memory used by each
+/// subsystem along with the allocation counters and server uptime
+pub async fn get_memory_metrics(
+    AdminAuth(_): AdminAuth,
+    Extension(sessions): Extension<Arc<Sessions>>,
+    Extension(game_manager): Extension<Arc<GameManager>>,
+    Extension(replay_cache): Extension<Arc<ReplayCache>>,
+) -> HttpResult<MemoryReport> {
+    Ok(Json(
+        diagnostics::memory_report(&sessions, &game_manager, &replay_cache).await,
+    ))
+}
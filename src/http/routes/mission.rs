@@ -1,7 +1,11 @@
 use crate::{
-    database::entity::{strike_team_mission_progress::UserMissionState, StrikeTeamMission},
+    config::Config,
+    database::entity::{
+        strike_team_mission::MissionAccessibility, strike_team_mission_progress::UserMissionState,
+        StrikeTeamMission,
+    },
     http::{
-        middleware::{user::Auth, JsonDump},
+        middleware::{signed_json::SignedJson, user::Auth, JsonDump},
         models::{
             errors::{DynHttpError, HttpResult},
             mission::*,
@@ -9,15 +13,22 @@ use crate::{
             VecWithCount,
         },
     },
-    services::game_manager::GameManager,
+    services::{
+        game::Game, game_manager::GameManager, mission_results::MissionResultWorker,
+        strike_team_resolve,
+    },
+    utils::clock,
 };
 use axum::{extract::Path, Extension, Json};
-use chrono::Utc;
 use hyper::StatusCode;
 use log::debug;
 use sea_orm::DatabaseConnection;
 use serde_json::Value;
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
+use tokio::time::timeout;
+
+/// Maximum time to wait for the results of a finished mission to be processed
+const PROCESSING_TIMEOUT: Duration = Duration::from_secs(20);
 
 /// GET /mission/current
 ///
@@ -26,24 +37,30 @@ pub async fn current_missions(
     Auth(user): Auth,
     Extension(db): Extension<DatabaseConnection>,
 ) -> HttpResult<VecWithCount<StrikeTeamMissionWithState>> {
-    let current_time = Utc::now().timestamp();
+    let current_time = clock::timestamp();
     let missions = StrikeTeamMission::visible_missions(&db, &user, current_time).await?;
+    let config = &Config::get().strike_teams;
 
     let missions: Vec<StrikeTeamMissionWithState> = missions
         .into_iter()
-        .map(|(mission, progress)| match progress {
-            Some(value) => StrikeTeamMissionWithState {
-                mission,
-                user_mission_state: value.user_mission_state,
-                seen: value.seen,
-                completed: value.completed,
-            },
-            None => StrikeTeamMissionWithState {
-                mission,
-                user_mission_state: UserMissionState::Available,
-                seen: false,
-                completed: false,
-            },
+        .map(|(mission, progress)| {
+            let reward_preview = strike_team_resolve::reward_preview(config, &mission);
+            match progress {
+                Some(value) => StrikeTeamMissionWithState {
+                    mission,
+                    user_mission_state: value.user_mission_state,
+                    seen: value.seen,
+                    completed: value.completed,
+                    reward_preview,
+                },
+                None => StrikeTeamMissionWithState {
+                    mission,
+                    user_mission_state: UserMissionState::Available,
+                    seen: false,
+                    completed: false,
+                    reward_preview,
+                },
+            }
         })
         .collect();
 
@@ -57,10 +74,10 @@ pub async fn current_missions(
 /// Obtains the details about a specific mission
 ///
 /// Called at end of game to obtain information about the
-/// game and rewards etc
+/// game and rewards etc, waits for the results to finish processing
+/// if they haven't already
 pub async fn get_mission(
     Path(mission_id): Path<u32>,
-    Extension(db): Extension<DatabaseConnection>,
     Extension(game_manager): Extension<Arc<GameManager>>,
 ) -> HttpResult<MissionDetails> {
     debug!("Requested mission details: {}", mission_id);
@@ -70,21 +87,45 @@ pub async fn get_mission(
         .await
         .ok_or(MissionError::UnknownGame)?;
 
-    let game = &mut *game.write().await;
+    // Start listening before checking so the notification can't be missed
+    let processed = game.read().await.mission_processed.clone();
+    let notified = processed.notified();
+    tokio::pin!(notified);
+    notified.as_mut().enable();
 
-    let mission_data = game
-        .get_mission_details(&db)
+    {
+        let game = &*game.read().await;
+        if let Some(details) = &game.processed_data {
+            return Ok(Json(details.clone()));
+        }
+
+        if game.mission_data.is_none() {
+            return Err(MissionError::MissingMissionData.into());
+        }
+    }
+
+    timeout(PROCESSING_TIMEOUT, notified)
+        .await
+        .map_err(|_| MissionError::StillProcessing)?;
+
+    let details = game
+        .read()
         .await
+        .processed_data
+        .clone()
         .ok_or(MissionError::MissingMissionData)?;
 
-    Ok(Json(mission_data))
+    Ok(Json(details))
 }
 
 /// POST /user/mission/:id/start
 ///
-/// Starts a mission
+/// Starts a mission, the mission is reserved for the game so that
+/// only started missions can be finished
 pub async fn start_mission(
+    Auth(user): Auth,
     Path(mission_id): Path<u32>,
+    Extension(db): Extension<DatabaseConnection>,
     Extension(game_manager): Extension<Arc<GameManager>>,
     JsonDump(req): JsonDump<StartMissionRequest>,
 ) -> HttpResult<StartMissionResponse> {
@@ -95,8 +136,48 @@ pub async fn start_mission(
         .await
         .ok_or(MissionError::UnknownGame)?;
 
+    // Ensure APEX missions can currently be played, checked for every
+    // player starting the mission not just the host
+    if let Some(apex_mission) = req.mission {
+        let mission = StrikeTeamMission::by_id(&db, apex_mission)
+            .await?
+            .ok_or(MissionError::MissionUnavailable)?;
+
+        let now = clock::timestamp();
+        if now < mission.start_seconds || now >= mission.end_seconds {
+            return Err(MissionError::MissionUnavailable.into());
+        }
+
+        if mission.accessibility == MissionAccessibility::SinglePlayer {
+            return Err(MissionError::MissionNotAccessible.into());
+        }
+    }
+
     {
         let game = &mut *game.write().await;
+
+        if !game.has_player(user.id) {
+            return Err(MissionError::NotInGame.into());
+        }
+
+        // Only the host starts the mission for the game, the ready check is
+        // reset once the game is moved in game over Blaze
+        if game.is_host(user.id) {
+            if game.state != Game::IN_GAME_STATE && !game.can_start_mission() {
+                return Err(MissionError::PlayersNotReady.into());
+            }
+        } else if let Some(reservation) = &game.mission_reservation {
+            // Other players join the mission the game is already playing
+            if reservation.mission != req.mission {
+                return Err(MissionError::MissionUnavailable.into());
+            }
+
+            return Ok(Json(StartMissionResponse {
+                match_id: mission_id.to_string(),
+            }));
+        }
+
+        game.reserve_mission(user.id, req.mission);
         game.set_modifiers(req.modifiers);
     }
 
@@ -108,12 +189,19 @@ pub async fn start_mission(
 
 /// POST /user/mission/:id/finish
 ///
-/// Submits the details of a mission that has been finished
+/// Submits the details of a mission that has been finished, rejected
+/// unless the mission was started by a player in the game. Each player
+/// in the game can finish the started mission once
 pub async fn finish_mission(
+    Auth(user): Auth,
     Path(mission_id): Path<u32>,
     Extension(game_manager): Extension<Arc<GameManager>>,
-    JsonDump(req): JsonDump<CompleteMissionData>,
+    Extension(mission_results): Extension<Arc<MissionResultWorker>>,
+    SignedJson(req): SignedJson<CompleteMissionData>,
 ) -> Result<StatusCode, DynHttpError> {
+    /// Allowance for differences between the client and server clocks
+    const DURATION_LEEWAY_SECONDS: i64 = 60;
+
     debug!("Mission finished: {} {:#?}", mission_id, req);
 
     let game = game_manager
@@ -121,10 +209,41 @@ pub async fn finish_mission(
         .await
         .ok_or(MissionError::UnknownGame)?;
 
-    {
+    let job = {
         let game = &mut *game.write().await;
+
+        if !game.has_player(user.id) {
+            return Err(MissionError::NotInGame.into());
+        }
+
+        // Each player can only finish the started mission once
+        let reservation = game
+            .mission_reservation
+            .as_mut()
+            .filter(|reservation| !reservation.finished.contains(&user.id))
+            .ok_or(MissionError::NotStarted)?;
+
+        let elapsed = (clock::now() - reservation.started).num_seconds();
+        if req.duration_sec as i64 > elapsed + DURATION_LEEWAY_SECONDS {
+            return Err(MissionError::InvalidDuration.into());
+        }
+
+        reservation.finished.push(user.id);
+
+        // The reservation is cleared once every player has finished
+        if game
+            .players
+            .iter()
+            .all(|player| reservation.finished.contains(&player.user.id))
+        {
+            game.mission_reservation = None;
+        }
+
         game.set_complete_mission(req)
-    }
+    };
+
+    // Rewards are processed after responding to keep finishing fast
+    mission_results.queue(job);
 
     Ok(StatusCode::NO_CONTENT)
 }
@@ -1,32 +1,59 @@
+use super::middleware::{
+    crash_context::crash_context, language::request_language, read_only::reject_read_only,
+};
 use axum::{
     error_handling::HandleErrorLayer,
+    extract::DefaultBodyLimit,
+    middleware,
     response::{IntoResponse, Response},
-    routing::{any, get, post, put},
+    routing::{any, delete, get, post, put},
     BoxError, Router,
 };
-use hyper::StatusCode;
+use hyper::{Method, StatusCode};
 use tower::ServiceBuilder;
 use tower_http::{
+    cors::{Any, CorsLayer},
     decompression::RequestDecompressionLayer,
     trace::{DefaultMakeSpan, DefaultOnRequest, DefaultOnResponse, TraceLayer},
 };
 
 mod activity;
+mod admin;
+mod assets;
 mod auth;
 mod challenge;
 mod character;
 mod client;
+mod companion;
 mod configuration;
+mod crashes;
+mod events;
+mod feature_flags;
+mod friends;
+mod games;
 mod inventory;
 mod leaderboard;
+mod logins;
+mod maintenance;
+mod matchmaking;
+mod metrics;
 mod mission;
+mod notifications;
+mod pack_experiments;
 mod presence;
+mod public;
 mod qos;
+mod reports;
 mod store;
 mod strike_teams;
 mod telemetry;
+mod titles;
 mod user_match;
 
+/// Maximum size of inventory import files, imports are used for
+/// migrating whole servers so are larger than other requests
+const IMPORT_BODY_LIMIT: usize = 32 * 1024 * 1024;
+
 pub fn router() -> Router {
     Router::new()
         .nest(
@@ -35,9 +62,118 @@ pub fn router() -> Router {
                 .route("/", get(client::details))
                 .route("/login", post(client::login))
                 .route("/create", post(client::create))
-                .route("/upgrade", get(client::upgrade)),
+                .route("/password", put(client::change_password))
+                .route("/link", post(client::link_account))
+                .route("/pocketRelay/login", post(client::pocket_relay_login))
+                .route("/pocketRelay/link", post(client::link_pocket_relay))
+                .route("/upgrade", get(client::upgrade))
+                .route(
+                    "/reports",
+                    get(reports::get_reports).post(reports::create_report),
+                )
+                .route("/logins", get(logins::get_logins))
+                .route("/users/:id/logins", get(logins::get_user_logins))
+                .route("/maintenance", post(maintenance::run))
+                .route(
+                    "/maintenance/readOnly",
+                    get(maintenance::get_read_only).put(maintenance::set_read_only),
+                )
+                .route("/crashes", get(crashes::get_crashes))
+                .route("/crashes/:id", get(crashes::get_crash))
+                .route("/metrics/database", get(metrics::get_database_metrics))
+                .route("/metrics/games", get(metrics::get_game_metrics))
+                .route("/metrics/memory", get(metrics::get_memory_metrics))
+                .route("/metrics/random", get(metrics::get_random_metrics))
+                .route("/events", get(events::get_events))
+                .route("/stats", get(telemetry::get_stats))
+                .route(
+                    "/featureFlags",
+                    get(feature_flags::get_flags).put(feature_flags::set_flag),
+                )
+                .route("/featureFlags/evaluated", get(feature_flags::get_evaluated))
+                .route("/featureFlags/:name", delete(feature_flags::delete_flag))
+                .route("/packExperiments", get(pack_experiments::get_report))
+                .route("/games", get(games::get_games))
+                .route("/games/:id/timeline", get(games::get_timeline))
+                .route(
+                    "/games/:id/timeline/visibility",
+                    put(games::set_timeline_visibility),
+                )
+                .nest(
+                    "/admin",
+                    Router::new()
+                        .route("/users", get(admin::get_users))
+                        .route(
+                            "/users/:id",
+                            get(admin::get_user).delete(admin::delete_user),
+                        )
+                        .route(
+                            "/users/:id/inventory",
+                            get(admin::get_inventory).post(admin::grant_item),
+                        )
+                        .route(
+                            "/users/:id/inventory/deleted",
+                            get(admin::get_deleted_inventory),
+                        )
+                        .route("/users/:id/characters", get(admin::get_characters))
+                        .route(
+                            "/users/:id/currencies",
+                            get(admin::get_currencies).post(admin::grant_currency),
+                        )
+                        .route("/users/:id/challenges", delete(admin::reset_challenges))
+                        .route("/users/:id/permissions", get(admin::get_permissions))
+                        .route(
+                            "/users/:id/ban",
+                            put(admin::ban_user).delete(admin::unban_user),
+                        )
+                        .route("/users/:id/notifications", post(admin::send_notification))
+                        .route(
+                            "/users/:id/inventory/export",
+                            get(admin::export_user_inventory),
+                        )
+                        .route("/inventory/export", get(admin::export_inventories))
+                        .route(
+                            "/inventory/import",
+                            post(admin::import_inventories)
+                                .layer(DefaultBodyLimit::max(IMPORT_BODY_LIMIT)),
+                        )
+                        .route("/inventory/import/:id", get(admin::get_import))
+                        .route("/bans", get(admin::get_bans))
+                        .route(
+                            "/users/:id/permissions/:permission",
+                            put(admin::grant_permission).delete(admin::revoke_permission),
+                        )
+                        .route("/permissions/audit", get(admin::get_permission_audit)),
+                ),
+        )
+        .nest(
+            "/api/public",
+            Router::new()
+                .route("/stats", get(public::get_stats))
+                .route("/users/:id/profile", get(titles::get_profile))
+                .route("/icons/:name", get(assets::get_icon))
+                // Public routes are embedded on external websites
+                .layer(
+                    CorsLayer::new()
+                        .allow_origin(Any)
+                        .allow_methods([Method::GET]),
+                ),
+        )
+        .nest(
+            "/api/companion",
+            Router::new()
+                .route("/teams", get(companion::get_overview))
+                .route("/teams/:id/resolve", post(companion::resolve))
+                .route(
+                    "/teams/:id/equipment/:name",
+                    post(companion::purchase_equipment),
+                )
+                .route("/deploy/:mission_id", post(companion::deploy))
+                .route("/resolveAll", post(companion::resolve_all))
+                .route("/shop", get(companion::get_shop)),
         )
         .route("/auth", post(auth::authenticate))
+        .route("/auth/refresh", post(auth::refresh_token))
         .route("/configuration", get(configuration::get_configuration))
         .nest(
             "/mission",
@@ -53,8 +189,21 @@ pub fn router() -> Router {
                 .route("/missionConfig", get(strike_teams::get_mission_config))
                 .route("/specializations", get(strike_teams::get_specializations))
                 .route("/equipment", get(strike_teams::get_equipment))
+                .route("/flair", get(strike_teams::get_flair))
+                .route("/retired", get(strike_teams::get_retired))
+                .route("/:id/flair", put(strike_teams::equip_flair))
+                .route("/:id/specialization", put(strike_teams::specialize))
+                .route("/:id/history", get(strike_teams::get_history))
+                .route(
+                    "/pendingResolutions",
+                    get(strike_teams::get_pending_resolutions),
+                )
+                .route("/deploy/:mission_id", post(strike_teams::deploy))
                 .route("/:id/mission/resolve", post(strike_teams::resolve_mission))
-                .route("/:id/mission/:id", get(strike_teams::get_mission))
+                .route(
+                    "/:id/mission/:id",
+                    get(strike_teams::get_mission).post(strike_teams::deploy_team),
+                )
                 .route("/:id/retire", post(strike_teams::retire))
                 .route(
                     "/:id/equipment/:name",
@@ -75,6 +224,14 @@ pub fn router() -> Router {
                             "/customization",
                             put(character::update_character_customization),
                         )
+                        .route(
+                            "/customization/history",
+                            get(character::get_customization_history),
+                        )
+                        .route(
+                            "/customization/history/:history_id/revert",
+                            post(character::revert_customization),
+                        )
                         .nest(
                             "/equipment",
                             Router::new()
@@ -85,7 +242,8 @@ pub fn router() -> Router {
                                 )
                                 .route("/history", get(character::get_character_equip_history)),
                         )
-                        .route("/skillTrees", put(character::update_skill_tree)),
+                        .route("/skillTrees", put(character::update_skill_tree))
+                        .route("/skillTrees/preview", post(character::preview_skill_tree)),
                 )
                 .route("/equipment/shared", put(character::update_shared_equip))
                 .route("/unlocked", post(character::character_unlocked))
@@ -98,12 +256,33 @@ pub fn router() -> Router {
                 .route("/catalogs", get(store::get_catalogs))
                 .route("/article", post(store::obtain_article))
                 .route("/article/seen", put(store::update_seen_articles))
+                .route("/purchases", get(store::get_refundable_purchases))
+                .route("/purchases/:id/refund", post(store::refund_purchase))
                 .route("/unclaimed/claimAll", post(store::claim_unclaimed)),
         )
         .nest(
             "/user",
             Router::new()
                 .route("/currencies", get(store::get_currencies))
+                .route("/currencies/convert", post(store::convert_currency))
+                .route("/currencies/history", get(store::get_currency_history))
+                .route("/titles", get(titles::get_titles))
+                .route("/friends", get(friends::get_friends))
+                .route(
+                    "/friends/:id",
+                    put(friends::add_friend).delete(friends::remove_friend),
+                )
+                .route("/notifications", get(notifications::get_notifications))
+                .route("/notifications/read", put(notifications::mark_all_read))
+                .route("/notifications/:id/read", put(notifications::mark_read))
+                .route(
+                    "/titles/active",
+                    put(titles::equip_title).delete(titles::unequip_title),
+                )
+                .route(
+                    "/matchmaking/preferences",
+                    get(matchmaking::get_preferences).put(matchmaking::update_preferences),
+                )
                 .nest(
                     "/match",
                     Router::new()
@@ -139,8 +318,13 @@ pub fn router() -> Router {
             Router::new()
                 .route("/", get(inventory::get_inventory))
                 .route("/definitions", get(inventory::get_definitions))
+                .route("/search", get(inventory::search_inventory))
+                .route("/mods/compatibility", get(inventory::get_mod_compatibility))
+                .route("/packs/openings", get(inventory::get_pack_openings))
+                .route("/packs/:name/preview", get(inventory::get_pack_preview))
                 .route("/seen", put(inventory::update_inventory_seen))
-                .route("/consume", post(inventory::consume_inventory)),
+                .route("/consume", post(inventory::consume_inventory))
+                .route("/consume/bulk", post(inventory::bulk_open_packs)),
         )
         .route("//em/v3/*path", any(ok))
         .route("/presence/session", put(presence::update_session))
@@ -149,6 +333,7 @@ pub fn router() -> Router {
             "/leaderboards",
             Router::new()
                 .route("/", get(leaderboard::get_leaderboards))
+                .route("/stats", get(leaderboard::get_leaderboard_stats))
                 .route("/:id", get(leaderboard::get_leaderboard)),
         )
         .route("/wv/playthrough/0", put(activity::update_playthrough))
@@ -166,6 +351,9 @@ pub fn router() -> Router {
                 }))
                 .layer(RequestDecompressionLayer::new()),
         )
+        .layer(middleware::from_fn(reject_read_only))
+        .layer(middleware::from_fn(request_language))
+        .layer(middleware::from_fn(crash_context))
         .layer(
             TraceLayer::new_for_http()
                 .make_span_with(DefaultMakeSpan::new().include_headers(true))
@@ -0,0 +1,54 @@
+use crate::{
+    database::entity::{notifications::NotificationId, Notification},
+    http::{
+        middleware::user::Auth,
+        models::{
+            notifications::{NotificationsError, NotificationsQuery, NotificationsResponse},
+            DynHttpError, HttpResult,
+        },
+    },
+};
+use axum::{
+    extract::{Path, Query},
+    Extension, Json,
+};
+use hyper::StatusCode;
+use sea_orm::DatabaseConnection;
+
+/// GET /user/notifications
+///
+/// Lists the most recent notifications of the user
+pub async fn get_notifications(
+    Auth(user): Auth,
+    Query(query): Query<NotificationsQuery>,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<NotificationsResponse> {
+    let list = Notification::recent(&db, user.id, query.unread).await?;
+    Ok(Json(NotificationsResponse { list }))
+}
+
+/// PUT /user/notifications/:id/read
+///
+/// Marks a notification as read
+pub async fn mark_read(
+    Auth(user): Auth,
+    Path(notification_id): Path<NotificationId>,
+    Extension(db): Extension<DatabaseConnection>,
+) -> Result<StatusCode, DynHttpError> {
+    if !Notification::set_read(&db, user.id, notification_id).await? {
+        return Err(NotificationsError::UnknownNotification.into());
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// PUT /user/notifications/read
+///
+/// Marks all the notifications of the user as read
+pub async fn mark_all_read(
+    Auth(user): Auth,
+    Extension(db): Extension<DatabaseConnection>,
+) -> Result<StatusCode, DynHttpError> {
+    Notification::set_all_read(&db, user.id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
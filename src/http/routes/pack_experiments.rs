@@ -0,0 +1,20 @@
+//! Routes for reporting on the drop-rate experiments
+
+use crate::{
+    http::{middleware::user::AdminAuth, models::HttpResult},
+    services::pack_experiments::{self, PackExperimentReport},
+};
+use axum::{Extension, Json};
+use sea_orm::DatabaseConnection;
+
+/// GET /api/server/packExperiments
+///
+/// Used by administrators to compare the pack outcomes of the control
+/// and experiment groups for each configured drop-rate experiment
+pub async fn get_report(
+    AdminAuth(_): AdminAuth,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<Vec<PackExperimentReport>> {
+    let reports = pack_experiments::report(&db).await?;
+    Ok(Json(reports))
+}
@@ -1,7 +1,23 @@
-use axum::response::{IntoResponse, Response};
+use crate::{http::middleware::user::Auth, services::sessions::Sessions};
+use axum::{
+    response::{IntoResponse, Response},
+    Extension,
+};
 use hyper::StatusCode;
+use std::sync::Arc;
 
 /// PUT /presence/session
-pub async fn update_session() -> Response {
+///
+/// Called by the client when its presence changes, the current
+/// session data is published to the friends of the user
+pub async fn update_session(
+    auth: Option<Auth>,
+    Extension(sessions): Extension<Arc<Sessions>>,
+) -> Response {
+    let session = auth.and_then(|Auth(user)| sessions.lookup_session(user.id));
+    if let Some(session) = session {
+        session.publish_presence();
+    }
+
     StatusCode::NO_CONTENT.into_response()
 }
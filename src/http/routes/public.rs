@@ -0,0 +1,23 @@
+use crate::{
+    http::models::errors::HttpResult,
+    services::{
+        public_stats::{PublicStats, PublicStatsService},
+        sessions::Sessions,
+    },
+};
+use axum::{Extension, Json};
+use sea_orm::DatabaseConnection;
+use std::sync::Arc;
+
+/// GET /api/public/stats
+///
+/// Obtains anonymized aggregate statistics about the server, intended
+/// to be embedded on community server websites
+pub async fn get_stats(
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(sessions): Extension<Arc<Sessions>>,
+    Extension(stats): Extension<Arc<PublicStatsService>>,
+) -> HttpResult<PublicStats> {
+    let stats = stats.get(&db, &sessions).await?;
+    Ok(Json(stats))
+}
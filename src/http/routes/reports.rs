@@ -0,0 +1,130 @@
+//! Routes for players submitting reports and administrators
+//! reviewing the submitted reports
+
+use crate::{
+    database::entity::{
+        reports::{ReportContext, ReportGameContext, ReportRecentItem, ReportSessionContext},
+        InventoryItem, Report, User,
+    },
+    http::{
+        middleware::{
+            json_validated::JsonValidated,
+            user::{Auth, ModeratorAuth},
+        },
+        models::{
+            reports::{CreateReportRequest, ReportsQuery},
+            HttpResult, VecWithCount,
+        },
+    },
+    services::{game::GameID, game_manager::GameManager, sessions::Sessions},
+};
+use axum::{extract::Query, Extension, Json};
+use sea_orm::{DatabaseConnection, DbErr};
+use std::sync::Arc;
+
+/// The number of recent items to include in the report context
+const RECENT_ITEMS_COUNT: u64 = 10;
+
+/// The maximum number of reports that can be requested at once
+const MAX_REPORTS_COUNT: u64 = 100;
+
+/// POST /api/server/reports
+///
+/// Used by players to submit a bug or abuse report, the report is stored
+/// along with context about the player at the time of reporting
+pub async fn create_report(
+    Auth(user): Auth,
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(sessions): Extension<Arc<Sessions>>,
+    Extension(game_manager): Extension<Arc<GameManager>>,
+    JsonValidated(req): JsonValidated<CreateReportRequest>,
+) -> HttpResult<Report> {
+    let context = create_report_context(&db, &user, &sessions, &game_manager, req.game_id).await?;
+
+    let report = Report::create(
+        &db,
+        &user,
+        req.category,
+        req.description,
+        req.game_id,
+        context,
+    )
+    .await?;
+
+    Ok(Json(report))
+}
+
+/// GET /api/server/reports
+///
+/// Used by administrators to list the submitted reports, newest first
+pub async fn get_reports(
+    ModeratorAuth(_): ModeratorAuth,
+    Query(query): Query<ReportsQuery>,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<VecWithCount<Report>> {
+    let count = query.count.min(MAX_REPORTS_COUNT);
+    let reports = Report::page(&db, query.offset, count).await?;
+
+    Ok(Json(VecWithCount::new(reports)))
+}
+
+/// Captures the current context for the provided `user`, uses
+/// the `game_id` if provided otherwise the game the users session
+/// is currently in
+async fn create_report_context(
+    db: &DatabaseConnection,
+    user: &User,
+    sessions: &Sessions,
+    game_manager: &GameManager,
+    game_id: Option<GameID>,
+) -> Result<ReportContext, DbErr> {
+    let session = sessions
+        .lookup_session(user.id)
+        .map(|session| ReportSessionContext {
+            session_id: session.uuid,
+            game_id: session.data.lock().game_id(),
+        });
+
+    // Use the explicit game or fallback to the current session game
+    let game_id = game_id.or_else(|| session.as_ref().and_then(|session| session.game_id));
+
+    let game = match game_id {
+        Some(game_id) => match game_manager.get_game(game_id).await {
+            Some(game_ref) => {
+                let game = &*game_ref.read().await;
+
+                Some(ReportGameContext {
+                    state: game.state,
+                    attributes: game
+                        .attributes
+                        .iter()
+                        .map(|(key, value)| (key.clone(), value.clone()))
+                        .collect(),
+                    players: game
+                        .players
+                        .iter()
+                        .map(|player| player.user.username.clone())
+                        .collect(),
+                })
+            }
+            None => None,
+        },
+        None => None,
+    };
+
+    let recent_items = InventoryItem::get_recent_items(db, user, RECENT_ITEMS_COUNT)
+        .await?
+        .into_iter()
+        .map(|item| ReportRecentItem {
+            definition_name: item.definition_name,
+            stack_size: item.stack_size,
+            last_grant: item.last_grant,
+        })
+        .collect();
+
+    Ok(ReportContext {
+        session,
+        game,
+        recent_items,
+    })
+}
@@ -1,34 +1,64 @@
 use crate::{
-    database::entity::{currency::CurrencyType, Currency, User},
-    definitions::store_catalogs::StoreCatalogs,
+    config::Config,
+    database::entity::{
+        currency::CurrencyType, currency_ledger::CurrencyLedgerReason,
+        store_purchases::StorePurchaseId, Currency, CurrencyLedgerEntry, InventoryItem,
+        StorePurchase, User,
+    },
+    definitions::{
+        items::{BaseCategory, ItemDefinition, Items},
+        store_catalogs::StoreCatalogs,
+    },
     http::{
         middleware::{user::Auth, JsonDump},
         models::{
             store::{
-                ClaimUncalimedResponse, ObtainStoreItemRequest, ObtainStoreItemResponse,
+                ClaimUncalimedResponse, ConvertCurrencyRequest, ConvertCurrencyResponse,
+                CurrencyHistoryQuery, CurrencyHistoryResponse, ObtainStoreItemRequest,
+                ObtainStoreItemResponse, RefundPurchaseResponse, RefundablePurchasesResponse,
                 StoreCatalogResponse, StoreError, UpdateSeenArticles, UserCurrenciesResponse,
             },
             CurrencyError, DynHttpError, HttpResult,
         },
     },
-    services::activity::{ActivityEvent, ActivityName, ActivityResult, ActivityService},
+    services::{
+        activity::{ActivityEvent, ActivityName, ActivityResult, ActivityService},
+        items, store_rotation,
+        user_locks::UserLocks,
+    },
+    utils::clock,
+};
+use axum::{
+    extract::{Path, Query},
+    Extension, Json,
 };
-use axum::{Extension, Json};
+use chrono::{Duration, TimeZone, Utc};
 use hyper::StatusCode;
 use log::debug;
 use sea_orm::{ConnectionTrait, DatabaseConnection, TransactionTrait};
+use std::sync::Arc;
+
+/// The maximum number of currency history entries that can be requested at once
+const MAX_HISTORY_COUNT: u64 = 100;
 
 /// GET /store/catalogs
 ///
 /// Obtains the definitions for the store catalogs. Responds with
 /// the store catalog definitions along with all the articles within
-/// each catalog
-pub async fn get_catalogs() -> Json<StoreCatalogResponse> {
+/// each catalog, limited time articles are from the current rotation
+/// with the purchase limits remaining for the user
+pub async fn get_catalogs(
+    Auth(user): Auth,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<StoreCatalogResponse> {
     let catalogs = StoreCatalogs::get();
 
-    Json(StoreCatalogResponse {
-        list: vec![&catalogs.catalog],
-    })
+    let mut catalog = store_rotation::rotating_catalog(&catalogs.catalog, clock::now());
+    store_rotation::apply_purchase_limits(&db, &user, &mut catalog.articles).await?;
+
+    Ok(Json(StoreCatalogResponse {
+        list: vec![catalog],
+    }))
 }
 
 /// PUT /store/article/seen
@@ -54,21 +84,12 @@ pub async fn try_spend_currency<C>(
 where
     C: ConnectionTrait + Send,
 {
-    // Ensure the user owns some of the currency
-    let currency = Currency::get(db, user, currency)
+    // Take the price from the currency balance, the balance is checked by
+    // the update itself so concurrent balance changes can't be overwritten
+    let currency = Currency::try_spend(db, user, currency, amount)
         .await?
-        // User doesn't have any of the requested currency
-        .ok_or(CurrencyError::InsufficientCurrency)?;
-
-    // Ensure they can afford the price
-    if currency.balance < amount {
-        return Err(CurrencyError::InsufficientCurrency.into());
-    }
-
-    let new_balance = currency.balance - amount;
-
-    // Take the price from the currency balance
-    let currency = currency.update(db, new_balance).await?;
+        // User doesn't have enough of the requested currency
+        .ok_or(CurrencyError::InsufficientCurrency { currency, amount })?;
 
     Ok(currency)
 }
@@ -79,27 +100,76 @@ where
 pub async fn obtain_article(
     Auth(user): Auth,
     Extension(db): Extension<DatabaseConnection>,
+    Extension(user_locks): Extension<Arc<UserLocks>>,
     JsonDump(req): JsonDump<ObtainStoreItemRequest>,
 ) -> HttpResult<ObtainStoreItemResponse> {
     let catalogs = StoreCatalogs::get();
+    let now = clock::now();
 
-    // Find the article we are looking for
-    let article = catalogs
-        .catalog
+    // Find the article we are looking for within the current rotation
+    let mut article = store_rotation::rotating_catalog(&catalogs.catalog, now)
         .get_article(&req.article_name)
+        .cloned()
         .ok_or(StoreError::UnknownArticle)?;
 
+    if !article.available_duration.contains(now) {
+        return Err(StoreError::ArticleUnavailable.into());
+    }
+
     // Find the price in the specified currency
     let price = article
         .price_by_currency(req.currency)
+        .cloned()
         .ok_or(CurrencyError::InvalidCurrency)?;
 
-    let result: ActivityResult = db
+    // Hold the user lock so racing purchases can't overspend currency
+    let _guard = user_locks.lock(user.id).await;
+
+    let (result, purchase): (ActivityResult, StorePurchase) = db
         .transaction(|db| {
             Box::pin(async move {
+                // Ensure the user hasn't reached the purchase limit
+                store_rotation::apply_purchase_limits(
+                    db,
+                    &user,
+                    std::slice::from_mut(&mut article),
+                )
+                .await?;
+                if article
+                    .limits
+                    .iter()
+                    .any(|limit| limit.quantity_remaining == 0)
+                {
+                    return Err(StoreError::PurchaseLimitReached.into());
+                }
+
+                // Ensure the purchased item can be given to the user, otherwise
+                // the currency would be spent without the item
+                let definition = Items::get()
+                    .by_name(&article.item_name)
+                    .ok_or(StoreError::UnknownArticleItem)?;
+                let owned = match definition.capacity {
+                    Some(_) => InventoryItem::get_by_name(db, &user, definition.name)
+                        .await?
+                        .map(|item| item.stack_size)
+                        .unwrap_or_default(),
+                    None => 0,
+                };
+                let unlocked = items::is_unlocked(db, &user, definition).await?;
+                check_article_item(definition, owned, unlocked)?;
+
                 // Spend the cost of the article
                 _ = try_spend_currency(db, &user, req.currency, price.final_price).await?;
 
+                CurrencyLedgerEntry::create(
+                    db,
+                    &user,
+                    req.currency,
+                    -(price.final_price as i64),
+                    CurrencyLedgerReason::StorePurchase,
+                )
+                .await?;
+
                 // Create the activity event
                 let event = ActivityEvent::new(ActivityName::ArticlePurchased)
                     .with_attribute("currencyName", req.currency.to_string())
@@ -107,20 +177,168 @@ pub async fn obtain_article(
                     .with_attribute("count", 1);
 
                 // Process the event
-                ActivityService::process_event(db, &user, event)
+                let result = ActivityService::process_event(db, &user, event)
                     .await
-                    .map_err(Into::<DynHttpError>::into)
+                    .map_err(Into::<DynHttpError>::into)?;
+
+                // Record the purchase so that it can be refunded
+                let item = result
+                    .items_earned
+                    .first()
+                    .ok_or(StoreError::ItemNotGranted)?;
+                let purchase = StorePurchase::create(
+                    db,
+                    &user,
+                    article.name,
+                    req.currency,
+                    price.final_price,
+                    item.id,
+                    item.stack_size,
+                )
+                .await?;
+
+                Ok::<_, DynHttpError>((result, purchase))
             })
         })
         .await?;
 
     Ok(Json(ObtainStoreItemResponse {
+        purchase_id: purchase.id,
         items: result.items_earned.clone(),
         definitions: result.item_definitions.clone(),
         generated_activity_result: result,
     }))
 }
 
+/// Checks that the article item `definition` can be given to a user that
+/// owns `owned` of the item, `unlocked` is whether the user has unlocked it
+fn check_article_item(
+    definition: &ItemDefinition,
+    owned: u32,
+    unlocked: bool,
+) -> Result<(), StoreError> {
+    if !unlocked {
+        return Err(StoreError::ItemLocked);
+    }
+
+    if definition
+        .capacity
+        .is_some_and(|capacity| owned >= capacity)
+    {
+        return Err(StoreError::ItemCapacityReached);
+    }
+
+    Ok(())
+}
+
+/// GET /store/purchases
+///
+/// Obtains the purchases made by the user that are still
+/// within the refund window
+pub async fn get_refundable_purchases(
+    Auth(user): Auth,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<RefundablePurchasesResponse> {
+    let window = Duration::minutes(Config::get().store.refund_window_minutes as i64);
+    let purchases = StorePurchase::all_since(&db, &user, clock::now() - window).await?;
+
+    let list = purchases
+        .into_iter()
+        .filter(|purchase| purchase.refunded.is_none())
+        .collect();
+
+    Ok(Json(RefundablePurchasesResponse { list }))
+}
+
+/// POST /store/purchases/:id/refund
+///
+/// Refunds a purchase made within the refund window, the purchased item
+/// is removed and the currency returned. Only possible while the purchased
+/// item stack hasn't been consumed
+pub async fn refund_purchase(
+    Auth(user): Auth,
+    Path(purchase_id): Path<StorePurchaseId>,
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(user_locks): Extension<Arc<UserLocks>>,
+) -> HttpResult<RefundPurchaseResponse> {
+    let window = Duration::minutes(Config::get().store.refund_window_minutes as i64);
+
+    // Hold the user lock so the same purchase can't be refunded twice
+    let _guard = user_locks.lock(user.id).await;
+
+    let response = db
+        .transaction(|db| {
+            Box::pin(async move {
+                let purchase = StorePurchase::get(db, &user, purchase_id)
+                    .await?
+                    .ok_or(StoreError::UnknownPurchase)?;
+
+                if purchase.refunded.is_some() {
+                    return Err(StoreError::AlreadyRefunded.into());
+                }
+
+                if purchase.created + window < clock::now() {
+                    return Err(StoreError::RefundWindowExpired.into());
+                }
+
+                let item_id = purchase.item_id.ok_or(StoreError::ItemConsumed)?;
+                let item = InventoryItem::get(db, &user, item_id)
+                    .await?
+                    .ok_or(StoreError::ItemConsumed)?;
+
+                // Characters are created when purchased so can't be returned
+                let is_character =
+                    Items::get()
+                        .by_name(&item.definition_name)
+                        .is_some_and(|definition| {
+                            definition.category.base_eq(&BaseCategory::Characters)
+                        });
+                if is_character {
+                    return Err(StoreError::NotRefundable.into());
+                }
+
+                // Ensure the purchased amount hasn't been consumed
+                if item.stack_size < purchase.stack_size {
+                    return Err(StoreError::ItemConsumed.into());
+                }
+
+                // Refunds over the currency cap would only be partially returned
+                if !Currency::can_hold(db, &user, purchase.currency, purchase.price).await? {
+                    return Err(CurrencyError::BalanceCapReached.into());
+                }
+
+                let stack_size = item.stack_size - purchase.stack_size;
+                item.set_stack_size(db, stack_size).await?;
+
+                Currency::add(db, &user, purchase.currency, purchase.price).await?;
+                CurrencyLedgerEntry::create(
+                    db,
+                    &user,
+                    purchase.currency,
+                    purchase.price as i64,
+                    CurrencyLedgerReason::StoreRefund,
+                )
+                .await?;
+
+                let purchase = purchase.set_refunded(db).await?;
+                let currencies = Currency::all(db, &user).await?;
+
+                debug!(
+                    "Refunded store purchase (User: {}, Purchase: {})",
+                    user.id, purchase.id
+                );
+
+                Ok::<_, DynHttpError>(RefundPurchaseResponse {
+                    purchase,
+                    currencies,
+                })
+            })
+        })
+        .await?;
+
+    Ok(Json(response))
+}
+
 /// POST /store/unclaimed/claimAll
 ///
 /// Possibly claims earned items from end of match?
@@ -143,3 +361,183 @@ pub async fn get_currencies(
 
     Ok(Json(UserCurrenciesResponse { list: currencies }))
 }
+
+/// GET /user/currencies/history
+///
+/// Responds with the balance of every currency, including the server only
+/// challenge and apex point currencies, along with a page of the currency
+/// transaction history
+pub async fn get_currency_history(
+    Auth(user): Auth,
+    Query(query): Query<CurrencyHistoryQuery>,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<CurrencyHistoryResponse> {
+    let count = query.count.min(MAX_HISTORY_COUNT);
+
+    let balances = Currency::all_types(&db, &user).await?;
+    let list = CurrencyLedgerEntry::page(&db, &user, query.offset, count).await?;
+
+    Ok(Json(CurrencyHistoryResponse { balances, list }))
+}
+
+/// POST /user/currencies/convert
+///
+/// Converts an amount of one currency into another currency using the
+/// exchange rates from the server configuration, conversions are recorded
+/// in the currency ledger which is used to enforce the daily caps
+pub async fn convert_currency(
+    Auth(user): Auth,
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(user_locks): Extension<Arc<UserLocks>>,
+    Json(req): Json<ConvertCurrencyRequest>,
+) -> HttpResult<ConvertCurrencyResponse> {
+    // Hold the user lock so racing conversions can't overspend currency
+    let _guard = user_locks.lock(user.id).await;
+
+    let rate = Config::get()
+        .currency_conversions
+        .iter()
+        .find(|rate| rate.from == req.from && rate.to == req.to)
+        .ok_or(CurrencyError::ConversionUnavailable)?;
+
+    // Amount must be a non zero multiple of the exchange rate
+    if rate.from_amount == 0 || req.amount == 0 || req.amount % rate.from_amount != 0 {
+        return Err(CurrencyError::InvalidConversionAmount.into());
+    }
+
+    let received = (req.amount / rate.from_amount)
+        .checked_mul(rate.to_amount)
+        .ok_or(CurrencyError::InvalidConversionAmount)?;
+
+    let response = db
+        .transaction(|db| {
+            Box::pin(async move {
+                let remaining_daily = match rate.daily_cap {
+                    Some(daily_cap) => {
+                        // Conversions are tracked since the start of the current UTC day
+                        let start_of_day = Utc.from_utc_datetime(
+                            &clock::now().date_naive().and_hms_opt(0, 0, 0).unwrap(),
+                        );
+
+                        // Conversions into the currency don't count towards the cap
+                        let converted_today = CurrencyLedgerEntry::spent_since(
+                            db,
+                            &user,
+                            req.from,
+                            CurrencyLedgerReason::Conversion,
+                            start_of_day,
+                        )
+                        .await?;
+
+                        let remaining = (daily_cap as u64).saturating_sub(converted_today);
+                        if (req.amount as u64) > remaining {
+                            return Err(CurrencyError::ConversionCapReached.into());
+                        }
+
+                        Some((remaining - req.amount as u64) as u32)
+                    }
+                    None => None,
+                };
+
+                // Converted currency over the cap would be lost after spending
+                if !Currency::can_hold(db, &user, req.to, received).await? {
+                    return Err(CurrencyError::BalanceCapReached.into());
+                }
+
+                try_spend_currency(db, &user, req.from, req.amount).await?;
+                Currency::add(db, &user, req.to, received).await?;
+
+                // Record the conversion in the ledger
+                CurrencyLedgerEntry::create(
+                    db,
+                    &user,
+                    req.from,
+                    -(req.amount as i64),
+                    CurrencyLedgerReason::Conversion,
+                )
+                .await?;
+                CurrencyLedgerEntry::create(
+                    db,
+                    &user,
+                    req.to,
+                    received as i64,
+                    CurrencyLedgerReason::Conversion,
+                )
+                .await?;
+
+                let currencies = Currency::all(db, &user).await?;
+
+                Ok::<_, DynHttpError>(ConvertCurrencyResponse {
+                    received,
+                    remaining_daily,
+                    currencies,
+                })
+            })
+        })
+        .await?;
+
+    Ok(Json(response))
+}
+
+#[cfg(test)]
+mod test {
+    use super::check_article_item;
+    use crate::{
+        database::entity::InventoryItem, definitions::items::Items,
+        http::models::store::StoreError, services::items::unlock_reached,
+    };
+    use uuid::{uuid, Uuid};
+
+    /// Tests that articles for items the user hasn't unlocked can't be
+    /// purchased, the M-8 Avenger S requires a maxed M-8 Avenger
+    #[test]
+    fn test_purchase_locked_article() {
+        let items = Items::get();
+        let definition = items
+            .by_name(&uuid!("9454bb7d-4e90-4676-aef0-322c791344b7"))
+            .unwrap();
+        let unlock_definition = items
+            .by_name(&definition.unlock_definition.unwrap())
+            .unwrap();
+
+        let unlock_item = |stack_size| InventoryItem {
+            id: Uuid::nil(),
+            user_id: 1,
+            definition_name: unlock_definition.name,
+            stack_size,
+            seen: false,
+            instance_attributes: Default::default(),
+            created: Default::default(),
+            last_grant: Default::default(),
+            earned_by: Default::default(),
+            restricted: false,
+            deleted_at: None,
+        };
+
+        // Unlock item isn't owned
+        let unlocked = unlock_reached(unlock_definition, None);
+        assert!(matches!(
+            check_article_item(definition, 0, unlocked),
+            Err(StoreError::ItemLocked)
+        ));
+
+        // Unlock item hasn't reached its capacity
+        let unlocked = unlock_reached(unlock_definition, Some(&unlock_item(1)));
+        assert!(matches!(
+            check_article_item(definition, 0, unlocked),
+            Err(StoreError::ItemLocked)
+        ));
+
+        // Unlock item has reached its capacity
+        let capacity = unlock_definition.capacity.unwrap();
+        let unlocked = unlock_reached(unlock_definition, Some(&unlock_item(capacity)));
+        assert!(check_article_item(definition, 0, unlocked).is_ok());
+
+        // Unlocked items still can't go over their own capacity
+        let owned = definition.capacity.unwrap();
+        assert!(matches!(
+            check_article_item(definition, owned, unlocked),
+            Err(StoreError::ItemCapacityReached)
+        ));
+    }
+}
@@ -1,67 +1,110 @@
 use crate::{
-    database::entity::{
-        currency::CurrencyType, strike_team_mission::StrikeTeamMissionId,
-        strike_team_mission_progress::UserMissionState, strike_teams::StrikeTeamId, Currency,
-        StrikeTeam, StrikeTeamMission, StrikeTeamMissionProgress,
+    config::Config,
+    database::{
+        entity::{
+            currency::CurrencyType,
+            strike_team_mission::{MissionAccessibility, StrikeTeamMissionId},
+            strike_team_mission_progress::UserMissionState,
+            strike_teams::{self, StrikeTeamId},
+            Currency, RetiredStrikeTeam, StrikeTeam, StrikeTeamDeployment, StrikeTeamMission,
+            StrikeTeamMissionHistory, StrikeTeamMissionProgress, UnlockedStrikeTeamFlair, User,
+        },
+        DbResult,
     },
-    definitions::strike_teams::{
-        create_user_strike_team, StrikeTeamEquipment, StrikeTeamSpecialization, StrikeTeams,
-        MAX_STRIKE_TEAMS, STRIKE_TEAM_COSTS,
+    definitions::{
+        strike_team_flair::StrikeTeamFlairs,
+        strike_teams::{
+            create_user_strike_team, max_strike_teams, mission_success_rate, strike_team_cost,
+            StrikeTeamEquipment, StrikeTeamSpecialization, StrikeTeams, DEFAULT_STRIKE_TEAMS,
+            MIN_SPECIALIZATION_LEVEL,
+        },
     },
     http::{
         middleware::user::Auth,
         models::{
             strike_teams::{
-                PurchaseQuery, PurchaseResponse, StrikeTeamError, StrikeTeamMissionSpecific,
-                StrikeTeamMissionWithState, StrikeTeamSuccessRate, StrikeTeamWithMission,
-                StrikeTeamsList, StrikeTeamsResponse,
+                DeployResponse, DeployTeamQuery, EquipFlairRequest, MissionHistoryQuery,
+                PendingResolutionsResponse, PurchaseQuery, PurchaseResponse,
+                ResolveMissionResponse, SpecializeRequest, StrikeTeamActiveMission,
+                StrikeTeamError, StrikeTeamFlairWithUnlock, StrikeTeamMissionSpecific,
+                StrikeTeamMissionWithState, StrikeTeamSlot, StrikeTeamSuccessRate,
+                StrikeTeamWithMission, StrikeTeamsList, StrikeTeamsResponse,
             },
             CurrencyError, DynHttpError, HttpResult, ListWithCount, RawJson, VecWithCount,
         },
     },
+    services::{
+        maintenance::ReadOnlyMode,
+        random::{RandomPurpose, RandomService},
+        server_events::{ServerEvent, ServerEvents},
+        strike_team_deployment::{self, Deployment, StrikeTeamState},
+        strike_team_resolve::{self, MissionOutcome},
+        strike_team_retirement,
+        user_locks::UserLocks,
+    },
+    utils::clock,
 };
 use axum::{
     extract::{Path, Query},
     Extension, Json,
 };
-use chrono::Utc;
 use log::debug;
-use sea_orm::{prelude::DateTimeUtc, DatabaseConnection, TransactionTrait};
-use std::collections::HashMap;
-use uuid::Uuid;
+use sea_orm::{
+    prelude::DateTimeUtc, ConnectionTrait, DatabaseConnection, ModelTrait, TransactionTrait,
+};
+use std::{collections::HashMap, sync::Arc};
 
 use super::store::try_spend_currency;
 
 /// GET /striketeams
 pub async fn get(
     Extension(db): Extension<DatabaseConnection>,
+    Extension(read_only): Extension<Arc<ReadOnlyMode>>,
     Auth(user): Auth,
 ) -> HttpResult<StrikeTeamsResponse> {
+    let active = strike_team_deployment::view(&db, &user, &read_only).await?;
     let strike_teams: Vec<StrikeTeam> = StrikeTeam::get_by_user(&db, &user).await?;
 
-    // TODO: Load current missions
-    let teams: Vec<StrikeTeamWithMission> = strike_teams
-        .into_iter()
-        .map(|team| StrikeTeamWithMission {
-            mission: None,
-            team,
-        })
-        .collect();
+    let mut teams: Vec<StrikeTeamWithMission> = Vec::with_capacity(strike_teams.len());
+    for team in strike_teams {
+        let progress = active
+            .iter()
+            .find(|progress| progress.strike_team_id == Some(team.id));
+        teams.push(team_with_mission(&db, team, progress).await?);
+    }
+
+    let config = &Config::get().strike_teams;
 
     // Create a map of the next costs
-    let next_purchase_costs: HashMap<CurrencyType, u32> = STRIKE_TEAM_COSTS
-        .get(teams.len())
+    let next_purchase_costs: HashMap<CurrencyType, u32> = strike_team_cost(config, teams.len())
         .into_iter()
-        .map(|value| (CurrencyType::Mission, *value))
+        .map(|value| (CurrencyType::Mission, value))
+        .collect();
+
+    let cap = max_strike_teams();
+
+    // Create the slot ownership details
+    let slots: Vec<StrikeTeamSlot> = (0..cap)
+        .filter_map(|index| {
+            let cost = strike_team_cost(config, index)?;
+
+            Some(StrikeTeamSlot {
+                index,
+                owned: index < teams.len(),
+                expansion: index >= DEFAULT_STRIKE_TEAMS,
+                cost,
+            })
+        })
         .collect();
 
     Ok(Json(StrikeTeamsResponse {
         teams: StrikeTeamsList {
             total_count: teams.len(),
-            cap: MAX_STRIKE_TEAMS,
+            cap,
             list: teams,
+            slots,
         },
-        min_specialization_level: 16,
+        min_specialization_level: MIN_SPECIALIZATION_LEVEL,
         next_purchase_costs,
         inventory_item_limit: 200,
         inventory_item_count: 0,
@@ -73,22 +116,17 @@ pub async fn get_success_rate(
     Extension(db): Extension<DatabaseConnection>,
     Auth(user): Auth,
 ) -> HttpResult<VecWithCount<StrikeTeamSuccessRate>> {
-    let current_time = Utc::now().timestamp();
+    let current_time = clock::timestamp();
     let strike_teams = StrikeTeam::get_by_user(&db, &user).await?;
     let missions = StrikeTeamMission::available_missions(&db, &user, current_time).await?;
 
-    fn compute_success_rate(_strike_team: &StrikeTeam, _mission: &StrikeTeamMission) -> f32 {
-        // Compute actual success rate
-        0.91
-    }
-
     let rates: Vec<StrikeTeamSuccessRate> = strike_teams
         .into_iter()
         .map(|team| {
             let mission_success_rate = missions
                 .iter()
                 .map(|(mission, _)| {
-                    let rate = compute_success_rate(&team, mission);
+                    let rate = mission_success_rate(&team, mission);
                     (mission.id, rate)
                 })
                 .collect();
@@ -130,8 +168,6 @@ pub async fn purchase_equipment(
     Path((id, name)): Path<(StrikeTeamId, String)>,
     Extension(db): Extension<DatabaseConnection>,
 ) -> HttpResult<PurchaseResponse> {
-    let strike_teams = StrikeTeams::get();
-
     // Find the strike team the user wants to equip
     let team = StrikeTeam::get_by_id(&db, &user, id)
         .await?
@@ -141,16 +177,7 @@ pub async fn purchase_equipment(
         return Err(StrikeTeamError::TeamOnMission.into());
     }
 
-    let equipment = strike_teams
-        .equipment
-        .iter()
-        .find(|equip| equip.name.eq(&name))
-        .ok_or(StrikeTeamError::UnknownEquipmentItem)?;
-
-    let equipment_cost = *equipment
-        .cost_by_currency
-        .get(&query.currency)
-        .ok_or(CurrencyError::InvalidCurrency)?;
+    let (equipment, equipment_cost) = purchasable_equipment(&team, &name, query.currency)?;
 
     let (team, currency_balance): (StrikeTeam, Currency) = db
         .transaction(|db| {
@@ -174,19 +201,410 @@ pub async fn purchase_equipment(
     }))
 }
 
+/// GET /striketeams/flair
+///
+/// Lists all the strike team flair along with which flair
+/// the user has unlocked
+pub async fn get_flair(
+    Auth(user): Auth,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<VecWithCount<StrikeTeamFlairWithUnlock>> {
+    let unlocked = UnlockedStrikeTeamFlair::all(&db, &user).await?;
+
+    let list = StrikeTeamFlairs::get()
+        .values
+        .iter()
+        .map(|flair| StrikeTeamFlairWithUnlock {
+            flair,
+            unlocked: unlocked
+                .iter()
+                .find(|unlocked| unlocked.name == flair.name)
+                .map(|unlocked| unlocked.unlocked),
+        })
+        .collect();
+
+    Ok(Json(VecWithCount::new(list)))
+}
+
+/// PUT /striketeams/:id/flair
+///
+/// Equips unlocked flair onto a strike team
+pub async fn equip_flair(
+    Auth(user): Auth,
+    Path(id): Path<StrikeTeamId>,
+    Extension(db): Extension<DatabaseConnection>,
+    Json(req): Json<EquipFlairRequest>,
+) -> HttpResult<StrikeTeam> {
+    let team = StrikeTeam::get_by_id(&db, &user, id)
+        .await?
+        .ok_or(StrikeTeamError::UnknownTeam)?;
+
+    if let Some(name) = &req.name {
+        let flair = StrikeTeamFlairs::get()
+            .by_name(name)
+            .ok_or(StrikeTeamError::UnknownFlair)?;
+
+        if flair.kind != req.kind {
+            return Err(StrikeTeamError::FlairKindMismatch.into());
+        }
+
+        if !UnlockedStrikeTeamFlair::is_unlocked(&db, &user, name).await? {
+            return Err(StrikeTeamError::FlairNotUnlocked.into());
+        }
+    }
+
+    let team = team.set_flair(&db, req.kind, req.name).await?;
+    Ok(Json(team))
+}
+
+/// PUT /striketeams/:id/specialization
+///
+/// Chooses the specialization for a strike team, teams can only be
+/// specialized once they reach the minimum level
+pub async fn specialize(
+    Auth(user): Auth,
+    Path(id): Path<StrikeTeamId>,
+    Extension(db): Extension<DatabaseConnection>,
+    Json(req): Json<SpecializeRequest>,
+) -> HttpResult<StrikeTeam> {
+    let team = StrikeTeam::get_by_id(&db, &user, id)
+        .await?
+        .ok_or(StrikeTeamError::UnknownTeam)?;
+
+    let specialization = StrikeTeams::get()
+        .specializations
+        .iter()
+        .find(|value| value.name == req.name)
+        .ok_or(StrikeTeamError::UnknownSpecialization)?;
+
+    if team.specialization.is_some() {
+        return Err(StrikeTeamError::AlreadySpecialized.into());
+    }
+
+    if team.level < MIN_SPECIALIZATION_LEVEL {
+        return Err(StrikeTeamError::SpecializationLevel(MIN_SPECIALIZATION_LEVEL).into());
+    }
+
+    let team = team
+        .set_specialization(&db, specialization.name.clone())
+        .await?;
+
+    Ok(Json(team))
+}
+
 /// POST /striketeams/:id/mission/resolve
-pub async fn resolve_mission(Path(id): Path<Uuid>) -> RawJson {
+///
+/// Resolves the mission a strike team has returned from, successful
+/// missions grant the team XP and the mission rewards
+pub async fn resolve_mission(
+    Auth(user): Auth,
+    Path(id): Path<StrikeTeamId>,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<ResolveMissionResponse> {
     debug!("Strike team mission resolve: {}", id);
 
-    // TODO: Handle resolving a mission in pending resolve state
-    // updating to completed state and granting rewards
+    let team = StrikeTeam::get_by_id(&db, &user, id)
+        .await?
+        .ok_or(StrikeTeamError::UnknownTeam)?;
+
+    // Ensure teams that have returned are marked as pending
+    strike_team_deployment::refresh(&db, &user).await?;
 
-    static DEFS: &str =
-        include_str!("../../resources/defaults/strikeTeams/placeholderResolve.json");
-    RawJson(DEFS)
+    let progress = StrikeTeamMissionProgress::get_by_team(&db, &team)
+        .await?
+        .filter(|progress| progress.user_mission_state == UserMissionState::PendingResolve)
+        .ok_or(StrikeTeamError::MissionNotReturned)?;
+
+    let mission = StrikeTeamMission::by_id(&db, progress.mission_id)
+        .await?
+        .ok_or(StrikeTeamError::UnknownMission)?;
+
+    // Missions returned before outcomes were stored are rolled now
+    let outcome = MissionOutcome::from_progress(&progress).unwrap_or_else(|| {
+        let mut rng = RandomService::get().rng(RandomPurpose::StrikeTeamOutcome);
+        MissionOutcome::roll(&mut rng, &team, &mission)
+    });
+
+    let mission_id = mission.id;
+    let tx_user = user.clone();
+    let resolution = db
+        .transaction(|db| {
+            Box::pin(async move {
+                // Only the first of any concurrent resolves completes the mission
+                if !progress.set_completed(db).await? {
+                    return Err(StrikeTeamError::MissionNotReturned.into());
+                }
+
+                let resolution =
+                    strike_team_resolve::resolve(db, &tx_user, team, &mission, outcome).await?;
+                Ok::<_, DynHttpError>(resolution)
+            })
+        })
+        .await?;
+
+    ServerEvents::get().publish(ServerEvent::StrikeTeamMissionResolved {
+        user_id: user.id,
+        team_id: id,
+        mission_id,
+        successful: resolution.successful,
+    });
+
+    // The team is now idle so the next queued mission can be deployed
+    strike_team_deployment::refresh(&db, &user).await?;
+
+    let team = team_with_mission(&db, resolution.team, None).await?;
+
+    Ok(Json(ResolveMissionResponse {
+        team,
+        mission_successful: resolution.successful,
+        traits_acquired: resolution.traits_acquired,
+        activity_response: resolution.result,
+        streak_bonus: resolution.streak_bonus,
+    }))
 }
 
-/// POST /striketeams/:id/mission/:id
+/// POST /striketeams/:id/mission/:id?equipment=name&currency=MissionCurrency
+///
+/// Deploys a specific strike team onto a mission, the optional equipment
+/// is purchased for the team before it's deployed
+pub async fn deploy_team(
+    Auth(user): Auth,
+    Path((id, mission_id)): Path<(StrikeTeamId, StrikeTeamMissionId)>,
+    Query(query): Query<DeployTeamQuery>,
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(user_locks): Extension<Arc<UserLocks>>,
+) -> HttpResult<StrikeTeamWithMission> {
+    debug!("Strike team deploy: {} {} {:?}", id, mission_id, query);
+
+    // Hold the user lock so racing deployments can't both spend currency
+    // on equipment or deploy the same team
+    let _guard = user_locks.lock(user.id).await;
+
+    let team = StrikeTeam::get_by_id(&db, &user, id)
+        .await?
+        .ok_or(StrikeTeamError::UnknownTeam)?;
+    let mission = deployable_mission(&db, &user, mission_id).await?;
+
+    strike_team_deployment::refresh(&db, &user).await?;
+
+    if team.is_on_mission(&db).await? {
+        return Err(StrikeTeamError::TeamOnMission.into());
+    }
+
+    let equipment = match &query.equipment {
+        Some(name) => Some(purchasable_equipment(&team, name, query.currency)?),
+        None => None,
+    };
+
+    let tx_user = user.clone();
+    let (team, progress) = db
+        .transaction(|db| {
+            Box::pin(async move {
+                // Purchase the equipment boosting the team for the mission
+                let team = match equipment {
+                    Some((equipment, cost)) => {
+                        _ = try_spend_currency(db, &tx_user, query.currency, cost).await?;
+                        team.set_equipment(db, Some(equipment.clone())).await?
+                    }
+                    None => team,
+                };
+
+                // Fails when a concurrent request already deployed onto the mission
+                // which rolls back the equipment purchase
+                let progress = StrikeTeamMissionProgress::deploy(db, &tx_user, &team, &mission)
+                    .await?
+                    .ok_or(StrikeTeamError::MissionAlreadyDeployed)?;
+                Ok::<_, DynHttpError>((team, progress))
+            })
+        })
+        .await?;
+
+    let team = team_with_mission(&db, team, Some(&progress)).await?;
+
+    Ok(Json(team))
+}
+
+/// POST /striketeams/deploy/:mission_id
+///
+/// Deploys the next available strike team onto a mission, when all
+/// the strike teams are busy the mission is queued until one returns
+pub async fn deploy(
+    Auth(user): Auth,
+    Path(mission_id): Path<StrikeTeamMissionId>,
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(user_locks): Extension<Arc<UserLocks>>,
+) -> HttpResult<DeployResponse> {
+    // Hold the user lock so racing deployments can't deploy the same team
+    let _guard = user_locks.lock(user.id).await;
+
+    let mission = deployable_mission(&db, &user, mission_id).await?;
+
+    if StrikeTeamDeployment::is_queued(&db, &user, mission_id).await? {
+        return Err(StrikeTeamError::MissionAlreadyQueued.into());
+    }
+
+    let response = match strike_team_deployment::deploy_or_queue(&db, &user, &mission).await? {
+        Deployment::Deployed(progress) => {
+            let team = progress
+                .find_related(strike_teams::Entity)
+                .one(&db)
+                .await?
+                .ok_or(StrikeTeamError::UnknownTeam)?;
+
+            DeployResponse {
+                queued: false,
+                team: Some(team_with_mission(&db, team, Some(&progress)).await?),
+                queue_position: None,
+            }
+        }
+        Deployment::Queued { position } => DeployResponse {
+            queued: true,
+            team: None,
+            queue_position: Some(position),
+        },
+        Deployment::AlreadyDeployed => {
+            return Err(StrikeTeamError::MissionAlreadyDeployed.into());
+        }
+    };
+
+    Ok(Json(response))
+}
+
+/// GET /striketeams/pendingResolutions
+///
+/// Obtains all the strike teams that have returned from missions so
+/// the client can resolve them together when opening the menu
+pub async fn get_pending_resolutions(
+    Auth(user): Auth,
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(read_only): Extension<Arc<ReadOnlyMode>>,
+) -> HttpResult<PendingResolutionsResponse> {
+    let active = strike_team_deployment::view(&db, &user, &read_only).await?;
+
+    let mut resolutions = Vec::new();
+    for progress in active
+        .iter()
+        .filter(|progress| progress.user_mission_state == UserMissionState::PendingResolve)
+    {
+        let team = match progress.strike_team_id {
+            Some(id) => StrikeTeam::get_by_id(&db, &user, id).await?,
+            None => None,
+        };
+
+        if let Some(team) = team {
+            resolutions.push(team_with_mission(&db, team, Some(progress)).await?);
+        }
+    }
+
+    let queue = StrikeTeamDeployment::all(&db, &user).await?;
+
+    Ok(Json(PendingResolutionsResponse { resolutions, queue }))
+}
+
+/// Finds the mission with the `mission_id` ensuring that it can
+/// have a strike team deployed onto it by the `user`
+async fn deployable_mission<C>(
+    db: &C,
+    user: &User,
+    mission_id: StrikeTeamMissionId,
+) -> Result<StrikeTeamMission, DynHttpError>
+where
+    C: ConnectionTrait + Send,
+{
+    let mission = StrikeTeamMission::by_id(db, mission_id)
+        .await?
+        .ok_or(StrikeTeamError::UnknownMission)?;
+
+    // Ensure the mission is part of the current rotation
+    let now = clock::timestamp();
+    if mission.start_seconds > now {
+        return Err(StrikeTeamError::MissionNotStarted.into());
+    }
+    if mission.end_seconds <= now {
+        return Err(StrikeTeamError::MissionExpired.into());
+    }
+
+    if mission.accessibility == MissionAccessibility::MultiPlayer {
+        return Err(StrikeTeamError::MissionApexOnly.into());
+    }
+
+    let progress = StrikeTeamMissionProgress::get_by_mission(db, user, mission_id).await?;
+    match progress.map(|progress| progress.user_mission_state) {
+        Some(UserMissionState::InProgress | UserMissionState::PendingResolve) => {
+            Err(StrikeTeamError::MissionAlreadyDeployed.into())
+        }
+        Some(UserMissionState::Completed) => Err(StrikeTeamError::MissionUnavailable.into()),
+        _ => Ok(mission),
+    }
+}
+
+/// Finds the equipment with the provided `name` ensuring the `team` can use
+/// it, responds with the equipment and its cost in the `currency`
+fn purchasable_equipment(
+    team: &StrikeTeam,
+    name: &str,
+    currency: CurrencyType,
+) -> Result<(&'static StrikeTeamEquipment, u32), DynHttpError> {
+    let equipment = StrikeTeams::get()
+        .equipment
+        .iter()
+        .find(|equip| equip.name.eq(name))
+        .ok_or(StrikeTeamError::UnknownEquipmentItem)?;
+
+    if team.level < equipment.level_required {
+        return Err(StrikeTeamError::EquipmentLevelRequired(equipment.level_required).into());
+    }
+
+    let cost = *equipment
+        .cost_by_currency
+        .get(&currency)
+        .ok_or(CurrencyError::InvalidCurrency)?;
+
+    Ok((equipment, cost))
+}
+
+/// Creates the response details for a `team` with the `progress` of
+/// the mission the team is assigned to
+pub(super) async fn team_with_mission<C>(
+    db: &C,
+    team: StrikeTeam,
+    progress: Option<&StrikeTeamMissionProgress>,
+) -> DbResult<StrikeTeamWithMission>
+where
+    C: ConnectionTrait + Send,
+{
+    let state = StrikeTeamState::from_progress(progress);
+
+    let mission = match progress {
+        Some(progress) => StrikeTeamMission::by_id(db, progress.mission_id)
+            .await?
+            .map(|mission| StrikeTeamActiveMission {
+                name: mission.id,
+                live_mission: StrikeTeamMissionWithState {
+                    mission,
+                    user_mission_state: progress.user_mission_state,
+                    seen: progress.seen,
+                    completed: progress.completed,
+                    reward_preview: None,
+                },
+                finish_time: progress.finish_time,
+                // Outcome is rolled when the team returns, missions still
+                // in progress haven't succeeded yet
+                successful: MissionOutcome::from_progress(progress)
+                    .is_some_and(|outcome| outcome.successful),
+                earn_negative_trait: progress.earn_negative_trait,
+            }),
+        None => None,
+    };
+
+    Ok(StrikeTeamWithMission {
+        team,
+        mission,
+        state,
+    })
+}
+
+/// GET /striketeams/:id/mission/:id
 ///
 /// Obtain the details about a specific strike team mission
 pub async fn get_mission(
@@ -199,28 +617,33 @@ pub async fn get_mission(
     let mission = StrikeTeamMission::by_id(&db, mission_id)
         .await?
         .ok_or(StrikeTeamError::UnknownMission)?;
-    let strike_team = StrikeTeam::get_by_id(&db, &user, id)
+    StrikeTeam::get_by_id(&db, &user, id)
         .await?
         .ok_or(StrikeTeamError::UnknownTeam)?;
-    let progress = StrikeTeamMissionProgress::get_by_team(&db, &strike_team).await?;
+    let progress = StrikeTeamMissionProgress::get_by_mission(&db, &user, mission_id).await?;
+    let finish_time: DateTimeUtc = progress
+        .as_ref()
+        .and_then(|progress| progress.finish_time)
+        .unwrap_or_else(clock::now);
 
+    let reward_preview = strike_team_resolve::reward_preview(&Config::get().strike_teams, &mission);
     let live_mission = match progress {
         Some(value) => StrikeTeamMissionWithState {
             mission,
             user_mission_state: value.user_mission_state,
             seen: value.seen,
             completed: value.completed,
+            reward_preview,
         },
         None => StrikeTeamMissionWithState {
             mission,
             user_mission_state: UserMissionState::Available,
             seen: false,
             completed: false,
+            reward_preview,
         },
     };
 
-    let finish_time: DateTimeUtc = Utc::now(); /* TODO: Proper finish time */
-
     Ok(Json(StrikeTeamMissionSpecific {
         name: mission_id,
         live_mission,
@@ -231,52 +654,90 @@ pub async fn get_mission(
 /// POST /striketeams/:id/retire
 ///
 /// Retires (Removes) a strike team from the players
-/// strike teams
+/// strike teams, the team is recorded in the history
+/// and rewards are granted based on its level
 pub async fn retire(
     Auth(user): Auth,
     Path(id): Path<StrikeTeamId>,
     Extension(db): Extension<DatabaseConnection>,
 ) -> Result<(), DynHttpError> {
     debug!("Strike team retire: {}", id);
+
+    strike_team_retirement::retire(&db, &Config::get().strike_teams, &user, id).await?;
+
+    Ok(())
+}
+
+/// GET /striketeams/retired
+///
+/// Lists the strike teams the player has retired, most recent first
+pub async fn get_retired(
+    Auth(user): Auth,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<VecWithCount<RetiredStrikeTeam>> {
+    let retired = RetiredStrikeTeam::all(&db, &user).await?;
+    Ok(Json(VecWithCount::new(retired)))
+}
+
+/// GET /striketeams/:id/history?offset=0&count=20
+///
+/// Lists the missions the strike team has completed, most recent
+/// first, along with the outcome and rewards of each mission
+pub async fn get_history(
+    Auth(user): Auth,
+    Path(id): Path<StrikeTeamId>,
+    Query(query): Query<MissionHistoryQuery>,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<VecWithCount<StrikeTeamMissionHistory>> {
     let team = StrikeTeam::get_by_id(&db, &user, id)
         .await?
         .ok_or(StrikeTeamError::UnknownTeam)?;
 
-    team.delete(&db).await?;
+    let count = query.count.min(StrikeTeamMissionHistory::MAX_LISTED);
+    let history = StrikeTeamMissionHistory::page(&db, team.id, query.offset, count).await?;
+    let total_count = StrikeTeamMissionHistory::count_by_team(&db, team.id).await?;
 
-    Ok(())
+    Ok(Json(VecWithCount {
+        total_count: total_count as usize,
+        list: history,
+    }))
 }
 
 /// POST /striketeams/purchase?currency=MissionCurrency
 pub async fn purchase(
     Auth(user): Auth,
     Extension(db): Extension<DatabaseConnection>,
+    Extension(user_locks): Extension<Arc<UserLocks>>,
 ) -> HttpResult<PurchaseResponse> {
-    // Get the number of teams they already have
-    let strike_teams = StrikeTeam::get_user_count(&db, &user).await? as usize;
+    // Hold the user lock so racing purchases can't go over the team limit
+    let _guard = user_locks.lock(user.id).await;
 
-    // Get the cost of a new team
-    let strike_team_cost = *STRIKE_TEAM_COSTS
-        .get(strike_teams)
-        .ok_or(StrikeTeamError::MaxTeams)?;
-
-    let (team, currency_balance): (StrikeTeam, Currency) = db
+    let (team, currency_balance, strike_teams): (StrikeTeam, Currency, usize) = db
         .transaction(|db| {
             Box::pin(async move {
+                let config = &Config::get().strike_teams;
+
+                // Get the number of teams they already have
+                let strike_teams = StrikeTeam::get_user_count(db, &user).await? as usize;
+
+                // Get the cost of a new team
+                let team_cost = strike_team_cost(config, strike_teams)
+                    .ok_or(StrikeTeamError::MaxTeams(config.max_teams))?;
+
                 // Spend the cost of the strike team
                 let currency_balance =
-                    try_spend_currency(db, &user, CurrencyType::Mission, strike_team_cost).await?;
+                    try_spend_currency(db, &user, CurrencyType::Mission, team_cost).await?;
 
                 // Create the strike team
                 let team = create_user_strike_team(db, &user).await?;
 
-                Ok::<_, DynHttpError>((team, currency_balance))
+                Ok::<_, DynHttpError>((team, currency_balance, strike_teams))
             })
         })
         .await?;
 
     // Get the cost of the next team
-    let next_purchase_cost = STRIKE_TEAM_COSTS.get(strike_teams + 1).copied();
+    let next_purchase_cost = strike_team_cost(&Config::get().strike_teams, strike_teams + 1);
 
     Ok(Json(PurchaseResponse {
         currency_balance,
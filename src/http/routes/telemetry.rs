@@ -1,17 +1,53 @@
-use crate::http::models::telemetry::PinResponse;
-use axum::Json;
-use log::debug;
+use crate::{
+    http::{
+        middleware::user::Auth,
+        models::{telemetry::PinResponse, HttpResult},
+    },
+    services::{
+        sessions::Sessions,
+        telemetry::{self, PinPayload, TelemetryStats},
+    },
+};
+use axum::{Extension, Json};
+use log::{debug, error};
+use sea_orm::DatabaseConnection;
+use std::sync::Arc;
 
 /// POST /pinEvents
 ///
-/// Recieves telemetry messages from the client always responding
-/// with an ok status
-///
-/// TODO: Log / save the messages sent to this endpoint (Its JSON just string is more readable)
-pub async fn pin_events(req: String) -> Json<PinResponse> {
+/// Recieves telemetry messages from the authenticated client always
+/// responding with an ok status, gameplay statistics from the messages
+/// are decoded and stored for the authenticated user
+pub async fn pin_events(
+    Auth(user): Auth,
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(sessions): Extension<Arc<Sessions>>,
+    req: String,
+) -> Json<PinResponse> {
     debug!("Event pinned: {}", req);
 
+    match serde_json::from_str::<PinPayload>(&req) {
+        Ok(payload) => {
+            if let Err(err) = telemetry::record_pin_events(&db, &sessions, &user, &payload).await {
+                error!("Failed to store pin event statistics: {}", err);
+            }
+        }
+        Err(err) => debug!("Failed to decode pin events: {}", err),
+    }
+
     Json(PinResponse {
         status: "ok".to_string(),
     })
 }
+
+/// GET /api/server/stats
+///
+/// Obtains the aggregate gameplay statistics decoded from
+/// the telemetry sent by clients
+pub async fn get_stats(
+    Auth(_): Auth,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<TelemetryStats> {
+    let stats = telemetry::stats(&db).await?;
+    Ok(Json(stats))
+}
@@ -0,0 +1,128 @@
+use crate::{
+    database::entity::{users::UserId, PlayerTitle, SharedData, User},
+    definitions::titles::PlayerTitles,
+    http::{
+        middleware::user::Auth,
+        models::{
+            titles::{
+                EquipTitleRequest, PlayerTitleWithUnlock, PublicProfileResponse, TitleError,
+                TitlesResponse,
+            },
+            DynHttpError, HttpResult,
+        },
+    },
+    services::{game::PlayerCosmetics, sessions::Sessions},
+};
+use axum::{extract::Path, Extension, Json};
+use hyper::StatusCode;
+use sea_orm::{DatabaseConnection, DbErr};
+use std::sync::Arc;
+
+/// GET /user/titles
+///
+/// Lists all the player titles along with which titles
+/// the user has earned and the equipped title
+pub async fn get_titles(
+    Auth(user): Auth,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<TitlesResponse> {
+    let unlocked = PlayerTitle::all(&db, &user).await?;
+
+    let list = PlayerTitles::get()
+        .values
+        .iter()
+        .map(|title| PlayerTitleWithUnlock {
+            title,
+            unlocked: unlocked
+                .iter()
+                .find(|unlocked| unlocked.name == title.name)
+                .map(|unlocked| unlocked.unlocked),
+        })
+        .collect();
+
+    Ok(Json(TitlesResponse {
+        active: user.title,
+        list,
+    }))
+}
+
+/// PUT /user/titles/active
+///
+/// Equips an earned title
+pub async fn equip_title(
+    Auth(user): Auth,
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(sessions): Extension<Arc<Sessions>>,
+    Json(req): Json<EquipTitleRequest>,
+) -> Result<StatusCode, DynHttpError> {
+    if PlayerTitles::get().by_name(&req.name).is_none() {
+        return Err(TitleError::UnknownTitle.into());
+    }
+
+    if !PlayerTitle::is_unlocked(&db, &user, &req.name).await? {
+        return Err(TitleError::TitleNotUnlocked.into());
+    }
+
+    let user = user.set_title(&db, Some(req.name)).await?;
+    update_session(&db, &sessions, user).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// DELETE /user/titles/active
+///
+/// Unequips the equipped title
+pub async fn unequip_title(
+    Auth(user): Auth,
+    Extension(db): Extension<DatabaseConnection>,
+    Extension(sessions): Extension<Arc<Sessions>>,
+) -> Result<StatusCode, DynHttpError> {
+    let user = user.set_title(&db, None).await?;
+    update_session(&db, &sessions, user).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Updates the connected session of the `user` so that the
+/// other players see the changed title
+async fn update_session(
+    db: &DatabaseConnection,
+    sessions: &Sessions,
+    user: User,
+) -> Result<(), DbErr> {
+    let Some(session) = sessions.lookup_session(user.id) else {
+        return Ok(());
+    };
+
+    let shared_data = SharedData::get(db, &user).await?;
+    let cosmetics = PlayerCosmetics::from_equipment(&shared_data.shared_equipment.list)
+        .with_title(user.title.clone());
+
+    session.set_user(user);
+    session.set_cosmetics(cosmetics);
+
+    Ok(())
+}
+
+/// GET /api/public/users/:id/profile
+///
+/// Obtains the public profile of a user
+pub async fn get_profile(
+    Path(user_id): Path<UserId>,
+    Extension(db): Extension<DatabaseConnection>,
+) -> HttpResult<PublicProfileResponse> {
+    let user = User::by_id(&db, user_id)
+        .await?
+        .ok_or(TitleError::UnknownUser)?;
+
+    let title = user
+        .title
+        .as_deref()
+        .and_then(|title| PlayerTitles::get().by_name(title));
+
+    Ok(Json(PublicProfileResponse {
+        id: user.id,
+        username: user.username,
+        title,
+    }))
+}
@@ -1,17 +1,32 @@
 use axum::Extension;
-use definitions::i18n::I18n;
-use definitions::strike_teams::StrikeTeams;
-use definitions::{
-    badges::Badges, challenges::Challenges, classes::Classes, items::Items,
-    level_tables::LevelTables, match_modifiers::MatchModifiers,
-};
-use log::error;
+use chrono::{DateTime, Utc};
+use config::Config;
 use log::LevelFilter;
-use services::mission::MissionBackgroundTask;
-use services::{game_manager::GameManager, sessions::Sessions};
+use log::{error, info, warn};
+use services::activity_buffer::ActivityBuffer;
+use services::economy_simulator;
+use services::feature_flags::FeatureFlags;
+use services::game_stats::GameStatsRecorder;
+use services::inventory_transfer::InventoryImports;
+use services::mission_results::MissionResultWorker;
+use services::public_stats::PublicStatsService;
+use services::replay_cache::ReplayCache;
+use services::restarts::RestartScheduler;
+use services::user_locks::UserLocks;
+use services::{
+    auth::AuthService, game_manager::GameManager, pocket_relay::PocketRelayService,
+    sessions::Sessions,
+};
+use services::{
+    leaderboard::LeaderboardBackgroundTask,
+    maintenance::{MaintenanceBackgroundTask, ReadOnlyMode},
+    mission::MissionBackgroundTask,
+};
 
 use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::process::ExitCode;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::join;
 use tokio::signal;
 use utils::{constants::SERVER_PORT, signing::SigningKey};
@@ -19,58 +34,251 @@ use utils::{constants::SERVER_PORT, signing::SigningKey};
 #[allow(unused)]
 mod blaze;
 
+mod config;
 mod database;
 mod definitions;
 mod http;
 mod services;
 mod utils;
 
+/// Counts allocations for the memory diagnostics
+#[global_allocator]
+static ALLOCATOR: utils::alloc::CountingAllocator = utils::alloc::CountingAllocator;
+
 /// The server version extracted from the Cargo.toml
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// Command line flag that validates the definitions and exits
+const CHECK_DEFINITIONS_FLAG: &str = "--check-definitions";
+
+/// Command line flag that applies the database migrations and exits
+const MIGRATE_ONLY_FLAG: &str = "--migrate-only";
+
+/// Command line flag that simulates pack openings and mission rewards
+/// then prints the report and exits
+const SIMULATE_ECONOMY_FLAG: &str = "--simulate-economy";
+
+/// Debug command line flag that freezes the clock at the RFC 3339 time
+/// following the flag, or at the current time when no time is provided
+const FREEZE_TIME_FLAG: &str = "--freeze-time";
+
+/// Debug command line flag that replays the packet capture file following
+/// the flag through the blaze router then exits
+#[cfg(feature = "packet-capture")]
+const REPLAY_CAPTURE_FLAG: &str = "--replay-capture";
+
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
     std::env::set_var("RUST_LOG", "tower_http=trace");
 
     utils::logging::setup(LevelFilter::Debug);
 
+    // Track uptime for the memory diagnostics
+    services::diagnostics::init();
+
+    // Load the server configuration, an invalid configuration stops the server
+    // rather than starting with the defaults
+    if let Err(err) = Config::init() {
+        error!("{:#}", err);
+        return ExitCode::FAILURE;
+    }
+
+    // Store crash reports for panics, must be after logging so panics are still logged
+    services::crash_reports::init();
+
+    // Allows reproducing time based behavior like rotation boundaries and mission expiration
+    let mut freeze_args = std::env::args().skip_while(|arg| arg != FREEZE_TIME_FLAG);
+    if freeze_args.next().is_some() {
+        let time = match freeze_args.next().filter(|arg| !arg.starts_with("--")) {
+            Some(value) => match DateTime::parse_from_rfc3339(&value) {
+                Ok(value) => value.with_timezone(&Utc),
+                Err(err) => {
+                    error!("Invalid {} time \"{}\": {}", FREEZE_TIME_FLAG, value, err);
+                    return ExitCode::FAILURE;
+                }
+            },
+            None => Utc::now(),
+        };
+
+        warn!(
+            "Clock is frozen at {}, time based behavior will not progress",
+            time
+        );
+        utils::clock::freeze(time);
+    }
+
+    // Operators editing definition files can check them without starting the server
+    if std::env::args().any(|arg| arg == CHECK_DEFINITIONS_FLAG) {
+        return match definitions::check() {
+            Ok(()) => {
+                info!("All definitions loaded successfully");
+                ExitCode::SUCCESS
+            }
+            Err(report) => {
+                error!("{}", report);
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    // Allows operators to upgrade the database separately from starting the server
+    if std::env::args().any(|arg| arg == MIGRATE_ONLY_FLAG) {
+        return match database::connect_database().await {
+            Ok(_) => {
+                info!("Database is up to date");
+                ExitCode::SUCCESS
+            }
+            Err(err) => {
+                error!("{}", err);
+                ExitCode::FAILURE
+            }
+        };
+    }
+
     // Pre-initialize all shared definitions
-    _ = Items::get();
-    _ = Classes::get();
-    _ = LevelTables::get();
-    _ = Challenges::get();
-    _ = I18n::get();
-    _ = Badges::get();
-    _ = MatchModifiers::get();
-    _ = StrikeTeams::get();
+    if let Err(report) = definitions::init() {
+        error!("{}", report);
+        return ExitCode::FAILURE;
+    }
+
+    // Operators tuning the economy can simulate the rewards without a database
+    if std::env::args().any(|arg| arg == SIMULATE_ECONOMY_FLAG) {
+        return match economy_simulator::run_command(std::env::args().skip(1)) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(err) => {
+                error!("Failed to simulate economy: {:#}", err);
+                ExitCode::FAILURE
+            }
+        };
+    }
 
     let (db, signing_key) = join!(crate::database::init(), SigningKey::global());
+    let db = match db {
+        Ok(value) => value,
+        Err(err) => {
+            error!("{}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // Import accounts from a Pocket Relay server database
+    if let Some(import) = &Config::get().pocket_relay_import {
+        if let Err(err) = services::pocket_relay_import::run(&db, import).await {
+            error!("Failed to import Pocket Relay accounts: {:#}", err);
+        }
+    }
+
+    // Process the missions that were interrupted when the server last stopped
+    services::shutdown::restore_missions(&db).await;
+
+    let sessions = Arc::new(Sessions::new(
+        signing_key,
+        Config::get().sessions.token_lifetime_hours * 60 * 60,
+    ));
+
+    let read_only = Arc::new(ReadOnlyMode::new(Config::get().maintenance.read_only));
 
     // Start the strike team mission background task
-    MissionBackgroundTask::new(db.clone()).start();
+    MissionBackgroundTask::new(db.clone(), sessions.clone(), read_only.clone()).start();
+
+    // Start the database maintenance background task
+    MaintenanceBackgroundTask::new(db.clone()).start();
+
+    // Start the leaderboard recalculation background task
+    LeaderboardBackgroundTask::new(db.clone()).start();
 
-    let game_manager = Arc::new(GameManager::new());
-    let sessions = Arc::new(Sessions::new(signing_key));
+    // Start the worker storing matchmaking and game statistics
+    let game_stats = GameStatsRecorder::start(db.clone());
+    let game_manager = Arc::new(GameManager::new(game_stats));
+    // Start the matchmaking queue background task
+    game_manager.start_matchmaking();
+    // Start writing the buffered activity progress
+    let activity_buffer = Arc::new(ActivityBuffer::new(db.clone()));
+    activity_buffer.start();
+    // Start the worker processing finished missions
+    let mission_results = Arc::new(MissionResultWorker::start(
+        db.clone(),
+        game_manager.clone(),
+        activity_buffer.clone(),
+        read_only.clone(),
+    ));
+    let auth = Arc::new(AuthService::new());
+    let pocket_relay = Arc::new(PocketRelayService::new());
+    let public_stats = Arc::new(PublicStatsService::new());
+    let user_locks = Arc::new(UserLocks::new());
+    let replay_cache = Arc::new(ReplayCache::new());
+    let inventory_imports = Arc::new(InventoryImports::new());
+    let feature_flags = Arc::new(FeatureFlags::new(Config::get().feature_flags.clone()));
+
+    // Start the scheduled restarts
+    let restarts = Arc::new(RestartScheduler::new());
+    restarts.start(sessions.clone(), game_manager.clone());
 
     let mut router = blaze::routes::router();
     router.add_extension(db.clone());
     router.add_extension(game_manager.clone());
+    router.add_extension(restarts.clone());
+    router.add_extension(sessions.clone());
     let router = router.build();
 
+    // Captured sessions can be replayed to diagnose unknown packet structures
+    #[cfg(feature = "packet-capture")]
+    if let Some(path) = std::env::args()
+        .skip_while(|arg| arg != REPLAY_CAPTURE_FLAG)
+        .nth(1)
+    {
+        let path = std::path::Path::new(&path);
+        return match blaze::capture::replay(path, &db, &router, sessions.clone()).await {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(err) => {
+                error!("Failed to replay packet capture: {:#}", err);
+                ExitCode::FAILURE
+            }
+        };
+    }
+
     let router = http::routes::router()
         .layer(Extension(router))
-        .layer(Extension(db))
-        .layer(Extension(game_manager))
-        .layer(Extension(sessions));
+        .layer(Extension(db.clone()))
+        .layer(Extension(game_manager.clone()))
+        .layer(Extension(sessions.clone()))
+        .layer(Extension(auth))
+        .layer(Extension(pocket_relay))
+        .layer(Extension(public_stats))
+        .layer(Extension(user_locks))
+        .layer(Extension(replay_cache))
+        .layer(Extension(inventory_imports))
+        .layer(Extension(feature_flags))
+        .layer(Extension(read_only))
+        .layer(Extension(activity_buffer.clone()))
+        .layer(Extension(mission_results.clone()));
 
     let addr: SocketAddr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, SERVER_PORT));
     if let Err(err) = axum::Server::bind(&addr)
-        .serve(router.into_make_service())
+        .serve(router.into_make_service_with_connect_info::<SocketAddr>())
         .with_graceful_shutdown(async move {
-            _ = signal::ctrl_c().await;
+            tokio::select! {
+                // Restarts warn the players themselves
+                _ = signal::ctrl_c() => services::shutdown::notify_sessions(&sessions),
+                _ = restarts.wait_for_restart() => {}
+            }
         })
         .await
     {
         error!("Failed to bind HTTP server on {}: {:?}", addr, err);
+        return ExitCode::FAILURE;
     }
+
+    // Finish storing the rewards for missions that were completed
+    if !mission_results.flush(Duration::from_secs(30)).await {
+        error!("Server stopped before all mission results were processed");
+    }
+
+    // Store the missions still in flight to be restored on the next startup
+    services::shutdown::persist_missions(&db, &game_manager, &activity_buffer).await;
+
+    // Write any activity progress still waiting in the buffer
+    activity_buffer.flush_all().await;
+
+    ExitCode::SUCCESS
 }
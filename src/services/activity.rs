@@ -6,21 +6,34 @@
 
 use crate::{
     database::entity::{
-        challenge_progress::{ChallengeCounterName, ChallengeId},
-        Currency, InventoryItem, User,
+        challenge_progress::{ChallengeCounterName, ChallengeId, CounterUpdateType},
+        characters::CharacterId,
+        currency::CurrencyType,
+        currency_ledger::CurrencyLedgerReason,
+        strike_teams::StrikeTeamId,
+        ChallengeProgress, Character, Currency, InventoryItem, PackOpening, StrikeTeam, User,
+        XpBoost,
     },
     definitions::{
-        characters::acquire_item_character,
-        classes::Classes,
+        characters::DuplicateCharacter,
+        classes::{ClassName, Classes},
+        item_effects::{item_effects, ItemEffect, ItemEventKind},
         items::{BaseCategory, Category, ItemDefinition, ItemName, Items},
-        level_tables::LevelTables,
-        packs::{GenerateError, ItemReward, Packs, RewardCollection},
+        packs::{GenerateError, Pack, Packs, RewardCollection},
         store_catalogs::{StoreArticleName, StoreCatalogs},
+        strike_teams::{StrikeTeamTraitAction, StrikeTeamTraitError, StrikeTeams},
+        titles::PlayerTitles,
+    },
+    services::{
+        game::{activity_challenge_progress, ChallengeProgressChange},
+        pack_experiments::{self, RarityCounts},
+        random::{RandomPurpose, RandomService},
+        rewards::{Entitlement, RewardBundle, RewardEngine},
     },
 };
 use log::debug;
 use rand::{rngs::StdRng, SeedableRng};
-use sea_orm::ConnectionTrait;
+use sea_orm::{ConnectionTrait, TransactionTrait};
 use serde::{ser::SerializeStruct, Deserialize, Serialize};
 use serde_json::Value;
 use std::{
@@ -55,6 +68,24 @@ pub enum ItemConsumeError {
 
     #[error(transparent)]
     GenerateError(#[from] GenerateError),
+
+    #[error("Unknown strike team {0}")]
+    UnknownStrikeTeam(StrikeTeamId),
+
+    #[error(transparent)]
+    StrikeTeamTrait(#[from] StrikeTeamTraitError),
+
+    #[error("Unknown item '{0}' granted by item effect")]
+    UnknownEffectItem(ItemName),
+
+    #[error("Unknown character {0}")]
+    UnknownCharacter(CharacterId),
+
+    #[error("Missing class '{0}'")]
+    MissingClass(ClassName),
+
+    #[error("XP boost already active")]
+    XpBoostActive,
 }
 
 impl ActivityService {
@@ -64,7 +95,7 @@ impl ActivityService {
         event: ActivityEvent,
     ) -> anyhow::Result<ActivityResult>
     where
-        C: ConnectionTrait + Send,
+        C: ConnectionTrait + TransactionTrait + Send,
     {
         let mut result = ActivityResult::default();
 
@@ -82,7 +113,7 @@ impl ActivityService {
         events: Vec<ActivityEvent>,
     ) -> anyhow::Result<ActivityResult>
     where
-        C: ConnectionTrait + Send,
+        C: ConnectionTrait + TransactionTrait + Send,
     {
         let mut result = ActivityResult::default();
 
@@ -107,10 +138,15 @@ impl ActivityService {
         result: &mut ActivityResult,
     ) -> anyhow::Result<()>
     where
-        C: ConnectionTrait + Send,
+        C: ConnectionTrait + TransactionTrait + Send,
     {
         debug!("Processing Activity: {:?}", event);
 
+        // Update the progress of any challenge tracking the event
+        for change in activity_challenge_progress(std::slice::from_ref(&event)) {
+            Self::apply_challenge_progress(db, user, &change, result).await?;
+        }
+
         match event.name {
             ActivityName::ItemConsumed => {
                 Self::process_item_consumed(db, user, event, result).await?;
@@ -128,10 +164,81 @@ impl ActivityService {
             ActivityName::PrestigeLevelUp => {}
             ActivityName::PathfinderRatingUpdated => {}
             ActivityName::StrikeTeamRecruited => {}
+            ActivityName::EquipmentSetCompleted => {}
             ActivityName::Named(_) => {}
         }
 
-        // TODO: Update challenges
+        Ok(())
+    }
+
+    /// Stores the challenge progress `change` giving the challenge
+    /// rewards for each completion
+    pub async fn apply_challenge_progress<'db, C>(
+        db: &'db C,
+        user: &User,
+        change: &ChallengeProgressChange,
+        result: &mut ActivityResult,
+    ) -> anyhow::Result<()>
+    where
+        C: ConnectionTrait + TransactionTrait + Send,
+    {
+        let item_definitions = Items::get();
+        let definition = change.definition;
+
+        let (model, counter, change_type, completions) =
+            ChallengeProgress::update(db, user, change).await?;
+
+        let mut rewards = RewardBundle::default();
+
+        // Give the challenge rewards for each completion
+        for _ in 0..completions {
+            if let Some(points) = definition.point_value {
+                rewards.add_currency(CurrencyType::ChallengePoints, points);
+            }
+
+            for currency in &definition.reward.currencies {
+                rewards.add_currency(currency.name, currency.value);
+            }
+
+            for reward in &definition.reward.items {
+                if let Some(item_definition) = item_definitions.by_name(&reward.name) {
+                    rewards.add_item(item_definition, reward.count);
+                }
+            }
+        }
+
+        if completions > 0 {
+            for flair in &definition.reward.flair {
+                rewards.add_entitlement(Entitlement::StrikeTeamFlair(flair.clone()));
+            }
+
+            for title in PlayerTitles::get().by_challenge(&definition.name) {
+                rewards.add_entitlement(Entitlement::PlayerTitle(title.name.clone()));
+            }
+        }
+
+        RewardEngine::grant(
+            db,
+            user,
+            rewards,
+            CurrencyLedgerReason::ChallengeReward,
+            result,
+        )
+        .await?;
+
+        result.challeges_completed += completions;
+        result.challenges_updated.push(ChallengeUpdated {
+            challenge_id: model.challenge_id,
+            counters: vec![ChallengeUpdateCounter {
+                name: counter.name,
+                current_count: counter.current_count,
+            }],
+            status_change: match change_type {
+                CounterUpdateType::Changed => ChallengeStatusChange::Changed,
+                CounterUpdateType::Created => ChallengeStatusChange::Notify,
+            },
+        });
+
         Ok(())
     }
 
@@ -142,12 +249,10 @@ impl ActivityService {
         result: &mut ActivityResult,
     ) -> anyhow::Result<()>
     where
-        C: ConnectionTrait + Send,
+        C: ConnectionTrait + TransactionTrait + Send,
     {
         let catalogs = StoreCatalogs::get();
         let item_definitions = Items::get();
-        let classes = Classes::get();
-        let level_tables = LevelTables::get();
 
         let article_name: StoreArticleName = event.attribute_uuid("articleName")?;
         let stack_size: u32 = event.attribute_u32("count")?;
@@ -165,26 +270,55 @@ impl ActivityService {
             .ok_or(ArticlePurchaseError::UnknownArticleItem)?;
 
         // Give the user the article item
-        {
-            // TODO: Check that the user hasn't already reached the item capacity
-
-            let item = InventoryItem::add_item(
-                db,
-                user,
-                item_definition.name,
-                stack_size,
-                item_definition.capacity,
+        let mut rewards = RewardBundle::default();
+        rewards.add_item(item_definition, stack_size);
+
+        RewardEngine::grant(
+            db,
+            user,
+            rewards,
+            CurrencyLedgerReason::StorePurchase,
+            result,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Applies the trait `action` from a consumed trait item to
+    /// the strike team with the provided `team_id`
+    async fn process_trait_item<'db, C>(
+        db: &'db C,
+        user: &User,
+        team_id: StrikeTeamId,
+        action: StrikeTeamTraitAction,
+    ) -> anyhow::Result<()>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let team = StrikeTeam::get_by_id(db, user, team_id)
+            .await?
+            .ok_or(ItemConsumeError::UnknownStrikeTeam(team_id))?;
+
+        let mut positive = team.positive_traits.0.clone();
+        let mut negative = team.negative_traits.0.clone();
+
+        let mut rng = RandomService::get().rng(RandomPurpose::StrikeTeamTraits);
+        action
+            .apply(
+                &mut rng,
+                &StrikeTeams::get().traits,
+                &mut positive,
+                &mut negative,
             )
-            .await?;
+            .map_err(ItemConsumeError::from)?;
 
-            result.add_item(item, stack_size, item_definition);
+        debug!(
+            "Applied trait action {:?} to strike team {}",
+            action, team.id
+        );
 
-            // Handle character creation for character items
-            if item_definition.category.base_eq(&BaseCategory::Characters) {
-                acquire_item_character(db, user, &item_definition.name, classes, level_tables)
-                    .await?;
-            }
-        }
+        team.set_traits(db, positive, negative).await?;
 
         Ok(())
     }
@@ -198,18 +332,16 @@ impl ActivityService {
         result: &mut ActivityResult,
     ) -> anyhow::Result<()>
     where
-        C: ConnectionTrait + Send,
+        C: ConnectionTrait + TransactionTrait + Send,
     {
         let item_definitions = Items::get();
-        let classes = Classes::get();
-        let level_tables = LevelTables::get();
         let packs = Packs::get();
 
         let category: Category = event.attribute_parsed("category")?;
         let definition_name: ItemName = event.attribute_uuid("definitionName")?;
         let _count: u32 = event.attribute_u32("count")?;
 
-        let mut rewards: RewardCollection = RewardCollection::default();
+        let mut rewards = RewardBundle::default();
 
         match category.base() {
             BaseCategory::ItemPack => {
@@ -218,43 +350,173 @@ impl ActivityService {
                     .by_name(&definition_name)
                     .ok_or(ItemConsumeError::PackNotImplemented(definition_name))?;
 
-                // Create a random generator
-                let mut rng = StdRng::from_entropy();
-
                 // Generate colleciton of rewards
-                pack.generate_rewards(db, user, &mut rng, item_definitions, &mut rewards)
-                    .await
-                    .map_err(ItemConsumeError::GenerateError)?;
+                Self::open_pack(db, user, pack, &mut rewards).await?;
             }
 
             BaseCategory::ApexPoints => {
                 // TODO: Apex point awards
             }
             BaseCategory::StrikeTeamReward => {
+                // Trait items change the traits of the targeted strike team
+                if let Some(action) = item_definitions
+                    .by_name(&definition_name)
+                    .and_then(|definition| definition.attributes.strike_team_trait_action)
+                {
+                    let team_id: StrikeTeamId = event.attribute_u32("targetId")?;
+                    Self::process_trait_item(db, user, team_id, action).await?;
+                }
+
                 // TODO: Strike team rewards
             }
             BaseCategory::Consumable => {}
             BaseCategory::Boosters => {}
+            // Capacity increases aren't consumable, the client applies them
+            // from the owned stack size (Up to the item cap) so there is no
+            // effect to execute. Only the respec item in this category is
+            // consumed which is handled by its item effect
             BaseCategory::CapacityUpgrade => {}
 
             _ => {}
         }
 
-        for reward in rewards.rewards {
-            let ItemReward {
-                definition,
-                stack_size,
-            } = reward;
+        // Execute the effects of consuming the item
+        if let Some(definition) = item_definitions.by_name(&definition_name) {
+            let effects = item_effects(definition, ItemEventKind::Consume);
+            Self::process_item_effects(db, user, &event, &effects, &mut rewards).await?;
+
+            // Removal effects are executed once the last of the item is consumed
+            let remaining = InventoryItem::get_by_name(db, user, definition.name)
+                .await?
+                .map(|item| item.stack_size)
+                .unwrap_or_default();
+            if remaining == 0 {
+                let effects = item_effects(definition, ItemEventKind::Remove);
+                Self::process_item_effects(db, user, &event, &effects, &mut rewards).await?;
+            }
+        }
+
+        let granted =
+            RewardEngine::grant(db, user, rewards, CurrencyLedgerReason::ItemEffect, result)
+                .await?;
 
-            let item =
-                InventoryItem::add_item(db, user, definition.name, stack_size, definition.capacity)
-                    .await?;
+        // Execute the effects of the items being added, items granted by these
+        // effects don't execute their own effects to prevent endless chains
+        let mut added_rewards = RewardBundle::default();
+        for reward in granted.items {
+            let effects = item_effects(reward.definition, ItemEventKind::Add);
+            Self::process_item_effects(db, user, &event, &effects, &mut added_rewards).await?;
+        }
 
-            result.add_item(item, stack_size, definition);
+        RewardEngine::grant(
+            db,
+            user,
+            added_rewards,
+            CurrencyLedgerReason::ItemEffect,
+            result,
+        )
+        .await?;
 
-            // Handle character creation for character items
-            if definition.category.base_eq(&BaseCategory::Characters) {
-                acquire_item_character(db, user, &definition.name, classes, level_tables).await?;
+        Ok(())
+    }
+
+    /// Opens the `pack` for the `user` adding the generated items to `rewards`,
+    /// packs covered by a drop-rate experiment use the weights for the group
+    /// the user is placed in. The opening is recorded in the pack history
+    async fn open_pack<C>(
+        db: &C,
+        user: &User,
+        pack: &Pack,
+        rewards: &mut RewardBundle,
+    ) -> anyhow::Result<()>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let assignment = pack_experiments::assign(&pack.name, user.id);
+        let weights = assignment.as_ref().and_then(|value| value.weights());
+
+        // Seed is recorded so the opening can be replayed
+        let seed = RandomService::get().seed(RandomPurpose::PackOpening);
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut generated = RewardCollection::default();
+        pack.generate_rewards(db, user, &mut rng, Items::get(), weights, &mut generated)
+            .await
+            .map_err(ItemConsumeError::GenerateError)?;
+
+        let experiment = assignment.map(|value| (value.experiment.name.clone(), value.treatment));
+        PackOpening::create(
+            db,
+            user,
+            pack.name,
+            experiment,
+            RarityCounts::from_rewards(&generated),
+            seed,
+        )
+        .await?;
+
+        for reward in generated.rewards {
+            rewards.add_item(reward.definition, reward.stack_size);
+        }
+
+        Ok(())
+    }
+
+    /// Executes the item `effects` for the item from the `event`, rewards
+    /// granted by the effects are added to `rewards`
+    async fn process_item_effects<'db, C>(
+        db: &'db C,
+        user: &User,
+        event: &ActivityEvent,
+        effects: &[ItemEffect],
+        rewards: &mut RewardBundle,
+    ) -> anyhow::Result<()>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let item_definitions = Items::get();
+
+        for effect in effects {
+            match effect {
+                ItemEffect::GrantPack { pack } => {
+                    let pack = Packs::get()
+                        .by_name(pack)
+                        .ok_or(ItemConsumeError::PackNotImplemented(*pack))?;
+
+                    Self::open_pack(db, user, pack, rewards).await?;
+                }
+                ItemEffect::GrantItem { item, count } => {
+                    let definition = item_definitions
+                        .by_name(item)
+                        .ok_or(ItemConsumeError::UnknownEffectItem(*item))?;
+                    rewards.add_item(definition, *count);
+                }
+                ItemEffect::GrantCurrency { currency, amount } => {
+                    rewards.add_currency(*currency, *amount);
+                }
+                ItemEffect::GrantXp { amount } => {
+                    let character_id: CharacterId = event.attribute_u32("targetId")?;
+                    let character = Character::find_by_id_user(db, user, character_id)
+                        .await?
+                        .ok_or(ItemConsumeError::UnknownCharacter(character_id))?;
+
+                    rewards.add_xp(character, *amount);
+                }
+                ItemEffect::XpBoost { percent } => {
+                    if !XpBoost::activate(db, user, *percent).await? {
+                        return Err(ItemConsumeError::XpBoostActive.into());
+                    }
+                }
+                ItemEffect::Respec => {
+                    let character_id: CharacterId = event.attribute_u32("targetId")?;
+                    let character = Character::find_by_id_user(db, user, character_id)
+                        .await?
+                        .ok_or(ItemConsumeError::UnknownCharacter(character_id))?;
+                    let class = Classes::get()
+                        .by_name(&character.class_name)
+                        .ok_or(ItemConsumeError::MissingClass(character.class_name))?;
+
+                    character.respec(db, class.skill_trees.clone()).await?;
+                }
             }
         }
 
@@ -352,6 +614,13 @@ pub enum ActivityName {
     /// - count (number)
     #[serde(rename = "_strikeTeamRecruited")]
     StrikeTeamRecruited,
+    /// Every item in an equipment set was equipped
+    ///
+    /// Known attributes:
+    /// - setName (string)
+    /// - count (number)
+    #[serde(rename = "_equipmentSetCompleted")]
+    EquipmentSetCompleted,
     /// Activity represented by a [Uuid] these events can be
     /// published by clients
     #[serde(untagged)]
@@ -672,6 +941,9 @@ pub struct ActivityResult {
 
     /// Prestige progression that resulted from the activity
     pub prestige_progression: PrestigeProgression,
+
+    /// Duplicate character items that were converted into rewards
+    pub duplicate_characters: Vec<DuplicateCharacter>,
 }
 
 impl ActivityResult {
@@ -688,6 +960,46 @@ impl ActivityResult {
         self.items_earned.push(item);
         self.item_definitions.push(definition);
     }
+
+    /// Adds an earned `amount` of the `ty` currency to the result
+    pub fn add_currency(&mut self, user: &User, ty: CurrencyType, amount: u32) {
+        match self.currency_earned.iter_mut().find(|value| value.ty == ty) {
+            Some(existing) => existing.balance = existing.balance.saturating_add(amount),
+            None => self.currency_earned.push(Currency {
+                user_id: user.id,
+                ty,
+                balance: amount,
+            }),
+        }
+    }
+
+    /// Merges the items, currencies and duplicate character conversions
+    /// from `other` into this result, stacks of the same item are combined
+    pub fn merge(&mut self, user: &User, other: ActivityResult) {
+        for (item, definition) in other.items_earned.into_iter().zip(other.item_definitions) {
+            match self
+                .items_earned
+                .iter_mut()
+                .find(|existing| existing.id == item.id)
+            {
+                Some(existing) => {
+                    existing.stack_size = existing.stack_size.saturating_add(item.stack_size)
+                }
+                None => {
+                    self.items_earned.push(item);
+                    self.item_definitions.push(definition);
+                }
+            }
+        }
+
+        for currency in other.currency_earned {
+            self.add_currency(user, currency.ty, currency.balance);
+        }
+
+        self.challeges_completed += other.challeges_completed;
+        self.challenges_updated.extend(other.challenges_updated);
+        self.duplicate_characters.extend(other.duplicate_characters);
+    }
 }
 
 impl Serialize for ActivityResult {
@@ -695,7 +1007,7 @@ impl Serialize for ActivityResult {
     where
         S: serde::Serializer,
     {
-        let mut value = serializer.serialize_struct("ActivityResult", 18)?;
+        let mut value = serializer.serialize_struct("ActivityResult", 19)?;
         value.serialize_field("previousXp", &self.previous_xp)?;
         value.serialize_field("xp", &self.current_xp)?;
         value.serialize_field("xpGained", &self.gained_xp)?;
@@ -727,6 +1039,11 @@ impl Serialize for ActivityResult {
         value.serialize_field("itemDefinitions", &self.item_definitions)?;
         value.serialize_field("entitlementsGranted", &self.entitlements_granted)?;
         value.serialize_field("prestigeProgressionMap", &self.prestige_progression)?;
+
+        if !self.duplicate_characters.is_empty() {
+            value.serialize_field("duplicateCharacters", &self.duplicate_characters)?;
+        }
+
         value.end()
     }
 }
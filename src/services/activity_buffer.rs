@@ -0,0 +1,289 @@
+//! Write-behind buffer for the challenge progress from activities reported
+//! during matches. Players report activities many times throughout a match
+//! so the progress is combined per user and written in batches instead of
+//! writing each report.
+//!
+//! Buffered progress is written on an interval, when a user has too many
+//! buffered updates, when the mission finishes, and when the server shuts
+//! down gracefully. The buffer only lives in memory so progress reported
+//! since the last write is lost if the server crashes. Progress written
+//! during a mission is tracked so that it can be removed from the mission
+//! results which report the same activities.
+//!
+//! Writes for a user are serialized, the pending progress is swapped out
+//! under the lock and activities reported while it is being written are
+//! buffered for the next write. A mission finishing waits for any write
+//! already in flight so its results see all of the written progress.
+//!
+//! Community challenges aren't buffered, their progress is shared between
+//! the players when the mission finishes
+
+use super::{
+    activity::{ActivityEvent, ActivityResult, ActivityService},
+    crash_reports::{self, CrashContext},
+    game::{
+        activity_challenge_progress, merge_challenge_progress, ChallengeProgressChange, GameID,
+    },
+    user_locks::UserLocks,
+};
+use crate::{
+    config::Config,
+    database::entity::{users::UserId, User},
+    utils::hashing::IntHashMap,
+};
+use log::{debug, error};
+use parking_lot::Mutex;
+use sea_orm::{DatabaseConnection, TransactionError, TransactionTrait};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tokio::time::{interval, Instant};
+
+/// Duration after the last report that the progress written for a game
+/// is forgotten, missions don't last this long so the game is over
+const STALE_DURATION: Duration = Duration::from_secs(60 * 60 * 3);
+
+/// Buffered progress for a single user
+struct UserBuffer {
+    /// Game the activities were reported in
+    game_id: Option<GameID>,
+    /// Progress waiting to be written
+    pending: Vec<ChallengeProgressChange>,
+    /// Number of updates combined into the pending progress
+    pending_updates: usize,
+    /// Progress already written during the current game
+    applied: Vec<ChallengeProgressChange>,
+    /// When the user last reported activities
+    last_report: Instant,
+}
+
+impl UserBuffer {
+    fn new(game_id: Option<GameID>) -> Self {
+        Self {
+            game_id,
+            pending: Vec::new(),
+            pending_updates: 0,
+            applied: Vec::new(),
+            last_report: Instant::now(),
+        }
+    }
+}
+
+/// Buffer of the challenge progress waiting to be written
+pub struct ActivityBuffer {
+    /// Database connection the progress is written to
+    db: DatabaseConnection,
+    /// Buffered progress for each user
+    users: Mutex<IntHashMap<UserId, UserBuffer>>,
+    /// Locks held while writing the progress of a user so only one
+    /// write for the user is in flight at a time
+    flushes: UserLocks,
+}
+
+impl ActivityBuffer {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self {
+            db,
+            users: Default::default(),
+            flushes: UserLocks::new(),
+        }
+    }
+
+    /// Starts writing the buffered progress on the configured interval
+    pub fn start(self: &Arc<Self>) {
+        let buffer = self.clone();
+        let config = &Config::get().activity_buffer;
+        // Prevent an interval of zero from constantly running
+        let period = Duration::from_secs(config.flush_interval_secs.max(1));
+
+        let context = CrashContext::new("activity buffer");
+        tokio::spawn(crash_reports::scope(context, async move {
+            let mut interval = interval(period);
+            loop {
+                interval.tick().await;
+                buffer.flush_all().await;
+                buffer.remove_stale();
+            }
+        }));
+    }
+
+    /// Buffers the challenge progress from the `activities` reported by
+    /// the user while in the game with the provided `game_id`
+    pub async fn push(
+        &self,
+        user_id: UserId,
+        game_id: Option<GameID>,
+        activities: &[ActivityEvent],
+    ) {
+        let mut changes = activity_challenge_progress(activities);
+        changes.retain(|change| !change.definition.community);
+
+        if changes.is_empty() {
+            return;
+        }
+
+        let flush = {
+            let users = &mut *self.users.lock();
+            let buffer = users
+                .entry(user_id)
+                .or_insert_with(|| UserBuffer::new(game_id));
+
+            // Progress applied in a previous game isn't part of this game's results
+            if buffer.game_id != game_id {
+                buffer.game_id = game_id;
+                buffer.applied.clear();
+            }
+
+            buffer.last_report = Instant::now();
+            buffer.pending_updates += changes.len();
+            for change in changes {
+                merge_challenge_progress(&mut buffer.pending, change);
+            }
+
+            buffer.pending_updates >= Config::get().activity_buffer.max_pending_updates
+        };
+
+        if flush {
+            self.flush_user(user_id).await;
+        }
+    }
+
+    /// Writes the buffered progress of the `user_ids` that played in the
+    /// game with the provided `game_id`, responds with the progress that
+    /// was written during the game for each user
+    pub async fn finish_mission(
+        &self,
+        game_id: GameID,
+        user_ids: &[UserId],
+    ) -> HashMap<UserId, Vec<ChallengeProgressChange>> {
+        let mut applied = HashMap::with_capacity(user_ids.len());
+
+        for user_id in user_ids {
+            self.flush_user(*user_id).await;
+
+            let users = &mut *self.users.lock();
+            let Some(buffer) = users.get_mut(user_id) else {
+                continue;
+            };
+
+            if buffer.game_id == Some(game_id) {
+                applied.insert(*user_id, std::mem::take(&mut buffer.applied));
+            }
+
+            if buffer.pending.is_empty() {
+                users.remove(user_id);
+            }
+        }
+
+        applied
+    }
+
+    /// Writes the buffered progress for every user, called on the interval
+    /// and before the server exits gracefully
+    pub async fn flush_all(&self) {
+        let user_ids: Vec<UserId> = self
+            .users
+            .lock()
+            .iter()
+            .filter(|(_, buffer)| !buffer.pending.is_empty())
+            .map(|(user_id, _)| *user_id)
+            .collect();
+
+        if user_ids.is_empty() {
+            return;
+        }
+
+        debug!(
+            "Writing buffered activity progress for {} user(s)",
+            user_ids.len()
+        );
+
+        for user_id in user_ids {
+            self.flush_user(user_id).await;
+        }
+    }
+
+    /// Writes the buffered progress for the user with the provided `user_id`,
+    /// progress that fails to be written is kept to be retried. Waits for
+    /// any write already in flight for the user before swapping out the
+    /// pending progress
+    async fn flush_user(&self, user_id: UserId) {
+        let _guard = self.flushes.lock(user_id).await;
+
+        let pending = {
+            let users = &mut *self.users.lock();
+            let Some(buffer) = users.get_mut(&user_id) else {
+                return;
+            };
+            buffer.pending_updates = 0;
+            std::mem::take(&mut buffer.pending)
+        };
+
+        if pending.is_empty() {
+            return;
+        }
+
+        let result = write_progress(&self.db, user_id, pending.clone()).await;
+
+        let users = &mut *self.users.lock();
+
+        match result {
+            Ok(()) => {
+                let Some(buffer) = users.get_mut(&user_id) else {
+                    return;
+                };
+                for change in pending {
+                    merge_challenge_progress(&mut buffer.applied, change);
+                }
+            }
+            Err(err) => {
+                error!(
+                    "Failed to write buffered activity progress for {}: {}",
+                    user_id, err
+                );
+
+                // The user may have been removed while writing
+                let buffer = users
+                    .entry(user_id)
+                    .or_insert_with(|| UserBuffer::new(None));
+                for change in pending {
+                    merge_challenge_progress(&mut buffer.pending, change);
+                }
+            }
+        }
+    }
+
+    /// Removes the users that haven't reported activities recently
+    /// and don't have any progress waiting to be written
+    fn remove_stale(&self) {
+        self.users.lock().retain(|_, buffer| {
+            !buffer.pending.is_empty() || buffer.last_report.elapsed() < STALE_DURATION
+        });
+    }
+}
+
+/// Writes the challenge progress `changes` for the user with the
+/// provided `user_id` within a single transaction
+async fn write_progress(
+    db: &DatabaseConnection,
+    user_id: UserId,
+    changes: Vec<ChallengeProgressChange>,
+) -> anyhow::Result<()> {
+    // Progress for deleted users is dropped
+    let Some(user) = User::by_id(db, user_id).await? else {
+        return Ok(());
+    };
+
+    db.transaction(|db| {
+        Box::pin(async move {
+            let mut result = ActivityResult::default();
+            for change in &changes {
+                ActivityService::apply_challenge_progress(db, &user, change, &mut result).await?;
+            }
+            Ok::<_, anyhow::Error>(())
+        })
+    })
+    .await
+    .map_err(|err| match err {
+        TransactionError::Connection(err) => err.into(),
+        TransactionError::Transaction(err) => err,
+    })
+}
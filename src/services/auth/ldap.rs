@@ -0,0 +1,141 @@
+//! Authentication provider for accounts from an LDAP directory, users
+//! are authenticated by binding as their own DN
+
+use super::{
+    link_external_user, resolve_external_user, AuthProvider, AuthProviderError, ExternalIdentity,
+};
+use crate::{config::LdapConfig, database::entity::User};
+use anyhow::Context;
+use futures::future::BoxFuture;
+use ldap3::{dn_escape, LdapConnAsync, LdapError, Scope, SearchEntry};
+use log::warn;
+use sea_orm::DatabaseConnection;
+
+/// Name of the provider used when linking identities
+const PROVIDER_NAME: &str = "ldap";
+
+/// LDAP result code for invalid credentials
+const INVALID_CREDENTIALS: u32 = 49;
+
+/// Provider authenticating against an LDAP directory
+pub struct LdapProvider {
+    /// The LDAP configuration
+    config: &'static LdapConfig,
+}
+
+impl LdapProvider {
+    pub fn new(config: &'static LdapConfig) -> Self {
+        Self { config }
+    }
+
+    /// Binds as the user with the provided `email` and `password`
+    /// and loads their identity
+    async fn bind_identity(
+        &self,
+        email: &str,
+        password: &str,
+    ) -> Result<ExternalIdentity, AuthProviderError> {
+        let config = self.config;
+
+        let (conn, mut ldap) = LdapConnAsync::new(&config.url)
+            .await
+            .context("Failed to connect to LDAP server")?;
+        ldap3::drive!(conn);
+
+        let dn = config.bind_dn.replace("{email}", &dn_escape(email));
+
+        match ldap
+            .simple_bind(&dn, password)
+            .await
+            .and_then(|result| result.success())
+        {
+            Ok(_) => {}
+            Err(LdapError::LdapResult { result }) if result.rc == INVALID_CREDENTIALS => {
+                return Err(AuthProviderError::InvalidCredentials)
+            }
+            Err(err) => return Err(anyhow::Error::new(err).context("Failed LDAP bind").into()),
+        }
+
+        let (entries, _) = ldap
+            .search(
+                &dn,
+                Scope::Base,
+                "(objectClass=*)",
+                vec![
+                    config.email_attribute.as_str(),
+                    config.username_attribute.as_str(),
+                ],
+            )
+            .await
+            .and_then(|result| result.success())
+            .context("Failed to search LDAP user")?;
+
+        if let Err(err) = ldap.unbind().await {
+            warn!("Failed to unbind from LDAP server: {}", err);
+        }
+
+        let entry = entries
+            .into_iter()
+            .next()
+            .map(SearchEntry::construct)
+            .ok_or(AuthProviderError::AccountNotFound)?;
+
+        let attribute = |name: &str| -> Option<String> {
+            entry
+                .attrs
+                .get(name)
+                .and_then(|values| values.first())
+                .cloned()
+        };
+
+        // The login email is only verified as a login name, not as an email
+        let (email, email_verified) = match attribute(&config.email_attribute) {
+            Some(value) => (value, config.verified_emails),
+            None => (email.to_string(), false),
+        };
+
+        Ok(ExternalIdentity {
+            email,
+            email_verified,
+            username: attribute(&config.username_attribute),
+            subject: entry.dn,
+        })
+    }
+}
+
+impl AuthProvider for LdapProvider {
+    fn authenticate<'a>(
+        &'a self,
+        db: &'a DatabaseConnection,
+        email: &'a str,
+        password: &'a str,
+    ) -> BoxFuture<'a, Result<User, AuthProviderError>> {
+        Box::pin(async move {
+            // Empty passwords would perform an unauthenticated bind
+            if password.is_empty() {
+                return Err(AuthProviderError::InvalidCredentials);
+            }
+
+            let identity = self.bind_identity(email, password).await?;
+            resolve_external_user(db, PROVIDER_NAME, identity).await
+        })
+    }
+
+    fn link<'a>(
+        &'a self,
+        db: &'a DatabaseConnection,
+        user: &'a User,
+        email: &'a str,
+        password: &'a str,
+    ) -> BoxFuture<'a, Result<(), AuthProviderError>> {
+        Box::pin(async move {
+            // Empty passwords would perform an unauthenticated bind
+            if password.is_empty() {
+                return Err(AuthProviderError::InvalidCredentials);
+            }
+
+            let identity = self.bind_identity(email, password).await?;
+            link_external_user(db, user, PROVIDER_NAME, identity.subject).await
+        })
+    }
+}
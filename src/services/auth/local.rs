@@ -0,0 +1,37 @@
+//! Authentication provider for local accounts with passwords
+//! stored in the database
+
+use super::{AuthProvider, AuthProviderError};
+use crate::{database::entity::User, utils::hashing::verify_password};
+use futures::future::BoxFuture;
+use sea_orm::DatabaseConnection;
+
+/// Provider authenticating against the local user passwords
+pub struct LocalProvider;
+
+impl AuthProvider for LocalProvider {
+    fn authenticate<'a>(
+        &'a self,
+        db: &'a DatabaseConnection,
+        email: &'a str,
+        password: &'a str,
+    ) -> BoxFuture<'a, Result<User, AuthProviderError>> {
+        Box::pin(async move {
+            // Find the user requested
+            let user = User::by_email(db, email)
+                .await?
+                .ok_or(AuthProviderError::AccountNotFound)?;
+
+            // Ensure the passwords match
+            if !verify_password(password, &user.password) {
+                return Err(AuthProviderError::InvalidCredentials);
+            }
+
+            Ok(user)
+        })
+    }
+
+    fn allows_registration(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,282 @@
+//! Authentication service, verifies user logins using the authentication
+//! provider selected in the server configuration.
+//!
+//! External providers (LDAP, OAuth) provide identities which are mapped to
+//! local user accounts, accounts are created for identities that haven't
+//! logged in before. Identities are only linked to an existing account with
+//! the same email when the provider has verified the email, otherwise the
+//! user must login to the account and link the identity themselves
+
+use crate::{
+    config::{AuthProviderConfig, Config},
+    database::entity::{users::CreateUser, Currency, SharedData, User, UserIdentity},
+    definitions::{strike_teams::create_user_strike_team, welcome_kit::WelcomeKit},
+};
+use futures::future::BoxFuture;
+use log::debug;
+use sea_orm::{DatabaseConnection, DbErr, TransactionError, TransactionTrait};
+use thiserror::Error;
+
+mod ldap;
+mod local;
+mod oauth;
+
+/// Maximum length of a username
+const MAX_USERNAME_LENGTH: usize = 16;
+
+/// Errors that can occur while authenticating
+#[derive(Debug, Error)]
+pub enum AuthProviderError {
+    /// Account for the login doesn't exist
+    #[error("Account not found")]
+    AccountNotFound,
+    /// The login credentials were incorrect
+    #[error("Incorrect password")]
+    InvalidCredentials,
+    /// An account already exists with the unverified email of the identity
+    #[error("An account with this email already exists, login to it and link your account")]
+    AccountExists,
+    /// The identity is linked to another user
+    #[error("Account is already linked to another user")]
+    LinkedToOther,
+    /// The user already has an identity from the provider linked
+    #[error("Account is already linked")]
+    AlreadyLinked,
+    /// The provider doesn't support linking identities
+    #[error("Account linking is not supported")]
+    LinkingUnsupported,
+    /// Database error occurred
+    #[error(transparent)]
+    Database(#[from] DbErr),
+    /// Error from the external provider or while creating the account
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+/// Identity of a user from an external authentication provider
+#[derive(Debug)]
+pub struct ExternalIdentity {
+    /// Unique identifier for the user within the provider
+    pub subject: String,
+    /// Email of the user
+    pub email: String,
+    /// Whether the provider has verified that the user owns the email
+    pub email_verified: bool,
+    /// Preferred username of the user
+    pub username: Option<String>,
+}
+
+/// Trait implemented by authentication providers
+pub trait AuthProvider: Send + Sync {
+    /// Authenticates the user with the provided `email` and `password`
+    fn authenticate<'a>(
+        &'a self,
+        db: &'a DatabaseConnection,
+        email: &'a str,
+        password: &'a str,
+    ) -> BoxFuture<'a, Result<User, AuthProviderError>>;
+
+    /// Links the identity for the provided `email` and `password` to the
+    /// existing `user`, only supported by external providers
+    fn link<'a>(
+        &'a self,
+        _db: &'a DatabaseConnection,
+        _user: &'a User,
+        _email: &'a str,
+        _password: &'a str,
+    ) -> BoxFuture<'a, Result<(), AuthProviderError>> {
+        Box::pin(async { Err(AuthProviderError::LinkingUnsupported) })
+    }
+
+    /// Whether accounts can be created through the server, external
+    /// providers manage their own accounts
+    fn allows_registration(&self) -> bool {
+        false
+    }
+}
+
+/// Service for authenticating users with the configured provider
+pub struct AuthService {
+    /// The configured provider
+    provider: Box<dyn AuthProvider>,
+}
+
+impl AuthService {
+    /// Creates a new authentication service using the provider
+    /// from the server configuration
+    pub fn new() -> Self {
+        let provider: Box<dyn AuthProvider> = match &Config::get().auth {
+            AuthProviderConfig::Local => Box::new(local::LocalProvider),
+            AuthProviderConfig::Ldap(config) => Box::new(ldap::LdapProvider::new(config)),
+            AuthProviderConfig::OAuth(config) => Box::new(oauth::OAuthProvider::new(config)),
+        };
+
+        Self { provider }
+    }
+
+    /// Authenticates the user with the provided `email` and `password`
+    pub async fn authenticate(
+        &self,
+        db: &DatabaseConnection,
+        email: &str,
+        password: &str,
+    ) -> Result<User, AuthProviderError> {
+        self.provider.authenticate(db, email, password).await
+    }
+
+    /// Links the external identity for the `email` and `password` to the
+    /// authenticated `user`
+    pub async fn link(
+        &self,
+        db: &DatabaseConnection,
+        user: &User,
+        email: &str,
+        password: &str,
+    ) -> Result<(), AuthProviderError> {
+        self.provider.link(db, user, email, password).await
+    }
+
+    /// Whether accounts can be created through the server
+    pub fn allows_registration(&self) -> bool {
+        self.provider.allows_registration()
+    }
+}
+
+/// Creates a new user account along with all the default
+/// data for the account
+pub async fn create_account(
+    db: &DatabaseConnection,
+    create: CreateUser,
+) -> Result<User, AuthProviderError> {
+    db.transaction(|db| {
+        Box::pin(async move {
+            // Create the user account
+            let user = User::create(db, create).await?;
+
+            // Give the user the default currencies
+            Currency::set_default(db, &user).await?;
+
+            // Give the user the welcome kit items and currencies
+            WelcomeKit::get().apply(db, &user).await?;
+
+            // Setup the user shared data
+            SharedData::create_default(db, &user).await?;
+
+            // Setup the user strike teams
+            create_user_strike_team(db, &user).await?;
+
+            Ok::<_, AuthProviderError>(user)
+        })
+    })
+    .await
+    .map_err(|err| match err {
+        TransactionError::Connection(err) => err.into(),
+        TransactionError::Transaction(err) => err,
+    })
+}
+
+/// Finds the local user linked to the external `identity` from `provider`.
+///
+/// Identities that haven't been linked are linked to the user with the
+/// same email when the provider has verified the email, or a new user
+/// account is created for the identity. Anyone can claim an unverified
+/// email so those are never linked to existing accounts
+pub(crate) async fn resolve_external_user(
+    db: &DatabaseConnection,
+    provider: &str,
+    identity: ExternalIdentity,
+) -> Result<User, AuthProviderError> {
+    if let Some(user) = UserIdentity::find_user(db, provider, &identity.subject).await? {
+        return Ok(user);
+    }
+
+    let user = match User::by_email(db, &identity.email).await? {
+        Some(user) if identity.email_verified => user,
+        Some(_) => return Err(AuthProviderError::AccountExists),
+        None => {
+            let username = create_username(db, &identity).await?;
+
+            debug!(
+                "Creating account for external identity {} from {} ({})",
+                identity.subject, provider, username
+            );
+
+            create_account(
+                db,
+                CreateUser {
+                    email: identity.email.clone(),
+                    username,
+                    // External accounts don't have a local password, an empty
+                    // password hash will never successfully verify
+                    password: String::new(),
+                },
+            )
+            .await?
+        }
+    };
+
+    UserIdentity::create(db, &user, provider.to_string(), identity.subject).await?;
+
+    Ok(user)
+}
+
+/// Links the identity with the `subject` from `provider` to the `user`,
+/// the user must have been authenticated by the caller
+pub(crate) async fn link_external_user(
+    db: &DatabaseConnection,
+    user: &User,
+    provider: &str,
+    subject: String,
+) -> Result<(), AuthProviderError> {
+    if let Some(linked) = UserIdentity::find_user(db, provider, &subject).await? {
+        // Linking an identity twice does nothing
+        if linked.id == user.id {
+            return Ok(());
+        }
+
+        return Err(AuthProviderError::LinkedToOther);
+    }
+
+    if UserIdentity::has_provider(db, user, provider).await? {
+        return Err(AuthProviderError::AlreadyLinked);
+    }
+
+    UserIdentity::create(db, user, provider.to_string(), subject).await?;
+    Ok(())
+}
+
+/// Creates a unique username for the external `identity`, uses the
+/// preferred username or the email name when not available
+pub(crate) async fn create_username(
+    db: &DatabaseConnection,
+    identity: &ExternalIdentity,
+) -> Result<String, DbErr> {
+    let base: String = identity
+        .username
+        .as_deref()
+        .unwrap_or_else(|| identity.email.split('@').next().unwrap_or_default())
+        .chars()
+        .take(MAX_USERNAME_LENGTH)
+        .collect();
+
+    if !User::username_exists(db, &base).await? {
+        return Ok(base);
+    }
+
+    // Append a number to the username until its unique
+    let mut suffix: u32 = 1;
+    loop {
+        let suffix_str = suffix.to_string();
+        let prefix: String = base
+            .chars()
+            .take(MAX_USERNAME_LENGTH - suffix_str.len())
+            .collect();
+        let username = format!("{prefix}{suffix_str}");
+
+        if !User::username_exists(db, &username).await? {
+            return Ok(username);
+        }
+
+        suffix += 1;
+    }
+}
@@ -0,0 +1,146 @@
+//! Authentication provider for accounts from an OAuth2 / OpenID Connect
+//! provider, logins are verified using the resource owner password
+//! credentials grant and the identity is loaded from the user info endpoint
+
+use super::{
+    link_external_user, resolve_external_user, AuthProvider, AuthProviderError, ExternalIdentity,
+};
+use crate::{config::OAuthConfig, database::entity::User};
+use anyhow::Context;
+use futures::future::BoxFuture;
+use reqwest::{Client, StatusCode};
+use sea_orm::DatabaseConnection;
+use serde::Deserialize;
+
+/// Name of the provider used when linking identities
+const PROVIDER_NAME: &str = "oauth";
+
+/// Provider authenticating against an OAuth2 provider
+pub struct OAuthProvider {
+    /// The OAuth configuration
+    config: &'static OAuthConfig,
+    /// HTTP client for making requests to the provider
+    client: Client,
+}
+
+/// Response from the token endpoint
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+}
+
+/// Response from the user info endpoint
+#[derive(Deserialize)]
+struct UserInfoResponse {
+    sub: String,
+    email: Option<String>,
+    #[serde(default)]
+    email_verified: bool,
+    preferred_username: Option<String>,
+}
+
+impl OAuthProvider {
+    pub fn new(config: &'static OAuthConfig) -> Self {
+        Self {
+            config,
+            client: Client::new(),
+        }
+    }
+
+    /// Exchanges the `email` and `password` for an access token
+    /// then loads the identity of the user
+    async fn request_identity(
+        &self,
+        email: &str,
+        password: &str,
+    ) -> Result<ExternalIdentity, AuthProviderError> {
+        let config = self.config;
+
+        let mut params = vec![
+            ("grant_type", "password"),
+            ("username", email),
+            ("password", password),
+            ("client_id", config.client_id.as_str()),
+            ("scope", config.scope.as_str()),
+        ];
+
+        if let Some(client_secret) = &config.client_secret {
+            params.push(("client_secret", client_secret.as_str()));
+        }
+
+        let response = self
+            .client
+            .post(&config.token_url)
+            .form(&params)
+            .send()
+            .await
+            .context("Failed to request OAuth token")?;
+
+        // Providers respond with bad request or unauthorized for invalid logins
+        if matches!(
+            response.status(),
+            StatusCode::BAD_REQUEST | StatusCode::UNAUTHORIZED
+        ) {
+            return Err(AuthProviderError::InvalidCredentials);
+        }
+
+        let token: TokenResponse = response
+            .error_for_status()
+            .context("OAuth token request failed")?
+            .json()
+            .await
+            .context("Failed to parse OAuth token response")?;
+
+        let user_info: UserInfoResponse = self
+            .client
+            .get(&config.user_info_url)
+            .bearer_auth(token.access_token)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .context("Failed to request OAuth user info")?
+            .json()
+            .await
+            .context("Failed to parse OAuth user info response")?;
+
+        // The login email is only verified as a login name, not as an email
+        let (email, email_verified) = match user_info.email {
+            Some(value) => (value, user_info.email_verified),
+            None => (email.to_string(), false),
+        };
+
+        Ok(ExternalIdentity {
+            subject: user_info.sub,
+            email,
+            email_verified,
+            username: user_info.preferred_username,
+        })
+    }
+}
+
+impl AuthProvider for OAuthProvider {
+    fn authenticate<'a>(
+        &'a self,
+        db: &'a DatabaseConnection,
+        email: &'a str,
+        password: &'a str,
+    ) -> BoxFuture<'a, Result<User, AuthProviderError>> {
+        Box::pin(async move {
+            let identity = self.request_identity(email, password).await?;
+            resolve_external_user(db, PROVIDER_NAME, identity).await
+        })
+    }
+
+    fn link<'a>(
+        &'a self,
+        db: &'a DatabaseConnection,
+        user: &'a User,
+        email: &'a str,
+        password: &'a str,
+    ) -> BoxFuture<'a, Result<(), AuthProviderError>> {
+        Box::pin(async move {
+            let identity = self.request_identity(email, password).await?;
+            link_external_user(db, user, PROVIDER_NAME, identity.subject).await
+        })
+    }
+}
@@ -0,0 +1,280 @@
+//! Crash reporting, panics from request handlers and background tasks are
+//! captured along with the context of the task that panicked (component,
+//! route, user and game) and stored as JSON files so that operators can
+//! include them when reporting bugs upstream
+
+use super::game::GameID;
+use crate::{config::Config, database::entity::users::UserId, utils::clock, VERSION};
+use chrono::{DateTime, Utc};
+use log::{debug, error};
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+use std::{
+    any::Any,
+    backtrace::Backtrace,
+    cell::RefCell,
+    future::Future,
+    io::{self, ErrorKind},
+    panic::Location,
+    path::{Path, PathBuf},
+};
+use uuid::Uuid;
+
+tokio::task_local! {
+    /// Context of the task currently being run
+    static CRASH_CONTEXT: RefCell<CrashContext>;
+}
+
+/// Type alias for a [String] representing a [CrashReport::id], these
+/// are prefixed with the time of the crash so they sort oldest first
+pub type CrashReportId = String;
+
+/// Context about what the server was doing when a crash occurred
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashContext {
+    /// The part of the server that was running (i.e "http", "blaze", "matchmaking")
+    pub component: String,
+    /// The HTTP route being handled
+    pub route: Option<String>,
+    /// The user the task was running for
+    pub user_id: Option<UserId>,
+    /// The game the task was running for
+    pub game_id: Option<GameID>,
+}
+
+impl CrashContext {
+    pub fn new(component: &str) -> Self {
+        Self {
+            component: component.to_string(),
+            route: None,
+            user_id: None,
+            game_id: None,
+        }
+    }
+
+    pub fn with_route(mut self, route: String) -> Self {
+        self.route = Some(route);
+        self
+    }
+
+    pub fn with_user(mut self, user_id: UserId) -> Self {
+        self.user_id = Some(user_id);
+        self
+    }
+
+    pub fn with_game(mut self, game_id: GameID) -> Self {
+        self.game_id = Some(game_id);
+        self
+    }
+}
+
+/// Stored details about a panic
+#[skip_serializing_none]
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashReport {
+    /// Unique ID of the report
+    pub id: CrashReportId,
+    /// When the crash occurred
+    pub created_at: DateTime<Utc>,
+    /// Version of the server that crashed
+    pub version: String,
+    /// The panic message
+    pub message: String,
+    /// Source location of the panic
+    pub location: Option<String>,
+    /// Name of the thread that panicked
+    pub thread: Option<String>,
+    /// Context of the task that panicked, missing when the panic
+    /// occurred outside of a task with context
+    pub context: Option<CrashContext>,
+    /// Backtrace captured at the panic
+    pub backtrace: String,
+}
+
+impl CrashReport {
+    /// Creates a report for a panic with the provided `payload` that occurred
+    /// at `location`, includes the context of the current task
+    fn capture(payload: &(dyn Any + Send), location: Option<&Location<'_>>) -> Self {
+        let created_at = clock::now();
+        let uuid = Uuid::new_v4().simple().to_string();
+        let id = format!("{}-{}", created_at.format("%Y%m%d%H%M%S%3f"), &uuid[..8]);
+
+        let message = if let Some(value) = payload.downcast_ref::<&str>() {
+            value.to_string()
+        } else if let Some(value) = payload.downcast_ref::<String>() {
+            value.clone()
+        } else {
+            "Unknown panic payload".to_string()
+        };
+
+        // Context may already be borrowed if the panic occurred while it was being updated
+        let context = CRASH_CONTEXT
+            .try_with(|context| context.try_borrow().ok().map(|context| context.clone()))
+            .ok()
+            .flatten();
+
+        Self {
+            id,
+            created_at,
+            version: VERSION.to_string(),
+            message,
+            location: location.map(|location| location.to_string()),
+            thread: std::thread::current().name().map(str::to_string),
+            context,
+            backtrace: Backtrace::force_capture().to_string(),
+        }
+    }
+
+    /// Writes the report to the crash reports directory, removing the oldest
+    /// reports when there are more than the configured maximum
+    fn save(&self, directory: &Path, max_reports: usize) -> io::Result<()> {
+        std::fs::create_dir_all(directory)?;
+
+        let data = serde_json::to_vec_pretty(self)?;
+        std::fs::write(report_path(directory, &self.id), data)?;
+
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(directory)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+
+        if paths.len() > max_reports {
+            paths.sort();
+            let excess = paths.len() - max_reports;
+            for path in &paths[..excess] {
+                std::fs::remove_file(path)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Installs the panic hook that stores crash reports, the previous panic
+/// hook is still called afterwards so panics continue to be logged
+pub fn init() {
+    let config = &Config::get().crash_reports;
+    if !config.enabled {
+        return;
+    }
+
+    let directory = PathBuf::from(&config.directory);
+    let max_reports = config.max_reports;
+
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let report = CrashReport::capture(info.payload(), info.location());
+        match report.save(&directory, max_reports) {
+            Ok(()) => error!("Stored crash report {}", report.id),
+            Err(err) => error!("Failed to store crash report: {}", err),
+        }
+
+        previous(info);
+    }));
+
+    debug!("Installed crash reporting panic hook");
+}
+
+/// Runs the provided `future` with the crash `context`, any panics
+/// within the future will include the context in their report
+pub fn scope<F>(context: CrashContext, future: F) -> impl Future<Output = F::Output>
+where
+    F: Future,
+{
+    CRASH_CONTEXT.scope(RefCell::new(context), future)
+}
+
+/// Sets the user for the context of the current task
+pub fn set_user(user_id: UserId) {
+    _ = CRASH_CONTEXT.try_with(|context| context.borrow_mut().user_id = Some(user_id));
+}
+
+/// Sets the game for the context of the current task
+pub fn set_game(game_id: Option<GameID>) {
+    _ = CRASH_CONTEXT.try_with(|context| context.borrow_mut().game_id = game_id);
+}
+
+/// Loads all the stored crash reports, newest first
+pub async fn all() -> io::Result<Vec<CrashReport>> {
+    let directory = PathBuf::from(&Config::get().crash_reports.directory);
+
+    let mut entries = match tokio::fs::read_dir(&directory).await {
+        Ok(value) => value,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+
+    let mut reports = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if !path.extension().is_some_and(|ext| ext == "json") {
+            continue;
+        }
+
+        let data = tokio::fs::read(&path).await?;
+        match serde_json::from_slice::<CrashReport>(&data) {
+            Ok(report) => reports.push(report),
+            Err(err) => error!("Invalid crash report {}: {}", path.display(), err),
+        }
+    }
+
+    reports.sort_by(|a, b| b.id.cmp(&a.id));
+    Ok(reports)
+}
+
+/// Loads a specific crash report by `id`
+pub async fn get(id: &str) -> io::Result<Option<CrashReport>> {
+    // Only allow IDs that were created by reports, prevents escaping the directory
+    if !id
+        .chars()
+        .all(|value| value.is_ascii_alphanumeric() || value == '-')
+    {
+        return Ok(None);
+    }
+
+    let directory = PathBuf::from(&Config::get().crash_reports.directory);
+    let data = match tokio::fs::read(report_path(&directory, id)).await {
+        Ok(value) => value,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err),
+    };
+
+    let report = serde_json::from_slice(&data)?;
+    Ok(Some(report))
+}
+
+/// Path to the file for the crash report with the provided `id`
+fn report_path(directory: &Path, id: &str) -> PathBuf {
+    directory.join(format!("{id}.json"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{scope, set_game, CrashContext, CrashReport};
+
+    /// Tests that reports include the context of the task that panicked
+    #[tokio::test]
+    async fn test_capture_context() {
+        let context = CrashContext::new("test").with_user(1);
+        let report = scope(context, async {
+            set_game(Some(2));
+            CrashReport::capture(&"test panic", None)
+        })
+        .await;
+
+        assert_eq!(report.message, "test panic");
+
+        let context = report.context.unwrap();
+        assert_eq!(context.component, "test");
+        assert_eq!(context.user_id, Some(1));
+        assert_eq!(context.game_id, Some(2));
+
+        // Reports created outside of a scope have no context
+        let report = CrashReport::capture(&"test panic", None);
+        assert!(report.context.is_none());
+    }
+}
@@ -0,0 +1,102 @@
+//! Memory usage diagnostics for administrators tracking down memory growth
+//! on long running instances.
+//!
+//! Subsystem sizes are approximate, they're computed from the number of
+//! values each subsystem holds and the size of those values rather than
+//! walking every allocation. The allocation counters give the exact totals
+//! for the whole process
+
+use super::{
+    game_manager::{GameManager, GameMemoryUsage},
+    replay_cache::ReplayCache,
+    sessions::Sessions,
+};
+use crate::{definitions, utils::alloc::AllocationCounters};
+use serde::Serialize;
+use std::{sync::OnceLock, time::Instant};
+
+/// When the server was started
+static STARTED: OnceLock<Instant> = OnceLock::new();
+
+/// Records the server start time, called at startup
+pub fn init() {
+    _ = STARTED.set(Instant::now());
+}
+
+/// Report of the approximate memory used by each subsystem
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryReport {
+    /// Seconds since the server was started
+    pub uptime_secs: u64,
+    /// Process wide allocation counters
+    pub allocations: AllocationCounters,
+    /// Memory held by the authenticated sessions
+    pub sessions: SessionMemoryUsage,
+    /// Memory held by the active games and matchmaking
+    pub games: GameMemoryUsage,
+    /// Memory held by the loaded definitions
+    pub definitions: DefinitionMemoryUsage,
+    /// Memory held by the request replay cache
+    pub replay_cache: ReplayCacheMemoryUsage,
+}
+
+/// Approximate memory held by the authenticated sessions
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionMemoryUsage {
+    /// Number of sessions that are still connected
+    pub active: usize,
+    /// Number of entries in the session lookup, including sessions
+    /// that have disconnected but haven't been removed yet
+    pub tracked: usize,
+    /// Approximate number of bytes used
+    pub approximate_bytes: usize,
+}
+
+/// Approximate memory held by the loaded definitions
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DefinitionMemoryUsage {
+    /// Size of the definition files that were loaded, the parsed
+    /// definitions are roughly proportional to this size
+    pub source_bytes: u64,
+}
+
+/// Approximate memory held by the request replay cache
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayCacheMemoryUsage {
+    /// Number of nonces stored
+    pub nonces: usize,
+    /// Approximate number of bytes used
+    pub approximate_bytes: usize,
+}
+
+/// Collects the current memory usage of each subsystem
+pub async fn memory_report(
+    sessions: &Sessions,
+    game_manager: &GameManager,
+    replay_cache: &ReplayCache,
+) -> MemoryReport {
+    let uptime_secs = STARTED
+        .get()
+        .map(|started| started.elapsed().as_secs())
+        .unwrap_or_default();
+
+    let nonces = replay_cache.count();
+
+    MemoryReport {
+        uptime_secs,
+        allocations: AllocationCounters::get(),
+        sessions: sessions.memory_usage(),
+        games: game_manager.memory_usage().await,
+        definitions: DefinitionMemoryUsage {
+            source_bytes: definitions::loaded_bytes(),
+        },
+        replay_cache: ReplayCacheMemoryUsage {
+            nonces,
+            approximate_bytes: nonces * ReplayCache::ENTRY_SIZE,
+        },
+    }
+}
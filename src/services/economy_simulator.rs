@@ -0,0 +1,377 @@
+//! Dry-run economy simulator, simulates pack openings and mission rewards
+//! against the current definitions without touching the database so that
+//! operators can evaluate balance changes before applying them to a live
+//! server.
+//!
+//! Packs are simulated as if opened by a new account, items that require
+//! another item to reach its capacity are never dropped
+
+use crate::{
+    database::entity::currency::CurrencyType,
+    definitions::{
+        items::{ItemDefinition, ItemName, Items},
+        match_modifiers::MatchModifiers,
+        packs::{Pack, Packs, RewardCollection},
+    },
+    http::models::mission::MissionModifier,
+    services::game::{compute_modifiers, PlayerDataBuilder},
+};
+use anyhow::{anyhow, Context};
+use log::info;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+};
+
+/// Options for running the simulation
+#[derive(Debug)]
+pub struct SimulationOptions {
+    /// Number of pack opens and missions to simulate
+    pub iterations: u32,
+    /// Seed for the random number generator, allows runs to be repeated
+    pub seed: u64,
+    /// Only simulate the pack with this name, all packs when [None]
+    pub pack: Option<ItemName>,
+    /// Minimum mission score to simulate
+    pub min_score: u32,
+    /// Maximum mission score to simulate
+    pub max_score: u32,
+    /// File to write the report to, printed when [None]
+    pub output: Option<PathBuf>,
+}
+
+impl SimulationOptions {
+    /// Parses the simulation options from the command line `args`
+    /// (e.g. "--iterations 1000 --seed 5 --pack <name>")
+    pub fn from_args<I>(args: I) -> anyhow::Result<Self>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut options = Self {
+            iterations: 10_000,
+            seed: rand::random(),
+            pack: None,
+            min_score: 10_000,
+            max_score: 50_000,
+            output: None,
+        };
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .with_context(|| format!("Missing value for {}", arg))
+            };
+
+            match arg.as_str() {
+                "--iterations" => options.iterations = value()?.parse()?,
+                "--seed" => options.seed = value()?.parse()?,
+                "--pack" => options.pack = Some(value()?.parse()?),
+                "--min-score" => options.min_score = value()?.parse()?,
+                "--max-score" => options.max_score = value()?.parse()?,
+                "--output" => options.output = Some(value()?.into()),
+                _ => continue,
+            }
+        }
+
+        if options.min_score > options.max_score {
+            return Err(anyhow!("--min-score must not be above --max-score"));
+        }
+
+        Ok(options)
+    }
+}
+
+/// Report of the simulated economy
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EconomyReport {
+    /// Number of iterations simulated
+    pub iterations: u32,
+    /// Seed used for the simulation
+    pub seed: u64,
+    /// Drop rates for each of the simulated packs
+    pub packs: Vec<PackReport>,
+    /// Income from the simulated missions
+    pub missions: MissionReport,
+}
+
+/// Drop rates for a simulated pack
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackReport {
+    /// Name of the pack item
+    pub name: ItemName,
+    /// Number of items dropped for each rarity
+    pub rarities: BTreeMap<String, u32>,
+    /// Drop rates of the items dropped by the pack
+    pub items: Vec<ItemDropRate>,
+}
+
+/// Drop rate of an item from a pack
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemDropRate {
+    /// Name of the item definition
+    pub name: ItemName,
+    /// Rarity of the item
+    pub rarity: Option<String>,
+    /// Number of opens that dropped the item
+    pub drops: u32,
+    /// Chance of the item dropping from a single open
+    pub rate: f32,
+    /// Total stack size dropped across all opens
+    pub total_stack_size: u64,
+}
+
+/// Income distributions from the simulated missions
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MissionReport {
+    /// Distribution of the xp earned
+    pub xp: Distribution,
+    /// Distribution of each currency earned
+    pub currencies: Vec<CurrencyIncome>,
+}
+
+/// Distribution of a currency earned from missions
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurrencyIncome {
+    /// The currency earned
+    pub currency: CurrencyType,
+    /// Distribution of the amount earned
+    pub distribution: Distribution,
+}
+
+/// Summary of the distribution of a set of values
+#[derive(Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Distribution {
+    /// Smallest value
+    pub min: u32,
+    /// Largest value
+    pub max: u32,
+    /// Average of the values
+    pub mean: f64,
+    /// Middle value
+    pub median: u32,
+    /// Value 90% of the values are at or below
+    pub p90: u32,
+}
+
+impl Distribution {
+    /// Summarizes the provided `values`
+    fn from_values(mut values: Vec<u32>) -> Self {
+        if values.is_empty() {
+            return Self::default();
+        }
+
+        values.sort_unstable();
+
+        let total: u64 = values.iter().map(|value| *value as u64).sum();
+        let percentile = |percent: usize| values[(values.len() - 1) * percent / 100];
+
+        Self {
+            min: values[0],
+            max: values[values.len() - 1],
+            mean: total as f64 / values.len() as f64,
+            median: percentile(50),
+            p90: percentile(90),
+        }
+    }
+}
+
+/// Runs the simulation using the options from the command line `args`
+/// writing the report as JSON to the output file or stdout
+pub fn run_command<I>(args: I) -> anyhow::Result<()>
+where
+    I: IntoIterator<Item = String>,
+{
+    let options = SimulationOptions::from_args(args)?;
+    let report = run(&options)?;
+    let report = serde_json::to_string_pretty(&report)?;
+
+    match &options.output {
+        Some(path) => {
+            std::fs::write(path, report)
+                .with_context(|| format!("Failed to write report to {}", path.display()))?;
+            info!("Wrote economy report to {}", path.display());
+        }
+        None => println!("{}", report),
+    }
+
+    Ok(())
+}
+
+/// Runs the simulation with the provided `options`
+pub fn run(options: &SimulationOptions) -> anyhow::Result<EconomyReport> {
+    let mut rng = StdRng::seed_from_u64(options.seed);
+
+    let mut packs: Vec<&Pack> = match &options.pack {
+        Some(name) => vec![Packs::get()
+            .by_name(name)
+            .with_context(|| format!("Unknown pack {}", name))?],
+        None => Packs::get().all().collect(),
+    };
+
+    // Sorted so that the same seed produces the same report
+    packs.sort_by_key(|pack| pack.name);
+
+    let packs = packs
+        .into_iter()
+        .map(|pack| simulate_pack(pack, options.iterations, &mut rng))
+        .collect::<anyhow::Result<_>>()?;
+
+    let missions = simulate_missions(options, &mut rng);
+
+    Ok(EconomyReport {
+        iterations: options.iterations,
+        seed: options.seed,
+        packs,
+        missions,
+    })
+}
+
+/// Simulates opening the `pack` the provided number of `iterations`
+fn simulate_pack(pack: &Pack, iterations: u32, rng: &mut StdRng) -> anyhow::Result<PackReport> {
+    let mut items: Vec<&ItemDefinition> = Pack::droppable_items(Items::get());
+    // New accounts don't own the items required to unlock these
+    items.retain(|item| item.unlock_definition.is_none());
+
+    let mut drops: HashMap<ItemName, (&ItemDefinition, u32, u64)> = HashMap::new();
+
+    for _ in 0..iterations {
+        let mut rewards = RewardCollection::default();
+        pack.generate_from_items(rng, &items, None, &mut rewards)
+            .with_context(|| format!("Failed to generate rewards for pack {}", pack.name))?;
+
+        for reward in rewards.rewards {
+            let (_, count, total_stack_size) =
+                drops
+                    .entry(reward.definition.name)
+                    .or_insert((reward.definition, 0, 0));
+            *count += 1;
+            *total_stack_size += reward.stack_size as u64;
+        }
+    }
+
+    let mut rarities: BTreeMap<String, u32> = BTreeMap::new();
+    let mut items: Vec<ItemDropRate> = drops
+        .into_values()
+        .map(|(definition, drops, total_stack_size)| {
+            let rarity = definition.rarity.map(|rarity| format!("{:?}", rarity));
+            if let Some(rarity) = &rarity {
+                *rarities.entry(rarity.clone()).or_default() += drops;
+            }
+
+            ItemDropRate {
+                name: definition.name,
+                rarity,
+                drops,
+                rate: drops as f32 / iterations.max(1) as f32,
+                total_stack_size,
+            }
+        })
+        .collect();
+
+    // Most common drops first
+    items.sort_by(|a, b| b.drops.cmp(&a.drops).then(a.name.cmp(&b.name)));
+
+    Ok(PackReport {
+        name: pack.name,
+        rarities,
+        items,
+    })
+}
+
+/// Simulates completing missions with random scores and
+/// random match modifiers
+fn simulate_missions(options: &SimulationOptions, rng: &mut StdRng) -> MissionReport {
+    let match_modifiers = MatchModifiers::get();
+
+    let mut xp: Vec<u32> = Vec::with_capacity(options.iterations as usize);
+    let mut currencies: Vec<(CurrencyType, Vec<u32>)> = Vec::new();
+
+    for _ in 0..options.iterations {
+        let modifiers: Vec<MissionModifier> = match_modifiers
+            .values
+            .iter()
+            .filter_map(|modifier| {
+                let value = modifier.values.choose(rng)?;
+                Some(MissionModifier {
+                    name: modifier.name.clone(),
+                    value: value.name.clone(),
+                })
+            })
+            .collect();
+
+        let score = rng.gen_range(options.min_score..=options.max_score);
+
+        // Same rewards as processed for completed missions
+        let mut data_builder = PlayerDataBuilder::new();
+        data_builder.add_reward_xp("base", score);
+        compute_modifiers(&modifiers, &mut data_builder);
+
+        xp.push(data_builder.xp_earned);
+
+        for (currency, amount) in data_builder.total_currency {
+            match currencies.iter_mut().find(|(ty, _)| *ty == currency) {
+                Some((_, amounts)) => amounts.push(amount),
+                None => currencies.push((currency, vec![amount])),
+            }
+        }
+    }
+
+    let currencies = currencies
+        .into_iter()
+        .map(|(currency, mut amounts)| {
+            // Missions that didn't earn the currency earned zero
+            amounts.resize(options.iterations as usize, 0);
+            CurrencyIncome {
+                currency,
+                distribution: Distribution::from_values(amounts),
+            }
+        })
+        .collect();
+
+    MissionReport {
+        xp: Distribution::from_values(xp),
+        currencies,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{run, Distribution, SimulationOptions};
+
+    /// Tests that the distribution summary is computed correctly
+    #[test]
+    fn test_distribution() {
+        let distribution = Distribution::from_values((1..=10).rev().collect());
+        assert_eq!(
+            distribution,
+            Distribution {
+                min: 1,
+                max: 10,
+                mean: 5.5,
+                median: 5,
+                p90: 9,
+            }
+        );
+    }
+
+    /// Tests that the same seed produces the same report
+    #[test]
+    fn test_seeded_run() {
+        let options =
+            SimulationOptions::from_args(["--iterations", "50", "--seed", "7"].map(String::from))
+                .unwrap();
+
+        let first = serde_json::to_value(run(&options).unwrap()).unwrap();
+        let second = serde_json::to_value(run(&options).unwrap()).unwrap();
+        assert_eq!(first, second);
+    }
+}
@@ -0,0 +1,59 @@
+//! Service for sending emails to users through the SMTP server
+//! from the [EmailConfig]
+
+use crate::config::{Config, EmailConfig};
+use anyhow::Context;
+use lettre::{
+    transport::smtp::authentication::Credentials, AsyncSmtpTransport, AsyncTransport, Message,
+    Tokio1Executor,
+};
+use log::{debug, error};
+
+/// Sends an email in the background to the address `to` if email
+/// has been configured, otherwise the email is ignored
+pub fn send_email(to: String, subject: String, body: String) {
+    let config = match &Config::get().email {
+        Some(value) => value,
+        None => {
+            debug!("Email not configured, skipping email to {}", to);
+            return;
+        }
+    };
+
+    tokio::spawn(async move {
+        if let Err(err) = try_send_email(config, &to, subject, body).await {
+            error!("Failed to send email to {}: {:?}", to, err);
+        }
+    });
+}
+
+/// Attempts to send an email using the provided `config`
+async fn try_send_email(
+    config: &EmailConfig,
+    to: &str,
+    subject: String,
+    body: String,
+) -> anyhow::Result<()> {
+    let message = Message::builder()
+        .from(config.from.parse().context("Invalid from address")?)
+        .to(to.parse().context("Invalid to address")?)
+        .subject(subject)
+        .body(body)
+        .context("Failed to create email")?;
+
+    let transport = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)
+        .context("Failed to create SMTP transport")?
+        .port(config.port)
+        .credentials(Credentials::new(
+            config.username.clone(),
+            config.password.clone(),
+        ))
+        .build();
+
+    transport
+        .send(message)
+        .await
+        .context("Failed to send email")?;
+
+    Ok(())
+}
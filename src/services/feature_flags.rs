@@ -0,0 +1,147 @@
+//! Feature flags delivered to the client plugin, flags are loaded from
+//! the configuration and can be changed at runtime by administrators.
+//! Runtime changes are not persisted and reset when the server restarts
+
+use crate::{config::FeatureFlag, database::entity::users::UserId};
+use parking_lot::RwLock;
+use std::collections::BTreeMap;
+
+/// Evaluated flags for a user, flag name mapped to whether its enabled
+pub type EvaluatedFlags = BTreeMap<String, bool>;
+
+pub struct FeatureFlags {
+    flags: RwLock<Vec<FeatureFlag>>,
+}
+
+impl FeatureFlags {
+    pub fn new(flags: Vec<FeatureFlag>) -> Self {
+        Self {
+            flags: RwLock::new(flags),
+        }
+    }
+
+    /// Evaluates all the flags for the provided user
+    pub fn evaluate(&self, user_id: UserId) -> EvaluatedFlags {
+        self.flags
+            .read()
+            .iter()
+            .map(|flag| (flag.name.clone(), is_enabled(flag, user_id)))
+            .collect()
+    }
+
+    /// Gets a copy of all the flags
+    pub fn all(&self) -> Vec<FeatureFlag> {
+        self.flags.read().clone()
+    }
+
+    /// Sets a flag, replacing any existing flag with the same name
+    pub fn set(&self, flag: FeatureFlag) {
+        let flags = &mut *self.flags.write();
+        match flags.iter_mut().find(|value| value.name == flag.name) {
+            Some(existing) => *existing = flag,
+            None => flags.push(flag),
+        }
+    }
+
+    /// Removes the flag with the provided `name`, returns
+    /// whether the flag existed
+    pub fn remove(&self, name: &str) -> bool {
+        let flags = &mut *self.flags.write();
+        let length = flags.len();
+        flags.retain(|flag| flag.name != name);
+        flags.len() != length
+    }
+}
+
+/// Checks whether the `flag` is enabled for the provided user
+fn is_enabled(flag: &FeatureFlag, user_id: UserId) -> bool {
+    if !flag.enabled {
+        return false;
+    }
+
+    if flag.users.contains(&user_id) {
+        return true;
+    }
+
+    rollout_bucket(&flag.name, user_id) < flag.rollout_percent.min(100) as u32
+}
+
+/// Places the user into a stable bucket (0-99) for the flag so that the
+/// same users remain enabled as the rollout percentage increases. Uses
+/// FNV-1a rather than the std hasher which isn't stable across releases
+pub(crate) fn rollout_bucket(name: &str, user_id: UserId) -> u32 {
+    const OFFSET_BASIS: u32 = 0x811c9dc5;
+    const PRIME: u32 = 0x01000193;
+
+    name.as_bytes()
+        .iter()
+        .chain(user_id.to_le_bytes().iter())
+        .fold(OFFSET_BASIS, |hash, byte| {
+            (hash ^ *byte as u32).wrapping_mul(PRIME)
+        })
+        % 100
+}
+
+#[cfg(test)]
+mod test {
+    use super::{is_enabled, FeatureFlags};
+    use crate::config::FeatureFlag;
+
+    fn flag(rollout_percent: u8) -> FeatureFlag {
+        FeatureFlag {
+            name: "websocketTunnel".to_string(),
+            enabled: true,
+            rollout_percent,
+            users: Vec::new(),
+        }
+    }
+
+    /// Tests the rollout percentage boundaries and explicit users
+    #[test]
+    fn test_rollout() {
+        let none = flag(0);
+        let all = flag(100);
+
+        for user_id in 0..500 {
+            assert!(!is_enabled(&none, user_id));
+            assert!(is_enabled(&all, user_id));
+        }
+
+        let mut listed = flag(0);
+        listed.users.push(7);
+        assert!(is_enabled(&listed, 7));
+
+        let mut disabled = flag(100);
+        disabled.enabled = false;
+        disabled.users.push(7);
+        assert!(!is_enabled(&disabled, 7));
+    }
+
+    /// Tests that users enabled at a lower rollout stay enabled when
+    /// the rollout percentage is increased
+    #[test]
+    fn test_rollout_stable() {
+        let low = flag(20);
+        let high = flag(60);
+
+        let enabled = (0..1000).filter(|user_id| is_enabled(&low, *user_id));
+        for user_id in enabled {
+            assert!(is_enabled(&high, user_id));
+        }
+    }
+
+    /// Tests setting and removing flags at runtime
+    #[test]
+    fn test_set_remove() {
+        let flags = FeatureFlags::new(vec![flag(0)]);
+        assert_eq!(flags.evaluate(1).get("websocketTunnel"), Some(&false));
+
+        flags.set(flag(100));
+        assert_eq!(flags.all().len(), 1);
+        assert_eq!(flags.evaluate(1).get("websocketTunnel"), Some(&true));
+
+        assert!(flags.remove("websocketTunnel"));
+        assert!(!flags.remove("websocketTunnel"));
+        assert!(flags.evaluate(1).is_empty());
+    }
+}
@@ -0,0 +1,68 @@
+//! Friends service, links the user sessions of online mutual friends so
+//! that friends are notified through the user sessions component when the
+//! other friend comes online, goes offline or changes game.
+//!
+//! Friends are linked the same way players in the same game are, a user
+//! going offline is handled by the session clearing its subscriptions
+
+use super::sessions::Sessions;
+use crate::{
+    blaze::session::Session,
+    database::{
+        entity::{users::UserId, Friend},
+        DbResult,
+    },
+};
+use sea_orm::ConnectionTrait;
+
+/// Links the `session` of the user with the provided `user_id` that has
+/// just come online with the sessions of their online mutual friends
+pub async fn connect<C>(
+    db: &C,
+    sessions: &Sessions,
+    session: &Session,
+    user_id: UserId,
+) -> DbResult<()>
+where
+    C: ConnectionTrait + Send,
+{
+    let friends = Friend::mutual(db, user_id).await?;
+
+    friends
+        .into_iter()
+        .filter_map(|friend_id| Some((friend_id, sessions.lookup_session(friend_id)?)))
+        .for_each(|(friend_id, friend)| link_sessions(session, user_id, &friend, friend_id));
+
+    Ok(())
+}
+
+/// Links the sessions of the provided users that have just become
+/// mutual friends, does nothing unless both users are online
+pub fn link(sessions: &Sessions, user_id: UserId, friend_id: UserId) {
+    let (Some(session), Some(friend)) = (
+        sessions.lookup_session(user_id),
+        sessions.lookup_session(friend_id),
+    ) else {
+        return;
+    };
+
+    link_sessions(&session, user_id, &friend, friend_id);
+}
+
+/// Unlinks the sessions of the provided users that are no longer
+/// mutual friends
+pub fn unlink(sessions: &Sessions, user_id: UserId, friend_id: UserId) {
+    if let Some(session) = sessions.lookup_session(user_id) {
+        session.remove_subscriber(friend_id);
+    }
+
+    if let Some(friend) = sessions.lookup_session(friend_id) {
+        friend.remove_subscriber(user_id);
+    }
+}
+
+/// Subscribes the sessions of two friends to each other
+fn link_sessions(session: &Session, user_id: UserId, friend: &Session, friend_id: UserId) {
+    session.add_subscriber(friend_id, friend.notify_handle());
+    friend.add_subscriber(user_id, session.notify_handle());
+}
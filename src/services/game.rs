@@ -1,15 +1,23 @@
 use super::{
-    activity::{ActivityEvent, PrestigeData, PrestigeProgression},
+    activity::{ActivityEvent, ActivityResult, PrestigeData, PrestigeProgression},
+    crash_reports::{self, CrashContext},
     game_manager::GameManager,
+    game_stats::GameStatsEvent,
+    game_timeline::{GameTimeline, GameTimelineEvent, TimelinePlayer, TimelineSnapshot},
+    household, leaderboard,
+    mission_results::MissionResultJob,
+    mutators, ready_check,
+    rewards::{Entitlement, RewardBundle, RewardEngine},
+    server_events::{ServerEvent, ServerEvents},
 };
 use crate::{
     blaze::{
         components::{self, game_manager, user_sessions::PLAYER_SESSION_TYPE},
         models::{
             game_manager::{
-                AttributesChange, GameSetupContext, GameSetupResponse, NotifyGameReplay,
-                NotifyGameStateChange, NotifyPostJoinedGame, PlayerAttributesChange, PlayerRemoved,
-                RemoveReason,
+                AttributesChange, GameSetupContext, GameSetupResponse, HostMigrateFinished,
+                HostMigrateStart, NotifyGameReplay, NotifyGameStateChange, NotifyPostJoinedGame,
+                PlayerAttributesChange, PlayerRemoved, RemoveReason,
             },
             PlayerState,
         },
@@ -17,33 +25,38 @@ use crate::{
         session::{NetData, SessionNotifyHandle, WeakSessionLink},
     },
     database::entity::{
-        challenge_progress::CounterUpdateType, currency::CurrencyType, users::UserId,
-        ChallengeProgress, Character, Currency, InventoryItem, SharedData, User,
+        challenge_progress::CounterUpdateType, currency::CurrencyType,
+        currency_ledger::CurrencyLedgerReason, strike_team_mission::StrikeTeamMissionId,
+        users::UserId, BadgeReward, ChallengeProgress, Character, InventoryItem, MatchRecord,
+        SharedData, User, XpBoost,
     },
     definitions::{
-        badges::{BadgeLevelName, Badges},
+        badges::{Badge, BadgeLevel, BadgeLevelName, BadgeName, Badges},
         challenges::{ChallengeCounter, ChallengeDefinition, Challenges, CurrencyReward},
-        classes::Classes,
+        classes::{CharacterEquipment, Classes, EquipmentSlot, NameOrEmpty},
+        items::{ItemName, Items},
         level_tables::LevelTables,
         match_modifiers::MatchModifiers,
+        titles::{PlayerTitleName, PlayerTitles},
     },
     http::models::mission::{
         CompleteMissionData, MissionDetails, MissionModifier, MissionPlayerData, MissionPlayerInfo,
         PlayerInfoBadge, PlayerInfoResult, RewardSource,
     },
     services::activity::{ChallengeStatusChange, ChallengeUpdateCounter, ChallengeUpdated},
-    utils::models::Sku,
+    utils::{clock, models::Sku},
 };
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use log::{debug, error};
 use sea_orm::{DatabaseConnection, DbErr};
 use std::{
     collections::{BTreeMap, HashMap},
     sync::{Arc, Weak},
+    time::Instant,
 };
 use tdf::{ObjectId, TdfMap};
 use thiserror::Error;
-use tokio::sync::RwLock;
+use tokio::sync::{Notify, RwLock};
 use uuid::Uuid;
 
 pub type GameID = u32;
@@ -61,10 +74,39 @@ pub struct Game {
     pub attributes: AttrMap,
     /// The list of players in this game
     pub players: Vec<Player>,
+    /// ID of the player hosting the game, the first player to join
+    /// until the host leaves and the game migrates to another player
+    pub host: Option<UserId>,
+    /// When the game was created
+    pub created: Instant,
+    /// Most players that have been in the game at once
+    pub peak_players: usize,
 
     pub modifiers: Vec<MissionModifier>,
+    /// Reservation created when the mission is started, finish
+    /// submissions are only accepted while a reservation exists
+    pub mission_reservation: Option<MissionReservation>,
     pub mission_data: Option<CompleteMissionData>,
     pub processed_data: Option<MissionDetails>,
+    /// Incremented whenever mission data is submitted, prevents results
+    /// from older submissions replacing the results of newer ones
+    pub mission_revision: u32,
+    /// Revision of the mission data the result worker has started
+    /// processing, prevents the same submission being processed twice
+    pub mission_claimed: Option<u32>,
+    /// Notified when the submitted mission data has been processed
+    pub mission_processed: Arc<Notify>,
+    /// Names of the seasonal event mutators applied to the game
+    pub mutators: Vec<String>,
+    /// Players the host has kicked with a ban, banned players
+    /// aren't matched back into the game
+    pub banned: Vec<UserId>,
+    /// When the current ready check started, restarted when a player
+    /// joins or a mission is started
+    pub ready_check_started: DateTime<Utc>,
+
+    /// Timeline of events for spectators
+    pub timeline: GameTimeline,
 
     /// Services access
     pub game_manager: Arc<GameManager>,
@@ -80,6 +122,8 @@ enum PlayerDataProcessError {
     MissingCharacter,
     #[error("Missing class")]
     MissingClass,
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
 }
 
 pub struct PlayerDataBuilder {
@@ -129,20 +173,7 @@ impl PlayerDataBuilder {
     }
 
     pub fn add_challenge_progress(&mut self, update: ChallengeProgressChange) {
-        let existing = self
-            .challenges_updates
-            .iter_mut()
-            // Check if theres already a matching progress update
-            .find(|value| {
-                value.definition.name == update.definition.name
-                    && value.counter.name == update.counter.name
-            });
-
-        if let Some(existing) = existing {
-            existing.progress = existing.progress.saturating_add(update.progress);
-        } else {
-            self.challenges_updates.push(update);
-        }
+        merge_challenge_progress(&mut self.challenges_updates, update);
     }
 
     pub fn add_reward_xp(&mut self, name: &str, xp: u32) {
@@ -202,11 +233,13 @@ async fn process_player_data(
     db: DatabaseConnection,
     data: &MissionPlayerData,
     mission_data: &CompleteMissionData,
+    challenges: Vec<ChallengeProgressChange>,
 ) -> Result<MissionPlayerInfo, PlayerDataProcessError> {
     debug!("Processing player data");
 
     let classes = Classes::get();
     let level_tables = LevelTables::get();
+    let item_definitions = Items::get();
 
     let user = User::by_id(&db, data.nucleus_id)
         .await?
@@ -222,7 +255,7 @@ async fn process_player_data(
         .active_character_id
         .ok_or(PlayerDataProcessError::MissingCharacter)?;
 
-    let mut character = Character::find_by_id_user(&db, &user, active_character_id)
+    let character = Character::find_by_id_user(&db, &user, active_character_id)
         .await?
         .ok_or(PlayerDataProcessError::MissingCharacter)?;
 
@@ -239,7 +272,12 @@ async fn process_player_data(
 
     debug!("Processing badges");
 
-    process_badges(&data.activity_report.activities, &mut data_builder);
+    let mut rewarded_badges = BadgeReward::all(&db, &user).await?;
+    let newly_rewarded = process_badges(
+        &data.activity_report.activities,
+        &mut rewarded_badges,
+        &mut data_builder,
+    );
 
     debug!("Base score reward");
     // Base reward xp is the score earned
@@ -251,19 +289,18 @@ async fn process_player_data(
     // Compute modifier amounts
     compute_modifiers(&mission_data.modifiers, &mut data_builder);
 
-    debug!("Compute leveling");
+    // Apply the seasonal event mutators the game was created with
+    mutators::compute_rewards(&mission_data.modifiers, &mut data_builder);
 
-    // Character leveling
-    let level_table = level_tables
-        .by_name(&class.level_name)
-        .expect("Missing class level table");
+    // Apply the XP boost activated by consuming a booster
+    if let Some(boost) = XpBoost::take(&db, &user).await? {
+        let xp = data_builder.xp_earned.saturating_mul(boost.percent) / 100;
+        data_builder.add_reward_xp("xpBoost", xp);
+    }
 
     let previous_xp = character.xp;
     let previous_level = character.level;
 
-    let (new_xp, level) =
-        level_table.compute_leveling(character.xp, character.level, data_builder.xp_earned);
-
     debug!("Compute prestige");
 
     // Insert the before change
@@ -304,13 +341,59 @@ async fn process_player_data(
 
     debug!("Process challenges");
 
-    process_challenges(&data.activity_report.activities, &mut data_builder);
+    for change in challenges {
+        data_builder.add_challenge_progress(change);
+    }
 
     let mut challenges_updated: BTreeMap<String, ChallengeUpdated> = BTreeMap::new();
 
+    let challenges_updates = std::mem::take(&mut data_builder.challenges_updates);
+
+    // Challenge points are server only so aren't part of the mission rewards
+    let mut challenge_points = RewardBundle::default();
+    let mut rewards = RewardBundle::default();
+
     // Save challenge changes
-    for (index, change) in data_builder.challenges_updates.iter().enumerate() {
-        let (model, counter, change_type) = ChallengeProgress::update(&db, &user, change).await?;
+    for (index, change) in challenges_updates.iter().enumerate() {
+        let (model, counter, change_type, completions) =
+            ChallengeProgress::update(&db, &user, change).await?;
+
+        // Give the challenge rewards for each completion
+        for _ in 0..completions {
+            let reward = &change.definition.reward;
+            let reward_name = change.definition.name.to_string();
+
+            if let Some(points) = change.definition.point_value {
+                challenge_points.add_currency(CurrencyType::ChallengePoints, points);
+            }
+
+            for currency in &reward.currencies {
+                data_builder.add_reward_currency(&reward_name, currency.name, currency.value);
+            }
+
+            for item in &reward.items {
+                let Some(definition) = item_definitions.by_name(&item.name) else {
+                    error!(
+                        "Challenge {} rewards unknown item {}",
+                        reward_name, item.name
+                    );
+                    continue;
+                };
+
+                rewards.add_item(definition, item.count);
+            }
+        }
+
+        // Unlock any strike team flair and titles rewarded by the challenge
+        if completions > 0 {
+            for flair in &change.definition.reward.flair {
+                rewards.add_entitlement(Entitlement::StrikeTeamFlair(flair.clone()));
+            }
+
+            for title in PlayerTitles::get().by_challenge(&change.definition.name) {
+                rewards.add_entitlement(Entitlement::PlayerTitle(title.name.clone()));
+            }
+        }
 
         let status_change = match change_type {
             CounterUpdateType::Changed => ChallengeStatusChange::Changed,
@@ -331,32 +414,67 @@ async fn process_player_data(
         );
     }
 
-    debug!("Saving character level and xp");
+    debug!("Applying household earn limits");
+
+    household::limit_mission_earnings(&db, &user, &mut data_builder.total_currency).await?;
 
-    // TOD: Character leveling up needs to add 3 skill points per level
+    debug!("Granting rewards");
 
-    // Update character level and xp
-    if new_xp != previous_xp || level > previous_level {
-        character = character.update_xp(&db, new_xp, level).await?
+    for (ty, amount) in &data_builder.total_currency {
+        rewards.add_currency(*ty, *amount);
     }
 
-    debug!("Updating currencies");
+    if data_builder.xp_earned > 0 {
+        rewards.add_xp(character.clone(), data_builder.xp_earned);
+    }
 
-    // Add all the new currency amounts
-    Currency::add_many(
+    // Mission rewards are granted through the reward engine which levels
+    // up the character and caps the currencies
+    let mut granted = ActivityResult::default();
+    RewardEngine::grant(
         &db,
         &user,
-        data_builder
-            .total_currency
-            .iter()
-            .map(|(key, value)| (*key, *value)),
+        rewards,
+        CurrencyLedgerReason::MissionReward,
+        &mut granted,
     )
     .await?;
+    RewardEngine::grant(
+        &db,
+        &user,
+        challenge_points,
+        CurrencyLedgerReason::ChallengeReward,
+        &mut granted,
+    )
+    .await?;
+
+    // Store the rewarded badge levels so they aren't rewarded again
+    for badge_name in newly_rewarded {
+        if let Some(levels) = rewarded_badges.get(&badge_name) {
+            BadgeReward::set_rewarded(&db, &user, badge_name, *levels).await?;
+        }
+    }
 
-    let total_currencies_earned = data_builder
-        .total_currency
+    data_builder.items_earned.extend(granted.items_earned);
+
+    let (current_xp, level) = match granted.character_class_name {
+        Some(_) => (granted.current_xp, granted.current_level),
+        None => (previous_xp.current, previous_level),
+    };
+
+    // Leaderboards aren't essential to the mission results
+    if let Err(err) = leaderboard::update_user(&db, &user, true).await {
+        error!("Failed to update leaderboard stats: {}", err);
+    }
+
+    // Currencies actually granted after the caps were applied
+    let total_currencies_earned = granted
+        .currency_earned
         .into_iter()
-        .map(|(name, value)| CurrencyReward { name, value })
+        .map(|currency| CurrencyReward {
+            name: currency.ty,
+            value: currency.balance,
+        })
         .collect();
 
     let result = PlayerInfoResult {
@@ -364,10 +482,10 @@ async fn process_player_data(
         items_earned: data_builder.items_earned,
         xp_earned: data_builder.xp_earned,
         previous_xp: previous_xp.current,
-        current_xp: new_xp.current,
+        current_xp,
         previous_level,
-        level: character.level,
-        leveled_up: character.level != previous_level,
+        level,
+        leveled_up: level != previous_level,
         score: data_builder.score,
         total_score: data_builder.score,
         character_class_name: class.name,
@@ -395,35 +513,180 @@ async fn process_player_data(
     })
 }
 
+/// Processes the submitted `mission_data` computing the rewards and challenge
+/// progress for each player, this is done by the [MissionResultWorker] after
+/// the mission has been finished
+///
+/// The `applied` challenge progress for each player was already stored from
+/// activities they reported during the mission, its removed from the mission
+/// progress so that it isn't counted twice
+///
+/// [MissionResultWorker]: super::mission_results::MissionResultWorker
+pub async fn process_mission_data(
+    db: &DatabaseConnection,
+    mission_data: CompleteMissionData,
+    applied: HashMap<UserId, Vec<ChallengeProgressChange>>,
+) -> MissionDetails {
+    let now = clock::now();
+
+    let waves = mission_data
+        .player_data
+        .iter()
+        .map(|value| value.waves_completed)
+        .max()
+        .unwrap_or_default();
+
+    let level: String = mission_data
+        .modifiers
+        .iter()
+        .find(|value| value.name == "level")
+        .map(|value| value.value.clone())
+        .unwrap_or_else(|| "MPAqua".to_string());
+    let difficulty: String = mission_data
+        .modifiers
+        .iter()
+        .find(|value| value.name == "difficulty")
+        .map(|value| value.value.clone())
+        .unwrap_or_else(|| "bronze".to_string());
+    let enemy_type: String = mission_data
+        .modifiers
+        .iter()
+        .find(|value| value.name == "enemytype")
+        .map(|value| value.value.clone())
+        .unwrap_or_else(|| "outlaw".to_string());
+
+    let mut player_infos = Vec::with_capacity(mission_data.player_data.len());
+
+    let mut player_challenges = collect_player_challenges(&mission_data.player_data);
+    for (user_id, applied) in &applied {
+        if let Some(changes) = player_challenges.get_mut(user_id) {
+            subtract_challenge_progress(changes, applied);
+        }
+    }
+
+    let community = community_progress(&player_challenges);
+
+    for value in &mission_data.player_data {
+        // Players reported more than once only progress their challenges once
+        let mut challenges = player_challenges
+            .remove(&value.nucleus_id)
+            .unwrap_or_default();
+
+        // Players that left the mission don't share in the community progress
+        if value.present_at_end {
+            share_community_progress(&mut challenges, &community);
+        }
+
+        match process_player_data(db.clone(), value, &mission_data, challenges).await {
+            Ok(info) => {
+                player_infos.push(info);
+            }
+            Err(err) => {
+                error!("Error while processing player: {}", err);
+            }
+        }
+    }
+
+    let data = MissionDetails {
+        sku: Sku::default(),
+        name: mission_data.match_id,
+        duration_sec: mission_data.duration_sec,
+        percent_complete: mission_data.percent_complete,
+        waves_encountered: waves,
+        extraction_state: mission_data.extraction_state,
+        enemy_type,
+        difficulty,
+        map: level,
+        start: now,
+        end: now,
+        processed: now,
+        player_infos,
+        modifiers: mission_data.modifiers,
+    };
+
+    // Record the match for the server statistics
+    if let Err(err) = MatchRecord::create(
+        db,
+        data.map.clone(),
+        data.enemy_type.clone(),
+        data.difficulty.clone(),
+        data.player_infos.len() as u32,
+    )
+    .await
+    {
+        error!("Failed to record match: {}", err);
+    }
+
+    data
+}
+
 /// Processes the `activities` from the game adding any rewards
-/// and badges from completed badge levels
-fn process_badges(activities: &[ActivityEvent], data_builder: &mut PlayerDataBuilder) {
+/// and badges from completed badge levels. Only levels past the
+/// `rewarded` level counts are rewarded, the counts are updated
+/// and the names of the badges with new levels are returned
+fn process_badges(
+    activities: &[ActivityEvent],
+    rewarded: &mut HashMap<BadgeName, u32>,
+    data_builder: &mut PlayerDataBuilder,
+) -> Vec<BadgeName> {
     let badges = Badges::get();
 
-    activities
-        .iter()
-        // Find matching badges for the activity
-        .filter_map(|activity| {
-            // Find a badge matching the activity
-            let (badge, progress, levels) = badges.by_activity(activity)?;
-            // Only continue if they have a level achieved
-            let highest_level = *levels.last()?;
+    // Badges earned from the activities with the progress and reached levels
+    let mut earned: Vec<(&Badge, u32, Vec<&BadgeLevel>)> = Vec::new();
 
-            Some((badge, progress, levels, highest_level))
-        })
-        .for_each(|(badge, progress, levels, highest_level)| {
-            // Total accumulated XP and currency from achieved levels
-            let mut total_xp: u32 = 0;
-            let mut total_currency: u32 = 0;
-
-            // Names of the levels that have been earned
-            let mut level_names: Vec<BadgeLevelName> = Vec::with_capacity(levels.len());
-
-            for level in levels {
-                total_xp += level.xp_reward;
-                total_currency += level.currency_reward;
-                level_names.push(level.name.clone());
+    for activity in activities {
+        // Find a badge matching the activity or named by a badge earned activity
+        let Some((badge, progress, levels)) = badges
+            .by_activity(activity)
+            .or_else(|| badges.by_earned_activity(activity))
+        else {
+            continue;
+        };
+
+        // The same badge may be reported more than once, only the
+        // highest progress is awarded so levels aren't rewarded twice
+        match earned
+            .iter_mut()
+            .find(|(existing, _, _)| existing.name == badge.name)
+        {
+            Some(existing) => {
+                if progress > existing.1 {
+                    *existing = (badge, progress, levels);
+                }
             }
+            None => earned.push((badge, progress, levels)),
+        }
+    }
+
+    // Badges with levels that were rewarded for the first time
+    let mut newly_rewarded: Vec<BadgeName> = Vec::new();
+
+    for (badge, progress, levels) in earned {
+        // Only continue if they have a level achieved
+        let Some(highest_level) = levels.last().copied() else {
+            continue;
+        };
+
+        // Levels rewarded by previous missions are skipped
+        let rewarded_levels = rewarded.entry(badge.name).or_default();
+        let new_levels = levels.get(*rewarded_levels as usize..).unwrap_or_default();
+
+        // Total accumulated XP and currency from newly achieved levels
+        let mut total_xp: u32 = 0;
+        let mut total_currency: u32 = 0;
+
+        // Names of the levels that have been earned
+        let mut level_names: Vec<BadgeLevelName> = Vec::with_capacity(new_levels.len());
+
+        for level in new_levels {
+            total_xp += level.xp_reward;
+            total_currency += level.currency_reward;
+            level_names.push(level.name.clone());
+        }
+
+        if !new_levels.is_empty() {
+            *rewarded_levels = levels.len() as u32;
+            newly_rewarded.push(badge.name);
 
             // The reward source is the badge name
             let reward_name = badge.name.to_string();
@@ -431,16 +694,21 @@ fn process_badges(activities: &[ActivityEvent], data_builder: &mut PlayerDataBui
             // Append the rewards
             data_builder.add_reward_xp(&reward_name, total_xp);
             data_builder.add_reward_currency(&reward_name, badge.currency, total_currency);
-            data_builder.badges.push(PlayerInfoBadge {
-                count: progress,
-                level_name: highest_level.name.clone(),
-                rewarded_levels: level_names,
-                name: badge.name,
-            });
+        }
+
+        data_builder.badges.push(PlayerInfoBadge {
+            count: progress,
+            level_name: highest_level.name.clone(),
+            rewarded_levels: level_names,
+            name: badge.name,
         });
+    }
+
+    newly_rewarded
 }
 
 /// Temporary data for storing changes to challenges
+#[derive(Debug, Clone)]
 pub struct ChallengeProgressChange {
     /// The challenge definition
     pub definition: &'static ChallengeDefinition,
@@ -476,9 +744,105 @@ fn process_challenges(activities: &[ActivityEvent], data_builder: &mut PlayerDat
         });
 }
 
+/// Collects the challenge progress made from the `activities`
+pub fn activity_challenge_progress(activities: &[ActivityEvent]) -> Vec<ChallengeProgressChange> {
+    let mut data_builder = PlayerDataBuilder::new();
+    process_challenges(activities, &mut data_builder);
+    data_builder.challenges_updates
+}
+
+/// Adds the `update` to the `changes` combining it with any existing
+/// progress for the same challenge counter
+pub fn merge_challenge_progress(
+    changes: &mut Vec<ChallengeProgressChange>,
+    update: ChallengeProgressChange,
+) {
+    let existing = changes
+        .iter_mut()
+        // Check if theres already a matching progress update
+        .find(|value| {
+            value.definition.name == update.definition.name
+                && value.counter.name == update.counter.name
+        });
+
+    if let Some(existing) = existing {
+        existing.progress = existing.progress.saturating_add(update.progress);
+    } else {
+        changes.push(update);
+    }
+}
+
+/// Collects the challenge progress made by each of the players from the
+/// mission `player_data`, players that were reported more than once only
+/// have their first report counted
+fn collect_player_challenges(
+    player_data: &[MissionPlayerData],
+) -> HashMap<UserId, Vec<ChallengeProgressChange>> {
+    let mut player_challenges = HashMap::with_capacity(player_data.len());
+
+    for data in player_data {
+        player_challenges
+            .entry(data.nucleus_id)
+            .or_insert_with(|| activity_challenge_progress(&data.activity_report.activities));
+    }
+
+    player_challenges
+}
+
+/// Removes the `applied` progress from the `changes`, used for progress
+/// that was already stored from activities reported during the mission
+fn subtract_challenge_progress(
+    changes: &mut Vec<ChallengeProgressChange>,
+    applied: &[ChallengeProgressChange],
+) {
+    for change in changes.iter_mut() {
+        let existing = applied.iter().find(|value| {
+            value.definition.name == change.definition.name
+                && value.counter.name == change.counter.name
+        });
+
+        if let Some(existing) = existing {
+            change.progress = change.progress.saturating_sub(existing.progress);
+        }
+    }
+
+    changes.retain(|change| change.progress > 0);
+}
+
+/// Totals the progress made towards community challenges by
+/// all of the players in the mission
+fn community_progress(
+    player_challenges: &HashMap<UserId, Vec<ChallengeProgressChange>>,
+) -> Vec<ChallengeProgressChange> {
+    let mut community = Vec::new();
+
+    for change in player_challenges.values().flatten() {
+        if change.definition.community {
+            merge_challenge_progress(&mut community, change.clone());
+        }
+    }
+
+    community
+}
+
+/// Replaces the community challenge progress in the player `changes` with
+/// the `community` progress of the whole mission. The player's own progress
+/// is already part of the community total so it's replaced rather than added
+/// to prevent it from being counted twice
+fn share_community_progress(
+    changes: &mut Vec<ChallengeProgressChange>,
+    community: &[ChallengeProgressChange],
+) {
+    changes.retain(|change| !change.definition.community);
+    changes.extend(community.iter().cloned());
+}
+
 /// Computes the xp and currency rewards from the provided mission modifiers
 /// appending them to the provided data builder
-fn compute_modifiers(mission_modifiers: &[MissionModifier], data_builder: &mut PlayerDataBuilder) {
+pub fn compute_modifiers(
+    mission_modifiers: &[MissionModifier],
+    data_builder: &mut PlayerDataBuilder,
+) {
     let match_modifiers = MatchModifiers::get();
 
     mission_modifiers
@@ -518,9 +882,27 @@ fn compute_modifiers(mission_modifiers: &[MissionModifier], data_builder: &mut P
 
 pub const DEFAULT_FIT: u16 = 21600;
 
+/// Checks whether players removed for the `reason` lost their connection
+/// rather than leaving the game
+fn is_disconnect(reason: RemoveReason) -> bool {
+    matches!(
+        reason,
+        RemoveReason::PlayerConnectionLost | RemoveReason::ServerConnectionLost
+    )
+}
+
 impl Game {
     pub const MAX_PLAYERS: usize = 4;
 
+    /// Game state while the host is being migrated
+    const MIGRATING_STATE: u8 = 0x5;
+
+    /// Game state while the mission is being played
+    pub const IN_GAME_STATE: u8 = 131;
+
+    /// Migration type for moving the topology host to another player
+    const TOPOLOGY_HOST_MIGRATION: u32 = 0x2;
+
     pub fn new(
         id: u32,
         attributes: TdfMap<String, String>,
@@ -532,9 +914,20 @@ impl Game {
             settings: 262144,
             attributes,
             players: Vec::with_capacity(4),
+            host: None,
+            created: Instant::now(),
+            peak_players: 0,
             modifiers: Vec::new(),
+            mission_reservation: None,
             mission_data: None,
             processed_data: None,
+            mission_revision: 0,
+            mission_claimed: None,
+            mission_processed: Default::default(),
+            mutators: Vec::new(),
+            banned: Vec::new(),
+            ready_check_started: clock::now(),
+            timeline: GameTimeline::default(),
             game_manager,
         }
     }
@@ -549,7 +942,15 @@ impl Game {
             },
         );
 
+        self.timeline.push(GameTimelineEvent::AttributesChanged {
+            attributes: attributes
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+        });
+
         self.attributes.insert_presorted(attributes.into_inner());
+        self.game_manager.update_index(self);
 
         debug!("Updated game attributes");
 
@@ -557,6 +958,10 @@ impl Game {
     }
 
     pub fn set_player_attributes(&mut self, user_id: UserId, attributes: AttrMap) {
+        let ready_changed = attributes
+            .iter()
+            .any(|(key, _)| key == ready_check::READY_ATTR);
+
         let packet = Packet::notify(
             game_manager::COMPONENT,
             game_manager::PLAYER_ATTR_UPDATE,
@@ -579,92 +984,149 @@ impl Game {
         if let Some(player) = player {
             player.attr.insert_presorted(attributes.into_inner());
         }
+
+        if ready_changed {
+            self.update_all_ready();
+        }
     }
 
-    pub fn set_complete_mission(&mut self, mission_data: CompleteMissionData) {
-        self.mission_data = Some(mission_data);
-        self.processed_data = None;
+    /// Checks whether all the players other than the host are ready
+    fn all_ready(&self) -> bool {
+        ready_check::all_ready(
+            self.players
+                .iter()
+                .filter(|player| !self.is_host(player.user.id))
+                .map(|player| &player.attr),
+        )
     }
 
-    pub fn set_modifiers(&mut self, modifiers: Vec<MissionModifier>) {
-        self.modifiers = modifiers;
+    /// Updates the game attribute advertising whether all the players
+    /// are ready, only notifies the players when the state changes
+    fn update_all_ready(&mut self) {
+        if let Some(attributes) =
+            ready_check::all_ready_attributes(&self.attributes, self.all_ready())
+        {
+            self.set_attributes(attributes);
+        }
+    }
+
+    /// Checks whether the host can start the mission, requires all the
+    /// players to be ready unless the ready check has timed out
+    pub fn can_start_mission(&self) -> bool {
+        self.all_ready() || ready_check::timed_out(self.ready_check_started)
     }
 
-    pub async fn get_mission_details(&mut self, db: &DatabaseConnection) -> Option<MissionDetails> {
-        if let Some(processed) = self.processed_data.clone() {
-            return Some(processed);
+    /// Clears the ready state of the players once the mission has been
+    /// started so they have to ready up again for the next mission
+    pub fn reset_ready_check(&mut self) {
+        let user_ids: Vec<UserId> = self
+            .players
+            .iter()
+            .map(|player| player.user.id)
+            .filter(|user_id| !self.is_host(*user_id))
+            .collect();
+
+        for user_id in user_ids {
+            self.set_player_attributes(user_id, ready_check::not_ready_attributes());
         }
 
-        let mission_data = self.mission_data.clone()?;
+        self.ready_check_started = clock::now();
+    }
 
-        let now = Utc::now();
+    /// Checks whether the user with the provided `user_id` is a player in the game
+    pub fn has_player(&self, user_id: UserId) -> bool {
+        self.players.iter().any(|player| player.user.id == user_id)
+    }
 
-        let waves = mission_data
-            .player_data
-            .iter()
-            .map(|value| value.waves_completed)
-            .max()
-            .unwrap_or_default();
+    /// Reserves the mission for the game, replaces any previous reservation
+    /// when the mission is restarted
+    pub fn reserve_mission(&mut self, user_id: UserId, mission: Option<StrikeTeamMissionId>) {
+        self.mission_reservation = Some(MissionReservation {
+            started_by: user_id,
+            mission,
+            started: clock::now(),
+            finished: Vec::new(),
+        });
+    }
 
-        let level: String = mission_data
-            .modifiers
-            .iter()
-            .find(|value| value.name == "level")
-            .map(|value| value.value.clone())
-            .unwrap_or_else(|| "MPAqua".to_string());
-        let difficulty: String = mission_data
-            .modifiers
-            .iter()
-            .find(|value| value.name == "difficulty")
-            .map(|value| value.value.clone())
-            .unwrap_or_else(|| "bronze".to_string());
-        let enemy_type: String = mission_data
-            .modifiers
-            .iter()
-            .find(|value| value.name == "enemytype")
-            .map(|value| value.value.clone())
-            .unwrap_or_else(|| "outlaw".to_string());
+    /// Stores the completed `mission_data` responding with the job for
+    /// processing a snapshot of it
+    pub fn set_complete_mission(
+        &mut self,
+        mut mission_data: CompleteMissionData,
+    ) -> MissionResultJob {
+        mutators::apply_modifiers(&mut mission_data.modifiers, &self.mutators);
 
-        let mut player_infos = Vec::with_capacity(mission_data.player_data.len());
+        self.timeline.push(GameTimelineEvent::MissionFinished {
+            percent_complete: mission_data.percent_complete,
+            extraction_state: mission_data.extraction_state.clone(),
+        });
 
-        for value in &mission_data.player_data {
-            match process_player_data(db.clone(), value, &mission_data).await {
-                Ok(info) => {
-                    player_infos.push(info);
-                }
-                Err(err) => {
-                    error!("Error while processing player: {}", err);
-                }
-            }
+        self.mission_data = Some(mission_data.clone());
+        self.processed_data = None;
+        self.mission_revision = self.mission_revision.wrapping_add(1);
+
+        MissionResultJob {
+            game_id: self.id,
+            revision: self.mission_revision,
+            mission_data,
         }
+    }
 
-        let data = MissionDetails {
-            sku: Sku,
-            name: mission_data.match_id,
-            duration_sec: mission_data.duration_sec,
-            percent_complete: mission_data.percent_complete,
-            waves_encountered: waves,
-            extraction_state: mission_data.extraction_state,
-            enemy_type,
-            difficulty,
-            map: level,
-            start: now,
-            end: now,
-            processed: now,
-            player_infos,
-            modifiers: mission_data.modifiers,
-        };
+    /// Stores the `details` processed from the mission data submitted at
+    /// `revision` waking anyone waiting for them, ignored if newer mission
+    /// data has been submitted since
+    pub fn set_processed_data(&mut self, revision: u32, details: MissionDetails) {
+        if self.mission_revision != revision {
+            return;
+        }
+
+        ServerEvents::get().publish(ServerEvent::GameFinished {
+            game_id: self.id,
+            map: details.map.clone(),
+            enemy_type: details.enemy_type.clone(),
+            difficulty: details.difficulty.clone(),
+            percent_complete: details.percent_complete,
+            extraction_state: details.extraction_state.clone(),
+            player_count: details.player_infos.len(),
+        });
+
+        self.processed_data = Some(details);
+        self.mission_processed.notify_waiters();
+    }
+
+    pub fn set_modifiers(&mut self, modifiers: Vec<MissionModifier>) {
+        self.timeline.push(GameTimelineEvent::MissionStarted {
+            modifiers: modifiers.clone(),
+        });
 
-        self.processed_data = Some(data.clone());
+        self.modifiers = modifiers;
+    }
 
-        Some(data)
+    /// Creates a snapshot of the current game for spectators
+    pub fn timeline_snapshot(&self) -> TimelineSnapshot {
+        TimelineSnapshot {
+            game_id: self.id,
+            state: self.state,
+            attributes: self
+                .attributes
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+            players: self.players.iter().map(Player::timeline_player).collect(),
+            modifiers: self.modifiers.clone(),
+        }
     }
 
     pub fn set_state(&mut self, state: u8) {
         self.state = state;
+        self.game_manager.update_index(self);
 
         debug!("Updated game state (Value: {:?})", &state);
 
+        self.timeline
+            .push(GameTimelineEvent::StateChanged { state });
+
         self.notify_all(Packet::notify(
             game_manager::COMPONENT,
             game_manager::GAME_STATE_CHANGE,
@@ -679,18 +1141,60 @@ impl Game {
     /// removed from the game list
     pub(crate) fn stopped(self) {
         debug!("Game is stopped (GID: {})", self.id);
+
+        let (difficulty, completed) = match &self.mission_data {
+            Some(mission_data) => (
+                mission_data
+                    .modifiers
+                    .iter()
+                    .find(|value| value.name == "difficulty")
+                    .map(|value| value.value.clone()),
+                mission_data.percent_complete >= 100,
+            ),
+            None => (None, false),
+        };
+
+        self.game_manager.record_stats(GameStatsEvent::GameStopped {
+            difficulty,
+            duration: self.created.elapsed(),
+            completed,
+            peak_players: self.peak_players,
+        });
     }
 
     fn stop(&mut self) {
         // Mark the game as stopping
         // self.state = GameState::Destructing;
 
+        // Stop matching players into the game while it's removed
+        self.game_manager.remove_from_index(self.id);
+
         let game_manager = self.game_manager.clone();
         // Remove the stopping game
         let game_id = self.id;
-        tokio::spawn(async move {
+        let context = CrashContext::new("games").with_game(game_id);
+        tokio::spawn(crash_reports::scope(context, async move {
             game_manager.remove_game(game_id).await;
-        });
+        }));
+    }
+
+    /// Checks whether the player with the provided `user_id` is the host
+    pub fn is_host(&self, user_id: UserId) -> bool {
+        self.host == Some(user_id)
+    }
+
+    /// Obtains the player hosting the game
+    pub fn host_player(&self) -> Option<&Player> {
+        let host = self.host?;
+        self.players.iter().find(|player| player.user.id == host)
+    }
+
+    /// Prevents the player with the provided `user_id` from being
+    /// matched into the game again
+    pub fn ban_player(&mut self, user_id: UserId) {
+        if !self.banned.contains(&user_id) {
+            self.banned.push(user_id);
+        }
     }
 
     pub fn remove_player(&mut self, user_id: u32, reason: RemoveReason) {
@@ -711,9 +1215,21 @@ impl Game {
         // Remove the player
         let player = self.players.remove(index);
 
+        // Hold the slot of players that lost their connection mid mission
+        // so they can rejoin before it opens to other players
+        if is_disconnect(reason) && self.state == Self::IN_GAME_STATE {
+            self.game_manager.reserve_rejoin(self.id, user_id);
+        }
+
+        self.game_manager.update_index(self);
+
         // Set current game of this player
         player.try_clear_game();
 
+        self.timeline.push(GameTimelineEvent::PlayerRemoved {
+            player: player.timeline_player(),
+        });
+
         // Update the other players
         self.notify_player_removed(&player, reason);
         // self.notify_fetch_data(&player);
@@ -724,21 +1240,87 @@ impl Game {
             player.user.id, self.id
         );
 
-        // If the player was in the host slot attempt migration
-        if index == 0 {
-            // self.try_migrate_host();
-        }
-
         if self.players.is_empty() {
             // Game is empty stop it
             self.stop();
+            return;
         }
+
+        // If the player was the host migrate to a new host
+        if self.is_host(user_id) {
+            self.migrate_host();
+        }
+
+        // The leaving player may have been the only player not ready
+        self.update_all_ready();
+    }
+
+    /// Migrates the host to the remaining player with the least restrictive
+    /// NAT so that the game stays alive when the host leaves, players keep
+    /// their slots.
+    ///
+    /// The server doesn't tunnel game traffic (Players connect directly to
+    /// the host address from the game data) so there are no tunnels to
+    /// rebind, players behind a strict NAT may be unable to reach the new
+    /// host until tunneling is supported
+    fn migrate_host(&mut self) {
+        let Some(new_host) = self
+            .players
+            .iter()
+            .min_by_key(|player| player.net.qos.nat_type.host_priority())
+        else {
+            return;
+        };
+
+        let host_id = new_host.user.id;
+        // Slots are always below the maximum player count
+        let slot = new_host.slot as u8;
+        let host = new_host.timeline_player();
+        self.host = Some(host_id);
+
+        debug!("Migrating game host (PID: {}, GID: {})", host_id, self.id);
+
+        let previous_state = self.state;
+        self.set_state(Self::MIGRATING_STATE);
+
+        self.notify_all(Packet::notify(
+            game_manager::COMPONENT,
+            game_manager::HOST_MIGRATION_START,
+            HostMigrateStart {
+                game_id: self.id,
+                host_id,
+                pmig: Self::TOPOLOGY_HOST_MIGRATION,
+                slot,
+            },
+        ));
+
+        self.timeline.push(GameTimelineEvent::HostMigrated { host });
+
+        self.notify_all(Packet::notify(
+            game_manager::COMPONENT,
+            game_manager::HOST_MIGRATION_FINISHED,
+            HostMigrateFinished { game_id: self.id },
+        ));
+
+        // Return to the state the game was in before the host left
+        self.set_state(previous_state);
     }
 
-    pub fn add_player(&mut self, player: Player, context: GameSetupContext) -> usize {
-        let slot = self.players.len();
+    pub fn add_player(&mut self, mut player: Player, context: GameSetupContext) -> usize {
+        // Players take the lowest free slot, slots are kept until the player leaves
+        let slot = (0..)
+            .find(|slot| !self.players.iter().any(|player| player.slot == *slot))
+            .unwrap_or_default();
+        player.slot = slot;
+
+        // The first player to join hosts the game
+        if self.host.is_none() {
+            self.host = Some(player.user.id);
+        }
 
         self.players.push(player);
+        self.peak_players = self.peak_players.max(self.players.len());
+        self.game_manager.update_index(self);
 
         // Obtain the player that was just added
         let player = self
@@ -746,6 +1328,10 @@ impl Game {
             .last()
             .expect("Player was added but is missing from players");
 
+        self.timeline.push(GameTimelineEvent::PlayerJoined {
+            player: player.timeline_player(),
+        });
+
         // NOTIFY PLAYER JOINING
         // Notify other players of the joined player
         // self.notify_all(
@@ -778,6 +1364,10 @@ impl Game {
             },
         ));
 
+        // Joining players have to ready up before the mission can start
+        self.ready_check_started = clock::now();
+        self.update_all_ready();
+
         slot
     }
 
@@ -859,6 +1449,105 @@ impl Game {
 /// Attributes map type
 pub type AttrMap = TdfMap<String, String>;
 
+/// Reservation for a mission that has been started within a game
+#[derive(Debug, Clone)]
+pub struct MissionReservation {
+    /// ID of the user that started the mission
+    pub started_by: UserId,
+    /// The APEX mission being played if the mission is one
+    pub mission: Option<StrikeTeamMissionId>,
+    /// When the mission was started
+    pub started: DateTime<Utc>,
+    /// IDs of the players that have finished the mission
+    pub finished: Vec<UserId>,
+}
+
+/// Cosmetics the player has equipped that are shown to the other
+/// players in the lobby, loaded into the session data at login
+#[derive(Debug, Default, Clone)]
+pub struct PlayerCosmetics {
+    /// Name of the equipped banner / nameplate item
+    pub banner: Option<ItemName>,
+    /// Name of the equipped player title
+    pub title: Option<PlayerTitleName>,
+}
+
+impl PlayerCosmetics {
+    /// Player attribute containing the equipped banner item name
+    const BANNER_ATTR: &str = "banner";
+    /// Player attribute containing the texture of the equipped banner
+    const BANNER_TEXTURE_ATTR: &str = "bannerTexture";
+    /// Banner definition custom attribute containing the banner texture
+    const TEXTURE_CUSTOM_ATTR: &str = "UnlockedTextureId";
+    /// Player attribute containing the equipped title name
+    const TITLE_ATTR: &str = "title";
+    /// Player attribute containing the text of the equipped title
+    const TITLE_TEXT_ATTR: &str = "titleText";
+
+    /// Finds the equipped cosmetics within the shared `equipment`
+    pub fn from_equipment(equipment: &[CharacterEquipment]) -> Self {
+        let banner = equipment
+            .iter()
+            .find(|equipment| equipment.slot == EquipmentSlot::BannerSlot)
+            .and_then(|equipment| match equipment.name {
+                NameOrEmpty::Name(name) => Some(name),
+                NameOrEmpty::Empty => None,
+            });
+
+        Self {
+            banner,
+            title: None,
+        }
+    }
+
+    /// Sets the equipped player `title`
+    pub fn with_title(mut self, title: Option<PlayerTitleName>) -> Self {
+        self.title = title;
+        self
+    }
+
+    /// Creates the player attributes describing the cosmetics, the banner
+    /// texture is taken from the custom attributes of the banner definition
+    /// and the title text from the title definition.
+    /// Attributes for unequipped cosmetics are empty so that unequipping
+    /// clears the cosmetic for the other players
+    pub fn attributes(&self) -> AttrMap {
+        let banner = self.banner.map(|banner| banner.to_string());
+        let texture = self
+            .banner
+            .and_then(|banner| Items::get().by_name(&banner))
+            .and_then(|definition| {
+                definition
+                    .custom_attributes
+                    .get_str(Self::TEXTURE_CUSTOM_ATTR)
+            })
+            .map(str::to_string);
+        let title_text = self
+            .title
+            .as_deref()
+            .and_then(|title| PlayerTitles::get().by_name(title))
+            .map(|title| title.text.clone());
+
+        [
+            (Self::BANNER_ATTR.to_string(), banner.unwrap_or_default()),
+            (
+                Self::BANNER_TEXTURE_ATTR.to_string(),
+                texture.unwrap_or_default(),
+            ),
+            (
+                Self::TITLE_ATTR.to_string(),
+                self.title.clone().unwrap_or_default(),
+            ),
+            (
+                Self::TITLE_TEXT_ATTR.to_string(),
+                title_text.unwrap_or_default(),
+            ),
+        ]
+        .into_iter()
+        .collect()
+    }
+}
+
 pub struct Player {
     pub user: Arc<User>,
     pub link: WeakSessionLink,
@@ -866,6 +1555,8 @@ pub struct Player {
     pub net: Arc<NetData>,
     pub state: PlayerState,
     pub attr: AttrMap,
+    /// Slot the player occupies in the game
+    pub slot: usize,
 }
 
 impl Drop for Player {
@@ -880,6 +1571,7 @@ impl Player {
         link: WeakSessionLink,
         notify_handle: SessionNotifyHandle,
         net: Arc<NetData>,
+        cosmetics: &PlayerCosmetics,
     ) -> Self {
         Self {
             user,
@@ -887,7 +1579,18 @@ impl Player {
             notify_handle,
             net,
             state: PlayerState::ActiveConnecting,
-            attr: AttrMap::default(),
+            // Cosmetics are included in the attributes sent to other players
+            attr: cosmetics.attributes(),
+            slot: 0,
+        }
+    }
+
+    /// Details about the player to show on the game timeline
+    pub fn timeline_player(&self) -> TimelinePlayer {
+        TimelinePlayer {
+            user_id: self.user.id,
+            username: self.user.username.clone(),
+            platform: self.user.platform,
         }
     }
 
@@ -914,7 +1617,7 @@ impl Player {
         self.notify_handle.notify(packet);
     }
 
-    pub fn encode<S: tdf::TdfSerializer>(&self, game_id: u32, slot: usize, w: &mut S) {
+    pub fn encode<S: tdf::TdfSerializer>(&self, game_id: u32, w: &mut S) {
         w.tag_blob_empty(b"BLOB");
         w.tag_owned(b"CONG", self.user.id);
         w.tag_u8(b"CSID", 0);
@@ -936,7 +1639,7 @@ impl Player {
         w.tag_u8(b"PSET", 1);
         w.tag_u8(b"RCRE", 0);
         w.tag_str_empty(b"ROLE");
-        w.tag_usize(b"SID", slot);
+        w.tag_usize(b"SID", self.slot);
         w.tag_u8(b"SLOT", 0);
         w.tag_ref(b"STAT", &self.state);
         w.tag_u16(b"TIDX", 0);
@@ -959,3 +1662,151 @@ impl Player {
         w.tag_group_end();
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{
+        community_progress, process_badges, share_community_progress, subtract_challenge_progress,
+        ChallengeProgressChange, PlayerDataBuilder,
+    };
+    use crate::{
+        definitions::{
+            badges::Badges,
+            challenges::{ChallengeDefinition, Challenges},
+        },
+        services::activity::{ActivityEvent, ActivityName},
+    };
+    use std::collections::HashMap;
+
+    /// Creates a progress change for a copy of the first challenge
+    /// definition with the provided `community` flag
+    fn change(community: bool, progress: u32) -> ChallengeProgressChange {
+        let definition = Challenges::get()
+            .values
+            .iter()
+            .find(|definition| !definition.counters.is_empty())
+            .expect("Missing challenge with counters");
+
+        let mut definition: ChallengeDefinition =
+            serde_json::from_value(serde_json::to_value(definition).unwrap()).unwrap();
+        definition.community = community;
+        if community {
+            definition.name = uuid::Uuid::new_v4();
+        }
+
+        let definition: &'static ChallengeDefinition = Box::leak(Box::new(definition));
+
+        ChallengeProgressChange {
+            definition,
+            counter: &definition.counters[0],
+            progress,
+        }
+    }
+
+    /// Tests that community progress from every player in a mixed party is
+    /// shared with each player without counting their own progress twice
+    #[test]
+    fn test_share_community_progress() {
+        let community = change(true, 2);
+        let personal = change(false, 5);
+
+        let mut player_challenges = HashMap::new();
+        // Player progressing both challenges
+        player_challenges.insert(1, vec![community.clone(), personal.clone()]);
+        // Player only progressing the community challenge
+        player_challenges.insert(
+            2,
+            vec![ChallengeProgressChange {
+                progress: 3,
+                ..community.clone()
+            }],
+        );
+        // Player without any community progress
+        player_challenges.insert(3, vec![personal.clone()]);
+
+        let total = community_progress(&player_challenges);
+        assert_eq!(total.len(), 1);
+        assert_eq!(total[0].progress, 5);
+
+        // Own community progress is replaced by the total, personal progress is kept
+        let mut changes = player_challenges.remove(&1).unwrap();
+        share_community_progress(&mut changes, &total);
+        assert_eq!(changes.len(), 2);
+        assert!(changes
+            .iter()
+            .any(|change| !change.definition.community && change.progress == 5));
+        assert!(changes
+            .iter()
+            .any(|change| change.definition.community && change.progress == 5));
+
+        // Players without their own progress still receive the total
+        let mut changes = player_challenges.remove(&3).unwrap();
+        share_community_progress(&mut changes, &total);
+        assert_eq!(
+            changes
+                .iter()
+                .filter(|change| change.definition.community)
+                .map(|change| change.progress)
+                .sum::<u32>(),
+            5
+        );
+    }
+
+    /// Tests that progress already stored during the mission is removed
+    /// from the mission progress
+    #[test]
+    fn test_subtract_challenge_progress() {
+        let mut changes = vec![change(false, 5)];
+        subtract_challenge_progress(&mut changes, &[change(false, 3)]);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].progress, 2);
+
+        // Fully applied progress is removed
+        subtract_challenge_progress(&mut changes, &[change(false, 4)]);
+        assert!(changes.is_empty());
+    }
+
+    /// Tests that finishing a mission twice with the same badge progress
+    /// only rewards the badge levels once
+    #[test]
+    fn test_process_badges_rewards_once() {
+        let badge = Badges::get()
+            .all()
+            .iter()
+            .find(|badge| {
+                badge.enabled
+                    && badge
+                        .levels
+                        .first()
+                        .is_some_and(|level| level.xp_reward > 0)
+            })
+            .expect("Missing badge with XP rewards");
+        let target_count = badge.levels.first().unwrap().target_count;
+
+        let activities = [ActivityEvent {
+            name: ActivityName::BadgeEarned,
+            attributes: [
+                ("badgeName".to_string(), badge.name.into()),
+                ("count".to_string(), target_count.into()),
+            ]
+            .into_iter()
+            .collect(),
+        }];
+
+        let mut rewarded = HashMap::new();
+
+        let mut data_builder = PlayerDataBuilder::new();
+        let newly_rewarded = process_badges(&activities, &mut rewarded, &mut data_builder);
+        assert_eq!(newly_rewarded, vec![badge.name]);
+        assert!(data_builder.xp_earned > 0);
+        assert_eq!(data_builder.badges[0].rewarded_levels.len(), 1);
+
+        // The same progress again doesn't give any more rewards
+        let mut data_builder = PlayerDataBuilder::new();
+        let newly_rewarded = process_badges(&activities, &mut rewarded, &mut data_builder);
+        assert!(newly_rewarded.is_empty());
+        assert_eq!(data_builder.xp_earned, 0);
+        assert!(data_builder.reward_sources.is_empty());
+        assert!(data_builder.badges[0].rewarded_levels.is_empty());
+    }
+}
@@ -0,0 +1,381 @@
+//! Index of the active games by state and attribute, kept up to date
+//! by the games themselves so that matchmaking can find candidate games
+//! without locking every game on the server
+
+use super::game::{AttrMap, GameID};
+use crate::{
+    blaze::models::user_sessions::NatType,
+    database::entity::users::{Platform, UserId},
+    utils::hashing::{IntHashMap, IntHashSet},
+};
+use std::collections::HashMap;
+
+/// Summary of a game stored in the index
+struct IndexedGame {
+    /// The current game state
+    state: u8,
+    /// The game attributes
+    attributes: AttrMap,
+    /// Number of players in the game
+    players: usize,
+    /// Details about the game host
+    host: IndexedHost,
+    /// Players banned from the game by the host
+    banned: Vec<UserId>,
+}
+
+/// Details about the host of an indexed game
+#[derive(Debug, Default, Clone)]
+pub struct IndexedHost {
+    /// Ping site closest to the game host
+    pub region: Option<String>,
+    /// Platform of the game host
+    pub platform: Platform,
+    /// NAT classification of the game host
+    pub nat_type: NatType,
+}
+
+/// Candidate game found by [`GameIndex::matching`]
+pub struct IndexMatch {
+    /// ID of the matching game
+    pub game_id: GameID,
+    /// Number of players in the game
+    pub players: usize,
+    /// Details about the game host
+    pub host: IndexedHost,
+    /// Players banned from the game by the host
+    pub banned: Vec<UserId>,
+}
+
+/// Indexed game listed by [`GameIndex::browse`]
+pub struct IndexedSummary {
+    /// ID of the game
+    pub game_id: GameID,
+    /// The current game state
+    pub state: u8,
+    /// The game attributes
+    pub attributes: AttrMap,
+    /// Number of players in the game
+    pub players: usize,
+}
+
+#[derive(Default)]
+pub struct GameIndex {
+    /// Summaries of the indexed games
+    games: IntHashMap<GameID, IndexedGame>,
+    /// Games grouped by their current state
+    by_state: HashMap<u8, IntHashSet<GameID>>,
+    /// Games grouped by attribute key then attribute value
+    by_attribute: HashMap<String, HashMap<String, IntHashSet<GameID>>>,
+}
+
+impl GameIndex {
+    /// Inserts or replaces the indexed details for a game
+    pub fn update(
+        &mut self,
+        game_id: GameID,
+        state: u8,
+        attributes: &AttrMap,
+        players: usize,
+        host: IndexedHost,
+        banned: &[UserId],
+    ) {
+        self.remove(game_id);
+
+        self.by_state.entry(state).or_default().insert(game_id);
+
+        for (key, value) in attributes.iter() {
+            self.by_attribute
+                .entry(key.clone())
+                .or_default()
+                .entry(value.clone())
+                .or_default()
+                .insert(game_id);
+        }
+
+        self.games.insert(
+            game_id,
+            IndexedGame {
+                state,
+                attributes: attributes.clone(),
+                players,
+                host,
+                banned: banned.to_vec(),
+            },
+        );
+    }
+
+    /// Removes a game from the index
+    pub fn remove(&mut self, game_id: GameID) {
+        let Some(game) = self.games.remove(&game_id) else {
+            return;
+        };
+
+        if let Some(games) = self.by_state.get_mut(&game.state) {
+            games.remove(&game_id);
+            if games.is_empty() {
+                self.by_state.remove(&game.state);
+            }
+        }
+
+        for (key, value) in game.attributes.iter() {
+            let Some(values) = self.by_attribute.get_mut(key) else {
+                continue;
+            };
+
+            if let Some(games) = values.get_mut(value) {
+                games.remove(&game_id);
+                if games.is_empty() {
+                    values.remove(value);
+                }
+            }
+
+            if values.is_empty() {
+                self.by_attribute.remove(key);
+            }
+        }
+    }
+
+    /// Finds the games in one of the provided `states` that have at most
+    /// `max_mismatched` of the `criteria` attributes differing, games are
+    /// ordered oldest first
+    pub fn matching(
+        &self,
+        criteria: &AttrMap,
+        max_mismatched: usize,
+        states: &[u8],
+    ) -> Vec<IndexMatch> {
+        let required = criteria.len().saturating_sub(max_mismatched);
+
+        let mut game_ids: Vec<GameID> = if required == 0 {
+            // Every game in the allowed states matches
+            states
+                .iter()
+                .filter_map(|state| self.by_state.get(state))
+                .flatten()
+                .copied()
+                .collect()
+        } else {
+            // Count the criteria each game matches using the attribute index
+            let mut matched: IntHashMap<GameID, usize> = IntHashMap::default();
+            for (key, value) in criteria.iter() {
+                let Some(games) = self
+                    .by_attribute
+                    .get(key)
+                    .and_then(|values| values.get(value))
+                else {
+                    continue;
+                };
+
+                for game_id in games {
+                    *matched.entry(*game_id).or_default() += 1;
+                }
+            }
+
+            matched
+                .into_iter()
+                .filter(|(game_id, count)| {
+                    *count >= required
+                        && states.iter().any(|state| {
+                            self.by_state
+                                .get(state)
+                                .is_some_and(|games| games.contains(game_id))
+                        })
+                })
+                .map(|(game_id, _)| game_id)
+                .collect()
+        };
+
+        game_ids.sort_unstable();
+        game_ids
+            .into_iter()
+            .filter_map(|game_id| {
+                let game = self.games.get(&game_id)?;
+                Some(IndexMatch {
+                    game_id,
+                    players: game.players,
+                    host: game.host.clone(),
+                    banned: game.banned.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Checks whether the game is indexed in one of the provided `states`
+    pub fn in_state(&self, game_id: GameID, states: &[u8]) -> bool {
+        self.games
+            .get(&game_id)
+            .is_some_and(|game| states.contains(&game.state))
+    }
+
+    /// Lists the games that have all of the `filters` attributes,
+    /// games are ordered oldest first
+    pub fn browse(&self, filters: &[(&str, &str)]) -> Vec<IndexedSummary> {
+        let mut games: Vec<IndexedSummary> = self
+            .games
+            .iter()
+            .filter(|(_, game)| {
+                filters.iter().all(|(key, value)| {
+                    game.attributes
+                        .iter()
+                        .any(|(other_key, other_value)| other_key == key && other_value == value)
+                })
+            })
+            .map(|(game_id, game)| IndexedSummary {
+                game_id: *game_id,
+                state: game.state,
+                attributes: game.attributes.clone(),
+                players: game.players,
+            })
+            .collect();
+
+        games.sort_unstable_by_key(|game| game.game_id);
+        games
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{GameIndex, IndexedHost};
+    use crate::{
+        blaze::models::user_sessions::NatType, database::entity::users::Platform,
+        services::game::AttrMap,
+    };
+
+    fn attributes(values: &[(&str, &str)]) -> AttrMap {
+        values
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    /// Tests that games are matched with the allowed number of
+    /// mismatched criteria and only in the requested states
+    #[test]
+    fn test_matching_relaxed_criteria() {
+        let mut index = GameIndex::default();
+        index.update(
+            1,
+            1,
+            &attributes(&[("ME3map", "1"), ("ME3privacy", "0")]),
+            1,
+            IndexedHost::default(),
+            &[],
+        );
+        index.update(
+            2,
+            1,
+            &attributes(&[("ME3map", "2"), ("ME3privacy", "0")]),
+            2,
+            IndexedHost::default(),
+            &[],
+        );
+        index.update(
+            3,
+            4,
+            &attributes(&[("ME3map", "1"), ("ME3privacy", "0")]),
+            3,
+            IndexedHost::default(),
+            &[],
+        );
+
+        let criteria = attributes(&[("ME3map", "1"), ("ME3privacy", "0")]);
+
+        let ids = |max_mismatched| -> Vec<u32> {
+            index
+                .matching(&criteria, max_mismatched, &[1])
+                .into_iter()
+                .map(|game| game.game_id)
+                .collect()
+        };
+
+        assert_eq!(ids(0), vec![1]);
+        assert_eq!(ids(1), vec![1, 2]);
+        assert_eq!(ids(2), vec![1, 2]);
+    }
+
+    /// Tests that updating and removing games leaves no stale entries
+    #[test]
+    fn test_update_replaces_entries() {
+        let mut index = GameIndex::default();
+        index.update(
+            1,
+            1,
+            &attributes(&[("ME3map", "1")]),
+            1,
+            IndexedHost::default(),
+            &[],
+        );
+        index.update(
+            1,
+            130,
+            &attributes(&[("ME3map", "2")]),
+            2,
+            IndexedHost {
+                region: Some("bio-dub".to_string()),
+                platform: Platform::Steam,
+                nat_type: NatType::Moderate,
+            },
+            &[],
+        );
+
+        let criteria = attributes(&[("ME3map", "1")]);
+        assert!(index.matching(&criteria, 0, &[1, 130]).is_empty());
+
+        let criteria = attributes(&[("ME3map", "2")]);
+        let matches = index.matching(&criteria, 0, &[130]);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].players, 2);
+        assert_eq!(matches[0].host.region.as_deref(), Some("bio-dub"));
+        assert_eq!(matches[0].host.platform, Platform::Steam);
+        assert_eq!(matches[0].host.nat_type, NatType::Moderate);
+
+        index.remove(1);
+        assert!(index.games.is_empty());
+        assert!(index.by_state.is_empty());
+        assert!(index.by_attribute.is_empty());
+    }
+
+    /// Tests that browsing only lists games with every filter attribute
+    #[test]
+    fn test_browse_filters() {
+        let mut index = GameIndex::default();
+        index.update(
+            2,
+            1,
+            &attributes(&[("difficulty", "gold"), ("level", "MPAqua")]),
+            1,
+            IndexedHost::default(),
+            &[],
+        );
+        index.update(
+            1,
+            131,
+            &attributes(&[("difficulty", "gold"), ("level", "MPRoad")]),
+            4,
+            IndexedHost::default(),
+            &[],
+        );
+        index.update(
+            3,
+            1,
+            &attributes(&[("difficulty", "bronze"), ("level", "MPAqua")]),
+            2,
+            IndexedHost::default(),
+            &[],
+        );
+
+        let ids = |filters: &[(&str, &str)]| -> Vec<u32> {
+            index
+                .browse(filters)
+                .into_iter()
+                .map(|game| game.game_id)
+                .collect()
+        };
+
+        assert_eq!(ids(&[]), vec![1, 2, 3]);
+        assert_eq!(ids(&[("difficulty", "gold")]), vec![1, 2]);
+        assert_eq!(ids(&[("difficulty", "gold"), ("level", "MPAqua")]), vec![2]);
+        assert!(ids(&[("difficulty", "platinum")]).is_empty());
+    }
+}
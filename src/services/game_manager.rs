@@ -1,77 +1,769 @@
-use super::game::{AttrMap, Game, GameID, GameRef, Player};
+use super::{
+    crash_reports::{self, CrashContext},
+    game::{AttrMap, Game, GameID, GameRef, MissionReservation, Player, DEFAULT_FIT},
+    game_index::{GameIndex, IndexMatch, IndexedHost},
+    game_stats::{GameStatsEvent, GameStatsRecorder},
+    mutators,
+    server_events::{ServerEvent, ServerEvents},
+};
 use crate::{
-    blaze::{models::game_manager::GameSetupContext, session::SessionLink},
-    utils::hashing::IntHashMap,
+    blaze::{
+        components::game_manager,
+        models::game_manager::{GameSetupContext, MatchmakingResult, NotifyMatchmakingStatus},
+        packet::Packet,
+        session::SessionLink,
+    },
+    config::Config,
+    database::entity::{
+        users::{Platform, UserId},
+        MatchmakingPreferences,
+    },
+    http::models::mission::CompleteMissionData,
+    utils::{clock, hashing::IntHashMap},
 };
 use log::{debug, warn};
+use parking_lot::Mutex;
+use serde::Serialize;
 use std::{
+    collections::{HashMap, VecDeque},
+    mem::size_of,
     sync::{
         atomic::{AtomicU32, Ordering},
         Arc,
     },
-    time::Duration,
+    time::{Duration, Instant},
 };
-use tokio::sync::RwLock;
+use tokio::{sync::RwLock, time::interval};
+
+/// Number of shards the games are split across
+const SHARD_COUNT: usize = 16;
 
 /// Manager which controls all the active games on the server
 /// commanding them to do different actions and removing them
 /// once they are no longer used
 pub struct GameManager {
-    /// The map of games to the actual game address
-    games: RwLock<IntHashMap<GameID, GameRef>>,
+    /// The games split across shards by game ID so that looking up
+    /// games doesn't contend on a single lock
+    shards: [RwLock<IntHashMap<GameID, GameRef>>; SHARD_COUNT],
+    /// Index of the games by state and attributes used for matchmaking
+    index: Mutex<GameIndex>,
     /// Stored value for the ID to give the next game
     next_id: AtomicU32,
+    /// Players waiting in the matchmaking queue
+    queue: Mutex<VecDeque<MatchmakingEntry>>,
+    /// Durations players recently waited before finding a game, used
+    /// to estimate the wait time for queued players
+    recent_waits: Mutex<VecDeque<Duration>>,
+    /// Recorder for the matchmaking and game statistics
+    stats: GameStatsRecorder,
+    /// Slots held in games for matched players that haven't joined yet
+    reservations: Mutex<SlotReservations>,
+}
+
+/// Approximate memory held by the games and matchmaking state
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GameMemoryUsage {
+    /// Number of active games
+    pub games: usize,
+    /// Number of players across all the games
+    pub players: usize,
+    /// Number of games holding submitted or processed mission results
+    pub games_with_results: usize,
+    /// Number of players waiting in the matchmaking queue
+    pub queued: usize,
+    /// Number of slots reserved for matched players
+    pub reservations: usize,
+    /// Approximate number of bytes used, excludes the contents of
+    /// the game attributes and mission results
+    pub approximate_bytes: usize,
+}
+
+/// Game listed in the server browser
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BrowsedGame {
+    /// ID of the game
+    pub id: GameID,
+    /// The current game state
+    pub state: u8,
+    /// The game attributes (difficulty, map, enemy, etc)
+    pub attributes: HashMap<String, String>,
+    /// Number of players in the game
+    pub players: usize,
+    /// Maximum number of players the game can hold
+    pub max_players: usize,
+    /// Whether players can currently join the game
+    pub joinable: bool,
+}
+
+/// Mission that was still in flight when the server stopped
+pub struct InFlightMission {
+    /// ID of the game the mission was played in
+    pub game_id: GameID,
+    /// Reservation for a mission that was still being played
+    pub reservation: Option<MissionReservation>,
+    /// Submitted mission data that hadn't been processed
+    pub mission_data: Option<CompleteMissionData>,
+}
+
+/// Slot held in a game for a matched player until they join
+struct SlotReservation {
+    /// The player the slot is held for
+    user_id: UserId,
+    /// When the reservation stops holding the slot
+    expires: Instant,
+    /// Whether the slot is held for a player that disconnected
+    /// from the game so they can rejoin it
+    rejoin: bool,
+}
+
+/// Collection of the slot reservations for each game, reserved slots
+/// are counted as taken so that players matched at the same time
+/// can't both be given the last slot in a game
+#[derive(Default)]
+struct SlotReservations {
+    games: IntHashMap<GameID, Vec<SlotReservation>>,
+}
+
+impl SlotReservations {
+    /// Removes any reservations that have expired at `now`
+    fn remove_expired(&mut self, now: Instant) {
+        self.games.retain(|_, reservations| {
+            reservations.retain(|reservation| reservation.expires > now);
+            !reservations.is_empty()
+        });
+    }
+
+    /// Number of slots reserved in the game by players other than `user_id`
+    fn reserved(&self, game_id: GameID, user_id: Option<UserId>) -> usize {
+        self.games
+            .get(&game_id)
+            .map(|reservations| {
+                reservations
+                    .iter()
+                    .filter(|reservation| Some(reservation.user_id) != user_id)
+                    .count()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Reserves a slot in the game for `user_id` until `expires`, `rejoin`
+    /// marks slots held for players returning to the game
+    fn reserve(&mut self, game_id: GameID, user_id: UserId, expires: Instant, rejoin: bool) {
+        let reservations = self.games.entry(game_id).or_default();
+        reservations.retain(|reservation| reservation.user_id != user_id);
+        reservations.push(SlotReservation {
+            user_id,
+            expires,
+            rejoin,
+        });
+    }
+
+    /// Finds the game holding a rejoin slot for `user_id`
+    fn rejoin_game(&self, user_id: UserId) -> Option<GameID> {
+        self.games
+            .iter()
+            .find(|(_, reservations)| {
+                reservations
+                    .iter()
+                    .any(|reservation| reservation.rejoin && reservation.user_id == user_id)
+            })
+            .map(|(game_id, _)| *game_id)
+    }
+
+    /// Releases the slot reserved in the game for `user_id`
+    fn release(&mut self, game_id: GameID, user_id: UserId) {
+        if let Some(reservations) = self.games.get_mut(&game_id) {
+            reservations.retain(|reservation| reservation.user_id != user_id);
+            if reservations.is_empty() {
+                self.games.remove(&game_id);
+            }
+        }
+    }
+
+    /// Removes all the reservations for a game
+    fn remove_game(&mut self, game_id: GameID) {
+        self.games.remove(&game_id);
+    }
+}
+
+/// Player waiting in the matchmaking queue
+struct MatchmakingEntry {
+    /// The queued player
+    player: Player,
+    /// Game attributes the player is searching for
+    criteria: AttrMap,
+    /// Region and latency preferences of the player
+    preferences: Option<MatchmakingPreferences>,
+    /// When the player started matchmaking
+    started: Instant,
+}
+
+impl MatchmakingEntry {
+    /// Current criteria relaxation stage, each stage allows one more
+    /// of the criteria attributes to differ from the game. When the
+    /// player has preferences they are relaxed in a final stage
+    fn relaxation_stage(&self, now: Instant) -> usize {
+        let elapsed = now.duration_since(self.started);
+        let stage = (elapsed.as_secs() / GameManager::RELAXATION_INTERVAL.as_secs()) as usize;
+        stage.min(self.max_stage())
+    }
+
+    /// The final relaxation stage where everything that can be relaxed is
+    fn max_stage(&self) -> usize {
+        match self.preferences {
+            Some(_) => self.criteria.len() + 1,
+            None => self.criteria.len(),
+        }
+    }
+
+    /// Checks whether the indexed `game` meets the player preferences
+    /// at the relaxation `stage`, platform restrictions are never relaxed
+    fn accepts(&self, game: &IndexMatch, stage: usize) -> bool {
+        if !self.accepts_platform(game.host.platform) {
+            return false;
+        }
+
+        // Players likely unable to connect to the host are only matched
+        // once everything else has been relaxed
+        if stage < self.max_stage()
+            && !self
+                .player
+                .net
+                .qos
+                .nat_type
+                .is_compatible(game.host.nat_type)
+        {
+            return false;
+        }
+
+        let Some(preferences) = &self.preferences else {
+            return true;
+        };
+
+        // Preferences have been relaxed
+        if stage > self.criteria.len() {
+            return true;
+        }
+
+        let region = game.host.region.as_deref();
+
+        if let Some(preferred_region) = &preferences.preferred_region {
+            if region != Some(preferred_region.as_str()) {
+                return false;
+            }
+        }
+
+        // Games without a known host region are allowed as the
+        // latency to them can't be estimated
+        match (preferences.max_latency, region) {
+            (Some(max_latency), Some(region)) => {
+                match self.player.net.ping_site_latency.get(region) {
+                    Some(latency) => *latency <= max_latency,
+                    None => true,
+                }
+            }
+            _ => true,
+        }
+    }
+
+    /// Checks whether the player can join a game hosted on `platform`,
+    /// PC platforms can always play together
+    fn accepts_platform(&self, platform: Platform) -> bool {
+        let player_platform = self.player.user.platform;
+
+        Config::get().platforms.cross_platform_matching
+            || player_platform == platform
+            || (player_platform.is_pc() && platform.is_pc())
+    }
 }
 
 impl GameManager {
     /// Max number of times to poll a game for shutdown before erroring
     const MAX_RELEASE_ATTEMPTS: u8 = 5;
+    /// Interval between matchmaking status updates sent to queued players
+    const STATUS_INTERVAL: Duration = Duration::from_secs(5);
+    /// Time a player spends in each criteria relaxation stage
+    const RELAXATION_INTERVAL: Duration = Duration::from_secs(15);
+    /// Number of recent wait times used for estimating the wait
+    const MAX_RECENT_WAITS: usize = 10;
+    /// Time a slot is held for a matched player before it can be
+    /// given to another player
+    const RESERVATION_TIMEOUT: Duration = Duration::from_secs(30);
+    /// Game states that matchmaking will place players into (Initializing,
+    /// PreGame and InGame)
+    const JOINABLE_STATES: &[u8] = &[1, 130, 131];
 
     /// Starts a new game manager service returning its link
-    pub fn new() -> Self {
+    pub fn new(stats: GameStatsRecorder) -> Self {
         Self {
-            games: Default::default(),
+            shards: Default::default(),
+            index: Default::default(),
             next_id: AtomicU32::new(1),
+            queue: Default::default(),
+            recent_waits: Default::default(),
+            stats,
+            reservations: Default::default(),
+        }
+    }
+
+    /// Records a matchmaking or game statistic
+    pub(crate) fn record_stats(&self, event: GameStatsEvent) {
+        self.stats.record(event);
+    }
+
+    /// Starts the background task that matches queued players as their
+    /// criteria relax and sends them matchmaking status updates
+    pub fn start_matchmaking(self: &Arc<Self>) {
+        let game_manager = self.clone();
+        let context = CrashContext::new("matchmaking");
+        tokio::spawn(crash_reports::scope(context, async move {
+            let mut interval = interval(Self::STATUS_INTERVAL);
+            loop {
+                interval.tick().await;
+                game_manager.process_queue().await;
+            }
+        }));
+    }
+
+    /// Adds the `player` to the matchmaking queue searching for a game
+    /// with the provided `criteria`, the player is matched on the next
+    /// queue update
+    pub fn queue(
+        &self,
+        player: Player,
+        criteria: AttrMap,
+        preferences: Option<MatchmakingPreferences>,
+    ) {
+        let user_id = player.user.id;
+
+        // Preferences without any restrictions don't need relaxing
+        let preferences = preferences.filter(|preferences| {
+            preferences.max_latency.is_some() || preferences.preferred_region.is_some()
+        });
+
+        let entry = MatchmakingEntry {
+            player,
+            criteria,
+            preferences,
+            started: Instant::now(),
+        };
+
+        let queue = &mut *self.queue.lock();
+        // Replace any existing search from the same player
+        queue.retain(|entry| entry.player.user.id != user_id);
+        queue.push_back(entry);
+
+        debug!("Added player to matchmaking queue (PID: {})", user_id);
+    }
+
+    /// Matches queued players against the indexed games, players that
+    /// couldn't be matched are sent a status update
+    async fn process_queue(&self) {
+        let estimated_wait = self.estimated_wait();
+        let now = Instant::now();
+
+        let mut matched = Vec::new();
+        {
+            let queue = &mut *self.queue.lock();
+            let index = &*self.index.lock();
+            let reservations = &mut *self.reservations.lock();
+
+            // Slots of players that never finished joining are freed up
+            reservations.remove_expired(now);
+
+            // Remove players whose sessions have disconnected
+            queue.retain(|entry| entry.player.link.strong_count() > 0);
+
+            let mut position = 0;
+            while let Some(entry) = queue.get(position) {
+                let stage = entry.relaxation_stage(now);
+
+                // Players returning after a disconnect are placed back into their
+                // game before being matched using their criteria
+                let rejoin_game = reservations
+                    .rejoin_game(entry.player.user.id)
+                    .filter(|game_id| index.in_state(*game_id, Self::JOINABLE_STATES));
+
+                if let Some(game_id) = rejoin_game {
+                    debug!(
+                        "Rejoining player to previous game (PID: {}, GID: {})",
+                        entry.player.user.id, game_id
+                    );
+                    reservations.reserve(
+                        game_id,
+                        entry.player.user.id,
+                        now + Self::RESERVATION_TIMEOUT,
+                        false,
+                    );
+                    if let Some(entry) = queue.remove(position) {
+                        matched.push((entry, game_id, stage));
+                    }
+                    continue;
+                }
+
+                let max_mismatched = stage.min(entry.criteria.len());
+                let games: Vec<IndexMatch> = index
+                    .matching(&entry.criteria, max_mismatched, Self::JOINABLE_STATES)
+                    .into_iter()
+                    .filter(|game| !game.banned.contains(&entry.player.user.id))
+                    .filter(|game| entry.accepts(game, stage))
+                    .collect();
+
+                let game = games.iter().find(|game| {
+                    game.players + reservations.reserved(game.game_id, None) < Game::MAX_PLAYERS
+                });
+
+                if let Some(game) = game {
+                    reservations.reserve(
+                        game.game_id,
+                        entry.player.user.id,
+                        now + Self::RESERVATION_TIMEOUT,
+                        false,
+                    );
+                    if let Some(entry) = queue.remove(position) {
+                        matched.push((entry, game.game_id, stage));
+                    }
+                    continue;
+                }
+
+                let players_found = games.iter().map(|game| game.players).sum::<usize>();
+
+                entry.player.notify(Packet::notify(
+                    game_manager::COMPONENT,
+                    game_manager::MATCHMAKING_ASYNC_STATUS,
+                    NotifyMatchmakingStatus {
+                        pid: entry.player.user.id,
+                        players_found: players_found as u32,
+                        relaxation_stage: stage as u32,
+                        estimated_wait: estimated_wait
+                            .saturating_sub(now.duration_since(entry.started))
+                            .as_secs() as u32,
+                    },
+                ));
+
+                position += 1;
+            }
+        }
+
+        for (entry, game_id, stage) in matched {
+            match self.get_game(game_id).await {
+                Some(game_ref) => self.join_matched(entry, game_ref, stage).await,
+                // Game was removed before the player could join, try again
+                // on the next update
+                None => self.queue.lock().push_front(entry),
+            }
         }
     }
 
-    pub async fn create(self: &Arc<Self>, attributes: AttrMap) -> (GameRef, GameID) {
-        let games = &mut *self.games.write().await;
+    /// Adds a matched queued player to the game they were matched with
+    /// at the criteria relaxation `stage`
+    async fn join_matched(&self, entry: MatchmakingEntry, game_ref: GameRef, stage: usize) {
+        let Some(session) = entry.player.link.upgrade() else {
+            return;
+        };
+
+        let wait = entry.started.elapsed();
+        let user_id = entry.player.user.id;
+        let result = self
+            .add_to_game(
+                game_ref.clone(),
+                entry.player,
+                session,
+                GameSetupContext::Matchmaking {
+                    fit_score: DEFAULT_FIT,
+                    fit_score_2: 0,
+                    max_fit_score: DEFAULT_FIT,
+                    id_1: user_id,
+                    id_2: user_id,
+                    result: MatchmakingResult::JoinedExistingGame,
+                    tout: 15000000,
+                    ttm: wait.as_millis() as u32,
+                    id_3: user_id,
+                },
+            )
+            .await;
+
+        // Game filled up before the player could join, try again
+        // on the next update
+        if let Err(player) = result {
+            debug!("Matched game was full, requeuing player (PID: {})", user_id);
+            self.queue
+                .lock()
+                .push_front(MatchmakingEntry { player, ..entry });
+            return;
+        }
+
+        {
+            let recent_waits = &mut *self.recent_waits.lock();
+            if recent_waits.len() >= Self::MAX_RECENT_WAITS {
+                recent_waits.pop_front();
+            }
+            recent_waits.push_back(wait);
+        }
+
+        let game_size = game_ref.read().await.players.len();
+        self.record_stats(GameStatsEvent::Matched {
+            queue_time: wait,
+            relaxation_stage: stage,
+            game_size,
+        });
+    }
 
+    /// Estimates the time a player will wait in the queue using the
+    /// average of the recent wait times
+    fn estimated_wait(&self) -> Duration {
+        let recent_waits = &*self.recent_waits.lock();
+        if recent_waits.is_empty() {
+            return Duration::ZERO;
+        }
+        recent_waits.iter().sum::<Duration>() / recent_waits.len() as u32
+    }
+
+    /// Gets the shard that the game with the provided ID is stored in
+    fn shard(&self, game_id: GameID) -> &RwLock<IntHashMap<GameID, GameRef>> {
+        &self.shards[game_id as usize % SHARD_COUNT]
+    }
+
+    pub async fn create(self: &Arc<Self>, mut attributes: AttrMap) -> (GameRef, GameID) {
         let id = self.next_id.fetch_add(1, Ordering::AcqRel);
 
-        let game = Arc::new(RwLock::new(Game::new(id, attributes, self.clone())));
-        games.insert(id, game.clone());
+        // Apply the seasonal event mutators active when the game is created
+        let active_mutators = mutators::active(clock::now());
+        if !active_mutators.is_empty() {
+            attributes.insert_presorted(mutators::attributes(&active_mutators).into_inner());
+        }
+
+        let mut game = Game::new(id, attributes, self.clone());
+        game.mutators = active_mutators
+            .iter()
+            .map(|mutator| mutator.name.clone())
+            .collect();
+        self.update_index(&game);
+
+        let game = Arc::new(RwLock::new(game));
+        self.shard(id).write().await.insert(id, game.clone());
+
+        ServerEvents::get().publish(ServerEvent::GameCreated { game_id: id });
 
         (game, id)
     }
 
+    /// Updates the matchmaking index with the current details of the
+    /// provided game, called by games whenever they change
+    pub(crate) fn update_index(&self, game: &Game) {
+        let host = game
+            .host_player()
+            .map(|host| IndexedHost {
+                // The game region is the ping site closest to the host
+                region: host.net.best_ping_site().map(str::to_string),
+                platform: host.user.platform,
+                nat_type: host.net.qos.nat_type,
+            })
+            .unwrap_or_default();
+
+        self.index.lock().update(
+            game.id,
+            game.state,
+            &game.attributes,
+            game.players.len(),
+            host,
+            &game.banned,
+        );
+    }
+
+    /// Removes a game from the matchmaking index so that no more
+    /// players are matched into it
+    pub(crate) fn remove_from_index(&self, game_id: GameID) {
+        self.index.lock().remove(game_id);
+    }
+
+    /// Holds the slot of the player with the provided `user_id` that
+    /// disconnected from the game so that they have priority to rejoin
+    /// the game through matchmaking before the slot opens to others
+    pub(crate) fn reserve_rejoin(&self, game_id: GameID, user_id: UserId) {
+        let config = &Config::get().rejoin;
+        if !config.enabled {
+            return;
+        }
+
+        let expires = Instant::now() + Duration::from_secs(config.window_secs);
+        self.reservations
+            .lock()
+            .reserve(game_id, user_id, expires, true);
+
+        debug!(
+            "Holding slot for disconnected player (PID: {}, GID: {})",
+            user_id, game_id
+        );
+    }
+
+    /// Lists the games with all of the `filters` attributes for the server
+    /// browser, when `joinable` is provided only games with the matching
+    /// joinability are included, slots reserved by matchmaking aren't
+    /// joinable. Returns the total number of matching games along with
+    /// the page of games starting at `offset`
+    pub fn browse(
+        &self,
+        filters: &[(&str, &str)],
+        joinable: Option<bool>,
+        offset: usize,
+        count: usize,
+    ) -> (usize, Vec<BrowsedGame>) {
+        let index = &*self.index.lock();
+        let reservations = &mut *self.reservations.lock();
+        reservations.remove_expired(Instant::now());
+
+        let games: Vec<BrowsedGame> = index
+            .browse(filters)
+            .into_iter()
+            .map(|game| BrowsedGame {
+                id: game.game_id,
+                state: game.state,
+                attributes: game
+                    .attributes
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect(),
+                players: game.players,
+                max_players: Game::MAX_PLAYERS,
+                joinable: Self::JOINABLE_STATES.contains(&game.state)
+                    && game.players + reservations.reserved(game.game_id, None) < Game::MAX_PLAYERS,
+            })
+            .filter(|game| match joinable {
+                Some(joinable) => game.joinable == joinable,
+                None => true,
+            })
+            .collect();
+
+        let total = games.len();
+        let page = games.into_iter().skip(offset).take(count).collect();
+
+        (total, page)
+    }
+
+    /// Adds the `player` to the game, slots reserved for other players
+    /// can't be taken. When the game has no free slots the player is
+    /// returned as the error
     pub async fn add_to_game(
         &self,
         game_ref: GameRef,
         player: Player,
         session: SessionLink,
         context: GameSetupContext,
-    ) {
+    ) -> Result<(), Player> {
         let (game_id, _slot) = {
             let game = &mut *game_ref.write().await;
+            let user_id = player.user.id;
+
+            {
+                let reservations = &mut *self.reservations.lock();
+                let reserved = reservations.reserved(game.id, Some(user_id));
+                reservations.release(game.id, user_id);
+
+                if game.players.len() + reserved >= Game::MAX_PLAYERS {
+                    return Err(player);
+                }
+            }
+
             let slot = game.add_player(player, context);
             (game.id, slot)
         };
 
-        // TODO: Tunneling association
+        // TODO: Tunneling association, once players are associated with a
+        // tunnel host migration must also rebind the tunnels to the new host
 
         session.set_game(game_id, Arc::downgrade(&game_ref));
+        Ok(())
+    }
+
+    /// Approximate memory held by the games and matchmaking state
+    pub async fn memory_usage(&self) -> GameMemoryUsage {
+        let mut usage = GameMemoryUsage {
+            queued: self.queue.lock().len(),
+            reservations: self.reservations.lock().games.values().map(Vec::len).sum(),
+            ..Default::default()
+        };
+
+        for shard in &self.shards {
+            let games: Vec<GameRef> = shard.read().await.values().cloned().collect();
+            for game in games {
+                let game = &*game.read().await;
+                usage.games += 1;
+                usage.players += game.players.len();
+                if game.mission_data.is_some() || game.processed_data.is_some() {
+                    usage.games_with_results += 1;
+                }
+            }
+        }
+
+        usage.approximate_bytes = usage.games * size_of::<Game>()
+            + usage.players * size_of::<Player>()
+            + usage.queued * size_of::<MatchmakingEntry>()
+            + usage.reservations * size_of::<SlotReservation>();
+        usage
+    }
+
+    /// Counts the games that have a mission in progress
+    pub async fn count_in_mission(&self) -> usize {
+        let mut count = 0;
+        for shard in &self.shards {
+            let games: Vec<GameRef> = shard.read().await.values().cloned().collect();
+            for game in games {
+                if game.read().await.mission_reservation.is_some() {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    /// Takes the missions that are still being played or have results that
+    /// the result worker hasn't started processing, the taken results
+    /// won't be processed by the worker
+    pub async fn take_in_flight_missions(&self) -> Vec<InFlightMission> {
+        let mut missions = Vec::new();
+        for shard in &self.shards {
+            let games: Vec<GameRef> = shard.read().await.values().cloned().collect();
+            for game in games {
+                let game = &mut *game.write().await;
+
+                let mission_data = if game.processed_data.is_none()
+                    && game.mission_claimed != Some(game.mission_revision)
+                {
+                    game.mission_data.take()
+                } else {
+                    None
+                };
+
+                let reservation = game.mission_reservation.take();
+                if reservation.is_none() && mission_data.is_none() {
+                    continue;
+                }
+
+                missions.push(InFlightMission {
+                    game_id: game.id,
+                    reservation,
+                    mission_data,
+                });
+            }
+        }
+        missions
     }
 
     pub async fn get_game(&self, game_id: GameID) -> Option<GameRef> {
-        let games = &*self.games.read().await;
+        let games = &*self.shard(game_id).read().await;
         games.get(&game_id).cloned()
     }
 
     pub async fn remove_game(&self, game_id: GameID) {
-        let games = &mut *self.games.write().await;
-        if let Some(mut game) = games.remove(&game_id) {
+        self.remove_from_index(game_id);
+        self.reservations.lock().remove_game(game_id);
+
+        // Release the shard lock before waiting on the game references
+        let game = self.shard(game_id).write().await.remove(&game_id);
+        if let Some(mut game) = game {
             let mut attempt: u8 = 1;
 
             // Attempt to obtain the owned game
@@ -107,3 +799,51 @@ impl GameManager {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::SlotReservations;
+    use std::time::{Duration, Instant};
+
+    /// Tests that reserved slots are counted until released or expired
+    #[test]
+    fn test_slot_reservations() {
+        let now = Instant::now();
+        let mut reservations = SlotReservations::default();
+
+        reservations.reserve(1, 10, now + Duration::from_secs(30), false);
+        reservations.reserve(1, 11, now + Duration::from_secs(5), false);
+        // Reserving again replaces the existing reservation
+        reservations.reserve(1, 10, now + Duration::from_secs(30), false);
+
+        assert_eq!(reservations.reserved(1, None), 2);
+        assert_eq!(reservations.reserved(1, Some(10)), 1);
+        assert_eq!(reservations.reserved(2, None), 0);
+
+        reservations.remove_expired(now + Duration::from_secs(10));
+        assert_eq!(reservations.reserved(1, None), 1);
+
+        reservations.release(1, 10);
+        assert_eq!(reservations.reserved(1, None), 0);
+    }
+
+    /// Tests that rejoin slots are found for the disconnected player and
+    /// are replaced once the player is matched back into the game
+    #[test]
+    fn test_rejoin_reservations() {
+        let now = Instant::now();
+        let mut reservations = SlotReservations::default();
+
+        reservations.reserve(1, 10, now + Duration::from_secs(30), false);
+        reservations.reserve(2, 11, now + Duration::from_secs(60), true);
+
+        assert_eq!(reservations.rejoin_game(10), None);
+        assert_eq!(reservations.rejoin_game(11), Some(2));
+        // Rejoin slots are held from other players
+        assert_eq!(reservations.reserved(2, Some(10)), 1);
+
+        reservations.reserve(2, 11, now + Duration::from_secs(30), false);
+        assert_eq!(reservations.rejoin_game(11), None);
+        assert_eq!(reservations.reserved(2, None), 1);
+    }
+}
@@ -0,0 +1,283 @@
+//! Statistics about matchmaking attempts and the lifecycle of games used by
+//! administrators for balancing. Statistics are written to the database by a
+//! background task so that recording never holds up matchmaking or games
+
+use super::crash_reports::{self, CrashContext};
+use crate::database::entity::{GameRecord, MatchmakingRecord};
+use log::error;
+use sea_orm::DatabaseConnection;
+use serde::Serialize;
+use std::{collections::BTreeMap, time::Duration};
+use tokio::sync::mpsc;
+
+/// Statistic recorded by the [GameStatsRecorder]
+pub enum GameStatsEvent {
+    /// Queued player was matched into a game
+    Matched {
+        /// Time the player spent in the queue
+        queue_time: Duration,
+        /// Criteria relaxation stage the player was matched at
+        relaxation_stage: usize,
+        /// Number of players in the game after the player joined
+        game_size: usize,
+    },
+    /// Game was stopped
+    GameStopped {
+        /// Difficulty of the last mission played if one was finished
+        difficulty: Option<String>,
+        /// Time the game existed for
+        duration: Duration,
+        /// Whether the last mission played was fully completed
+        completed: bool,
+        /// Most players that were in the game at once
+        peak_players: usize,
+    },
+}
+
+/// Handle for recording statistics in the background
+pub struct GameStatsRecorder {
+    /// Sender for the statistics to record
+    tx: mpsc::UnboundedSender<GameStatsEvent>,
+}
+
+impl GameStatsRecorder {
+    /// Starts the background task that stores the recorded statistics
+    pub fn start(db: DatabaseConnection) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let context = CrashContext::new("game stats");
+        tokio::spawn(crash_reports::scope(context, Self::run(db, rx)));
+        Self { tx }
+    }
+
+    /// Queues the `event` to be stored
+    pub fn record(&self, event: GameStatsEvent) {
+        _ = self.tx.send(event);
+    }
+
+    async fn run(db: DatabaseConnection, mut rx: mpsc::UnboundedReceiver<GameStatsEvent>) {
+        while let Some(event) = rx.recv().await {
+            let result = match event {
+                GameStatsEvent::Matched {
+                    queue_time,
+                    relaxation_stage,
+                    game_size,
+                } => MatchmakingRecord::create(
+                    &db,
+                    queue_time.as_millis().min(u32::MAX as u128) as u32,
+                    relaxation_stage as u32,
+                    game_size as u32,
+                )
+                .await
+                .map(|_| ()),
+                GameStatsEvent::GameStopped {
+                    difficulty,
+                    duration,
+                    completed,
+                    peak_players,
+                } => GameRecord::create(
+                    &db,
+                    difficulty,
+                    duration.as_secs().min(u32::MAX as u64) as u32,
+                    completed,
+                    peak_players as u32,
+                )
+                .await
+                .map(|_| ()),
+            };
+
+            if let Err(err) = result {
+                error!("Failed to record game statistics: {}", err);
+            }
+        }
+    }
+}
+
+/// Summary of the recorded matchmaking attempts
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchmakingSummary {
+    /// Number of players matched into games
+    pub matched: usize,
+    /// Average milliseconds players spent in the queue
+    pub average_queue_time_ms: u64,
+    /// Longest milliseconds a player spent in the queue
+    pub max_queue_time_ms: u32,
+    /// Average number of players in the game after a player joined
+    pub average_game_size: f32,
+    /// Number of players matched at each relaxation stage
+    pub by_relaxation_stage: BTreeMap<u32, usize>,
+}
+
+impl MatchmakingSummary {
+    /// Summarizes the provided matchmaking `records`
+    pub fn from_records(records: &[MatchmakingRecord]) -> Self {
+        if records.is_empty() {
+            return Self::default();
+        }
+
+        let matched = records.len();
+        let total_queue_time: u64 = records
+            .iter()
+            .map(|record| record.queue_time_ms as u64)
+            .sum();
+        let total_game_size: u64 = records.iter().map(|record| record.game_size as u64).sum();
+
+        let mut by_relaxation_stage = BTreeMap::new();
+        for record in records {
+            *by_relaxation_stage
+                .entry(record.relaxation_stage)
+                .or_default() += 1;
+        }
+
+        Self {
+            matched,
+            average_queue_time_ms: total_queue_time / matched as u64,
+            max_queue_time_ms: records
+                .iter()
+                .map(|record| record.queue_time_ms)
+                .max()
+                .unwrap_or_default(),
+            average_game_size: total_game_size as f32 / matched as f32,
+            by_relaxation_stage,
+        }
+    }
+}
+
+/// Summary of the recorded games
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GameSummary {
+    /// Number of games that stopped
+    pub games: usize,
+    /// Average seconds games existed for
+    pub average_duration_secs: u64,
+    /// Number of games that stopped without finishing a mission
+    pub without_mission: usize,
+    /// Summary of the games grouped by mission difficulty
+    pub by_difficulty: BTreeMap<String, DifficultySummary>,
+}
+
+/// Summary of the games played on a single difficulty
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DifficultySummary {
+    /// Number of games played on the difficulty
+    pub games: usize,
+    /// Number of the games where the mission was fully completed
+    pub completed: usize,
+    /// Fraction (0.0 - 1.0) of the games where the mission was completed
+    pub completion_rate: f32,
+    /// Average seconds the games existed for
+    pub average_duration_secs: u64,
+}
+
+impl GameSummary {
+    /// Summarizes the provided game `records`
+    pub fn from_records(records: &[GameRecord]) -> Self {
+        if records.is_empty() {
+            return Self::default();
+        }
+
+        let games = records.len();
+        let total_duration: u64 = records
+            .iter()
+            .map(|record| record.duration_secs as u64)
+            .sum();
+
+        let mut without_mission = 0;
+        let mut durations: BTreeMap<String, u64> = BTreeMap::new();
+        let mut by_difficulty: BTreeMap<String, DifficultySummary> = BTreeMap::new();
+
+        for record in records {
+            let Some(difficulty) = &record.difficulty else {
+                without_mission += 1;
+                continue;
+            };
+
+            let summary = by_difficulty.entry(difficulty.clone()).or_default();
+            summary.games += 1;
+            if record.completed {
+                summary.completed += 1;
+            }
+
+            *durations.entry(difficulty.clone()).or_default() += record.duration_secs as u64;
+        }
+
+        for (difficulty, summary) in by_difficulty.iter_mut() {
+            let duration = durations.get(difficulty).copied().unwrap_or_default();
+            summary.completion_rate = summary.completed as f32 / summary.games as f32;
+            summary.average_duration_secs = duration / summary.games as u64;
+        }
+
+        Self {
+            games,
+            average_duration_secs: total_duration / games as u64,
+            without_mission,
+            by_difficulty,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{GameSummary, MatchmakingSummary};
+    use crate::database::entity::{GameRecord, MatchmakingRecord};
+    use chrono::Utc;
+
+    /// Tests that matchmaking records are averaged and grouped by stage
+    #[test]
+    fn test_matchmaking_summary() {
+        let record = |queue_time_ms, relaxation_stage, game_size| MatchmakingRecord {
+            id: 0,
+            queue_time_ms,
+            relaxation_stage,
+            game_size,
+            created: Utc::now(),
+        };
+
+        let summary = MatchmakingSummary::from_records(&[
+            record(1000, 0, 2),
+            record(3000, 0, 3),
+            record(8000, 2, 4),
+        ]);
+
+        assert_eq!(summary.matched, 3);
+        assert_eq!(summary.average_queue_time_ms, 4000);
+        assert_eq!(summary.max_queue_time_ms, 8000);
+        assert_eq!(summary.average_game_size, 3.0);
+        assert_eq!(summary.by_relaxation_stage.get(&0), Some(&2));
+        assert_eq!(summary.by_relaxation_stage.get(&2), Some(&1));
+    }
+
+    /// Tests that game completion rates are computed per difficulty
+    #[test]
+    fn test_game_summary() {
+        let record = |difficulty: Option<&str>, duration_secs, completed| GameRecord {
+            id: 0,
+            difficulty: difficulty.map(str::to_string),
+            duration_secs,
+            completed,
+            peak_players: 4,
+            created: Utc::now(),
+        };
+
+        let summary = GameSummary::from_records(&[
+            record(Some("gold"), 1200, true),
+            record(Some("gold"), 600, false),
+            record(Some("bronze"), 900, true),
+            record(None, 60, false),
+        ]);
+
+        assert_eq!(summary.games, 4);
+        assert_eq!(summary.without_mission, 1);
+
+        let gold = &summary.by_difficulty["gold"];
+        assert_eq!(gold.games, 2);
+        assert_eq!(gold.completed, 1);
+        assert_eq!(gold.completion_rate, 0.5);
+        assert_eq!(gold.average_duration_secs, 900);
+
+        let bronze = &summary.by_difficulty["bronze"];
+        assert_eq!(bronze.completion_rate, 1.0);
+    }
+}
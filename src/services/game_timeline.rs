@@ -0,0 +1,124 @@
+//! Timeline of events that occur within a game, published to spectators
+//! (community casters, companion apps) that are watching an in-progress game
+
+use super::{activity::ActivityEvent, game::GameID};
+use crate::{
+    database::entity::users::{Platform, UserId},
+    http::models::mission::MissionModifier,
+    utils::clock,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::sync::broadcast;
+
+/// Number of timeline entries buffered for slow spectators before
+/// they start missing entries
+const TIMELINE_CAPACITY: usize = 64;
+
+/// Whether the host has allowed their game timeline to be spectated
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimelineVisibility {
+    /// Only the players in the game can see the timeline
+    #[default]
+    Private,
+    /// Any authenticated user can spectate the timeline
+    Public,
+}
+
+/// Player details exposed on the timeline
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelinePlayer {
+    pub user_id: UserId,
+    pub username: String,
+    pub platform: Platform,
+}
+
+/// Events that can occur within a game
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum GameTimelineEvent {
+    /// Player joined the game
+    PlayerJoined { player: TimelinePlayer },
+    /// Player was removed from the game
+    PlayerRemoved { player: TimelinePlayer },
+    /// The host left and the player became the new host
+    HostMigrated { host: TimelinePlayer },
+    /// The game state changed
+    StateChanged { state: u8 },
+    /// The game attributes were updated
+    AttributesChanged { attributes: HashMap<String, String> },
+    /// The mission was started with the provided modifiers
+    MissionStarted { modifiers: Vec<MissionModifier> },
+    /// A player submitted activities during the game
+    ActivitySubmitted {
+        player: TimelinePlayer,
+        activities: Vec<ActivityEvent>,
+    },
+    /// The mission was finished
+    MissionFinished {
+        #[serde(rename = "percentComplete")]
+        percent_complete: u8,
+        #[serde(rename = "extractionState")]
+        extraction_state: String,
+    },
+}
+
+/// Timeline event along with the time it occurred
+#[derive(Debug, Clone, Serialize)]
+pub struct TimelineEntry {
+    pub time: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: GameTimelineEvent,
+}
+
+/// Snapshot of the game sent to spectators when they start watching
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineSnapshot {
+    pub game_id: GameID,
+    pub state: u8,
+    pub attributes: HashMap<String, String>,
+    pub players: Vec<TimelinePlayer>,
+    pub modifiers: Vec<MissionModifier>,
+}
+
+/// Publisher for the timeline of a single game
+pub struct GameTimeline {
+    /// Who can spectate the timeline
+    pub visibility: TimelineVisibility,
+    /// Sender for publishing entries to spectators
+    tx: broadcast::Sender<TimelineEntry>,
+}
+
+impl Default for GameTimeline {
+    fn default() -> Self {
+        let (tx, _) = broadcast::channel(TIMELINE_CAPACITY);
+        Self {
+            visibility: TimelineVisibility::default(),
+            tx,
+        }
+    }
+}
+
+impl GameTimeline {
+    /// Publishes the `event` to any current spectators
+    pub fn push(&self, event: GameTimelineEvent) {
+        // Nobody watching the timeline
+        if self.tx.receiver_count() == 0 {
+            return;
+        }
+
+        _ = self.tx.send(TimelineEntry {
+            time: clock::now(),
+            event,
+        });
+    }
+
+    /// Subscribes to future timeline entries, the receiver is closed
+    /// once the game is stopped
+    pub fn subscribe(&self) -> broadcast::Receiver<TimelineEntry> {
+        self.tx.subscribe()
+    }
+}
@@ -0,0 +1,86 @@
+//! Restrictions for accounts sharing a household, used to curb self-boosting
+//! on competitive servers. Accounts are linked to the same household when
+//! they have recently logged in from the same IP address.
+//!
+//! Restrictions are only enforced when enabled in the server configuration
+
+use super::sessions::Sessions;
+use crate::{
+    config::Config,
+    database::{
+        entity::{
+            currency::CurrencyType, currency_ledger::CurrencyLedgerReason, users::UserId,
+            CurrencyLedgerEntry, LoginEvent, User,
+        },
+        DbResult,
+    },
+    utils::clock,
+};
+use chrono::{Duration, TimeZone, Utc};
+use log::debug;
+use sea_orm::ConnectionTrait;
+use std::{collections::HashMap, net::IpAddr};
+
+/// Checks whether connecting another session for `user_id` from the
+/// `ip` address would exceed the concurrent household session limit
+pub fn exceeds_session_limit(sessions: &Sessions, ip: IpAddr, user_id: UserId) -> bool {
+    let config = &Config::get().household;
+    if !config.enabled {
+        return false;
+    }
+
+    sessions.count_by_ip(ip, user_id) >= config.max_concurrent_sessions
+}
+
+/// Limits the `earned` mission currencies for the `user` to the remaining
+/// daily earn limits shared across their household
+pub async fn limit_mission_earnings<C>(
+    db: &C,
+    user: &User,
+    earned: &mut HashMap<CurrencyType, u32>,
+) -> DbResult<()>
+where
+    C: ConnectionTrait + Send,
+{
+    let config = &Config::get().household;
+    if !config.enabled || config.daily_earn_limits.is_empty() {
+        return Ok(());
+    }
+
+    let now = clock::now();
+    let linked_since = now - Duration::days(config.link_days as i64);
+    let user_ids = LoginEvent::linked_user_ids(db, user, linked_since).await?;
+
+    // Earnings are tracked since the start of the current UTC day
+    let start_of_day = Utc.from_utc_datetime(&now.date_naive().and_hms_opt(0, 0, 0).unwrap());
+
+    for limit in &config.daily_earn_limits {
+        let amount = match earned.get_mut(&limit.currency) {
+            Some(value) => value,
+            None => continue,
+        };
+
+        let earned_today = CurrencyLedgerEntry::sum_since_users(
+            db,
+            user_ids.clone(),
+            limit.currency,
+            CurrencyLedgerReason::MissionReward,
+            start_of_day,
+        )
+        .await?
+        .max(0) as u64;
+
+        let remaining = (limit.limit as u64).saturating_sub(earned_today) as u32;
+        if *amount > remaining {
+            debug!(
+                "Household earn limit reached for {} (User: {}, Household: {})",
+                limit.currency,
+                user.id,
+                user_ids.len()
+            );
+            *amount = remaining;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,537 @@
+//! Bulk export and import of user inventories and currencies for migrating
+//! community servers. Exports list the items and currency balances of users
+//! as rows, imports either set the balances of users to the amounts in the
+//! rows (used to import exports) or apply the rows as adjustments. Rows are
+//! keyed by the email of the user as IDs differ between servers.
+//!
+//! Imports run as a background job, each row is validated and applied on
+//! its own so a bad row is reported without stopping the rest of the import
+
+use crate::{
+    database::entity::{
+        currency::CurrencyType, currency_ledger::CurrencyLedgerReason, Currency,
+        CurrencyLedgerEntry, InventoryItem, User,
+    },
+    definitions::items::{ItemName, Items},
+    services::{
+        crash_reports::{self, CrashContext},
+        items,
+    },
+};
+use log::info;
+use parking_lot::Mutex;
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbErr, TransactionTrait};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+};
+use thiserror::Error;
+
+/// Number of users loaded at once when exporting every user
+const EXPORT_PAGE_SIZE: u64 = 100;
+
+/// Maximum number of rows that can be imported at once
+const MAX_IMPORT_ROWS: usize = 100_000;
+
+/// Maximum number of finished import jobs kept for reporting
+const MAX_FINISHED_JOBS: usize = 20;
+
+/// File formats rows can be exported and imported as
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TransferFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+/// How the amounts of imported rows are applied
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportMode {
+    /// Amounts are added to the balances, negative amounts remove
+    /// items and currency
+    #[default]
+    Adjust,
+    /// Balances are set to the amounts, used when importing exports
+    Set,
+}
+
+impl ImportMode {
+    /// Computes the change to the `current` balance for the row `amount`
+    fn change(self, current: u32, amount: i64) -> Result<i64, RowFailure> {
+        match self {
+            ImportMode::Adjust if amount == 0 => Err(RowFailure::ZeroAmount),
+            ImportMode::Adjust => Ok(amount),
+            ImportMode::Set if amount < 0 => Err(RowFailure::NegativeAmount),
+            ImportMode::Set => Ok(amount - current as i64),
+        }
+    }
+}
+
+/// Kind of balance a row is for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum InventoryRowKind {
+    /// Stack size of an inventory item
+    Item,
+    /// Balance of a currency
+    Currency,
+}
+
+/// Row of an inventory export or import. Exported rows contain the current
+/// balances while imported rows are applied based on the [ImportMode]
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InventoryRow {
+    /// Email of the user the row is for
+    pub email: String,
+    /// Kind of balance the row is for
+    pub kind: InventoryRowKind,
+    /// Item definition name or currency name
+    pub name: String,
+    /// The amount of the item or currency
+    pub amount: i64,
+}
+
+/// Errors that prevent an import file from being imported at all
+#[derive(Debug, Error)]
+pub enum ImportError {
+    /// The file couldn't be read as the requested format
+    #[error("Invalid import file: {0}")]
+    InvalidFile(String),
+    /// The file contains more than [MAX_IMPORT_ROWS] rows
+    #[error("Import file contains too many rows")]
+    TooManyRows,
+}
+
+/// Errors for individual rows that couldn't be applied
+#[derive(Debug, Error)]
+enum RowFailure {
+    #[error("Unknown user {0}")]
+    UnknownUser(String),
+    #[error("Unknown item {0}")]
+    UnknownItem(String),
+    #[error("Unknown currency {0}")]
+    UnknownCurrency(String),
+    #[error("Amount must not be zero")]
+    ZeroAmount,
+    #[error("Amount must not be negative")]
+    NegativeAmount,
+    #[error("Amount is too large")]
+    AmountTooLarge,
+    #[error("User doesn't have enough of the item")]
+    InsufficientItems,
+    #[error("Item capacity reached")]
+    ItemCapacityReached,
+    #[error("User doesn't have enough currency")]
+    InsufficientCurrency,
+    #[error("Currency capacity reached")]
+    CurrencyCapacityReached,
+    #[error(transparent)]
+    Database(#[from] DbErr),
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+/// Collects the items and currencies of the `user` as rows
+pub async fn export_user<C>(db: &C, user: &User) -> Result<Vec<InventoryRow>, DbErr>
+where
+    C: ConnectionTrait + Send,
+{
+    let mut rows = Vec::new();
+    append_user_rows(db, user, &mut rows).await?;
+    Ok(rows)
+}
+
+/// Collects the items and currencies of every user as rows
+pub async fn export_all<C>(db: &C) -> Result<Vec<InventoryRow>, DbErr>
+where
+    C: ConnectionTrait + Send,
+{
+    let mut rows = Vec::new();
+    let mut offset = 0;
+
+    loop {
+        let users = User::page(db, offset, EXPORT_PAGE_SIZE).await?;
+        if users.is_empty() {
+            break;
+        }
+
+        offset += users.len() as u64;
+
+        for user in &users {
+            append_user_rows(db, user, &mut rows).await?;
+        }
+    }
+
+    Ok(rows)
+}
+
+/// Appends the rows for the items and currencies of the `user` to `rows`
+async fn append_user_rows<C>(db: &C, user: &User, rows: &mut Vec<InventoryRow>) -> Result<(), DbErr>
+where
+    C: ConnectionTrait + Send,
+{
+    let items = InventoryItem::get_all_items(db, user).await?;
+    let currencies = Currency::all_types(db, user).await?;
+
+    rows.extend(items.into_iter().map(|item| InventoryRow {
+        email: user.email.clone(),
+        kind: InventoryRowKind::Item,
+        name: item.definition_name.to_string(),
+        amount: item.stack_size as i64,
+    }));
+
+    rows.extend(currencies.into_iter().map(|currency| InventoryRow {
+        email: user.email.clone(),
+        kind: InventoryRowKind::Currency,
+        name: currency.ty.to_string(),
+        amount: currency.balance as i64,
+    }));
+
+    Ok(())
+}
+
+/// Encodes the `rows` as the provided `format`
+pub fn encode(rows: &[InventoryRow], format: TransferFormat) -> anyhow::Result<Vec<u8>> {
+    match format {
+        TransferFormat::Json => Ok(serde_json::to_vec(rows)?),
+        TransferFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            for row in rows {
+                writer.serialize(row)?;
+            }
+            Ok(writer.into_inner().map_err(|err| err.into_error())?)
+        }
+    }
+}
+
+/// Decodes the rows from the `data` of an import file, rows that can't be
+/// decoded are kept as errors so they can be reported with their row number
+pub fn decode(
+    data: &[u8],
+    format: TransferFormat,
+) -> Result<Vec<Result<InventoryRow, String>>, ImportError> {
+    let rows: Vec<Result<InventoryRow, String>> = match format {
+        TransferFormat::Json => {
+            let values: Vec<serde_json::Value> = serde_json::from_slice(data)
+                .map_err(|err| ImportError::InvalidFile(err.to_string()))?;
+
+            if values.len() > MAX_IMPORT_ROWS {
+                return Err(ImportError::TooManyRows);
+            }
+
+            values
+                .into_iter()
+                .map(|value| serde_json::from_value(value).map_err(|err| err.to_string()))
+                .collect()
+        }
+        TransferFormat::Csv => csv::Reader::from_reader(data)
+            .into_deserialize()
+            .take(MAX_IMPORT_ROWS + 1)
+            .map(|row| row.map_err(|err| err.to_string()))
+            .collect(),
+    };
+
+    if rows.len() > MAX_IMPORT_ROWS {
+        return Err(ImportError::TooManyRows);
+    }
+
+    Ok(rows)
+}
+
+/// Converts the size of a balance `change` into an amount
+fn change_amount(change: i64) -> Result<u32, RowFailure> {
+    change
+        .unsigned_abs()
+        .try_into()
+        .map_err(|_| RowFailure::AmountTooLarge)
+}
+
+/// Applies a single import `row` using the provided `mode`
+async fn apply_row<C>(db: &C, row: InventoryRow, mode: ImportMode) -> Result<(), RowFailure>
+where
+    C: ConnectionTrait + TransactionTrait + Send,
+{
+    let user = User::by_email(db, &row.email)
+        .await?
+        .ok_or_else(|| RowFailure::UnknownUser(row.email.clone()))?;
+
+    let db = db.begin().await?;
+
+    match row.kind {
+        InventoryRowKind::Item => {
+            let definition = row
+                .name
+                .parse::<ItemName>()
+                .ok()
+                .and_then(|name| Items::get().by_name(&name))
+                .ok_or_else(|| RowFailure::UnknownItem(row.name.clone()))?;
+
+            let item = InventoryItem::get_by_name(&db, &user, definition.name).await?;
+            let owned = item
+                .as_ref()
+                .map(|item| item.stack_size)
+                .unwrap_or_default();
+
+            let change = mode.change(owned, row.amount)?;
+            let amount = change_amount(change)?;
+
+            if change > 0 {
+                let granted = items::grant_item(&db, &user, definition, amount).await?;
+                if granted.overflow > 0 {
+                    return Err(RowFailure::ItemCapacityReached);
+                }
+            } else if change < 0 {
+                let item = item
+                    .filter(|item| item.stack_size >= amount)
+                    .ok_or(RowFailure::InsufficientItems)?;
+
+                // Items removed down to an empty stack are deleted
+                let stack_size = item.stack_size - amount;
+                item.set_stack_size(&db, stack_size).await?;
+            }
+        }
+        InventoryRowKind::Currency => {
+            let ty: CurrencyType = row
+                .name
+                .parse()
+                .map_err(|_| RowFailure::UnknownCurrency(row.name.clone()))?;
+
+            let balance = Currency::get(&db, &user, ty)
+                .await?
+                .map(|currency| currency.balance)
+                .unwrap_or_default();
+
+            let change = mode.change(balance, row.amount)?;
+            let amount = change_amount(change)?;
+
+            if change > 0 {
+                let earned =
+                    Currency::earn(&db, &user, ty, amount, CurrencyLedgerReason::AdminImport)
+                        .await?;
+                if earned < amount {
+                    return Err(RowFailure::CurrencyCapacityReached);
+                }
+            } else if change < 0 {
+                Currency::try_spend(&db, &user, ty, amount)
+                    .await?
+                    .ok_or(RowFailure::InsufficientCurrency)?;
+                CurrencyLedgerEntry::create(
+                    &db,
+                    &user,
+                    ty,
+                    change,
+                    CurrencyLedgerReason::AdminImport,
+                )
+                .await?;
+            }
+        }
+    }
+
+    db.commit().await?;
+
+    Ok(())
+}
+
+/// Type alias for a [u32] representing the ID of an import job
+pub type ImportJobId = u32;
+
+/// Progress of an import job
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportJob {
+    /// ID of the job
+    pub id: ImportJobId,
+    /// Whether every row has been processed
+    pub finished: bool,
+    /// Total number of rows in the import
+    pub total: usize,
+    /// Number of rows that have been processed
+    pub processed: usize,
+    /// Number of rows that were applied
+    pub applied: usize,
+    /// Errors for the rows that couldn't be applied
+    pub errors: Vec<ImportRowError>,
+}
+
+/// Error for a row that couldn't be imported
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportRowError {
+    /// Row number within the import file, starting at 1
+    pub row: usize,
+    /// Reason the row couldn't be imported
+    pub error: String,
+}
+
+/// Service tracking the inventory import jobs
+pub struct InventoryImports {
+    /// ID for the next import job
+    next_id: AtomicU32,
+    /// Running and recently finished jobs
+    jobs: Mutex<HashMap<ImportJobId, ImportJob>>,
+}
+
+impl InventoryImports {
+    pub fn new() -> Self {
+        Self {
+            next_id: AtomicU32::new(1),
+            jobs: Default::default(),
+        }
+    }
+
+    /// Gets the current progress of the job with the provided `id`
+    pub fn get(&self, id: ImportJobId) -> Option<ImportJob> {
+        self.jobs.lock().get(&id).cloned()
+    }
+
+    /// Starts a background job applying the `rows` using the `mode`,
+    /// responds with the ID of the job for checking its progress
+    pub fn start(
+        self: &Arc<Self>,
+        db: DatabaseConnection,
+        rows: Vec<Result<InventoryRow, String>>,
+        mode: ImportMode,
+    ) -> ImportJobId {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+
+        {
+            let jobs = &mut *self.jobs.lock();
+
+            // Forget the oldest finished jobs
+            let mut finished: Vec<ImportJobId> = jobs
+                .values()
+                .filter(|job| job.finished)
+                .map(|job| job.id)
+                .collect();
+            if finished.len() >= MAX_FINISHED_JOBS {
+                finished.sort_unstable();
+                for id in &finished[..=finished.len() - MAX_FINISHED_JOBS] {
+                    jobs.remove(id);
+                }
+            }
+
+            jobs.insert(
+                id,
+                ImportJob {
+                    id,
+                    finished: false,
+                    total: rows.len(),
+                    processed: 0,
+                    applied: 0,
+                    errors: Vec::new(),
+                },
+            );
+        }
+
+        let imports = self.clone();
+        let context = CrashContext::new("inventory-import");
+        tokio::spawn(crash_reports::scope(context, async move {
+            imports.run(db, id, rows, mode).await;
+        }));
+
+        id
+    }
+
+    /// Applies each of the `rows` recording the progress on the job
+    async fn run(
+        &self,
+        db: DatabaseConnection,
+        id: ImportJobId,
+        rows: Vec<Result<InventoryRow, String>>,
+        mode: ImportMode,
+    ) {
+        for (index, row) in rows.into_iter().enumerate() {
+            let result = match row {
+                Ok(row) => apply_row(&db, row, mode)
+                    .await
+                    .map_err(|err| err.to_string()),
+                Err(err) => Err(err),
+            };
+
+            self.update(id, |job| {
+                job.processed += 1;
+                match result {
+                    Ok(()) => job.applied += 1,
+                    Err(error) => job.errors.push(ImportRowError {
+                        row: index + 1,
+                        error,
+                    }),
+                }
+            });
+        }
+
+        self.update(id, |job| {
+            job.finished = true;
+            info!(
+                "Inventory import {} finished ({} applied, {} failed)",
+                id,
+                job.applied,
+                job.errors.len()
+            );
+        });
+    }
+
+    /// Updates the job with the provided `id` using `action`
+    fn update(&self, id: ImportJobId, action: impl FnOnce(&mut ImportJob)) {
+        if let Some(job) = self.jobs.lock().get_mut(&id) {
+            action(job);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{decode, encode, ImportMode, InventoryRow, InventoryRowKind, TransferFormat};
+
+    /// Tests that encoded rows can be decoded in both formats
+    #[test]
+    fn test_round_trip() {
+        let rows = vec![InventoryRow {
+            email: "test@example.com".to_string(),
+            kind: InventoryRowKind::Currency,
+            name: "MissionCurrency".to_string(),
+            amount: -50,
+        }];
+
+        for format in [TransferFormat::Json, TransferFormat::Csv] {
+            let data = encode(&rows, format).unwrap();
+            let decoded = decode(&data, format).unwrap();
+            assert_eq!(decoded.len(), 1);
+
+            let row = decoded[0].as_ref().unwrap();
+            assert_eq!(row.email, "test@example.com");
+            assert_eq!(row.kind, InventoryRowKind::Currency);
+            assert_eq!(row.amount, -50);
+        }
+    }
+
+    /// Tests that invalid rows are kept as errors without
+    /// failing the other rows
+    #[test]
+    fn test_decode_invalid_row() {
+        let data =
+            b"email,kind,name,amount\na@example.com,item,abc,5\nb@example.com,unknown,abc,5\n";
+        let decoded = decode(data, TransferFormat::Csv).unwrap();
+        assert_eq!(decoded.len(), 2);
+        assert!(decoded[0].is_ok());
+        assert!(decoded[1].is_err());
+    }
+
+    /// Tests that set imports change the balance to the row amount while
+    /// adjustments are applied as is
+    #[test]
+    fn test_import_mode_change() {
+        assert_eq!(ImportMode::Set.change(30, 50).unwrap(), 20);
+        assert_eq!(ImportMode::Set.change(30, 0).unwrap(), -30);
+        assert!(ImportMode::Set.change(30, -5).is_err());
+
+        assert_eq!(ImportMode::Adjust.change(30, -5).unwrap(), -5);
+        assert!(ImportMode::Adjust.change(30, 0).is_err());
+    }
+}
@@ -0,0 +1,159 @@
+//! Item grant pipeline shared by the paths that reward items (packs, store
+//! purchases, challenge rewards and strike team rewards). Grants are limited
+//! to the item capacity, items locked behind another item reaching its
+//! capacity can't be granted until they are unlocked, and the amount that
+//! couldn't be granted is converted into the configured overflow currency.
+//! Character items also unlock the character they are for.
+
+use crate::{
+    config::Config,
+    database::entity::{InventoryItem, User},
+    definitions::{
+        challenges::CurrencyReward,
+        characters::{acquire_item_character, AcquiredCharacter},
+        classes::Classes,
+        items::{BaseCategory, ItemDefinition, Items},
+        level_tables::LevelTables,
+    },
+};
+use sea_orm::{ConnectionTrait, DbErr};
+
+/// Outcome of granting an item through [grant_item]
+pub struct GrantedItem {
+    /// The updated inventory item, [None] when nothing was added
+    pub item: Option<InventoryItem>,
+    /// Amount of the item added to the inventory
+    pub granted: u32,
+    /// Amount of the item that couldn't be added
+    pub overflow: u32,
+    /// Character acquired from a character item
+    pub character: Option<AcquiredCharacter>,
+}
+
+/// Grants up to `amount` of the item with the provided `definition` to
+/// the `user`. Locked items and any amount over the item capacity are
+/// reported as overflow rather than being added
+pub async fn grant_item<C>(
+    db: &C,
+    user: &User,
+    definition: &'static ItemDefinition,
+    amount: u32,
+) -> anyhow::Result<GrantedItem>
+where
+    C: ConnectionTrait + Send,
+{
+    let (granted, overflow) = if is_unlocked(db, user, definition).await? {
+        let owned = InventoryItem::get_by_name(db, user, definition.name)
+            .await?
+            .map(|item| item.stack_size)
+            .unwrap_or_default();
+        fit_capacity(definition.capacity, owned, amount)
+    } else {
+        (0, amount)
+    };
+
+    if granted == 0 {
+        return Ok(GrantedItem {
+            item: None,
+            granted,
+            overflow,
+            character: None,
+        });
+    }
+
+    let item =
+        InventoryItem::add_item(db, user, definition.name, granted, definition.capacity).await?;
+
+    let character = if definition.category.base_eq(&BaseCategory::Characters) {
+        Some(
+            acquire_item_character(
+                db,
+                user,
+                &definition.name,
+                Classes::get(),
+                LevelTables::get(),
+            )
+            .await?,
+        )
+    } else {
+        None
+    };
+
+    Ok(GrantedItem {
+        item: Some(item),
+        granted,
+        overflow,
+        character,
+    })
+}
+
+/// Checks whether the `user` has unlocked the item with the provided
+/// `definition`, items without an unlock definition are always unlocked
+pub async fn is_unlocked<C>(db: &C, user: &User, definition: &ItemDefinition) -> Result<bool, DbErr>
+where
+    C: ConnectionTrait + Send,
+{
+    let Some(unlock_name) = definition.unlock_definition else {
+        return Ok(true);
+    };
+
+    // Items with a missing unlock definition can never be unlocked
+    let Some(unlock_definition) = Items::get().by_name(&unlock_name) else {
+        return Ok(false);
+    };
+
+    let owned = InventoryItem::get_by_name(db, user, unlock_name).await?;
+    Ok(unlock_reached(unlock_definition, owned.as_ref()))
+}
+
+/// Checks whether the `owned` item unlocks the items that depend on the
+/// `unlock_definition`, the item unlocks them once it reaches its capacity
+pub fn unlock_reached(unlock_definition: &ItemDefinition, owned: Option<&InventoryItem>) -> bool {
+    match (unlock_definition.capacity, owned) {
+        (Some(capacity), Some(owned)) => owned.stack_size >= capacity,
+        _ => false,
+    }
+}
+
+/// Splits an `amount` of an item into the amount that fits within the item
+/// `capacity` when `owned` are already owned and the amount that overflows
+pub fn fit_capacity(capacity: Option<u32>, owned: u32, amount: u32) -> (u32, u32) {
+    match capacity {
+        Some(capacity) => {
+            let granted = amount.min(capacity.saturating_sub(owned));
+            (granted, amount - granted)
+        }
+        None => (amount, 0),
+    }
+}
+
+/// Converts an `overflow` amount of items into the configured overflow
+/// currency, [None] when the overflowing items are discarded
+pub fn overflow_currency(overflow: u32) -> Option<CurrencyReward> {
+    let config = &Config::get().inventory;
+    let value = overflow.saturating_mul(config.overflow_value);
+    if value == 0 {
+        return None;
+    }
+
+    Some(CurrencyReward {
+        name: config.overflow_currency,
+        value,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::fit_capacity;
+
+    /// Tests that amounts are limited to the remaining capacity
+    #[test]
+    fn test_fit_capacity() {
+        assert_eq!(fit_capacity(None, 50, 10), (10, 0));
+        assert_eq!(fit_capacity(Some(20), 0, 10), (10, 0));
+        assert_eq!(fit_capacity(Some(20), 15, 10), (5, 5));
+        assert_eq!(fit_capacity(Some(20), 20, 10), (0, 10));
+        // Items already over capacity don't go negative
+        assert_eq!(fit_capacity(Some(20), 25, 10), (0, 10));
+    }
+}
@@ -0,0 +1,253 @@
+//! Service for the leaderboards, aggregates the stats of each user into the
+//! leaderboard stats table which the leaderboards are ranked from.
+//!
+//! Stats are updated for each player when a mission is completed and all
+//! users are periodically recalculated so that changes made outside of
+//! missions (Challenges, admin changes, etc) are reflected
+
+use crate::{
+    config::Config,
+    database::{
+        entity::{
+            characters, leaderboard_stats, ChallengeProgress, LeaderboardStats, PlayerTitle,
+            SharedData, User,
+        },
+        DbResult,
+    },
+    definitions::{challenges::Challenges, titles::PlayerTitles},
+    services::crash_reports::{self, CrashContext},
+};
+use log::{debug, error, info};
+use sea_orm::{ConnectionTrait, DatabaseConnection, ModelTrait};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::time::sleep;
+use uuid::{uuid, Uuid};
+
+/// Name of the APEX rating leaderboard category
+pub const APEX_CATEGORY: Uuid = uuid!("2e9181f0-bd7b-e489-1a64-91598df0780c");
+/// Name of the challenge rating leaderboard category
+pub const CHALLENGE_CATEGORY: Uuid = uuid!("aff90bf0-a9fd-0a5e-679d-60fc8691ff45");
+
+/// Number of users recalculated at a time
+const RECALCULATE_BATCH_SIZE: u64 = 100;
+
+/// Stats that the leaderboards can be ranked by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LeaderboardStat {
+    /// Total level of all the user characters
+    #[default]
+    N7Rating,
+    /// APEX (pathfinder) rating
+    ApexRating,
+    /// Points earned from completed challenges
+    ChallengePoints,
+    /// Number of completed missions
+    MatchCount,
+}
+
+impl LeaderboardStat {
+    /// Finds the stat ranked by the leaderboard category with the provided `name`
+    pub fn from_category(name: &Uuid) -> Option<Self> {
+        match *name {
+            APEX_CATEGORY => Some(Self::ApexRating),
+            CHALLENGE_CATEGORY => Some(Self::ChallengePoints),
+            _ => None,
+        }
+    }
+
+    /// The database column storing the stat
+    pub fn column(&self) -> leaderboard_stats::Column {
+        match self {
+            Self::N7Rating => leaderboard_stats::Column::N7Rating,
+            Self::ApexRating => leaderboard_stats::Column::ApexRating,
+            Self::ChallengePoints => leaderboard_stats::Column::ChallengePoints,
+            Self::MatchCount => leaderboard_stats::Column::MatchCount,
+        }
+    }
+
+    /// Gets the value of the stat from the provided `stats`
+    pub fn value(&self, stats: &LeaderboardStats) -> f32 {
+        match self {
+            Self::N7Rating => stats.n7_rating as f32,
+            Self::ApexRating => stats.apex_rating,
+            Self::ChallengePoints => stats.challenge_points as f32,
+            Self::MatchCount => stats.match_count as f32,
+        }
+    }
+}
+
+/// Updates the leaderboard stats for the `user` from their current data,
+/// `completed_match` increases the match count for a completed mission.
+///
+/// Titles for the career milestones reached by the stats are unlocked
+pub async fn update_user<C>(db: &C, user: &User, completed_match: bool) -> DbResult<()>
+where
+    C: ConnectionTrait + Send,
+{
+    let n7_rating: u32 = user
+        .find_related(characters::Entity)
+        .all(db)
+        .await?
+        .iter()
+        .map(|character| character.level)
+        .sum();
+
+    let apex_rating = SharedData::get(db, user)
+        .await?
+        .shared_stats
+        .pathfinder_rating;
+
+    let challenge_points = challenge_points(&ChallengeProgress::all(db, user).await?);
+
+    LeaderboardStats::set(
+        db,
+        user,
+        n7_rating,
+        apex_rating,
+        challenge_points,
+        u32::from(completed_match),
+    )
+    .await?;
+
+    // Read back the stored stats for the updated match count
+    let Some(stats) = LeaderboardStats::get(db, user.id).await? else {
+        return Ok(());
+    };
+
+    for title in PlayerTitles::get().milestones_reached(&stats) {
+        PlayerTitle::unlock(db, user, title.name.clone()).await?;
+    }
+
+    Ok(())
+}
+
+/// Totals the points for each time the challenges in `progress` were completed
+fn challenge_points(progress: &[ChallengeProgress]) -> u32 {
+    let challenges = Challenges::get();
+
+    progress
+        .iter()
+        .filter_map(|progress| {
+            let point_value = challenges.by_name(&progress.challenge_id)?.point_value?;
+            Some(point_value.saturating_mul(progress.times_completed))
+        })
+        .fold(0, u32::saturating_add)
+}
+
+/// Background task that recalculates the leaderboard
+/// stats for every user on the configured schedule
+pub struct LeaderboardBackgroundTask {
+    /// Database access for updating the stats
+    db: DatabaseConnection,
+}
+
+impl LeaderboardBackgroundTask {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    /// Starts the task in a background tokio task if
+    /// the recalculation is enabled
+    pub fn start(self) {
+        let config = &Config::get().leaderboards;
+        if !config.recalculate {
+            debug!("Leaderboard recalculation is disabled");
+            return;
+        }
+
+        // Prevent an interval of zero from constantly running
+        let interval = Duration::from_secs(config.recalculate_interval_minutes.max(1) * 60);
+
+        let context = CrashContext::new("leaderboards");
+        tokio::spawn(crash_reports::scope(context, async move {
+            loop {
+                match recalculate(&self.db).await {
+                    Ok(updated) => info!("Recalculated leaderboard stats for {} user(s)", updated),
+                    Err(err) => error!("Error while recalculating leaderboards: {}", err),
+                }
+
+                sleep(interval).await;
+            }
+        }));
+    }
+}
+
+/// Recalculates the leaderboard stats for every user, responds
+/// with the number of users that were updated
+async fn recalculate(db: &DatabaseConnection) -> DbResult<usize> {
+    let mut offset = 0;
+    let mut updated = 0;
+
+    loop {
+        let users = User::page(db, offset, RECALCULATE_BATCH_SIZE).await?;
+        if users.is_empty() {
+            break;
+        }
+
+        offset += users.len() as u64;
+
+        for user in users {
+            // Failing users are skipped so they don't block the others
+            match update_user(db, &user, false).await {
+                Ok(()) => updated += 1,
+                Err(err) => error!(
+                    "Failed to recalculate leaderboard stats for {}: {}",
+                    user.id, err
+                ),
+            }
+        }
+    }
+
+    Ok(updated)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{challenge_points, LeaderboardStat, APEX_CATEGORY, CHALLENGE_CATEGORY};
+    use crate::{
+        database::entity::{challenge_progress::ChallengeState, ChallengeProgress, SeaJson},
+        definitions::challenges::Challenges,
+    };
+    use chrono::Utc;
+
+    /// Tests that the known categories rank the expected stats
+    #[test]
+    fn test_from_category() {
+        assert_eq!(
+            LeaderboardStat::from_category(&APEX_CATEGORY),
+            Some(LeaderboardStat::ApexRating)
+        );
+        assert_eq!(
+            LeaderboardStat::from_category(&CHALLENGE_CATEGORY),
+            Some(LeaderboardStat::ChallengePoints)
+        );
+        assert_eq!(LeaderboardStat::from_category(&uuid::Uuid::nil()), None);
+    }
+
+    /// Tests that challenge points are awarded for each completion
+    #[test]
+    fn test_challenge_points() {
+        let definition = Challenges::get()
+            .values
+            .iter()
+            .find(|definition| definition.point_value.is_some_and(|value| value > 0))
+            .expect("Missing challenge with points");
+        let point_value = definition.point_value.unwrap();
+
+        let progress = ChallengeProgress {
+            user_id: 1,
+            challenge_id: definition.name,
+            counters: SeaJson(Vec::new()),
+            state: ChallengeState::Completed,
+            times_completed: 3,
+            last_completed: None,
+            first_completed: None,
+            last_changed: Utc::now(),
+            rewarded: true,
+        };
+
+        assert_eq!(challenge_points(&[progress]), point_value * 3);
+    }
+}
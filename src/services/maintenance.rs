@@ -0,0 +1,191 @@
+//! Service for periodic database maintenance, prunes old data and
+//! optimizes the database so long running servers don't degrade
+//! over time
+//!
+//! Sessions aren't persisted to the database so they don't require
+//! any pruning
+
+use crate::{
+    config::{Config, MaintenanceConfig},
+    database::entity::{
+        seen_articles, GameRecord, InventoryItem, LoginEvent, MatchTelemetry, MatchmakingRecord,
+    },
+    definitions::store_catalogs::StoreCatalogs,
+    services::crash_reports::{self, CrashContext},
+    utils::clock,
+};
+use anyhow::Context;
+use chrono::Days;
+use log::{debug, error, info};
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend};
+use serde::Serialize;
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+use tokio::time::sleep;
+
+/// Background task that runs the maintenance on the
+/// configured schedule
+pub struct MaintenanceBackgroundTask {
+    /// Database access is required for maintenance
+    db: DatabaseConnection,
+}
+
+/// Read-only mode for the HTTP API, while enabled requests that modify
+/// player data are rejected. Starts with the configured state and can be
+/// changed at runtime by administrators, changes are not persisted
+pub struct ReadOnlyMode {
+    enabled: AtomicBool,
+}
+
+impl ReadOnlyMode {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled: AtomicBool::new(enabled),
+        }
+    }
+
+    /// Checks whether the read-only mode is enabled
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Acquire)
+    }
+
+    /// Enables or disables the read-only mode
+    pub fn set(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Release);
+    }
+}
+
+/// Report of the changes made by a maintenance run
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MaintenanceReport {
+    /// Number of login events pruned
+    pub login_events_pruned: u64,
+    /// Number of seen articles pruned
+    pub seen_articles_pruned: u64,
+    /// Number of deleted inventory items purged
+    pub deleted_items_purged: u64,
+    /// Number of match telemetry records pruned
+    pub match_telemetry_pruned: u64,
+    /// Number of game records pruned
+    pub game_records_pruned: u64,
+    /// Number of matchmaking records pruned
+    pub matchmaking_records_pruned: u64,
+    /// Whether the database was vacuumed
+    pub vacuumed: bool,
+    /// Time taken in milliseconds
+    pub duration_ms: u128,
+}
+
+impl MaintenanceBackgroundTask {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    /// Starts the task in a background tokio task if
+    /// scheduled maintenance is enabled
+    pub fn start(self) {
+        let config = &Config::get().maintenance;
+        if !config.enabled {
+            debug!("Scheduled database maintenance is disabled");
+            return;
+        }
+
+        // Prevent an interval of zero from constantly running
+        let interval = Duration::from_secs(config.interval_hours.max(1) * 60 * 60);
+
+        let context = CrashContext::new("maintenance");
+        tokio::spawn(crash_reports::scope(context, async move {
+            loop {
+                sleep(interval).await;
+
+                if let Err(err) = run_maintenance(&self.db, config).await {
+                    error!("Error while running database maintenance: {:?}", err);
+                }
+            }
+        }));
+    }
+}
+
+/// Runs the database maintenance using the provided `config`
+pub async fn run_maintenance(
+    db: &DatabaseConnection,
+    config: &MaintenanceConfig,
+) -> anyhow::Result<MaintenanceReport> {
+    let start = Instant::now();
+    let mut report = MaintenanceReport::default();
+
+    // Prune old login history
+    let before = clock::now()
+        .checked_sub_days(Days::new(config.login_history_days as u64))
+        .context("Login history retention out of range")?;
+    report.login_events_pruned = LoginEvent::delete_before(db, before)
+        .await
+        .context("Failed to prune login events")?
+        .rows_affected;
+
+    // Prune seen states for articles no longer in the store
+    let articles = StoreCatalogs::get()
+        .catalog
+        .articles
+        .iter()
+        .map(|article| article.name);
+    report.seen_articles_pruned = seen_articles::Model::delete_not_in(db, articles)
+        .await
+        .context("Failed to prune seen articles")?
+        .rows_affected;
+
+    // Purge inventory items deleted long enough ago
+    let before = clock::now()
+        .checked_sub_days(Days::new(config.deleted_items_days as u64))
+        .context("Deleted item retention out of range")?;
+    report.deleted_items_purged = InventoryItem::purge_deleted_before(db, before)
+        .await
+        .context("Failed to purge deleted inventory items")?
+        .rows_affected;
+
+    // Prune old match telemetry
+    let before = clock::now()
+        .checked_sub_days(Days::new(config.match_telemetry_days as u64))
+        .context("Match telemetry retention out of range")?;
+    report.match_telemetry_pruned = MatchTelemetry::delete_before(db, before)
+        .await
+        .context("Failed to prune match telemetry")?
+        .rows_affected;
+
+    // Prune old game and matchmaking records
+    let before = clock::now()
+        .checked_sub_days(Days::new(config.game_records_days as u64))
+        .context("Game record retention out of range")?;
+    report.game_records_pruned = GameRecord::delete_before(db, before)
+        .await
+        .context("Failed to prune game records")?
+        .rows_affected;
+    report.matchmaking_records_pruned = MatchmakingRecord::delete_before(db, before)
+        .await
+        .context("Failed to prune matchmaking records")?
+        .rows_affected;
+
+    // MySQL maintains its own statistics and doesn't support vacuuming
+    if db.get_database_backend() != DbBackend::MySql {
+        // Update the query planner statistics
+        db.execute_unprepared("ANALYZE")
+            .await
+            .context("Failed to analyze database")?;
+
+        if config.vacuum {
+            db.execute_unprepared("VACUUM")
+                .await
+                .context("Failed to vacuum database")?;
+            report.vacuumed = true;
+        }
+    }
+
+    report.duration_ms = start.elapsed().as_millis();
+
+    info!("Completed database maintenance: {:?}", report);
+
+    Ok(report)
+}
@@ -1,18 +1,27 @@
 //! Service for keeping track of creating missions and managing
-//! existing missions
+//! existing missions, also returns strike teams from their missions
+//! once the mission time has passed
 
-use std::{ops::Add, time::Duration};
+use std::{ops::Add, sync::Arc, time::Duration};
 
 use anyhow::Context;
 use chrono::{Datelike, Days, TimeZone, Timelike, Utc};
 use log::{debug, error};
-use rand::{rngs::StdRng, SeedableRng};
 use sea_orm::{prelude::DateTimeUtc, DatabaseConnection};
-use tokio::time::sleep;
+use tokio::time::{interval, sleep};
 
 use crate::{
-    database::entity::StrikeTeamMission,
+    database::entity::{notifications::NotificationKind, StrikeTeamMission},
     definitions::strike_teams::{random_mission, MissionDifficulty, StrikeTeamMissionData},
+    services::{
+        crash_reports::{self, CrashContext},
+        maintenance::ReadOnlyMode,
+        notifications,
+        random::{RandomPurpose, RandomService},
+        sessions::Sessions,
+        strike_team_deployment,
+    },
+    utils::clock,
 };
 
 /// Background task that handles creating missions on the fixed
@@ -20,24 +29,47 @@ use crate::{
 pub struct MissionBackgroundTask {
     /// Database access is required for missions
     db: DatabaseConnection,
+    /// Sessions for notifying users when their strike teams return
+    sessions: Arc<Sessions>,
+    /// Read-only mode, strike teams aren't returned while enabled
+    read_only: Arc<ReadOnlyMode>,
 }
 
 /// Represents an hour offset for execution
 type HourOffset = u32;
 
 impl MissionBackgroundTask {
-    pub fn new(db: DatabaseConnection) -> Self {
-        Self { db }
+    pub fn new(
+        db: DatabaseConnection,
+        sessions: Arc<Sessions>,
+        read_only: Arc<ReadOnlyMode>,
+    ) -> Self {
+        Self {
+            db,
+            sessions,
+            read_only,
+        }
     }
 
     /// Starts the task in a background tokio task
     pub fn start(self) {
-        tokio::spawn(async move {
+        let db = self.db.clone();
+        let sessions = self.sessions.clone();
+        let read_only = self.read_only.clone();
+        let context = CrashContext::new("strike-team-returns");
+        tokio::spawn(crash_reports::scope(context, async move {
+            Self::run_returns(db, sessions, read_only).await;
+        }));
+
+        let context = CrashContext::new("missions");
+        tokio::spawn(crash_reports::scope(context, async move {
             self.run().await;
-        });
+        }));
     }
 
     const HOURS_IN_DAY: u32 = 24;
+    /// Interval between checking for strike teams that have returned
+    const RETURN_INTERVAL: Duration = Duration::from_secs(60);
     const SCHEDULE_HOURLY_INTERVAL: u32 = 4;
     const TOTAL_DAILY_OFFSETS: u32 = Self::HOURS_IN_DAY / Self::SCHEDULE_HOURLY_INTERVAL;
 
@@ -70,6 +102,56 @@ impl MissionBackgroundTask {
             })
     }
 
+    /// Periodically returns the strike teams that have finished their
+    /// missions so they are ready to resolve without the user having to
+    /// refresh their strike teams first, users are notified that their
+    /// teams have returned. Teams aren't returned while read-only mode
+    /// is enabled
+    async fn run_returns(
+        db: DatabaseConnection,
+        sessions: Arc<Sessions>,
+        read_only: Arc<ReadOnlyMode>,
+    ) {
+        let mut interval = interval(Self::RETURN_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            if read_only.is_enabled() {
+                continue;
+            }
+
+            let returned = match strike_team_deployment::return_finished(&db).await {
+                Ok(value) => value,
+                Err(err) => {
+                    error!("Failed to return strike teams from missions: {}", err);
+                    continue;
+                }
+            };
+
+            if returned.is_empty() {
+                continue;
+            }
+
+            debug!("Returned {} strike team(s) from missions", returned.len());
+
+            for progress in returned {
+                let message =
+                    "A strike team has returned from its mission and is ready to be resolved";
+                if let Err(err) = notifications::push(
+                    &db,
+                    &sessions,
+                    progress.user_id,
+                    NotificationKind::StrikeTeamResult,
+                    message.to_string(),
+                )
+                .await
+                {
+                    error!("Failed to notify returned strike team: {}", err);
+                }
+            }
+        }
+    }
+
     async fn run(&self) {
         let mut failures = 0;
 
@@ -94,7 +176,7 @@ impl MissionBackgroundTask {
     }
 
     async fn process(&self) -> anyhow::Result<()> {
-        let current_time = Utc::now();
+        let current_time = clock::now();
 
         let last_date_time = self
             .last_mission_time()
@@ -171,7 +253,7 @@ impl MissionBackgroundTask {
         const PM_8: HourOffset = 5;
         const PM_12: HourOffset = 6;
 
-        let mut rng = StdRng::from_entropy();
+        let mut rng = RandomService::get().rng(RandomPurpose::MissionRotation);
 
         // Mission data to create
         let mut mission_data: Vec<StrikeTeamMissionData> = Vec::new();
@@ -223,7 +305,7 @@ impl MissionBackgroundTask {
 
     /// Sleeps until the provided date time is reached
     async fn sleep_until(date: DateTimeUtc) -> anyhow::Result<()> {
-        let now = Utc::now();
+        let now = clock::now();
 
         // Already passed the date
         if date.lt(&now) {
@@ -0,0 +1,187 @@
+//! Worker for processing finished missions, computing the rewards, badges
+//! and challenge progress for every player can be slow with many definitions
+//! so its done in the background after the finish request has responded
+
+use super::{
+    activity_buffer::ActivityBuffer,
+    crash_reports::{self, CrashContext},
+    game::{process_mission_data, GameID},
+    game_manager::GameManager,
+    maintenance::ReadOnlyMode,
+};
+use crate::http::models::mission::CompleteMissionData;
+use log::{debug, error};
+use sea_orm::DatabaseConnection;
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::{
+    sync::mpsc,
+    time::{sleep, Instant},
+};
+
+/// Mission data submitted by a game waiting to be processed, the data is
+/// a snapshot taken when submitted so later submissions don't change it
+pub struct MissionResultJob {
+    /// ID of the game the mission was played in
+    pub game_id: GameID,
+    /// Revision of the game mission data the snapshot was taken at
+    pub revision: u32,
+    /// The submitted mission data
+    pub mission_data: CompleteMissionData,
+}
+
+/// Handle for queueing finished missions to be processed
+pub struct MissionResultWorker {
+    /// Sender for the missions to process
+    tx: mpsc::UnboundedSender<MissionResultJob>,
+    /// Number of queued missions that haven't finished processing
+    pending: Arc<AtomicUsize>,
+}
+
+impl MissionResultWorker {
+    /// Starts the background worker task
+    pub fn start(
+        db: DatabaseConnection,
+        game_manager: Arc<GameManager>,
+        activity_buffer: Arc<ActivityBuffer>,
+        read_only: Arc<ReadOnlyMode>,
+    ) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let pending = Arc::new(AtomicUsize::new(0));
+        tokio::spawn(Self::run(
+            db,
+            game_manager,
+            activity_buffer,
+            read_only,
+            rx,
+            pending.clone(),
+        ));
+        Self { tx, pending }
+    }
+
+    /// Queues the finished mission `job` to be processed
+    pub fn queue(&self, job: MissionResultJob) {
+        self.pending.fetch_add(1, Ordering::AcqRel);
+        if self.tx.send(job).is_err() {
+            self.pending.fetch_sub(1, Ordering::AcqRel);
+        }
+    }
+
+    /// Waits for the queued missions to finish processing, used before
+    /// the server exits so players don't lose their rewards. Gives up
+    /// after `timeout` responding with whether all missions were processed
+    pub async fn flush(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        while self.pending.load(Ordering::Acquire) > 0 {
+            if Instant::now() >= deadline {
+                return false;
+            }
+            sleep(Duration::from_millis(100)).await;
+        }
+        true
+    }
+
+    /// Processes the queued missions one at a time so that many missions
+    /// finishing at once don't compete for the database. Missions queued
+    /// before read-only mode was enabled are held until it's disabled, if
+    /// the server stops first they are stored with the other in-flight
+    /// missions
+    async fn run(
+        db: DatabaseConnection,
+        game_manager: Arc<GameManager>,
+        activity_buffer: Arc<ActivityBuffer>,
+        read_only: Arc<ReadOnlyMode>,
+        mut rx: mpsc::UnboundedReceiver<MissionResultJob>,
+        pending: Arc<AtomicUsize>,
+    ) {
+        while let Some(job) = rx.recv().await {
+            while read_only.is_enabled() {
+                sleep(Duration::from_secs(1)).await;
+            }
+
+            let game_id = job.game_id;
+
+            // Processed in a separate task so a panic doesn't stop the worker
+            let context = CrashContext::new("mission results").with_game(game_id);
+            let task = tokio::spawn(crash_reports::scope(
+                context,
+                process_game(
+                    db.clone(),
+                    game_manager.clone(),
+                    activity_buffer.clone(),
+                    job,
+                ),
+            ));
+
+            if let Err(err) = task.await {
+                error!("Failed to process mission (GID: {}): {}", game_id, err);
+            }
+
+            pending.fetch_sub(1, Ordering::AcqRel);
+        }
+    }
+}
+
+/// Processes the mission data snapshot from the `job`, the game is only
+/// locked while claiming the mission and storing the results. Missions
+/// from games that have since stopped are still processed so players
+/// that leave straight after finishing get their rewards
+async fn process_game(
+    db: DatabaseConnection,
+    game_manager: Arc<GameManager>,
+    activity_buffer: Arc<ActivityBuffer>,
+    job: MissionResultJob,
+) {
+    let MissionResultJob {
+        game_id,
+        revision,
+        mission_data,
+    } = job;
+
+    let game = game_manager.get_game(game_id).await;
+
+    if let Some(game) = &game {
+        let game = &mut *game.write().await;
+
+        // Newer mission data is processed by its own job
+        if game.mission_revision != revision {
+            return;
+        }
+
+        // Mission may have already been processed
+        if game.processed_data.is_some() || game.mission_claimed == Some(revision) {
+            return;
+        }
+
+        // Mission data may have been taken to be stored during shutdown
+        if game.mission_data.is_none() {
+            return;
+        }
+
+        game.mission_claimed = Some(revision);
+    } else {
+        debug!(
+            "Game stopped before mission was processed (GID: {})",
+            game_id
+        );
+    }
+
+    // Progress buffered during the mission is written before the results
+    let user_ids: Vec<_> = mission_data
+        .player_data
+        .iter()
+        .map(|data| data.nucleus_id)
+        .collect();
+    let applied = activity_buffer.finish_mission(game_id, &user_ids).await;
+
+    let details = process_mission_data(&db, mission_data, applied).await;
+
+    if let Some(game) = game {
+        game.write().await.set_processed_data(revision, details);
+    }
+}
@@ -1,5 +1,41 @@
 pub mod activity;
+pub mod activity_buffer;
+pub mod auth;
+pub mod crash_reports;
+pub mod diagnostics;
+pub mod economy_simulator;
+pub mod email;
+pub mod feature_flags;
+pub mod friends;
 pub mod game;
+pub mod game_index;
 pub mod game_manager;
+pub mod game_stats;
+pub mod game_timeline;
+pub mod household;
+pub mod inventory_transfer;
+pub mod items;
+pub mod leaderboard;
+pub mod maintenance;
 pub mod mission;
+pub mod mission_results;
+pub mod mutators;
+pub mod notifications;
+pub mod pack_experiments;
+pub mod pocket_relay;
+pub mod pocket_relay_import;
+pub mod public_stats;
+pub mod random;
+pub mod ready_check;
+pub mod replay_cache;
+pub mod restarts;
+pub mod rewards;
+pub mod server_events;
 pub mod sessions;
+pub mod shutdown;
+pub mod store_rotation;
+pub mod strike_team_deployment;
+pub mod strike_team_resolve;
+pub mod strike_team_retirement;
+pub mod telemetry;
+pub mod user_locks;
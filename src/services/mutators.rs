@@ -0,0 +1,123 @@
+//! Operator defined game mutators for seasonal events (e.g. "all platinum
+//! enemies"), games created during the event window of a mutator have the
+//! mutator attributes added to their game attributes which the client
+//! applies, and the missions played in those games earn multiplied rewards.
+//!
+//! The mutators of a game are added to the submitted mission data as
+//! modifiers so the multipliers still apply when the mission results are
+//! processed after a restart
+
+use super::game::{AttrMap, PlayerDataBuilder};
+use crate::{
+    config::{Config, Mutator},
+    http::models::mission::MissionModifier,
+};
+use chrono::{DateTime, Utc};
+
+/// Name of the mission modifier the mutators of a game are stored as
+pub const MUTATOR_MODIFIER: &str = "mutator";
+
+/// Game attribute listing the names of the active mutators
+const MUTATORS_ATTR: &str = "mutators";
+
+/// Finds the mutators that apply to games created at `now`
+pub fn active(now: DateTime<Utc>) -> Vec<&'static Mutator> {
+    Config::get()
+        .mutators
+        .iter()
+        .filter(|mutator| mutator.is_active(now))
+        .collect()
+}
+
+/// Finds a mutator by its `name`
+fn by_name(name: &str) -> Option<&'static Mutator> {
+    Config::get()
+        .mutators
+        .iter()
+        .find(|mutator| mutator.name == name)
+}
+
+/// Creates the game attributes advertising the `mutators` to the client
+pub fn attributes(mutators: &[&Mutator]) -> AttrMap {
+    let names = mutators
+        .iter()
+        .map(|mutator| mutator.name.as_str())
+        .collect::<Vec<_>>()
+        .join(",");
+
+    mutators
+        .iter()
+        .flat_map(|mutator| mutator.attributes.iter())
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .chain(std::iter::once((MUTATORS_ATTR.to_string(), names)))
+        .collect()
+}
+
+/// Replaces any mutator modifiers in the submitted `modifiers` with the
+/// mutators of the game, clients can't add mutators to their own missions
+pub fn apply_modifiers(modifiers: &mut Vec<MissionModifier>, mutators: &[String]) {
+    modifiers.retain(|modifier| modifier.name != MUTATOR_MODIFIER);
+    modifiers.extend(mutators.iter().map(|name| MissionModifier {
+        name: MUTATOR_MODIFIER.to_string(),
+        value: name.clone(),
+    }));
+}
+
+/// Adds the bonus rewards from the mutators in the `mission_modifiers`,
+/// must be computed after all the other rewards as the bonus is a
+/// percentage of the earned rewards.
+///
+/// The bonuses of multiple mutators are added together rather than
+/// compounding, each bonus is a percentage of the rewards earned before
+/// any mutator bonus (Two 150% mutators give a 200% total)
+pub fn compute_rewards(
+    mission_modifiers: &[MissionModifier],
+    data_builder: &mut PlayerDataBuilder,
+) {
+    let mutators = mission_modifiers
+        .iter()
+        .filter(|modifier| modifier.name == MUTATOR_MODIFIER)
+        .filter_map(|modifier| by_name(&modifier.value));
+
+    let base_xp = data_builder.xp_earned;
+    let base_currencies: Vec<_> = data_builder
+        .total_currency
+        .iter()
+        .map(|(currency, amount)| (*currency, *amount))
+        .collect();
+
+    for mutator in mutators {
+        let xp = bonus_amount(base_xp, mutator.xp_percent);
+        if xp > 0 {
+            data_builder.add_reward_xp(&mutator.name, xp);
+        }
+
+        for (currency, amount) in &base_currencies {
+            let amount = bonus_amount(*amount, mutator.currency_percent);
+            if amount > 0 {
+                data_builder.add_reward_currency(&mutator.name, *currency, amount);
+            }
+        }
+    }
+}
+
+/// Bonus added to the `amount` to reach the `percent` of the amount,
+/// rewards are never reduced by a percentage below 100
+fn bonus_amount(amount: u32, percent: u32) -> u32 {
+    let bonus = (amount as u64 * percent.saturating_sub(100) as u64) / 100;
+    bonus.min(u32::MAX as u64) as u32
+}
+
+#[cfg(test)]
+mod test {
+    use super::bonus_amount;
+
+    /// Tests the bonus reaches the percentage without reducing rewards
+    #[test]
+    fn test_bonus_amount() {
+        assert_eq!(bonus_amount(1000, 150), 500);
+        assert_eq!(bonus_amount(1000, 100), 0);
+        assert_eq!(bonus_amount(1000, 50), 0);
+        assert_eq!(bonus_amount(u32::MAX, 300), u32::MAX);
+    }
+}
@@ -0,0 +1,69 @@
+//! Notification inbox, notifications are stored so that events which
+//! happen while the user is offline aren't lost. Notifications are sent
+//! to the client as messages straight away when the user is online, any
+//! that weren't sent are delivered when the user next logs in
+
+use super::sessions::Sessions;
+use crate::{
+    blaze::{
+        components::messaging, models::messaging::NotifyMessage, packet::Packet, session::Session,
+    },
+    database::{
+        entity::{notifications::NotificationKind, users::UserId, Notification},
+        DbResult,
+    },
+};
+use sea_orm::ConnectionTrait;
+
+/// Stores a notification for the user with the provided `user_id`,
+/// the notification is sent immediately if the user is online
+pub async fn push<C>(
+    db: &C,
+    sessions: &Sessions,
+    user_id: UserId,
+    kind: NotificationKind,
+    message: String,
+) -> DbResult<()>
+where
+    C: ConnectionTrait + Send,
+{
+    let notification = Notification::create(db, user_id, kind, message).await?;
+
+    if let Some(session) = sessions.lookup_session(user_id) {
+        send(&session, user_id, &notification.message);
+        Notification::set_delivered(db, vec![notification.id]).await?;
+    }
+
+    Ok(())
+}
+
+/// Sends the notifications that haven't been delivered to the `session`
+/// of the user with the provided `user_id` that has just logged in
+pub async fn deliver_pending<C>(db: &C, session: &Session, user_id: UserId) -> DbResult<()>
+where
+    C: ConnectionTrait + Send,
+{
+    let pending = Notification::undelivered(db, user_id).await?;
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    for notification in &pending {
+        send(session, user_id, &notification.message);
+    }
+
+    let ids = pending
+        .into_iter()
+        .map(|notification| notification.id)
+        .collect();
+    Notification::set_delivered(db, ids).await
+}
+
+/// Sends the notification `message` to the `session`
+fn send(session: &Session, user_id: UserId, message: &str) {
+    session.notify_handle().notify(Packet::notify(
+        messaging::COMPONENT,
+        messaging::SEND_MESSAGE,
+        NotifyMessage { user_id, message },
+    ));
+}
@@ -0,0 +1,233 @@
+//! Drop-rate experiments that serve alternative pack rarity weights to a
+//! stable percentage of users, the outcomes of the packs opened by each
+//! group are recorded so they can be compared against the control group
+
+use super::feature_flags::rollout_bucket;
+use crate::{
+    config::{Config, PackExperiment, RarityWeights},
+    database::{
+        entity::{users::UserId, PackOpening},
+        DbResult,
+    },
+    definitions::{
+        items::{ItemName, ItemRarity},
+        packs::RewardCollection,
+    },
+};
+use sea_orm::ConnectionTrait;
+use serde::Serialize;
+
+/// Experiment group a user is placed in for a pack
+pub struct PackExperimentAssignment<'a> {
+    /// The experiment covering the pack
+    pub experiment: &'a PackExperiment,
+    /// Whether the user receives the experiment weights rather
+    /// than being part of the control group
+    pub treatment: bool,
+}
+
+impl<'a> PackExperimentAssignment<'a> {
+    /// Provides the rarity weights the user should receive, [None]
+    /// for the control group which uses the default weights
+    pub fn weights(&self) -> Option<&'a RarityWeights> {
+        self.treatment.then_some(&self.experiment.weights)
+    }
+}
+
+/// Finds the experiment group the user is placed in for the `pack`,
+/// [None] when the pack isn't covered by a running experiment
+pub fn assign(pack: &ItemName, user_id: UserId) -> Option<PackExperimentAssignment<'static>> {
+    find_assignment(&Config::get().pack_experiments, pack, user_id)
+}
+
+/// Finds the first running experiment from `experiments` covering the `pack`
+/// and places the user into a group, the same buckets as feature flags are
+/// used so the same users remain in the experiment as the percentage grows
+fn find_assignment<'a>(
+    experiments: &'a [PackExperiment],
+    pack: &ItemName,
+    user_id: UserId,
+) -> Option<PackExperimentAssignment<'a>> {
+    let experiment = experiments.iter().find(|experiment| {
+        experiment.enabled && (experiment.packs.is_empty() || experiment.packs.contains(pack))
+    })?;
+
+    let treatment = rollout_bucket(&experiment.name, user_id) < experiment.percent.min(100) as u32;
+
+    Some(PackExperimentAssignment {
+        experiment,
+        treatment,
+    })
+}
+
+/// Number of items of each rarity generated by pack openings
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RarityCounts {
+    /// Total number of items
+    pub items: u32,
+    pub common: u32,
+    pub uncommon: u32,
+    pub rare: u32,
+    pub ultra_rare: u32,
+}
+
+impl RarityCounts {
+    /// Counts the items of each rarity within the `rewards`
+    pub fn from_rewards(rewards: &RewardCollection) -> Self {
+        let mut counts = Self {
+            items: rewards.rewards.len() as u32,
+            ..Default::default()
+        };
+
+        for reward in &rewards.rewards {
+            match reward.definition.rarity {
+                Some(ItemRarity::Common) => counts.common += 1,
+                Some(ItemRarity::Uncommon) => counts.uncommon += 1,
+                Some(ItemRarity::Rare) => counts.rare += 1,
+                Some(ItemRarity::UltraRare) => counts.ultra_rare += 1,
+                Some(ItemRarity::Max) | None => {}
+            }
+        }
+
+        counts
+    }
+}
+
+/// Comparison of the groups for a configured experiment
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackExperimentReport {
+    /// The experiment configuration
+    pub experiment: &'static PackExperiment,
+    /// Outcomes for the users receiving the default weights
+    pub control: PackGroupStats,
+    /// Outcomes for the users receiving the experiment weights
+    pub treatment: PackGroupStats,
+}
+
+/// Outcomes of the packs opened by one group of an experiment
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackGroupStats {
+    /// Number of packs opened
+    pub openings: u32,
+    /// Total number of items of each rarity
+    pub totals: RarityCounts,
+    /// Average number of items of each rarity per opening
+    pub averages: RarityAverages,
+}
+
+/// Average number of items of each rarity per opening
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RarityAverages {
+    pub items: f32,
+    pub common: f32,
+    pub uncommon: f32,
+    pub rare: f32,
+    pub ultra_rare: f32,
+}
+
+impl PackGroupStats {
+    fn new(openings: u32, totals: RarityCounts) -> Self {
+        let average = |value: u32| {
+            if openings == 0 {
+                0.0
+            } else {
+                value as f32 / openings as f32
+            }
+        };
+
+        Self {
+            openings,
+            averages: RarityAverages {
+                items: average(totals.items),
+                common: average(totals.common),
+                uncommon: average(totals.uncommon),
+                rare: average(totals.rare),
+                ultra_rare: average(totals.ultra_rare),
+            },
+            totals,
+        }
+    }
+}
+
+/// Creates a report comparing the groups of each configured experiment
+pub async fn report<C>(db: &C) -> DbResult<Vec<PackExperimentReport>>
+where
+    C: ConnectionTrait + Send,
+{
+    let mut reports = Vec::new();
+
+    for experiment in &Config::get().pack_experiments {
+        let mut report = PackExperimentReport {
+            experiment,
+            control: PackGroupStats::default(),
+            treatment: PackGroupStats::default(),
+        };
+
+        for (treatment, openings, totals) in
+            PackOpening::experiment_totals(db, &experiment.name).await?
+        {
+            let stats = PackGroupStats::new(openings, totals);
+            if treatment {
+                report.treatment = stats;
+            } else {
+                report.control = stats;
+            }
+        }
+
+        reports.push(report);
+    }
+
+    Ok(reports)
+}
+
+#[cfg(test)]
+mod test {
+    use super::find_assignment;
+    use crate::config::{PackExperiment, RarityWeights};
+    use uuid::uuid;
+
+    fn experiment(percent: u8) -> PackExperiment {
+        PackExperiment {
+            name: "rareBoost".to_string(),
+            enabled: true,
+            percent,
+            packs: vec![uuid!("c5b3d9e6-7932-4579-ba8a-fd469ed43fda")],
+            weights: RarityWeights {
+                rare: Some(32),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Tests that only the covered packs are assigned and that the
+    /// percentage boundaries place users into the correct group
+    #[test]
+    fn test_assignment() {
+        let covered = uuid!("c5b3d9e6-7932-4579-ba8a-fd469ed43fda");
+        let other = uuid!("e4f4d32a-90c3-4f5c-9362-3bb5933706c7");
+
+        let none = [experiment(0)];
+        let all = [experiment(100)];
+
+        for user_id in 0..500 {
+            let assignment = find_assignment(&none, &covered, user_id).unwrap();
+            assert!(assignment.weights().is_none());
+
+            let assignment = find_assignment(&all, &covered, user_id).unwrap();
+            assert_eq!(
+                assignment.weights().and_then(|weights| weights.rare),
+                Some(32)
+            );
+
+            assert!(find_assignment(&all, &other, user_id).is_none());
+        }
+
+        let mut disabled = experiment(100);
+        disabled.enabled = false;
+        assert!(find_assignment(&[disabled], &covered, 1).is_none());
+    }
+}
@@ -0,0 +1,207 @@
+//! Login and account linking against a Pocket Relay (Mass Effect 3) server,
+//! allows players running both servers to use a single login.
+//!
+//! Credentials are verified using the Pocket Relay HTTP API, the Pocket Relay
+//! account is then linked to a local account through a user identity using
+//! the same provider as the database import so accounts that were already
+//! imported are reused
+
+use super::{
+    auth::{link_external_user, resolve_external_user, AuthProviderError, ExternalIdentity},
+    pocket_relay_import::PROVIDER_NAME,
+};
+use crate::{
+    config::{Config, PocketRelayConfig},
+    database::entity::User,
+};
+use anyhow::Context;
+use reqwest::{Client, StatusCode};
+use sea_orm::{DatabaseConnection, DbErr};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Header Pocket Relay reads the session token from
+const TOKEN_HEADER: &str = "X-Token";
+
+/// Errors that can occur while using a Pocket Relay account
+#[derive(Debug, Error)]
+pub enum PocketRelayError {
+    /// No Pocket Relay server is configured
+    #[error("Pocket Relay accounts are not enabled")]
+    Disabled,
+    /// The Pocket Relay login credentials were incorrect
+    #[error("Incorrect Pocket Relay email or password")]
+    InvalidCredentials,
+    /// The Pocket Relay account is linked to another user
+    #[error("Pocket Relay account is already linked to another account")]
+    LinkedToOther,
+    /// The user already has a linked Pocket Relay account
+    #[error("Account is already linked to a Pocket Relay account")]
+    AlreadyLinked,
+    /// An account already exists with the email of the Pocket Relay account
+    #[error(
+        "An account with this email already exists, login to it and link your Pocket Relay account"
+    )]
+    AccountExists,
+    /// Database error occurred
+    #[error(transparent)]
+    Database(#[from] DbErr),
+    /// Error from the Pocket Relay server or while creating the account
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+impl From<AuthProviderError> for PocketRelayError {
+    fn from(value: AuthProviderError) -> Self {
+        match value {
+            AuthProviderError::AccountNotFound | AuthProviderError::InvalidCredentials => {
+                Self::InvalidCredentials
+            }
+            AuthProviderError::AccountExists => Self::AccountExists,
+            AuthProviderError::LinkedToOther => Self::LinkedToOther,
+            AuthProviderError::AlreadyLinked => Self::AlreadyLinked,
+            AuthProviderError::LinkingUnsupported => Self::Disabled,
+            AuthProviderError::Database(err) => Self::Database(err),
+            AuthProviderError::Other(err) => Self::Other(err),
+        }
+    }
+}
+
+/// Account on the Pocket Relay server
+struct PocketRelayAccount {
+    /// ID of the Pocket Relay player
+    id: u32,
+    /// Email of the player
+    email: String,
+    /// Display name of the player
+    display_name: String,
+}
+
+/// Request to the Pocket Relay login endpoint
+#[derive(Serialize)]
+struct LoginRequest<'a> {
+    email: &'a str,
+    password: &'a str,
+}
+
+/// Response from the Pocket Relay login endpoint
+#[derive(Deserialize)]
+struct LoginResponse {
+    token: String,
+}
+
+/// Response from the Pocket Relay current user endpoint
+#[derive(Deserialize)]
+struct PlayerResponse {
+    id: u32,
+    email: String,
+    #[serde(alias = "displayName")]
+    display_name: String,
+}
+
+/// Service for logging in with Pocket Relay accounts
+pub struct PocketRelayService {
+    /// The Pocket Relay configuration, [None] when not configured
+    config: Option<&'static PocketRelayConfig>,
+    /// HTTP client for making requests to Pocket Relay
+    client: Client,
+}
+
+impl PocketRelayService {
+    pub fn new() -> Self {
+        Self {
+            config: Config::get().pocket_relay.as_ref(),
+            client: Client::new(),
+        }
+    }
+
+    /// Logs in with the Pocket Relay account for the `email` and `password`,
+    /// accounts that haven't been linked are imported as a new user. Pocket
+    /// Relay emails aren't verified so existing users with the same email
+    /// must link the account themselves
+    pub async fn login(
+        &self,
+        db: &DatabaseConnection,
+        email: &str,
+        password: &str,
+    ) -> Result<User, PocketRelayError> {
+        let account = self.authenticate(email, password).await?;
+
+        let identity = ExternalIdentity {
+            subject: account.id.to_string(),
+            email: account.email,
+            // Pocket Relay doesn't verify account emails
+            email_verified: false,
+            username: Some(account.display_name),
+        };
+
+        let user = resolve_external_user(db, PROVIDER_NAME, identity).await?;
+        Ok(user)
+    }
+
+    /// Links the Pocket Relay account for the `email` and `password`
+    /// to the existing `user`
+    pub async fn link(
+        &self,
+        db: &DatabaseConnection,
+        user: &User,
+        email: &str,
+        password: &str,
+    ) -> Result<(), PocketRelayError> {
+        let account = self.authenticate(email, password).await?;
+        link_external_user(db, user, PROVIDER_NAME, account.id.to_string()).await?;
+        Ok(())
+    }
+
+    /// Verifies the `email` and `password` with the Pocket Relay server
+    /// and loads the details of the account
+    async fn authenticate(
+        &self,
+        email: &str,
+        password: &str,
+    ) -> Result<PocketRelayAccount, PocketRelayError> {
+        let config = self.config.ok_or(PocketRelayError::Disabled)?;
+        let base_url = config.url.trim_end_matches('/');
+
+        let response = self
+            .client
+            .post(format!("{base_url}/api/auth/login"))
+            .json(&LoginRequest { email, password })
+            .send()
+            .await
+            .context("Failed to request Pocket Relay login")?;
+
+        // Pocket Relay responds with not found for unknown emails
+        if matches!(
+            response.status(),
+            StatusCode::BAD_REQUEST | StatusCode::UNAUTHORIZED | StatusCode::NOT_FOUND
+        ) {
+            return Err(PocketRelayError::InvalidCredentials);
+        }
+
+        let login: LoginResponse = response
+            .error_for_status()
+            .context("Pocket Relay login request failed")?
+            .json()
+            .await
+            .context("Failed to parse Pocket Relay login response")?;
+
+        let player: PlayerResponse = self
+            .client
+            .get(format!("{base_url}/api/users/self"))
+            .header(TOKEN_HEADER, login.token)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .context("Failed to request Pocket Relay account")?
+            .json()
+            .await
+            .context("Failed to parse Pocket Relay account response")?;
+
+        Ok(PocketRelayAccount {
+            id: player.id,
+            email: player.email,
+            display_name: player.display_name,
+        })
+    }
+}
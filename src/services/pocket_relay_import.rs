@@ -0,0 +1,194 @@
+//! Importer for accounts from a Pocket Relay (Mass Effect 3) server database,
+//! allows communities moving from ME3 to keep their existing logins.
+//!
+//! Pocket Relay accounts are linked to local accounts through a user identity
+//! so that players are only imported once. Pocket Relay stores passwords as
+//! argon2 hashes which are reused as-is for accounts created by the import
+
+use super::{
+    auth::{create_account, create_username, ExternalIdentity},
+    items,
+};
+use crate::{
+    config::PocketRelayImportConfig,
+    database::entity::{users::CreateUser, User, UserIdentity},
+    definitions::items::{ItemName, Items},
+};
+use anyhow::{anyhow, Context};
+use log::{debug, error, info};
+use sea_orm::{
+    ConnectionTrait, Database, DatabaseConnection, FromQueryResult, Statement, TransactionError,
+    TransactionTrait,
+};
+use uuid::uuid;
+
+/// Name of the identity provider imported accounts are linked with
+pub(crate) const PROVIDER_NAME: &str = "pocket_relay";
+
+/// LOYALTY PACK (ME3) item granted to players who have played ME3
+const LOYALTY_PACK: ItemName = uuid!("47088308-e623-494e-a436-cccfd7f4150f");
+
+/// Player from the Pocket Relay database
+#[derive(Debug, FromQueryResult)]
+struct PocketRelayPlayer {
+    id: u32,
+    email: String,
+    display_name: String,
+    /// Argon2 password hash, missing for accounts that were
+    /// created through Origin authentication
+    password: Option<String>,
+    /// Whether the player has any ME3 multiplayer data
+    has_data: bool,
+}
+
+/// Outcome of importing a single player
+enum ImportOutcome {
+    /// Player was already imported by a previous import
+    Skipped,
+    /// Player was linked to an account, `created` when the
+    /// account was created by the import
+    Linked { created: bool, loyalty_pack: bool },
+}
+
+/// Imports the players from the Pocket Relay database described by
+/// the `config`, players that fail to import are logged and skipped
+pub async fn run(db: &DatabaseConnection, config: &PocketRelayImportConfig) -> anyhow::Result<()> {
+    let source = Database::connect(format!("sqlite:{}?mode=ro", config.database_path))
+        .await
+        .context("Failed to open Pocket Relay database")?;
+
+    let players = PocketRelayPlayer::find_by_statement(Statement::from_string(
+        source.get_database_backend(),
+        "SELECT `p`.`id`, `p`.`email`, `p`.`display_name`, `p`.`password`, \
+        EXISTS(SELECT 1 FROM `player_data` `d` WHERE `d`.`player_id` = `p`.`id`) AS `has_data` \
+        FROM `players` `p` ORDER BY `p`.`id`",
+    ))
+    .all(&source)
+    .await
+    .context("Failed to read Pocket Relay players")?;
+
+    let (mut created, mut linked, mut skipped, mut loyalty_packs, mut failed) = (0, 0, 0, 0, 0);
+
+    for player in players {
+        let player_id = player.id;
+        match import_player(db, player, config.grant_loyalty_pack).await {
+            Ok(ImportOutcome::Skipped) => skipped += 1,
+            Ok(ImportOutcome::Linked {
+                created: was_created,
+                loyalty_pack,
+            }) => {
+                if was_created {
+                    created += 1;
+                } else {
+                    linked += 1;
+                }
+                if loyalty_pack {
+                    loyalty_packs += 1;
+                }
+            }
+            Err(err) => {
+                error!(
+                    "Failed to import Pocket Relay player {}: {:#}",
+                    player_id, err
+                );
+                failed += 1;
+            }
+        }
+    }
+
+    info!(
+        "Pocket Relay import complete: {} created, {} linked, {} already imported, \
+        {} loyalty pack(s) granted, {} failed",
+        created, linked, skipped, loyalty_packs, failed
+    );
+
+    Ok(())
+}
+
+/// Imports a single `player`, existing accounts with the same email are
+/// linked to the player otherwise a new account is created
+async fn import_player(
+    db: &DatabaseConnection,
+    player: PocketRelayPlayer,
+    grant_loyalty_pack: bool,
+) -> anyhow::Result<ImportOutcome> {
+    let subject = player.id.to_string();
+
+    if UserIdentity::find_user(db, PROVIDER_NAME, &subject)
+        .await?
+        .is_some()
+    {
+        return Ok(ImportOutcome::Skipped);
+    }
+
+    let (user, created) = match User::by_email(db, &player.email).await? {
+        Some(user) => (user, false),
+        None => {
+            // Origin accounts have no password to reuse, an empty password
+            // hash will never verify so they must reset their password
+            let password = player.password.clone().unwrap_or_default();
+            let identity = ExternalIdentity {
+                subject: subject.clone(),
+                email: player.email.clone(),
+                email_verified: false,
+                username: Some(player.display_name.clone()),
+            };
+            let username = create_username(db, &identity).await?;
+
+            debug!(
+                "Creating account for Pocket Relay player {} ({})",
+                player.id, username
+            );
+
+            let user = create_account(
+                db,
+                CreateUser {
+                    email: player.email,
+                    username,
+                    password,
+                },
+            )
+            .await?;
+
+            (user, true)
+        }
+    };
+
+    let loyalty_pack = grant_loyalty_pack && player.has_data;
+
+    db.transaction(|db| {
+        Box::pin(async move {
+            UserIdentity::create(db, &user, PROVIDER_NAME.to_string(), subject).await?;
+
+            if loyalty_pack {
+                grant_loyalty_pack_item(db, &user).await?;
+            }
+
+            Ok::<_, anyhow::Error>(())
+        })
+    })
+    .await
+    .map_err(|err| match err {
+        TransactionError::Connection(err) => err.into(),
+        TransactionError::Transaction(err) => err,
+    })?;
+
+    Ok(ImportOutcome::Linked {
+        created,
+        loyalty_pack,
+    })
+}
+
+/// Grants the ME3 loyalty pack to the `user` through the shared item
+/// grant pipeline, users that already hold the maximum are left as is
+async fn grant_loyalty_pack_item<C>(db: &C, user: &User) -> anyhow::Result<()>
+where
+    C: ConnectionTrait + Send,
+{
+    let definition = Items::get()
+        .by_name(&LOYALTY_PACK)
+        .ok_or(anyhow!("Missing loyalty pack definition"))?;
+
+    items::grant_item(db, user, definition, 1).await?;
+    Ok(())
+}
@@ -0,0 +1,88 @@
+//! Service producing anonymized aggregate statistics about the server for
+//! community server landing pages. Statistics are cached to prevent public
+//! requests from repeatedly querying the database
+
+use super::sessions::Sessions;
+use crate::{
+    database::{
+        entity::{match_records, MatchRecord, User},
+        DbResult,
+    },
+    utils::clock,
+};
+use chrono::{TimeZone, Utc};
+use sea_orm::DatabaseConnection;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Duration the statistics are cached for before being recomputed
+const CACHE_DURATION: Duration = Duration::from_secs(60);
+
+/// Aggregate statistics about the server
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PublicStats {
+    /// Total number of registered users
+    pub total_users: u64,
+    /// Number of matches finished since the start of the current UTC day
+    pub matches_today: u64,
+    /// The map with the most finished matches
+    pub most_played_map: Option<String>,
+    /// The enemy type with the most finished matches
+    pub most_played_enemy: Option<String>,
+    /// Number of players currently online
+    pub online_count: usize,
+}
+
+/// Cache for the public statistics
+#[derive(Default)]
+pub struct PublicStatsService {
+    /// The cached statistics along with when they were computed, uses an
+    /// async mutex so concurrent requests wait for a single computation
+    cached: Mutex<Option<(Instant, PublicStats)>>,
+}
+
+impl PublicStatsService {
+    /// Creates a new empty statistics cache
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Obtains the current statistics, recomputing them if
+    /// the cached statistics have expired
+    pub async fn get(&self, db: &DatabaseConnection, sessions: &Sessions) -> DbResult<PublicStats> {
+        let cached = &mut *self.cached.lock().await;
+
+        if let Some((created, stats)) = cached {
+            if created.elapsed() < CACHE_DURATION {
+                return Ok(stats.clone());
+            }
+        }
+
+        let stats = Self::compute(db, sessions).await?;
+        *cached = Some((Instant::now(), stats.clone()));
+
+        Ok(stats)
+    }
+
+    /// Computes the statistics from the database and active sessions
+    async fn compute(db: &DatabaseConnection, sessions: &Sessions) -> DbResult<PublicStats> {
+        let now = clock::now();
+        let start_of_day = Utc.from_utc_datetime(&now.date_naive().and_hms_opt(0, 0, 0).unwrap());
+
+        let total_users = User::count(db).await?;
+        let matches_today = MatchRecord::count_since(db, start_of_day).await?;
+        let most_played_map = MatchRecord::most_played(db, match_records::Column::Map).await?;
+        let most_played_enemy =
+            MatchRecord::most_played(db, match_records::Column::EnemyType).await?;
+
+        Ok(PublicStats {
+            total_users,
+            matches_today,
+            most_played_map,
+            most_played_enemy,
+            online_count: sessions.count(),
+        })
+    }
+}
@@ -0,0 +1,163 @@
+//! Central source of the random generators used for generation (mission
+//! rotation, pack opening, strike teams). Each purpose has its own stream
+//! which is seeded once, generators handed out are seeded from the stream
+//! rather than from the operating system entropy so busy servers don't
+//! make an entropy syscall for every generation.
+//!
+//! When a seed is configured the streams are seeded from it making the
+//! generation reproducible for debugging
+
+use crate::config::Config;
+use parking_lot::Mutex;
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+use serde::Serialize;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    OnceLock,
+};
+
+/// Purpose that random generation is used for, each purpose is
+/// given its own stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RandomPurpose {
+    /// Creating the strike team missions for the rotation
+    MissionRotation,
+    /// Generating the contents of opened packs
+    PackOpening,
+    /// Creating new strike teams
+    StrikeTeamCreation,
+    /// Rolling the outcome of strike team missions
+    StrikeTeamOutcome,
+    /// Picking the traits gained or lost by strike teams
+    StrikeTeamTraits,
+}
+
+impl RandomPurpose {
+    /// All the purposes in the order of their streams
+    const ALL: [RandomPurpose; 5] = [
+        RandomPurpose::MissionRotation,
+        RandomPurpose::PackOpening,
+        RandomPurpose::StrikeTeamCreation,
+        RandomPurpose::StrikeTeamOutcome,
+        RandomPurpose::StrikeTeamTraits,
+    ];
+}
+
+/// Stream of randomness for a single purpose
+struct RandomStream {
+    /// Generator the handed out generators are seeded from
+    rng: Mutex<StdRng>,
+    /// Number of generators and seeds taken from the stream
+    generated: AtomicU64,
+}
+
+/// Service handing out the random generators for each purpose
+pub struct RandomService {
+    /// The stream for each purpose, indexed by the purpose
+    streams: [RandomStream; RandomPurpose::ALL.len()],
+}
+
+/// Number of generators and seeds taken for a purpose
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RandomStreamMetrics {
+    /// The purpose of the stream
+    pub purpose: RandomPurpose,
+    /// Number of generators and seeds taken from the stream
+    pub generated: u64,
+}
+
+/// Static storage for the random service
+static STORE: OnceLock<RandomService> = OnceLock::new();
+
+impl RandomService {
+    /// Gets a static reference to the global random service, the streams
+    /// are seeded from the configured seed when one is set
+    pub fn get() -> &'static RandomService {
+        STORE.get_or_init(|| Self::new(Config::get().random.seed))
+    }
+
+    /// Creates the streams for each purpose, streams are seeded from
+    /// entropy when no `seed` is provided
+    fn new(seed: Option<u64>) -> Self {
+        let streams = RandomPurpose::ALL.map(|purpose| {
+            let rng = match seed {
+                // Each purpose gets a distinct stream from the same seed
+                Some(seed) => StdRng::seed_from_u64(seed.wrapping_add(purpose as u64)),
+                None => StdRng::from_entropy(),
+            };
+
+            RandomStream {
+                rng: Mutex::new(rng),
+                generated: AtomicU64::new(0),
+            }
+        });
+
+        Self { streams }
+    }
+
+    /// Gets the stream for the provided `purpose`, counting the generation
+    fn stream(&self, purpose: RandomPurpose) -> &RandomStream {
+        let stream = &self.streams[purpose as usize];
+        stream.generated.fetch_add(1, Ordering::Relaxed);
+        stream
+    }
+
+    /// Creates a generator for the provided `purpose` seeded from its stream
+    pub fn rng(&self, purpose: RandomPurpose) -> StdRng {
+        let mut seed = <StdRng as SeedableRng>::Seed::default();
+        self.stream(purpose).rng.lock().fill_bytes(&mut seed);
+        StdRng::from_seed(seed)
+    }
+
+    /// Takes a seed from the stream for the provided `purpose`, used when
+    /// the seed is recorded so the generation can be replayed
+    pub fn seed(&self, purpose: RandomPurpose) -> u64 {
+        self.stream(purpose).rng.lock().next_u64()
+    }
+
+    /// Obtains the number of generators and seeds taken for each purpose
+    pub fn metrics(&self) -> Vec<RandomStreamMetrics> {
+        RandomPurpose::ALL
+            .iter()
+            .zip(&self.streams)
+            .map(|(purpose, stream)| RandomStreamMetrics {
+                purpose: *purpose,
+                generated: stream.generated.load(Ordering::Relaxed),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{RandomPurpose, RandomService};
+    use rand::RngCore;
+
+    /// Tests that seeded services hand out the same generators, that each
+    /// purpose has its own stream and that generations are counted
+    #[test]
+    fn test_seeded_streams() {
+        let first = RandomService::new(Some(1));
+        let second = RandomService::new(Some(1));
+
+        let value = first.rng(RandomPurpose::PackOpening).next_u64();
+        assert_eq!(value, second.rng(RandomPurpose::PackOpening).next_u64());
+        assert_ne!(
+            first.seed(RandomPurpose::MissionRotation),
+            first.seed(RandomPurpose::StrikeTeamCreation)
+        );
+
+        // Generators are taken from the stream rather than repeating
+        assert_ne!(value, first.rng(RandomPurpose::PackOpening).next_u64());
+
+        let metrics = first.metrics();
+        assert_eq!(metrics.len(), RandomPurpose::ALL.len());
+        assert_eq!(metrics[RandomPurpose::PackOpening as usize].generated, 2);
+        assert_eq!(
+            metrics[RandomPurpose::StrikeTeamTraits as usize].generated,
+            0
+        );
+    }
+}
@@ -0,0 +1,107 @@
+//! Lobby ready check, players mark themselves ready through a player
+//! attribute and the host can only start the mission once all the other
+//! players are ready (or the ready timeout has passed). Hosts starting
+//! the mission while other players are still loading desyncs their game.
+//!
+//! Whether everyone is ready is advertised to the players as a game
+//! attribute so the host client knows when the mission can be started.
+//! The check is enforced when the host moves the game into the in game
+//! state over Blaze, the ready check is disabled by default as it needs
+//! a client plugin that sets the ready attribute
+
+use super::game::AttrMap;
+use crate::{config::Config, utils::clock};
+use chrono::{DateTime, Duration, Utc};
+
+/// Player attribute set to [READY_VALUE] when the player is ready
+pub const READY_ATTR: &str = "ready";
+
+/// Value of the [READY_ATTR] attribute for ready players
+const READY_VALUE: &str = "1";
+
+/// Value of the [READY_ATTR] attribute for players that aren't ready
+const NOT_READY_VALUE: &str = "0";
+
+/// Game attribute set to [READY_VALUE] when all the players are ready
+const ALL_READY_ATTR: &str = "allReady";
+
+/// Checks whether the player `attributes` mark the player as ready
+pub fn is_ready(attributes: &AttrMap) -> bool {
+    attributes
+        .iter()
+        .any(|(key, value)| key == READY_ATTR && value == READY_VALUE)
+}
+
+/// Checks whether the players with the provided `attributes` are all
+/// ready, the host should be excluded as they start the mission themselves
+pub fn all_ready<'a>(mut attributes: impl Iterator<Item = &'a AttrMap>) -> bool {
+    attributes.all(is_ready)
+}
+
+/// Checks whether the ready check started at `started` has timed out
+/// allowing the host to start without everyone being ready
+pub fn timed_out(started: DateTime<Utc>) -> bool {
+    let config = &Config::get().ready_check;
+    !config.enabled || clock::now() - started >= Duration::seconds(config.timeout_secs as i64)
+}
+
+/// Player attributes clearing the ready state of a player
+pub fn not_ready_attributes() -> AttrMap {
+    [(READY_ATTR.to_string(), NOT_READY_VALUE.to_string())]
+        .into_iter()
+        .collect()
+}
+
+/// Creates the game attributes for the `all_ready` state, [None] when the
+/// current game `attributes` already advertise the state
+pub fn all_ready_attributes(attributes: &AttrMap, all_ready: bool) -> Option<AttrMap> {
+    let value = if all_ready {
+        READY_VALUE
+    } else {
+        NOT_READY_VALUE
+    };
+
+    let current = attributes
+        .iter()
+        .find(|(key, _)| *key == ALL_READY_ATTR)
+        .map(|(_, value)| value.as_str());
+
+    if current == Some(value) {
+        return None;
+    }
+
+    Some(
+        [(ALL_READY_ATTR.to_string(), value.to_string())]
+            .into_iter()
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::{all_ready, all_ready_attributes, not_ready_attributes, READY_ATTR};
+    use crate::services::game::AttrMap;
+
+    fn ready() -> AttrMap {
+        [(READY_ATTR.to_string(), "1".to_string())]
+            .into_iter()
+            .collect()
+    }
+
+    /// Tests that every player must be ready
+    #[test]
+    fn test_all_ready() {
+        assert!(all_ready(std::iter::empty()));
+        assert!(all_ready([&ready(), &ready()].into_iter()));
+        assert!(!all_ready([&ready(), &not_ready_attributes()].into_iter()));
+        assert!(!all_ready([&AttrMap::new()].into_iter()));
+    }
+
+    /// Tests the game attribute is only changed when the state changes
+    #[test]
+    fn test_all_ready_attributes() {
+        let attributes = all_ready_attributes(&AttrMap::new(), true).unwrap();
+        assert!(all_ready_attributes(&attributes, true).is_none());
+        assert!(all_ready_attributes(&attributes, false).is_some());
+    }
+}
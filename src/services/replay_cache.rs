@@ -0,0 +1,73 @@
+//! Cache of the nonces used by signed client requests, nonces are kept
+//! until the request timestamp falls outside of the allowed window so
+//! that captured requests can't be submitted again. Nonces are stored
+//! for each user so users can't reject the requests of other users by
+//! submitting their nonces first
+
+use crate::database::entity::users::UserId;
+use parking_lot::Mutex;
+use std::{collections::HashMap, mem::size_of};
+
+/// Key of a used nonce, the user that used the nonce and the nonce
+type NonceKey = (UserId, String);
+
+/// Collection of recently used request nonces
+#[derive(Default)]
+pub struct ReplayCache {
+    /// Used nonces mapped to the unix timestamp in seconds they expire at
+    nonces: Mutex<HashMap<NonceKey, i64>>,
+}
+
+impl ReplayCache {
+    /// Approximate size of a stored nonce, assumes nonces are half
+    /// of the maximum allowed length on average
+    pub const ENTRY_SIZE: usize = size_of::<(NonceKey, i64)>() + 32;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of nonces currently stored
+    pub fn count(&self) -> usize {
+        self.nonces.lock().len()
+    }
+
+    /// Records the `nonce` as used by the user with the provided `user_id`
+    /// until `expires_at`, responds with false if the user has already
+    /// used the nonce
+    pub fn insert(&self, user_id: UserId, nonce: &str, expires_at: i64, now: i64) -> bool {
+        let nonces = &mut *self.nonces.lock();
+
+        // Remove nonces whose requests would now be rejected as expired
+        nonces.retain(|_, expiry| *expiry > now);
+
+        let key = (user_id, nonce.to_string());
+        if nonces.contains_key(&key) {
+            return false;
+        }
+
+        nonces.insert(key, expires_at);
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ReplayCache;
+
+    /// Tests that nonces are rejected until they expire
+    #[test]
+    fn test_replayed_nonce() {
+        let cache = ReplayCache::new();
+
+        assert!(cache.insert(1, "nonce", 100, 0));
+        assert!(!cache.insert(1, "nonce", 100, 50));
+        assert!(cache.insert(1, "other", 100, 50));
+
+        // Nonces used by other users don't affect the user
+        assert!(cache.insert(2, "nonce", 100, 50));
+
+        // Expired nonces are removed and can be used again
+        assert!(cache.insert(1, "nonce", 200, 100));
+    }
+}
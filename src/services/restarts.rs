@@ -0,0 +1,197 @@
+//! Service for operator scheduled server restarts, players are warned ahead
+//! of each restart and new matchmaking is blocked close to the restart so
+//! that as few missions as possible are interrupted.
+//!
+//! The server exits cleanly at the restart time, a process supervisor
+//! (systemd, docker, etc) is expected to start the server again
+
+use crate::{
+    blaze::{components, models::messaging::NotifyMessage, packet::Packet},
+    config::{Config, RestartsConfig},
+    services::{
+        crash_reports::{self, CrashContext},
+        game_manager::GameManager,
+        sessions::Sessions,
+    },
+    utils::clock,
+};
+use chrono::{DateTime, Days, Duration as ChronoDuration, NaiveTime, TimeZone, Utc};
+use log::{debug, info, warn};
+use parking_lot::Mutex;
+use std::{sync::Arc, time::Duration};
+use tokio::{
+    sync::Notify,
+    time::{sleep, Instant},
+};
+
+/// Interval between checks for missions finishing when a restart is
+/// waiting on missions in progress
+const MISSION_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Schedules the configured restarts and signals when the server
+/// should shut down
+pub struct RestartScheduler {
+    /// Time of the next scheduled restart
+    next_restart: Mutex<Option<DateTime<Utc>>>,
+    /// Notified when the server should shut down to restart
+    shutdown: Notify,
+}
+
+impl RestartScheduler {
+    pub fn new() -> Self {
+        Self {
+            next_restart: Mutex::new(None),
+            shutdown: Notify::new(),
+        }
+    }
+
+    /// Starts the scheduler in a background tokio task if restarts
+    /// are enabled
+    pub fn start(self: &Arc<Self>, sessions: Arc<Sessions>, game_manager: Arc<GameManager>) {
+        let config = &Config::get().restarts;
+        if !config.enabled || config.times.is_empty() {
+            debug!("Scheduled restarts are disabled");
+            return;
+        }
+
+        let scheduler = self.clone();
+        let context = CrashContext::new("restarts");
+        tokio::spawn(crash_reports::scope(context, async move {
+            scheduler.run(config, &sessions, &game_manager).await;
+        }));
+    }
+
+    /// Whether new matchmaking should be blocked because the
+    /// next restart is close
+    pub fn blocks_matchmaking(&self) -> bool {
+        let Some(restart) = *self.next_restart.lock() else {
+            return false;
+        };
+
+        let block = Config::get().restarts.block_matchmaking_minutes;
+        clock::now() + ChronoDuration::minutes(block as i64) >= restart
+    }
+
+    /// Waits until the server should shut down for a restart
+    pub async fn wait_for_restart(&self) {
+        self.shutdown.notified().await;
+    }
+
+    /// Waits for the next restart time warning players along the way
+    /// then notifies the server to shut down
+    async fn run(&self, config: &RestartsConfig, sessions: &Sessions, game_manager: &GameManager) {
+        let Some(restart) = next_restart_time(&config.times, clock::now()) else {
+            return;
+        };
+
+        *self.next_restart.lock() = Some(restart);
+        info!("Next scheduled restart at {}", restart);
+
+        let mut warning_minutes = config.warning_minutes.clone();
+        warning_minutes.sort_unstable_by(|a, b| b.cmp(a));
+        warning_minutes.dedup();
+
+        for minutes in warning_minutes {
+            let warn_at = restart - ChronoDuration::minutes(minutes as i64);
+
+            // Warnings that have already passed are skipped
+            if warn_at < clock::now() {
+                continue;
+            }
+
+            sleep(duration_until(warn_at)).await;
+            broadcast(
+                sessions,
+                &format!("The server will restart in {} minute(s)", minutes),
+            );
+        }
+
+        sleep(duration_until(restart)).await;
+
+        // Give missions in progress a chance to finish before restarting
+        let deadline = Instant::now() + Duration::from_secs(config.max_mission_delay_minutes * 60);
+        loop {
+            let in_mission = game_manager.count_in_mission().await;
+            if in_mission == 0 {
+                break;
+            }
+
+            if Instant::now() >= deadline {
+                warn!(
+                    "Restarting with {} mission(s) still in progress",
+                    in_mission
+                );
+                break;
+            }
+
+            debug!("Delaying restart for {} mission(s) in progress", in_mission);
+            sleep(MISSION_POLL_INTERVAL).await;
+        }
+
+        broadcast(sessions, "The server is restarting now");
+        info!("Shutting down for scheduled restart");
+        self.shutdown.notify_one();
+    }
+}
+
+/// Sends the `message` to every connected session
+pub(crate) fn broadcast(sessions: &Sessions, message: &str) {
+    for session in sessions.all() {
+        let user_id = session.data.lock().user.id;
+        session.notify_handle().notify(Packet::notify(
+            components::messaging::COMPONENT,
+            components::messaging::SEND_MESSAGE,
+            NotifyMessage { user_id, message },
+        ));
+    }
+}
+
+/// Time remaining until `time`, zero if the time has passed
+fn duration_until(time: DateTime<Utc>) -> Duration {
+    (time - clock::now()).to_std().unwrap_or_default()
+}
+
+/// Finds the first of the restart `times` (UTC) after `now`
+fn next_restart_time(times: &[NaiveTime], now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    times
+        .iter()
+        .filter_map(|time| {
+            let today = Utc.from_utc_datetime(&now.date_naive().and_time(*time));
+            if today > now {
+                Some(today)
+            } else {
+                today.checked_add_days(Days::new(1))
+            }
+        })
+        .min()
+}
+
+#[cfg(test)]
+mod test {
+    use super::next_restart_time;
+    use chrono::{NaiveTime, TimeZone, Utc};
+
+    /// Tests that the closest restart time after now is chosen,
+    /// wrapping to the next day when all times have passed
+    #[test]
+    fn test_next_restart_time() {
+        let times = [
+            NaiveTime::from_hms_opt(4, 0, 0).unwrap(),
+            NaiveTime::from_hms_opt(16, 0, 0).unwrap(),
+        ];
+
+        let now = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        assert_eq!(
+            next_restart_time(&times, now),
+            Some(Utc.with_ymd_and_hms(2024, 1, 1, 16, 0, 0).unwrap())
+        );
+
+        let now = Utc.with_ymd_and_hms(2024, 1, 1, 18, 0, 0).unwrap();
+        assert_eq!(
+            next_restart_time(&times, now),
+            Some(Utc.with_ymd_and_hms(2024, 1, 2, 4, 0, 0).unwrap())
+        );
+
+        assert_eq!(next_restart_time(&[], now), None);
+    }
+}
@@ -0,0 +1,399 @@
+//! Reward engine shared by every system that grants rewards (store
+//! purchases, packs, item effects, challenges, strike teams and the
+//! welcome kit) so that rewards are granted the same way everywhere.
+//!
+//! Systems describe what they grant as a [RewardBundle], the engine grants
+//! items through the [item grant pipeline](super::items) converting the
+//! items that can't be granted into currency, caps currencies to their
+//! maximum balance recording them in the currency ledger, converts
+//! duplicate character items, levels up the characters earning XP, and
+//! unlocks entitlements all within a single transaction. The granted
+//! rewards are added to the [ActivityResult] returned to the client and
+//! published as a server event, activities for the rewards (such as
+//! characters leveling up) are processed once the rewards are stored
+
+use super::{
+    activity::{ActivityEvent, ActivityName, ActivityResult, ActivityService},
+    items,
+    server_events::{ServerEvent, ServerEvents},
+};
+use crate::{
+    config::Config,
+    database::entity::{
+        currency::CurrencyType, currency_ledger::CurrencyLedgerReason, Character, Currency,
+        PlayerTitle, UnlockedStrikeTeamFlair, User,
+    },
+    definitions::{
+        challenges::CurrencyReward,
+        characters::{
+            duplicate_conversion, AcquiredCharacter, DuplicateCharacter, DuplicateConversion,
+        },
+        classes::Classes,
+        items::ItemDefinition,
+        level_tables::LevelTables,
+        packs::{ItemReward, RewardCollection},
+        strike_team_flair::StrikeTeamFlairName,
+        titles::PlayerTitleName,
+    },
+};
+use sea_orm::{ConnectionTrait, TransactionTrait};
+use std::{future::Future, pin::Pin};
+
+/// Entitlements that can be granted by rewards
+#[derive(Debug, Clone)]
+pub enum Entitlement {
+    /// Strike team flair to unlock
+    StrikeTeamFlair(StrikeTeamFlairName),
+    /// Player title to unlock
+    PlayerTitle(PlayerTitleName),
+}
+
+/// XP earned by a character
+pub struct XpReward {
+    /// The character earning the XP
+    pub character: Character,
+    /// Amount of XP earned
+    pub amount: u32,
+}
+
+/// Collection of rewards that are granted together
+#[derive(Default)]
+pub struct RewardBundle {
+    /// Items to add to the inventory
+    pub items: RewardCollection<'static>,
+    /// Currencies to add to the balances
+    pub currencies: Vec<CurrencyReward>,
+    /// XP earned by each character
+    pub xp: Vec<XpReward>,
+    /// Entitlements to unlock
+    pub entitlements: Vec<Entitlement>,
+}
+
+impl RewardBundle {
+    /// Adds `stack_size` of the item with the provided `definition`
+    pub fn add_item(&mut self, definition: &'static ItemDefinition, stack_size: u32) {
+        self.items.add_reward(definition, stack_size);
+    }
+
+    /// Adds an `amount` of the `ty` currency
+    pub fn add_currency(&mut self, ty: CurrencyType, amount: u32) {
+        match self.currencies.iter_mut().find(|value| value.name == ty) {
+            Some(existing) => existing.value = existing.value.saturating_add(amount),
+            None => self.currencies.push(CurrencyReward {
+                name: ty,
+                value: amount,
+            }),
+        }
+    }
+
+    /// Adds an `amount` of XP earned by the `character`
+    pub fn add_xp(&mut self, character: Character, amount: u32) {
+        add_xp(&mut self.xp, character, amount);
+    }
+
+    /// Adds an `entitlement` to unlock
+    pub fn add_entitlement(&mut self, entitlement: Entitlement) {
+        self.entitlements.push(entitlement);
+    }
+
+    /// Whether the bundle doesn't grant anything
+    pub fn is_empty(&self) -> bool {
+        self.items.rewards.is_empty()
+            && self.currencies.is_empty()
+            && self.xp.is_empty()
+            && self.entitlements.is_empty()
+    }
+}
+
+/// Rewards that were actually granted by the [RewardEngine], item amounts
+/// are limited by the item capacities and the currencies include items
+/// converted into currency and are limited to the currency maximums
+#[derive(Default)]
+pub struct GrantedRewards {
+    /// Items that were added along with the amount of each item added
+    pub items: Vec<ItemReward<'static>>,
+    /// Currencies that were granted
+    pub currencies: Vec<CurrencyReward>,
+}
+
+/// Adds an `amount` of XP earned by the `character` to the `xp` rewards,
+/// combined with any XP the character is already earning so that the
+/// character is only updated once
+fn add_xp(xp: &mut Vec<XpReward>, character: Character, amount: u32) {
+    match xp
+        .iter_mut()
+        .find(|existing| existing.character.id == character.id)
+    {
+        Some(existing) => existing.amount = existing.amount.saturating_add(amount),
+        None => xp.push(XpReward { character, amount }),
+    }
+}
+
+/// Engine granting [RewardBundle]s
+pub struct RewardEngine;
+
+impl RewardEngine {
+    /// Grants the `bundle` to the `user` adding the granted rewards to the
+    /// `result`, currency changes are recorded in the ledger using `reason`.
+    /// Responds with the rewards that were actually granted
+    pub async fn grant<C>(
+        db: &C,
+        user: &User,
+        bundle: RewardBundle,
+        reason: CurrencyLedgerReason,
+        result: &mut ActivityResult,
+    ) -> anyhow::Result<GrantedRewards>
+    where
+        C: ConnectionTrait + TransactionTrait + Send,
+    {
+        if bundle.is_empty() {
+            return Ok(GrantedRewards::default());
+        }
+
+        let RewardBundle {
+            items,
+            mut currencies,
+            mut xp,
+            entitlements,
+        } = bundle;
+
+        let txn = db.begin().await?;
+
+        let (added, overflow) =
+            Self::grant_items(&txn, user, items, &mut currencies, &mut xp, result).await?;
+
+        // Convert items that couldn't be granted into currency
+        if let Some(currency) = items::overflow_currency(overflow) {
+            currencies.push(currency);
+        }
+
+        let mut granted_currencies = Vec::with_capacity(currencies.len());
+        for currency in currencies {
+            let amount = Currency::earn(&txn, user, currency.name, currency.value, reason).await?;
+            if amount == 0 {
+                continue;
+            }
+
+            // Server only currencies aren't included in the result
+            if CurrencyType::CLIENT.contains(&currency.name) {
+                result.add_currency(user, currency.name, amount);
+            }
+
+            granted_currencies.push(CurrencyReward {
+                name: currency.name,
+                value: amount,
+            });
+        }
+
+        let xp_earned = xp
+            .iter()
+            .fold(0u32, |total, xp| total.saturating_add(xp.amount));
+
+        let mut activities = Vec::new();
+        for xp in xp {
+            if let Some(event) = Self::grant_xp(&txn, xp, result).await? {
+                activities.push(event);
+            }
+        }
+
+        for entitlement in entitlements {
+            match entitlement {
+                Entitlement::StrikeTeamFlair(name) => {
+                    UnlockedStrikeTeamFlair::unlock(&txn, user, name).await?
+                }
+                Entitlement::PlayerTitle(name) => PlayerTitle::unlock(&txn, user, name).await?,
+            }
+        }
+
+        txn.commit().await?;
+
+        ServerEvents::get().publish(ServerEvent::RewardsGranted {
+            user_id: user.id,
+            reason,
+            items: added.len(),
+            currencies: granted_currencies.clone(),
+            xp: xp_earned,
+        });
+
+        // Activities may complete challenges which grant their rewards
+        // through the engine, the processing is boxed to allow the recursion
+        for event in activities {
+            let process: Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send + '_>> = Box::pin(
+                ActivityService::process_event_inner(db, user, event, result),
+            );
+            process.await?;
+        }
+
+        Ok(GrantedRewards {
+            items: added,
+            currencies: granted_currencies,
+        })
+    }
+
+    /// Adds the `items` to the user inventory through the item grant
+    /// pipeline, responds with the items and amounts that were added
+    /// and the number of items that couldn't be granted. Duplicate character
+    /// items are converted into XP for the character or into `currencies`
+    async fn grant_items<C>(
+        db: &C,
+        user: &User,
+        items: RewardCollection<'static>,
+        currencies: &mut Vec<CurrencyReward>,
+        xp: &mut Vec<XpReward>,
+        result: &mut ActivityResult,
+    ) -> anyhow::Result<(Vec<ItemReward<'static>>, u32)>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let mut added = Vec::with_capacity(items.rewards.len());
+        let mut overflow: u32 = 0;
+
+        for reward in items.rewards {
+            let ItemReward {
+                definition,
+                stack_size,
+            } = reward;
+
+            let granted = items::grant_item(db, user, definition, stack_size).await?;
+            overflow = overflow.saturating_add(granted.overflow);
+
+            let Some(item) = granted.item else {
+                continue;
+            };
+
+            result.add_item(item, granted.granted, definition);
+            added.push(ItemReward {
+                definition,
+                stack_size: granted.granted,
+            });
+
+            if let Some(AcquiredCharacter::Duplicate(character)) = granted.character {
+                let class_name = character.class_name;
+                let conversion = Self::convert_duplicate(character, currencies, xp);
+                result.duplicate_characters.push(DuplicateCharacter {
+                    class_name,
+                    conversion,
+                });
+            }
+        }
+
+        Ok((added, overflow))
+    }
+
+    /// Converts a duplicate item for the unlocked `character` into XP for
+    /// the character which is added to the `xp` rewards, characters that
+    /// can't level up any further are given currency which is added to the
+    /// `currencies` to grant
+    fn convert_duplicate(
+        character: Character,
+        currencies: &mut Vec<CurrencyReward>,
+        xp: &mut Vec<XpReward>,
+    ) -> DuplicateConversion {
+        let level_table = Classes::get()
+            .by_name(&character.class_name)
+            .and_then(|class| LevelTables::get().by_name(&class.level_name));
+
+        let conversion =
+            duplicate_conversion(&Config::get().characters, level_table, character.level);
+
+        match &conversion {
+            DuplicateConversion::Xp { amount } => add_xp(xp, character, *amount),
+            DuplicateConversion::Currency { name, amount } => {
+                if *amount > 0 {
+                    currencies.push(CurrencyReward {
+                        name: *name,
+                        value: *amount,
+                    });
+                }
+            }
+        }
+
+        conversion
+    }
+
+    /// Adds the earned `xp` to the character leveling up the character, the
+    /// `result` only describes the first character to earn XP. Responds
+    /// with the activity for the character leveling up
+    async fn grant_xp<C>(
+        db: &C,
+        xp: XpReward,
+        result: &mut ActivityResult,
+    ) -> anyhow::Result<Option<ActivityEvent>>
+    where
+        C: ConnectionTrait + Send,
+    {
+        let XpReward { character, amount } = xp;
+
+        let level_table = Classes::get()
+            .by_name(&character.class_name)
+            .and_then(|class| LevelTables::get().by_name(&class.level_name));
+
+        let (new_xp, level) = match level_table {
+            Some(level_table) => {
+                level_table.compute_leveling(character.xp, character.level, amount)
+            }
+            None => (character.xp, character.level),
+        };
+
+        if !result
+            .character_class_name
+            .is_some_and(|name| name != character.class_name)
+        {
+            if result.character_class_name.is_none() {
+                result.previous_xp = character.xp.current;
+                result.previous_level = character.level;
+            }
+            result.gained_xp = result.gained_xp.saturating_add(amount);
+            result.current_xp = new_xp.current;
+            result.current_level = level;
+            result.character_class_name = Some(character.class_name);
+        }
+
+        let event = (level > character.level).then(|| {
+            ActivityEvent::new(ActivityName::CharacterLevelUp)
+                .with_attribute("newLevel", level)
+                .with_attribute("characterClass", character.class_name)
+                .with_attribute("count", level - character.level)
+        });
+
+        character.update_xp(db, new_xp, level).await?;
+
+        Ok(event)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Entitlement, RewardBundle};
+    use crate::database::entity::currency::CurrencyType;
+
+    /// Tests that currencies of the same type are combined
+    #[test]
+    fn test_add_currency() {
+        let mut bundle = RewardBundle::default();
+        assert!(bundle.is_empty());
+
+        bundle.add_currency(CurrencyType::Mission, 100);
+        bundle.add_currency(CurrencyType::Grind, 5);
+        bundle.add_currency(CurrencyType::Mission, 50);
+        assert!(!bundle.is_empty());
+
+        let currencies: Vec<_> = bundle
+            .currencies
+            .iter()
+            .map(|currency| (currency.name, currency.value))
+            .collect();
+        assert_eq!(
+            currencies,
+            [(CurrencyType::Mission, 150), (CurrencyType::Grind, 5)]
+        );
+    }
+
+    /// Tests that entitlements alone make the bundle non empty
+    #[test]
+    fn test_entitlements_not_empty() {
+        let mut bundle = RewardBundle::default();
+        bundle.add_entitlement(Entitlement::PlayerTitle("Recruit".to_string()));
+        assert!(!bundle.is_empty());
+    }
+}
@@ -0,0 +1,103 @@
+//! Bus of structured server events streamed to external dashboard tooling
+//! so it can display the live server status without polling. Sessions,
+//! games, and activity processing publish into the bus from wherever the
+//! event occurs, events published while nothing is subscribed are dropped
+
+use super::game::GameID;
+use crate::{
+    database::entity::{
+        currency_ledger::CurrencyLedgerReason, strike_team_mission::StrikeTeamMissionId,
+        strike_teams::StrikeTeamId, users::UserId,
+    },
+    definitions::challenges::CurrencyReward,
+    utils::clock,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::OnceLock;
+use tokio::sync::broadcast;
+
+/// Number of events buffered for slow subscribers before
+/// they start missing events
+const EVENTS_CAPACITY: usize = 256;
+
+/// Events that occur on the server
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ServerEvent {
+    /// Player connected to the server
+    #[serde(rename_all = "camelCase")]
+    PlayerLogin { user_id: UserId, username: String },
+    /// A new game was created
+    #[serde(rename_all = "camelCase")]
+    GameCreated { game_id: GameID },
+    /// The mission for a game finished and was processed
+    #[serde(rename_all = "camelCase")]
+    GameFinished {
+        game_id: GameID,
+        map: String,
+        enemy_type: String,
+        difficulty: String,
+        percent_complete: u8,
+        extraction_state: String,
+        player_count: usize,
+    },
+    /// A strike team mission was resolved
+    #[serde(rename_all = "camelCase")]
+    StrikeTeamMissionResolved {
+        user_id: UserId,
+        team_id: StrikeTeamId,
+        mission_id: StrikeTeamMissionId,
+        successful: bool,
+    },
+    /// Rewards were granted to a player
+    #[serde(rename_all = "camelCase")]
+    RewardsGranted {
+        user_id: UserId,
+        reason: CurrencyLedgerReason,
+        items: usize,
+        currencies: Vec<CurrencyReward>,
+        xp: u32,
+    },
+}
+
+/// Server event along with the time it occurred
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerEventEntry {
+    pub time: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: ServerEvent,
+}
+
+/// Publisher for the server events
+pub struct ServerEvents {
+    /// Sender for publishing events to subscribers
+    tx: broadcast::Sender<ServerEventEntry>,
+}
+
+/// Static storage for the event bus
+static STORE: OnceLock<ServerEvents> = OnceLock::new();
+
+impl ServerEvents {
+    /// Gets a static reference to the global event bus
+    pub fn get() -> &'static ServerEvents {
+        STORE.get_or_init(|| {
+            let (tx, _) = broadcast::channel(EVENTS_CAPACITY);
+            Self { tx }
+        })
+    }
+
+    /// Publishes the `event` to the current subscribers
+    pub fn publish(&self, event: ServerEvent) {
+        // Sending only fails when there are no subscribers
+        _ = self.tx.send(ServerEventEntry {
+            time: clock::now(),
+            event,
+        });
+    }
+
+    /// Subscribes to the events published from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<ServerEventEntry> {
+        self.tx.subscribe()
+    }
+}
@@ -1,19 +1,43 @@
 //! Service for storing links to all the currenly active
 //! authenticated sessions on the server
 
-use crate::blaze::session::{SessionLink, WeakSessionLink};
+use super::diagnostics::SessionMemoryUsage;
+use crate::blaze::session::{Session, SessionLink, WeakSessionLink};
 use crate::database::entity::users::UserId;
-use crate::http::models::HttpError;
+use crate::http::models::{HttpError, HttpErrorI18n};
+use crate::utils::clock;
 use crate::utils::hashing::IntHashMap;
 use crate::utils::signing::SigningKey;
 use base64ct::{Base64UrlUnpadded, Encoding};
 use hyper::StatusCode;
 use parking_lot::Mutex;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::mem::size_of;
+use std::net::IpAddr;
 use thiserror::Error;
 
 type SessionMap = IntHashMap<UserId, WeakSessionLink>;
 
+/// Length of the token message, consists of the user ID followed by
+/// the issued (milliseconds) and expiry (seconds) timestamps
+const TOKEN_MESSAGE_LENGTH: usize = 4 + 8 + 8;
+
+/// Claims from a verified token
+#[derive(Debug)]
+pub struct TokenClaims {
+    /// ID of the user the token was issued to
+    pub user_id: UserId,
+    /// Unix timestamp in milliseconds that the token was issued
+    pub issued_at: u64,
+}
+
+impl TokenClaims {
+    /// Checks whether the token was issued before the tokens of the
+    /// user were revoked at `revoked_at` (Unix timestamp in milliseconds)
+    pub fn is_revoked(&self, revoked_at: i64) -> bool {
+        u64::try_from(revoked_at).is_ok_and(|revoked_at| self.issued_at < revoked_at)
+    }
+}
+
 /// Service for storing links to authenticated sessions and
 /// functionality for authenticating sessions
 pub struct Sessions {
@@ -26,33 +50,37 @@ pub struct Sessions {
 
     /// HMAC key used for computing signatures
     key: SigningKey,
+
+    /// Seconds that tokens are valid for
+    lifetime: u64,
 }
 
 impl Sessions {
-    /// Expiry time for tokens
-    const EXPIRY_TIME: Duration = Duration::from_secs(60 * 60 * 24 * 30 /* 30 Days */);
-
-    /// Starts a new service returning its link
-    pub fn new(key: SigningKey) -> Self {
+    /// Starts a new service returning its link, tokens created
+    /// are valid for `lifetime` seconds
+    pub fn new(key: SigningKey, lifetime: u64) -> Self {
         Self {
             sessions: Default::default(),
             key,
+            lifetime,
         }
     }
 
     pub fn create_token(&self, user_id: UserId) -> String {
-        // Compute expiry timestamp
-        let exp = SystemTime::now()
-            .checked_add(Self::EXPIRY_TIME)
-            .expect("Expiry timestamp too far into the future")
-            .duration_since(UNIX_EPOCH)
-            .expect("Clock went backwards")
-            .as_secs();
+        // Compute issued and expiry timestamps, tokens are issued with
+        // millisecond precision so tokens issued immediately after the
+        // tokens of a user are revoked remain valid
+        let iat: u64 = clock::now()
+            .timestamp_millis()
+            .try_into()
+            .expect("Clock went backwards");
+        let exp = Self::now_secs() + self.lifetime;
 
         // Create encoded token value
-        let mut data = [0u8; 12];
+        let mut data = [0u8; TOKEN_MESSAGE_LENGTH];
         data[..4].copy_from_slice(&user_id.to_be_bytes());
-        data[4..].copy_from_slice(&exp.to_be_bytes());
+        data[4..12].copy_from_slice(&iat.to_be_bytes());
+        data[12..].copy_from_slice(&exp.to_be_bytes());
         let data = &data;
 
         // Encode the message
@@ -66,15 +94,29 @@ impl Sessions {
         [msg, sig].join(".")
     }
 
-    pub fn verify_token(&self, token: &str) -> Result<UserId, VerifyError> {
+    /// Derives the key the client signs requests with for the provided
+    /// `token`, the key is only given to the client when the token is
+    /// issued so it isn't sent with every request like the token
+    pub fn request_signing_key(&self, token: &str) -> String {
+        let mut data = b"request-signing\n".to_vec();
+        data.extend_from_slice(token.as_bytes());
+
+        let key = self.key.sign(&data);
+        Base64UrlUnpadded::encode_string(key.as_ref())
+    }
+
+    /// Verifies the `token` responding with its claims, tokens revoked
+    /// after being issued must be checked against the user using
+    /// [TokenClaims::is_revoked]
+    pub fn verify_token(&self, token: &str) -> Result<TokenClaims, VerifyError> {
         // Split the token parts
         let (msg_raw, sig_raw) = match token.split_once('.') {
             Some(value) => value,
             None => return Err(VerifyError::Invalid),
         };
 
-        // Decode the 12 byte token message
-        let mut msg = [0u8; 12];
+        // Decode the token message
+        let mut msg = [0u8; TOKEN_MESSAGE_LENGTH];
         Base64UrlUnpadded::decode(msg_raw, &mut msg).map_err(|_| VerifyError::Invalid)?;
 
         // Decode 32byte signature (SHA256)
@@ -86,26 +128,33 @@ impl Sessions {
             return Err(VerifyError::Invalid);
         }
 
-        // Extract ID and expiration from the msg bytes
+        // Extract ID, issued, and expiration from the msg bytes
         let mut id = [0u8; 4];
         id.copy_from_slice(&msg[..4]);
         let id = u32::from_be_bytes(id);
 
+        let mut iat = [0u8; 8];
+        iat.copy_from_slice(&msg[4..12]);
+        let iat = u64::from_be_bytes(iat);
+
         let mut exp = [0u8; 8];
-        exp.copy_from_slice(&msg[4..]);
+        exp.copy_from_slice(&msg[12..]);
         let exp = u64::from_be_bytes(exp);
 
         // Ensure the timestamp is not expired
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("Clock went backwards")
-            .as_secs();
-
-        if exp < now {
+        if exp < Self::now_secs() {
             return Err(VerifyError::Expired);
         }
 
-        Ok(id)
+        Ok(TokenClaims {
+            user_id: id,
+            issued_at: iat,
+        })
+    }
+
+    /// Current unix timestamp in seconds from the clock
+    fn now_secs() -> u64 {
+        clock::timestamp().try_into().expect("Clock went backwards")
     }
 
     pub fn remove_session(&self, user_id: UserId) {
@@ -118,9 +167,55 @@ impl Sessions {
         sessions.insert(user_id, link);
     }
 
-    /// Currently unused but here for future implementation of invite system for looking
-    /// up users to connect players
-    #[allow(unused)]
+    /// Counts the number of sessions that are still active
+    pub fn count(&self) -> usize {
+        let sessions = &*self.sessions.lock();
+        sessions
+            .values()
+            .filter(|session| session.strong_count() > 0)
+            .count()
+    }
+
+    /// Approximate memory held by the sessions and the lookup map
+    pub fn memory_usage(&self) -> SessionMemoryUsage {
+        let sessions = &*self.sessions.lock();
+        let tracked = sessions.len();
+        let active = sessions
+            .values()
+            .filter(|session| session.strong_count() > 0)
+            .count();
+
+        SessionMemoryUsage {
+            active,
+            tracked,
+            approximate_bytes: active * size_of::<Session>()
+                + sessions.capacity() * size_of::<(UserId, WeakSessionLink)>(),
+        }
+    }
+
+    /// Counts the active sessions connected from the `ip` address,
+    /// excluding any session belonging to `exclude_user_id`
+    pub fn count_by_ip(&self, ip: IpAddr, exclude_user_id: UserId) -> usize {
+        let sessions = &*self.sessions.lock();
+        sessions
+            .iter()
+            .filter(|(user_id, _)| **user_id != exclude_user_id)
+            .filter_map(|(_, session)| session.upgrade())
+            .filter(|session| session.addr == ip)
+            .count()
+    }
+
+    /// Collects all the sessions that are still active
+    pub fn all(&self) -> Vec<SessionLink> {
+        let sessions = &*self.sessions.lock();
+        sessions
+            .values()
+            .filter_map(|session| session.upgrade())
+            .collect()
+    }
+
+    /// Looks up the active session for the provided `user_id` if the
+    /// user has one
     pub fn lookup_session(&self, user_id: UserId) -> Option<SessionLink> {
         let sessions = &mut *self.sessions.lock();
         let session = sessions.get(&user_id)?;
@@ -152,24 +247,83 @@ impl HttpError for VerifyError {
     fn status(&self) -> hyper::StatusCode {
         StatusCode::BAD_REQUEST
     }
+
+    fn i18n(&self) -> Option<HttpErrorI18n> {
+        match self {
+            // "Expired"
+            VerifyError::Expired => Some(HttpErrorI18n::new(11020)),
+            // "Error"
+            VerifyError::Invalid => Some(HttpErrorI18n::new(12873)),
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::utils::signing::SigningKey;
+    use crate::utils::{clock::set_test_time, signing::SigningKey};
+    use chrono::{Duration, Utc};
+
+    use super::{Sessions, VerifyError};
 
-    use super::Sessions;
+    /// Token lifetime used by the tests
+    const LIFETIME: u64 = 60 * 60 * 24 * 30;
 
     /// Tests that tokens can be created and verified correctly
     #[test]
     fn test_token() {
         let (key, _) = SigningKey::generate();
-        let sessions = Sessions::new(key);
+        let sessions = Sessions::new(key, LIFETIME);
 
         let player_id = 32;
         let token = sessions.create_token(player_id);
-        let claim = sessions.verify_token(&token).unwrap();
+        let claims = sessions.verify_token(&token).unwrap();
+
+        assert_eq!(player_id, claims.user_id)
+    }
+
+    /// Tests that tokens expire once the expiry time has passed
+    #[test]
+    fn test_token_expiry() {
+        let (key, _) = SigningKey::generate();
+        let sessions = Sessions::new(key, LIFETIME);
+
+        let created = Utc::now();
+        set_test_time(Some(created));
+        let token = sessions.create_token(32);
+
+        set_test_time(Some(created + Duration::days(29)));
+        assert!(sessions.verify_token(&token).is_ok());
+
+        set_test_time(Some(created + Duration::days(31)));
+        assert!(matches!(
+            sessions.verify_token(&token),
+            Err(VerifyError::Expired)
+        ));
+
+        set_test_time(None);
+    }
+
+    /// Tests that tokens issued before revoking are revoked while tokens
+    /// issued after remain valid, even within the same second
+    #[test]
+    fn test_token_revoke() {
+        let (key, _) = SigningKey::generate();
+        let sessions = Sessions::new(key, LIFETIME);
+
+        let created = Utc::now();
+        set_test_time(Some(created));
+        let old = sessions.verify_token(&sessions.create_token(32)).unwrap();
+
+        let revoked_at = created + Duration::minutes(5);
+        set_test_time(Some(revoked_at + Duration::milliseconds(1)));
+        let new = sessions.verify_token(&sessions.create_token(32)).unwrap();
+
+        let revoked_at = revoked_at.timestamp_millis();
+        assert!(old.is_revoked(revoked_at));
+        assert!(!new.is_revoked(revoked_at));
+        // Users that never had their tokens revoked
+        assert!(!old.is_revoked(0));
 
-        assert_eq!(player_id, claim)
+        set_test_time(None);
     }
 }
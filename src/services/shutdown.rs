@@ -0,0 +1,187 @@
+//! Graceful shutdown of the server, connected players are told that the
+//! server is stopping and missions that are still in flight are stored so
+//! that they can be restored when the server starts again.
+//!
+//! Finished missions that hadn't been processed have their rewards granted
+//! on the next startup. Missions that were still being played can't be
+//! resumed as players are placed into new games when they reconnect, these
+//! are only reported on startup
+
+use super::{
+    activity_buffer::ActivityBuffer,
+    game::{process_mission_data, ChallengeProgressChange},
+    game_manager::GameManager,
+    restarts,
+    sessions::Sessions,
+};
+use crate::{
+    database::entity::{users::UserId, InterruptedMission},
+    definitions::challenges::{ChallengeName, Challenges},
+};
+use log::{error, info, warn};
+use sea_orm::DatabaseConnection;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Message sent to the connected players when the server shuts down
+const SHUTDOWN_MESSAGE: &str = "The server is shutting down";
+
+/// Challenge progress that was written from activities reported during a
+/// mission, stored by name as the definitions can't be stored directly
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppliedProgress {
+    /// ID of the user the progress was written for
+    pub user_id: UserId,
+    /// Name of the challenge definition
+    pub challenge: ChallengeName,
+    /// Name of the challenge counter
+    pub counter: String,
+    /// The progress that was written
+    pub progress: u32,
+}
+
+impl AppliedProgress {
+    fn new(user_id: UserId, change: ChallengeProgressChange) -> Self {
+        Self {
+            user_id,
+            challenge: change.definition.name,
+            counter: change.counter.name.to_string(),
+            progress: change.progress,
+        }
+    }
+
+    /// Finds the definitions for the stored progress, [None] if the
+    /// challenge was removed while the server was stopped
+    fn resolve(&self) -> Option<ChallengeProgressChange> {
+        let definition = Challenges::get().by_name(&self.challenge)?;
+        let counter = definition
+            .counters
+            .iter()
+            .find(|counter| counter.name.as_ref() == self.counter)?;
+
+        Some(ChallengeProgressChange {
+            definition,
+            counter,
+            progress: self.progress,
+        })
+    }
+}
+
+/// Tells every connected session that the server is shutting down
+pub fn notify_sessions(sessions: &Sessions) {
+    restarts::broadcast(sessions, SHUTDOWN_MESSAGE);
+}
+
+/// Stores the missions that are still in flight so they can be restored
+/// on the next startup, must be called before the activity buffer is
+/// flushed so the progress written during the missions is known
+pub async fn persist_missions(
+    db: &DatabaseConnection,
+    game_manager: &GameManager,
+    activity_buffer: &ActivityBuffer,
+) {
+    let missions = game_manager.take_in_flight_missions().await;
+    if missions.is_empty() {
+        return;
+    }
+
+    let mut stored = 0;
+    for mission in missions {
+        let game_id = mission.game_id;
+
+        let applied = match &mission.mission_data {
+            Some(mission_data) => {
+                let user_ids: Vec<UserId> = mission_data
+                    .player_data
+                    .iter()
+                    .map(|data| data.nucleus_id)
+                    .collect();
+
+                activity_buffer
+                    .finish_mission(game_id, &user_ids)
+                    .await
+                    .into_iter()
+                    .flat_map(|(user_id, changes)| {
+                        changes
+                            .into_iter()
+                            .map(move |change| AppliedProgress::new(user_id, change))
+                    })
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+
+        if let Err(err) = InterruptedMission::create(
+            db,
+            game_id,
+            mission.reservation,
+            mission.mission_data,
+            applied,
+        )
+        .await
+        {
+            error!(
+                "Failed to store interrupted mission (GID: {}): {}",
+                game_id, err
+            );
+            continue;
+        }
+
+        stored += 1;
+    }
+
+    info!("Stored {} mission(s) that were still in flight", stored);
+}
+
+/// Restores the missions that were stored when the server last shut down,
+/// finished missions have their rewards processed
+pub async fn restore_missions(db: &DatabaseConnection) {
+    let missions = match InterruptedMission::all(db).await {
+        Ok(value) => value,
+        Err(err) => {
+            error!("Failed to load interrupted missions: {}", err);
+            return;
+        }
+    };
+
+    for mission in missions {
+        let game_id = mission.game_id;
+        let started_by = mission.started_by;
+        let mission_data = mission.mission_data.as_ref().clone();
+
+        let mut applied: HashMap<UserId, Vec<ChallengeProgressChange>> = HashMap::new();
+        for progress in mission.applied.as_ref() {
+            if let Some(change) = progress.resolve() {
+                applied.entry(progress.user_id).or_default().push(change);
+            }
+        }
+
+        // Removed before processing so the rewards can't be granted twice
+        if let Err(err) = mission.delete(db).await {
+            error!(
+                "Failed to remove interrupted mission (GID: {}): {}",
+                game_id, err
+            );
+            continue;
+        }
+
+        match mission_data {
+            Some(mission_data) => {
+                let details = process_mission_data(db, mission_data, applied).await;
+                info!(
+                    "Processed mission interrupted by shutdown for {} player(s) (GID: {})",
+                    details.player_infos.len(),
+                    game_id
+                );
+            }
+            None => {
+                warn!(
+                    "Mission started by {} was interrupted by shutdown and can't be resumed (GID: {})",
+                    started_by.unwrap_or_default(),
+                    game_id
+                );
+            }
+        }
+    }
+}
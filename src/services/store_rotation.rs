@@ -0,0 +1,217 @@
+//! Rotation of the limited time store articles, a selection of the limited
+//! time articles from the store catalog are featured each day and week with
+//! availability windows covering the period they are featured for.
+//!
+//! The featured articles are chosen using a generator seeded from the start
+//! of the period so that the rotation is the same across requests and
+//! restarts without needing to be stored
+
+use crate::{
+    config::{Config, StoreRotationConfig},
+    database::{
+        entity::{StorePurchase, User},
+        DbResult,
+    },
+    definitions::store_catalogs::{StoreArticle, StoreCatalog},
+    utils::models::DateDuration,
+};
+use chrono::{DateTime, Datelike, Duration, NaiveTime, TimeZone, Utc};
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use sea_orm::ConnectionTrait;
+
+/// Scope of purchase limits that apply to each user
+const USER_LIMIT_SCOPE: &str = "USER";
+
+/// Period that a featured article is available for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RotationPeriod {
+    Daily,
+    Weekly,
+}
+
+impl RotationPeriod {
+    /// Finds the period the `article` rotates on, limited purchase
+    /// deals rotate daily while other articles rotate weekly
+    fn of(article: &StoreArticle) -> Self {
+        if article.limits.is_empty() {
+            Self::Weekly
+        } else {
+            Self::Daily
+        }
+    }
+
+    /// Number of articles featured each period
+    fn count(self, config: &StoreRotationConfig) -> usize {
+        match self {
+            Self::Daily => config.daily_articles,
+            Self::Weekly => config.weekly_articles,
+        }
+    }
+
+    /// Start and end of the period that contains `now`, days start at
+    /// midnight UTC and weeks start on Monday
+    fn window(self, now: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
+        let today = Utc.from_utc_datetime(&now.date_naive().and_time(NaiveTime::MIN));
+        match self {
+            Self::Daily => (today, today + Duration::days(1)),
+            Self::Weekly => {
+                let start = today - Duration::days(now.weekday().num_days_from_monday() as i64);
+                (start, start + Duration::weeks(1))
+            }
+        }
+    }
+}
+
+/// Creates a copy of the `catalog` where the limited time articles are
+/// replaced with the articles featured at `now`. When rotation is disabled
+/// the limited time articles are always available instead
+pub fn rotating_catalog(catalog: &StoreCatalog, now: DateTime<Utc>) -> StoreCatalog {
+    let config = &Config::get().store.rotation;
+    let mut catalog = catalog.clone();
+
+    if !config.enabled {
+        // The catalog availability windows are from the official servers
+        // and have long since ended so they are cleared
+        catalog
+            .articles
+            .iter_mut()
+            .filter(|article| article.limited_availability)
+            .for_each(|article| {
+                article.available_duration = DateDuration::default();
+                article.visible_duration = DateDuration::default();
+            });
+        return catalog;
+    }
+
+    let featured: Vec<StoreArticle> = [RotationPeriod::Daily, RotationPeriod::Weekly]
+        .into_iter()
+        .flat_map(|period| featured_articles(&catalog.articles, period, period.count(config), now))
+        .collect();
+
+    catalog
+        .articles
+        .retain(|article| !article.limited_availability);
+    catalog.articles.extend(featured);
+    catalog
+}
+
+/// Chooses `count` of the limited time `articles` that rotate on the
+/// `period` setting their availability to the period containing `now`
+fn featured_articles(
+    articles: &[StoreArticle],
+    period: RotationPeriod,
+    count: usize,
+    now: DateTime<Utc>,
+) -> Vec<StoreArticle> {
+    let (start, end) = period.window(now);
+
+    let pool: Vec<&StoreArticle> = articles
+        .iter()
+        .filter(|article| article.limited_availability && RotationPeriod::of(article) == period)
+        .collect();
+
+    let mut rng = StdRng::seed_from_u64(start.timestamp() as u64);
+
+    pool.choose_multiple(&mut rng, count)
+        .map(|article| {
+            let mut article = (*article).clone();
+            let duration = DateDuration {
+                start: Some(start),
+                end: Some(end - Duration::milliseconds(1)),
+            };
+            article.available_duration = duration.clone();
+            article.visible_duration = duration;
+            article
+        })
+        .collect()
+}
+
+/// Sets the remaining quantity for the user scoped limits on the `articles`
+/// from the purchases the `user` has made during each article availability
+pub async fn apply_purchase_limits<C>(
+    db: &C,
+    user: &User,
+    articles: &mut [StoreArticle],
+) -> DbResult<()>
+where
+    C: ConnectionTrait + Send,
+{
+    let limited: Vec<_> = articles
+        .iter()
+        .filter(|article| !article.limits.is_empty())
+        .map(|article| article.name)
+        .collect();
+
+    if limited.is_empty() {
+        return Ok(());
+    }
+
+    let purchases = StorePurchase::all_by_articles(db, user, limited).await?;
+
+    for article in articles.iter_mut() {
+        let purchased = purchases
+            .iter()
+            .filter(|purchase| {
+                purchase.article_name == article.name
+                    // Only purchases from the current availability count
+                    && !article
+                        .available_duration
+                        .start
+                        .is_some_and(|start| purchase.created < start)
+            })
+            .count() as u32;
+
+        for limit in &mut article.limits {
+            if limit.scope.as_ref() == USER_LIMIT_SCOPE {
+                limit.quantity_remaining = limit.maximum.saturating_sub(purchased);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{featured_articles, RotationPeriod};
+    use crate::definitions::store_catalogs::StoreCatalogs;
+    use chrono::{Duration, TimeZone, Utc};
+
+    /// Tests that the featured articles stay the same throughout a period
+    /// and are available for the whole period
+    #[test]
+    fn test_featured_articles() {
+        let articles = &StoreCatalogs::get().catalog.articles;
+
+        // Wednesday
+        let now = Utc.with_ymd_and_hms(2024, 1, 3, 9, 0, 0).unwrap();
+        let later = now + Duration::days(2);
+
+        let weekly = featured_articles(articles, RotationPeriod::Weekly, 2, now);
+        let weekly_later = featured_articles(articles, RotationPeriod::Weekly, 2, later);
+        assert_eq!(weekly.len(), 2);
+        assert!(weekly
+            .iter()
+            .zip(&weekly_later)
+            .all(|(a, b)| a.name == b.name));
+
+        let monday = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        for article in &weekly {
+            assert!(article.limited_availability);
+            assert!(article.limits.is_empty());
+            assert_eq!(article.available_duration.start, Some(monday));
+            assert!(article.available_duration.contains(later));
+            assert!(!article
+                .available_duration
+                .contains(monday + Duration::weeks(1)));
+        }
+
+        let daily = featured_articles(articles, RotationPeriod::Daily, 4, now);
+        assert_eq!(daily.len(), 4);
+        for article in &daily {
+            assert!(!article.limits.is_empty());
+            assert!(article.available_duration.contains(now));
+            assert!(!article.available_duration.contains(later));
+        }
+    }
+}
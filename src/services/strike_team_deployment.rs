@@ -0,0 +1,303 @@
+//! Deployment of strike teams onto strike team missions. Each strike team
+//! can be on a single mission at a time but different teams can be deployed
+//! on different missions concurrently. When every strike team is busy
+//! missions are queued and deployed in order as teams become available.
+//!
+//! Teams that have finished their missions are returned by the mission
+//! background task, the outcome of the mission is decided on return.
+
+use super::{
+    maintenance::ReadOnlyMode,
+    random::{RandomPurpose, RandomService},
+    strike_team_resolve::MissionOutcome,
+};
+use crate::{
+    database::{
+        entity::{
+            strike_team_mission_progress::UserMissionState,
+            strike_teams::{self, StrikeTeamId},
+            StrikeTeam, StrikeTeamDeployment, StrikeTeamMission, StrikeTeamMissionProgress, User,
+        },
+        DbResult,
+    },
+    utils::clock,
+};
+use log::debug;
+use sea_orm::{ConnectionTrait, ModelTrait};
+use serde::Serialize;
+
+/// State of an individual strike team
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum StrikeTeamState {
+    /// Team is available to be deployed
+    Idle,
+    /// Team is currently out on a mission
+    Deployed,
+    /// Team has returned from a mission that is waiting to be resolved
+    Returned,
+}
+
+impl StrikeTeamState {
+    /// Determines the state of a strike team from the progress of
+    /// the mission its currently assigned to
+    pub fn from_progress(progress: Option<&StrikeTeamMissionProgress>) -> Self {
+        match progress.map(|value| value.user_mission_state) {
+            Some(UserMissionState::InProgress) => Self::Deployed,
+            Some(UserMissionState::PendingResolve) => Self::Returned,
+            _ => Self::Idle,
+        }
+    }
+}
+
+/// Outcome of a deployment request
+pub enum Deployment {
+    /// A strike team was deployed onto the mission
+    Deployed(StrikeTeamMissionProgress),
+    /// All strike teams were busy so the mission was queued
+    Queued {
+        /// Position of the mission in the queue (Starting at 1)
+        position: usize,
+    },
+    /// The mission already had a strike team deployed by a concurrent request
+    AlreadyDeployed,
+}
+
+/// Updates the state of the `user` strike teams, missions with teams that
+/// have returned are moved to pending resolve and queued missions are
+/// deployed to any idle teams.
+///
+/// Returns the progress for the missions the strike teams are assigned to
+pub async fn refresh<C>(db: &C, user: &User) -> DbResult<Vec<StrikeTeamMissionProgress>>
+where
+    C: ConnectionTrait + Send,
+{
+    let now = clock::now();
+    let mut active = Vec::new();
+
+    for progress in StrikeTeamMissionProgress::get_active(db, user).await? {
+        let mission_id = progress.mission_id;
+        let returned = progress.user_mission_state == UserMissionState::InProgress
+            && progress.finish_time.is_some_and(|time| time <= now);
+
+        let progress = if returned {
+            match return_team(db, progress).await? {
+                Some(value) => Some(value),
+                // Mission was updated by a concurrent return or resolve
+                None => StrikeTeamMissionProgress::get_by_mission(db, user, mission_id)
+                    .await?
+                    .filter(|value| UserMissionState::ACTIVE.contains(&value.user_mission_state)),
+            }
+        } else {
+            Some(progress)
+        };
+
+        active.extend(progress);
+    }
+
+    dispatch_queue(db, user, &mut active).await?;
+
+    Ok(active)
+}
+
+/// Finds the progress for the missions the `user` strike teams are assigned
+/// to when viewing the strike teams. The strike teams are refreshed unless
+/// `read_only` mode is enabled, in which case returned teams and queued
+/// missions are left until it's disabled
+pub async fn view<C>(
+    db: &C,
+    user: &User,
+    read_only: &ReadOnlyMode,
+) -> DbResult<Vec<StrikeTeamMissionProgress>>
+where
+    C: ConnectionTrait + Send,
+{
+    if read_only.is_enabled() {
+        return StrikeTeamMissionProgress::get_active(db, user).await;
+    }
+
+    refresh(db, user).await
+}
+
+/// Returns the strike teams of every user that have finished their
+/// missions, responds with the progress of the returned missions
+pub async fn return_finished<C>(db: &C) -> DbResult<Vec<StrikeTeamMissionProgress>>
+where
+    C: ConnectionTrait + Send,
+{
+    let returned = StrikeTeamMissionProgress::get_returned(db, clock::now()).await?;
+    let mut progress = Vec::with_capacity(returned.len());
+
+    for value in returned {
+        // Missions already returned by a concurrent refresh are skipped
+        if let Some(value) = return_team(db, value).await? {
+            progress.push(value);
+        }
+    }
+
+    Ok(progress)
+}
+
+/// Marks the strike team on the mission `progress` as returned, the
+/// outcome of the mission is rolled using the current team details.
+///
+/// Responds with [None] if the mission was no longer in progress
+async fn return_team<C>(
+    db: &C,
+    progress: StrikeTeamMissionProgress,
+) -> DbResult<Option<StrikeTeamMissionProgress>>
+where
+    C: ConnectionTrait + Send,
+{
+    debug!(
+        "Strike team returned from mission (User: {}, Mission: {})",
+        progress.user_id, progress.mission_id
+    );
+
+    let team = progress.find_related(strike_teams::Entity).one(db).await?;
+    let mission = StrikeTeamMission::by_id(db, progress.mission_id).await?;
+
+    let outcome = match (team, mission) {
+        (Some(team), Some(mission)) => {
+            let mut rng = RandomService::get().rng(RandomPurpose::StrikeTeamOutcome);
+            MissionOutcome::roll(&mut rng, &team, &mission)
+        }
+        // Missions without a team or mission definition can't succeed
+        _ => MissionOutcome {
+            successful: false,
+            earn_negative_trait: false,
+        },
+    };
+
+    progress
+        .set_returned(db, outcome.successful, outcome.earn_negative_trait)
+        .await
+}
+
+/// Deploys the queued missions of the `user` onto their idle strike teams,
+/// the newly deployed missions are added to `active`
+async fn dispatch_queue<C>(
+    db: &C,
+    user: &User,
+    active: &mut Vec<StrikeTeamMissionProgress>,
+) -> DbResult<()>
+where
+    C: ConnectionTrait + Send,
+{
+    let queue = StrikeTeamDeployment::all(db, user).await?;
+    if queue.is_empty() {
+        return Ok(());
+    }
+
+    let mut idle = idle_teams(db, user, active).await?.into_iter();
+    // Team left over from a queued mission that couldn't be deployed
+    let mut unused: Option<StrikeTeam> = None;
+
+    for entry in queue {
+        let mission = match StrikeTeamMission::by_id(db, entry.mission_id).await? {
+            Some(value) => value,
+            // Missions that no longer exist are removed from the queue
+            None => {
+                entry.delete(db).await?;
+                continue;
+            }
+        };
+
+        let team = match unused.take() {
+            Some(value) => value,
+            None => match next_available(db, &mut idle).await? {
+                Some(value) => value,
+                None => break,
+            },
+        };
+
+        debug!(
+            "Deploying queued strike team mission (User: {}, Team: {}, Mission: {})",
+            user.id, team.id, mission.id
+        );
+
+        let progress = StrikeTeamMissionProgress::deploy(db, user, &team, &mission).await?;
+        entry.delete(db).await?;
+
+        match progress {
+            Some(progress) => active.push(progress),
+            // Mission was deployed by a concurrent request, the team
+            // is kept for the next queued mission
+            None => unused = Some(team),
+        }
+    }
+
+    Ok(())
+}
+
+/// Takes the next team from `idle` that is still available, the team
+/// may have been deployed by a concurrent request since the idle teams
+/// were found so its checked against the database before being used
+async fn next_available<C>(
+    db: &C,
+    idle: &mut impl Iterator<Item = StrikeTeam>,
+) -> DbResult<Option<StrikeTeam>>
+where
+    C: ConnectionTrait + Send,
+{
+    for team in idle {
+        if StrikeTeamMissionProgress::get_by_team(db, &team)
+            .await?
+            .is_none()
+        {
+            return Ok(Some(team));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Finds the strike teams of the `user` that aren't assigned to
+/// any of the `active` missions
+async fn idle_teams<C>(
+    db: &C,
+    user: &User,
+    active: &[StrikeTeamMissionProgress],
+) -> DbResult<Vec<StrikeTeam>>
+where
+    C: ConnectionTrait + Send,
+{
+    let busy: Vec<StrikeTeamId> = active
+        .iter()
+        .filter_map(|progress| progress.strike_team_id)
+        .collect();
+
+    let teams = StrikeTeam::get_by_user(db, user).await?;
+
+    Ok(teams
+        .into_iter()
+        .filter(|team| !busy.contains(&team.id))
+        .collect())
+}
+
+/// Deploys the first idle strike team of the `user` onto the `mission`,
+/// when no teams are idle the mission is added to the queue instead
+pub async fn deploy_or_queue<C>(
+    db: &C,
+    user: &User,
+    mission: &StrikeTeamMission,
+) -> DbResult<Deployment>
+where
+    C: ConnectionTrait + Send,
+{
+    let active = refresh(db, user).await?;
+    let mut idle = idle_teams(db, user, &active).await?.into_iter();
+
+    if let Some(team) = next_available(db, &mut idle).await? {
+        let deployment = match StrikeTeamMissionProgress::deploy(db, user, &team, mission).await? {
+            Some(progress) => Deployment::Deployed(progress),
+            None => Deployment::AlreadyDeployed,
+        };
+        return Ok(deployment);
+    }
+
+    StrikeTeamDeployment::create(db, user, mission.id).await?;
+    let position = StrikeTeamDeployment::all(db, user).await?.len();
+
+    Ok(Deployment::Queued { position })
+}
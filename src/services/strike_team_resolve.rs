@@ -0,0 +1,533 @@
+//! Resolution of strike team missions once the team has returned, the outcome
+//! of the mission is decided when the team returns and applied when resolved
+//! granting the team progression along with the mission rewards. Consecutive
+//! successes build up a streak which grants escalating bonuses, any failure
+//! resets the streak and may leave the team with a negative trait.
+//!
+//! Every resolved mission is recorded in the mission history of the team
+
+use crate::{
+    config::{Config, StreakBonus, StrikeTeamsConfig},
+    database::entity::{
+        currency::CurrencyType,
+        currency_ledger::CurrencyLedgerReason,
+        strike_team_mission::MissionAccessibility,
+        strike_team_mission_history::{
+            MissionHistoryItem, MissionHistoryRewards, MissionHistoryTraits,
+        },
+        Currency, StrikeTeam, StrikeTeamMission, StrikeTeamMissionHistory,
+        StrikeTeamMissionProgress, User,
+    },
+    definitions::{
+        items::{ItemName, Items},
+        level_tables::LevelTables,
+        strike_teams::{
+            mission_success_rate, MissionDifficulty, MissionRewards, StrikeTeamTrait, StrikeTeams,
+            STRIKE_TEAM_LEVEL_TABLE,
+        },
+    },
+    services::{
+        activity::ActivityResult,
+        random::{RandomPurpose, RandomService},
+        rewards::{RewardBundle, RewardEngine},
+    },
+};
+use log::debug;
+use rand::Rng;
+use sea_orm::{ConnectionTrait, TransactionTrait};
+use serde::Serialize;
+use serde_with::serde_as;
+
+/// Outcome of a strike team mission, decided when the team returns
+#[derive(Debug, Clone, Copy)]
+pub struct MissionOutcome {
+    /// Whether the mission was successful
+    pub successful: bool,
+    /// Whether the team gains a negative trait from the mission
+    pub earn_negative_trait: bool,
+}
+
+impl MissionOutcome {
+    /// Rolls the outcome of the `team` undertaking the `mission`, only
+    /// failed missions can give the team a negative trait
+    pub fn roll<R>(rng: &mut R, team: &StrikeTeam, mission: &StrikeTeamMission) -> Self
+    where
+        R: Rng,
+    {
+        let config = &Config::get().strike_teams;
+        let successful = rng.gen::<f32>() < mission_success_rate(team, mission);
+        let earn_negative_trait = !successful && rng.gen::<f32>() < config.negative_trait_chance;
+
+        Self {
+            successful,
+            earn_negative_trait,
+        }
+    }
+
+    /// Obtains the outcome stored on the `progress` when the team returned
+    pub fn from_progress(progress: &StrikeTeamMissionProgress) -> Option<Self> {
+        progress.successful.map(|successful| Self {
+            successful,
+            earn_negative_trait: progress.earn_negative_trait,
+        })
+    }
+}
+
+/// Outcome of resolving a strike team mission
+pub struct MissionResolution {
+    /// The updated strike team
+    pub team: StrikeTeam,
+    /// Whether the mission was successful
+    pub successful: bool,
+    /// Negative traits the team gained from failing the mission
+    pub traits_acquired: Vec<StrikeTeamTrait>,
+    /// Streak bonus applied to the mission if one was reached
+    pub streak_bonus: Option<StreakBonus>,
+    /// Rewards granted to the user
+    pub result: ActivityResult,
+}
+
+/// Resolves the `mission` the `team` returned from applying the mission
+/// `outcome`, rewards are only granted for successful missions. Equipment
+/// is used up by the mission regardless of the outcome
+pub async fn resolve<C>(
+    db: &C,
+    user: &User,
+    mut team: StrikeTeam,
+    mission: &StrikeTeamMission,
+    outcome: MissionOutcome,
+) -> anyhow::Result<MissionResolution>
+where
+    C: ConnectionTrait + TransactionTrait + Send,
+{
+    let config = &Config::get().strike_teams;
+    let successful = outcome.successful;
+    let previous_traits = all_traits(&team);
+
+    if team.equipment.is_some() {
+        team = team.set_equipment(db, None).await?;
+    }
+
+    let mut result = ActivityResult::default();
+
+    if !successful {
+        debug!(
+            "Strike team mission failed, resetting streak (Team: {})",
+            team.id
+        );
+
+        let mut traits_acquired = Vec::new();
+
+        if outcome.earn_negative_trait {
+            let mut rng = RandomService::get().rng(RandomPurpose::StrikeTeamTraits);
+            let negative_trait = StrikeTeams::get().traits.random_negative(
+                &mut rng,
+                &mission.tags.0,
+                &team.negative_traits.0,
+            );
+
+            if let Some(negative_trait) = negative_trait {
+                let positive = team.positive_traits.0.clone();
+                let mut negative = team.negative_traits.0.clone();
+                negative.push(negative_trait.clone());
+                traits_acquired.push(negative_trait);
+
+                team = team.set_traits(db, positive, negative).await?;
+            }
+        }
+
+        let (xp, level) = (team.xp, team.level);
+        let team = team.set_progression(db, xp, level, 0).await?;
+
+        StrikeTeamMissionHistory::create(
+            db,
+            team.id,
+            mission,
+            successful,
+            0,
+            MissionHistoryRewards::default(),
+            trait_changes(&previous_traits, &all_traits(&team)),
+        )
+        .await?;
+
+        result.currencies = Currency::all(db, user).await?;
+
+        return Ok(MissionResolution {
+            team,
+            successful,
+            traits_acquired,
+            streak_bonus: None,
+            result,
+        });
+    }
+
+    let success_streak = team.success_streak.saturating_add(1);
+    let streak_bonus = config.streak_bonus(success_streak).cloned();
+
+    // Apply the streak XP bonus to the mission XP
+    let mut xp_earned = 0;
+    if mission.mission_type.give_xp {
+        let bonus_percent = streak_bonus
+            .as_ref()
+            .map(|bonus| bonus.bonus_xp_percent)
+            .unwrap_or_default();
+        xp_earned = config
+            .mission_xp
+            .saturating_add(config.mission_xp.saturating_mul(bonus_percent) / 100);
+    }
+
+    let (xp, level) = match LevelTables::get().by_name(&STRIKE_TEAM_LEVEL_TABLE) {
+        Some(level_table) => level_table.compute_leveling(team.xp, team.level, xp_earned),
+        None => (team.xp, team.level),
+    };
+
+    // Improve the reward tier when the streak bonus provides one
+    let improved_rewards = streak_bonus
+        .as_ref()
+        .filter(|bonus| bonus.reward_tier_bonus > 0)
+        .zip(mission.difficulty())
+        .map(|(bonus, difficulty)| {
+            let improved = difficulty.improved(bonus.reward_tier_bonus, mission.accessibility);
+            improve_rewards(
+                &mission.rewards,
+                mission.accessibility,
+                difficulty,
+                improved,
+            )
+        });
+    let rewards = improved_rewards.as_ref().unwrap_or(&mission.rewards);
+
+    let mut bundle = RewardBundle::default();
+
+    if mission.mission_type.give_currency {
+        let currency = &rewards.currency_reward;
+        bundle.add_currency(currency.name, currency.value);
+    }
+
+    let items = Items::get();
+    for (item_name, count) in &rewards.sp_item_rewards {
+        if let Some(definition) = items.by_name(item_name) {
+            bundle.add_item(definition, *count);
+        }
+    }
+
+    let granted = RewardEngine::grant(
+        db,
+        user,
+        bundle,
+        CurrencyLedgerReason::StrikeTeamReward,
+        &mut result,
+    )
+    .await?;
+
+    // History records the rewards after capacities and overflow are applied
+    let history_rewards = MissionHistoryRewards {
+        currencies: granted.currencies,
+        items: granted
+            .items
+            .iter()
+            .map(|reward| MissionHistoryItem {
+                name: reward.definition.name,
+                count: reward.stack_size,
+            })
+            .collect(),
+    };
+
+    result.currencies = Currency::all(db, user).await?;
+
+    debug!(
+        "Strike team mission succeeded (Team: {}, Streak: {}, XP: {})",
+        team.id, success_streak, xp_earned
+    );
+
+    let team = team.set_progression(db, xp, level, success_streak).await?;
+
+    StrikeTeamMissionHistory::create(
+        db,
+        team.id,
+        mission,
+        successful,
+        xp_earned,
+        history_rewards,
+        trait_changes(&previous_traits, &all_traits(&team)),
+    )
+    .await?;
+
+    Ok(MissionResolution {
+        team,
+        successful,
+        traits_acquired: Vec::new(),
+        streak_bonus,
+        result,
+    })
+}
+
+/// Improves the mission `rewards` from the `difficulty` reward tier to the
+/// `improved` tier. Only the difference between the default rewards of the
+/// two tiers is added on top of the mission rewards so missions with custom
+/// rewards keep them and only earn the bonus from the improvement
+fn improve_rewards(
+    rewards: &MissionRewards,
+    accessibility: MissionAccessibility,
+    difficulty: MissionDifficulty,
+    improved: MissionDifficulty,
+) -> MissionRewards {
+    let base = MissionRewards::default(difficulty, accessibility);
+    let improved = MissionRewards::default(improved, accessibility);
+
+    let mut rewards = rewards.clone();
+
+    if rewards.currency_reward.name == improved.currency_reward.name {
+        let bonus = improved
+            .currency_reward
+            .value
+            .saturating_sub(base.currency_reward.value);
+        rewards.currency_reward.value = rewards.currency_reward.value.saturating_add(bonus);
+    }
+
+    for (item_name, count) in &improved.sp_item_rewards {
+        let base_count = base
+            .sp_item_rewards
+            .iter()
+            .find(|(name, _)| name == item_name)
+            .map(|(_, count)| *count)
+            .unwrap_or_default();
+
+        let bonus = count.saturating_sub(base_count);
+        if bonus == 0 {
+            continue;
+        }
+
+        match rewards
+            .sp_item_rewards
+            .iter_mut()
+            .find(|(name, _)| name == item_name)
+        {
+            Some((_, count)) => *count = count.saturating_add(bonus),
+            None => rewards.sp_item_rewards.push((*item_name, bonus)),
+        }
+    }
+
+    rewards
+}
+
+/// Collects both the positive and negative traits of the `team`
+fn all_traits(team: &StrikeTeam) -> Vec<StrikeTeamTrait> {
+    team.positive_traits
+        .0
+        .iter()
+        .chain(team.negative_traits.0.iter())
+        .cloned()
+        .collect()
+}
+
+/// Determines the traits gained and lost going from the `previous`
+/// traits of a team to the `current` traits
+fn trait_changes(
+    previous: &[StrikeTeamTrait],
+    current: &[StrikeTeamTrait],
+) -> MissionHistoryTraits {
+    let missing_from = |traits: &[StrikeTeamTrait], value: &StrikeTeamTrait| {
+        !traits.iter().any(|other| other.name == value.name)
+    };
+
+    MissionHistoryTraits {
+        gained: current
+            .iter()
+            .filter(|value| missing_from(previous, value))
+            .cloned()
+            .collect(),
+        lost: previous
+            .iter()
+            .filter(|value| missing_from(current, value))
+            .cloned()
+            .collect(),
+    }
+}
+
+/// Preview of the rewards a strike team can earn from a mission, the
+/// rewards improve when the team reaches a success streak with a reward
+/// tier bonus
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MissionRewardPreview {
+    /// Currency the mission rewards
+    pub currency: CurrencyType,
+    /// Currency earned without a streak bonus
+    pub min_currency: u32,
+    /// Currency earned with the best streak bonus
+    pub max_currency: u32,
+    /// The reward tiers that can be earned, starting with the
+    /// rewards earned without a streak bonus
+    pub tiers: Vec<MissionRewardTier>,
+}
+
+/// Rewards earned from a mission once a success streak is reached
+#[serde_as]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MissionRewardTier {
+    /// Success streak required to earn the tier
+    pub streak: u32,
+    /// Difficulty the rewards are for
+    pub difficulty: Option<MissionDifficulty>,
+    /// Currency earned
+    pub currency: u32,
+    /// Items earned along with the amount of each
+    #[serde_as(as = "serde_with::Map<_, _>")]
+    pub items: Vec<(ItemName, u32)>,
+}
+
+impl MissionRewardTier {
+    fn new(
+        streak: u32,
+        difficulty: Option<MissionDifficulty>,
+        mission: &StrikeTeamMission,
+        rewards: &MissionRewards,
+    ) -> Self {
+        let currency = if mission.mission_type.give_currency {
+            rewards.currency_reward.value
+        } else {
+            0
+        };
+
+        Self {
+            streak,
+            difficulty,
+            currency,
+            items: rewards.sp_item_rewards.clone(),
+        }
+    }
+}
+
+/// Computes the preview of the rewards a strike team can earn from the
+/// `mission`, [None] for missions strike teams can't be deployed on
+pub fn reward_preview(
+    config: &StrikeTeamsConfig,
+    mission: &StrikeTeamMission,
+) -> Option<MissionRewardPreview> {
+    if mission.accessibility == MissionAccessibility::MultiPlayer {
+        return None;
+    }
+
+    let difficulty = mission.difficulty();
+    let mut tiers = vec![MissionRewardTier::new(
+        0,
+        difficulty,
+        mission,
+        &mission.rewards,
+    )];
+
+    // Only streaks improving the reward tier change the rewards
+    if let Some(difficulty) = difficulty {
+        let mut bonuses: Vec<&StreakBonus> = config
+            .streak_bonuses
+            .iter()
+            .filter(|bonus| bonus.reward_tier_bonus > 0)
+            .collect();
+        bonuses.sort_by_key(|bonus| bonus.streak);
+
+        for bonus in bonuses {
+            let improved = difficulty.improved(bonus.reward_tier_bonus, mission.accessibility);
+
+            // Improvements beyond the highest difficulty don't change the rewards
+            if tiers
+                .last()
+                .is_some_and(|tier| tier.difficulty == Some(improved))
+            {
+                continue;
+            }
+
+            let rewards = improve_rewards(
+                &mission.rewards,
+                mission.accessibility,
+                difficulty,
+                improved,
+            );
+            tiers.push(MissionRewardTier::new(
+                bonus.streak,
+                Some(improved),
+                mission,
+                &rewards,
+            ));
+        }
+    }
+
+    let currencies = tiers.iter().map(|tier| tier.currency);
+    let min_currency = currencies.clone().min().unwrap_or_default();
+    let max_currency = currencies.max().unwrap_or_default();
+
+    Some(MissionRewardPreview {
+        currency: mission.rewards.currency_reward.name,
+        min_currency,
+        max_currency,
+        tiers,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{improve_rewards, trait_changes};
+    use crate::{
+        database::entity::strike_team_mission::MissionAccessibility,
+        definitions::strike_teams::{MissionDifficulty, MissionRewards, StrikeTeams},
+    };
+    use uuid::uuid;
+
+    /// Tests that improving the reward tier keeps the custom mission rewards
+    /// and only adds the difference between the tiers
+    #[test]
+    fn test_improve_rewards() {
+        let accessibility = MissionAccessibility::SinglePlayer;
+        let custom_item = uuid!("af3d2d2d-3e1b-4b2b-9d5f-8b0b8b0b8b0b");
+
+        let mut rewards = MissionRewards::default(MissionDifficulty::Bronze, accessibility);
+        rewards.currency_reward.value = 50;
+        rewards.sp_item_rewards.push((custom_item, 2));
+
+        let improved = improve_rewards(
+            &rewards,
+            accessibility,
+            MissionDifficulty::Bronze,
+            MissionDifficulty::Silver,
+        );
+
+        // Both tiers give the same currency so no currency bonus is earned
+        assert_eq!(improved.currency_reward.value, 50);
+
+        // The custom and bronze rewards are kept alongside the silver rewards
+        let silver = MissionRewards::default(MissionDifficulty::Silver, accessibility);
+        for item in rewards
+            .sp_item_rewards
+            .iter()
+            .chain(&silver.sp_item_rewards)
+        {
+            assert!(improved.sp_item_rewards.contains(item));
+        }
+        assert_eq!(
+            improved.sp_item_rewards.len(),
+            rewards.sp_item_rewards.len() + silver.sp_item_rewards.len()
+        );
+    }
+
+    /// Tests that traits are compared by name to find the gained and lost traits
+    #[test]
+    fn test_trait_changes() {
+        let traits = &StrikeTeams::get().traits;
+
+        let kept = traits.positive[0].clone();
+        let lost = traits.positive[1].clone();
+        let gained = traits.negative[0].clone();
+
+        let changes = trait_changes(
+            &[kept.clone(), lost.clone()],
+            &[kept.clone(), gained.clone()],
+        );
+        assert_eq!(changes.gained, vec![gained]);
+        assert_eq!(changes.lost, vec![lost]);
+
+        let changes = trait_changes(&[kept.clone()], &[kept]);
+        assert!(changes.gained.is_empty());
+        assert!(changes.lost.is_empty());
+    }
+}
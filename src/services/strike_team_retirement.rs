@@ -0,0 +1,143 @@
+//! Retirement of strike teams, retired teams are removed from the user
+//! and recorded in their strike team history. Retiring a team grants a
+//! reward that scales with the levels the team gained after it was recruited
+
+use crate::{
+    config::StrikeTeamsConfig,
+    database::entity::{
+        currency_ledger::CurrencyLedgerReason, strike_teams::StrikeTeamId, RetiredStrikeTeam,
+        StrikeTeam, User,
+    },
+    definitions::challenges::CurrencyReward,
+    services::{
+        activity::ActivityResult,
+        rewards::{RewardBundle, RewardEngine},
+    },
+};
+use log::debug;
+use sea_orm::{ConnectionTrait, DbErr, TransactionTrait};
+use thiserror::Error;
+
+/// Level strike teams are recruited at, only the levels gained
+/// beyond it are rewarded so recruiting and retiring a team can't
+/// be used to farm currency
+const RECRUITED_LEVEL: u32 = 1;
+
+/// Errors that can occur while retiring a strike team
+#[derive(Debug, Error)]
+pub enum RetireError {
+    /// The strike team doesn't exist or was already retired
+    #[error("Strike team doesn't exist")]
+    UnknownTeam,
+    /// The strike team is currently on a mission
+    #[error("Team on mission")]
+    TeamOnMission,
+    /// Database error occurred
+    #[error(transparent)]
+    Database(#[from] DbErr),
+    /// Error while granting the rewards
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+/// Computes the currencies granted for retiring a team at `level`
+pub fn retirement_rewards(config: &StrikeTeamsConfig, level: u32) -> Vec<CurrencyReward> {
+    let levels_gained = level.saturating_sub(RECRUITED_LEVEL);
+
+    let mut rewards: Vec<CurrencyReward> = config
+        .retirement_reward_per_level
+        .iter()
+        .map(|(currency, amount)| CurrencyReward {
+            name: *currency,
+            value: amount.saturating_mul(levels_gained),
+        })
+        .filter(|reward| reward.value > 0)
+        .collect();
+
+    // Keep the order stable for the history
+    rewards.sort_by_key(|reward| reward.name as u8);
+    rewards
+}
+
+/// Retires the team with the provided `id` from the `user`, the team is
+/// recorded in the history and the retirement rewards are granted to the
+/// user. The team is loaded and removed within the same transaction so
+/// concurrent retirements can only reward the team once
+pub async fn retire<C>(
+    db: &C,
+    config: &StrikeTeamsConfig,
+    user: &User,
+    id: StrikeTeamId,
+) -> Result<RetiredStrikeTeam, RetireError>
+where
+    C: ConnectionTrait + TransactionTrait + Send,
+{
+    let db = db.begin().await?;
+
+    let team = StrikeTeam::get_by_id(&db, user, id)
+        .await?
+        .ok_or(RetireError::UnknownTeam)?;
+
+    if team.is_on_mission(&db).await? {
+        return Err(RetireError::TeamOnMission);
+    }
+
+    let rewards = retirement_rewards(config, team.level);
+
+    let mut bundle = RewardBundle::default();
+    for reward in &rewards {
+        bundle.add_currency(reward.name, reward.value);
+    }
+
+    let retired = RetiredStrikeTeam::create(&db, &team, rewards).await?;
+
+    // Another request already retired the team
+    if !team.delete(&db).await? {
+        return Err(RetireError::UnknownTeam);
+    }
+
+    // The result is only used by activities
+    let mut result = ActivityResult::default();
+    RewardEngine::grant(
+        &db,
+        user,
+        bundle,
+        CurrencyLedgerReason::StrikeTeamRetirement,
+        &mut result,
+    )
+    .await?;
+
+    db.commit().await?;
+
+    debug!(
+        "Strike team retired (User: {}, Name: {}, Level: {})",
+        user.id, retired.name, retired.level
+    );
+
+    Ok(retired)
+}
+
+#[cfg(test)]
+mod test {
+    use super::retirement_rewards;
+    use crate::{config::StrikeTeamsConfig, database::entity::currency::CurrencyType};
+    use std::collections::HashMap;
+
+    /// Tests that the rewards scale with the levels gained by the team
+    #[test]
+    fn test_retirement_rewards() {
+        let config = StrikeTeamsConfig {
+            retirement_reward_per_level: HashMap::from([(CurrencyType::Mission, 5)]),
+            ..Default::default()
+        };
+
+        let rewards = retirement_rewards(&config, 12);
+        assert_eq!(rewards.len(), 1);
+        assert_eq!(rewards[0].name, CurrencyType::Mission);
+        assert_eq!(rewards[0].value, 55);
+
+        // Teams that haven't gained any levels aren't rewarded
+        assert!(retirement_rewards(&config, 1).is_empty());
+        assert!(retirement_rewards(&config, 0).is_empty());
+    }
+}
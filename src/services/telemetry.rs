@@ -0,0 +1,228 @@
+//! Decoding of the telemetry pin events sent by the client, gameplay
+//! statistics from the events are stored for each user and match.
+//!
+//! Events are attributed to the authenticated user that sent them, the
+//! persona ID (`pid`) within the events is ignored so users can't record
+//! statistics for other users. The match is taken from the game ID (`gid`)
+//! of the event falling back to the game the user is currently in
+
+use super::{game::GameID, sessions::Sessions};
+use crate::database::{
+    entity::{MatchTelemetry, User},
+    DbResult,
+};
+use sea_orm::ConnectionTrait;
+use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, DisplayFromStr, PickFirst};
+use std::collections::HashMap;
+
+/// Event sent when the user kills enemies, `count` kills
+const KILL_EVENT: &str = "kill";
+/// Event sent when the user extracts at the end of a match
+const EXTRACTION_EVENT: &str = "extraction";
+/// Event sent when the user completes the `wave`
+const WAVE_EVENT: &str = "wave_complete";
+
+/// Payload of pin events sent to the `/pinEvents` endpoint
+#[derive(Debug, Deserialize)]
+pub struct PinPayload {
+    /// The events within the payload
+    #[serde(default)]
+    pub events: Vec<PinEvent>,
+}
+
+/// Telemetry event, only the fields used for gameplay statistics are decoded
+#[serde_as]
+#[derive(Debug, Deserialize)]
+pub struct PinEvent {
+    /// Common fields present on all events
+    pub core: PinEventCore,
+    /// ID of the game the event occurred in
+    #[serde_as(as = "Option<PickFirst<(_, DisplayFromStr)>>")]
+    pub gid: Option<GameID>,
+    /// Number of kills for kill events
+    #[serde_as(as = "Option<PickFirst<(_, DisplayFromStr)>>")]
+    pub count: Option<u32>,
+    /// Wave number for wave events
+    #[serde_as(as = "Option<PickFirst<(_, DisplayFromStr)>>")]
+    pub wave: Option<u32>,
+}
+
+/// Common fields present on all pin events
+#[derive(Debug, Deserialize)]
+pub struct PinEventCore {
+    /// Name of the event
+    pub en: String,
+}
+
+/// Gameplay statistics for a match
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchStats {
+    /// Number of enemies killed
+    pub kills: u32,
+    /// Number of times the user extracted
+    pub extractions: u32,
+    /// Highest wave reached
+    pub highest_wave: u32,
+}
+
+impl PinEvent {
+    /// Checks whether the event contains gameplay statistics
+    fn is_gameplay(&self) -> bool {
+        matches!(
+            self.core.en.as_str(),
+            KILL_EVENT | EXTRACTION_EVENT | WAVE_EVENT
+        )
+    }
+}
+
+impl MatchStats {
+    /// Applies the gameplay statistics from the `event`
+    fn apply(&mut self, event: &PinEvent) {
+        match event.core.en.as_str() {
+            KILL_EVENT => self.kills = self.kills.saturating_add(event.count.unwrap_or(1)),
+            EXTRACTION_EVENT => self.extractions = self.extractions.saturating_add(1),
+            WAVE_EVENT => self.highest_wave = self.highest_wave.max(event.wave.unwrap_or(0)),
+            _ => {}
+        }
+    }
+}
+
+/// Collects the gameplay statistics from the `events` for each match,
+/// `current_game` is the game the user is currently in and is used for
+/// events without a game ID
+fn collect(events: &[PinEvent], current_game: Option<GameID>) -> HashMap<GameID, MatchStats> {
+    let mut matches: HashMap<GameID, MatchStats> = HashMap::new();
+
+    for event in events.iter().filter(|event| event.is_gameplay()) {
+        let Some(game_id) = event.gid.or(current_game) else {
+            continue;
+        };
+
+        matches.entry(game_id).or_default().apply(event);
+    }
+
+    matches
+}
+
+/// Decodes the gameplay statistics from the pin events `payload` sent
+/// by the `user` and stores them for each match
+pub async fn record_pin_events<C>(
+    db: &C,
+    sessions: &Sessions,
+    user: &User,
+    payload: &PinPayload,
+) -> DbResult<()>
+where
+    C: ConnectionTrait + Send,
+{
+    let current_game = sessions
+        .lookup_session(user.id)
+        .and_then(|session| session.data.lock().game_id());
+
+    let matches = collect(&payload.events, current_game);
+
+    for (game_id, stats) in matches {
+        MatchTelemetry::record(db, user.id, game_id, stats).await?;
+    }
+
+    Ok(())
+}
+
+/// Aggregate gameplay statistics across all the recorded matches
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TelemetryStats {
+    /// Number of matches with recorded statistics
+    pub matches: u64,
+    /// Total number of enemies killed
+    pub kills: u32,
+    /// Total number of extractions
+    pub extractions: u32,
+    /// Highest wave reached in any match
+    pub highest_wave: u32,
+    /// Average number of kills per match
+    pub average_kills: f32,
+    /// Average highest wave reached per match
+    pub average_wave: f32,
+    /// Fraction of matches (0-1) ending in an extraction
+    pub extraction_rate: f32,
+}
+
+/// Computes the aggregate gameplay statistics from the recorded matches
+pub async fn stats<C>(db: &C) -> DbResult<TelemetryStats>
+where
+    C: ConnectionTrait + Send,
+{
+    let (matches, totals, waves) = MatchTelemetry::totals(db).await?;
+
+    let average = |value: u64| {
+        if matches == 0 {
+            0.0
+        } else {
+            value as f32 / matches as f32
+        }
+    };
+
+    Ok(TelemetryStats {
+        matches,
+        kills: totals.kills,
+        extractions: totals.extractions,
+        highest_wave: totals.highest_wave,
+        average_kills: average(totals.kills as u64),
+        average_wave: average(waves),
+        extraction_rate: average(totals.extractions as u64).min(1.0),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{collect, MatchStats, PinPayload};
+
+    /// Tests decoding a payload and collecting the statistics for
+    /// each match, the persona IDs within the events are ignored
+    #[test]
+    fn test_collect() {
+        let payload: PinPayload = serde_json::from_str(
+            r#"{
+                "taxv": "1.1",
+                "events": [
+                    { "core": { "en": "boot_start", "pid": "1" } },
+                    { "core": { "en": "kill", "pid": "1" }, "gid": "4", "count": 3 },
+                    { "core": { "en": "kill", "pid": 1 }, "gid": 4 },
+                    { "core": { "en": "wave_complete", "pid": "1" }, "gid": "4", "wave": "7" },
+                    { "core": { "en": "wave_complete", "pid": "1" }, "gid": "4", "wave": "5" },
+                    { "core": { "en": "extraction", "pid": "2" } },
+                    { "core": { "en": "kill", "pid": "3" }, "gid": "4" }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        // The user is currently in game 9
+        let matches = collect(&payload.events, Some(9));
+
+        assert_eq!(matches.len(), 2);
+        assert_eq!(
+            matches.get(&4),
+            Some(&MatchStats {
+                kills: 5,
+                extractions: 0,
+                highest_wave: 7,
+            })
+        );
+        assert_eq!(
+            matches.get(&9),
+            Some(&MatchStats {
+                kills: 0,
+                extractions: 1,
+                highest_wave: 0,
+            })
+        );
+
+        // Events without a game are dropped when the user isn't in a game
+        let matches = collect(&payload.events, None);
+        assert!(!matches.contains_key(&9));
+    }
+}
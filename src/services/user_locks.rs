@@ -0,0 +1,118 @@
+//! Per-user operation locking, used to serialize requests that mutate
+//! a users inventory or currencies so that racing requests (i.e double
+//! clicking "open pack") can't duplicate grants or overspend items
+
+use crate::{database::entity::users::UserId, utils::hashing::IntHashMap};
+use parking_lot::Mutex;
+use std::sync::{Arc, Weak};
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+
+/// Guard held while performing a locked operation for a user, the
+/// lock is released once the guard is dropped
+pub type UserLockGuard = OwnedMutexGuard<()>;
+
+/// Collection of per-user operation locks
+#[derive(Default)]
+pub struct UserLocks {
+    /// Locks for each user, only weak references are stored so that
+    /// locks no longer in use can be cleaned up
+    locks: Mutex<IntHashMap<UserId, Weak<AsyncMutex<()>>>>,
+}
+
+impl UserLocks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Acquires the operation lock for the provided user, waits until
+    /// any other operations for the same user have completed
+    pub async fn lock(&self, user_id: UserId) -> UserLockGuard {
+        let lock = {
+            let locks = &mut *self.locks.lock();
+
+            // Remove locks that are no longer held by anyone
+            locks.retain(|_, lock| lock.strong_count() > 0);
+
+            match locks.get(&user_id).and_then(Weak::upgrade) {
+                Some(lock) => lock,
+                None => {
+                    let lock = Arc::new(AsyncMutex::new(()));
+                    locks.insert(user_id, Arc::downgrade(&lock));
+                    lock
+                }
+            }
+        };
+
+        lock.lock_owned().await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::UserLocks;
+    use std::{
+        sync::{
+            atomic::{AtomicU32, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+    use tokio::{task::JoinSet, time::sleep};
+
+    /// Tests that concurrent operations for the same user are serialized
+    /// by simulating racing requests that read, wait, then write a stack size
+    #[tokio::test]
+    async fn test_concurrent_requests_serialized() {
+        let locks = Arc::new(UserLocks::new());
+        let stack_size = Arc::new(AtomicU32::new(1));
+        let consumed = Arc::new(AtomicU32::new(0));
+
+        let mut tasks = JoinSet::new();
+        for _ in 0..8 {
+            let locks = locks.clone();
+            let stack_size = stack_size.clone();
+            let consumed = consumed.clone();
+
+            tasks.spawn(async move {
+                let _guard = locks.lock(1).await;
+
+                let current = stack_size.load(Ordering::SeqCst);
+                // Allow the other requests to run between the read and write
+                sleep(Duration::from_millis(5)).await;
+
+                if current > 0 {
+                    stack_size.store(current - 1, Ordering::SeqCst);
+                    consumed.fetch_add(1, Ordering::SeqCst);
+                }
+            });
+        }
+
+        while tasks.join_next().await.is_some() {}
+
+        // Only the single item should have been consumed
+        assert_eq!(stack_size.load(Ordering::SeqCst), 0);
+        assert_eq!(consumed.load(Ordering::SeqCst), 1);
+    }
+
+    /// Tests that operations for different users don't block eachother
+    #[tokio::test]
+    async fn test_different_users_not_blocked() {
+        let locks = UserLocks::new();
+
+        let _first = locks.lock(1).await;
+        let second = tokio::time::timeout(Duration::from_secs(1), locks.lock(2)).await;
+
+        assert!(second.is_ok());
+    }
+
+    /// Tests that unused locks are removed
+    #[tokio::test]
+    async fn test_unused_locks_removed() {
+        let locks = UserLocks::new();
+
+        drop(locks.lock(1).await);
+        drop(locks.lock(2).await);
+
+        assert_eq!(locks.locks.lock().len(), 1);
+    }
+}
@@ -0,0 +1,97 @@
+//! Global allocator that counts the allocations made by the server, used by
+//! the memory diagnostics to track down memory growth on long running
+//! instances. Counters are relaxed atomics so that counting doesn't
+//! synchronize the allocating threads
+
+use serde::Serialize;
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// Number of allocations made
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+/// Number of allocations freed
+static DEALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+/// Number of allocations that were resized
+static REALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+/// Total bytes allocated
+static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+/// Total bytes freed
+static FREED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// Allocator wrapping the [System] allocator that
+/// counts the allocations passing through it
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            ALLOCATED_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            ALLOCATED_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        DEALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        FREED_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            REALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            FREED_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+            ALLOCATED_BYTES.fetch_add(new_size as u64, Ordering::Relaxed);
+        }
+        new_ptr
+    }
+}
+
+/// Snapshot of the allocation counters
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AllocationCounters {
+    /// Number of allocations made
+    pub allocations: u64,
+    /// Number of allocations freed
+    pub deallocations: u64,
+    /// Number of allocations that were resized
+    pub reallocations: u64,
+    /// Total bytes allocated
+    pub allocated_bytes: u64,
+    /// Total bytes freed
+    pub freed_bytes: u64,
+    /// Bytes currently allocated
+    pub live_bytes: u64,
+}
+
+impl AllocationCounters {
+    /// Obtains the current allocation counters
+    pub fn get() -> Self {
+        let allocated_bytes = ALLOCATED_BYTES.load(Ordering::Relaxed);
+        let freed_bytes = FREED_BYTES.load(Ordering::Relaxed);
+
+        Self {
+            allocations: ALLOCATIONS.load(Ordering::Relaxed),
+            deallocations: DEALLOCATIONS.load(Ordering::Relaxed),
+            reallocations: REALLOCATIONS.load(Ordering::Relaxed),
+            allocated_bytes,
+            freed_bytes,
+            // Counters are read separately so may briefly be out of step
+            live_bytes: allocated_bytes.saturating_sub(freed_bytes),
+        }
+    }
+}
@@ -0,0 +1,65 @@
+//! Clock used for time based behavior (Store rotations, challenge windows,
+//! strike team timers, token expiry) so that the time can be controlled.
+//!
+//! The `--freeze-time` debug flag freezes the clock for the whole server so
+//! rotation boundaries and mission expiration can be reproduced, tests set
+//! the time for their own thread using [set_test_time]
+
+use chrono::{DateTime, Utc};
+use std::sync::OnceLock;
+
+/// Time the clock was frozen at by the `--freeze-time` flag
+static FROZEN: OnceLock<DateTime<Utc>> = OnceLock::new();
+
+#[cfg(test)]
+thread_local! {
+    /// Time set by the current test, tests run on their own
+    /// threads so they don't affect each other
+    static TEST_TIME: std::cell::Cell<Option<DateTime<Utc>>> = const { std::cell::Cell::new(None) };
+}
+
+/// Freezes the clock at the provided `time` for the rest of the process,
+/// the clock can only be frozen once
+pub fn freeze(time: DateTime<Utc>) {
+    _ = FROZEN.set(time);
+}
+
+/// Provides the current time from the clock
+pub fn now() -> DateTime<Utc> {
+    #[cfg(test)]
+    if let Some(time) = TEST_TIME.with(|time| time.get()) {
+        return time;
+    }
+
+    FROZEN.get().copied().unwrap_or_else(Utc::now)
+}
+
+/// Provides the current unix timestamp in seconds from the clock
+pub fn timestamp() -> i64 {
+    now().timestamp()
+}
+
+/// Sets the time returned by the clock for the current test thread,
+/// [None] returns the clock to the real time
+#[cfg(test)]
+pub fn set_test_time(time: Option<DateTime<Utc>>) {
+    TEST_TIME.with(|value| value.set(time));
+}
+
+#[cfg(test)]
+mod test {
+    use super::{now, set_test_time};
+    use chrono::{TimeZone, Utc};
+
+    /// Tests that the test time overrides the clock
+    #[test]
+    fn test_test_time() {
+        let time = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+
+        set_test_time(Some(time));
+        assert_eq!(now(), time);
+
+        set_test_time(None);
+        assert_ne!(now(), time);
+    }
+}
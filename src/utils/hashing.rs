@@ -1,6 +1,6 @@
 //! Hashing utility for hashing and verifying passwords
 
-use hashbrown::HashMap;
+use hashbrown::{HashMap, HashSet};
 use std::hash::{BuildHasher, Hasher};
 
 use argon2::{
@@ -36,6 +36,9 @@ pub fn verify_password(password: &str, hash: &str) -> bool {
 /// Alias for a [`HashMap`] that used [`IntHasher`] as its [`Hasher`]
 pub type IntHashMap<K, V> = HashMap<K, V, BuildIntHasher>;
 
+/// Alias for a [`HashSet`] that used [`IntHasher`] as its [`Hasher`]
+pub type IntHashSet<K> = HashSet<K, BuildIntHasher>;
+
 /// Const safe [`BuildHasher`] implementation for [`IntHasher`]
 #[derive(Default)]
 pub struct BuildIntHasher;
@@ -1,3 +1,5 @@
+pub mod alloc;
+pub mod clock;
 pub mod constants;
 pub mod hashing;
 pub mod lock;
@@ -1,65 +1,51 @@
+use crate::database::entity::users::Platform;
 use sea_orm::prelude::DateTimeUtc;
-use serde::{
-    de::{MapAccess, Visitor},
-    ser::SerializeStruct,
-    Deserialize, Deserializer, Serialize, Serializer,
-};
+use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 
-#[derive(Debug, Clone, Default)]
-pub struct Sku;
-
-impl Serialize for Sku {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        let mut this = serializer.serialize_struct("Sku", 2)?;
-        this.serialize_field("title", "mec.game")?;
-        this.serialize_field("platform", "origin")?;
-        this.end()
-    }
+/// Title and platform of the game client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Sku {
+    /// Title of the game
+    pub title: String,
+    /// Platform the game is running on
+    pub platform: String,
 }
 
-impl<'de> Deserialize<'de> for Sku {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        struct NopVisitor;
+impl Sku {
+    /// Title of the game for all platforms
+    const TITLE: &str = "mec.game";
 
-        impl<'de> Visitor<'de> for NopVisitor {
-            type Value = ();
-
-            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-                formatter.write_str("Sku")
-            }
-
-            fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
-            where
-                A: MapAccess<'de>,
-            {
-                loop {
-                    let entry = map.next_entry::<&str, &str>()?;
-                    if entry.is_none() {
-                        break;
-                    }
-                }
-                Ok(())
-            }
+    /// Creates the SKU for the provided `platform`
+    pub fn new(platform: Platform) -> Self {
+        Self {
+            title: Self::TITLE.to_string(),
+            platform: platform.sku_name().to_string(),
         }
+    }
+}
 
-        deserializer.deserialize_struct("Sku", &["title", "platform"], NopVisitor)?;
-
-        Ok(Self)
+impl Default for Sku {
+    fn default() -> Self {
+        Self::new(Platform::default())
     }
 }
 
 /// Represents a duration of time that something will be available for.
 /// Can be open ended by only specifying a start/end
 #[skip_serializing_none]
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct DateDuration {
     pub start: Option<DateTimeUtc>,
     pub end: Option<DateTimeUtc>,
 }
+
+impl DateDuration {
+    /// Checks whether the provided `time` is within the duration
+    pub fn contains(&self, time: DateTimeUtc) -> bool {
+        let before_start = self.start.is_some_and(|start| time < start);
+        let after_end = self.end.is_some_and(|end| end < time);
+        !before_start && !after_end
+    }
+}